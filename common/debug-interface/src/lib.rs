@@ -3,9 +3,11 @@
 
 use anyhow::Result;
 use libra_logger::json_log::JsonLogEntry;
+use network::transport::fault_injection::NetworkFaultsConfig;
 use reqwest::blocking;
 use std::collections::HashMap;
 
+pub mod network_admin;
 pub mod node_debug_service;
 
 /// Implement default utility client for NodeDebugInterface
@@ -50,6 +52,31 @@ impl NodeDebugClient {
 
         Ok(response.json()?)
     }
+
+    /// Replace the faults injected in the connections of the network at `network_index`, which
+    /// must have fault injection enabled. `token` is the network admin token of the node.
+    pub fn set_network_faults(
+        &mut self,
+        token: &str,
+        network_index: usize,
+        faults: &NetworkFaultsConfig,
+    ) -> Result<()> {
+        let response = self
+            .client
+            .put(&format!("{}/network/{}/faults", self.addr, network_index))
+            .bearer_auth(token)
+            .json(faults)
+            .send()?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(anyhow::format_err!(
+                "Failed to set network faults: {}",
+                response.text()?
+            ))
+        }
+    }
 }
 
 /// Implement default utility client for AsyncNodeDebugInterface
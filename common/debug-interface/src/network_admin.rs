@@ -0,0 +1,533 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Endpoints to administer the networks of the node at runtime.
+//!
+//! Requests must come from a loopback address and carry the configured token in an
+//! `Authorization: Bearer <token>` header. Without a configured token, all requests are refused.
+//! * `GET /network/peers` lists, for each network, the connected peers with their connection
+//!   metadata, negotiated protocols and health, and the eligible peers.
+//! * `POST /network/<index>/dial` with `{"peer_id": .., "address": ..}` dials a peer.
+//! * `POST /network/<index>/disconnect` with `{"peer_id": ..}` disconnects from a peer.
+//! * `POST /network/<index>/ban` with `{"peer_id": .., "duration_secs": ..}` disconnects from a
+//!   peer and refuses connections with it for the given duration.
+//! * `GET /network/<index>/faults` returns the faults injected in the connections, and
+//!   `PUT /network/<index>/faults` replaces them, on networks with fault injection enabled.
+//!
+//! `<index>` is the position of the network in the list returned by `/network/peers`.
+
+use libra_infallible::RwLock;
+use libra_network_address::NetworkAddress;
+use libra_types::PeerId;
+use network::{admin::NetworkAdmin, transport::fault_injection::NetworkFaultsConfig};
+use serde::{Deserialize, Serialize};
+use std::{convert::Infallible, net::SocketAddr, sync::Arc, time::Duration};
+use warp::{http::StatusCode, reject::Reject, Filter, Rejection, Reply};
+
+/// The networks of the node, registered once they are built.
+pub type NetworkAdmins = Arc<RwLock<Vec<NetworkAdmin>>>;
+
+/// Longest ban, to keep the end of bans representable.
+const MAX_BAN_SECS: u64 = 7 * 24 * 60 * 60;
+
+#[derive(Deserialize)]
+struct DialRequest {
+    peer_id: PeerId,
+    address: NetworkAddress,
+}
+
+#[derive(Deserialize)]
+struct DisconnectRequest {
+    peer_id: PeerId,
+}
+
+#[derive(Deserialize)]
+struct BanRequest {
+    peer_id: PeerId,
+    duration_secs: u64,
+}
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+#[derive(Debug)]
+struct Unauthorized;
+
+impl Reject for Unauthorized {}
+
+/// Compares in constant time, so that the token can't be guessed from response times.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn authenticate(token: Option<String>) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::addr::remote()
+        .and(warp::header::optional::<String>("authorization"))
+        .and_then(
+            move |remote: Option<SocketAddr>, authorization: Option<String>| {
+                let authorized = remote.map_or(false, |addr| addr.ip().is_loopback())
+                    && match (&token, authorization) {
+                        (Some(token), Some(authorization)) => constant_time_eq(
+                            authorization.as_bytes(),
+                            format!("Bearer {}", token).as_bytes(),
+                        ),
+                        _ => false,
+                    };
+                async move {
+                    if authorized {
+                        Ok(())
+                    } else {
+                        Err(warp::reject::custom(Unauthorized))
+                    }
+                }
+            },
+        )
+        .untuple_one()
+}
+
+fn reply<T: Serialize>(result: Result<T, String>) -> warp::reply::WithStatus<warp::reply::Json> {
+    match result {
+        Ok(value) => warp::reply::with_status(warp::reply::json(&value), StatusCode::OK),
+        Err(error) => warp::reply::with_status(
+            warp::reply::json(&ErrorResponse { error }),
+            StatusCode::BAD_REQUEST,
+        ),
+    }
+}
+
+fn network_admin(admins: &NetworkAdmins, index: usize) -> Result<NetworkAdmin, String> {
+    admins
+        .read()
+        .get(index)
+        .cloned()
+        .ok_or_else(|| format!("Unknown network: {}", index))
+}
+
+async fn list_peers(admins: NetworkAdmins) -> Result<impl Reply, Infallible> {
+    let admins = admins.read().clone();
+    let mut statuses = Vec::with_capacity(admins.len());
+    for admin in admins {
+        match admin.status().await {
+            Ok(status) => statuses.push(status),
+            Err(e) => {
+                return Ok(reply::<()>(Err(format!(
+                    "{}: {}",
+                    admin.network_context(),
+                    e
+                ))))
+            }
+        }
+    }
+    Ok(reply(Ok(statuses)))
+}
+
+async fn dial_peer(
+    index: usize,
+    admins: NetworkAdmins,
+    request: DialRequest,
+) -> Result<impl Reply, Infallible> {
+    let result = match network_admin(&admins, index) {
+        Ok(admin) => admin
+            .dial_peer(request.peer_id, request.address)
+            .await
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e),
+    };
+    Ok(reply(result))
+}
+
+async fn disconnect_peer(
+    index: usize,
+    admins: NetworkAdmins,
+    request: DisconnectRequest,
+) -> Result<impl Reply, Infallible> {
+    let result = match network_admin(&admins, index) {
+        Ok(admin) => admin
+            .disconnect_peer(request.peer_id)
+            .await
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e),
+    };
+    Ok(reply(result))
+}
+
+async fn ban_peer(
+    index: usize,
+    admins: NetworkAdmins,
+    request: BanRequest,
+) -> Result<impl Reply, Infallible> {
+    let result = if request.duration_secs > MAX_BAN_SECS {
+        Err(format!("Bans can't exceed {} seconds", MAX_BAN_SECS))
+    } else {
+        match network_admin(&admins, index) {
+            Ok(admin) => admin
+                .ban_peer(request.peer_id, Duration::from_secs(request.duration_secs))
+                .await
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e),
+        }
+    };
+    Ok(reply(result))
+}
+
+async fn get_faults(index: usize, admins: NetworkAdmins) -> Result<impl Reply, Infallible> {
+    let result = network_admin(&admins, index).and_then(|admin| match admin.network_faults() {
+        Some(faults) => Ok(faults.config()),
+        None => Err(format!(
+            "Fault injection is not enabled on {}",
+            admin.network_context()
+        )),
+    });
+    Ok(reply(result))
+}
+
+async fn set_faults(
+    index: usize,
+    admins: NetworkAdmins,
+    config: NetworkFaultsConfig,
+) -> Result<impl Reply, Infallible> {
+    let result = network_admin(&admins, index).and_then(|admin| match admin.network_faults() {
+        Some(faults) => {
+            faults.set_config(config);
+            Ok(())
+        }
+        None => Err(format!(
+            "Fault injection is not enabled on {}",
+            admin.network_context()
+        )),
+    });
+    Ok(reply(result))
+}
+
+async fn handle_rejection(rejection: Rejection) -> Result<impl Reply, Rejection> {
+    if rejection.find::<Unauthorized>().is_some() {
+        Ok(warp::reply::with_status(
+            "Unauthorized",
+            StatusCode::UNAUTHORIZED,
+        ))
+    } else {
+        Err(rejection)
+    }
+}
+
+/// The network admin routes, authenticated with `token`.
+pub fn routes(
+    admins: NetworkAdmins,
+    token: Option<String>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    let with_admins = warp::any().map(move || admins.clone());
+    // 16kb is more than enough for any request
+    let body_limit = warp::body::content_length_limit(1024 * 16);
+
+    // GET /network/peers
+    let peers = warp::get()
+        .and(warp::path!("network" / "peers"))
+        .and(authenticate(token.clone()))
+        .and(with_admins.clone())
+        .and_then(list_peers);
+
+    // POST /network/<index>/dial
+    let dial = warp::post()
+        .and(warp::path!("network" / usize / "dial"))
+        .and(authenticate(token.clone()))
+        .and(with_admins.clone())
+        .and(body_limit.clone())
+        .and(warp::body::json())
+        .and_then(dial_peer);
+
+    // POST /network/<index>/disconnect
+    let disconnect = warp::post()
+        .and(warp::path!("network" / usize / "disconnect"))
+        .and(authenticate(token.clone()))
+        .and(with_admins.clone())
+        .and(body_limit.clone())
+        .and(warp::body::json())
+        .and_then(disconnect_peer);
+
+    // POST /network/<index>/ban
+    let ban = warp::post()
+        .and(warp::path!("network" / usize / "ban"))
+        .and(authenticate(token.clone()))
+        .and(with_admins.clone())
+        .and(body_limit.clone())
+        .and(warp::body::json())
+        .and_then(ban_peer);
+
+    // GET /network/<index>/faults
+    let get_faults = warp::get()
+        .and(warp::path!("network" / usize / "faults"))
+        .and(authenticate(token.clone()))
+        .and(with_admins.clone())
+        .and_then(get_faults);
+
+    // PUT /network/<index>/faults
+    let set_faults = warp::put()
+        .and(warp::path!("network" / usize / "faults"))
+        .and(authenticate(token))
+        .and(with_admins)
+        .and(body_limit)
+        .and(warp::body::json())
+        .and_then(set_faults);
+
+    peers
+        .or(dial)
+        .or(disconnect)
+        .or(ban)
+        .or(get_faults)
+        .or(set_faults)
+        .recover(handle_rejection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use channel::{libra_channel, message_queues::QueueStyle};
+    use futures::StreamExt;
+    use libra_config::{
+        config::RoleType,
+        network_id::{NetworkContext, NetworkId},
+    };
+    use libra_infallible::Mutex;
+    use network::{
+        peer_manager::{ConnectionRequest, ConnectionRequestSender},
+        transport::fault_injection::{LinkFaults, NetworkFaults},
+    };
+    use serde_json::json;
+    use std::num::NonZeroUsize;
+    use warp::test::request;
+
+    const TOKEN: &str = "secret";
+
+    /// The connection requests received by the networks.
+    type Requests = Arc<Mutex<Vec<String>>>;
+
+    fn loopback() -> SocketAddr {
+        ([127, 0, 0, 1], 9101).into()
+    }
+
+    /// A network without fault injection followed by one with fault injection, whose connection
+    /// requests all succeed and are recorded.
+    fn network_admins() -> (NetworkAdmins, Requests) {
+        let requests = Arc::new(Mutex::new(vec![]));
+        let admins = [None, Some(NetworkFaults::new())]
+            .iter()
+            .cloned()
+            .map(|network_faults| {
+                let (connection_reqs_tx, mut connection_reqs_rx) =
+                    libra_channel::new(QueueStyle::FIFO, NonZeroUsize::new(8).unwrap(), None);
+                let requests = requests.clone();
+                tokio::spawn(async move {
+                    while let Some(request) = connection_reqs_rx.next().await {
+                        match request {
+                            ConnectionRequest::DialPeer(peer_id, address, callback) => {
+                                requests
+                                    .lock()
+                                    .push(format!("dial {} {}", peer_id, address));
+                                let _ = callback.send(Ok(()));
+                            }
+                            ConnectionRequest::DisconnectPeer(peer_id, callback) => {
+                                requests.lock().push(format!("disconnect {}", peer_id));
+                                let _ = callback.send(Ok(()));
+                            }
+                            ConnectionRequest::BanPeer(peer_id, duration, callback) => {
+                                requests.lock().push(format!(
+                                    "ban {} {}",
+                                    peer_id,
+                                    duration.as_secs()
+                                ));
+                                let _ = callback.send(Ok(()));
+                            }
+                            ConnectionRequest::GetConnectedPeers(callback) => {
+                                let _ = callback.send(vec![]);
+                            }
+                        }
+                    }
+                });
+                NetworkAdmin::new(
+                    Arc::new(NetworkContext::new(
+                        NetworkId::Validator,
+                        RoleType::Validator,
+                        PeerId::random(),
+                    )),
+                    ConnectionRequestSender::new(connection_reqs_tx),
+                    Arc::new(RwLock::new(Default::default())),
+                    None,
+                    network_faults,
+                )
+            })
+            .collect();
+        (Arc::new(RwLock::new(admins)), requests)
+    }
+
+    #[tokio::test]
+    async fn test_authentication() {
+        let (admins, _) = network_admins();
+        let routes = routes(admins, Some(TOKEN.to_string()));
+        let authorization = format!("Bearer {}", TOKEN);
+        let get_peers = || request().method("GET").path("/network/peers");
+
+        let response = get_peers()
+            .remote_addr(loopback())
+            .header("authorization", &authorization)
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // missing token
+        let response = get_peers().remote_addr(loopback()).reply(&routes).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        // wrong token
+        let response = get_peers()
+            .remote_addr(loopback())
+            .header("authorization", "Bearer wrong")
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        // token without the bearer scheme
+        let response = get_peers()
+            .remote_addr(loopback())
+            .header("authorization", TOKEN)
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        // not from a loopback address
+        let response = get_peers()
+            .remote_addr(([10, 0, 0, 1], 9101).into())
+            .header("authorization", &authorization)
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        // unknown remote address
+        let response = get_peers()
+            .header("authorization", &authorization)
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        // the mutating routes are authenticated as well
+        let response = request()
+            .method("POST")
+            .path("/network/0/disconnect")
+            .remote_addr(loopback())
+            .json(&json!({ "peer_id": PeerId::random() }))
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_disabled_without_token() {
+        let (admins, requests) = network_admins();
+        let routes = routes(admins, None);
+        for authorization in &["Bearer ", "Bearer None", "Bearer secret"] {
+            let response = request()
+                .method("GET")
+                .path("/network/peers")
+                .remote_addr(loopback())
+                .header("authorization", *authorization)
+                .reply(&routes)
+                .await;
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+            let response = request()
+                .method("POST")
+                .path("/network/0/ban")
+                .remote_addr(loopback())
+                .header("authorization", *authorization)
+                .json(&json!({ "peer_id": PeerId::random(), "duration_secs": 60 }))
+                .reply(&routes)
+                .await;
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+        assert!(requests.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_routes() {
+        let (admins, requests) = network_admins();
+        let routes = routes(admins, Some(TOKEN.to_string()));
+        let authorization = format!("Bearer {}", TOKEN);
+        let call = |method: &str, path: &str| {
+            request()
+                .method(method)
+                .path(path)
+                .remote_addr(loopback())
+                .header("authorization", &authorization)
+        };
+        let peer_id = PeerId::random();
+
+        let response = call("GET", "/network/peers").reply(&routes).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let statuses: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(statuses.as_array().unwrap().len(), 2);
+        assert!(statuses[0]["connected_peers"]
+            .as_array()
+            .unwrap()
+            .is_empty());
+
+        let address = "/ip4/127.0.0.1/tcp/6180";
+        let response = call("POST", "/network/0/dial")
+            .json(&json!({ "peer_id": peer_id, "address": address }))
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = call("POST", "/network/1/disconnect")
+            .json(&json!({ "peer_id": peer_id }))
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = call("POST", "/network/0/ban")
+            .json(&json!({ "peer_id": peer_id, "duration_secs": 60 }))
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            *requests.lock(),
+            vec![
+                format!("dial {} {}", peer_id, address),
+                format!("disconnect {}", peer_id),
+                format!("ban {} 60", peer_id),
+            ]
+        );
+
+        // invalid requests
+        let response = call("POST", "/network/0/ban")
+            .json(&json!({ "peer_id": peer_id, "duration_secs": MAX_BAN_SECS + 1 }))
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = call("POST", "/network/2/disconnect")
+            .json(&json!({ "peer_id": peer_id }))
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(requests.lock().len(), 3);
+
+        // faults can only be injected on the second network
+        let response = call("GET", "/network/0/faults").reply(&routes).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let config = NetworkFaultsConfig {
+            default: LinkFaults {
+                latency_ms: 100,
+                ..LinkFaults::default()
+            },
+            links: vec![],
+        };
+        let response = call("PUT", "/network/0/faults")
+            .json(&config)
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = call("PUT", "/network/1/faults")
+            .json(&config)
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = call("GET", "/network/1/faults").reply(&routes).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            serde_json::from_slice::<NetworkFaultsConfig>(response.body()).unwrap(),
+            config
+        );
+    }
+}
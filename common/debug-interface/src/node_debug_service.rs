@@ -3,7 +3,10 @@
 
 //! Debug interface to access information in a specific node.
 
+use crate::network_admin::{self, NetworkAdmins};
+use libra_infallible::RwLock;
 use libra_logger::{info, json_log, Filter, Logger};
+use network::admin::NetworkAdmin;
 use std::{net::SocketAddr, sync::Arc};
 use tokio::runtime::{Builder, Runtime};
 use warp::Filter as _;
@@ -11,10 +14,15 @@ use warp::Filter as _;
 #[derive(Debug)]
 pub struct NodeDebugService {
     runtime: Runtime,
+    network_admins: NetworkAdmins,
 }
 
 impl NodeDebugService {
-    pub fn new(address: SocketAddr, logger: Option<Arc<Logger>>) -> Self {
+    pub fn new(
+        address: SocketAddr,
+        logger: Option<Arc<Logger>>,
+        network_admin_token: Option<String>,
+    ) -> Self {
         let runtime = Builder::new()
             .thread_name("nodedebug")
             .threaded_scheduler()
@@ -66,12 +74,24 @@ impl NodeDebugService {
             .and(warp::path("log"))
             .and(local_filter.or(remote_filter));
 
-        let routes = log.or(warp::get().and(metrics.or(events)));
+        // /network/...
+        let network_admins: NetworkAdmins = Arc::new(RwLock::new(Vec::new()));
+        let network = network_admin::routes(network_admins.clone(), network_admin_token);
+
+        let routes = log.or(warp::get().and(metrics.or(events))).or(network);
 
         let server = runtime.enter(move || warp::serve(routes).bind(address));
         runtime.handle().spawn(server);
 
-        Self { runtime }
+        Self {
+            runtime,
+            network_admins,
+        }
+    }
+
+    /// Exposes a network through the network admin endpoints.
+    pub fn add_network_admin(&self, network_admin: NetworkAdmin) {
+        self.network_admins.write().push(network_admin);
     }
 
     pub fn runtime(&self) -> &Runtime {
@@ -20,6 +20,13 @@ impl<T> Mutex<T> {
             .lock()
             .expect("libra cannot currently handle a poisoned lock")
     }
+
+    /// consumes the mutex, returning the underlying data
+    pub fn into_inner(self) -> T {
+        self.0
+            .into_inner()
+            .expect("libra cannot currently handle a poisoned lock")
+    }
 }
 
 #[cfg(test)]
@@ -125,6 +125,13 @@ pub fn validate_address(
                 has_addr = true
             }
             Protocol::Tcp(_) => has_port = true,
+            // Validators not supporting QUIC would fail to parse the whole validator set
+            Protocol::Quic(_) => {
+                return Err(Error::CommandArgumentError(format!(
+                    "{}: QUIC addresses can't be published on-chain yet.  Protocol: '{}'",
+                    address_name, protocol
+                )))
+            }
             Protocol::Dns(_) | Protocol::Ip6(_) | Protocol::Dns6(_) => {
                 return Err(Error::CommandArgumentError(format!(
                     "{}: IPv6 is currently not supported.  Protocol: '{}'",
@@ -177,6 +184,7 @@ mod tests {
         let ipv4_and_ipv6 = NetworkAddress::from_str("/dns/localhost").unwrap();
         let bad_protocol = NetworkAddress::from_str("/ln-handshake/0").unwrap();
         let ip_in_dns = NetworkAddress::from_str("/dns4/127.0.0.1/tcp/1234").unwrap();
+        let quic = NetworkAddress::from_str("/ip4/127.0.0.1/quic/1234").unwrap();
 
         validate_address("no_port", &no_port).expect_err("Failed to check for port");
         validate_address("no_ip", &no_ip).expect_err("Failed to check for no IP");
@@ -185,6 +193,7 @@ mod tests {
         validate_address("bad_protocol", &bad_protocol)
             .expect_err("Failed to check for bad protocol");
         validate_address("ip_in_dns", &ip_in_dns).expect_err("Failed to check for ip in DNS");
+        validate_address("quic", &quic).expect_err("Failed to check for quic");
     }
 
     #[test]
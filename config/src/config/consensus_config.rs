@@ -20,6 +20,9 @@ pub struct ConsensusConfig {
     pub sync_only: bool,
     // how many times to wait for txns from mempool when propose
     pub mempool_poll_count: u64,
+    // how many ordered commits can wait to be persisted before committing blocks back-pressures
+    pub max_pending_commits: usize,
+    pub observer: ConsensusObserverConfig,
 }
 
 impl Default for ConsensusConfig {
@@ -29,13 +32,17 @@ impl Default for ConsensusConfig {
             max_block_size: 1000,
             max_pruned_blocks_in_mem: 10000,
             round_initial_timeout_ms: 1000,
-            proposer_type: ConsensusProposerType::LeaderReputation(LeaderReputationConfig {
-                active_weights: 99,
-                inactive_weights: 1,
-            }),
+            proposer_type: ConsensusProposerType::LeaderReputation(
+                LeaderReputationConfig::ActiveInactive(ActiveInactiveConfig {
+                    active_weights: 99,
+                    inactive_weights: 1,
+                }),
+            ),
             safety_rules: SafetyRulesConfig::default(),
             sync_only: false,
             mempool_poll_count: 1,
+            max_pending_commits: 4,
+            observer: ConsensusObserverConfig::default(),
         }
     }
 }
@@ -46,6 +53,29 @@ impl ConsensusConfig {
     }
 }
 
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConsensusObserverConfig {
+    // Validators publish their certified blocks and commit decisions to the subscribed full
+    // nodes, full nodes subscribe to their upstream peers, execute the blocks ahead of state
+    // sync and republish them to their downstream peers
+    pub enabled: bool,
+    // how many full nodes a validator or a full node publishes to
+    pub max_subscribers: usize,
+    // how many certified blocks a full node buffers while waiting for their commit decision
+    pub max_pending_blocks: usize,
+}
+
+impl Default for ConsensusObserverConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_subscribers: 32,
+            max_pending_blocks: 100,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(rename_all = "snake_case", tag = "type")]
 pub enum ConsensusProposerType {
@@ -61,9 +91,49 @@ pub enum ConsensusProposerType {
     RoundProposer(HashMap<Round, AccountAddress>),
 }
 
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum LeaderReputationConfig {
+    // Candidates that proposed or voted in the recent history are preferred
+    ActiveInactive(ActiveInactiveConfig),
+    // Like ActiveInactive, additionally penalizing the proposers of failed rounds,
+    // with the history of previous epochs exponentially decayed
+    ProposerAndFailedRounds(ProposerAndFailedRoundsConfig),
+}
+
 #[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
-pub struct LeaderReputationConfig {
+pub struct ActiveInactiveConfig {
     pub active_weights: u64,
     pub inactive_weights: u64,
 }
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProposerAndFailedRoundsConfig {
+    pub active_weights: u64,
+    pub inactive_weights: u64,
+    pub failed_weights: u64,
+    // proposers that failed more than this percentage of their rounds get failed_weights
+    pub failure_threshold_percent: u64,
+    // percentage of the reputation kept from one epoch to the next
+    pub decay_percent: u64,
+    // size of the committed history, as a multiple of the number of validators
+    pub window_num_validators_multiplier: usize,
+    // number of most recent rounds excluded from the history, as they might not be committed
+    pub exclude_round: u64,
+}
+
+impl Default for ProposerAndFailedRoundsConfig {
+    fn default() -> Self {
+        Self {
+            active_weights: 1000,
+            inactive_weights: 10,
+            failed_weights: 1,
+            failure_threshold_percent: 10,
+            decay_percent: 50,
+            window_num_validators_multiplier: 10,
+            exclude_round: 4,
+        }
+    }
+}
@@ -13,6 +13,8 @@ pub struct DebugInterfaceConfig {
     pub libra_trace: LibraTraceConfig,
     pub metrics_server_port: u16,
     pub public_metrics_server_port: u16,
+    // Token required by the network admin endpoints, which are disabled without it
+    pub network_admin_token: Option<String>,
 }
 
 impl Default for DebugInterfaceConfig {
@@ -23,6 +25,7 @@ impl Default for DebugInterfaceConfig {
             metrics_server_port: 9101,
             public_metrics_server_port: 9102,
             libra_trace: LibraTraceConfig::default(),
+            network_admin_token: None,
         }
     }
 }
@@ -435,6 +435,19 @@ mod test {
         }
     }
 
+    #[test]
+    fn verify_discovery_without_mutual_authentication() {
+        let mut network = NetworkConfig::network_with_id(NetworkId::Public);
+        network.discovery_method = DiscoveryMethod::File(FileDiscoveryConfig {
+            path: PathBuf::from("peers.txt"),
+            interval_secs: 10,
+        });
+        network.load(RoleType::FullNode).unwrap();
+
+        network.mutual_authentication = true;
+        network.load(RoleType::FullNode).unwrap_err();
+    }
+
     #[test]
     fn verify_configs() {
         NodeConfig::default_for_public_full_node();
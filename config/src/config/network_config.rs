@@ -8,7 +8,7 @@ use crate::{
     utils,
 };
 use libra_crypto::{x25519, Uniform};
-use libra_network_address::NetworkAddress;
+use libra_network_address::{CidrBlock, NetworkAddress};
 use libra_network_address_encryption::Encryptor;
 use libra_secure_storage::{CryptoStorage, KVStorage, Storage};
 use libra_types::{transaction::authenticator::AuthenticationKey, PeerId};
@@ -18,8 +18,9 @@ use rand::{
 };
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     convert::TryFrom,
+    path::PathBuf,
     string::ToString,
 };
 
@@ -40,6 +41,11 @@ pub const MAX_CONNECTION_DELAY_MS: u64 = 60_000; /* 1 minute */
 pub const MAX_FULLNODE_CONNECTIONS: usize = 3;
 pub const MAX_FRAME_SIZE: usize = 8 * 1024 * 1024; /* 8 MiB */
 pub const CONNECTION_BACKOFF_BASE: u64 = 2;
+pub const DEFAULT_PROTOCOL_WEIGHT: u32 = 1;
+pub const CONSENSUS_PROTOCOL_WEIGHT: u32 = 8;
+pub const IPV4_SUBNET_PREFIX_LEN: u8 = 24;
+pub const IPV6_SUBNET_PREFIX_LEN: u8 = 48;
+pub const HANDSHAKE_FAILURE_PENALTY_SECS: u64 = 60;
 
 pub type SeedPublicKeys = HashMap<PeerId, HashSet<x25519::PublicKey>>;
 pub type SeedAddresses = HashMap<PeerId, Vec<NetworkAddress>>;
@@ -85,6 +91,12 @@ pub struct NetworkConfig {
     pub max_frame_size: usize,
     // Enables proxy protocol on incoming connections to get original source addresses
     pub enable_proxy_protocol: bool,
+    // Advertises the compression capability in the handshake, to compress the payloads of
+    // compressible protocols with peers which advertise it too
+    pub enable_compression: bool,
+    // Subjects connections to the link faults set through the network admin interface, to test
+    // the node under degraded networks. Only allowed on test chains.
+    pub enable_fault_injection: bool,
     // Interval to send healthcheck pings to peers
     pub ping_interval_ms: u64,
     // Timeout until a healthcheck ping is rejected
@@ -93,6 +105,14 @@ pub struct NetworkConfig {
     pub ping_failures_tolerated: u64,
     // Maximum number of allows fullnode connections.  Will prevent future outbound connections
     pub max_fullnode_connections: usize,
+    // Share of the outbound bandwidth of each connection given to the protocols, by protocol
+    // name (e.g. `ConsensusRpc`). Protocols not listed get the default quota. Unknown names are
+    // rejected when the network is built.
+    pub protocol_quotas: BTreeMap<String, ProtocolQuotaConfig>,
+    // Limits on the inbound connections by source IP address. They're enforced before the
+    // proxy protocol header is read, so behind a proxy the source is the proxy. The handshake
+    // failure penalty applies to the address in the header instead.
+    pub inbound_admission: InboundAdmissionConfig,
 }
 
 impl Default for NetworkConfig {
@@ -114,6 +134,8 @@ impl NetworkConfig {
             seed_addrs: HashMap::default(),
             max_frame_size: MAX_FRAME_SIZE,
             enable_proxy_protocol: false,
+            enable_compression: false,
+            enable_fault_injection: false,
             max_connection_delay_ms: MAX_CONNECTION_DELAY_MS,
             connectivity_check_interval_ms: CONNECTIVITY_CHECK_INTERVAL_MS,
             network_channel_size: NETWORK_CHANNEL_SIZE,
@@ -124,6 +146,8 @@ impl NetworkConfig {
             ping_timeout_ms: PING_TIMEOUT_MS,
             ping_failures_tolerated: PING_FAILURES_TOLERATED,
             max_fullnode_connections: MAX_FULLNODE_CONNECTIONS,
+            protocol_quotas: default_protocol_quotas(),
+            inbound_admission: InboundAdmissionConfig::default(),
         };
         config.prepare_identity();
         config
@@ -179,6 +203,20 @@ impl NetworkConfig {
             ));
         }
 
+        // Peers discovered from DNS or a file are only authenticated by the key in their address,
+        // which must not be enough to be trusted by a mutually authenticated network.
+        crate::config::invariant(
+            !self.mutual_authentication
+                || !matches!(
+                    self.discovery_method,
+                    DiscoveryMethod::Dns(_) | DiscoveryMethod::File(_)
+                ),
+            format!(
+                "Discovery method {:?} can't be used with mutual_authentication",
+                self.discovery_method
+            ),
+        )?;
+
         self.prepare_identity();
         Ok(())
     }
@@ -255,9 +293,99 @@ impl NetworkConfig {
 #[serde(rename_all = "snake_case")]
 pub enum DiscoveryMethod {
     Onchain,
+    // Periodically resolve the TXT records of a DNS name, each holding a peer's `NetworkAddress`
+    Dns(DnsDiscoveryConfig),
+    // Watch a local file listing one peer `NetworkAddress` per line
+    File(FileDiscoveryConfig),
     None,
 }
 
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct DnsDiscoveryConfig {
+    // DNS name whose TXT records list the peers, e.g. `seeds.example.com`
+    pub name: String,
+    // Interval between two lookups
+    pub interval_secs: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct FileDiscoveryConfig {
+    // File listing the peers
+    pub path: PathBuf,
+    // Interval between two checks of the file for changes
+    pub interval_secs: u64,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProtocolQuotaConfig {
+    // Weight of the protocol in the fair queueing of the outbound messages of a connection
+    pub weight: u32,
+    // Maximum rate of outbound bytes of the protocol on a connection, unlimited if not set
+    pub max_bytes_per_sec: Option<u64>,
+}
+
+impl Default for ProtocolQuotaConfig {
+    fn default() -> Self {
+        Self {
+            weight: DEFAULT_PROTOCOL_WEIGHT,
+            max_bytes_per_sec: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InboundAdmissionConfig {
+    // Maximum number of inbound connections from a single IP address, unlimited if not set
+    pub max_connections_per_ip: Option<usize>,
+    // Maximum number of inbound connections from a single subnet, unlimited if not set
+    pub max_connections_per_subnet: Option<usize>,
+    // Prefix lengths of the subnets of IPv4 and IPv6 addresses
+    pub ipv4_subnet_prefix_len: u8,
+    pub ipv6_subnet_prefix_len: u8,
+    // Only addresses in these blocks may connect, any address may connect if empty
+    pub allow: Vec<CidrBlock>,
+    // Addresses in these blocks may not connect, even if allowed
+    pub deny: Vec<CidrBlock>,
+    // Number of consecutive failed handshakes after which an address is refused for
+    // `handshake_failure_penalty_secs`, never if not set. Failures of trusted peers don't count.
+    pub max_handshake_failures: Option<usize>,
+    pub handshake_failure_penalty_secs: u64,
+}
+
+impl Default for InboundAdmissionConfig {
+    fn default() -> Self {
+        Self {
+            max_connections_per_ip: None,
+            max_connections_per_subnet: None,
+            ipv4_subnet_prefix_len: IPV4_SUBNET_PREFIX_LEN,
+            ipv6_subnet_prefix_len: IPV6_SUBNET_PREFIX_LEN,
+            allow: Vec::new(),
+            deny: Vec::new(),
+            max_handshake_failures: None,
+            handshake_failure_penalty_secs: HANDSHAKE_FAILURE_PENALTY_SECS,
+        }
+    }
+}
+
+/// Consensus messages are latency sensitive, so they get a larger share of the bandwidth than
+/// bulk traffic like state sync chunks.
+fn default_protocol_quotas() -> BTreeMap<String, ProtocolQuotaConfig> {
+    let consensus_quota = ProtocolQuotaConfig {
+        weight: CONSENSUS_PROTOCOL_WEIGHT,
+        max_bytes_per_sec: None,
+    };
+    vec![
+        ("ConsensusRpc".to_string(), consensus_quota),
+        ("ConsensusDirectSend".to_string(), consensus_quota),
+    ]
+    .into_iter()
+    .collect()
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "snake_case", tag = "type")]
 pub enum Identity {
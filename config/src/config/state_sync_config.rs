@@ -8,10 +8,20 @@ use serde::{Deserialize, Serialize};
 pub struct StateSyncConfig {
     // Size of chunk to request for state synchronization
     pub chunk_limit: u64,
+    // Whether to download the account state at the latest epoch boundary from peers instead of
+    // replaying all the transactions before it, when syncing starts
+    pub enable_fast_sync: bool,
     // default timeout used for long polling to remote peer
     pub long_poll_timeout_ms: u64,
     // valid maximum chunk limit for sanity check
     pub max_chunk_limit: u64,
+    // max number of chunk requests for non-overlapping ranges of transactions that can be
+    // outstanding at the same time, each sent to a different peer. Parallel requests are only sent
+    // when the target to sync to within the current epoch is known. 1 disables parallel requests.
+    pub max_concurrent_chunk_requests: usize,
+    // max duration for which state sync stops applying chunks when paused by a component
+    // committing to the local storage, after which it resumes even if the pause was not released
+    pub max_pause_ms: u64,
     // max number of pending ledger info's to keep in memory
     // This is to prevent OOM
     pub max_pending_li_limit: usize,
@@ -21,6 +31,14 @@ pub struct StateSyncConfig {
     // if no progress is made by sending chunk requests to a number of networks,
     // the next sync request will be multicasted, i.e. sent to more networks
     pub multicast_timeout_ms: u64,
+    // duration for which an upstream peer is not sent any requests after too many bad responses
+    // (e.g., chunks with invalid proofs) or timeouts
+    pub peer_ban_duration_ms: u64,
+    // Number of accounts to request in a state snapshot chunk during fast sync (also the maximum
+    // served to peers)
+    pub snapshot_chunk_limit: u64,
+    // timeout before re-sending a state snapshot chunk request
+    pub snapshot_request_timeout_ms: u64,
     // default timeout for sync request
     pub sync_request_timeout_ms: u64,
     // interval used for checking state synchronization progress
@@ -31,11 +49,17 @@ impl Default for StateSyncConfig {
     fn default() -> Self {
         Self {
             chunk_limit: 250,
+            enable_fast_sync: false,
             long_poll_timeout_ms: 10_000,
             max_chunk_limit: 1000,
+            max_concurrent_chunk_requests: 1,
+            max_pause_ms: 10_000,
             max_pending_li_limit: 1000,
             max_timeout_ms: 120_000,
             multicast_timeout_ms: 30_000,
+            peer_ban_duration_ms: 60_000,
+            snapshot_chunk_limit: 1000,
+            snapshot_request_timeout_ms: 10_000,
             sync_request_timeout_ms: 60_000,
             tick_interval_ms: 100,
         }
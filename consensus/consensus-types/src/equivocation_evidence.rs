@@ -0,0 +1,122 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    block::Block,
+    common::{Author, Round},
+    vote::Vote,
+};
+use anyhow::{ensure, format_err};
+use libra_crypto::hash::CryptoHash;
+use libra_types::validator_verifier::ValidatorVerifier;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// EquivocationEvidence is the signed proof that a validator sent two conflicting messages
+/// for the same round. Both messages carry the signature of the misbehaving validator, so the
+/// evidence can be checked by anyone knowing the validator set of the epoch.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub enum EquivocationEvidence {
+    /// Two votes of the same author for different ledger infos.
+    Votes(Vote, Vote),
+    /// Two different proposals of the same author.
+    Proposals(Block, Block),
+}
+
+impl Display for EquivocationEvidence {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            EquivocationEvidence::Votes(first, second) => {
+                write!(f, "EquivocatingVotes: [{}, {}]", first, second)
+            }
+            EquivocationEvidence::Proposals(first, second) => {
+                write!(f, "EquivocatingProposals: [{}, {}]", first, second)
+            }
+        }
+    }
+}
+
+impl EquivocationEvidence {
+    /// The validator who equivocated. Proposals without an author are never equivocating, hence
+    /// the zero address is only returned for ill-formed evidence, which `verify` rejects.
+    pub fn author(&self) -> Author {
+        match self {
+            EquivocationEvidence::Votes(first, _) => first.author(),
+            EquivocationEvidence::Proposals(first, _) => first.author().unwrap_or(Author::ZERO),
+        }
+    }
+
+    /// The kind of the conflicting messages, either "vote" or "proposal".
+    pub fn kind(&self) -> &'static str {
+        match self {
+            EquivocationEvidence::Votes(..) => "vote",
+            EquivocationEvidence::Proposals(..) => "proposal",
+        }
+    }
+
+    pub fn epoch(&self) -> u64 {
+        match self {
+            EquivocationEvidence::Votes(first, _) => first.epoch(),
+            EquivocationEvidence::Proposals(first, _) => first.epoch(),
+        }
+    }
+
+    pub fn round(&self) -> Round {
+        match self {
+            EquivocationEvidence::Votes(first, _) => first.vote_data().proposed().round(),
+            EquivocationEvidence::Proposals(first, _) => first.round(),
+        }
+    }
+
+    /// Verifies that both messages are signed by the same author of the given validator set,
+    /// belong to the same epoch and round, and conflict with each other.
+    pub fn verify(&self, validator: &ValidatorVerifier) -> anyhow::Result<()> {
+        match self {
+            EquivocationEvidence::Votes(first, second) => {
+                ensure!(
+                    first.author() == second.author(),
+                    "Votes of different authors"
+                );
+                ensure!(
+                    first.epoch() == second.epoch()
+                        && first.vote_data().proposed().round()
+                            == second.vote_data().proposed().round(),
+                    "Votes for different rounds"
+                );
+                ensure!(
+                    first.ledger_info().hash() != second.ledger_info().hash(),
+                    "Votes are not conflicting"
+                );
+                first.verify(validator)?;
+                second.verify(validator)
+            }
+            EquivocationEvidence::Proposals(first, second) => {
+                let author = first
+                    .author()
+                    .ok_or_else(|| format_err!("Proposal without an author"))?;
+                ensure!(
+                    Some(author) == second.author(),
+                    "Proposals of different authors"
+                );
+                ensure!(
+                    first.epoch() == second.epoch() && first.round() == second.round(),
+                    "Proposals for different rounds"
+                );
+                ensure!(first.id() != second.id(), "Proposals are not conflicting");
+                first.validate_signature(validator)?;
+                second.validate_signature(validator)
+            }
+        }
+    }
+}
+
+/// Read access to the equivocation evidence collected by a validator.
+pub trait EquivocationEvidenceReader: Send + Sync {
+    /// Returns at most `limit` pieces of evidence starting from `start_epoch`, ordered by epoch,
+    /// round and author.
+    fn get_equivocation_evidence(
+        &self,
+        start_epoch: u64,
+        limit: u64,
+    ) -> anyhow::Result<Vec<EquivocationEvidence>>;
+}
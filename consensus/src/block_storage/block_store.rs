@@ -5,6 +5,7 @@ use crate::{
     block_storage::{block_tree::BlockTree, BlockReader},
     counters,
     logging::{LogEvent, LogSchema},
+    observer::{ObserverMsg, ObserverPublisher, OrderedBlock},
     persistent_liveness_storage::{
         PersistentLivenessStorage, RecoveryData, RootInfo, RootMetadata,
     },
@@ -35,6 +36,20 @@ mod block_store_and_lec_recovery_test;
 #[path = "sync_manager.rs"]
 pub mod sync_manager;
 
+/// Update the counters for the committed blocks and remove the blocks pruned from the tree from
+/// the persistent storage.
+fn finish_commit(
+    storage: &dyn PersistentLivenessStorage,
+    blocks_to_commit: &[Arc<ExecutedBlock>],
+    blocks_to_remove: Vec<HashValue>,
+) {
+    update_counters_for_committed_blocks(blocks_to_commit);
+    if let Err(e) = storage.prune_tree(blocks_to_remove) {
+        // it's fine to fail here, the next restart will clean up dangling blocks.
+        error!(error = ?e, "fail to delete block");
+    }
+}
+
 fn update_counters_for_committed_blocks(blocks_to_commit: &[Arc<ExecutedBlock>]) {
     for block in blocks_to_commit {
         if let Some(time_to_commit) =
@@ -94,6 +109,8 @@ pub struct BlockStore {
     storage: Arc<dyn PersistentLivenessStorage>,
     /// Used to ensure that any block stored will have a timestamp < the local time
     time_service: Arc<dyn TimeService>,
+    /// Publishes the certified blocks and the commit decisions to the consensus observers.
+    observer_publisher: Option<Arc<ObserverPublisher>>,
 }
 
 impl BlockStore {
@@ -103,6 +120,7 @@ impl BlockStore {
         state_computer: Arc<dyn StateComputer>,
         max_pruned_blocks_in_mem: usize,
         time_service: Arc<dyn TimeService>,
+        observer_publisher: Option<Arc<ObserverPublisher>>,
     ) -> Self {
         let highest_tc = initial_data.highest_timeout_certificate();
         let (root, root_metadata, blocks, quorum_certs) = initial_data.take();
@@ -116,6 +134,7 @@ impl BlockStore {
             storage,
             max_pruned_blocks_in_mem,
             time_service,
+            observer_publisher,
         )
     }
 
@@ -129,6 +148,7 @@ impl BlockStore {
         storage: Arc<dyn PersistentLivenessStorage>,
         max_pruned_blocks_in_mem: usize,
         time_service: Arc<dyn TimeService>,
+        observer_publisher: Option<Arc<ObserverPublisher>>,
     ) -> Self {
         let RootInfo(root_block, root_qc, root_li) = root;
         //verify root is correct
@@ -176,6 +196,7 @@ impl BlockStore {
             state_computer,
             storage,
             time_service,
+            observer_publisher,
         };
         for block in blocks {
             block_store
@@ -217,16 +238,52 @@ impl BlockStore {
             end_trace!("commit", {"block", block.id()});
         }
 
+        let current_round = self.root().round();
+        let committed_round = block_to_commit.round();
+        // The blocks leaving the tree are only removed from the persistent storage once the
+        // commit is finalized, so that the block of the latest ledger info is always recoverable.
+        let blocks_to_remove: Vec<_> = self.prune_tree(block_id_to_commit).into_iter().collect();
+        let storage = Arc::clone(&self.storage);
+        let state_computer = Arc::clone(&self.state_computer);
+        let block_ids = blocks_to_commit.iter().map(|b| b.id()).collect();
+        if let Some(publisher) = &self.observer_publisher {
+            publisher.publish(ObserverMsg::CommitDecision(Box::new(
+                finality_proof.clone(),
+            )));
+        }
         self.state_computer
             .commit(
-                blocks_to_commit.iter().map(|b| b.id()).collect(),
+                block_ids,
                 finality_proof,
+                Box::new(move |_, ledger_info, result| match result {
+                    Ok(()) => finish_commit(&*storage, &blocks_to_commit, blocks_to_remove),
+                    Err(e) => {
+                        error!(
+                            error = ?e,
+                            committed_round = committed_round,
+                            "Failed to persist commit, syncing to it",
+                        );
+                        counters::FAILED_COMMITS_COUNT.inc();
+                        // The in-memory tree is already pruned up to the committed block, so the
+                        // storage has to catch up with it. State sync also resets the executor,
+                        // which fails the commits following a failed one until then.
+                        tokio::spawn(async move {
+                            match state_computer.sync_to(ledger_info).await {
+                                Ok(()) => {
+                                    finish_commit(&*storage, &blocks_to_commit, blocks_to_remove)
+                                }
+                                Err(e) => error!(
+                                    error = ?e,
+                                    committed_round = committed_round,
+                                    "Failed to sync to a commit that failed to persist",
+                                ),
+                            }
+                        });
+                    }
+                }),
             )
             .await
-            .expect("Failed to persist commit");
-        update_counters_for_committed_blocks(&blocks_to_commit);
-        let current_round = self.root().round();
-        let committed_round = block_to_commit.round();
+            .expect("Failed to hand over commit to the state computer");
         debug!(
             LogSchema::new(LogEvent::CommitViaBlock).round(current_round),
             committed_round = committed_round,
@@ -238,7 +295,6 @@ impl BlockStore {
             "round": committed_round,
             "parent_id": block_to_commit.parent_id().short_str(),
         );
-        self.prune_tree(block_to_commit.id());
         Ok(())
     }
 
@@ -262,6 +318,7 @@ impl BlockStore {
             Arc::clone(&self.storage),
             max_pruned_blocks_in_mem,
             Arc::clone(&self.time_service),
+            self.observer_publisher.clone(),
         );
         let to_remove = self.inner.read().get_all_block_id();
         if let Err(e) = self.storage.prune_tree(to_remove) {
@@ -344,7 +401,7 @@ impl BlockStore {
         // state and on restart, a new execution will agree with it.  A new execution will match
         // the QuorumCert's state on the next restart will work if there is a memory
         // corruption, for example.
-        match self.get_block(qc.certified_block().id()) {
+        let executed_block = match self.get_block(qc.certified_block().id()) {
             Some(executed_block) => {
                 ensure!(
                     executed_block.block_info() == *qc.certified_block(),
@@ -353,14 +410,27 @@ impl BlockStore {
                     qc.certified_block(),
                     executed_block.block_info()
                 );
+                executed_block
             }
             None => bail!("Insert {} without having the block in store first", qc),
-        }
+        };
 
         self.storage
             .save_tree(vec![], vec![qc.clone()])
             .context("Insert block failed when saving quorum")?;
-        self.inner.write().insert_quorum_cert(qc)
+        let is_new_qc = self
+            .get_quorum_cert_for_block(qc.certified_block().id())
+            .is_none();
+        self.inner.write().insert_quorum_cert(qc.clone())?;
+        if let Some(publisher) = &self.observer_publisher {
+            if is_new_qc {
+                publisher.publish(ObserverMsg::OrderedBlock(Box::new(OrderedBlock {
+                    block: executed_block.block().clone(),
+                    quorum_cert: qc,
+                })));
+            }
+        }
+        Ok(())
     }
 
     /// Replace the highest timeout certificate in case the given one has a higher round.
@@ -387,18 +457,10 @@ impl BlockStore {
     /// prune_tree(B3) should be left with
     /// B3--> B4, root = B3
     ///
-    /// Returns the block ids of the blocks removed.
+    /// Returns the block ids of the blocks removed, which are left to the caller to delete from
+    /// the persistent storage.
     fn prune_tree(&self, next_root_id: HashValue) -> VecDeque<HashValue> {
         let id_to_remove = self.inner.read().find_blocks_to_prune(next_root_id);
-        if let Err(e) = self
-            .storage
-            .prune_tree(id_to_remove.clone().into_iter().collect())
-        {
-            // it's fine to fail here, as long as the commit succeeds, the next restart will clean
-            // up dangling blocks, and we need to prune the tree to keep the root consistent with
-            // executor.
-            error!(error = ?e, "fail to delete block");
-        }
         self.inner
             .write()
             .process_pruned_blocks(next_root_id, id_to_remove.clone());
@@ -5,7 +5,7 @@ use crate::{
     block_storage::{block_store::BlockStore, BlockReader},
     persistent_liveness_storage::{LedgerRecoveryData, RecoveryData, RootMetadata},
     state_computer::ExecutionProxy,
-    test_utils::{EmptyStorage, TreeInserter},
+    test_utils::{consensus_runtime, EmptyStorage, TreeInserter},
     util::mock_time_service::SimulatedTimeService,
 };
 use consensus_types::{block::Block, quorum_cert::QuorumCert};
@@ -19,6 +19,7 @@ use libra_types::validator_signer::ValidatorSigner;
 use state_synchronizer::StateSyncClient;
 use std::sync::Arc;
 use storage_interface::DbReader;
+use tokio::runtime::Handle;
 
 fn get_initial_data_and_qc(db: &dyn DbReader) -> (RecoveryData, QuorumCert) {
     // find the block corresponding to storage latest ledger info
@@ -62,12 +63,15 @@ fn build_inserter(
     config: &NodeConfig,
     initial_data: RecoveryData,
     lec_client: Box<dyn ExecutionCorrectness + Send + Sync>,
+    handle: &Handle,
 ) -> TreeInserter {
     let (coordinator_sender, _coordinator_receiver) = mpsc::unbounded();
 
     let state_computer = Arc::new(ExecutionProxy::new(
         lec_client,
         Arc::new(StateSyncClient::new(coordinator_sender)),
+        handle,
+        config.consensus.max_pending_commits,
     ));
 
     TreeInserter::new_with_store(
@@ -81,6 +85,7 @@ fn build_inserter(
             state_computer,
             10, // max pruned blocks in mem
             Arc::new(SimulatedTimeService::new()),
+            None,
         )),
     )
 }
@@ -89,6 +94,7 @@ fn build_inserter(
 fn test_executor_restart() {
     // Start storage service
     let (config, _handle, db) = start_storage_service();
+    let runtime = consensus_runtime();
     let execution_correctness_manager = ExecutionCorrectnessManager::new(&config);
 
     let (initial_data, qc) = get_initial_data_and_qc(&*db);
@@ -97,6 +103,7 @@ fn test_executor_restart() {
         &config,
         initial_data,
         execution_correctness_manager.client(),
+        runtime.handle(),
     );
 
     let block_store = inserter.block_store();
@@ -130,6 +137,7 @@ fn test_executor_restart() {
 fn test_block_store_restart() {
     // Start storage service
     let (config, _handle, db) = start_storage_service();
+    let runtime = consensus_runtime();
 
     let execution_correctness_manager = ExecutionCorrectnessManager::new(&config);
 
@@ -139,6 +147,7 @@ fn test_block_store_restart() {
             &config,
             initial_data,
             execution_correctness_manager.client(),
+            runtime.handle(),
         );
 
         let block_store = inserter.block_store();
@@ -162,6 +171,7 @@ fn test_block_store_restart() {
             &config,
             initial_data,
             execution_correctness_manager.client(),
+            runtime.handle(),
         );
         let block_store = inserter.block_store();
         let genesis = block_store.root();
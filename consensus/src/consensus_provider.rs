@@ -2,10 +2,14 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    consensusdb::ConsensusDB,
     counters,
     epoch_manager::EpochManager,
     network::NetworkTask,
     network_interface::{ConsensusNetworkEvents, ConsensusNetworkSender},
+    observer::{
+        ConsensusObserver, ObserverNetworkEvents, ObserverNetworkSender, ObserverPublisher,
+    },
     persistent_liveness_storage::StorageWriteProxy,
     state_computer::ExecutionProxy,
     txn_manager::MempoolProxy,
@@ -23,11 +27,19 @@ use std::sync::Arc;
 use storage_interface::DbReader;
 use tokio::runtime::{self, Runtime};
 
+/// Opens the consensus db of the node. The handle is shared between consensus and the
+/// services reading the equivocation evidence collected by consensus.
+pub fn open_consensus_db(node_config: &NodeConfig) -> Arc<ConsensusDB> {
+    Arc::new(ConsensusDB::new(node_config.storage.dir()))
+}
+
 /// Helper function to start consensus based on configuration and return the runtime
 pub fn start_consensus(
     node_config: &NodeConfig,
+    consensus_db: Arc<ConsensusDB>,
     network_sender: ConsensusNetworkSender,
     network_events: ConsensusNetworkEvents,
+    observer_network_handles: Vec<(ObserverNetworkSender, ObserverNetworkEvents)>,
     state_sync_client: Arc<StateSyncClient>,
     consensus_to_mempool_sender: mpsc::Sender<ConsensusRequest>,
     libra_db: Arc<dyn DbReader>,
@@ -39,7 +51,7 @@ pub fn start_consensus(
         .enable_all()
         .build()
         .expect("Failed to create Tokio runtime!");
-    let storage = Arc::new(StorageWriteProxy::new(node_config, libra_db));
+    let storage = Arc::new(StorageWriteProxy::new(consensus_db, libra_db));
     let txn_manager = Arc::new(MempoolProxy::new(
         consensus_to_mempool_sender,
         node_config.consensus.mempool_poll_count,
@@ -48,12 +60,27 @@ pub fn start_consensus(
     let state_computer = Arc::new(ExecutionProxy::new(
         execution_correctness_manager.client(),
         state_sync_client,
+        runtime.handle(),
+        node_config.consensus.max_pending_commits,
     ));
     let time_service = Arc::new(ClockTimeService::new(runtime.handle().clone()));
 
     let (timeout_sender, timeout_receiver) = channel::new(1_024, &counters::PENDING_ROUND_TIMEOUTS);
     let (self_sender, self_receiver) = channel::new(1_024, &counters::PENDING_SELF_MESSAGES);
 
+    // The consensus observers subscribe on the full node networks.
+    let observer_publisher = if observer_network_handles.is_empty() {
+        None
+    } else {
+        let publisher = Arc::new(ObserverPublisher::new(
+            node_config.consensus.observer.max_subscribers,
+        ));
+        for (observer_sender, observer_events) in observer_network_handles {
+            runtime.spawn(Arc::clone(&publisher).start(observer_sender, observer_events));
+        }
+        Some(publisher)
+    };
+
     let epoch_mgr = EpochManager::new(
         node_config,
         time_service,
@@ -64,6 +91,7 @@ pub fn start_consensus(
         state_computer,
         storage,
         reconfig_events,
+        observer_publisher,
     );
 
     let (network_task, network_receiver) = NetworkTask::new(network_events, self_receiver);
@@ -74,3 +102,34 @@ pub fn start_consensus(
     debug!("Consensus started.");
     runtime
 }
+
+/// Helper function to start the consensus observer of a full node and return the runtime
+pub fn start_consensus_observer(
+    node_config: &NodeConfig,
+    network_handles: Vec<(ObserverNetworkSender, ObserverNetworkEvents)>,
+    state_sync_client: Arc<StateSyncClient>,
+    reconfig_events: libra_channel::Receiver<(), OnChainConfigPayload>,
+) -> Runtime {
+    let runtime = runtime::Builder::new()
+        .thread_name("consensus-observer")
+        .threaded_scheduler()
+        .enable_all()
+        .build()
+        .expect("Failed to create Tokio runtime!");
+    // Full nodes don't sign the execution results.
+    let execution_correctness_manager = ExecutionCorrectnessManager::new_local(
+        node_config.storage.address,
+        None,
+        node_config.storage.timeout_ms,
+    );
+    let observer = ConsensusObserver::new(
+        &node_config.consensus.observer,
+        execution_correctness_manager.client(),
+        state_sync_client,
+        reconfig_events,
+    );
+    runtime.spawn(observer.start(network_handles));
+
+    debug!("Consensus observer started.");
+    runtime
+}
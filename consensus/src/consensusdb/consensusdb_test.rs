@@ -4,6 +4,7 @@
 use super::*;
 use consensus_types::block::block_test_utils::certificate_for_genesis;
 use libra_temppath::TempPath;
+use libra_types::validator_signer::ValidatorSigner;
 
 #[test]
 fn test_put_get() {
@@ -65,3 +66,34 @@ fn test_delete_block_and_qc() {
     assert_eq!(db.get_blocks().unwrap().len(), 0);
     assert_eq!(db.get_quorum_certificates().unwrap().len(), 0);
 }
+
+#[test]
+fn test_equivocation_evidence() {
+    let tmp_dir = TempPath::new();
+    let db = ConsensusDB::new(&tmp_dir);
+    let signer = ValidatorSigner::random(None);
+
+    let proposal = |round, timestamp| {
+        Block::new_proposal(vec![], round, timestamp, certificate_for_genesis(), &signer)
+    };
+    let evidence_round_2 = EquivocationEvidence::Proposals(proposal(2, 1), proposal(2, 2));
+    let evidence_round_1 = EquivocationEvidence::Proposals(proposal(1, 1), proposal(1, 2));
+    db.save_equivocation_evidence(&evidence_round_2).unwrap();
+    db.save_equivocation_evidence(&evidence_round_1).unwrap();
+    // only the first evidence of an author in a round is kept
+    db.save_equivocation_evidence(&EquivocationEvidence::Proposals(
+        proposal(1, 3),
+        proposal(1, 4),
+    ))
+    .unwrap();
+
+    assert_eq!(
+        db.get_equivocation_evidence(0, 10).unwrap(),
+        vec![evidence_round_1.clone(), evidence_round_2]
+    );
+    assert_eq!(
+        db.get_equivocation_evidence(0, 1).unwrap(),
+        vec![evidence_round_1]
+    );
+    assert!(db.get_equivocation_evidence(2, 10).unwrap().is_empty());
+}
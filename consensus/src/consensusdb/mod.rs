@@ -8,16 +8,22 @@ mod schema;
 use crate::{
     consensusdb::schema::{
         block::BlockSchema,
+        equivocation_evidence::EquivocationEvidenceSchema,
         quorum_certificate::QCSchema,
         single_entry::{SingleEntryKey, SingleEntrySchema},
     },
     error::DbError,
 };
 use anyhow::Result;
-use consensus_types::{block::Block, quorum_cert::QuorumCert};
+use consensus_types::{
+    block::Block,
+    equivocation_evidence::{EquivocationEvidence, EquivocationEvidenceReader},
+    quorum_cert::QuorumCert,
+};
 use libra_crypto::HashValue;
 use libra_logger::prelude::*;
-use schema::{BLOCK_CF_NAME, QC_CF_NAME, SINGLE_ENTRY_CF_NAME};
+use libra_types::account_address::AccountAddress;
+use schema::{BLOCK_CF_NAME, EQUIVOCATION_EVIDENCE_CF_NAME, QC_CF_NAME, SINGLE_ENTRY_CF_NAME};
 use schemadb::{ReadOptions, SchemaBatch, DB, DEFAULT_CF_NAME};
 use std::{collections::HashMap, iter::Iterator, path::Path, time::Instant};
 
@@ -30,6 +36,7 @@ impl ConsensusDB {
         let column_families = vec![
             /* UNUSED CF = */ DEFAULT_CF_NAME,
             BLOCK_CF_NAME,
+            EQUIVOCATION_EVIDENCE_CF_NAME,
             QC_CF_NAME,
             SINGLE_ENTRY_CF_NAME,
         ];
@@ -133,6 +140,21 @@ impl ConsensusDB {
         self.commit(batch)
     }
 
+    /// Persist the evidence of a validator equivocating, only the first evidence of an author in
+    /// a round is kept.
+    pub fn save_equivocation_evidence(
+        &self,
+        evidence: &EquivocationEvidence,
+    ) -> Result<(), DbError> {
+        let key = (evidence.epoch(), evidence.round(), evidence.author());
+        if self.db.get::<EquivocationEvidenceSchema>(&key)?.is_some() {
+            return Ok(());
+        }
+        let mut batch = SchemaBatch::new();
+        batch.put::<EquivocationEvidenceSchema>(&key, evidence)?;
+        self.commit(batch)
+    }
+
     /// Write the whole schema batch including all data necessary to mutate the ledger
     /// state of some transaction by leveraging rocksdb atomicity support.
     fn commit(&self, batch: SchemaBatch) -> Result<(), DbError> {
@@ -182,3 +204,19 @@ impl ConsensusDB {
         Ok(iter.collect::<Result<HashMap<HashValue, QuorumCert>>>()?)
     }
 }
+
+impl EquivocationEvidenceReader for ConsensusDB {
+    fn get_equivocation_evidence(
+        &self,
+        start_epoch: u64,
+        limit: u64,
+    ) -> Result<Vec<EquivocationEvidence>> {
+        let mut iter = self
+            .db
+            .iter::<EquivocationEvidenceSchema>(ReadOptions::default())?;
+        iter.seek(&(start_epoch, 0, AccountAddress::ZERO))?;
+        iter.take(limit as usize)
+            .map(|entry| entry.map(|(_key, evidence)| evidence))
+            .collect()
+    }
+}
@@ -0,0 +1,64 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines physical storage schema for the evidence of validators equivocating.
+//!
+//! Serialized evidence identified by the epoch, round and author of the conflicting messages.
+//! The integers are big endian encoded so that the evidence is ordered by epoch and round.
+//! ```text
+//! |<----------key---------->|<-------value------->|
+//! | epoch | round | author  | EquivocationEvidence |
+//! ```
+
+use super::{ensure_slice_len_eq, EQUIVOCATION_EVIDENCE_CF_NAME};
+use anyhow::Result;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use consensus_types::{
+    common::{Author, Round},
+    equivocation_evidence::EquivocationEvidence,
+};
+use schemadb::{
+    define_schema,
+    schema::{KeyCodec, ValueCodec},
+};
+use std::{convert::TryFrom, mem::size_of};
+
+define_schema!(
+    EquivocationEvidenceSchema,
+    (u64, Round, Author),
+    EquivocationEvidence,
+    EQUIVOCATION_EVIDENCE_CF_NAME
+);
+
+impl KeyCodec<EquivocationEvidenceSchema> for (u64, Round, Author) {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        let (epoch, round, author) = self;
+        let mut encoded = vec![];
+        encoded.write_u64::<BigEndian>(*epoch)?;
+        encoded.write_u64::<BigEndian>(*round)?;
+        encoded.extend_from_slice(author.as_ref());
+        Ok(encoded)
+    }
+
+    fn decode_key(data: &[u8]) -> Result<Self> {
+        ensure_slice_len_eq(data, 2 * size_of::<u64>() + Author::LENGTH)?;
+        let mut reader = data;
+        let epoch = reader.read_u64::<BigEndian>()?;
+        let round = reader.read_u64::<BigEndian>()?;
+        let author = Author::try_from(reader)?;
+        Ok((epoch, round, author))
+    }
+}
+
+impl ValueCodec<EquivocationEvidenceSchema> for EquivocationEvidence {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        Ok(lcs::to_bytes(self)?)
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        Ok(lcs::from_bytes(data)?)
+    }
+}
+
+#[cfg(test)]
+mod test;
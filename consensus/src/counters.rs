@@ -2,8 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use libra_metrics::{
-    register_histogram, register_int_counter, register_int_counter_vec, register_int_gauge,
-    DurationHistogram, Histogram, IntCounter, IntCounterVec, IntGauge,
+    register_histogram, register_histogram_vec, register_int_counter, register_int_counter_vec,
+    register_int_gauge, DurationHistogram, Histogram, HistogramVec, IntCounter, IntCounterVec,
+    IntGauge,
 };
 use once_cell::sync::Lazy;
 
@@ -60,6 +61,15 @@ pub static COMMITTED_TXNS_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Count of the commits that failed to persist since last restart.
+pub static FAILED_COMMITS_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "libra_consensus_failed_commits_count",
+        "Count of the commits that failed to persist since last restart."
+    )
+    .unwrap()
+});
+
 //////////////////////
 // PROPOSAL ELECTION
 //////////////////////
@@ -79,6 +89,16 @@ pub static VOTE_NIL_COUNT: Lazy<IntCounter> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Count the equivocating votes and proposals received since last restart.
+pub static EQUIVOCATION_EVIDENCE_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "libra_consensus_equivocation_evidence_count",
+        "Count the equivocating votes and proposals received since last restart.",
+        &["type"]
+    )
+    .unwrap()
+});
+
 //////////////////////
 // RoundState COUNTERS
 //////////////////////
@@ -216,6 +236,18 @@ pub static WAIT_DURATION_S: Lazy<DurationHistogram> = Lazy::new(|| {
     DurationHistogram::new(register_histogram!("libra_consensus_wait_duration_s", "Histogram of the time it requires to wait before inserting blocks into block store. Measured as the block's timestamp minus the local timestamp.").unwrap())
 });
 
+/// Duration of each stage a block goes through in the execution and commit pipeline:
+/// `execute`, `order` (waiting for room in the commit pipeline), `commit_queue`, `persist`
+/// and `notify_state_sync`.
+pub static PIPELINE_STAGE_DURATION_S: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "libra_consensus_pipeline_stage_duration_s",
+        "Duration of each stage a block goes through in the execution and commit pipeline",
+        &["stage"]
+    )
+    .unwrap()
+});
+
 ///////////////////
 // CHANNEL COUNTERS
 ///////////////////
@@ -237,6 +269,15 @@ pub static PENDING_ROUND_TIMEOUTS: Lazy<IntGauge> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Count of the commits ordered by consensus but not yet persisted by execution
+pub static PENDING_COMMITS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "libra_consensus_pending_commits",
+        "Count of the commits ordered by consensus but not yet persisted by execution"
+    )
+    .unwrap()
+});
+
 /// Counter of pending network events to Consensus
 pub static PENDING_CONSENSUS_NETWORK_EVENTS: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
@@ -266,3 +307,42 @@ pub static BLOCK_RETRIEVAL_CHANNEL_MSGS: Lazy<IntCounterVec> = Lazy::new(|| {
     )
     .unwrap()
 });
+
+/// Counter of pending network events to the consensus observer
+pub static PENDING_OBSERVER_NETWORK_EVENTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "libra_consensus_observer_pending_network_events",
+        "Counters(queued,dequeued,dropped) related to pending network notifications to the consensus observer",
+        &["state"]
+    )
+    .unwrap()
+});
+
+/// Number of full nodes subscribed to the certified blocks of this validator
+pub static OBSERVER_SUBSCRIBERS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "libra_consensus_observer_subscribers",
+        "Number of full nodes subscribed to the certified blocks of this validator"
+    )
+    .unwrap()
+});
+
+/// Count the blocks handled by the consensus observer of a full node, by outcome
+/// (executed, committed, dropped)
+pub static OBSERVER_BLOCKS_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "libra_consensus_observer_blocks_count",
+        "Count the blocks handled by the consensus observer of a full node, by outcome",
+        &["state"]
+    )
+    .unwrap()
+});
+
+/// The round of the last block committed by the consensus observer of a full node
+pub static OBSERVER_LAST_COMMITTED_ROUND: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "libra_consensus_observer_last_committed_round",
+        "The round of the last block committed by the consensus observer of a full node"
+    )
+    .unwrap()
+});
@@ -6,7 +6,10 @@ use crate::{
     counters,
     error::{error_kind, DbError},
     liveness::{
-        leader_reputation::{ActiveInactiveHeuristic, LeaderReputation, LibraDBBackend},
+        leader_reputation::{
+            ActiveInactiveHeuristic, LeaderReputation, LibraDBBackend,
+            ProposerAndFailedRoundsHeuristic, ReputationHeuristic,
+        },
         proposal_generator::ProposalGenerator,
         proposer_election::ProposerElection,
         rotating_proposer_election::{choose_leader, RotatingProposer},
@@ -17,6 +20,7 @@ use crate::{
     metrics_safety_rules::MetricsSafetyRules,
     network::{IncomingBlockRetrievalRequest, NetworkReceivers, NetworkSender},
     network_interface::{ConsensusMsg, ConsensusNetworkSender},
+    observer::ObserverPublisher,
     persistent_liveness_storage::{LedgerRecoveryData, PersistentLivenessStorage, RecoveryData},
     round_manager::{RecoveryManager, RoundManager, UnverifiedEvent, VerifiedEvent},
     state_replication::{StateComputer, TxnManager},
@@ -29,7 +33,9 @@ use consensus_types::{
     epoch_retrieval::EpochRetrievalRequest,
 };
 use futures::{select, StreamExt};
-use libra_config::config::{ConsensusConfig, ConsensusProposerType, NodeConfig};
+use libra_config::config::{
+    ConsensusConfig, ConsensusProposerType, LeaderReputationConfig, NodeConfig,
+};
 use libra_infallible::duration_since_epoch;
 use libra_logger::prelude::*;
 use libra_metrics::monitor;
@@ -82,6 +88,7 @@ pub struct EpochManager {
     safety_rules_manager: SafetyRulesManager,
     processor: Option<RoundProcessor>,
     reconfig_events: libra_channel::Receiver<(), OnChainConfigPayload>,
+    observer_publisher: Option<Arc<ObserverPublisher>>,
 }
 
 impl EpochManager {
@@ -95,6 +102,7 @@ impl EpochManager {
         state_computer: Arc<dyn StateComputer>,
         storage: Arc<dyn PersistentLivenessStorage>,
         reconfig_events: libra_channel::Receiver<(), OnChainConfigPayload>,
+        observer_publisher: Option<Arc<ObserverPublisher>>,
     ) -> Self {
         let author = node_config.validator_network.as_ref().unwrap().peer_id();
         let config = node_config.consensus.clone();
@@ -113,6 +121,7 @@ impl EpochManager {
             safety_rules_manager,
             processor: None,
             reconfig_events,
+            observer_publisher,
         }
     }
 
@@ -169,15 +178,36 @@ impl EpochManager {
                 ))
             }
             ConsensusProposerType::LeaderReputation(heuristic_config) => {
-                let backend = Box::new(LibraDBBackend::new(
-                    proposers.len(),
-                    self.storage.libra_db(),
-                ));
-                let heuristic = Box::new(ActiveInactiveHeuristic::new(
-                    heuristic_config.active_weights,
-                    heuristic_config.inactive_weights,
-                ));
-                Box::new(LeaderReputation::new(proposers, backend, heuristic))
+                let (window_size, heuristic, exclude_round): (_, Box<dyn ReputationHeuristic>, _) =
+                    match heuristic_config {
+                        LeaderReputationConfig::ActiveInactive(config) => (
+                            proposers.len(),
+                            Box::new(ActiveInactiveHeuristic::new(
+                                config.active_weights,
+                                config.inactive_weights,
+                            )),
+                            4,
+                        ),
+                        LeaderReputationConfig::ProposerAndFailedRounds(config) => (
+                            proposers.len() * config.window_num_validators_multiplier,
+                            Box::new(ProposerAndFailedRoundsHeuristic::new(
+                                config.active_weights,
+                                config.inactive_weights,
+                                config.failed_weights,
+                                config.failure_threshold_percent,
+                                config.decay_percent,
+                            )),
+                            config.exclude_round,
+                        ),
+                    };
+                let backend = Box::new(LibraDBBackend::new(window_size, self.storage.libra_db()));
+                Box::new(LeaderReputation::new(
+                    epoch_state.epoch,
+                    proposers,
+                    backend,
+                    heuristic,
+                    exclude_round,
+                ))
             }
             ConsensusProposerType::RoundProposer(round_proposers) => {
                 // Hardcoded to the first proposer
@@ -298,6 +328,7 @@ impl EpochManager {
             Arc::clone(&self.state_computer),
             self.config.max_pruned_blocks_in_mem,
             Arc::clone(&self.time_service),
+            self.observer_publisher.clone(),
         ));
 
         info!(epoch = epoch, "Update SafetyRules");
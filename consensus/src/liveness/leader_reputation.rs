@@ -1,7 +1,8 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::liveness::proposer_election::{next, ProposerElection};
+use crate::liveness::proposer_election::{choose_index, ProposerElection};
+use anyhow::format_err;
 use consensus_types::{
     block::Block,
     common::{Author, Round},
@@ -12,22 +13,34 @@ use libra_logger::prelude::*;
 use libra_types::block_metadata::{new_block_event_key, NewBlockEvent};
 use std::{
     cmp::Ordering,
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     sync::Arc,
 };
 use storage_interface::{DbReader, Order};
 
+/// The committed BlockMetadata the proposers are elected from.
+#[derive(Clone, Default)]
+pub struct History {
+    /// The committed blocks with their epoch, from the most recently committed one.
+    pub blocks: Vec<(u64, NewBlockEvent)>,
+    /// The validators of the previous epochs of `blocks`, in the order of the election.
+    pub proposers: HashMap<u64, Vec<Author>>,
+}
+
 /// Interface to query committed BlockMetadata.
 pub trait MetadataBackend: Send + Sync {
-    /// Return a contiguous BlockMetadata window in which last one is at target_round or
-    /// latest committed, return all previous one if not enough.
-    fn get_block_metadata(&self, target_round: Round) -> Vec<NewBlockEvent>;
+    /// Return a contiguous BlockMetadata window in which last one is at target_round of
+    /// target_epoch or latest committed, return all previous one if not enough.
+    fn get_block_metadata(&self, target_epoch: u64, target_round: Round) -> History;
 }
 
 pub struct LibraDBBackend {
     window_size: usize,
     libra_db: Arc<dyn DbReader>,
-    window: Mutex<Vec<(u64, NewBlockEvent)>>,
+    // (version, epoch, event) of the blocks of the window
+    window: Mutex<Vec<(u64, u64, NewBlockEvent)>>,
+    // the first version and the validators of the epochs seen so far, which never change
+    epochs: Mutex<HashMap<u64, (u64, Vec<Author>)>>,
 }
 
 impl LibraDBBackend {
@@ -36,10 +49,42 @@ impl LibraDBBackend {
             window_size,
             libra_db,
             window: Mutex::new(vec![]),
+            epochs: Mutex::new(HashMap::new()),
         }
     }
 
-    fn refresh_window(&self, target_round: Round) -> anyhow::Result<()> {
+    /// The first version and the validators of `epoch`, read from the ledger info ending the
+    /// previous epoch.
+    fn epoch_info(&self, epoch: u64) -> anyhow::Result<(u64, Vec<Author>)> {
+        if epoch == 0 {
+            return Ok((0, vec![]));
+        }
+        if let Some(info) = self.epochs.lock().get(&epoch) {
+            return Ok(info.clone());
+        }
+        let proof = self
+            .libra_db
+            .get_epoch_ending_ledger_infos(epoch - 1, epoch)?;
+        let ledger_info = proof
+            .ledger_info_with_sigs
+            .first()
+            .ok_or_else(|| format_err!("No ledger info ending epoch {}", epoch - 1))?
+            .ledger_info();
+        let epoch_state = ledger_info.next_epoch_state().ok_or_else(|| {
+            format_err!("Ledger info of epoch {} is not an epoch change", epoch - 1)
+        })?;
+        let info = (
+            ledger_info.version() + 1,
+            epoch_state
+                .verifier
+                .get_ordered_account_addresses_iter()
+                .collect(),
+        );
+        self.epochs.lock().insert(epoch, info.clone());
+        Ok(info)
+    }
+
+    fn refresh_window(&self, target_epoch: u64, target_round: Round) -> anyhow::Result<()> {
         // assumes target round is not too far from latest commit
         let buffer = 10;
         let events = self.libra_db.get_events(
@@ -48,11 +93,24 @@ impl LibraDBBackend {
             Order::Descending,
             self.window_size as u64 + buffer,
         )?;
+        // the epoch of the versions is read from the epoch ending ledger infos, going back from
+        // the epoch of the latest ledger info as the events are in descending order
+        let mut epoch = self
+            .libra_db
+            .get_latest_ledger_info()?
+            .ledger_info()
+            .epoch();
+        let mut epoch_start = self.epoch_info(epoch)?.0;
         let mut result = vec![];
         for (v, e) in events {
+            while v < epoch_start {
+                epoch -= 1;
+                epoch_start = self.epoch_info(epoch)?.0;
+            }
             let e = lcs::from_bytes::<NewBlockEvent>(e.event_data())?;
-            if e.round() <= target_round && result.len() < self.window_size {
-                result.push((v, e));
+            if (epoch, e.round()) <= (target_epoch, target_round) && result.len() < self.window_size
+            {
+                result.push((v, epoch, e));
             }
         }
         *self.window.lock() = result;
@@ -62,36 +120,51 @@ impl LibraDBBackend {
 
 impl MetadataBackend for LibraDBBackend {
     // assume the target_round only increases
-    fn get_block_metadata(&self, target_round: Round) -> Vec<NewBlockEvent> {
-        let (known_version, known_round) = self
+    fn get_block_metadata(&self, target_epoch: u64, target_round: Round) -> History {
+        let (known_version, known_epoch, known_round) = self
             .window
             .lock()
             .first()
-            .map(|(v, e)| (*v, e.round()))
-            .unwrap_or((0, 0));
-        if !(known_round == target_round
+            .map(|(v, epoch, e)| (*v, *epoch, e.round()))
+            .unwrap_or((0, 0, 0));
+        if !((known_epoch, known_round) == (target_epoch, target_round)
             || known_version == self.libra_db.get_latest_version().unwrap_or(0))
         {
-            if let Err(e) = self.refresh_window(target_round) {
+            if let Err(e) = self.refresh_window(target_epoch, target_round) {
                 error!(
                     error = ?e, "[leader reputation] Fail to refresh window",
                 );
-                return vec![];
+                return History::default();
             }
         }
-        self.window
+        let blocks: Vec<_> = self
+            .window
             .lock()
-            .clone()
-            .into_iter()
-            .map(|(_, e)| e)
-            .collect()
+            .iter()
+            .map(|(_, epoch, e)| (*epoch, e.clone()))
+            .collect();
+        let epochs = self.epochs.lock();
+        let proposers = blocks
+            .iter()
+            .filter_map(|(epoch, _)| {
+                epochs
+                    .get(epoch)
+                    .map(|(_, proposers)| (*epoch, proposers.clone()))
+            })
+            .collect();
+        History { blocks, proposers }
     }
 }
 
 /// Interface to calculate weights for proposers based on history.
 pub trait ReputationHeuristic: Send + Sync {
-    /// Return the weights of all candidates based on the history.
-    fn get_weights(&self, candidates: &[Author], history: &[NewBlockEvent]) -> Vec<u64>;
+    /// Return the weights of all candidates of `epoch` based on the committed history.
+    fn get_weights(&self, epoch: u64, candidates: &[Author], history: &History) -> Vec<u64>;
+
+    /// The seed of the weighted random selection of the proposer of a round.
+    fn election_seed(&self, _epoch: u64, round: Round) -> Vec<u8> {
+        round.to_le_bytes().to_vec()
+    }
 }
 
 /// If candidate appear in the history, it's assigned active_weight otherwise inactive weight.
@@ -110,12 +183,15 @@ impl ActiveInactiveHeuristic {
 }
 
 impl ReputationHeuristic for ActiveInactiveHeuristic {
-    fn get_weights(&self, candidates: &[Author], history: &[NewBlockEvent]) -> Vec<u64> {
-        let set = history.iter().fold(HashSet::new(), |mut set, meta| {
-            set.insert(meta.proposer());
-            set.extend(meta.votes().into_iter());
-            set
-        });
+    fn get_weights(&self, _epoch: u64, candidates: &[Author], history: &History) -> Vec<u64> {
+        let set = history
+            .blocks
+            .iter()
+            .fold(HashSet::new(), |mut set, (_, meta)| {
+                set.insert(meta.proposer());
+                set.extend(meta.votes().into_iter());
+                set
+            });
         candidates
             .iter()
             .map(|author| {
@@ -129,25 +205,170 @@ impl ReputationHeuristic for ActiveInactiveHeuristic {
     }
 }
 
+/// Fixed point scale of the decay applied to the history of previous epochs.
+const DECAY_SCALE: u64 = 1_000_000;
+
+/// The contributions of the committed blocks of an epoch to the reputation of the candidates.
+#[derive(Default)]
+struct EpochReputation {
+    proposals: HashMap<Author, u64>,
+    participations: HashMap<Author, u64>,
+    failures: HashMap<Author, u64>,
+}
+
+impl EpochReputation {
+    fn add_block(&mut self, event: &NewBlockEvent) {
+        *self.proposals.entry(event.proposer()).or_default() += 1;
+        *self.participations.entry(event.proposer()).or_default() += 1;
+        for voter in event.votes() {
+            *self.participations.entry(voter).or_default() += 1;
+        }
+    }
+
+    fn add_failure(&mut self, leader: Author) {
+        *self.failures.entry(leader).or_default() += 1;
+    }
+}
+
+/// Extends `ActiveInactiveHeuristic` by assigning `failed_weight` to the candidates who failed
+/// more than `failure_threshold_percent` of the rounds they were elected for.
+///
+/// A round is considered failed when it is skipped between two committed blocks of the same
+/// epoch, i.e. it ended with a timeout certificate. The failure is attributed to the leader of
+/// that round, which is elected again over the committed history preceding it, so that every
+/// validator attributes the same failures whatever rounds it took part in.
+///
+/// The contribution of every block is decayed by `decay_percent` per epoch elapsed since it was
+/// committed, so that the reputation recovers over epochs.
+pub struct ProposerAndFailedRoundsHeuristic {
+    active_weight: u64,
+    inactive_weight: u64,
+    failed_weight: u64,
+    failure_threshold_percent: u64,
+    decay_percent: u64,
+}
+
+impl ProposerAndFailedRoundsHeuristic {
+    pub fn new(
+        active_weight: u64,
+        inactive_weight: u64,
+        failed_weight: u64,
+        failure_threshold_percent: u64,
+        decay_percent: u64,
+    ) -> Self {
+        Self {
+            active_weight,
+            inactive_weight,
+            failed_weight,
+            failure_threshold_percent,
+            decay_percent: std::cmp::min(decay_percent, 100),
+        }
+    }
+
+    fn decay(&self, epoch_age: u64) -> u64 {
+        (0..epoch_age).fold(DECAY_SCALE, |decay, _| decay * self.decay_percent / 100)
+    }
+
+    /// The weights of the candidates of `epoch` given the reputation of the blocks committed in
+    /// each epoch.
+    fn weights(
+        &self,
+        epoch: u64,
+        candidates: &[Author],
+        reputation: &BTreeMap<u64, EpochReputation>,
+    ) -> Vec<u64> {
+        candidates
+            .iter()
+            .map(|author| {
+                let (mut failed, mut proposed, mut participated) = (0, 0, 0);
+                for (block_epoch, epoch_reputation) in reputation {
+                    let decay = self.decay(epoch.saturating_sub(*block_epoch));
+                    let count = |counts: &HashMap<Author, u64>| {
+                        decay * counts.get(author).copied().unwrap_or(0)
+                    };
+                    failed += count(&epoch_reputation.failures);
+                    proposed += count(&epoch_reputation.proposals);
+                    participated += count(&epoch_reputation.participations);
+                }
+                if failed * 100 > self.failure_threshold_percent * (failed + proposed) {
+                    self.failed_weight
+                } else if participated > 0 {
+                    self.active_weight
+                } else {
+                    self.inactive_weight
+                }
+            })
+            .collect()
+    }
+}
+
+impl ReputationHeuristic for ProposerAndFailedRoundsHeuristic {
+    fn get_weights(&self, epoch: u64, candidates: &[Author], history: &History) -> Vec<u64> {
+        let mut reputation: BTreeMap<u64, EpochReputation> = BTreeMap::new();
+        // replay the history from the oldest block, electing the leaders of the failed rounds
+        // with the reputation of the blocks committed before them
+        for (i, (block_epoch, event)) in history.blocks.iter().enumerate().rev() {
+            let proposers = if *block_epoch == epoch {
+                Some(candidates)
+            } else {
+                history.proposers.get(block_epoch).map(Vec::as_slice)
+            };
+            match (history.blocks.get(i + 1), proposers) {
+                // rounds restart with each epoch
+                (Some((older_epoch, older)), Some(proposers)) if older_epoch == block_epoch => {
+                    let weights = self.weights(*block_epoch, proposers, &reputation);
+                    if weights.iter().sum::<u64>() > 0 {
+                        for round in older.round() + 1..event.round() {
+                            let leader = proposers[choose_index(
+                                weights.clone(),
+                                self.election_seed(*block_epoch, round),
+                            )];
+                            reputation
+                                .entry(*block_epoch)
+                                .or_default()
+                                .add_failure(leader);
+                        }
+                    }
+                }
+                _ => (),
+            }
+            reputation.entry(*block_epoch).or_default().add_block(event);
+        }
+        self.weights(epoch, candidates, &reputation)
+    }
+
+    fn election_seed(&self, epoch: u64, round: Round) -> Vec<u8> {
+        let mut seed = epoch.to_le_bytes().to_vec();
+        seed.extend_from_slice(&round.to_le_bytes());
+        seed
+    }
+}
+
 /// Committed history based proposer election implementation that could help bias towards
 /// successful leaders to help improve performance.
 pub struct LeaderReputation {
+    epoch: u64,
     proposers: Vec<Author>,
     backend: Box<dyn MetadataBackend>,
     heuristic: Box<dyn ReputationHeuristic>,
+    exclude_round: u64,
     already_proposed: Mutex<(Round, HashMap<Author, HashValue>)>,
 }
 
 impl LeaderReputation {
     pub fn new(
+        epoch: u64,
         proposers: Vec<Author>,
         backend: Box<dyn MetadataBackend>,
         heuristic: Box<dyn ReputationHeuristic>,
+        exclude_round: u64,
     ) -> Self {
         Self {
+            epoch,
             proposers,
             backend,
             heuristic,
+            exclude_round,
             already_proposed: Mutex::new((0, HashMap::new())),
         }
     }
@@ -155,27 +376,13 @@ impl LeaderReputation {
 
 impl ProposerElection for LeaderReputation {
     fn get_valid_proposer(&self, round: Round) -> Author {
-        // TODO: configure the round gap
-        let target_round = if round >= 4 { round - 4 } else { 0 };
-        let sliding_window = self.backend.get_block_metadata(target_round);
-        let mut weights = self.heuristic.get_weights(&self.proposers, &sliding_window);
+        let target_round = round.saturating_sub(self.exclude_round);
+        let sliding_window = self.backend.get_block_metadata(self.epoch, target_round);
+        let weights = self
+            .heuristic
+            .get_weights(self.epoch, &self.proposers, &sliding_window);
         assert_eq!(weights.len(), self.proposers.len());
-        let mut total_weight = 0;
-        for w in &mut weights {
-            total_weight += *w;
-            *w = total_weight;
-        }
-        let mut state = round.to_le_bytes().to_vec();
-        let chosen_weight = next(&mut state) % total_weight;
-        let chosen_index = weights
-            .binary_search_by(|w| {
-                if *w <= chosen_weight {
-                    Ordering::Less
-                } else {
-                    Ordering::Greater
-                }
-            })
-            .unwrap_err();
+        let chosen_index = choose_index(weights, self.heuristic.election_seed(self.epoch, round));
         self.proposers[chosen_index]
     }
 
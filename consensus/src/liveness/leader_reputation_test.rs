@@ -3,7 +3,8 @@
 
 use crate::liveness::{
     leader_reputation::{
-        ActiveInactiveHeuristic, LeaderReputation, MetadataBackend, ReputationHeuristic,
+        ActiveInactiveHeuristic, History, LeaderReputation, MetadataBackend,
+        ProposerAndFailedRoundsHeuristic, ReputationHeuristic,
     },
     proposer_election::{next, ProposerElection},
 };
@@ -25,18 +26,43 @@ impl MockHistory {
 }
 
 impl MetadataBackend for MockHistory {
-    fn get_block_metadata(&self, _target_round: Round) -> Vec<NewBlockEvent> {
+    fn get_block_metadata(&self, target_epoch: u64, _target_round: Round) -> History {
         let start = if self.data.len() > self.window_size {
             self.data.len() - self.window_size
         } else {
             0
         };
-        self.data[start..].to_vec()
+        history(
+            self.data[start..]
+                .iter()
+                .map(|event| (target_epoch, event.clone()))
+                .collect(),
+        )
+    }
+}
+
+fn history(blocks: Vec<(u64, NewBlockEvent)>) -> History {
+    History {
+        blocks,
+        ..History::default()
     }
 }
 
 fn create_block(proposer: Author, voters: Vec<&ValidatorSigner>) -> NewBlockEvent {
-    NewBlockEvent::new(0, proposer, voters.iter().map(|v| v.author()).collect(), 0)
+    create_block_at_round(0, proposer, voters)
+}
+
+fn create_block_at_round(
+    round: Round,
+    proposer: Author,
+    voters: Vec<&ValidatorSigner>,
+) -> NewBlockEvent {
+    NewBlockEvent::new(
+        round,
+        proposer,
+        voters.iter().map(|v| v.author()).collect(),
+        0,
+    )
 }
 
 #[test]
@@ -52,18 +78,22 @@ fn test_simple_heuristic() {
     }
     let heuristic = ActiveInactiveHeuristic::new(active_weight, inactive_weight);
     // 1. Window size not enough
-    let weights = heuristic.get_weights(&proposers, &[]);
+    let weights = heuristic.get_weights(0, &proposers, &History::default());
     assert_eq!(weights.len(), proposers.len());
     for w in weights {
         assert_eq!(w, inactive_weight);
     }
     // 2. Sliding window with [proposer 0, voters 1, 2], [proposer 0, voters 3]
     let weights = heuristic.get_weights(
+        0,
         &proposers,
-        &[
-            create_block(proposers[0], vec![&signers[1], &signers[2]]),
-            create_block(proposers[0], vec![&signers[3]]),
-        ],
+        &history(vec![
+            (
+                0,
+                create_block(proposers[0], vec![&signers[1], &signers[2]]),
+            ),
+            (0, create_block(proposers[0], vec![&signers[3]])),
+        ]),
     );
     assert_eq!(weights.len(), proposers.len());
     for (i, w) in weights.iter().enumerate() {
@@ -92,9 +122,11 @@ fn test_api() {
         create_block(proposers[0], vec![&signers[3]]),
     ];
     let leader_reputation = LeaderReputation::new(
+        1,
         proposers.clone(),
         Box::new(MockHistory::new(1, history)),
         Box::new(ActiveInactiveHeuristic::new(active_weight, inactive_weight)),
+        4,
     );
     let round = 42u64;
     // first metadata is ignored because of window size 1
@@ -154,3 +186,124 @@ fn test_api() {
     // good proposal still passes
     assert!(proposer_election.is_valid_proposal(&good_proposal));
 }
+
+#[test]
+fn test_failed_rounds_heuristic() {
+    let (active_weight, inactive_weight, failed_weight) = (100, 0, 1);
+    let mut proposers = vec![];
+    let mut signers = vec![];
+    for i in 0..4 {
+        let signer = ValidatorSigner::random([i; 32]);
+        proposers.push(signer.author());
+        signers.push(signer);
+    }
+    let heuristic = ProposerAndFailedRoundsHeuristic::new(
+        active_weight,
+        inactive_weight,
+        failed_weight,
+        10,
+        50,
+    );
+    // 1. Without failed rounds it behaves like the active/inactive heuristic
+    let blocks = history(vec![
+        (1, create_block_at_round(3, proposers[0], vec![&signers[1]])),
+        (1, create_block_at_round(2, proposers[1], vec![&signers[0]])),
+        (1, create_block_at_round(1, proposers[0], vec![&signers[1]])),
+    ]);
+    assert_eq!(
+        heuristic.get_weights(1, &proposers, &blocks),
+        vec![
+            active_weight,
+            active_weight,
+            inactive_weight,
+            inactive_weight
+        ]
+    );
+    // 2. The failed rounds are attributed to the leaders elected over the history before them,
+    // in which only the third candidate is active
+    let blocks = history(vec![
+        (
+            1,
+            create_block_at_round(10, proposers[0], vec![&signers[1]]),
+        ),
+        (1, create_block_at_round(2, proposers[2], vec![])),
+    ]);
+    assert_eq!(
+        heuristic.get_weights(1, &proposers, &blocks),
+        vec![active_weight, active_weight, failed_weight, inactive_weight]
+    );
+    // 3. The rounds skipped across an epoch boundary did not fail, even when the newer epoch
+    // reached a higher round
+    let blocks = history(vec![
+        (
+            2,
+            create_block_at_round(10, proposers[0], vec![&signers[1]]),
+        ),
+        (1, create_block_at_round(2, proposers[2], vec![])),
+    ]);
+    assert_eq!(
+        heuristic.get_weights(2, &proposers, &blocks),
+        vec![active_weight, active_weight, active_weight, inactive_weight]
+    );
+}
+
+#[test]
+fn test_failed_rounds_attributed_to_elected_leaders() {
+    let mut proposers = vec![];
+    let mut signers = vec![];
+    for i in 0..8 {
+        let signer = ValidatorSigner::random([i; 32]);
+        proposers.push(signer.author());
+        signers.push(signer);
+    }
+    // any failure is penalized
+    let heuristic = ProposerAndFailedRoundsHeuristic::new(100, 10, 1, 0, 50);
+    let older = create_block_at_round(2, proposers[0], vec![&signers[1], &signers[2]]);
+    let leader_reputation = LeaderReputation::new(
+        1,
+        proposers.clone(),
+        Box::new(MockHistory::new(10, vec![older.clone()])),
+        Box::new(ProposerAndFailedRoundsHeuristic::new(100, 10, 1, 0, 50)),
+        0,
+    );
+    let failed_leaders: Vec<_> = (3..10)
+        .map(|round| leader_reputation.get_valid_proposer(round))
+        .collect();
+
+    let blocks = history(vec![
+        (1, create_block_at_round(10, proposers[3], vec![])),
+        (1, older),
+    ]);
+    let weights = heuristic.get_weights(1, &proposers, &blocks);
+    for (author, weight) in proposers.iter().zip(weights) {
+        assert_eq!(weight == 1, failed_leaders.contains(author));
+    }
+}
+
+#[test]
+fn test_failed_rounds_decay() {
+    let mut proposers = vec![];
+    let mut signers = vec![];
+    for i in 0..4 {
+        let signer = ValidatorSigner::random([i; 32]);
+        proposers.push(signer.author());
+        signers.push(signer);
+    }
+    // the rounds between 2 and 10 failed in the previous epoch, in which only the third
+    // candidate was active
+    let mut blocks = history(vec![
+        (2, create_block_at_round(2, proposers[0], vec![&signers[1]])),
+        (2, create_block_at_round(1, proposers[1], vec![&signers[0]])),
+        (1, create_block_at_round(10, proposers[3], vec![])),
+        (1, create_block_at_round(2, proposers[2], vec![])),
+    ]);
+    // the validators of the previous epoch are needed to attribute its failed rounds
+    let heuristic = ProposerAndFailedRoundsHeuristic::new(100, 0, 1, 10, 100);
+    assert_eq!(heuristic.get_weights(2, &proposers, &blocks)[2], 100);
+    blocks.proposers.insert(1, proposers.clone());
+    assert_eq!(heuristic.get_weights(2, &proposers, &blocks)[2], 1);
+    // decaying everything from the previous epochs
+    let heuristic = ProposerAndFailedRoundsHeuristic::new(100, 0, 1, 10, 0);
+    let weights = heuristic.get_weights(2, &proposers, &blocks);
+    assert_eq!(weights, vec![100, 100, 0, 0]);
+}
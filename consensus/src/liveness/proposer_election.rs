@@ -5,6 +5,7 @@ use consensus_types::{
     block::Block,
     common::{Author, Round},
 };
+use std::cmp::Ordering;
 
 /// ProposerElection incorporates the logic of choosing a leader among multiple candidates.
 /// We are open to a possibility for having multiple proposers per round, the ultimate choice
@@ -29,6 +30,26 @@ pub trait ProposerElection {
     }
 }
 
+// choose_index picks an index with a probability proportional to its weight, deterministically
+// derived from the given state
+pub(crate) fn choose_index(mut weights: Vec<u64>, mut state: Vec<u8>) -> usize {
+    let mut total_weight = 0;
+    for w in &mut weights {
+        total_weight += *w;
+        *w = total_weight;
+    }
+    let chosen_weight = next(&mut state) % total_weight;
+    weights
+        .binary_search_by(|w| {
+            if *w <= chosen_weight {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            }
+        })
+        .unwrap_err()
+}
+
 // next continuously mutates a state and returns a u64-index
 pub(crate) fn next(state: &mut Vec<u8>) -> u64 {
     // state = SHA-3-256(state)
@@ -6,11 +6,22 @@ use crate::{
     pending_votes::{PendingVotes, VoteReceptionResult},
     util::time_service::{SendTask, TimeService},
 };
-use consensus_types::{common::Round, sync_info::SyncInfo, vote::Vote};
+use consensus_types::{
+    block::Block,
+    common::{Author, Round},
+    equivocation_evidence::EquivocationEvidence,
+    sync_info::SyncInfo,
+    vote::Vote,
+};
 use libra_logger::{prelude::*, Schema};
 use libra_types::validator_verifier::ValidatorVerifier;
 use serde::Serialize;
-use std::{fmt, sync::Arc, time::Duration};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fmt,
+    sync::Arc,
+    time::Duration,
+};
 
 /// A reason for starting a new round: introduced for monitoring / debug purposes.
 #[derive(Serialize, Eq, Debug, PartialEq)]
@@ -149,6 +160,8 @@ pub struct RoundState {
     pending_votes: PendingVotes,
     // Vote sent locally for the current round.
     vote_sent: Option<Vote>,
+    // The first proposal received from every author for the current round.
+    proposals_received: HashMap<Author, Block>,
 }
 
 #[derive(Default, Schema)]
@@ -196,6 +209,7 @@ impl RoundState {
             timeout_sender,
             pending_votes: PendingVotes::new(),
             vote_sent: None,
+            proposals_received: HashMap::new(),
         }
     }
 
@@ -233,6 +247,7 @@ impl RoundState {
             self.current_round = new_round;
             self.pending_votes = PendingVotes::new();
             self.vote_sent = None;
+            self.proposals_received.clear();
             let timeout = self.setup_timeout();
             // The new round reason is QCReady in case both QC and TC are equal
             let new_round_reason = if sync_info.highest_timeout_certificate().is_none() {
@@ -276,6 +291,24 @@ impl RoundState {
         self.vote_sent.clone()
     }
 
+    /// Record a proposal of the current round. In case its author already sent a different
+    /// proposal for the round, return both proposals as evidence of the equivocation.
+    pub fn record_proposal(&mut self, proposal: &Block) -> Option<EquivocationEvidence> {
+        if proposal.round() != self.current_round {
+            return None;
+        }
+        match self.proposals_received.entry(proposal.author()?) {
+            Entry::Occupied(entry) if entry.get().id() != proposal.id() => Some(
+                EquivocationEvidence::Proposals(entry.get().clone(), proposal.clone()),
+            ),
+            Entry::Occupied(_) => None,
+            Entry::Vacant(entry) => {
+                entry.insert(proposal.clone());
+                None
+            }
+        }
+    }
+
     /// Setup the timeout task and return the duration of the current timeout
     fn setup_timeout(&mut self) -> Duration {
         let timeout_sender = self.timeout_sender.clone();
@@ -0,0 +1,461 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    counters,
+    observer::{
+        network::{ObserverMsg, ObserverNetworkEvents, ObserverNetworkSender, OrderedBlock},
+        publisher::ObserverPublisher,
+    },
+};
+use anyhow::{ensure, Result};
+use channel::libra_channel;
+use consensus_types::common::Round;
+use execution_correctness::ExecutionCorrectness;
+use futures::{select, stream::select_all, StreamExt};
+use libra_config::config::ConsensusObserverConfig;
+use libra_crypto::HashValue;
+use libra_logger::prelude::*;
+use libra_types::{
+    epoch_state::EpochState,
+    ledger_info::LedgerInfoWithSignatures,
+    on_chain_config::{OnChainConfigPayload, ValidatorSet},
+    transaction::Version,
+};
+use netcore::transport::ConnectionOrigin;
+use network::protocols::network::Event;
+use state_synchronizer::StateSyncClient;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
+};
+
+#[cfg(test)]
+#[path = "consensus_observer_test.rs"]
+mod consensus_observer_test;
+
+/// The certified blocks received by the observer and not committed yet, along with the ones
+/// already executed on top of the committed state.
+struct PendingBlocks {
+    max_blocks: usize,
+    blocks: HashMap<HashValue, OrderedBlock>,
+    executed: HashSet<HashValue>,
+}
+
+impl PendingBlocks {
+    fn new(max_blocks: usize) -> Self {
+        Self {
+            max_blocks,
+            blocks: HashMap::new(),
+            executed: HashSet::new(),
+        }
+    }
+
+    /// Returns false if the block is already known. When full, the block with the lowest round
+    /// is evicted to make room for the new one.
+    fn insert(&mut self, ordered_block: OrderedBlock) -> bool {
+        let block_id = ordered_block.block.id();
+        if self.blocks.contains_key(&block_id) {
+            return false;
+        }
+        if self.blocks.len() >= self.max_blocks {
+            if let Some(oldest) = self
+                .blocks
+                .values()
+                .min_by_key(|ordered_block| {
+                    (ordered_block.block.epoch(), ordered_block.block.round())
+                })
+                .map(|ordered_block| ordered_block.block.id())
+            {
+                self.remove(oldest);
+                counters::OBSERVER_BLOCKS_COUNT
+                    .with_label_values(&["dropped"])
+                    .inc();
+            }
+        }
+        self.blocks.insert(block_id, ordered_block);
+        true
+    }
+
+    fn remove(&mut self, block_id: HashValue) {
+        self.blocks.remove(&block_id);
+        self.executed.remove(&block_id);
+    }
+
+    /// The not yet executed block with the lowest round whose parent is either the root or
+    /// executed.
+    fn next_to_execute(&self, root: HashValue) -> Option<&OrderedBlock> {
+        self.blocks
+            .values()
+            .filter(|ordered_block| {
+                let parent_id = ordered_block.block.parent_id();
+                !self.executed.contains(&ordered_block.block.id())
+                    && (parent_id == root || self.executed.contains(&parent_id))
+            })
+            .min_by_key(|ordered_block| (ordered_block.block.epoch(), ordered_block.block.round()))
+    }
+
+    fn mark_executed(&mut self, block_id: HashValue) {
+        if self.blocks.contains_key(&block_id) {
+            self.executed.insert(block_id);
+        }
+    }
+
+    /// The version of the state the given block is executed on top of.
+    fn parent_version(&self, block_id: HashValue) -> Option<Version> {
+        self.blocks
+            .get(&block_id)
+            .map(|ordered_block| ordered_block.quorum_cert.parent_block().version())
+    }
+
+    /// The ids of the executed blocks from the child of `root` to `block_id`, None if the chain
+    /// is not fully executed.
+    fn path_from(&self, root: HashValue, block_id: HashValue) -> Option<Vec<HashValue>> {
+        let mut path = vec![];
+        let mut current = block_id;
+        while current != root {
+            if !self.executed.contains(&current) {
+                return None;
+            }
+            path.push(current);
+            current = self.blocks.get(&current)?.block.parent_id();
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// Removes the blocks up to the given epoch and round, they are either committed or can't
+    /// be committed anymore.
+    fn prune(&mut self, epoch: u64, round: Round) {
+        let to_remove: Vec<_> = self
+            .blocks
+            .values()
+            .filter(|ordered_block| {
+                (ordered_block.block.epoch(), ordered_block.block.round()) <= (epoch, round)
+            })
+            .map(|ordered_block| ordered_block.block.id())
+            .collect();
+        for block_id in to_remove {
+            self.remove(block_id);
+        }
+    }
+
+    /// Forgets about the execution of the blocks, for example after the executor is reset.
+    fn reset_execution(&mut self) {
+        self.executed.clear();
+    }
+}
+
+/// The consensus observer of a full node: it subscribes to the certified blocks and the commit
+/// decisions of its upstream validators, executes the blocks speculatively as soon as they are
+/// certified and commits them once the LedgerInfoWithSignatures arrives. Full nodes thereby
+/// trail the validators by a round instead of a state sync round trip.
+///
+/// The observer only helps catching up faster: whenever it misses a block or fails to execute
+/// it, it resets to the latest committed state and state sync keeps the node up to date. Both
+/// write to the same storage, state sync is paused while the observer commits.
+///
+/// The verified certified blocks and commit decisions are republished to the downstream full
+/// nodes subscribed to this one, e.g. the public full nodes behind a validator full node.
+pub struct ConsensusObserver {
+    execution_correctness_client: Box<dyn ExecutionCorrectness + Send + Sync>,
+    state_sync_client: Arc<StateSyncClient>,
+    reconfig_events: libra_channel::Receiver<(), OnChainConfigPayload>,
+    epoch_state: Option<EpochState>,
+    /// The last block committed by the executor, the blocks are executed on top of it.
+    root: HashValue,
+    pending_blocks: PendingBlocks,
+    /// Messages of a future epoch, processed once state sync notifies about the new epoch.
+    future_messages: VecDeque<ObserverMsg>,
+    max_future_messages: usize,
+    publisher: ObserverPublisher,
+}
+
+impl ConsensusObserver {
+    /// Creates an observer executing the blocks with the given executor and notifying state sync
+    /// about the commits.
+    pub fn new(
+        config: &ConsensusObserverConfig,
+        execution_correctness_client: Box<dyn ExecutionCorrectness + Send + Sync>,
+        state_sync_client: Arc<StateSyncClient>,
+        reconfig_events: libra_channel::Receiver<(), OnChainConfigPayload>,
+    ) -> Self {
+        Self {
+            execution_correctness_client,
+            state_sync_client,
+            reconfig_events,
+            epoch_state: None,
+            root: HashValue::zero(),
+            pending_blocks: PendingBlocks::new(config.max_pending_blocks),
+            future_messages: VecDeque::new(),
+            max_future_messages: config.max_pending_blocks,
+            publisher: ObserverPublisher::new(config.max_subscribers),
+        }
+    }
+
+    /// Subscribes to the upstream peers of the given networks and processes their messages, the
+    /// downstream peers of the same networks subscribe to this observer.
+    pub async fn start(
+        mut self,
+        network_handles: Vec<(ObserverNetworkSender, ObserverNetworkEvents)>,
+    ) {
+        let (mut network_senders, network_events): (Vec<_>, Vec<_>) =
+            network_handles.into_iter().unzip();
+        let mut network_events = select_all(
+            network_events
+                .into_iter()
+                .enumerate()
+                .map(|(idx, events)| events.map(move |event| (idx, event))),
+        )
+        .fuse();
+        self.reset();
+        loop {
+            select! {
+                payload = self.reconfig_events.select_next_some() => {
+                    self.process_reconfig(payload).await
+                }
+                (idx, event) = network_events.select_next_some() => {
+                    self.process_network_event(&mut network_senders[idx], event).await
+                }
+                complete => break,
+            }
+        }
+        debug!("Consensus observer stopped.");
+    }
+
+    async fn process_network_event(
+        &mut self,
+        network_sender: &mut ObserverNetworkSender,
+        event: Event<ObserverMsg>,
+    ) {
+        match event {
+            // Only subscribe to the upstream peers.
+            Event::NewPeer(peer_id, ConnectionOrigin::Outbound) => {
+                if let Err(e) = network_sender.send_to(peer_id, ObserverMsg::Subscribe) {
+                    warn!(
+                        remote_peer = peer_id,
+                        error = ?e,
+                        "Failed to subscribe to consensus observer"
+                    );
+                }
+            }
+            Event::Message(peer_id, ObserverMsg::Subscribe) => {
+                self.publisher.subscribe(peer_id, network_sender.clone())
+            }
+            Event::Message(_, message) => self.process_message(message).await,
+            Event::LostPeer(peer_id, _) => self.publisher.unsubscribe(peer_id),
+            _ => (),
+        }
+    }
+
+    async fn process_reconfig(&mut self, payload: OnChainConfigPayload) {
+        let validator_set: ValidatorSet = match payload.get() {
+            Ok(validator_set) => validator_set,
+            Err(e) => {
+                error!(error = ?e, "Failed to get ValidatorSet from payload");
+                return;
+            }
+        };
+        self.epoch_state = Some(EpochState {
+            epoch: payload.epoch(),
+            verifier: (&validator_set).into(),
+        });
+        info!(
+            epoch = payload.epoch(),
+            "Consensus observer started new epoch"
+        );
+        let future_messages = std::mem::take(&mut self.future_messages);
+        for message in future_messages {
+            self.process_message(message).await;
+        }
+    }
+
+    async fn process_message(&mut self, message: ObserverMsg) {
+        let epoch = match &message {
+            ObserverMsg::OrderedBlock(ordered_block) => ordered_block.block.epoch(),
+            ObserverMsg::CommitDecision(ledger_info) => ledger_info.ledger_info().epoch(),
+            ObserverMsg::Subscribe => return,
+        };
+        match &self.epoch_state {
+            Some(epoch_state) if epoch < epoch_state.epoch => return,
+            Some(epoch_state) if epoch == epoch_state.epoch => (),
+            _ => {
+                if self.future_messages.len() < self.max_future_messages {
+                    self.future_messages.push_back(message);
+                }
+                return;
+            }
+        }
+        let result = match message {
+            ObserverMsg::OrderedBlock(ordered_block) => self.process_ordered_block(*ordered_block),
+            ObserverMsg::CommitDecision(ledger_info) => {
+                self.process_commit_decision(*ledger_info).await
+            }
+            ObserverMsg::Subscribe => Ok(()),
+        };
+        if let Err(e) = result {
+            counters::ERROR_COUNT.inc();
+            error!(error = ?e, "Consensus observer failed to process message");
+        }
+    }
+
+    fn epoch_state(&self) -> Result<&EpochState> {
+        self.epoch_state
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Consensus observer has no epoch state"))
+    }
+
+    fn process_ordered_block(&mut self, ordered_block: OrderedBlock) -> Result<()> {
+        let epoch_state = self.epoch_state()?;
+        ensure!(
+            ordered_block.block.id() == ordered_block.quorum_cert.certified_block().id(),
+            "Block {} doesn't match its quorum cert {}",
+            ordered_block.block,
+            ordered_block.quorum_cert
+        );
+        ordered_block.block.verify_well_formed()?;
+        ordered_block.quorum_cert.verify(&epoch_state.verifier)?;
+        if self.pending_blocks.insert(ordered_block.clone()) {
+            self.publisher
+                .publish(ObserverMsg::OrderedBlock(Box::new(ordered_block)));
+            self.execute_pending_blocks();
+        }
+        Ok(())
+    }
+
+    /// Executes the certified blocks whose parent is already executed.
+    fn execute_pending_blocks(&mut self) {
+        while let Some(ordered_block) = self.pending_blocks.next_to_execute(self.root).cloned() {
+            let block_id = ordered_block.block.id();
+            let parent_id = ordered_block.block.parent_id();
+            match self
+                .execution_correctness_client
+                .execute_block(ordered_block.block, parent_id)
+            {
+                Ok(result)
+                    if result.root_hash()
+                        == ordered_block
+                            .quorum_cert
+                            .certified_block()
+                            .executed_state_id() =>
+                {
+                    self.pending_blocks.mark_executed(block_id);
+                    counters::OBSERVER_BLOCKS_COUNT
+                        .with_label_values(&["executed"])
+                        .inc();
+                }
+                Ok(_) => {
+                    error!(
+                        block_id = block_id,
+                        "Executed state of the block doesn't match its quorum cert"
+                    );
+                    self.pending_blocks.remove(block_id);
+                    counters::OBSERVER_BLOCKS_COUNT
+                        .with_label_values(&["dropped"])
+                        .inc();
+                }
+                Err(e) => {
+                    error!(block_id = block_id, error = ?e, "Failed to execute block");
+                    self.reset();
+                    return;
+                }
+            }
+        }
+    }
+
+    async fn process_commit_decision(
+        &mut self,
+        ledger_info: LedgerInfoWithSignatures,
+    ) -> Result<()> {
+        ledger_info.verify_signatures(&self.epoch_state()?.verifier)?;
+        let (epoch, round) = (
+            ledger_info.ledger_info().epoch(),
+            ledger_info.ledger_info().round(),
+        );
+        let block_id = ledger_info.ledger_info().consensus_block_id();
+        if block_id == self.root {
+            // Already committed, the decision is received from every upstream peer.
+            return Ok(());
+        }
+        self.publisher
+            .publish(ObserverMsg::CommitDecision(Box::new(ledger_info.clone())));
+        let (block_ids, parent_version) = match self
+            .pending_blocks
+            .path_from(self.root, block_id)
+            .and_then(|block_ids| {
+                let parent_version = self.pending_blocks.parent_version(*block_ids.first()?)?;
+                Some((block_ids, parent_version))
+            }) {
+            Some(path) => path,
+            None => {
+                // Some of the blocks were missed or state sync committed them in the meantime.
+                self.restart_from_storage(epoch, round);
+                return Ok(());
+            }
+        };
+        // State sync applies its chunks to the same storage: it is paused until the blocks are
+        // committed, and the blocks are only committed if it didn't move the storage past their
+        // parent in the meantime.
+        let (sync_state, sync_pause) = self.state_sync_client.pause().await?;
+        if sync_state.highest_version_in_local_storage() != parent_version {
+            drop(sync_pause);
+            self.restart_from_storage(epoch, round);
+            return Ok(());
+        }
+        let num_blocks = block_ids.len();
+        let committed = self
+            .execution_correctness_client
+            .commit_blocks(block_ids, ledger_info.clone());
+        drop(sync_pause);
+        let (committed_txns, reconfig_events) = match committed {
+            Ok(committed) => committed,
+            Err(e) => {
+                self.reset();
+                return Err(e.into());
+            }
+        };
+        self.state_sync_client
+            .commit(committed_txns, reconfig_events)
+            .await?;
+        counters::OBSERVER_BLOCKS_COUNT
+            .with_label_values(&["committed"])
+            .inc_by(num_blocks as i64);
+        counters::OBSERVER_LAST_COMMITTED_ROUND.set(round as i64);
+        debug!(
+            epoch = epoch,
+            round = round,
+            block_id = block_id,
+            "Consensus observer committed block"
+        );
+
+        self.pending_blocks.prune(epoch, round);
+        self.root = self.execution_correctness_client.committed_block_id()?;
+        if ledger_info.ledger_info().ends_epoch() {
+            // The blocks of the next epoch are verified once state sync notifies about it.
+            self.epoch_state = None;
+        }
+        Ok(())
+    }
+
+    /// Drops the blocks up to the given epoch and round, and executes the remaining ones on top of
+    /// the latest committed state.
+    fn restart_from_storage(&mut self, epoch: u64, round: Round) {
+        self.pending_blocks.prune(epoch, round);
+        self.reset();
+        self.execute_pending_blocks();
+    }
+
+    /// Resets the executor to the latest committed state, the pending blocks have to be executed
+    /// again.
+    fn reset(&mut self) {
+        self.pending_blocks.reset_execution();
+        if let Err(e) = self.execution_correctness_client.reset() {
+            error!(error = ?e, "Failed to reset the executor");
+            return;
+        }
+        match self.execution_correctness_client.committed_block_id() {
+            Ok(root) => self.root = root,
+            Err(e) => error!(error = ?e, "Failed to get the committed block of the executor"),
+        }
+    }
+}
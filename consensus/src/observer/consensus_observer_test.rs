@@ -0,0 +1,351 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{ConsensusObserver, PendingBlocks};
+use crate::{
+    observer::{ObserverMsg, ObserverNetworkSender, OrderedBlock},
+    test_utils::consensus_runtime,
+};
+use channel::{libra_channel, message_queues::QueueStyle};
+use consensus_types::block::{
+    block_test_utils::{certificate_for_genesis, placeholder_certificate_for_block},
+    Block,
+};
+use execution_correctness::ExecutionCorrectness;
+use executor_types::{Error, ExecutedTrees, StateComputeResult};
+use futures::{channel::mpsc, FutureExt, StreamExt};
+use libra_config::config::ConsensusObserverConfig;
+use libra_crypto::{hash::ACCUMULATOR_PLACEHOLDER_HASH, HashValue};
+use libra_infallible::Mutex;
+use libra_mempool::CommitResponse;
+use libra_types::{
+    contract_event::ContractEvent,
+    epoch_state::EpochState,
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    transaction::{Transaction, Version},
+    validator_signer::ValidatorSigner,
+    validator_verifier::ValidatorVerifier,
+    PeerId,
+};
+use network::{
+    peer_manager::{ConnectionRequestSender, PeerManagerRequest, PeerManagerRequestSender},
+    protocols::network::NewNetworkSender,
+    ProtocolId,
+};
+use state_synchronizer::{coordinator::CoordinatorMessage, StateSyncClient, SynchronizerState};
+use std::{
+    collections::BTreeMap,
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+/// A chain of `length` certified blocks on top of genesis.
+fn certified_chain(signer: &ValidatorSigner, length: u64) -> Vec<OrderedBlock> {
+    let mut chain = vec![];
+    let mut parent_qc = certificate_for_genesis();
+    for round in 1..=length {
+        let block = Block::new_proposal(vec![], round, round, parent_qc.clone(), signer);
+        let quorum_cert = placeholder_certificate_for_block(
+            vec![signer],
+            block.id(),
+            round,
+            block.parent_id(),
+            round - 1,
+        );
+        parent_qc = quorum_cert.clone();
+        chain.push(OrderedBlock { block, quorum_cert });
+    }
+    chain
+}
+
+#[test]
+fn test_execute_in_order() {
+    let signer = ValidatorSigner::random(None);
+    let chain = certified_chain(&signer, 3);
+    let root = chain[0].block.parent_id();
+    let mut pending_blocks = PendingBlocks::new(10);
+
+    // the child is only executable once its parent is executed
+    assert!(pending_blocks.insert(chain[1].clone()));
+    assert!(pending_blocks.next_to_execute(root).is_none());
+    assert!(pending_blocks.insert(chain[0].clone()));
+    assert!(!pending_blocks.insert(chain[0].clone()));
+    assert!(pending_blocks.insert(chain[2].clone()));
+
+    for ordered_block in &chain {
+        let next = pending_blocks.next_to_execute(root).unwrap().block.id();
+        assert_eq!(next, ordered_block.block.id());
+        pending_blocks.mark_executed(next);
+    }
+    assert!(pending_blocks.next_to_execute(root).is_none());
+
+    // the reset forgets about the execution
+    pending_blocks.reset_execution();
+    assert_eq!(
+        pending_blocks.next_to_execute(root).unwrap().block.id(),
+        chain[0].block.id()
+    );
+}
+
+#[test]
+fn test_path_and_prune() {
+    let signer = ValidatorSigner::random(None);
+    let chain = certified_chain(&signer, 3);
+    let root = chain[0].block.parent_id();
+    let mut pending_blocks = PendingBlocks::new(10);
+    for ordered_block in &chain {
+        pending_blocks.insert(ordered_block.clone());
+    }
+    pending_blocks.mark_executed(chain[0].block.id());
+    pending_blocks.mark_executed(chain[1].block.id());
+
+    // not executed yet
+    assert!(pending_blocks
+        .path_from(root, chain[2].block.id())
+        .is_none());
+    assert_eq!(
+        pending_blocks.path_from(root, chain[1].block.id()),
+        Some(vec![chain[0].block.id(), chain[1].block.id()])
+    );
+
+    // once committed, the next block is executed on top of the committed one
+    let committed = &chain[1].block;
+    pending_blocks.prune(committed.epoch(), committed.round());
+    assert_eq!(
+        pending_blocks
+            .next_to_execute(committed.id())
+            .unwrap()
+            .block
+            .id(),
+        chain[2].block.id()
+    );
+    pending_blocks.mark_executed(chain[2].block.id());
+    assert_eq!(
+        pending_blocks.path_from(committed.id(), chain[2].block.id()),
+        Some(vec![chain[2].block.id()])
+    );
+}
+
+#[test]
+fn test_evict_lowest_round() {
+    let signer = ValidatorSigner::random(None);
+    let chain = certified_chain(&signer, 3);
+    let root = chain[0].block.parent_id();
+    let mut pending_blocks = PendingBlocks::new(2);
+    for ordered_block in &chain {
+        pending_blocks.insert(ordered_block.clone());
+    }
+    // the first block is evicted, the remaining ones can't be executed
+    assert!(pending_blocks.next_to_execute(root).is_none());
+
+    // receiving it again evicts the second block instead
+    assert!(pending_blocks.insert(chain[0].clone()));
+    let next = pending_blocks.next_to_execute(root).unwrap().block.id();
+    assert_eq!(next, chain[0].block.id());
+    pending_blocks.mark_executed(next);
+    assert!(pending_blocks.next_to_execute(root).is_none());
+}
+
+/// Executes every block on top of the genesis state, and records the blocks committed along with
+/// whether state sync was paused.
+struct MockExecutionCorrectness {
+    committed_block_id: HashValue,
+    sync_paused: Arc<AtomicBool>,
+    commits: Commits,
+}
+
+impl ExecutionCorrectness for MockExecutionCorrectness {
+    fn committed_block_id(&mut self) -> Result<HashValue, Error> {
+        Ok(self.committed_block_id)
+    }
+
+    fn reset(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn execute_block(
+        &mut self,
+        _block: Block,
+        _parent_block_id: HashValue,
+    ) -> Result<StateComputeResult, Error> {
+        Ok(StateComputeResult::new(
+            *ACCUMULATOR_PLACEHOLDER_HASH,
+            vec![],
+            0,
+            vec![],
+            0,
+            None,
+            vec![],
+            vec![],
+        ))
+    }
+
+    fn commit_blocks(
+        &mut self,
+        block_ids: Vec<HashValue>,
+        ledger_info_with_sigs: LedgerInfoWithSignatures,
+    ) -> Result<(Vec<Transaction>, Vec<ContractEvent>), Error> {
+        self.commits
+            .lock()
+            .push((block_ids, self.sync_paused.load(Ordering::SeqCst)));
+        self.committed_block_id = ledger_info_with_sigs.ledger_info().consensus_block_id();
+        Ok((vec![], vec![]))
+    }
+}
+
+/// Runs a state sync coordinator whose local storage is at `synced_version`, it acknowledges the
+/// commit notifications and flags when it is paused.
+fn mock_state_sync(
+    runtime: &tokio::runtime::Runtime,
+    synced_version: Version,
+    sync_paused: Arc<AtomicBool>,
+) -> Arc<StateSyncClient> {
+    let (coordinator_sender, mut coordinator_receiver) = mpsc::unbounded();
+    runtime.spawn(async move {
+        while let Some(message) = coordinator_receiver.next().await {
+            match message {
+                CoordinatorMessage::Pause(callback, resume) => {
+                    let state = SynchronizerState::new(
+                        LedgerInfoWithSignatures::new(
+                            LedgerInfo::mock_genesis(None),
+                            BTreeMap::new(),
+                        ),
+                        ExecutedTrees::new(
+                            HashValue::zero(),
+                            vec![HashValue::zero()],
+                            synced_version + 1,
+                        ),
+                        EpochState::empty(),
+                    );
+                    sync_paused.store(true, Ordering::SeqCst);
+                    if callback.send(state).is_ok() {
+                        let _ = resume.await;
+                    }
+                    sync_paused.store(false, Ordering::SeqCst);
+                }
+                CoordinatorMessage::Commit(_, _, callback) => {
+                    let _ = callback.send(Ok(CommitResponse { msg: "".into() }));
+                }
+                _ => (),
+            }
+        }
+    });
+    Arc::new(StateSyncClient::new(coordinator_sender))
+}
+
+/// The commits of the executor: the ids of the committed blocks, and whether state sync was
+/// paused meanwhile.
+type Commits = Arc<Mutex<Vec<(Vec<HashValue>, bool)>>>;
+
+/// Feeds a certified chain of 3 blocks and the commit decision of the second one to an observer
+/// whose state sync is at `synced_version`, returns the chain, the commits of its executor and
+/// the messages republished to a downstream subscriber.
+fn observe_commit(synced_version: Version) -> (Vec<OrderedBlock>, Commits, Vec<ObserverMsg>) {
+    let mut runtime = consensus_runtime();
+    let signer = ValidatorSigner::random(None);
+    let chain = certified_chain(&signer, 3);
+    let sync_paused = Arc::new(AtomicBool::new(false));
+    let commits = Arc::new(Mutex::new(vec![]));
+    let execution_correctness = MockExecutionCorrectness {
+        committed_block_id: chain[0].block.parent_id(),
+        sync_paused: sync_paused.clone(),
+        commits: commits.clone(),
+    };
+    let (_reconfig_sender, reconfig_events) =
+        libra_channel::new(QueueStyle::LIFO, NonZeroUsize::new(1).unwrap(), None);
+    let mut observer = ConsensusObserver::new(
+        &ConsensusObserverConfig::default(),
+        Box::new(execution_correctness),
+        mock_state_sync(&runtime, synced_version, sync_paused),
+        reconfig_events,
+    );
+    observer.reset();
+    let (network_reqs_tx, mut network_reqs_rx) =
+        libra_channel::new(QueueStyle::FIFO, NonZeroUsize::new(8).unwrap(), None);
+    let (connection_reqs_tx, _) =
+        libra_channel::new(QueueStyle::FIFO, NonZeroUsize::new(8).unwrap(), None);
+    let downstream_peer = PeerId::random();
+    observer.publisher.subscribe(
+        downstream_peer,
+        ObserverNetworkSender::new(
+            PeerManagerRequestSender::new(network_reqs_tx),
+            ConnectionRequestSender::new(connection_reqs_tx),
+        ),
+    );
+    observer.epoch_state = Some(EpochState {
+        epoch: chain[0].block.epoch(),
+        verifier: ValidatorVerifier::new_single(signer.author(), signer.public_key()),
+    });
+
+    let ledger_info = LedgerInfo::new(
+        chain[1].quorum_cert.certified_block().clone(),
+        HashValue::zero(),
+    );
+    let mut signatures = BTreeMap::new();
+    signatures.insert(signer.author(), signer.sign(&ledger_info));
+    let commit_decision = LedgerInfoWithSignatures::new(ledger_info, signatures);
+    runtime.block_on(async {
+        for ordered_block in &chain {
+            observer
+                .process_message(ObserverMsg::OrderedBlock(Box::new(ordered_block.clone())))
+                .await;
+        }
+        observer
+            .process_message(ObserverMsg::CommitDecision(Box::new(commit_decision)))
+            .await;
+    });
+    let mut republished = vec![];
+    while let Some(Some(request)) = network_reqs_rx.next().now_or_never() {
+        match request {
+            PeerManagerRequest::SendMessage(peer_id, message) => {
+                assert_eq!(peer_id, downstream_peer);
+                assert_eq!(message.protocol_id, ProtocolId::ConsensusObserverDirectSend);
+                republished.push(lcs::from_bytes(&message.mdata).unwrap());
+            }
+            _ => panic!("Unexpected request"),
+        }
+    }
+    (chain, commits, republished)
+}
+
+#[test]
+fn test_commit_while_state_sync_paused() {
+    let (chain, commits, _) = observe_commit(0);
+    // the blocks are committed up to the commit decision, while state sync is paused
+    assert_eq!(
+        *commits.lock(),
+        vec![(vec![chain[0].block.id(), chain[1].block.id()], true)]
+    );
+}
+
+#[test]
+fn test_skip_commit_after_state_sync() {
+    // state sync moved the storage past the parent of the blocks, they are not committed again
+    let (_, commits, _) = observe_commit(1);
+    assert!(commits.lock().is_empty());
+}
+
+#[test]
+fn test_republish_downstream() {
+    let (chain, _, republished) = observe_commit(0);
+    // the certified blocks and the commit decision are republished as soon as they are verified
+    assert_eq!(republished.len(), chain.len() + 1);
+    for (message, ordered_block) in republished.iter().zip(&chain) {
+        match message {
+            ObserverMsg::OrderedBlock(block) => {
+                assert_eq!(block.block.id(), ordered_block.block.id())
+            }
+            _ => panic!("Unexpected message {:?}", message),
+        }
+    }
+    match republished.last() {
+        Some(ObserverMsg::CommitDecision(ledger_info)) => assert_eq!(
+            ledger_info.ledger_info().consensus_block_id(),
+            chain[1].block.id()
+        ),
+        message => panic!("Unexpected message {:?}", message),
+    }
+}
@@ -0,0 +1,18 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Consensus observer: validators publish their certified blocks and commit decisions to the
+//! full nodes subscribed on their full node networks, the full nodes execute the blocks
+//! speculatively and commit them as soon as the LedgerInfoWithSignatures arrives, ahead of state
+//! sync. The full nodes republish them to their own subscribers downstream.
+
+mod consensus_observer;
+mod network;
+mod publisher;
+
+pub use consensus_observer::ConsensusObserver;
+pub use network::{
+    network_endpoint_config, ObserverMsg, ObserverNetworkEvents, ObserverNetworkSender,
+    OrderedBlock,
+};
+pub use publisher::ObserverPublisher;
@@ -0,0 +1,89 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Interface between the consensus observer and the Network layer.
+
+use crate::counters;
+use channel::message_queues::QueueStyle;
+use consensus_types::{block::Block, quorum_cert::QuorumCert};
+use libra_metrics::IntCounterVec;
+use libra_types::{ledger_info::LedgerInfoWithSignatures, PeerId};
+use network::{
+    constants::NETWORK_CHANNEL_SIZE,
+    error::NetworkError,
+    peer_manager::{ConnectionRequestSender, PeerManagerRequestSender},
+    protocols::network::{NetworkEvents, NetworkSender, NewNetworkSender},
+    ProtocolId,
+};
+use serde::{Deserialize, Serialize};
+
+/// A certified block, along with the quorum certificate proving it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct OrderedBlock {
+    /// The certified block.
+    pub block: Block,
+    /// The quorum certificate of the block.
+    pub quorum_cert: QuorumCert,
+}
+
+/// Network type for the consensus observer
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum ObserverMsg {
+    /// Sent by a full node to its upstream peers to receive their certified blocks and commit
+    /// decisions.
+    Subscribe,
+    /// A block certified by the validators, sent as soon as its quorum certificate is formed.
+    OrderedBlock(Box<OrderedBlock>),
+    /// The ledger info committing a previously certified block.
+    CommitDecision(Box<LedgerInfoWithSignatures>),
+}
+
+/// The interface from Network to the consensus observer layer.
+pub type ObserverNetworkEvents = NetworkEvents<ObserverMsg>;
+
+/// The interface from the consensus observer to Networking layer.
+///
+/// This is a thin wrapper around a `NetworkSender<ObserverMsg>`, so it is easy to clone and
+/// share between the publisher tasks of the different networks.
+#[derive(Clone)]
+pub struct ObserverNetworkSender {
+    inner: NetworkSender<ObserverMsg>,
+}
+
+/// Configuration for the network endpoints to support the consensus observer.
+pub fn network_endpoint_config() -> (
+    Vec<ProtocolId>,
+    Vec<ProtocolId>,
+    QueueStyle,
+    usize,
+    Option<&'static IntCounterVec>,
+) {
+    (
+        vec![],
+        vec![ProtocolId::ConsensusObserverDirectSend],
+        // The blocks have to be executed in order, drop the newest messages on overflow.
+        QueueStyle::FIFO,
+        NETWORK_CHANNEL_SIZE,
+        Some(&counters::PENDING_OBSERVER_NETWORK_EVENTS),
+    )
+}
+
+impl NewNetworkSender for ObserverNetworkSender {
+    fn new(
+        peer_mgr_reqs_tx: PeerManagerRequestSender,
+        connection_reqs_tx: ConnectionRequestSender,
+    ) -> Self {
+        Self {
+            inner: NetworkSender::new(peer_mgr_reqs_tx, connection_reqs_tx),
+        }
+    }
+}
+
+impl ObserverNetworkSender {
+    /// Send a single message to the destination peer using the
+    /// `CONSENSUS_OBSERVER_DIRECT_SEND_PROTOCOL` ProtocolId.
+    pub fn send_to(&mut self, recipient: PeerId, message: ObserverMsg) -> Result<(), NetworkError> {
+        let protocol = ProtocolId::ConsensusObserverDirectSend;
+        self.inner.send_to(recipient, protocol, message)
+    }
+}
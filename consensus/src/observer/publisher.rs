@@ -0,0 +1,92 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    counters,
+    observer::network::{ObserverMsg, ObserverNetworkEvents, ObserverNetworkSender},
+};
+use futures::StreamExt;
+use libra_infallible::Mutex;
+use libra_logger::prelude::*;
+use libra_types::PeerId;
+use network::protocols::network::Event;
+use std::{collections::HashMap, sync::Arc};
+
+/// Publishes the certified blocks and the commit decisions of a validator, or the ones received
+/// by the consensus observer of a full node, to the full nodes subscribed on its full node
+/// networks. Subscribers are dropped when their connection is lost and have to subscribe again
+/// on reconnection.
+pub struct ObserverPublisher {
+    max_subscribers: usize,
+    subscribers: Mutex<HashMap<PeerId, ObserverNetworkSender>>,
+}
+
+impl ObserverPublisher {
+    /// Creates a publisher accepting at most `max_subscribers` subscribers.
+    pub fn new(max_subscribers: usize) -> Self {
+        Self {
+            max_subscribers,
+            subscribers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sends the message to all the subscribers, failures are only logged as the subscribers
+    /// can catch up through state sync.
+    pub fn publish(&self, message: ObserverMsg) {
+        for (peer_id, network_sender) in self.subscribers.lock().iter_mut() {
+            if let Err(e) = network_sender.send_to(*peer_id, message.clone()) {
+                warn!(
+                    remote_peer = peer_id,
+                    error = ?e,
+                    "Failed to publish to consensus observer"
+                );
+            }
+        }
+    }
+
+    /// Handles the subscriptions received on a single network.
+    pub async fn start(
+        self: Arc<Self>,
+        network_sender: ObserverNetworkSender,
+        mut network_events: ObserverNetworkEvents,
+    ) {
+        while let Some(event) = network_events.next().await {
+            match event {
+                Event::Message(peer_id, ObserverMsg::Subscribe) => {
+                    self.subscribe(peer_id, network_sender.clone())
+                }
+                Event::Message(peer_id, _) => {
+                    warn!(
+                        remote_peer = peer_id,
+                        "Unexpected consensus observer message from subscriber"
+                    );
+                }
+                Event::LostPeer(peer_id, _) => self.unsubscribe(peer_id),
+                _ => (),
+            }
+        }
+        debug!("Consensus observer publisher stopped.");
+    }
+
+    pub(crate) fn subscribe(&self, peer_id: PeerId, network_sender: ObserverNetworkSender) {
+        let mut subscribers = self.subscribers.lock();
+        if !subscribers.contains_key(&peer_id) && subscribers.len() >= self.max_subscribers {
+            warn!(
+                remote_peer = peer_id,
+                "Too many consensus observers, subscription rejected"
+            );
+            return;
+        }
+        subscribers.insert(peer_id, network_sender);
+        counters::OBSERVER_SUBSCRIBERS.set(subscribers.len() as i64);
+        info!(remote_peer = peer_id, "Consensus observer subscribed");
+    }
+
+    pub(crate) fn unsubscribe(&self, peer_id: PeerId) {
+        let mut subscribers = self.subscribers.lock();
+        if subscribers.remove(&peer_id).is_some() {
+            counters::OBSERVER_SUBSCRIBERS.set(subscribers.len() as i64);
+            info!(remote_peer = peer_id, "Consensus observer unsubscribed");
+        }
+    }
+}
@@ -8,7 +8,8 @@
 //! Votes are automatically dropped when the structure goes out of scope.
 
 use consensus_types::{
-    common::Author, quorum_cert::QuorumCert, timeout_certificate::TimeoutCertificate, vote::Vote,
+    common::Author, equivocation_evidence::EquivocationEvidence, quorum_cert::QuorumCert,
+    timeout_certificate::TimeoutCertificate, vote::Vote,
 };
 use libra_crypto::{hash::CryptoHash, HashValue};
 use libra_logger::prelude::*;
@@ -31,8 +32,9 @@ pub enum VoteReceptionResult {
     VoteAdded(u64),
     /// The very same vote message has been processed in past.
     DuplicateVote,
-    /// The very same author has already voted for another proposal in this round (equivocation).
-    EquivocateVote,
+    /// The very same author has already voted for another proposal in this round (equivocation),
+    /// carries both votes as evidence.
+    EquivocateVote(Box<EquivocationEvidence>),
     /// This block has just been certified after adding the vote.
     NewQuorumCertificate(Arc<QuorumCert>),
     /// The vote completes a new TimeoutCertificate
@@ -98,7 +100,10 @@ impl PendingVotes {
                     previous_vote = previously_seen_vote
                 );
 
-                return VoteReceptionResult::EquivocateVote;
+                return VoteReceptionResult::EquivocateVote(Box::new(EquivocationEvidence::Votes(
+                    previously_seen_vote.clone(),
+                    vote.clone(),
+                )));
             }
         }
 
@@ -232,7 +237,9 @@ impl fmt::Display for PendingVotes {
 #[cfg(test)]
 mod tests {
     use super::{PendingVotes, VoteReceptionResult};
-    use consensus_types::{vote::Vote, vote_data::VoteData};
+    use consensus_types::{
+        equivocation_evidence::EquivocationEvidence, vote::Vote, vote_data::VoteData,
+    };
     use libra_crypto::HashValue;
     use libra_types::{
         block_info::BlockInfo, ledger_info::LedgerInfo,
@@ -290,7 +297,10 @@ mod tests {
         );
         assert_eq!(
             pending_votes.insert_vote(&vote_data_2_author_0, &validator),
-            VoteReceptionResult::EquivocateVote
+            VoteReceptionResult::EquivocateVote(Box::new(EquivocationEvidence::Votes(
+                vote_data_1_author_0.clone(),
+                vote_data_2_author_0.clone()
+            )))
         );
 
         // a different author voting for a different result -> VoteAdded
@@ -4,10 +4,10 @@
 use crate::{consensusdb::ConsensusDB, epoch_manager::LivenessStorageData, error::DbError};
 use anyhow::{format_err, Context, Result};
 use consensus_types::{
-    block::Block, quorum_cert::QuorumCert, timeout_certificate::TimeoutCertificate, vote::Vote,
+    block::Block, equivocation_evidence::EquivocationEvidence, quorum_cert::QuorumCert,
+    timeout_certificate::TimeoutCertificate, vote::Vote,
 };
 use executor_types::ExecutedTrees;
-use libra_config::config::NodeConfig;
 use libra_crypto::HashValue;
 use libra_logger::prelude::*;
 use libra_trace::prelude::*;
@@ -43,6 +43,9 @@ pub trait PersistentLivenessStorage: Send + Sync {
     /// to jump to this round
     fn save_highest_timeout_cert(&self, highest_timeout_cert: TimeoutCertificate) -> Result<()>;
 
+    /// Persist the signed evidence of a validator equivocating.
+    fn save_equivocation_evidence(&self, evidence: &EquivocationEvidence) -> Result<()>;
+
     /// Retrieve a epoch change proof for SafetyRules so it can instantiate its
     /// ValidatorVerifier.
     fn retrieve_epoch_change_proof(&self, version: u64) -> Result<EpochChangeProof>;
@@ -277,8 +280,7 @@ pub struct StorageWriteProxy {
 }
 
 impl StorageWriteProxy {
-    pub fn new(config: &NodeConfig, libra_db: Arc<dyn DbReader>) -> Self {
-        let db = Arc::new(ConsensusDB::new(config.storage.dir()));
+    pub fn new(db: Arc<ConsensusDB>, libra_db: Arc<dyn DbReader>) -> Self {
         StorageWriteProxy { db, libra_db }
     }
 }
@@ -406,6 +408,10 @@ impl PersistentLivenessStorage for StorageWriteProxy {
             .save_highest_timeout_certificate(lcs::to_bytes(&highest_timeout_cert)?)?)
     }
 
+    fn save_equivocation_evidence(&self, evidence: &EquivocationEvidence) -> Result<()> {
+        Ok(self.db.save_equivocation_evidence(evidence)?)
+    }
+
     fn retrieve_epoch_change_proof(&self, version: u64) -> Result<EpochChangeProof> {
         let (_, proofs, _) = self
             .libra_db
@@ -23,6 +23,7 @@ use consensus_types::{
     block::Block,
     block_retrieval::{BlockRetrievalResponse, BlockRetrievalStatus},
     common::{Author, Round},
+    equivocation_evidence::EquivocationEvidence,
     proposal_msg::ProposalMsg,
     quorum_cert::QuorumCert,
     sync_info::SyncInfo,
@@ -489,6 +490,14 @@ impl RoundManager {
         let author = proposal
             .author()
             .expect("Proposal should be verified having an author");
+        if let Some(evidence) = self.round_state.record_proposal(&proposal) {
+            error!(
+                SecurityEvent::ConsensusEquivocatingProposal,
+                remote_peer = author,
+                evidence = evidence
+            );
+            self.record_equivocation(evidence);
+        }
         ensure!(
             self.proposer_election.is_valid_proposal(&proposal),
             "[RoundManager] Proposer {} for block {} is not a valid proposer for this round",
@@ -664,10 +673,28 @@ impl RoundManager {
                 self.new_qc_aggregated(qc, vote.author()).await
             }
             VoteReceptionResult::NewTimeoutCertificate(tc) => self.new_tc_aggregated(tc).await,
+            VoteReceptionResult::EquivocateVote(evidence) => {
+                self.record_equivocation(*evidence);
+                Ok(())
+            }
             _ => Ok(()),
         }
     }
 
+    /// Persist the evidence of a validator equivocating. Failures are only logged because the
+    /// evidence is not needed by the protocol itself.
+    fn record_equivocation(&self, evidence: EquivocationEvidence) {
+        counters::EQUIVOCATION_EVIDENCE_COUNT
+            .with_label_values(&[evidence.kind()])
+            .inc();
+        if let Err(e) = self.storage.save_equivocation_evidence(&evidence) {
+            error!(
+                error = ?e,
+                "[RoundManager] Failed to persist equivocation evidence",
+            );
+        }
+    }
+
     async fn new_qc_aggregated(
         &mut self,
         qc: Arc<QuorumCert>,
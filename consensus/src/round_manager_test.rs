@@ -29,6 +29,7 @@ use consensus_types::{
     },
     block_retrieval::{BlockRetrievalRequest, BlockRetrievalStatus},
     common::{Author, Payload},
+    equivocation_evidence::EquivocationEvidence,
     proposal_msg::ProposalMsg,
     sync_info::SyncInfo,
     timeout::Timeout,
@@ -178,6 +179,7 @@ impl NodeSetup {
             state_computer,
             10, // max pruned blocks in mem
             time_service.clone(),
+            None,
         ));
 
         let proposal_generator = ProposalGenerator::new(
@@ -339,6 +341,47 @@ fn vote_on_successful_proposal() {
     });
 }
 
+#[test]
+/// Two different proposals of the same author in a round are persisted as equivocation evidence
+fn equivocating_proposal_recorded() {
+    let mut runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.handle().clone());
+    let mut nodes = NodeSetup::create_nodes(&mut playground, runtime.handle().clone(), 1);
+    let node = &mut nodes[0];
+    let genesis_qc = certificate_for_genesis();
+    let first = Block::new_proposal(vec![], 1, 1, genesis_qc.clone(), &node.signer);
+    let second = Block::new_proposal(vec![], 1, 2, genesis_qc, &node.signer);
+    timed_block_on(&mut runtime, async {
+        // clear the message queue
+        node.next_proposal().await;
+
+        node.round_manager
+            .process_proposal(first.clone())
+            .await
+            .unwrap();
+        // the same proposal again is not an equivocation
+        node.round_manager
+            .process_proposal(first.clone())
+            .await
+            .unwrap_err();
+        assert!(node
+            .storage
+            .shared_storage
+            .equivocation_evidence
+            .lock()
+            .is_empty());
+
+        node.round_manager
+            .process_proposal(second.clone())
+            .await
+            .unwrap_err();
+        assert_eq!(
+            *node.storage.shared_storage.equivocation_evidence.lock(),
+            vec![EquivocationEvidence::Proposals(first, second)]
+        );
+    });
+}
+
 #[test]
 /// If the proposal does not pass voting rules,
 /// No votes are sent, but the block is still added to the block tree.
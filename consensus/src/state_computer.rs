@@ -1,37 +1,195 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{error::StateSyncError, state_replication::StateComputer};
+use crate::{
+    counters,
+    error::StateSyncError,
+    state_replication::{StateComputer, StateComputerCommitCallBackType},
+};
 use anyhow::Result;
 use consensus_types::block::Block;
 use execution_correctness::ExecutionCorrectness;
 use executor_types::{Error as ExecutionError, StateComputeResult};
 use fail::fail_point;
+use futures::{channel::oneshot, SinkExt, StreamExt};
 use libra_crypto::HashValue;
 use libra_infallible::Mutex;
 use libra_logger::prelude::*;
 use libra_metrics::monitor;
-use libra_types::ledger_info::LedgerInfoWithSignatures;
+use libra_types::{
+    contract_event::ContractEvent, ledger_info::LedgerInfoWithSignatures, transaction::Transaction,
+};
 use state_synchronizer::StateSyncClient;
-use std::{boxed::Box, sync::Arc};
+use std::{boxed::Box, sync::Arc, time::Instant};
+use tokio::{runtime::Handle, sync::Mutex as AsyncMutex, task};
+
+#[cfg(test)]
+#[path = "state_computer_test.rs"]
+mod state_computer_test;
+
+type ExecutionCorrectnessClient = Arc<Mutex<Box<dyn ExecutionCorrectness + Send + Sync>>>;
+
+/// Requests handled in order by the commit stage of the pipeline.
+enum CommitRequest {
+    Commit {
+        block_ids: Vec<HashValue>,
+        finality_proof: LedgerInfoWithSignatures,
+        callback: StateComputerCommitCallBackType,
+        ordered_at: Instant,
+    },
+    /// Acknowledged once every commit accepted before it is finalized.
+    Flush(oneshot::Sender<()>),
+}
 
 /// Basic communication with the Execution module;
 /// implements StateComputer traits.
+///
+/// Commits are persisted by a separate task, which only holds the execution correctness client
+/// while preparing and finishing a commit, so that the execution of the next blocks overlaps
+/// with the persistence of the previous ones. At most `max_pending_commits` commits can be
+/// waiting to be persisted, after which `commit` blocks until the pipeline drains.
 pub struct ExecutionProxy {
-    execution_correctness_client: Mutex<Box<dyn ExecutionCorrectness + Send + Sync>>,
+    execution_correctness_client: ExecutionCorrectnessClient,
     synchronizer: Arc<StateSyncClient>,
+    // A single sender is shared, each clone of a sender would have a slot of its own in the
+    // channel and defeat the back-pressure.
+    commit_sender: AsyncMutex<channel::Sender<CommitRequest>>,
 }
 
 impl ExecutionProxy {
     pub fn new(
         execution_correctness_client: Box<dyn ExecutionCorrectness + Send + Sync>,
         synchronizer: Arc<StateSyncClient>,
+        handle: &Handle,
+        max_pending_commits: usize,
     ) -> Self {
+        let execution_correctness_client = Arc::new(Mutex::new(execution_correctness_client));
+        // The sender has a slot of its own on top of the buffer of the channel.
+        let (commit_sender, commit_receiver) = channel::new(
+            max_pending_commits.saturating_sub(1),
+            &counters::PENDING_COMMITS,
+        );
+        handle.spawn(Self::commit_stage(
+            Arc::clone(&execution_correctness_client),
+            Arc::clone(&synchronizer),
+            commit_receiver,
+        ));
         Self {
-            execution_correctness_client: Mutex::new(execution_correctness_client),
+            execution_correctness_client,
             synchronizer,
+            commit_sender: AsyncMutex::new(commit_sender),
         }
     }
+
+    /// Persists the accepted commits in order and notifies state sync about them.
+    ///
+    /// Once a commit fails to persist, the executor is out of sync with storage until the next
+    /// state synchronization, so the commits accepted after it fail as well until a flush.
+    async fn commit_stage(
+        execution_correctness_client: ExecutionCorrectnessClient,
+        synchronizer: Arc<StateSyncClient>,
+        mut commit_receiver: channel::Receiver<CommitRequest>,
+    ) {
+        let mut persist_error: Option<String> = None;
+        while let Some(request) = commit_receiver.next().await {
+            let (block_ids, finality_proof, callback, ordered_at) = match request {
+                CommitRequest::Commit {
+                    block_ids,
+                    finality_proof,
+                    callback,
+                    ordered_at,
+                } => (block_ids, finality_proof, callback, ordered_at),
+                CommitRequest::Flush(ack) => {
+                    // Flushes precede the reset of the executor by `sync_to`.
+                    persist_error = None;
+                    let _ = ack.send(());
+                    continue;
+                }
+            };
+            observe_stage("commit_queue", ordered_at);
+
+            let persist_start = Instant::now();
+            let result = match &persist_error {
+                Some(error) => Err(ExecutionError::InternalError {
+                    error: format!("A previous commit failed to persist: {}", error),
+                }),
+                None => {
+                    let client = Arc::clone(&execution_correctness_client);
+                    let persist_block_ids = block_ids.clone();
+                    let persist_finality_proof = finality_proof.clone();
+                    monitor!(
+                        "commit_block",
+                        task::spawn_blocking(move || {
+                            Self::persist(&client, persist_block_ids, persist_finality_proof)
+                        })
+                        .await
+                    )
+                    .unwrap_or_else(|e| {
+                        Err(ExecutionError::InternalError {
+                            error: format!("Persisting task failed: {}", e),
+                        })
+                    })
+                }
+            };
+            observe_stage("persist", persist_start);
+
+            let (committed_txns, reconfig_events) = match result {
+                Ok(committed) => committed,
+                Err(e) => {
+                    error!(error = ?e, "Failed to persist commit");
+                    if persist_error.is_none() {
+                        persist_error = Some(e.to_string());
+                    }
+                    callback(&block_ids, finality_proof, Err(e));
+                    continue;
+                }
+            };
+
+            let notify_start = Instant::now();
+            if let Err(e) = monitor!(
+                "notify_state_sync",
+                synchronizer.commit(committed_txns, reconfig_events).await
+            ) {
+                error!(error = ?e, "Failed to notify state synchronizer");
+            }
+            observe_stage("notify_state_sync", notify_start);
+
+            callback(&block_ids, finality_proof, Ok(()));
+        }
+        debug!("Commit pipeline stopped.");
+    }
+
+    /// Commits the blocks, only holding the client while the commit is prepared and finished so
+    /// that blocks can be executed while the transactions are written to storage.
+    fn persist(
+        execution_correctness_client: &ExecutionCorrectnessClient,
+        block_ids: Vec<HashValue>,
+        finality_proof: LedgerInfoWithSignatures,
+    ) -> Result<(Vec<Transaction>, Vec<ContractEvent>), ExecutionError> {
+        let commit = execution_correctness_client
+            .lock()
+            .prepare_commit(block_ids, finality_proof)?;
+        commit.persist()?;
+        execution_correctness_client.lock().finish_commit(commit)
+    }
+
+    /// Waits until all the previously accepted commits are finalized.
+    async fn flush_commits(&self) -> Result<()> {
+        let (ack_sender, ack_receiver) = oneshot::channel();
+        self.commit_sender
+            .lock()
+            .await
+            .send(CommitRequest::Flush(ack_sender))
+            .await?;
+        ack_receiver.await?;
+        Ok(())
+    }
+}
+
+fn observe_stage(stage: &str, start: Instant) {
+    counters::PIPELINE_STAGE_DURATION_S
+        .with_label_values(&[stage])
+        .observe(start.elapsed().as_secs_f64());
 }
 
 #[async_trait::async_trait]
@@ -54,35 +212,44 @@ impl StateComputer for ExecutionProxy {
             "Executing block",
         );
 
+        let execute_start = Instant::now();
         // TODO: figure out error handling for the prologue txn
-        monitor!(
+        let result = monitor!(
             "execute_block",
             self.execution_correctness_client
                 .lock()
                 .execute_block(block.clone(), parent_block_id)
-        )
+        );
+        observe_stage("execute", execute_start);
+        result
     }
 
-    /// Send a successful commit. A future is fulfilled when the state is finalized.
+    /// Hand a successful commit over to the commit stage. The future is fulfilled once the
+    /// commit is accepted, `callback` is called when the state is finalized.
     async fn commit(
         &self,
         block_ids: Vec<HashValue>,
         finality_proof: LedgerInfoWithSignatures,
+        callback: StateComputerCommitCallBackType,
     ) -> Result<(), ExecutionError> {
-        let (committed_txns, reconfig_events) = monitor!(
-            "commit_block",
-            self.execution_correctness_client
+        let ordered_at = Instant::now();
+        monitor!(
+            "order_commit",
+            self.commit_sender
                 .lock()
-                .commit_blocks(block_ids, finality_proof)?
-        );
-        if let Err(e) = monitor!(
-            "notify_state_sync",
-            self.synchronizer
-                .commit(committed_txns, reconfig_events)
                 .await
-        ) {
-            error!(error = ?e, "Failed to notify state synchronizer");
-        }
+                .send(CommitRequest::Commit {
+                    block_ids,
+                    finality_proof,
+                    callback,
+                    ordered_at,
+                })
+                .await
+        )
+        .map_err(|e| ExecutionError::InternalError {
+            error: format!("Commit pipeline is closed: {}", e),
+        })?;
+        observe_stage("order", ordered_at);
         Ok(())
     }
 
@@ -91,6 +258,9 @@ impl StateComputer for ExecutionProxy {
         fail_point!("consensus::sync_to", |_| {
             Err(anyhow::anyhow!("Injected error in sync_to").into())
         });
+        // The commits in flight have to be persisted before state sync starts applying chunks
+        // on top of the committed state.
+        self.flush_commits().await?;
         // Here to start to do state synchronization where ChunkExecutor inside will
         // process chunks and commit to Storage. However, after block execution and
         // commitments, the the sync state of ChunkExecutor may be not up to date so
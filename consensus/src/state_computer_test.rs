@@ -0,0 +1,197 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    state_computer::ExecutionProxy,
+    state_replication::{StateComputer, StateComputerCommitCallBackType},
+    test_utils::consensus_runtime,
+};
+use anyhow::Result;
+use consensus_types::block::Block;
+use execution_correctness::ExecutionCorrectness;
+use executor_types::{Error, PreparedCommit, StateComputeResult};
+use futures::channel::mpsc as async_mpsc;
+use libra_crypto::{hash::ACCUMULATOR_PLACEHOLDER_HASH, HashValue};
+use libra_infallible::Mutex;
+use libra_types::{
+    contract_event::ContractEvent,
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    transaction::{Transaction, TransactionToCommit, Version},
+};
+use state_synchronizer::StateSyncClient;
+use std::{
+    collections::BTreeMap,
+    sync::{mpsc, Arc},
+    time::Duration,
+};
+use storage_interface::DbWriter;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A `DbWriter` whose writes wait to be released by the test.
+struct BlockingDbWriter {
+    started: Mutex<mpsc::Sender<()>>,
+    release: Mutex<mpsc::Receiver<()>>,
+}
+
+impl DbWriter for BlockingDbWriter {
+    fn save_transactions(
+        &self,
+        _txns_to_commit: &[TransactionToCommit],
+        _first_version: Version,
+        _ledger_info_with_sigs: Option<&LedgerInfoWithSignatures>,
+    ) -> Result<()> {
+        self.started.lock().send(())?;
+        self.release.lock().recv()?;
+        Ok(())
+    }
+}
+
+struct MockExecutionCorrectness {
+    db_writer: Arc<BlockingDbWriter>,
+}
+
+impl ExecutionCorrectness for MockExecutionCorrectness {
+    fn committed_block_id(&mut self) -> Result<HashValue, Error> {
+        Ok(HashValue::zero())
+    }
+
+    fn reset(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn execute_block(
+        &mut self,
+        _block: Block,
+        _parent_block_id: HashValue,
+    ) -> Result<StateComputeResult, Error> {
+        Ok(StateComputeResult::new(
+            *ACCUMULATOR_PLACEHOLDER_HASH,
+            vec![],
+            0,
+            vec![],
+            0,
+            None,
+            vec![],
+            vec![],
+        ))
+    }
+
+    fn commit_blocks(
+        &mut self,
+        block_ids: Vec<HashValue>,
+        ledger_info_with_sigs: LedgerInfoWithSignatures,
+    ) -> Result<(Vec<Transaction>, Vec<ContractEvent>), Error> {
+        let commit = self.prepare_commit(block_ids, ledger_info_with_sigs)?;
+        commit.persist()?;
+        self.finish_commit(commit)
+    }
+
+    fn prepare_commit(
+        &mut self,
+        block_ids: Vec<HashValue>,
+        ledger_info_with_sigs: LedgerInfoWithSignatures,
+    ) -> Result<PreparedCommit, Error> {
+        Ok(PreparedCommit::Pending {
+            db_writer: self.db_writer.clone(),
+            block_ids,
+            txns_to_commit: vec![],
+            first_version: 0,
+            ledger_info_with_sigs,
+        })
+    }
+
+    fn finish_commit(
+        &mut self,
+        _commit: PreparedCommit,
+    ) -> Result<(Vec<Transaction>, Vec<ContractEvent>), Error> {
+        Ok((vec![], vec![]))
+    }
+}
+
+/// Returns an `ExecutionProxy` whose commits are only persisted once released through the
+/// returned sender, with a receiver notified when a commit starts to be persisted.
+fn blocking_execution_proxy(
+    handle: &tokio::runtime::Handle,
+    max_pending_commits: usize,
+) -> (ExecutionProxy, mpsc::Receiver<()>, mpsc::Sender<()>) {
+    let (started_sender, started_receiver) = mpsc::channel();
+    let (release_sender, release_receiver) = mpsc::channel();
+    let db_writer = Arc::new(BlockingDbWriter {
+        started: Mutex::new(started_sender),
+        release: Mutex::new(release_receiver),
+    });
+    // The notifications to state sync fail, which the commit stage only logs.
+    let (coordinator_sender, _) = async_mpsc::unbounded();
+    let proxy = ExecutionProxy::new(
+        Box::new(MockExecutionCorrectness { db_writer }),
+        Arc::new(StateSyncClient::new(coordinator_sender)),
+        handle,
+        max_pending_commits,
+    );
+    (proxy, started_receiver, release_sender)
+}
+
+fn finality_proof() -> LedgerInfoWithSignatures {
+    LedgerInfoWithSignatures::new(LedgerInfo::mock_genesis(None), BTreeMap::new())
+}
+
+fn callback(sender: &mpsc::Sender<Result<(), Error>>) -> StateComputerCommitCallBackType {
+    let sender = sender.clone();
+    Box::new(move |_, _, result| {
+        let _ = sender.send(result);
+    })
+}
+
+#[test]
+fn test_execution_overlaps_persistence() {
+    let runtime = consensus_runtime();
+    let (proxy, persist_started, release_persist) = blocking_execution_proxy(runtime.handle(), 1);
+    let (callback_sender, callback_receiver) = mpsc::channel();
+
+    runtime
+        .block_on(proxy.commit(vec![], finality_proof(), callback(&callback_sender)))
+        .unwrap();
+    persist_started.recv_timeout(TIMEOUT).unwrap();
+
+    // The next block is executed while the previous commit is still being persisted.
+    let block = Block::make_genesis_block();
+    proxy.compute(&block, block.parent_id()).unwrap();
+    assert!(callback_receiver.try_recv().is_err());
+
+    release_persist.send(()).unwrap();
+    assert_eq!(callback_receiver.recv_timeout(TIMEOUT).unwrap(), Ok(()));
+}
+
+#[test]
+fn test_max_pending_commits_back_pressure() {
+    let runtime = consensus_runtime();
+    let max_pending_commits = 2;
+    let (proxy, persist_started, release_persist) =
+        blocking_execution_proxy(runtime.handle(), max_pending_commits);
+    let (callback_sender, callback_receiver) = mpsc::channel();
+
+    runtime
+        .block_on(proxy.commit(vec![], finality_proof(), callback(&callback_sender)))
+        .unwrap();
+    persist_started.recv_timeout(TIMEOUT).unwrap();
+    for _ in 0..max_pending_commits {
+        runtime
+            .block_on(proxy.commit(vec![], finality_proof(), callback(&callback_sender)))
+            .unwrap();
+    }
+
+    // The pipeline is full: the next commit is only accepted once the first one is persisted.
+    let mut blocked_commit = proxy.commit(vec![], finality_proof(), callback(&callback_sender));
+    assert!(runtime
+        .block_on(async { futures::poll!(&mut blocked_commit) })
+        .is_pending());
+
+    for _ in 0..max_pending_commits + 2 {
+        release_persist.send(()).unwrap();
+    }
+    runtime.block_on(blocked_commit).unwrap();
+    for _ in 0..max_pending_commits + 2 {
+        assert_eq!(callback_receiver.recv_timeout(TIMEOUT).unwrap(), Ok(()));
+    }
+}
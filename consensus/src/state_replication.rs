@@ -8,6 +8,12 @@ use executor_types::{Error as ExecutionError, StateComputeResult};
 use libra_crypto::HashValue;
 use libra_types::ledger_info::LedgerInfoWithSignatures;
 
+/// Invoked by the `StateComputer` once the committed blocks have been persisted, or with the
+/// error that prevented it.
+pub type StateComputerCommitCallBackType = Box<
+    dyn FnOnce(&[HashValue], LedgerInfoWithSignatures, Result<(), ExecutionError>) + Send + Sync,
+>;
+
 /// Retrieves and updates the status of transactions on demand (e.g., via talking with Mempool)
 #[async_trait::async_trait]
 pub trait TxnManager: Send + Sync {
@@ -48,14 +54,18 @@ pub trait StateComputer: Send + Sync {
         parent_block_id: HashValue,
     ) -> Result<StateComputeResult, ExecutionError>;
 
-    /// Send a successful commit. A future is fulfilled when the state is finalized.
+    /// Send a successful commit. The future is fulfilled once the commit has been accepted
+    /// for persistence, which might block if too many commits are still pending. The `callback`
+    /// is called once the state is finalized.
     async fn commit(
         &self,
         block_ids: Vec<HashValue>,
         finality_proof: LedgerInfoWithSignatures,
+        callback: StateComputerCommitCallBackType,
     ) -> Result<(), ExecutionError>;
 
     /// Best effort state synchronization to the given target LedgerInfo.
+    /// All the previously accepted commits are finalized before synchronizing.
     /// In case of success (`Result::Ok`) the LI of storage is at the given target.
     /// In case of failure (`Result::Error`) the LI of storage remains unchanged, and the validator
     /// can assume there were no modifications to the storage made.
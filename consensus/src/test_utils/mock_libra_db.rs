@@ -0,0 +1,210 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::test_utils::mock_storage::MockSharedStorage;
+use anyhow::Result;
+use libra_crypto::HashValue;
+use libra_types::{
+    account_address::AccountAddress,
+    account_state_blob::{AccountStateBlob, AccountStateWithProof},
+    block_info::BlockInfo,
+    contract_event::ContractEvent,
+    epoch_change::EpochChangeProof,
+    epoch_state::EpochState,
+    event::EventKey,
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    proof::{AccumulatorConsistencyProof, SparseMerkleProof},
+    transaction::{TransactionListWithProof, TransactionWithProof, Version},
+};
+use move_core_types::language_storage::TypeTag;
+use std::{collections::BTreeMap, sync::Arc};
+use storage_interface::{DbReader, Order, StartupInfo, TreeState};
+
+/// A DbReader only serving the NewBlockEvents of the blocks committed through the mock storage,
+/// used by the leader reputation in tests.
+pub struct MockLibraDB {
+    shared_storage: Arc<MockSharedStorage>,
+}
+
+impl MockLibraDB {
+    pub fn new(shared_storage: Arc<MockSharedStorage>) -> Self {
+        Self { shared_storage }
+    }
+
+    fn ledger_info(
+        &self,
+        epoch: u64,
+        version: Version,
+        next_epoch_state: Option<EpochState>,
+    ) -> LedgerInfoWithSignatures {
+        LedgerInfoWithSignatures::new(
+            LedgerInfo::new(
+                BlockInfo::new(
+                    epoch,
+                    0,
+                    HashValue::zero(),
+                    HashValue::zero(),
+                    version,
+                    0,
+                    next_epoch_state,
+                ),
+                HashValue::zero(),
+            ),
+            BTreeMap::new(),
+        )
+    }
+}
+
+impl DbReader for MockLibraDB {
+    /// Returns ledger infos ending each epoch after the last block committed in it, the
+    /// validators never change.
+    fn get_epoch_ending_ledger_infos(
+        &self,
+        start_epoch: u64,
+        end_epoch: u64,
+    ) -> Result<EpochChangeProof> {
+        let events = self.shared_storage.new_block_events.lock();
+        let ledger_infos = (start_epoch..end_epoch)
+            .map(|epoch| {
+                let version = events.iter().filter(|(e, _)| *e <= epoch).count() as Version;
+                self.ledger_info(
+                    epoch,
+                    version,
+                    Some(EpochState {
+                        epoch: epoch + 1,
+                        verifier: (&self.shared_storage.validator_set).into(),
+                    }),
+                )
+            })
+            .collect();
+        Ok(EpochChangeProof::new(ledger_infos, false))
+    }
+
+    fn get_transactions(
+        &self,
+        _start_version: Version,
+        _batch_size: u64,
+        _ledger_version: Version,
+        _fetch_events: bool,
+    ) -> Result<TransactionListWithProof> {
+        unimplemented!()
+    }
+
+    /// Returns the NewBlockEvents, the version of an event is its position in the history
+    /// starting from 1, the genesis being at version 0.
+    fn get_events(
+        &self,
+        event_key: &EventKey,
+        _start: u64,
+        order: Order,
+        limit: u64,
+    ) -> Result<Vec<(u64, ContractEvent)>> {
+        let mut events: Vec<_> = self
+            .shared_storage
+            .new_block_events
+            .lock()
+            .iter()
+            .enumerate()
+            .map(|(seq_num, (_, event))| {
+                let contract_event = ContractEvent::new(
+                    *event_key,
+                    seq_num as u64,
+                    TypeTag::Vector(Box::new(TypeTag::U8)),
+                    lcs::to_bytes(event)?,
+                );
+                Ok((seq_num as u64 + 1, contract_event))
+            })
+            .collect::<Result<_>>()?;
+        if let Order::Descending = order {
+            events.reverse();
+        }
+        events.truncate(limit as usize);
+        Ok(events)
+    }
+
+    fn get_block_timestamp(&self, _version: u64) -> Result<u64> {
+        unimplemented!()
+    }
+
+    fn get_latest_account_state(
+        &self,
+        _address: AccountAddress,
+    ) -> Result<Option<AccountStateBlob>> {
+        unimplemented!()
+    }
+
+    fn get_latest_ledger_info(&self) -> Result<LedgerInfoWithSignatures> {
+        let events = self.shared_storage.new_block_events.lock();
+        let epoch = events.last().map_or(0, |(epoch, _)| *epoch);
+        Ok(self.ledger_info(epoch, events.len() as Version, None))
+    }
+
+    fn get_latest_version(&self) -> Result<Version> {
+        Ok(self.shared_storage.new_block_events.lock().len() as Version)
+    }
+
+    fn get_startup_info(&self) -> Result<Option<StartupInfo>> {
+        unimplemented!()
+    }
+
+    fn get_txn_by_account(
+        &self,
+        _address: AccountAddress,
+        _seq_num: u64,
+        _ledger_version: Version,
+        _fetch_events: bool,
+    ) -> Result<Option<TransactionWithProof>> {
+        unimplemented!()
+    }
+
+    fn get_state_proof_with_ledger_info(
+        &self,
+        _known_version: u64,
+        _ledger_info: LedgerInfoWithSignatures,
+    ) -> Result<(EpochChangeProof, AccumulatorConsistencyProof)> {
+        unimplemented!()
+    }
+
+    fn get_state_proof(
+        &self,
+        _known_version: u64,
+    ) -> Result<(
+        LedgerInfoWithSignatures,
+        EpochChangeProof,
+        AccumulatorConsistencyProof,
+    )> {
+        unimplemented!()
+    }
+
+    fn get_account_state_with_proof(
+        &self,
+        _address: AccountAddress,
+        _version: Version,
+        _ledger_version: Version,
+    ) -> Result<AccountStateWithProof> {
+        unimplemented!()
+    }
+
+    fn get_account_state_with_proof_by_version(
+        &self,
+        _address: AccountAddress,
+        _version: Version,
+    ) -> Result<(Option<AccountStateBlob>, SparseMerkleProof)> {
+        unimplemented!()
+    }
+
+    fn get_latest_state_root(&self) -> Result<(Version, HashValue)> {
+        unimplemented!()
+    }
+
+    fn get_latest_tree_state(&self) -> Result<TreeState> {
+        unimplemented!()
+    }
+
+    fn get_epoch_ending_ledger_info(
+        &self,
+        _known_version: u64,
+    ) -> Result<LedgerInfoWithSignatures> {
+        unimplemented!()
+    }
+}
@@ -2,7 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    error::StateSyncError, state_replication::StateComputer, test_utils::mock_storage::MockStorage,
+    error::StateSyncError,
+    state_replication::{StateComputer, StateComputerCommitCallBackType},
+    test_utils::mock_storage::{CommittedBlock, MockStorage},
 };
 use anyhow::{format_err, Result};
 use consensus_types::{block::Block, common::Payload};
@@ -11,7 +13,10 @@ use futures::channel::mpsc;
 use libra_crypto::{hash::ACCUMULATOR_PLACEHOLDER_HASH, HashValue};
 use libra_infallible::Mutex;
 use libra_logger::prelude::*;
-use libra_types::ledger_info::LedgerInfoWithSignatures;
+use libra_types::{
+    account_address::AccountAddress, block_metadata::NewBlockEvent,
+    ledger_info::LedgerInfoWithSignatures,
+};
 use std::{collections::HashMap, sync::Arc};
 use termion::color::*;
 
@@ -19,7 +24,7 @@ pub struct MockStateComputer {
     state_sync_client: mpsc::UnboundedSender<Payload>,
     commit_callback: mpsc::UnboundedSender<LedgerInfoWithSignatures>,
     consensus_db: Arc<MockStorage>,
-    block_cache: Mutex<HashMap<HashValue, Payload>>,
+    block_cache: Mutex<HashMap<HashValue, Block>>,
 }
 
 impl MockStateComputer {
@@ -44,9 +49,7 @@ impl StateComputer for MockStateComputer {
         block: &Block,
         _parent_block_id: HashValue,
     ) -> Result<StateComputeResult, Error> {
-        self.block_cache
-            .lock()
-            .insert(block.id(), block.payload().unwrap_or(&vec![]).clone());
+        self.block_cache.lock().insert(block.id(), block.clone());
         let result = StateComputeResult::new(
             *ACCUMULATOR_PLACEHOLDER_HASH,
             vec![],
@@ -64,24 +67,55 @@ impl StateComputer for MockStateComputer {
         &self,
         block_ids: Vec<HashValue>,
         commit: LedgerInfoWithSignatures,
+        callback: StateComputerCommitCallBackType,
     ) -> Result<(), Error> {
         self.consensus_db
             .commit_to_storage(commit.ledger_info().clone());
 
         // mock sending commit notif to state sync
         let mut txns = vec![];
-        for block_id in block_ids {
-            let mut payload = self
+        for block_id in &block_ids {
+            let block = self
                 .block_cache
                 .lock()
-                .remove(&block_id)
+                .remove(block_id)
                 .ok_or_else(|| format_err!("Cannot find block"))?;
-            txns.append(&mut payload);
+            txns.extend(block.payload().cloned().unwrap_or_default());
+            self.consensus_db
+                .shared_storage
+                .committed_blocks
+                .lock()
+                .push(CommittedBlock {
+                    round: block.round(),
+                    id: block.id(),
+                    synced: false,
+                });
+            // mock the block metadata emitted by the execution of the block
+            self.consensus_db
+                .shared_storage
+                .new_block_events
+                .lock()
+                .push((
+                    block.epoch(),
+                    NewBlockEvent::new(
+                        block.round(),
+                        block.author().unwrap_or(AccountAddress::ZERO),
+                        block
+                            .quorum_cert()
+                            .ledger_info()
+                            .signatures()
+                            .keys()
+                            .cloned()
+                            .collect(),
+                        block.timestamp_usecs(),
+                    ),
+                ));
         }
         // they may fail during shutdown
         let _ = self.state_sync_client.unbounded_send(txns);
 
-        let _ = self.commit_callback.unbounded_send(commit);
+        let _ = self.commit_callback.unbounded_send(commit.clone());
+        callback(&block_ids, commit, Ok(()));
         Ok(())
     }
 
@@ -94,6 +128,16 @@ impl StateComputer for MockStateComputer {
         );
         self.consensus_db
             .commit_to_storage(commit.ledger_info().clone());
+        let commit_info = commit.ledger_info().commit_info();
+        self.consensus_db
+            .shared_storage
+            .committed_blocks
+            .lock()
+            .push(CommittedBlock {
+                round: commit_info.round(),
+                id: commit_info.id(),
+                synced: true,
+            });
         self.commit_callback
             .unbounded_send(commit)
             .expect("Fail to notify about sync");
@@ -124,9 +168,11 @@ impl StateComputer for EmptyStateComputer {
 
     async fn commit(
         &self,
-        _block_ids: Vec<HashValue>,
-        _commit: LedgerInfoWithSignatures,
+        block_ids: Vec<HashValue>,
+        commit: LedgerInfoWithSignatures,
+        callback: StateComputerCommitCallBackType,
     ) -> Result<(), Error> {
+        callback(&block_ids, commit, Ok(()));
         Ok(())
     }
 
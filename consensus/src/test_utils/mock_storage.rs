@@ -6,14 +6,17 @@ use crate::{
     persistent_liveness_storage::{
         LedgerRecoveryData, PersistentLivenessStorage, RecoveryData, RootMetadata,
     },
+    test_utils::mock_libra_db::MockLibraDB,
 };
 use anyhow::Result;
 use consensus_types::{
-    block::Block, quorum_cert::QuorumCert, timeout_certificate::TimeoutCertificate, vote::Vote,
+    block::Block, common::Round, equivocation_evidence::EquivocationEvidence,
+    quorum_cert::QuorumCert, timeout_certificate::TimeoutCertificate, vote::Vote,
 };
 use libra_crypto::HashValue;
 use libra_infallible::Mutex;
 use libra_types::{
+    block_metadata::NewBlockEvent,
     epoch_change::EpochChangeProof,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
     on_chain_config::ValidatorSet,
@@ -24,12 +27,25 @@ use std::{
 };
 use storage_interface::DbReader;
 
+/// A block committed by the node, or the target of a state synchronization when `synced`, in
+/// which case the blocks committed since the previous one are unknown.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CommittedBlock {
+    pub round: Round,
+    pub id: HashValue,
+    pub synced: bool,
+}
+
 pub struct MockSharedStorage {
     // Safety state
     pub block: Mutex<HashMap<HashValue, Block>>,
     pub qc: Mutex<HashMap<HashValue, QuorumCert>>,
     pub lis: Mutex<HashMap<u64, LedgerInfoWithSignatures>>,
+    // the epoch of each committed block and its metadata
+    pub new_block_events: Mutex<Vec<(u64, NewBlockEvent)>>,
+    pub committed_blocks: Mutex<Vec<CommittedBlock>>,
     pub last_vote: Mutex<Option<Vote>>,
+    pub equivocation_evidence: Mutex<Vec<EquivocationEvidence>>,
 
     // Liveness state
     pub highest_timeout_certificate: Mutex<Option<TimeoutCertificate>>,
@@ -42,7 +58,10 @@ impl MockSharedStorage {
             block: Mutex::new(HashMap::new()),
             qc: Mutex::new(HashMap::new()),
             lis: Mutex::new(HashMap::new()),
+            new_block_events: Mutex::new(vec![]),
+            committed_blocks: Mutex::new(vec![]),
             last_vote: Mutex::new(None),
+            equivocation_evidence: Mutex::new(vec![]),
             highest_timeout_certificate: Mutex::new(None),
             validator_set,
         }
@@ -212,6 +231,14 @@ impl PersistentLivenessStorage for MockStorage {
         Ok(())
     }
 
+    fn save_equivocation_evidence(&self, evidence: &EquivocationEvidence) -> Result<()> {
+        self.shared_storage
+            .equivocation_evidence
+            .lock()
+            .push(evidence.clone());
+        Ok(())
+    }
+
     fn retrieve_epoch_change_proof(&self, version: u64) -> Result<EpochChangeProof> {
         let lis = self
             .shared_storage
@@ -224,7 +251,7 @@ impl PersistentLivenessStorage for MockStorage {
     }
 
     fn libra_db(&self) -> Arc<dyn DbReader> {
-        unimplemented!()
+        Arc::new(MockLibraDB::new(Arc::clone(&self.shared_storage)))
     }
 }
 
@@ -283,6 +310,10 @@ impl PersistentLivenessStorage for EmptyStorage {
         Ok(())
     }
 
+    fn save_equivocation_evidence(&self, _: &EquivocationEvidence) -> Result<()> {
+        Ok(())
+    }
+
     fn retrieve_epoch_change_proof(&self, _version: u64) -> Result<EpochChangeProof> {
         unimplemented!()
     }
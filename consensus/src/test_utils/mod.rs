@@ -15,6 +15,7 @@ use libra_types::{ledger_info::LedgerInfo, validator_signer::ValidatorSigner};
 use std::{future::Future, sync::Arc, time::Duration};
 use tokio::{runtime, time::timeout};
 
+mod mock_libra_db;
 mod mock_state_computer;
 mod mock_storage;
 #[cfg(any(test, feature = "fuzzing"))]
@@ -24,7 +25,7 @@ use crate::util::mock_time_service::SimulatedTimeService;
 use consensus_types::{block::block_test_utils::gen_test_certificate, common::Payload};
 use libra_types::block_info::BlockInfo;
 pub use mock_state_computer::{EmptyStateComputer, MockStateComputer};
-pub use mock_storage::{EmptyStorage, MockSharedStorage, MockStorage};
+pub use mock_storage::{CommittedBlock, EmptyStorage, MockSharedStorage, MockStorage};
 pub use mock_txn_manager::MockTransactionManager;
 
 pub const TEST_TIMEOUT: Duration = Duration::from_secs(60);
@@ -65,6 +66,7 @@ pub fn build_empty_tree() -> Arc<BlockStore> {
         Arc::new(EmptyStateComputer),
         10, // max pruned blocks in mem
         Arc::new(SimulatedTimeService::new()),
+        None,
     ))
 }
 
@@ -9,7 +9,10 @@ use crate::{
 };
 use consensus_types::{block::Block, common::Round};
 use futures::StreamExt;
-use libra_config::config::ConsensusProposerType::{FixedProposer, RotatingProposer, RoundProposer};
+use libra_config::config::{
+    ConsensusProposerType::{FixedProposer, LeaderReputation, RotatingProposer, RoundProposer},
+    LeaderReputationConfig, ProposerAndFailedRoundsConfig,
+};
 use std::collections::HashMap;
 
 #[test]
@@ -308,3 +311,64 @@ fn twins_commit_test() {
         }
     });
 }
+
+#[test]
+/// This test checks that the leader reputation with failed rounds elects
+/// the same proposers on every node, including twins, as the history grows
+///
+/// Setup:
+///
+/// Network of 4 nodes (n0, n1, n2, n3), and 1 twin (twin0)
+///
+/// Test:
+///
+/// Use the leader reputation based on proposers and failed rounds with a
+/// short window, so that the committed history feeds back into the election.
+/// Pull out enough commits to go past the window, and check that the commits
+/// of n0 and twin0 agree on every round they both committed
+///
+/// Run the test:
+/// cargo xtest -p consensus twins_leader_reputation_test -- --nocapture
+fn twins_leader_reputation_test() {
+    let mut runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.handle().clone());
+    let num_nodes = 4;
+    let num_twins = 1;
+
+    let mut nodes = SMRNode::start_num_nodes_with_twins(
+        num_nodes,
+        num_twins,
+        &mut playground,
+        LeaderReputation(LeaderReputationConfig::ProposerAndFailedRounds(
+            ProposerAndFailedRoundsConfig {
+                window_num_validators_multiplier: 1,
+                ..ProposerAndFailedRoundsConfig::default()
+            },
+        )),
+        None,
+    );
+    runtime.spawn(playground.start());
+
+    timed_block_on(&mut runtime, async {
+        let mut node0_commits = HashMap::new();
+        let mut twin0_commits = HashMap::new();
+        for _ in 0..10 {
+            let node0_commit = nodes[0].commit_cb_receiver.next().await;
+            let twin0_commit = nodes[4].commit_cb_receiver.next().await;
+            match (node0_commit, twin0_commit) {
+                (Some(node0_commit_inner), Some(twin0_commit_inner)) => {
+                    let node0_info = node0_commit_inner.ledger_info().commit_info();
+                    let twin0_info = twin0_commit_inner.ledger_info().commit_info();
+                    node0_commits.insert(node0_info.round(), node0_info.id());
+                    twin0_commits.insert(twin0_info.round(), twin0_info.id());
+                }
+                _ => panic!("[TwinsTest] Test failed due to no commit(s)"),
+            }
+        }
+        for (round, node0_commit_id) in &node0_commits {
+            if let Some(twin0_commit_id) = twin0_commits.get(round) {
+                assert_eq!(node0_commit_id, twin0_commit_id);
+            }
+        }
+    });
+}
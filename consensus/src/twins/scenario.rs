@@ -0,0 +1,393 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Declarative twins scenarios: the leader and the network partitions of every round, and which
+//! nodes have a twin. Scenarios can be enumerated or randomly generated, executed against the
+//! `NetworkPlayground`, and the ones breaking safety are saved as regression files.
+
+use crate::{
+    network_tests::{NetworkPlayground, TwinId},
+    test_utils::CommittedBlock,
+    twins::twins_node::SMRNode,
+    util::mock_time_service::SimulatedTimeService,
+};
+use anyhow::{ensure, format_err, Result};
+use consensus_types::common::Round;
+use libra_config::config::ConsensusProposerType::RoundProposer;
+use libra_crypto::HashValue;
+use libra_logger::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::runtime::Runtime;
+
+/// Timeout of the rounds of the nodes running a scenario, rounds without a leader in a quorum
+/// partition have to time out for the scenario to make progress.
+const SCENARIO_ROUND_TIMEOUT_MS: u64 = 500;
+
+/// Maximum number of round timeouts simulated while running a scenario.
+const MAX_SCENARIO_ROUND_TIMEOUTS: usize = 32;
+
+/// How long the nodes exchange messages before the current round is timed out.
+const SCENARIO_STEP: Duration = Duration::from_millis(20);
+
+/// The configuration of a single round.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RoundScenario {
+    /// Index of the node proposing in the round, its twin proposes as well.
+    pub leader: usize,
+    /// Indices of the nodes (and twins) in each partition, the messages of the round sent across
+    /// partitions are dropped.
+    pub partitions: Vec<Vec<usize>>,
+}
+
+/// A twins scenario. Nodes are identified by their index, the twin of `twins[i]` has the index
+/// `num_nodes + i`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TwinsScenario {
+    pub num_nodes: usize,
+    /// The nodes that have a twin.
+    pub twins: Vec<usize>,
+    /// The configuration of the rounds starting from round 1, the following rounds are led by
+    /// node 0 without partitions.
+    pub rounds: Vec<RoundScenario>,
+}
+
+impl TwinsScenario {
+    pub fn num_nodes_with_twins(&self) -> usize {
+        self.num_nodes + self.twins.len()
+    }
+
+    /// Check that the scenario only refers to existing nodes, each placed in at most one
+    /// partition per round.
+    pub fn validate(&self) -> Result<()> {
+        ensure!(self.num_nodes > 0, "A scenario needs at least one node");
+        let twins: HashSet<_> = self.twins.iter().collect();
+        ensure!(twins.len() == self.twins.len(), "A node has several twins");
+        ensure!(
+            self.twins.iter().all(|idx| *idx < self.num_nodes),
+            "A twin refers to an unknown node"
+        );
+        for (i, round) in self.rounds.iter().enumerate() {
+            ensure!(
+                round.leader < self.num_nodes,
+                "Unknown leader {} in round {}",
+                round.leader,
+                i + 1
+            );
+            let mut placed = HashSet::new();
+            for idx in round.partitions.iter().flatten() {
+                ensure!(
+                    *idx < self.num_nodes_with_twins() && placed.insert(*idx),
+                    "Node {} is unknown or in several partitions in round {}",
+                    idx,
+                    i + 1
+                );
+            }
+        }
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let scenario: Self = serde_json::from_slice(&fs::read(path)?)?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    /// Save the scenario in the given directory, named after the hash of its content.
+    pub fn save(&self, dir: &Path) -> Result<PathBuf> {
+        let content = serde_json::to_vec_pretty(self)?;
+        fs::create_dir_all(dir)?;
+        let path = dir.join(format!(
+            "{}.json",
+            HashValue::sha3_256_of(&content).short_str()
+        ));
+        fs::write(&path, content)?;
+        Ok(path)
+    }
+
+    /// Run the scenario until every node committed a block past its rounds, or gave up after
+    /// `MAX_SCENARIO_ROUND_TIMEOUTS` round timeouts, and check that the nodes committed
+    /// consistent chains.
+    pub fn run(&self, runtime: &mut Runtime) -> Result<()> {
+        self.validate()?;
+        let mut playground = NetworkPlayground::new(runtime.handle().clone());
+        let round_proposers = self
+            .rounds
+            .iter()
+            .enumerate()
+            .map(|(i, round)| ((i + 1) as Round, round.leader))
+            .collect();
+        // The time of the nodes only advances when the scenario times out a round, it starts
+        // after the timestamp of the genesis block.
+        let mut time_service = SimulatedTimeService::new_at(Duration::from_secs(1));
+        let nodes = SMRNode::start_nodes_with_twins(
+            self.num_nodes,
+            &self.twins,
+            &mut playground,
+            RoundProposer(HashMap::new()),
+            Some(round_proposers),
+            SCENARIO_ROUND_TIMEOUT_MS,
+            Some(Arc::new(time_service.clone())),
+        );
+        let twin_ids: Vec<TwinId> = nodes.iter().map(|node| node.id).collect();
+        let round_partitions = self
+            .rounds
+            .iter()
+            .enumerate()
+            .map(|(i, round)| {
+                let partitions = round
+                    .partitions
+                    .iter()
+                    .map(|partition| partition.iter().map(|idx| twin_ids[*idx]).collect())
+                    .collect();
+                ((i + 1) as u64, partitions)
+            })
+            .collect();
+        ensure!(
+            playground.split_network_round(&round_partitions),
+            "Failed to create the partitions"
+        );
+        runtime.spawn(playground.start());
+
+        let committed_chains = || -> Vec<(TwinId, Vec<CommittedBlock>)> {
+            nodes
+                .iter()
+                .map(|node| {
+                    let chain = node.storage.shared_storage.committed_blocks.lock().clone();
+                    (node.id, chain)
+                })
+                .collect()
+        };
+        let last_round = self.rounds.len() as Round;
+        runtime.block_on(async {
+            for _ in 0..MAX_SCENARIO_ROUND_TIMEOUTS {
+                tokio::time::delay_for(SCENARIO_STEP).await;
+                let done = committed_chains()
+                    .iter()
+                    .all(|(_, chain)| chain.last().map_or(false, |block| block.round > last_round));
+                if done {
+                    break;
+                }
+                time_service
+                    .update_auto_advance_limit(Duration::from_millis(SCENARIO_ROUND_TIMEOUT_MS));
+            }
+        });
+        check_safety(&committed_chains())
+    }
+}
+
+/// The blocks committed by a node, by round.
+struct KnownChain {
+    blocks: HashMap<Round, HashValue>,
+    /// The rounds skipped by state synchronizations, exclusive bounds.
+    gaps: Vec<(Round, Round)>,
+    last_round: Round,
+}
+
+impl KnownChain {
+    fn new(chain: &[CommittedBlock]) -> Self {
+        let mut known = Self {
+            blocks: HashMap::new(),
+            gaps: vec![],
+            last_round: 0,
+        };
+        for block in chain {
+            if block.synced {
+                known.gaps.push((known.last_round, block.round));
+            }
+            known.blocks.insert(block.round, block.id);
+            known.last_round = std::cmp::max(known.last_round, block.round);
+        }
+        known
+    }
+
+    /// Whether the block committed in `round`, if any, is known.
+    fn covers(&self, round: Round) -> bool {
+        round <= self.last_round
+            && !self
+                .gaps
+                .iter()
+                .any(|(after, before)| *after < round && round < *before)
+    }
+}
+
+/// Check that the committed chain of every node is a prefix of the chain of every node that
+/// committed further: a block committed by a node has to be in the chain of any other node up
+/// to its last commit, unless the other node skipped it by synchronizing its state.
+pub fn check_safety(chains: &[(TwinId, Vec<CommittedBlock>)]) -> Result<()> {
+    for (twin_id, chain) in chains {
+        let known = KnownChain::new(chain);
+        for (other_twin_id, other_chain) in chains {
+            if other_twin_id == twin_id {
+                continue;
+            }
+            for block in other_chain {
+                if !known.covers(block.round) {
+                    continue;
+                }
+                ensure!(
+                    known.blocks.get(&block.round) == Some(&block.id),
+                    "Block {} committed by {:?} in round {} is not in the chain committed by {:?}",
+                    block.id,
+                    other_twin_id,
+                    block.round,
+                    twin_id,
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Generates the scenarios of a given network: every round picks a leader among the nodes and
+/// splits the nodes and twins into at most `max_partitions` partitions.
+pub struct ScenarioGenerator {
+    num_nodes: usize,
+    twins: Vec<usize>,
+    num_rounds: usize,
+    round_scenarios: Vec<RoundScenario>,
+}
+
+impl ScenarioGenerator {
+    pub fn new(
+        num_nodes: usize,
+        twins: Vec<usize>,
+        num_rounds: usize,
+        max_partitions: usize,
+    ) -> Self {
+        let num_nodes_with_twins = num_nodes + twins.len();
+        let partitions = partitions(num_nodes_with_twins, max_partitions);
+        let round_scenarios = (0..num_nodes)
+            .flat_map(|leader| {
+                partitions.iter().map(move |partitions| RoundScenario {
+                    leader,
+                    partitions: partitions.clone(),
+                })
+            })
+            .collect();
+        Self {
+            num_nodes,
+            twins,
+            num_rounds,
+            round_scenarios,
+        }
+    }
+
+    /// The possible configurations of a single round.
+    pub fn round_scenarios(&self) -> &[RoundScenario] {
+        &self.round_scenarios
+    }
+
+    fn scenario(&self, rounds: impl Iterator<Item = usize>) -> TwinsScenario {
+        TwinsScenario {
+            num_nodes: self.num_nodes,
+            twins: self.twins.clone(),
+            rounds: rounds.map(|i| self.round_scenarios[i].clone()).collect(),
+        }
+    }
+
+    /// Enumerate all the scenarios in a deterministic order.
+    pub fn enumerate(&self) -> impl Iterator<Item = TwinsScenario> + '_ {
+        // mixed radix counter over the configuration of every round
+        let mut counter = Some(vec![0; self.num_rounds]);
+        std::iter::from_fn(move || {
+            let current = counter.take()?;
+            let mut next = current.clone();
+            for digit in next.iter_mut() {
+                *digit += 1;
+                if *digit < self.round_scenarios.len() {
+                    counter = Some(next);
+                    break;
+                }
+                *digit = 0;
+            }
+            Some(self.scenario(current.into_iter()))
+        })
+    }
+
+    /// Generate `count` random scenarios, reproducible from the seed.
+    pub fn random(&self, seed: u64, count: usize) -> Vec<TwinsScenario> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..count)
+            .map(|_| {
+                let rounds: Vec<_> = (0..self.num_rounds)
+                    .map(|_| rng.gen_range(0, self.round_scenarios.len()))
+                    .collect();
+                self.scenario(rounds.into_iter())
+            })
+            .collect()
+    }
+}
+
+/// All the ways to split the nodes `0..num_nodes` into at most `max_partitions` non empty
+/// partitions.
+pub fn partitions(num_nodes: usize, max_partitions: usize) -> Vec<Vec<Vec<usize>>> {
+    let mut result: Vec<Vec<Vec<usize>>> = vec![vec![]];
+    for node in 0..num_nodes {
+        let mut extended = vec![];
+        for partitions in result {
+            for i in 0..partitions.len() {
+                let mut with_node = partitions.clone();
+                with_node[i].push(node);
+                extended.push(with_node);
+            }
+            if partitions.len() < max_partitions {
+                let mut with_node = partitions;
+                with_node.push(vec![node]);
+                extended.push(with_node);
+            }
+        }
+        result = extended;
+    }
+    result
+}
+
+/// Run the scenarios, the ones that fail are saved in `regression_dir`.
+/// Returns the scenarios that failed along with their error.
+pub fn check_scenarios(
+    scenarios: impl IntoIterator<Item = TwinsScenario>,
+    runtime: &mut Runtime,
+    regression_dir: &Path,
+) -> Vec<(TwinsScenario, anyhow::Error)> {
+    let mut failures = vec![];
+    for scenario in scenarios {
+        if let Err(e) = scenario.run(runtime) {
+            match scenario.save(regression_dir) {
+                Ok(path) => error!(
+                    "[TwinsTest] Scenario failed: {}, saved to {}",
+                    e,
+                    path.display()
+                ),
+                Err(save_error) => error!(
+                    "[TwinsTest] Scenario failed: {}, but could not be saved: {}",
+                    e, save_error
+                ),
+            }
+            failures.push((scenario, e));
+        }
+    }
+    failures
+}
+
+/// Load all the scenarios saved in the given directory.
+pub fn load_scenarios(dir: &Path) -> Result<Vec<TwinsScenario>> {
+    let mut paths = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    paths.sort();
+    paths
+        .iter()
+        .filter(|path| path.extension().map_or(false, |ext| ext == "json"))
+        .map(|path| {
+            TwinsScenario::load(path)
+                .map_err(|e| format_err!("Invalid scenario {}: {}", path.display(), e))
+        })
+        .collect()
+}
@@ -0,0 +1,202 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    network_tests::TwinId,
+    test_utils::{consensus_runtime, CommittedBlock},
+    twins::scenario::{
+        check_safety, check_scenarios, load_scenarios, partitions, RoundScenario,
+        ScenarioGenerator, TwinsScenario,
+    },
+};
+use consensus_types::common::Round;
+use libra_crypto::HashValue;
+use libra_types::account_address::AccountAddress;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+fn committed(round: Round, id: HashValue) -> CommittedBlock {
+    CommittedBlock {
+        round,
+        id,
+        synced: false,
+    }
+}
+
+fn synced(round: Round, id: HashValue) -> CommittedBlock {
+    CommittedBlock {
+        round,
+        id,
+        synced: true,
+    }
+}
+
+#[test]
+fn partitions_test() {
+    // {0, 1, 2}, {0, 1}{2}, {0, 2}{1}, {0}{1, 2}
+    assert_eq!(partitions(3, 2).len(), 4);
+    // Bell number B(4)
+    assert_eq!(partitions(4, 4).len(), 15);
+    for split in partitions(4, 3) {
+        assert!(split.len() <= 3);
+        let mut nodes: Vec<_> = split.into_iter().flatten().collect();
+        nodes.sort();
+        assert_eq!(nodes, vec![0, 1, 2, 3]);
+    }
+}
+
+#[test]
+fn scenario_generator_test() {
+    let generator = ScenarioGenerator::new(4, vec![0], 2, 2);
+    // 4 leaders times the 16 ways to split 5 nodes in at most 2 partitions
+    assert_eq!(generator.round_scenarios().len(), 64);
+    let scenarios: Vec<_> = generator.enumerate().collect();
+    assert_eq!(scenarios.len(), 64 * 64);
+    for scenario in &scenarios {
+        scenario.validate().unwrap();
+    }
+    // random scenarios are reproducible
+    assert_eq!(generator.random(42, 10), generator.random(42, 10));
+}
+
+#[test]
+fn scenario_validation_test() {
+    let mut scenario = TwinsScenario {
+        num_nodes: 4,
+        twins: vec![0],
+        rounds: vec![RoundScenario {
+            leader: 0,
+            partitions: vec![vec![0, 1, 2], vec![3, 4]],
+        }],
+    };
+    scenario.validate().unwrap();
+    let serialized = serde_json::to_string(&scenario).unwrap();
+    assert_eq!(
+        serde_json::from_str::<TwinsScenario>(&serialized).unwrap(),
+        scenario
+    );
+
+    scenario.rounds[0].partitions = vec![vec![0, 1, 2], vec![2, 3, 4]];
+    assert!(scenario.validate().is_err());
+    scenario.rounds[0].partitions = vec![vec![0, 1, 2], vec![3, 5]];
+    assert!(scenario.validate().is_err());
+    scenario.rounds[0].partitions = vec![];
+    scenario.rounds[0].leader = 4;
+    assert!(scenario.validate().is_err());
+}
+
+#[test]
+fn check_safety_test() {
+    let twin_ids: Vec<_> = (0..2)
+        .map(|id| TwinId {
+            id,
+            author: AccountAddress::random(),
+        })
+        .collect();
+    let (a, b, c, d) = (
+        HashValue::random(),
+        HashValue::random(),
+        HashValue::random(),
+        HashValue::random(),
+    );
+    let check = |chain0: Vec<CommittedBlock>, chain1: Vec<CommittedBlock>| {
+        check_safety(&[(twin_ids[0], chain0), (twin_ids[1], chain1)])
+    };
+
+    // a chain is a prefix of the other one
+    check(
+        vec![committed(1, a), committed(2, b)],
+        vec![committed(1, a), committed(2, b), committed(4, c)],
+    )
+    .unwrap();
+    // conflicting commits in the same round
+    assert!(check(vec![committed(1, a)], vec![committed(1, b)]).is_err());
+    // conflicting commits in different rounds: the block of round 2 is skipped by the other chain
+    assert!(check(
+        vec![committed(1, a), committed(2, b)],
+        vec![committed(1, a), committed(3, c)],
+    )
+    .is_err());
+    // the blocks skipped by a state synchronization are unknown
+    check(
+        vec![committed(1, a), committed(2, b), committed(3, c)],
+        vec![committed(1, a), synced(3, c), committed(5, d)],
+    )
+    .unwrap();
+    // but not the target of the synchronization
+    assert!(check(
+        vec![committed(1, a), committed(2, b), committed(3, c)],
+        vec![committed(1, a), synced(3, d)],
+    )
+    .is_err());
+}
+
+/// The directory in which the failing scenarios are saved, and replayed from.
+fn regression_dir() -> PathBuf {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("twins-regressions");
+    path
+}
+
+#[test]
+fn scenario_save_load_test() {
+    let dir = TempDir::new().unwrap();
+    let scenario = TwinsScenario {
+        num_nodes: 4,
+        twins: vec![0],
+        rounds: vec![
+            RoundScenario {
+                leader: 0,
+                partitions: vec![vec![0, 1, 2], vec![3, 4]],
+            },
+            RoundScenario {
+                leader: 0,
+                partitions: vec![vec![0, 3], vec![1, 2, 4]],
+            },
+        ],
+    };
+    let path = scenario.save(dir.path()).unwrap();
+    // the same scenario is saved only once
+    assert_eq!(scenario.save(dir.path()).unwrap(), path);
+    assert_eq!(load_scenarios(dir.path()).unwrap(), vec![scenario]);
+}
+
+#[test]
+/// This test runs random scenarios of 4 nodes and 1 twin, and checks
+/// that no conflicting blocks get committed. The failing scenarios are
+/// saved in `consensus/twins-regressions` to be replayed by `twins_regressions_test`.
+/// Set TWINS_SEED to explore other scenarios, and TWINS_COUNT to run more of them.
+///
+/// Run the test:
+/// cargo xtest -p consensus twins_random_scenarios_test -- --nocapture
+fn twins_random_scenarios_test() {
+    let mut runtime = consensus_runtime();
+    let seed = std::env::var("TWINS_SEED")
+        .ok()
+        .and_then(|seed| seed.parse().ok())
+        .unwrap_or(0);
+    let count = std::env::var("TWINS_COUNT")
+        .ok()
+        .and_then(|count| count.parse().ok())
+        .unwrap_or(20);
+    let generator = ScenarioGenerator::new(4, vec![0], 4, 2);
+    let failures = check_scenarios(
+        generator.random(seed, count),
+        &mut runtime,
+        &regression_dir(),
+    );
+    assert!(failures.is_empty(), "{:?}", failures);
+}
+
+#[test]
+/// This test replays the scenarios saved in `consensus/twins-regressions`.
+///
+/// Run the test:
+/// cargo xtest -p consensus twins_regressions_test -- --nocapture
+fn twins_regressions_test() {
+    let mut runtime = consensus_runtime();
+    let scenarios = load_scenarios(&regression_dir()).unwrap();
+    assert!(!scenarios.is_empty());
+    let failures = check_scenarios(scenarios, &mut runtime, &regression_dir());
+    assert!(failures.is_empty(), "{:?}", failures);
+}
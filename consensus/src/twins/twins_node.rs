@@ -8,7 +8,7 @@ use crate::{
     network_interface::{ConsensusNetworkEvents, ConsensusNetworkSender},
     network_tests::{NetworkPlayground, TwinId},
     test_utils::{MockStateComputer, MockStorage, MockTransactionManager},
-    util::time_service::ClockTimeService,
+    util::time_service::{ClockTimeService, TimeService},
 };
 use channel::{self, libra_channel, message_queues::QueueStyle};
 use consensus_types::common::{Author, Payload, Round};
@@ -54,6 +54,7 @@ impl SMRNode {
         config: NodeConfig,
         storage: Arc<MockStorage>,
         twin_id: TwinId,
+        time_service: Option<Arc<dyn TimeService>>,
     ) -> Self {
         let (network_reqs_tx, network_reqs_rx) =
             libra_channel::new(QueueStyle::FIFO, NonZeroUsize::new(8).unwrap(), None);
@@ -104,7 +105,8 @@ impl SMRNode {
             .build()
             .unwrap();
 
-        let time_service = Arc::new(ClockTimeService::new(runtime.handle().clone()));
+        let time_service = time_service
+            .unwrap_or_else(|| Arc::new(ClockTimeService::new(runtime.handle().clone())));
 
         let (timeout_sender, timeout_receiver) =
             channel::new(1_024, &counters::PENDING_ROUND_TIMEOUTS);
@@ -120,6 +122,7 @@ impl SMRNode {
             state_computer,
             storage.clone(),
             reconfig_events,
+            None,
         );
         let (network_task, network_receiver) = NetworkTask::new(network_events, self_receiver);
 
@@ -144,6 +147,31 @@ impl SMRNode {
         round_proposers_idx: Option<HashMap<Round, usize>>,
     ) -> Vec<Self> {
         assert!(num_nodes >= num_twins);
+        Self::start_nodes_with_twins(
+            num_nodes,
+            &(0..num_twins).collect::<Vec<_>>(),
+            playground,
+            proposer_type,
+            round_proposers_idx,
+            // Disable timeout in twins test to avoid flakiness
+            2_000_000,
+            None,
+        )
+    }
+
+    /// Starts a given number of nodes and a twin for each of the given nodes.
+    /// The twin of `twins[i]` is the node `num_nodes + i`.
+    /// The nodes share `time_service` if given, otherwise each node uses the clock.
+    pub fn start_nodes_with_twins(
+        num_nodes: usize,
+        twins: &[usize],
+        playground: &mut NetworkPlayground,
+        proposer_type: ConsensusProposerType,
+        round_proposers_idx: Option<HashMap<Round, usize>>,
+        round_initial_timeout_ms: u64,
+        time_service: Option<Arc<dyn TimeService>>,
+    ) -> Vec<Self> {
+        assert!(twins.iter().all(|idx| *idx < num_nodes));
         let ValidatorSwarm {
             nodes: mut node_configs,
         } = generator::validator_swarm_for_testing(num_nodes);
@@ -181,8 +209,8 @@ impl SMRNode {
         // We don't add twins to ValidatorSet or round_proposers above
         // because a node with twins should be treated the same at the
         // consensus level
-        for i in 0..num_twins {
-            let twin = node_configs[i].clone();
+        for idx in twins {
+            let twin = node_configs[*idx].clone();
             node_configs.push(twin);
         }
 
@@ -203,14 +231,19 @@ impl SMRNode {
             config.base.waypoint = WaypointConfig::FromConfig(waypoint);
             config.consensus.proposer_type = proposer_type.clone();
             config.consensus.safety_rules.verify_vote_proposal_signature = false;
-            // Disable timeout in twins test to avoid flakiness
-            config.consensus.round_initial_timeout_ms = 2_000_000;
+            config.consensus.round_initial_timeout_ms = round_initial_timeout_ms;
 
             let author = author_from_config(&config);
 
             let twin_id = TwinId { id: smr_id, author };
 
-            smr_nodes.push(Self::start(playground, config, storage, twin_id));
+            smr_nodes.push(Self::start(
+                playground,
+                config,
+                storage,
+                twin_id,
+                time_service.clone(),
+            ));
         }
         smr_nodes
     }
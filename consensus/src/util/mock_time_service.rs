@@ -81,6 +81,18 @@ impl SimulatedTimeService {
         }
     }
 
+    /// Creates new SimulatedTimeService in disabled state, with time stuck at `now`
+    pub fn new_at(now: Duration) -> SimulatedTimeService {
+        SimulatedTimeService {
+            inner: Arc::new(Mutex::new(SimulatedTimeServiceInner {
+                now,
+                pending: vec![],
+                time_limit: now,
+                max: Duration::from_secs(std::u64::MAX),
+            })),
+        }
+    }
+
     /// Creates new SimulatedTimeService that automatically advance time up to time_limit
     pub fn auto_advance_until(time_limit: Duration) -> SimulatedTimeService {
         SimulatedTimeService {
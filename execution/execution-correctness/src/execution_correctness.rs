@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use consensus_types::block::Block;
-use executor_types::{Error, StateComputeResult};
+use executor_types::{Error, PreparedCommit, StateComputeResult};
 use libra_crypto::HashValue;
 use libra_types::{
     contract_event::ContractEvent, ledger_info::LedgerInfoWithSignatures, transaction::Transaction,
@@ -27,4 +27,32 @@ pub trait ExecutionCorrectness: Send {
         block_ids: Vec<HashValue>,
         ledger_info_with_sigs: LedgerInfoWithSignatures,
     ) -> Result<(Vec<Transaction>, Vec<ContractEvent>), Error>;
+
+    /// Prepares the commit of the blocks, see `BlockExecutor::prepare_commit`. The commit is then
+    /// persisted without the client, so that other blocks can be executed meanwhile. By default
+    /// the blocks are committed right away, which is what the remote services do.
+    fn prepare_commit(
+        &mut self,
+        block_ids: Vec<HashValue>,
+        ledger_info_with_sigs: LedgerInfoWithSignatures,
+    ) -> Result<PreparedCommit, Error> {
+        let (committed_txns, reconfig_events) =
+            self.commit_blocks(block_ids, ledger_info_with_sigs)?;
+        Ok(PreparedCommit::Committed(committed_txns, reconfig_events))
+    }
+
+    /// Finishes a commit returned by `prepare_commit` once it has been persisted.
+    fn finish_commit(
+        &mut self,
+        commit: PreparedCommit,
+    ) -> Result<(Vec<Transaction>, Vec<ContractEvent>), Error> {
+        match commit {
+            PreparedCommit::Committed(committed_txns, reconfig_events) => {
+                Ok((committed_txns, reconfig_events))
+            }
+            PreparedCommit::Pending { .. } => Err(Error::InternalError {
+                error: "Pending commit not prepared by this client".into(),
+            }),
+        }
+    }
 }
@@ -3,7 +3,7 @@
 
 use crate::{execution_correctness::ExecutionCorrectness, id_and_transactions_from_block};
 use consensus_types::{block::Block, vote_proposal::VoteProposal};
-use executor_types::{BlockExecutor, Error, StateComputeResult};
+use executor_types::{BlockExecutor, Error, PreparedCommit, StateComputeResult};
 use libra_crypto::{ed25519::Ed25519PrivateKey, traits::SigningKey, HashValue};
 use libra_infallible::Mutex;
 use libra_types::{
@@ -78,4 +78,22 @@ impl ExecutionCorrectness for LocalClient {
             .block_executor
             .commit_blocks(block_ids, ledger_info_with_sigs)
     }
+
+    fn prepare_commit(
+        &mut self,
+        block_ids: Vec<HashValue>,
+        ledger_info_with_sigs: LedgerInfoWithSignatures,
+    ) -> Result<PreparedCommit, Error> {
+        self.internal
+            .lock()
+            .block_executor
+            .prepare_commit(block_ids, ledger_info_with_sigs)
+    }
+
+    fn finish_commit(
+        &mut self,
+        commit: PreparedCommit,
+    ) -> Result<(Vec<Transaction>, Vec<ContractEvent>), Error> {
+        self.internal.lock().block_executor.finish_commit(commit)
+    }
 }
@@ -26,7 +26,7 @@ use libra_types::{
         authenticator::AuthenticationKey, RawTransaction, Script, SignedTransaction, Transaction,
     },
 };
-use libra_vm::LibraVM;
+use libra_vm::{LibraVM, ParallelLibraVM, VMExecutor};
 use libradb::LibraDB;
 use rand::{rngs::StdRng, SeedableRng};
 use std::{
@@ -57,6 +57,14 @@ impl AccountData {
     }
 }
 
+/// The transactions creating and funding the test accounts do not pay for gas.
+const SETUP_MAX_GAS_AMOUNT: u64 = 1_000_000;
+
+/// The transfers pay for gas, so that their fees are accumulated in the `TransactionFee` resource
+/// as in production.
+const TRANSFER_MAX_GAS_AMOUNT: u64 = 100_000;
+const TRANSFER_GAS_UNIT_PRICE: u64 = 1;
+
 struct TransactionGenerator {
     /// The current state of the accounts. The main purpose is to keep track of the sequence number
     /// so generated transactions are guaranteed to be successfully executed.
@@ -129,6 +137,8 @@ impl TransactionGenerator {
                         vec![],
                         false, /* add all currencies */
                     ),
+                    SETUP_MAX_GAS_AMOUNT,
+                    0, /* gas_unit_price */
                 );
                 transactions.push(txn);
             }
@@ -160,6 +170,8 @@ impl TransactionGenerator {
                         vec![],
                         vec![],
                     ),
+                    SETUP_MAX_GAS_AMOUNT,
+                    0, /* gas_unit_price */
                 );
                 transactions.push(txn);
             }
@@ -195,6 +207,8 @@ impl TransactionGenerator {
                         vec![],
                         vec![],
                     ),
+                    TRANSFER_MAX_GAS_AMOUNT,
+                    TRANSFER_GAS_UNIT_PRICE,
                 );
                 transactions.push(txn);
 
@@ -228,15 +242,15 @@ impl TransactionGenerator {
     }
 }
 
-struct TransactionExecutor {
-    executor: Executor<LibraVM>,
+struct TransactionExecutor<V> {
+    executor: Executor<V>,
     parent_block_id: HashValue,
     block_receiver: mpsc::Receiver<Vec<Transaction>>,
 }
 
-impl TransactionExecutor {
+impl<V: VMExecutor> TransactionExecutor<V> {
     fn new(
-        executor: Executor<LibraVM>,
+        executor: Executor<V>,
         parent_block_id: HashValue,
         block_receiver: mpsc::Receiver<Vec<Transaction>>,
     ) -> Self {
@@ -301,9 +315,9 @@ impl TransactionExecutor {
     }
 }
 
-fn create_storage_service_and_executor(
+fn create_storage_service_and_executor<V: VMExecutor>(
     config: &NodeConfig,
-) -> (Arc<dyn DbReader>, Executor<LibraVM>) {
+) -> (Arc<dyn DbReader>, Executor<V>) {
     let (db, db_rw) = DbReaderWriter::wrap(
         LibraDB::open(
             &config.storage.dir(),
@@ -323,20 +337,48 @@ fn create_storage_service_and_executor(
     (db, executor)
 }
 
-/// Runs the benchmark with given parameters.
+/// Runs the benchmark with given parameters. If `parallel` is set, the transactions of a block are
+/// executed in parallel by `ParallelLibraVM`, otherwise one after the other by `LibraVM`.
 pub fn run_benchmark(
     num_accounts: usize,
     init_account_balance: u64,
     block_size: usize,
     num_transfer_blocks: usize,
     db_dir: Option<PathBuf>,
+    parallel: bool,
+) {
+    if parallel {
+        run_benchmark_with_vm::<ParallelLibraVM>(
+            num_accounts,
+            init_account_balance,
+            block_size,
+            num_transfer_blocks,
+            db_dir,
+        )
+    } else {
+        run_benchmark_with_vm::<LibraVM>(
+            num_accounts,
+            init_account_balance,
+            block_size,
+            num_transfer_blocks,
+            db_dir,
+        )
+    }
+}
+
+fn run_benchmark_with_vm<V: VMExecutor + 'static>(
+    num_accounts: usize,
+    init_account_balance: u64,
+    block_size: usize,
+    num_transfer_blocks: usize,
+    db_dir: Option<PathBuf>,
 ) {
     let (mut config, genesis_key) = libra_genesis_tool::test_config();
     if let Some(path) = db_dir {
         config.storage.dir = path;
     }
 
-    let (db, executor) = create_storage_service_and_executor(&config);
+    let (db, executor) = create_storage_service_and_executor::<V>(&config);
     let parent_block_id = executor.committed_block_id();
 
     let (block_sender, block_receiver) = mpsc::sync_channel(50 /* bound */);
@@ -375,6 +417,8 @@ fn create_transaction(
     private_key: &Ed25519PrivateKey,
     public_key: Ed25519PublicKey,
     program: Script,
+    max_gas_amount: u64,
+    gas_unit_price: u64,
 ) -> Transaction {
     let now = libra_infallible::duration_since_epoch();
     let expiration_time = now.as_secs() + 3600;
@@ -383,8 +427,8 @@ fn create_transaction(
         sender,
         sequence_number,
         program,
-        1_000_000,             /* max_gas_amount */
-        0,                     /* gas_unit_price */
+        max_gas_amount,
+        gas_unit_price,
         COIN1_NAME.to_owned(), /* gas_currency_code */
         expiration_time,
         ChainId::test(),
@@ -400,11 +444,24 @@ mod tests {
     #[test]
     fn test_benchmark() {
         super::run_benchmark(
-            25,   /* num_accounts */
-            10,   /* init_account_balance */
-            5,    /* block_size */
-            5,    /* num_transfer_blocks */
-            None, /* db_dir */
+            25,        /* num_accounts */
+            1_000_000, /* init_account_balance */
+            5,         /* block_size */
+            5,         /* num_transfer_blocks */
+            None,      /* db_dir */
+            false,     /* parallel */
+        );
+    }
+
+    #[test]
+    fn test_parallel_benchmark() {
+        super::run_benchmark(
+            25,        /* num_accounts */
+            1_000_000, /* init_account_balance */
+            5,         /* block_size */
+            5,         /* num_transfer_blocks */
+            None,      /* db_dir */
+            true,      /* parallel */
         );
     }
 }
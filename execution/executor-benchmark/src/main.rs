@@ -20,6 +20,10 @@ struct Opt {
 
     #[structopt(long, parse(from_os_str))]
     db_dir: Option<PathBuf>,
+
+    /// Execute the transactions of a block in parallel.
+    #[structopt(long)]
+    parallel: bool,
 }
 
 fn main() {
@@ -38,5 +42,6 @@ fn main() {
         opt.block_size,
         opt.num_transfer_blocks,
         opt.db_dir,
+        opt.parallel,
     );
 }
@@ -18,13 +18,14 @@ use libra_types::{
     ledger_info::LedgerInfoWithSignatures,
     proof::{accumulator::InMemoryAccumulator, AccumulatorExtensionProof, SparseMerkleProof},
     transaction::{
-        Transaction, TransactionInfo, TransactionListWithProof, TransactionStatus, Version,
+        Transaction, TransactionInfo, TransactionListWithProof, TransactionStatus,
+        TransactionToCommit, Version,
     },
 };
 use scratchpad::{ProofRead, SparseMerkleTree};
 use serde::{Deserialize, Serialize};
 use std::{cmp::max, collections::HashMap, sync::Arc};
-use storage_interface::TreeState;
+use storage_interface::{DbWriter, StateSnapshotReceiver, TreeState};
 
 pub trait ChunkExecutor: Send {
     /// Verifies the transactions based on the provided proofs and ledger info. If the transactions
@@ -39,6 +40,25 @@ pub trait ChunkExecutor: Send {
         // carrying any epoch change LI.
         epoch_change_li: Option<LedgerInfoWithSignatures>,
     ) -> Result<Vec<ContractEvent>>;
+
+    /// Gets a receiver for restoring the account state tree at `version` from a state snapshot.
+    fn get_state_snapshot_receiver(
+        &self,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<Box<dyn StateSnapshotReceiver>>;
+
+    /// Persists everything needed on top of a fully restored state snapshot at `version` to
+    /// continue executing chunks from `version + 1`. All the inputs are expected to have been
+    /// verified against the last of the `ledger_infos`, which ends an epoch at `version`.
+    fn finalize_state_snapshot(
+        &mut self,
+        version: Version,
+        transaction: Transaction,
+        transaction_info: TransactionInfo,
+        frozen_subtrees: Vec<HashValue>,
+        ledger_infos: Vec<LedgerInfoWithSignatures>,
+    ) -> Result<()>;
 }
 
 pub trait BlockExecutor: Send {
@@ -72,9 +92,65 @@ pub trait BlockExecutor: Send {
         &mut self,
         block_ids: Vec<HashValue>,
         ledger_info_with_sigs: LedgerInfoWithSignatures,
+    ) -> Result<(Vec<Transaction>, Vec<ContractEvent>), Error> {
+        let commit = self.prepare_commit(block_ids, ledger_info_with_sigs)?;
+        commit.persist()?;
+        self.finish_commit(commit)
+    }
+
+    /// First step of `commit_blocks`: gathers the transactions of the blocks to save to
+    /// persistent storage. The returned commit is persisted without holding the executor, so
+    /// that other blocks can be executed meanwhile, then handed back to `finish_commit`.
+    fn prepare_commit(
+        &mut self,
+        block_ids: Vec<HashValue>,
+        ledger_info_with_sigs: LedgerInfoWithSignatures,
+    ) -> Result<PreparedCommit, Error>;
+
+    /// Last step of `commit_blocks`, once `commit` has been persisted: prunes the committed
+    /// blocks and returns the same as `commit_blocks`.
+    fn finish_commit(
+        &mut self,
+        commit: PreparedCommit,
     ) -> Result<(Vec<Transaction>, Vec<ContractEvent>), Error>;
 }
 
+/// A commit of blocks prepared by `BlockExecutor::prepare_commit`.
+pub enum PreparedCommit {
+    /// The transactions to save to persistent storage, from `first_version` on.
+    Pending {
+        db_writer: Arc<dyn DbWriter>,
+        block_ids: Vec<HashValue>,
+        txns_to_commit: Vec<TransactionToCommit>,
+        first_version: Version,
+        ledger_info_with_sigs: LedgerInfoWithSignatures,
+    },
+    /// Nothing left to persist, with the committed transactions and reconfiguration events.
+    Committed(Vec<Transaction>, Vec<ContractEvent>),
+}
+
+impl PreparedCommit {
+    /// Saves the transactions of the commit to persistent storage. Only one commit may be
+    /// persisted at a time, in the order they were prepared.
+    pub fn persist(&self) -> Result<(), Error> {
+        if let PreparedCommit::Pending {
+            db_writer,
+            txns_to_commit,
+            first_version,
+            ledger_info_with_sigs,
+            ..
+        } = self
+        {
+            db_writer.save_transactions(
+                txns_to_commit,
+                *first_version,
+                Some(ledger_info_with_sigs),
+            )?;
+        }
+        Ok(())
+    }
+}
+
 pub trait TransactionReplayer: Send {
     fn replay_chunk(
         &mut self,
@@ -29,8 +29,8 @@ use crate::{
 };
 use anyhow::{bail, ensure, format_err, Result};
 use executor_types::{
-    BlockExecutor, ChunkExecutor, Error, ExecutedTrees, ProofReader, StateComputeResult,
-    TransactionReplayer,
+    BlockExecutor, ChunkExecutor, Error, ExecutedTrees, PreparedCommit, ProofReader,
+    StateComputeResult, TransactionReplayer,
 };
 use fail::fail_point;
 use libra_crypto::{
@@ -63,7 +63,9 @@ use std::{
     marker::PhantomData,
     sync::Arc,
 };
-use storage_interface::{state_view::VerifiedStateView, DbReaderWriter, TreeState};
+use storage_interface::{
+    state_view::VerifiedStateView, DbReaderWriter, StateSnapshotReceiver, TreeState,
+};
 
 /// `Executor` implements all functionalities the execution module needs to provide.
 pub struct Executor<V> {
@@ -625,6 +627,41 @@ impl<V: VMExecutor> ChunkExecutor for Executor<V> {
 
         Ok(reconfig_events)
     }
+
+    fn get_state_snapshot_receiver(
+        &self,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<Box<dyn StateSnapshotReceiver>> {
+        self.db
+            .writer
+            .get_state_snapshot_receiver(version, expected_root_hash)
+    }
+
+    fn finalize_state_snapshot(
+        &mut self,
+        version: Version,
+        transaction: Transaction,
+        transaction_info: TransactionInfo,
+        frozen_subtrees: Vec<HashValue>,
+        ledger_infos: Vec<LedgerInfoWithSignatures>,
+    ) -> Result<()> {
+        self.db.writer.finalize_state_snapshot(
+            version,
+            transaction,
+            transaction_info,
+            &frozen_subtrees,
+            &ledger_infos,
+        )?;
+        // The next chunk is executed on top of the snapshot.
+        self.reset_cache()?;
+
+        info!(
+            LogSchema::new(LogEntry::ChunkExecutor).synced_to_version(version),
+            "state_snapshot_finalized",
+        );
+        Ok(())
+    }
 }
 
 impl<V: VMExecutor> TransactionReplayer for Executor<V> {
@@ -785,6 +822,19 @@ impl<V: VMExecutor> BlockExecutor for Executor<V> {
         ledger_info_with_sigs: LedgerInfoWithSignatures,
     ) -> Result<(Vec<Transaction>, Vec<ContractEvent>), Error> {
         let _timer = LIBRA_EXECUTOR_COMMIT_BLOCKS_SECONDS.start_timer();
+        let commit = self.prepare_commit(block_ids, ledger_info_with_sigs)?;
+        {
+            let _timer = LIBRA_EXECUTOR_SAVE_TRANSACTIONS_SECONDS.start_timer();
+            commit.persist()?;
+        }
+        self.finish_commit(commit)
+    }
+
+    fn prepare_commit(
+        &mut self,
+        block_ids: Vec<HashValue>,
+        ledger_info_with_sigs: LedgerInfoWithSignatures,
+    ) -> Result<PreparedCommit, Error> {
         let block_id_to_commit = ledger_info_with_sigs.ledger_info().consensus_block_id();
 
         info!(
@@ -809,7 +859,8 @@ impl<V: VMExecutor> BlockExecutor for Executor<V> {
         }
 
         if num_txns_in_li == num_persistent_txns {
-            return Ok(self.cache.committed_txns_and_events());
+            let (committed_txns, reconfig_events) = self.cache.committed_txns_and_events();
+            return Ok(PreparedCommit::Committed(committed_txns, reconfig_events));
         }
 
         // All transactions that need to go to storage. In the above example, this means all the
@@ -879,29 +930,46 @@ impl<V: VMExecutor> BlockExecutor for Executor<V> {
         }
 
         // Skip duplicate txns that are already persistent.
-        let txns_to_commit = &txns_to_keep[num_txns_to_skip as usize..];
+        let txns_to_commit = txns_to_keep.split_off(num_txns_to_skip as usize);
 
         let num_txns_to_commit = txns_to_commit.len() as u64;
-        {
-            let _timer = LIBRA_EXECUTOR_SAVE_TRANSACTIONS_SECONDS.start_timer();
-            LIBRA_EXECUTOR_TRANSACTIONS_SAVED.observe(num_txns_to_commit as f64);
+        LIBRA_EXECUTOR_TRANSACTIONS_SAVED.observe(num_txns_to_commit as f64);
+        assert_eq!(first_version_to_commit, num_txns_in_li - num_txns_to_commit);
+        fail_point!("executor::commit_blocks", |_| {
+            Err(Error::from(anyhow::anyhow!(
+                "Injected error in commit_blocks"
+            )))
+        });
 
-            assert_eq!(first_version_to_commit, num_txns_in_li - num_txns_to_commit);
-            fail_point!("executor::commit_blocks", |_| {
-                Err(Error::from(anyhow::anyhow!(
-                    "Injected error in commit_blocks"
-                )))
-            });
-            self.db.writer.save_transactions(
+        Ok(PreparedCommit::Pending {
+            db_writer: Arc::clone(&self.db.writer),
+            block_ids,
+            txns_to_commit,
+            first_version: first_version_to_commit,
+            ledger_info_with_sigs,
+        })
+    }
+
+    fn finish_commit(
+        &mut self,
+        commit: PreparedCommit,
+    ) -> Result<(Vec<Transaction>, Vec<ContractEvent>), Error> {
+        let (block_ids, txns_to_commit, ledger_info_with_sigs) = match commit {
+            PreparedCommit::Pending {
+                block_ids,
                 txns_to_commit,
-                first_version_to_commit,
-                Some(&ledger_info_with_sigs),
-            )?;
-        }
+                ledger_info_with_sigs,
+                ..
+            } => (block_ids, txns_to_commit, ledger_info_with_sigs),
+            PreparedCommit::Committed(committed_txns, reconfig_events) => {
+                return Ok((committed_txns, reconfig_events))
+            }
+        };
 
         // Prune the tree.
-        for block in blocks {
-            for txn_data in block.output().transaction_data() {
+        for id in &block_ids {
+            let block = self.cache.get_block(id)?;
+            for txn_data in block.lock().output().transaction_data() {
                 txn_data.prune_state_tree();
             }
         }
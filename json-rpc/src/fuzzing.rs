@@ -116,6 +116,7 @@ pub fn request_fuzzer(json_request: serde_json::Value) {
     let registry = Arc::new(methods::build_registry());
     let service = methods::JsonRpcService::new(
         Arc::new(db),
+        None,
         mp_sender,
         config::RoleType::Validator,
         libra_types::chain_id::ChainId::test(),
@@ -5,11 +5,12 @@
 use crate::{
     errors::JsonRpcError,
     views::{
-        AccountStateWithProofView, AccountView, BytesView, CurrencyInfoView, EventView,
-        MetadataView, StateProofView, TransactionView,
+        AccountStateWithProofView, AccountView, BytesView, CurrencyInfoView,
+        EquivocationEvidenceView, EventView, MetadataView, StateProofView, TransactionView,
     },
 };
 use anyhow::{ensure, format_err, Error, Result};
+use consensus_types::equivocation_evidence::EquivocationEvidenceReader;
 use core::future::Future;
 use fail::fail_point;
 use futures::{channel::oneshot, SinkExt};
@@ -42,6 +43,7 @@ use storage_interface::{DbReader, Order};
 #[derive(Clone)]
 pub(crate) struct JsonRpcService {
     db: Arc<dyn DbReader>,
+    evidence_reader: Option<Arc<dyn EquivocationEvidenceReader>>,
     mempool_sender: MempoolClientSender,
     role: RoleType,
     chain_id: ChainId,
@@ -52,6 +54,7 @@ pub(crate) struct JsonRpcService {
 impl JsonRpcService {
     pub fn new(
         db: Arc<dyn DbReader>,
+        evidence_reader: Option<Arc<dyn EquivocationEvidenceReader>>,
         mempool_sender: MempoolClientSender,
         role: RoleType,
         chain_id: ChainId,
@@ -60,6 +63,7 @@ impl JsonRpcService {
     ) -> Self {
         Self {
             db,
+            evidence_reader,
             mempool_sender,
             role,
             chain_id,
@@ -591,6 +595,37 @@ async fn get_network_status(service: JsonRpcService, _request: JsonRpcRequest) -
     Ok(peers.get() as u64)
 }
 
+/// Returns the evidence of validators equivocating collected by the node, only validators
+/// collect evidence
+async fn get_equivocation_evidence(
+    service: JsonRpcService,
+    request: JsonRpcRequest,
+) -> Result<Vec<EquivocationEvidenceView>> {
+    let start_epoch: u64 = request.parse_param(0, "start_epoch")?;
+    let limit: u64 = request.parse_param(1, "limit")?;
+
+    service.validate_page_size_limit(limit as usize)?;
+
+    let evidence_reader = service.evidence_reader.as_ref().ok_or_else(|| {
+        JsonRpcError::invalid_request_with_msg(
+            "equivocation evidence is only collected by validators".to_string(),
+        )
+    })?;
+    evidence_reader
+        .get_equivocation_evidence(start_epoch, limit)?
+        .iter()
+        .map(|evidence| {
+            Ok(EquivocationEvidenceView {
+                epoch: evidence.epoch(),
+                round: evidence.round(),
+                author: evidence.author().as_ref().into(),
+                kind: evidence.kind().to_string(),
+                evidence: lcs::to_bytes(evidence)?.into(),
+            })
+        })
+        .collect()
+}
+
 /// Builds registry of all available RPC methods
 /// To register new RPC method, add it via `register_rpc_method!` macros call
 /// Note that RPC method name will equal to name of function
@@ -627,6 +662,13 @@ pub(crate) fn build_registry() -> RpcRegistry {
         0
     );
     register_rpc_method!(registry, "get_network_status", get_network_status, 0, 0);
+    register_rpc_method!(
+        registry,
+        "get_equivocation_evidence",
+        get_equivocation_evidence,
+        2,
+        0
+    );
 
     registry
 }
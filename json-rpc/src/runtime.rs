@@ -7,6 +7,7 @@ use crate::{
     methods::{build_registry, JsonRpcRequest, JsonRpcService, RpcRegistry},
     response::JsonRpcResponse,
 };
+use consensus_types::equivocation_evidence::EquivocationEvidenceReader;
 use futures::future::join_all;
 use libra_config::config::{NodeConfig, RoleType};
 use libra_logger::{debug, Level, Schema};
@@ -86,6 +87,7 @@ pub fn bootstrap(
     page_size_limit: u16,
     content_len_limit: usize,
     libra_db: Arc<dyn DbReader>,
+    evidence_reader: Option<Arc<dyn EquivocationEvidenceReader>>,
     mp_sender: MempoolClientSender,
     role: RoleType,
     chain_id: ChainId,
@@ -100,6 +102,7 @@ pub fn bootstrap(
     let registry = Arc::new(build_registry());
     let service = JsonRpcService::new(
         libra_db,
+        evidence_reader,
         mp_sender,
         role,
         chain_id,
@@ -158,10 +161,12 @@ pub fn bootstrap(
 }
 
 /// Creates JSON RPC endpoint by given node config
+/// `evidence_reader` serves the equivocation evidence collected by consensus on validators
 pub fn bootstrap_from_config(
     config: &NodeConfig,
     chain_id: ChainId,
     libra_db: Arc<dyn DbReader>,
+    evidence_reader: Option<Arc<dyn EquivocationEvidenceReader>>,
     mp_sender: MempoolClientSender,
 ) -> Runtime {
     bootstrap(
@@ -170,6 +175,7 @@ pub fn bootstrap_from_config(
         config.json_rpc.page_size_limit,
         config.json_rpc.content_length_limit,
         libra_db,
+        evidence_reader,
         mp_sender,
         config.base.role,
         chain_id,
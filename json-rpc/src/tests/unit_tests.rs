@@ -7,6 +7,12 @@ use crate::{
         genesis::generate_genesis_state,
         utils::{test_bootstrap, MockLibraDB},
     },
+    views::EquivocationEvidenceView,
+};
+use consensus_types::{
+    equivocation_evidence::{EquivocationEvidence, EquivocationEvidenceReader},
+    vote::Vote,
+    vote_data::VoteData,
 };
 use futures::{
     channel::{
@@ -15,7 +21,12 @@ use futures::{
     },
     StreamExt,
 };
-use libra_config::{config::DEFAULT_CONTENT_LENGTH_LIMIT, utils};
+use libra_config::{
+    config::{
+        RoleType, DEFAULT_BATCH_SIZE_LIMIT, DEFAULT_CONTENT_LENGTH_LIMIT, DEFAULT_PAGE_SIZE_LIMIT,
+    },
+    utils,
+};
 use libra_crypto::{ed25519::Ed25519PrivateKey, hash::CryptoHash, HashValue, PrivateKey, Uniform};
 use libra_json_rpc_client::{
     views::{
@@ -32,14 +43,16 @@ use libra_types::{
     account_config::{from_currency_code_string, AccountResource, FreezingBit, COIN1_NAME},
     account_state::AccountState,
     account_state_blob::{AccountStateBlob, AccountStateWithProof},
+    block_info::BlockInfo,
     chain_id::ChainId,
     contract_event::ContractEvent,
     event::EventKey,
-    ledger_info::LedgerInfoWithSignatures,
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
     mempool_status::{MempoolStatus, MempoolStatusCode},
     proof::{SparseMerkleProof, TransactionAccumulatorProof, TransactionInfoWithProof},
     test_helpers::transaction_test_helpers::get_test_signed_txn,
     transaction::{SignedTransaction, Transaction, TransactionInfo, TransactionPayload},
+    validator_signer::ValidatorSigner,
     vm_status::StatusCode,
 };
 use libradb::test_helper::arb_blocks_to_commit;
@@ -703,6 +716,22 @@ fn test_json_rpc_protocol_invalid_requests() {
                 "libra_ledger_version": version
             }),
         ),
+        (
+            "get_equivocation_evidence: not collected",
+            json!({"jsonrpc": "2.0", "method": "get_equivocation_evidence", "params": [0, 10], "id": 1}),
+            json!({
+                "error": {
+                    "code": -32600,
+                    "message": "Invalid Request: equivocation evidence is only collected by validators",
+                    "data": null
+                },
+                "id": 1,
+                "jsonrpc": "2.0",
+                "libra_chain_id": ChainId::test().id(),
+                "libra_ledger_timestampusec": timestamp,
+                "libra_ledger_version": version
+            }),
+        ),
         (
             "get_state_proof: invalid known_version",
             json!({"jsonrpc": "2.0", "method": "get_state_proof", "params": ["invalid"], "id": 1}),
@@ -1430,6 +1459,77 @@ fn test_get_network_status() {
     }
 }
 
+struct MockEvidenceReader(Vec<EquivocationEvidence>);
+
+impl EquivocationEvidenceReader for MockEvidenceReader {
+    fn get_equivocation_evidence(
+        &self,
+        start_epoch: u64,
+        limit: u64,
+    ) -> anyhow::Result<Vec<EquivocationEvidence>> {
+        Ok(self
+            .0
+            .iter()
+            .filter(|evidence| evidence.epoch() >= start_epoch)
+            .take(limit as usize)
+            .cloned()
+            .collect())
+    }
+}
+
+#[test]
+fn test_get_equivocation_evidence() {
+    let signer = ValidatorSigner::random(None);
+    let vote = || {
+        Vote::new(
+            VoteData::new(BlockInfo::random(1), BlockInfo::random(0)),
+            signer.author(),
+            LedgerInfo::new(BlockInfo::random(1), HashValue::random()),
+            &signer,
+        )
+    };
+    let evidence = EquivocationEvidence::Votes(vote(), vote());
+
+    let address = format!("127.0.0.1:{}", utils::get_available_port());
+    let (mp_sender, _mp_events) = channel(1);
+    let _runtime = crate::bootstrap(
+        address.parse().unwrap(),
+        DEFAULT_BATCH_SIZE_LIMIT,
+        DEFAULT_PAGE_SIZE_LIMIT,
+        DEFAULT_CONTENT_LENGTH_LIMIT,
+        Arc::new(mock_db()),
+        Some(Arc::new(MockEvidenceReader(vec![evidence.clone()]))),
+        mp_sender,
+        RoleType::Validator,
+        ChainId::test(),
+    );
+
+    let client = reqwest::blocking::Client::new();
+    let request = |start_epoch| {
+        let resp = client
+            .post(&format!("http://{}", address))
+            .json(&json!({"jsonrpc": "2.0", "method": "get_equivocation_evidence", "params": [start_epoch, 10], "id": 1}))
+            .send()
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+        let resp_json: serde_json::Value = resp.json().unwrap();
+        serde_json::from_value::<Vec<EquivocationEvidenceView>>(resp_json["result"].clone())
+            .unwrap()
+    };
+
+    let views = request(1);
+    assert_eq!(views.len(), 1);
+    assert_eq!(views[0].epoch, 1);
+    assert_eq!(views[0].round, 1);
+    assert_eq!(views[0].kind, "vote");
+    assert_eq!(views[0].author, BytesView::from(signer.author().as_ref()));
+    let decoded: EquivocationEvidence =
+        lcs::from_bytes(&views[0].evidence.clone().into_bytes().unwrap()).unwrap();
+    assert_eq!(decoded, evidence);
+
+    assert!(request(2).is_empty());
+}
+
 /// Creates and returns a MockLibraDB, JsonRpcAsyncClient and corresponding server Runtime tuple for
 /// testing. The given channel_buffer specifies the buffer size of the mempool client sender channel.
 fn create_database_client_and_runtime() -> (MockLibraDB, JsonRpcAsyncClient, Runtime) {
@@ -46,6 +46,7 @@ pub fn test_bootstrap(
         DEFAULT_PAGE_SIZE_LIMIT,
         DEFAULT_CONTENT_LENGTH_LIMIT,
         libra_db,
+        None,
         mp_sender,
         RoleType::Validator,
         ChainId::test(),
@@ -353,6 +353,18 @@ pub struct MetadataView {
     pub libra_version: Option<u64>,
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct EquivocationEvidenceView {
+    pub epoch: u64,
+    pub round: u64,
+    pub author: BytesView,
+    /// The kind of the conflicting messages: "vote" or "proposal"
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// LCS serialized evidence, carrying both messages signed by the author
+    pub evidence: BytesView,
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub struct BytesView(pub String);
 
@@ -0,0 +1,91 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use bytecode_verifier::DependencyChecker;
+use libra_types::vm_status::StatusCode;
+use move_core_types::identifier::Identifier;
+use vm::file_format::{
+    empty_module, AddressIdentifierIndex, CodeUnit, CompiledModule, FunctionDefinition,
+    FunctionHandle, FunctionHandleIndex, IdentifierIndex, ModuleHandle, ModuleHandleIndex,
+    SignatureIndex, Visibility,
+};
+
+// module Dep {
+//     friend Caller;
+//     <visibility> f() {}
+// }
+fn dependency(visibility: Visibility) -> CompiledModule {
+    let mut m = empty_module();
+    m.identifiers = vec![
+        Identifier::new("Dep").unwrap(),
+        Identifier::new("f").unwrap(),
+        Identifier::new("Caller").unwrap(),
+    ];
+    let caller = ModuleHandle {
+        address: AddressIdentifierIndex(0),
+        name: IdentifierIndex(2),
+    };
+    m.module_handles.push(caller.clone());
+    m.friend_decls.push(caller);
+    m.function_handles.push(FunctionHandle {
+        module: ModuleHandleIndex(0),
+        name: IdentifierIndex(1),
+        parameters: SignatureIndex(0),
+        return_: SignatureIndex(0),
+        type_parameters: vec![],
+    });
+    m.function_defs.push(FunctionDefinition {
+        function: FunctionHandleIndex(0),
+        visibility,
+        acquires_global_resources: vec![],
+        code: Some(CodeUnit {
+            locals: SignatureIndex(0),
+            code: vec![],
+        }),
+    });
+    m.freeze().unwrap()
+}
+
+// A module named `name` importing `Dep::f`.
+fn caller(name: &str) -> CompiledModule {
+    let mut m = empty_module();
+    m.identifiers = vec![
+        Identifier::new(name).unwrap(),
+        Identifier::new("Dep").unwrap(),
+        Identifier::new("f").unwrap(),
+    ];
+    m.module_handles.push(ModuleHandle {
+        address: AddressIdentifierIndex(0),
+        name: IdentifierIndex(1),
+    });
+    m.function_handles.push(FunctionHandle {
+        module: ModuleHandleIndex(1),
+        name: IdentifierIndex(2),
+        parameters: SignatureIndex(0),
+        return_: SignatureIndex(0),
+        type_parameters: vec![],
+    });
+    m.freeze().unwrap()
+}
+
+fn verify(module: &CompiledModule, dependency: &CompiledModule) -> Result<(), StatusCode> {
+    DependencyChecker::verify_module(module, vec![dependency]).map_err(|e| e.major_status())
+}
+
+#[test]
+fn friend_functions() {
+    let friend = caller("Caller");
+    let other = caller("Other");
+
+    let dep = dependency(Visibility::Friend);
+    assert_eq!(verify(&friend, &dep), Ok(()));
+    assert_eq!(verify(&other, &dep), Err(StatusCode::LOOKUP_FAILED));
+
+    let dep = dependency(Visibility::Public);
+    assert_eq!(verify(&friend, &dep), Ok(()));
+    assert_eq!(verify(&other, &dep), Ok(()));
+
+    let dep = dependency(Visibility::Private);
+    assert_eq!(verify(&friend, &dep), Err(StatusCode::LOOKUP_FAILED));
+    assert_eq!(verify(&other, &dep), Err(StatusCode::LOOKUP_FAILED));
+}
@@ -2,8 +2,12 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use bytecode_verifier::DuplicationChecker;
+use libra_types::vm_status::StatusCode;
+use move_core_types::identifier::Identifier;
 use proptest::prelude::*;
-use vm::file_format::CompiledModule;
+use vm::file_format::{
+    empty_module, AddressIdentifierIndex, CompiledModule, IdentifierIndex, ModuleHandle,
+};
 
 proptest! {
     #[test]
@@ -11,3 +15,38 @@ proptest! {
         prop_assert!(DuplicationChecker::verify_module(&module).is_ok());
     }
 }
+
+#[test]
+fn friend_decls() {
+    let mut m = empty_module();
+    m.identifiers.push(Identifier::new("Friend").unwrap());
+    let friend = ModuleHandle {
+        address: AddressIdentifierIndex(0),
+        name: IdentifierIndex(1),
+    };
+    m.module_handles.push(friend.clone());
+
+    m.friend_decls.push(friend.clone());
+    let module = m.clone().freeze().unwrap();
+    assert!(DuplicationChecker::verify_module(&module).is_ok());
+
+    let mut duplicate = m.clone();
+    duplicate.friend_decls.push(friend);
+    let module = duplicate.freeze().unwrap();
+    assert_eq!(
+        DuplicationChecker::verify_module(&module)
+            .unwrap_err()
+            .major_status(),
+        StatusCode::DUPLICATE_ELEMENT
+    );
+
+    let mut self_friend = m;
+    self_friend.friend_decls = vec![self_friend.module_handles[0].clone()];
+    let module = self_friend.freeze().unwrap();
+    assert_eq!(
+        DuplicationChecker::verify_module(&module)
+            .unwrap_err()
+            .major_status(),
+        StatusCode::INVALID_FRIEND_DECL
+    );
+}
@@ -123,7 +123,7 @@ fn make_module() -> CompiledModuleMut {
             // fun fn() { return; }
             FunctionDefinition {
                 function: FunctionHandleIndex(0),
-                is_public: true,
+                visibility: Visibility::Public,
                 acquires_global_resources: vec![],
                 code: Some(CodeUnit {
                     locals: SignatureIndex(0),
@@ -133,7 +133,7 @@ fn make_module() -> CompiledModuleMut {
             // fun g_fn<T>() { return; }
             FunctionDefinition {
                 function: FunctionHandleIndex(1),
-                is_public: false,
+                visibility: Visibility::Private,
                 acquires_global_resources: vec![],
                 code: Some(CodeUnit {
                     locals: SignatureIndex(0),
@@ -143,7 +143,7 @@ fn make_module() -> CompiledModuleMut {
             // fun test_fn() { ... } - tests will fill up the code
             FunctionDefinition {
                 function: FunctionHandleIndex(2),
-                is_public: false,
+                visibility: Visibility::Private,
                 acquires_global_resources: vec![],
                 code: Some(CodeUnit {
                     locals: SignatureIndex(0),
@@ -163,6 +163,7 @@ fn make_module() -> CompiledModuleMut {
             },
         ],
         field_handles: vec![],
+        friend_decls: vec![],
         struct_def_instantiations: vec![],
         function_instantiations: vec![],
         field_instantiations: vec![],
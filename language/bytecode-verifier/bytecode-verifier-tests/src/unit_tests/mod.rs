@@ -5,6 +5,7 @@ pub mod bounds_tests;
 pub mod code_unit_tests;
 pub mod constants_tests;
 pub mod control_flow_tests;
+pub mod dependencies_tests;
 pub mod duplication_tests;
 pub mod generic_ops_tests;
 pub mod multi_pass_tests;
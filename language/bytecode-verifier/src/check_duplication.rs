@@ -8,6 +8,7 @@
 //! - struct and field definitions are consistent
 //! - the handles in struct and function definitions point to the self module index
 //! - all struct and function handles pointing to the self module index have a definition
+//! - a module is not declared as a friend of itself
 use libra_types::vm_status::StatusCode;
 use move_core_types::{account_address::AccountAddress, identifier::Identifier};
 use std::{collections::HashSet, hash::Hash};
@@ -42,6 +43,7 @@ impl<'a> DuplicationChecker<'a> {
         Self::check_function_instantiations(module.function_instantiations())?;
 
         let checker = Self { module };
+        checker.check_friend_decls()?;
         checker.check_field_handles()?;
         checker.check_field_instantiations()?;
         checker.check_function_defintions()?;
@@ -160,6 +162,30 @@ impl<'a> DuplicationChecker<'a> {
     // Module only code
     //
 
+    // Friend declarations - a module may be declared a friend at most once, and never of itself
+    fn check_friend_decls(&self) -> PartialVMResult<()> {
+        let friend_decls = self.module.friend_decls();
+        if let Some(idx) = Self::first_duplicate_element(friend_decls) {
+            return Err(verification_error(
+                StatusCode::DUPLICATE_ELEMENT,
+                IndexKind::ModuleHandle,
+                idx,
+            ));
+        }
+        let self_id = self.module.self_id();
+        if let Some(idx) = friend_decls
+            .iter()
+            .position(|friend| self.module.module_id_for_handle(friend) == self_id)
+        {
+            return Err(verification_error(
+                StatusCode::INVALID_FRIEND_DECL,
+                IndexKind::ModuleHandle,
+                idx as TableIndex,
+            ));
+        }
+        Ok(())
+    }
+
     fn check_field_handles(&self) -> PartialVMResult<()> {
         match Self::first_duplicate_element(self.module.field_handles()) {
             Some(idx) => Err(verification_error(
@@ -22,7 +22,7 @@ pub struct DependencyChecker<'a> {
     dependency_map: BTreeMap<ModuleId, &'a CompiledModule>,
     // (Module::StructName -> handle) for all types of all dependencies
     struct_id_to_handle_map: HashMap<(ModuleId, Identifier), StructHandleIndex>,
-    // (Module::FunctionName -> handle) for all public functions of all dependencies
+    // (Module::FunctionName -> handle) for all functions of all dependencies visible to the module
     func_id_to_handle_map: HashMap<(ModuleId, Identifier), FunctionHandleIndex>,
 }
 
@@ -54,7 +54,7 @@ impl<'a> DependencyChecker<'a> {
             struct_id_to_handle_map: HashMap::new(),
             func_id_to_handle_map: HashMap::new(),
         };
-        checker.build_deps_entry_point();
+        checker.build_deps_entry_point(Some(&module_id));
 
         // verify dependencies
         checker.verify_imported_modules(module.module_handles(), Some(module.self_handle_idx()))?;
@@ -84,14 +84,16 @@ impl<'a> DependencyChecker<'a> {
             struct_id_to_handle_map: HashMap::new(),
             func_id_to_handle_map: HashMap::new(),
         };
-        checker.build_deps_entry_point();
+        checker.build_deps_entry_point(None);
 
         checker.verify_imported_modules(script.module_handles(), None)?;
         checker.verify_imported_structs(script.struct_handles(), None)?;
         checker.verify_imported_functions(script.function_handles(), None)
     }
 
-    fn build_deps_entry_point(&mut self) {
+    // `self_id` is the module being verified, if any. Friend functions of a dependency are only
+    // visible to modules the dependency declares as friends; scripts are never friends.
+    fn build_deps_entry_point(&mut self, self_id: Option<&ModuleId>) {
         for (module_id, module) in &self.dependency_map {
             // Module::StructName -> def handle idx
             for struct_def in module.struct_defs() {
@@ -102,9 +104,16 @@ impl<'a> DependencyChecker<'a> {
                     struct_def.struct_handle,
                 );
             }
+            let is_friend = match self_id {
+                Some(self_id) => module
+                    .friend_decls()
+                    .iter()
+                    .any(|friend| &module.module_id_for_handle(friend) == self_id),
+                None => false,
+            };
             // Module::FuncName -> def handle idx
             for func_def in module.function_defs() {
-                if !func_def.is_public {
+                if !(func_def.is_public() || (is_friend && func_def.is_friend())) {
                     continue;
                 }
                 let func_handle = module.function_handle_at(func_def.function);
@@ -27,8 +27,9 @@ use vm::{
     file_format::{
         Bytecode, CodeOffset, CodeUnit, CompiledModule, CompiledModuleMut, CompiledScript,
         CompiledScriptMut, Constant, FieldDefinition, FunctionDefinition, FunctionSignature, Kind,
-        Signature, SignatureToken, StructDefinition, StructDefinitionIndex, StructFieldInformation,
-        StructHandleIndex, TableIndex, TypeParameterIndex, TypeSignature,
+        ModuleHandle, Signature, SignatureToken, StructDefinition, StructDefinitionIndex,
+        StructFieldInformation, StructHandleIndex, TableIndex, TypeParameterIndex, TypeSignature,
+        Visibility,
     },
 };
 
@@ -488,6 +489,7 @@ pub fn compile_module<'a, T: 'a + ModuleAccess>(
     let self_module_handle_idx = context.declare_import(current_module, self_name.clone())?;
     // Explicitly declare all imports as they will be included even if not used
     compile_imports(&mut context, Some(address), module.imports)?;
+    let friend_decls = compile_friends(&mut context, module.friends)?;
 
     // Explicitly declare all structs as they will be included even if not used
     for s in &module.structs {
@@ -544,6 +546,7 @@ pub fn compile_module<'a, T: 'a + ModuleAccess>(
         struct_handles,
         function_handles,
         field_handles,
+        friend_decls,
         struct_def_instantiations,
         function_instantiations,
         field_instantiations,
@@ -562,6 +565,21 @@ pub fn compile_module<'a, T: 'a + ModuleAccess>(
         .map(|frozen_module| (frozen_module, source_map))
 }
 
+fn compile_friends(
+    context: &mut Context,
+    friends: Vec<QualifiedModuleIdent>,
+) -> Result<Vec<ModuleHandle>> {
+    friends
+        .into_iter()
+        .map(|friend| {
+            Ok(ModuleHandle {
+                address: context.address_index(friend.address)?,
+                name: context.identifier_index(friend.name.as_inner())?,
+            })
+        })
+        .collect()
+}
+
 fn compile_explicit_dependency_declarations(
     context: &mut Context,
     dependencies: Vec<ModuleDependency>,
@@ -843,9 +861,10 @@ fn compile_function(
 
     let ast_function = ast_function.value;
 
-    let is_public = match ast_function.visibility {
-        FunctionVisibility::Internal => false,
-        FunctionVisibility::Public => true,
+    let visibility = match ast_function.visibility {
+        FunctionVisibility::Internal => Visibility::Private,
+        FunctionVisibility::Public => Visibility::Public,
+        FunctionVisibility::Friend => Visibility::Friend,
     };
     let acquires_global_resources = ast_function
         .acquires
@@ -857,7 +876,7 @@ fn compile_function(
 
     Ok(FunctionDefinition {
         function: fh_idx,
-        is_public,
+        visibility,
         acquires_global_resources,
         code,
     })
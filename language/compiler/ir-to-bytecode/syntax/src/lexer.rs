@@ -63,6 +63,7 @@ pub enum Tok {
     Exists,
     False,
     Freeze,
+    Friend,
     /// Like borrow_global, but for spec language
     Global,
     /// Like exists, but for spec language
@@ -424,6 +425,7 @@ fn get_name_token(name: &str) -> Tok {
         "ensures" => Tok::Ensures,
         "false" => Tok::False,
         "freeze" => Tok::Freeze,
+        "friend" => Tok::Friend,
         "global" => Tok::Global,              // spec language
         "global_exists" => Tok::GlobalExists, // spec language
         "to_u8" => Tok::ToU8,
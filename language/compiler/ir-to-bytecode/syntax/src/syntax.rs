@@ -1730,11 +1730,18 @@ fn parse_function_decl<'input>(
         false
     };
 
-    let is_public = if tokens.peek() == Tok::Public {
+    let visibility = if tokens.peek() == Tok::Public {
         tokens.advance()?;
-        true
+        if tokens.peek() == Tok::LParen {
+            tokens.advance()?;
+            consume_token(tokens, Tok::Friend)?;
+            consume_token(tokens, Tok::RParen)?;
+            FunctionVisibility::Friend
+        } else {
+            FunctionVisibility::Public
+        }
     } else {
-        false
+        FunctionVisibility::Internal
     };
 
     let (name, type_parameters) = parse_name_and_type_parameters(tokens)?;
@@ -1765,11 +1772,7 @@ fn parse_function_decl<'input>(
 
     let func_name = FunctionName::new(name);
     let func = Function_::new(
-        if is_public {
-            FunctionVisibility::Public
-        } else {
-            FunctionVisibility::Internal
-        },
+        visibility,
         args,
         ret.unwrap_or_else(Vec::new),
         type_parameters,
@@ -1992,12 +1995,26 @@ fn parse_import_decl<'input>(
     Ok(ImportDefinition::new(ident, alias))
 }
 
+// FriendDecl: QualifiedModuleIdent = {
+//     "friend" <ident: QualifiedModuleIdent> ";" => { ... }
+// }
+
+fn parse_friend_decl<'input>(
+    tokens: &mut Lexer<'input>,
+) -> Result<QualifiedModuleIdent, ParseError<Loc, anyhow::Error>> {
+    consume_token(tokens, Tok::Friend)?;
+    let ident = parse_qualified_module_ident(tokens)?;
+    consume_token(tokens, Tok::Semicolon)?;
+    Ok(ident)
+}
+
 // pub Module : ModuleDefinition = {
 //     "module" <n: Name> "{"
 //         <imports: (ImportDecl)*>
+//         <friends: (FriendDecl)*>
 //         <structs: (StructDecl)*>
 //         <functions: (FunctionDecl)*>
-//     "}" =>? ModuleDefinition::new(n, imports, structs, functions),
+//     "}" =>? ModuleDefinition::new(n, imports, friends, structs, functions),
 // }
 
 fn is_struct_decl<'input>(
@@ -2022,6 +2039,11 @@ fn parse_module<'input>(
         imports.push(parse_import_decl(tokens)?);
     }
 
+    let mut friends = vec![];
+    while tokens.peek() == Tok::Friend {
+        friends.push(parse_friend_decl(tokens)?);
+    }
+
     let mut synthetics = vec![];
     while tokens.peek() == Tok::Synthetic {
         synthetics.push(parse_synthetic(tokens)?);
@@ -2041,6 +2063,7 @@ fn parse_module<'input>(
     Ok(ModuleDefinition::new(
         name,
         imports,
+        friends,
         vec![],
         structs,
         vec![],
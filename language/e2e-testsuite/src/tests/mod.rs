@@ -22,6 +22,7 @@ mod genesis_initializations;
 mod mint;
 mod module_publishing;
 mod on_chain_configs;
+mod parallel_execution;
 mod peer_to_peer;
 mod rotate_key;
 mod scripts;
@@ -1,6 +1,7 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use compiled_stdlib::transaction_scripts::StdlibScript;
 use language_e2e_tests::{
     account::{Account, AccountData},
     assert_prologue_parity,
@@ -10,8 +11,8 @@ use language_e2e_tests::{
 };
 use libra_types::{
     account_config::{self},
-    on_chain_config::VMPublishingOption,
-    transaction::TransactionStatus,
+    on_chain_config::{self, VMPublishingOption},
+    transaction::{Script, TransactionArgument, TransactionStatus},
     vm_status::{KeptVMStatus, StatusCode},
 };
 
@@ -291,3 +292,66 @@ pub fn test_publishing_allow_modules() {
         &TransactionStatus::Keep(KeptVMStatus::Executed)
     );
 }
+
+// Modules with friend functions need version 2 of the binary format, which can only be published
+// once the Libra version enables it.
+#[test]
+fn binary_format_version_gated_by_libra_version() {
+    let mut executor = FakeExecutor::from_genesis_with_options(VMPublishingOption::open());
+
+    let sequence_number = 2;
+    let account = AccountData::new(1_000_000, sequence_number);
+    executor.add_account_data(&account);
+
+    let program = String::from(
+        "
+        module M {
+            public(friend) f() {
+                return;
+            }
+        }
+        ",
+    );
+    let compiled_module = compile_module_with_address(account.address(), "file_name", &program).1;
+
+    let txn1 = account
+        .account()
+        .transaction()
+        .module(compiled_module.clone())
+        .sequence_number(sequence_number)
+        .sign();
+    let output1 = executor.execute_and_apply(txn1);
+    assert!(transaction_status_eq(
+        &output1.status(),
+        &TransactionStatus::Keep(KeptVMStatus::MiscellaneousError),
+    ));
+
+    let libra_root = Account::new_genesis_account(on_chain_config::config_address());
+    let libra_root_sequence_number = executor
+        .read_account_resource(&libra_root)
+        .unwrap()
+        .sequence_number();
+    let update_version = libra_root
+        .transaction()
+        .script(Script::new(
+            StdlibScript::UpdateLibraVersion.compiled_bytes().into_vec(),
+            vec![],
+            vec![TransactionArgument::U64(0), TransactionArgument::U64(2)],
+        ))
+        .sequence_number(libra_root_sequence_number)
+        .sign();
+    executor.new_block();
+    executor.execute_and_apply(update_version);
+
+    let txn2 = account
+        .account()
+        .transaction()
+        .module(compiled_module)
+        .sequence_number(sequence_number + 1)
+        .sign();
+    let output2 = executor.execute_and_apply(txn2);
+    assert!(transaction_status_eq(
+        &output2.status(),
+        &TransactionStatus::Keep(KeptVMStatus::Executed),
+    ));
+}
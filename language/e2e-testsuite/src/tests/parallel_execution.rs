@@ -0,0 +1,307 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use compiled_stdlib::transaction_scripts::StdlibScript;
+use language_e2e_tests::{
+    account::{Account, AccountData},
+    common_transactions::peer_to_peer_txn,
+    compile::{compile_module_with_address, compile_script_with_address},
+    executor::FakeExecutor,
+};
+use libra_crypto::HashValue;
+use libra_types::{
+    account_config,
+    block_metadata::BlockMetadata,
+    on_chain_config::{self, OnChainConfig, VMPublishingOption, ValidatorSet},
+    transaction::{
+        ChangeSet, Script, SignedTransaction, Transaction, TransactionArgument, TransactionOutput,
+        TransactionStatus, WriteSetPayload,
+    },
+    vm_status::{KeptVMStatus, StatusCode},
+};
+
+/// Execute `txns` both sequentially and in parallel, check that the outputs match and return them.
+fn execute_block(executor: &FakeExecutor, txns: Vec<Transaction>) -> Vec<TransactionOutput> {
+    let outputs = executor.execute_transaction_block(txns.clone()).unwrap();
+    assert_eq!(
+        executor.execute_transaction_block_parallel(txns).unwrap(),
+        outputs
+    );
+    outputs
+}
+
+/// A peer to peer transaction paying for gas.
+fn peer_to_peer_txn_with_fee(
+    sender: &Account,
+    receiver: &Account,
+    seq_num: u64,
+    transfer_amount: u64,
+) -> SignedTransaction {
+    sender
+        .transaction()
+        .script(Script::new(
+            StdlibScript::PeerToPeerWithMetadata
+                .compiled_bytes()
+                .into_vec(),
+            vec![account_config::coin1_tmp_tag()],
+            vec![
+                TransactionArgument::Address(*receiver.address()),
+                TransactionArgument::U64(transfer_amount),
+                TransactionArgument::U8Vector(vec![]),
+                TransactionArgument::U8Vector(vec![]),
+            ],
+        ))
+        .sequence_number(seq_num)
+        .gas_unit_price(1)
+        .sign()
+}
+
+#[test]
+fn parallel_execution_matches_sequential_execution() {
+    let mut executor = FakeExecutor::from_genesis_file();
+    let accounts: Vec<_> = (0..6)
+        .map(|_| {
+            let account = AccountData::new(1_000_000, 10);
+            executor.add_account_data(&account);
+            account
+        })
+        .collect();
+    let account = |idx: usize| accounts[idx].account();
+
+    let txns = vec![
+        peer_to_peer_txn(account(0), account(1), 10, 1_000),
+        peer_to_peer_txn(account(2), account(3), 10, 1_000),
+        // Same sender as the first transaction
+        peer_to_peer_txn(account(0), account(4), 11, 1_000),
+        // Same receiver as the first transaction
+        peer_to_peer_txn(account(5), account(1), 10, 1_000),
+        // Sequence number already used by the second transaction
+        peer_to_peer_txn(account(2), account(3), 10, 1_000),
+        // Balance too low, even with the payment received from the third transaction
+        peer_to_peer_txn(account(4), account(0), 10, 2_000_000),
+        // Balance only high enough with the payments received from the first and fourth
+        // transactions
+        peer_to_peer_txn(account(1), account(2), 10, 1_001_500),
+    ];
+
+    let outputs = executor.execute_block(txns.clone()).unwrap();
+    assert_eq!(executor.execute_block_parallel(txns).unwrap(), outputs);
+
+    assert_eq!(
+        outputs[4].status(),
+        &TransactionStatus::Discard(StatusCode::SEQUENCE_NUMBER_TOO_OLD)
+    );
+    assert!(matches!(
+        outputs[5].status(),
+        TransactionStatus::Keep(KeptVMStatus::MoveAbort(_, _))
+    ));
+    for output in outputs.iter().take(4).chain(outputs.last()) {
+        assert_eq!(
+            output.status(),
+            &TransactionStatus::Keep(KeptVMStatus::Executed)
+        );
+    }
+}
+
+#[test]
+fn parallel_execution_with_transaction_fees() {
+    let mut executor = FakeExecutor::from_genesis_file();
+    let accounts: Vec<_> = (0..4)
+        .map(|_| {
+            let account = AccountData::new(10_000_000, 10);
+            executor.add_account_data(&account);
+            account
+        })
+        .collect();
+    let account = |idx: usize| accounts[idx].account();
+
+    // Every transaction deposits its fee in the same `TransactionFee` resource
+    let txns = vec![
+        peer_to_peer_txn_with_fee(account(0), account(1), 10, 1_000),
+        peer_to_peer_txn_with_fee(account(2), account(3), 10, 1_000),
+        peer_to_peer_txn_with_fee(account(0), account(2), 11, 1_000),
+        peer_to_peer_txn_with_fee(account(3), account(0), 10, 1_000),
+    ]
+    .into_iter()
+    .map(Transaction::UserTransaction)
+    .collect();
+
+    let outputs = execute_block(&executor, txns);
+    for output in &outputs {
+        assert_eq!(
+            output.status(),
+            &TransactionStatus::Keep(KeptVMStatus::Executed)
+        );
+        assert!(output.gas_used() > 0);
+    }
+}
+
+#[test]
+fn parallel_execution_with_block_metadata_and_write_set() {
+    let mut executor = FakeExecutor::from_genesis_file();
+    let accounts: Vec<_> = (0..4)
+        .map(|_| {
+            let account = AccountData::new(1_000_000, 10);
+            executor.add_account_data(&account);
+            account
+        })
+        .collect();
+    let account = |idx: usize| accounts[idx].account();
+
+    let validator_set = ValidatorSet::fetch_config(executor.get_state_view())
+        .expect("Unable to retrieve the validator set from storage");
+    let block_metadata = BlockMetadata::new(
+        HashValue::zero(),
+        0,
+        executor.get_block_time() + 1,
+        vec![],
+        *validator_set.payload()[0].account_address(),
+    );
+    let new_account_data = AccountData::new(0, 10);
+    let write_set_txn = Account::new_libra_root()
+        .transaction()
+        .write_set(WriteSetPayload::Direct(ChangeSet::new(
+            new_account_data.to_writeset(),
+            vec![],
+        )))
+        .sequence_number(1)
+        .sign();
+
+    let txns = vec![
+        Transaction::UserTransaction(peer_to_peer_txn(account(0), account(1), 10, 1_000)),
+        Transaction::BlockMetadata(block_metadata),
+        Transaction::UserTransaction(peer_to_peer_txn(account(1), account(2), 10, 1_000)),
+        Transaction::UserTransaction(peer_to_peer_txn(account(0), account(3), 11, 1_000)),
+        Transaction::UserTransaction(write_set_txn),
+        Transaction::UserTransaction(peer_to_peer_txn(account(2), account(3), 10, 1_000)),
+    ];
+
+    let outputs = execute_block(&executor, txns);
+    for output in outputs.iter().take(5) {
+        assert_eq!(
+            output.status(),
+            &TransactionStatus::Keep(KeptVMStatus::Executed)
+        );
+    }
+}
+
+#[test]
+fn parallel_execution_with_module_publishing() {
+    let mut executor = FakeExecutor::from_genesis_with_options(VMPublishingOption::open());
+    let accounts: Vec<_> = (0..3)
+        .map(|_| {
+            let account = AccountData::new(1_000_000, 10);
+            executor.add_account_data(&account);
+            account
+        })
+        .collect();
+    let account = |idx: usize| accounts[idx].account();
+
+    let module_code = "
+        module M {
+            public fun f() {}
+        }
+        ";
+    let (compiled_module, module) =
+        compile_module_with_address(account(0).address(), "file_name", module_code);
+    let script_code = format!(
+        "
+        import 0x{}.M;
+
+        main() {{
+            M.f();
+            return;
+        }}
+        ",
+        account(0).address()
+    );
+    let script = compile_script_with_address(
+        account(1).address(),
+        "file_name",
+        &script_code,
+        vec![compiled_module],
+    );
+
+    let txns = vec![
+        peer_to_peer_txn(account(0), account(2), 10, 1_000),
+        // Calls the module before it is published
+        account(1)
+            .transaction()
+            .script(script.clone())
+            .sequence_number(10)
+            .sign(),
+        account(0)
+            .transaction()
+            .module(module)
+            .sequence_number(11)
+            .sign(),
+        // Calls the module published by the previous transaction
+        account(2)
+            .transaction()
+            .script(script)
+            .sequence_number(10)
+            .sign(),
+        peer_to_peer_txn(account(2), account(0), 11, 1_000),
+    ]
+    .into_iter()
+    .map(Transaction::UserTransaction)
+    .collect();
+
+    let outputs = execute_block(&executor, txns);
+    assert!(!matches!(
+        outputs[1].status(),
+        TransactionStatus::Keep(KeptVMStatus::Executed)
+    ));
+    for idx in &[0, 2, 3, 4] {
+        assert_eq!(
+            outputs[*idx].status(),
+            &TransactionStatus::Keep(KeptVMStatus::Executed)
+        );
+    }
+}
+
+#[test]
+fn parallel_execution_with_reconfiguration() {
+    let mut executor = FakeExecutor::from_genesis_file();
+    let accounts: Vec<_> = (0..4)
+        .map(|_| {
+            let account = AccountData::new(1_000_000, 10);
+            executor.add_account_data(&account);
+            account
+        })
+        .collect();
+    let account = |idx: usize| accounts[idx].account();
+    executor.new_block();
+
+    let update_libra_version_txn = Account::new_genesis_account(on_chain_config::config_address())
+        .transaction()
+        .script(Script::new(
+            StdlibScript::UpdateLibraVersion.compiled_bytes().into_vec(),
+            vec![],
+            vec![TransactionArgument::U64(0), TransactionArgument::U64(2)],
+        ))
+        .sequence_number(1)
+        .sign();
+
+    let txns = vec![
+        peer_to_peer_txn(account(0), account(1), 10, 1_000),
+        peer_to_peer_txn(account(2), account(3), 10, 1_000),
+        update_libra_version_txn,
+        peer_to_peer_txn(account(1), account(2), 10, 1_000),
+        peer_to_peer_txn(account(3), account(0), 10, 1_000),
+    ]
+    .into_iter()
+    .map(Transaction::UserTransaction)
+    .collect();
+
+    let outputs = execute_block(&executor, txns);
+    for output in outputs.iter().take(3) {
+        assert_eq!(
+            output.status(),
+            &TransactionStatus::Keep(KeptVMStatus::Executed)
+        );
+    }
+    for output in outputs.iter().skip(3) {
+        assert_eq!(output.status(), &TransactionStatus::Retry);
+    }
+}
@@ -75,6 +75,41 @@ fn single_peer_to_peer_with_event() {
     }
 }
 
+#[test]
+fn single_peer_to_peer_gas_profile() {
+    ::libra_logger::Logger::init_for_testing();
+    let mut executor = FakeExecutor::from_genesis_file();
+    let sender = AccountData::new(1_000_000, 10);
+    let receiver = AccountData::new(100_000, 10);
+    executor.add_account_data(&sender);
+    executor.add_account_data(&receiver);
+
+    let txn = peer_to_peer_txn(sender.account(), receiver.account(), 10, 1_000);
+    let (output, gas_profile) = executor.execute_transaction_with_gas_profile(txn);
+    assert_eq!(
+        output.status(),
+        &TransactionStatus::Keep(KeptVMStatus::Executed)
+    );
+
+    // gas is attributed to the functions called by the script, and to the transaction size
+    let stacks = gas_profile.stacks();
+    assert!(stacks.contains_key("main;0x1::LibraAccount::pay_from"));
+    assert!(stacks.contains_key("[INTRINSIC]"));
+    assert!(stacks
+        .keys()
+        .all(|stack| stack.starts_with("main") || stack.starts_with('[')));
+    assert!(gas_profile.kinds().contains_key("CALL_GENERIC"));
+    // the profile is in internal gas units, which are scaled down to the gas used
+    assert!(gas_profile.total_gas() >= output.gas_used());
+
+    let mut folded = vec![];
+    gas_profile.write_folded(&mut folded).unwrap();
+    assert_eq!(
+        String::from_utf8(folded).unwrap().lines().count(),
+        stacks.len()
+    );
+}
+
 #[test]
 fn single_peer_to_peer_with_padding() {
     ::libra_logger::Logger::init_for_testing();
@@ -18,7 +18,7 @@ use libra_types::{
 };
 use std::convert::TryFrom;
 
-pub trait LibraValidatorInterface {
+pub trait LibraValidatorInterface: Sync {
     fn get_account_state_by_version(
         &self,
         account: AccountAddress,
@@ -38,6 +38,17 @@ pub static SYSTEM_TRANSACTIONS_EXECUTED: Lazy<IntCounter> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Count the number of user transactions executed speculatively in parallel, with a "status"
+/// label to distinguish valid outputs from the ones invalidated by conflicting transactions.
+pub static SPECULATIVE_TRANSACTIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "libra_vm_speculative_transactions",
+        "Number of user transactions executed speculatively",
+        &["status"]
+    )
+    .unwrap()
+});
+
 pub static BLOCK_TRANSACTION_COUNT: Lazy<Histogram> = Lazy::new(|| {
     register_histogram!(
         "libra_vm_num_txns_per_block",
@@ -117,11 +117,12 @@ pub mod transaction_metadata;
 pub mod libra_transaction_executor;
 pub mod libra_transaction_validator;
 pub mod logging;
+pub mod parallel_executor;
 pub mod system_module_names;
 
 pub use crate::{
     libra_transaction_executor::LibraVM, libra_transaction_validator::LibraVMValidator,
-    libra_vm::txn_effects_to_writeset_and_events,
+    libra_vm::txn_effects_to_writeset_and_events, parallel_executor::ParallelLibraVM,
 };
 
 use libra_state_view::StateView;
@@ -10,6 +10,7 @@ use crate::{
         txn_effects_to_writeset_and_events_cached, LibraVMImpl, LibraVMInternals,
     },
     logging::AdapterLogSchema,
+    parallel_executor::SpeculativeRun,
     system_module_names::*,
     transaction_metadata::TransactionMetadata,
     txn_effects_to_writeset_and_events, VMExecutor,
@@ -35,6 +36,7 @@ use move_core_types::{
 };
 use move_vm_runtime::{data_cache::RemoteCache, logging::LogContext, session::Session};
 use move_vm_types::{
+    gas_profile::GasProfile,
     gas_schedule::{zero_cost_schedule, CostStrategy},
     values::Value,
 };
@@ -43,6 +45,7 @@ use std::{
     collections::HashSet,
     convert::{AsMut, AsRef},
 };
+use vm::deserializer::binary_format_version;
 
 pub struct LibraVM(LibraVMImpl);
 
@@ -252,6 +255,12 @@ impl LibraVM {
         cost_strategy
             .charge_intrinsic_gas(txn_data.transaction_size())
             .map_err(|e| e.into_vm_status())?;
+        // Malformed headers are reported by the deserializer when publishing.
+        if let Ok(version) = binary_format_version(module.code()) {
+            if version > self.0.get_max_binary_format_version()? {
+                return Err(VMStatus::Error(StatusCode::UNKNOWN_VERSION));
+            }
+        }
         session
             .publish_module(
                 module.code().to_vec(),
@@ -273,10 +282,13 @@ impl LibraVM {
         )
     }
 
-    fn execute_user_transaction(
-        &mut self,
+    /// Execute a user transaction. If `gas_profile` is given, the gas charged to the transaction
+    /// is recorded in it.
+    pub(crate) fn execute_user_transaction(
+        &self,
         remote_cache: &StateViewCache<'_>,
         txn: &SignatureCheckedTransaction,
+        gas_profile: Option<&mut GasProfile>,
         log_context: &impl LogContext,
     ) -> (VMStatus, TransactionOutput) {
         macro_rules! unwrap_or_discard {
@@ -291,6 +303,9 @@ impl LibraVM {
         let gas_schedule = unwrap_or_discard!(self.0.get_gas_schedule(log_context));
         let txn_data = TransactionMetadata::new(txn);
         let mut cost_strategy = CostStrategy::system(gas_schedule, txn_data.max_gas_amount());
+        if gas_profile.is_some() {
+            cost_strategy.enable_profiling();
+        }
         let account_currency_symbol = unwrap_or_discard!(
             account_config::from_currency_code_string(txn.gas_currency_code())
                 .map_err(|_| VMStatus::Error(StatusCode::INVALID_GAS_SPECIFIER))
@@ -327,6 +342,9 @@ impl LibraVM {
             .sub(cost_strategy.remaining_gas())
             .get();
         TXN_GAS_USAGE.observe(gas_usage as f64);
+        if let (Some(gas_profile), Some(profile)) = (gas_profile, cost_strategy.take_profile()) {
+            *gas_profile = profile;
+        }
 
         match result {
             Ok(output) => output,
@@ -411,11 +429,11 @@ impl LibraVM {
     fn process_waypoint_change_set(
         &mut self,
         remote_cache: &mut StateViewCache<'_>,
-        writeset_payload: WriteSetPayload,
+        writeset_payload: &WriteSetPayload,
         log_context: &impl LogContext,
     ) -> Result<(VMStatus, TransactionOutput), VMStatus> {
         let change_set =
-            match self.execute_writeset(remote_cache, &writeset_payload, None, log_context) {
+            match self.execute_writeset(remote_cache, writeset_payload, None, log_context) {
                 Ok(cs) => cs,
                 Err(e) => return e,
             };
@@ -486,7 +504,7 @@ impl LibraVM {
     fn process_writeset_transaction(
         &mut self,
         remote_cache: &mut StateViewCache<'_>,
-        txn: SignatureCheckedTransaction,
+        txn: &SignatureCheckedTransaction,
         log_context: &impl LogContext,
     ) -> Result<(VMStatus, TransactionOutput), VMStatus> {
         fail_point!("move_adapter::process_writeset_transaction", |_| {
@@ -495,7 +513,7 @@ impl LibraVM {
             ))
         });
 
-        let txn_data = TransactionMetadata::new(&txn);
+        let txn_data = TransactionMetadata::new(txn);
 
         let mut session = self.0.new_session(remote_cache);
 
@@ -606,11 +624,16 @@ impl LibraVM {
         ))
     }
 
-    fn execute_block_impl(
+    /// Execute a block of transactions. If `profile_gas` is set, the gas charged to each user
+    /// transaction is profiled. If `parallel` is set, runs of user transactions are executed
+    /// speculatively in parallel, see `SpeculativeRun`: the outputs are the same either way.
+    pub(crate) fn execute_block_impl(
         &mut self,
         transactions: Vec<Transaction>,
         data_cache: &mut StateViewCache,
-    ) -> Result<Vec<(VMStatus, TransactionOutput)>, VMStatus> {
+        profile_gas: bool,
+        parallel: bool,
+    ) -> Result<Vec<(VMStatus, TransactionOutput, Option<GasProfile>)>, VMStatus> {
         let count = transactions.len();
         let mut result = vec![];
         let mut current_block_id;
@@ -631,7 +654,8 @@ impl LibraVM {
                 .collect();
         }
 
-        for (idx, txn) in signature_verified_block.into_iter().enumerate() {
+        let mut speculative_run: Option<SpeculativeRun> = None;
+        for (idx, txn) in signature_verified_block.iter().enumerate() {
             let log_context = AdapterLogSchema::new(data_cache.id(), idx);
             if should_restart {
                 let txn_output = TransactionOutput::new(
@@ -640,17 +664,25 @@ impl LibraVM {
                     0,
                     TransactionStatus::Retry,
                 );
-                result.push((VMStatus::Error(StatusCode::UNKNOWN_STATUS), txn_output));
+                result.push((
+                    VMStatus::Error(StatusCode::UNKNOWN_STATUS),
+                    txn_output,
+                    None,
+                ));
                 debug!(log_context, "Retry after reconfiguration");
                 continue;
             };
+            let mut gas_profile = None;
             let (vm_status, output, sender) = match txn {
                 Ok(PreprocessedTransaction::BlockPrologue(block_metadata)) => {
                     execute_block_trace_guard.clear();
                     current_block_id = block_metadata.id();
                     trace_code_block!("libra_vm::execute_block_impl", {"block", current_block_id}, execute_block_trace_guard);
-                    let (vm_status, output) =
-                        self.process_block_prologue(data_cache, block_metadata, &log_context)?;
+                    let (vm_status, output) = self.process_block_prologue(
+                        data_cache,
+                        block_metadata.clone(),
+                        &log_context,
+                    )?;
                     (vm_status, output, Some("block_prologue".to_string()))
                 }
                 Ok(PreprocessedTransaction::WaypointWriteSet(write_set_payload)) => {
@@ -663,9 +695,40 @@ impl LibraVM {
                 }
                 Ok(PreprocessedTransaction::UserTransaction(txn)) => {
                     let sender = txn.sender().to_string();
+                    if parallel
+                        && !speculative_run
+                            .as_ref()
+                            .map_or(false, |run| run.contains(idx))
+                    {
+                        speculative_run = Some(SpeculativeRun::execute(
+                            self,
+                            data_cache,
+                            &signature_verified_block,
+                            idx,
+                            profile_gas,
+                        ));
+                    }
                     let _timer = TXN_TOTAL_SECONDS.start_timer();
-                    let (vm_status, output) =
-                        self.execute_user_transaction(data_cache, &txn, &log_context);
+                    let speculative_output = speculative_run
+                        .as_mut()
+                        .and_then(|run| run.take_output(idx, data_cache));
+                    let (vm_status, output) = match speculative_output {
+                        Some((vm_status, output, txn_gas_profile)) => {
+                            gas_profile = txn_gas_profile;
+                            (vm_status, output)
+                        }
+                        None => {
+                            if profile_gas {
+                                gas_profile = Some(GasProfile::new());
+                            }
+                            self.execute_user_transaction(
+                                data_cache,
+                                txn,
+                                gas_profile.as_mut(),
+                                &log_context,
+                            )
+                        }
+                    };
 
                     // Increment the counter for user transactions executed.
                     let counter_label = match output.status() {
@@ -680,16 +743,19 @@ impl LibraVM {
                 }
                 Ok(PreprocessedTransaction::WriteSet(txn)) => {
                     let (vm_status, output) =
-                        self.process_writeset_transaction(data_cache, *txn, &log_context)?;
+                        self.process_writeset_transaction(data_cache, txn, &log_context)?;
                     (vm_status, output, Some("write_set".to_string()))
                 }
                 Err(e) => {
-                    let (vm_status, output) = discard_error_vm_status(e);
+                    let (vm_status, output) = discard_error_vm_status(e.clone());
                     (vm_status, output, None)
                 }
             };
             if !output.status().is_discarded() {
                 data_cache.push_write_set(output.write_set());
+                if let Some(run) = speculative_run.as_mut() {
+                    run.record_write_set(output.write_set());
+                }
             } else {
                 match sender {
                     Some(s) => trace!(
@@ -713,7 +779,7 @@ impl LibraVM {
             // `result` is initially empty, a single element is pushed per loop iteration and
             // the number of iterations is bound to the max size of `signature_verified_block`
             assume!(result.len() < usize::max_value());
-            result.push((vm_status, output))
+            result.push((vm_status, output, gas_profile))
         }
 
         // Record the histogram count for transactions per block.
@@ -730,7 +796,22 @@ impl LibraVM {
     ) -> Result<Vec<(VMStatus, TransactionOutput)>, VMStatus> {
         let mut state_view_cache = StateViewCache::new(state_view);
         let mut vm = LibraVM::new(&state_view_cache);
-        vm.execute_block_impl(transactions, &mut state_view_cache)
+        let output = vm.execute_block_impl(transactions, &mut state_view_cache, false, false)?;
+        Ok(output
+            .into_iter()
+            .map(|(vm_status, txn_output, _gas_profile)| (vm_status, txn_output))
+            .collect())
+    }
+
+    /// Alternate form of 'execute_block_and_keep_vm_status' that also profiles the gas charged to
+    /// each user transaction. The gas profile of any other transaction is `None`.
+    pub fn execute_block_with_gas_profiles(
+        transactions: Vec<Transaction>,
+        state_view: &dyn StateView,
+    ) -> Result<Vec<(VMStatus, TransactionOutput, Option<GasProfile>)>, VMStatus> {
+        let mut state_view_cache = StateViewCache::new(state_view);
+        let mut vm = LibraVM::new(&state_view_cache);
+        vm.execute_block_impl(transactions, &mut state_view_cache, true, false)
     }
 }
 
@@ -762,7 +843,7 @@ fn is_reconfiguration(vm_output: &TransactionOutput) -> bool {
 /// Transactions divided by transaction flow.
 /// Transaction flows are different across different types of transactions.
 #[derive(Debug)]
-enum PreprocessedTransaction {
+pub(crate) enum PreprocessedTransaction {
     UserTransaction(Box<SignatureCheckedTransaction>),
     WaypointWriteSet(WriteSetPayload),
     BlockPrologue(BlockMetadata),
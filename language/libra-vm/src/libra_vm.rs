@@ -40,7 +40,14 @@ use move_vm_types::{
     values::Value,
 };
 use std::{convert::TryFrom, sync::Arc};
-use vm::errors::Location;
+use vm::{
+    errors::Location,
+    file_format_common::{VERSION_1, VERSION_2},
+};
+
+/// The Libra version from which transactions may publish modules in version 2 of the binary
+/// format, which adds friend declarations.
+const BINARY_FORMAT_VERSION_2_LIBRA_VERSION: u64 = 2;
 
 #[derive(Clone)]
 /// A wrapper to make VMRuntime standalone and thread safe.
@@ -123,6 +130,17 @@ impl LibraVMImpl {
         })
     }
 
+    /// The latest version of the binary format of the modules that transactions may publish. It
+    /// only changes with the on-chain Libra version, so that all the validators agree on it.
+    pub fn get_max_binary_format_version(&self) -> Result<u32, VMStatus> {
+        let version = self.get_libra_version()?;
+        Ok(if version.major >= BINARY_FORMAT_VERSION_2_LIBRA_VERSION {
+            VERSION_2
+        } else {
+            VERSION_1
+        })
+    }
+
     pub fn check_gas(
         &self,
         txn_data: &TransactionMetadata,
@@ -0,0 +1,334 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Optimistic parallel execution of the transactions of a block.
+//!
+//! The user transactions running scripts between two other transactions of a block form a run.
+//! All the transactions of a run are first executed speculatively and in parallel, against the
+//! state before the run, while recording the access paths each of them reads. The outputs are then
+//! committed in order: the output of a transaction is valid if none of the access paths it read was
+//! written by a transaction of the run committed before it, and the transaction is executed again
+//! against the up to date state otherwise. Committed outputs are thus the ones a sequential
+//! execution of the block would produce.
+//!
+//! Every user transaction deposits its fee in the `TransactionFee` resource of its gas currency,
+//! which would make each transaction of a run conflict with the previous ones. The fee deposited
+//! by a speculative execution is thus recorded as a delta on the balance of the resource, which is
+//! applied to the balance committed so far instead of being checked for conflicts.
+//!
+//! Transactions publishing modules are left out of the runs: the modules loaded by the VM are
+//! cached across the transactions of a block, so they could not be read speculatively.
+
+use crate::{
+    counters::SPECULATIVE_TRANSACTIONS, data_cache::StateViewCache,
+    libra_transaction_executor::PreprocessedTransaction, logging::AdapterLogSchema,
+    system_module_names::TRANSACTION_FEE_NAME, LibraVM, VMExecutor,
+};
+use fail::fail_point;
+use libra_infallible::Mutex;
+use libra_state_view::{StateView, StateViewId};
+use libra_types::{
+    access_path::AccessPath,
+    account_config,
+    transaction::{
+        SignatureCheckedTransaction, Transaction, TransactionOutput, TransactionPayload,
+    },
+    vm_status::{StatusCode, VMStatus},
+    write_set::{WriteOp, WriteSet, WriteSetMut},
+};
+use move_core_types::language_storage::StructTag;
+use move_vm_types::gas_profile::GasProfile;
+use rayon::prelude::*;
+use std::collections::HashSet;
+
+/// A VM executing the user transactions of a block in parallel, with the same outputs as `LibraVM`.
+pub struct ParallelLibraVM;
+
+impl ParallelLibraVM {
+    /// Alternate form of 'execute_block' that keeps the vm_status before it goes into the
+    /// `TransactionOutput`
+    pub fn execute_block_and_keep_vm_status(
+        transactions: Vec<Transaction>,
+        state_view: &dyn StateView,
+    ) -> Result<Vec<(VMStatus, TransactionOutput)>, VMStatus> {
+        let mut state_view_cache = StateViewCache::new(state_view);
+        let mut vm = LibraVM::new(&state_view_cache);
+        let output = vm.execute_block_impl(transactions, &mut state_view_cache, false, true)?;
+        Ok(output
+            .into_iter()
+            .map(|(vm_status, txn_output, _gas_profile)| (vm_status, txn_output))
+            .collect())
+    }
+}
+
+impl VMExecutor for ParallelLibraVM {
+    /// Execute a block of `transactions`, like `LibraVM::execute_block`.
+    fn execute_block(
+        transactions: Vec<Transaction>,
+        state_view: &dyn StateView,
+    ) -> Result<Vec<TransactionOutput>, VMStatus> {
+        fail_point!("move_adapter::execute_block", |_| {
+            Err(VMStatus::Error(
+                StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR,
+            ))
+        });
+
+        let output = Self::execute_block_and_keep_vm_status(transactions, state_view)?;
+        Ok(output
+            .into_iter()
+            .map(|(_vm_status, txn_output)| txn_output)
+            .collect())
+    }
+}
+
+/// The output of a transaction executed speculatively.
+struct SpeculativeOutput {
+    /// The access paths read by the execution.
+    reads: HashSet<AccessPath>,
+    /// The access path of the `TransactionFee` resource the fee was deposited in, and the amount
+    /// deposited.
+    fee_delta: Option<(AccessPath, u64)>,
+    vm_status: VMStatus,
+    output: TransactionOutput,
+    gas_profile: Option<GasProfile>,
+}
+
+/// The outputs of a run of user transactions of a block executed speculatively, and the access
+/// paths written by the transactions of the run committed so far.
+pub(crate) struct SpeculativeRun {
+    /// Index in the block of the first transaction of the run.
+    start: usize,
+    /// The outputs of the transactions of the run not committed yet.
+    outputs: Vec<Option<SpeculativeOutput>>,
+    written: HashSet<AccessPath>,
+}
+
+impl SpeculativeRun {
+    /// Execute in parallel the user transactions running scripts in `block`, from the one at
+    /// index `start` to the next transaction of another kind, against `data_cache`.
+    pub(crate) fn execute(
+        vm: &LibraVM,
+        data_cache: &StateViewCache,
+        block: &[Result<PreprocessedTransaction, VMStatus>],
+        start: usize,
+        profile_gas: bool,
+    ) -> Self {
+        let transactions = block[start..]
+            .iter()
+            .take_while(|txn| speculative_transaction(txn).is_some())
+            .filter_map(speculative_transaction)
+            .collect::<Vec<_>>();
+        let outputs = transactions
+            .into_par_iter()
+            .enumerate()
+            .map(|(offset, txn)| {
+                let log_context = AdapterLogSchema::new(data_cache.id(), start + offset);
+                let recorder = ReadSetRecorder::new(data_cache);
+                let mut gas_profile = if profile_gas {
+                    Some(GasProfile::new())
+                } else {
+                    None
+                };
+                let (vm_status, output) = vm.execute_user_transaction(
+                    &StateViewCache::new(&recorder),
+                    txn,
+                    gas_profile.as_mut(),
+                    &log_context,
+                );
+                let fee_delta = fee_delta(data_cache, txn, &output);
+                Some(SpeculativeOutput {
+                    reads: recorder.into_reads(),
+                    fee_delta,
+                    vm_status,
+                    output,
+                    gas_profile,
+                })
+            })
+            .collect();
+        Self {
+            start,
+            outputs,
+            written: HashSet::new(),
+        }
+    }
+
+    /// Whether the transaction at index `idx` in the block belongs to the run.
+    pub(crate) fn contains(&self, idx: usize) -> bool {
+        self.start <= idx && idx < self.start + self.outputs.len()
+    }
+
+    /// Take the output of the transaction at index `idx` in the block if it belongs to the run and
+    /// its speculative execution is still valid, i.e. it did not read any access path written
+    /// since the start of the run, other than the `TransactionFee` resource it deposited its fee
+    /// in. The fee is then deposited in the balance of the resource in `data_cache`.
+    pub(crate) fn take_output(
+        &mut self,
+        idx: usize,
+        data_cache: &StateViewCache,
+    ) -> Option<(VMStatus, TransactionOutput, Option<GasProfile>)> {
+        if !self.contains(idx) {
+            return None;
+        }
+        let output = self.outputs[idx - self.start].take()?;
+        let fee_path = output.fee_delta.as_ref().map(|(ap, _)| ap);
+        let valid = output
+            .reads
+            .iter()
+            .all(|ap| Some(ap) == fee_path || !self.written.contains(ap));
+        let txn_output = if valid {
+            match &output.fee_delta {
+                Some((ap, delta)) => apply_fee_delta(data_cache, output.output, ap, *delta),
+                None => Some(output.output),
+            }
+        } else {
+            None
+        };
+        match txn_output {
+            Some(txn_output) => {
+                SPECULATIVE_TRANSACTIONS.with_label_values(&["valid"]).inc();
+                Some((output.vm_status, txn_output, output.gas_profile))
+            }
+            None => {
+                SPECULATIVE_TRANSACTIONS
+                    .with_label_values(&["conflict"])
+                    .inc();
+                None
+            }
+        }
+    }
+
+    /// Record the write set of a transaction of the run being committed.
+    pub(crate) fn record_write_set(&mut self, write_set: &WriteSet) {
+        self.written
+            .extend(write_set.iter().map(|(ap, _)| ap.clone()));
+    }
+}
+
+/// The transaction `txn` if it can be executed speculatively.
+fn speculative_transaction(
+    txn: &Result<PreprocessedTransaction, VMStatus>,
+) -> Option<&SignatureCheckedTransaction> {
+    match txn {
+        Ok(PreprocessedTransaction::UserTransaction(txn))
+            if matches!(txn.payload(), TransactionPayload::Script(_)) =>
+        {
+            Some(txn.as_ref())
+        }
+        _ => None,
+    }
+}
+
+/// The access path of the `TransactionFee` resource of the gas currency of `txn`.
+fn fee_access_path(txn: &SignatureCheckedTransaction) -> Option<AccessPath> {
+    let currency_code = account_config::from_currency_code_string(txn.gas_currency_code()).ok()?;
+    let tag = StructTag {
+        address: account_config::CORE_CODE_ADDRESS,
+        module: TRANSACTION_FEE_NAME.clone(),
+        name: TRANSACTION_FEE_NAME.clone(),
+        type_params: vec![account_config::type_tag_for_currency_code(currency_code)],
+    };
+    Some(AccessPath::new(
+        account_config::treasury_compliance_account_address(),
+        AccessPath::resource_access_vec(&tag),
+    ))
+}
+
+/// The balance and the preburned amount of a serialized `TransactionFee` resource.
+fn fee_balances(blob: &[u8]) -> Option<(u64, u64)> {
+    lcs::from_bytes(blob).ok()
+}
+
+/// The access path of the `TransactionFee` resource `txn` deposited its fee in and the amount of
+/// the fee, if the only change `output` makes to the resource is such a deposit. Transactions sent
+/// by the treasury compliance account may burn the fees, so their changes are never recorded as
+/// deltas.
+fn fee_delta(
+    data_cache: &StateViewCache,
+    txn: &SignatureCheckedTransaction,
+    output: &TransactionOutput,
+) -> Option<(AccessPath, u64)> {
+    if txn.sender() == account_config::treasury_compliance_account_address()
+        || output.status().is_discarded()
+    {
+        return None;
+    }
+    let ap = fee_access_path(txn)?;
+    let written = output.write_set().iter().find_map(|(path, op)| match op {
+        WriteOp::Value(blob) if path == &ap => Some(blob),
+        _ => None,
+    })?;
+    let (balance, preburn) = fee_balances(&data_cache.get(&ap).ok()??)?;
+    let (new_balance, new_preburn) = fee_balances(written)?;
+    if new_preburn != preburn || new_balance < balance {
+        return None;
+    }
+    Some((ap, new_balance - balance))
+}
+
+/// Rewrite the write of `output` to the `TransactionFee` resource at `ap` as the deposit of
+/// `delta` in the resource in `data_cache`.
+fn apply_fee_delta(
+    data_cache: &StateViewCache,
+    output: TransactionOutput,
+    ap: &AccessPath,
+    delta: u64,
+) -> Option<TransactionOutput> {
+    let (balance, preburn) = fee_balances(&data_cache.get(ap).ok()??)?;
+    let blob = lcs::to_bytes(&(balance.checked_add(delta)?, preburn)).ok()?;
+    let write_set = output
+        .write_set()
+        .iter()
+        .map(|(path, op)| {
+            if path == ap {
+                (path.clone(), WriteOp::Value(blob.clone()))
+            } else {
+                (path.clone(), op.clone())
+            }
+        })
+        .collect::<Vec<_>>();
+    Some(TransactionOutput::new(
+        WriteSetMut::new(write_set).freeze().ok()?,
+        output.events().to_vec(),
+        output.gas_used(),
+        output.status().clone(),
+    ))
+}
+
+/// A `StateView` recording the access paths read through it.
+struct ReadSetRecorder<'a> {
+    data_view: &'a dyn StateView,
+    reads: Mutex<HashSet<AccessPath>>,
+}
+
+impl<'a> ReadSetRecorder<'a> {
+    fn new(data_view: &'a dyn StateView) -> Self {
+        Self {
+            data_view,
+            reads: Mutex::new(HashSet::new()),
+        }
+    }
+
+    fn into_reads(self) -> HashSet<AccessPath> {
+        self.reads.into_inner()
+    }
+}
+
+impl<'a> StateView for ReadSetRecorder<'a> {
+    fn get(&self, access_path: &AccessPath) -> anyhow::Result<Option<Vec<u8>>> {
+        self.reads.lock().insert(access_path.clone());
+        self.data_view.get(access_path)
+    }
+
+    fn multi_get(&self, access_paths: &[AccessPath]) -> anyhow::Result<Vec<Option<Vec<u8>>>> {
+        self.reads.lock().extend(access_paths.iter().cloned());
+        self.data_view.multi_get(access_paths)
+    }
+
+    fn is_genesis(&self) -> bool {
+        self.data_view.is_genesis()
+    }
+
+    fn id(&self) -> StateViewId {
+        self.data_view.id()
+    }
+}
@@ -27,5 +27,7 @@ pub static WRITESET_EPILOGUE_NAME: Lazy<Identifier> =
     Lazy::new(|| Identifier::new("writeset_epilogue").unwrap());
 pub static USER_EPILOGUE_NAME: Lazy<Identifier> =
     Lazy::new(|| Identifier::new("epilogue").unwrap());
+pub static TRANSACTION_FEE_NAME: Lazy<Identifier> =
+    Lazy::new(|| Identifier::new("TransactionFee").unwrap());
 pub static BLOCK_PROLOGUE: Lazy<Identifier> =
     Lazy::new(|| Identifier::new("block_prologue").unwrap());
@@ -552,6 +552,8 @@ pub enum StatusCode {
     // The sender is trying to publish a module named `M`, but the sender's account already
     // contains a module with this name.
     DUPLICATE_MODULE_NAME = 1095,
+    // A module declares itself as a friend.
+    INVALID_FRIEND_DECL = 1096,
 
     // These are errors that the VM might raise if a violation of internal
     // invariants takes place.
@@ -85,6 +85,8 @@ pub struct ModuleDefinition {
     pub name: ModuleName,
     /// the module's dependencies
     pub imports: Vec<ImportDefinition>,
+    /// the modules allowed to call the `public(friend)` procedures of the module
+    pub friends: Vec<QualifiedModuleIdent>,
     /// Explicit declaration of dependencies. If not provided, will be inferred based on given
     /// dependencies to the IR compiler
     pub explicit_dependency_declarations: Vec<ModuleDependency>,
@@ -312,12 +314,15 @@ pub struct FunctionDependency {
     pub signature: FunctionSignature,
 }
 
-/// Public or internal modifier for a procedure
+/// Public, friend or internal modifier for a procedure
 #[derive(PartialEq, Debug, Clone)]
 pub enum FunctionVisibility {
     /// The procedure can be invoked anywhere
     /// `public`
     Public,
+    /// The procedure can be invoked internally and by the friends of the module
+    /// `public(friend)`
+    Friend,
     /// The procedure can be invoked only internally
     /// `<no modifier>`
     Internal,
@@ -801,12 +806,13 @@ impl ModuleIdent {
 }
 
 impl ModuleDefinition {
-    /// Creates a new `ModuleDefinition` from its string name, dependencies, structs+resources,
-    /// and procedures
+    /// Creates a new `ModuleDefinition` from its string name, dependencies, friends,
+    /// structs+resources, and procedures
     /// Does not verify the correctness of any internal properties of its elements
     pub fn new(
         name: impl ToString,
         imports: Vec<ImportDefinition>,
+        friends: Vec<QualifiedModuleIdent>,
         explicit_dependency_declarations: Vec<ModuleDependency>,
         structs: Vec<StructDefinition>,
         constants: Vec<Constant>,
@@ -816,6 +822,7 @@ impl ModuleDefinition {
         Ok(ModuleDefinition {
             name: ModuleName::new(name.to_string()),
             imports,
+            friends,
             explicit_dependency_declarations,
             structs,
             constants,
@@ -1352,6 +1359,12 @@ impl fmt::Display for ModuleDefinition {
         }
         writeln!(f, ")")?;
 
+        writeln!(f, "Friends(")?;
+        for friend in &self.friends {
+            writeln!(f, "{};", friend)?;
+        }
+        writeln!(f, ")")?;
+
         writeln!(f, "Dependency(")?;
         for dependency in &self.explicit_dependency_declarations {
             writeln!(f, "{},", dependency)?;
@@ -126,6 +126,7 @@ fn module(
 ) -> (ModuleIdent, G::ModuleDefinition) {
     let is_source_module = mdef.is_source_module;
     let dependency_order = mdef.dependency_order;
+    let friends = mdef.friends;
     let structs = mdef.structs;
     let constants = mdef.constants.map(|name, c| constant(context, name, c));
     let functions = mdef.functions.map(|name, f| function(context, name, f));
@@ -134,6 +135,7 @@ fn module(
         G::ModuleDefinition {
             is_source_module,
             dependency_order,
+            friends,
             structs,
             constants,
             functions,
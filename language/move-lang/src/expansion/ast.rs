@@ -46,6 +46,9 @@ pub struct Script {
 pub struct ModuleDefinition {
     pub loc: Loc,
     pub is_source_module: bool,
+    /// The modules allowed to call the `public(friend)` functions, with the location of their
+    /// declaration
+    pub friends: BTreeMap<ModuleIdent, Loc>,
     pub structs: UniqueMap<StructName, StructDefinition>,
     pub functions: UniqueMap<FunctionName, Function>,
     pub constants: UniqueMap<ConstantName, Constant>,
@@ -397,6 +400,7 @@ impl AstDebug for ModuleDefinition {
         let ModuleDefinition {
             loc: _loc,
             is_source_module,
+            friends,
             structs,
             functions,
             constants,
@@ -407,6 +411,9 @@ impl AstDebug for ModuleDefinition {
         } else {
             "library module"
         });
+        for friend in friends.keys() {
+            w.writeln(&format!("friend {};", friend));
+        }
         for sdef in structs {
             sdef.ast_debug(w);
             w.new_line();
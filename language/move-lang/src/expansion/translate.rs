@@ -242,6 +242,7 @@ fn module_(context: &mut Context, mdef: P::ModuleDefinition) -> (ModuleIdent, E:
         "ICE there should be no aliases entering a module"
     );
 
+    let mut friends = BTreeMap::new();
     let mut functions = UniqueMap::new();
     let mut constants = UniqueMap::new();
     let mut structs = UniqueMap::new();
@@ -249,6 +250,7 @@ fn module_(context: &mut Context, mdef: P::ModuleDefinition) -> (ModuleIdent, E:
     for member in members {
         match member {
             P::ModuleMember::Use(_) => unreachable!(),
+            P::ModuleMember::Friend(f) => friend(context, &mut friends, &current_module, f),
             P::ModuleMember::Function(mut f) => {
                 if !context.is_source_module {
                     f.body.value = P::FunctionBody_::Native
@@ -270,6 +272,7 @@ fn module_(context: &mut Context, mdef: P::ModuleDefinition) -> (ModuleIdent, E:
     let def = E::ModuleDefinition {
         loc,
         is_source_module: context.is_source_module,
+        friends,
         structs,
         constants,
         functions,
@@ -309,9 +312,16 @@ fn script_(context: &mut Context, pscript: P::Script) -> E::Script {
     }
 
     let (function_name, function) = function_(context, pfunction);
-    if let FunctionVisibility::Public(loc) = &function.visibility {
-        let msg = "Extraneous 'public' modifier. Script functions are always public";
-        context.error(vec![(*loc, msg)]);
+    match &function.visibility {
+        FunctionVisibility::Public(loc) => {
+            let msg = "Extraneous 'public' modifier. Script functions are always public";
+            context.error(vec![(*loc, msg)]);
+        }
+        FunctionVisibility::Friend(loc) => {
+            let msg = "Invalid 'public(friend)' modifier. Script functions are always public";
+            context.error(vec![(*loc, msg)]);
+        }
+        FunctionVisibility::Internal => (),
     }
     match &function.body {
         sp!(_, E::FunctionBody_::Defined(_)) => (),
@@ -390,7 +400,7 @@ fn module_members(
                 }
                 _ => (),
             },
-            P::ModuleMember::Use(_) => (),
+            P::ModuleMember::Use(_) | P::ModuleMember::Friend(_) => (),
         };
     }
     members.add(mident, cur_members).unwrap();
@@ -426,6 +436,7 @@ fn aliases_from_member(
             use_(context, acc, u);
             None
         }
+        P::ModuleMember::Friend(f) => Some(P::ModuleMember::Friend(f)),
         P::ModuleMember::Function(f) => {
             let n = f.name.0.clone();
             check_name_and_add_implicit_alias!(ModuleMemberKind::Function, n);
@@ -576,6 +587,33 @@ fn unused_alias(context: &mut Context, alias: Name) {
     )])
 }
 
+//**************************************************************************************************
+// Friends
+//**************************************************************************************************
+
+fn friend(
+    context: &mut Context,
+    friends: &mut BTreeMap<ModuleIdent, Loc>,
+    current_module: &ModuleIdent,
+    pfriend: P::Friend,
+) {
+    let P::Friend { loc, module } = pfriend;
+    if &module == current_module {
+        let msg = "Invalid friend declaration. A module cannot be a friend of itself";
+        context.error(vec![(loc, msg)]);
+        return;
+    }
+    if let Some(old_loc) = friends.get(&module) {
+        let msg = format!("Duplicate friend declaration '{}'", module);
+        context.error(vec![
+            (loc, msg),
+            (*old_loc, "Previously declared here".into()),
+        ]);
+        return;
+    }
+    friends.insert(module, loc);
+}
+
 //**************************************************************************************************
 // Structs
 //**************************************************************************************************
@@ -694,6 +732,8 @@ fn function(
 
 fn function_(context: &mut Context, pfunction: P::Function) -> (FunctionName, E::Function) {
     let P::Function {
+        // attributes only matter when constructing unit test plans
+        attributes: _,
         loc,
         name,
         visibility,
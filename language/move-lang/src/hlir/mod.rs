@@ -3,3 +3,5 @@
 
 pub mod ast;
 pub(crate) mod translate;
+
+pub use translate::{display_var, DisplayVar};
@@ -176,6 +176,7 @@ fn module(
 ) -> (ModuleIdent, H::ModuleDefinition) {
     let is_source_module = mdef.is_source_module;
     let dependency_order = mdef.dependency_order;
+    let friends = mdef.friends;
 
     let structs = mdef.structs.map(|name, s| struct_def(context, name, s));
     context.add_struct_fields(&structs);
@@ -189,6 +190,7 @@ fn module(
         H::ModuleDefinition {
             is_source_module,
             dependency_order,
+            friends,
             structs,
             constants,
             functions,
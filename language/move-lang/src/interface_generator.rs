@@ -8,7 +8,7 @@ use move_vm::{
     access::ModuleAccess,
     file_format::{
         CompiledModule, FunctionDefinition, Kind, SignatureToken, StructDefinition,
-        StructFieldInformation, StructHandleIndex, TypeParameterIndex,
+        StructFieldInformation, StructHandleIndex, TypeParameterIndex, Visibility,
     },
 };
 use std::{collections::BTreeMap, fs};
@@ -48,6 +48,22 @@ pub fn write_to_string(compiled_module_file_input_path: &str) -> Result<(ModuleI
     push_line!(out, format!("module {} {{", id.name()));
     push_line!(out, "");
 
+    let friend_decls = module.friend_decls();
+    for friend in friend_decls {
+        let friend_id = module.module_id_for_handle(friend);
+        push_line!(
+            out,
+            format!(
+                "    friend {}::{};",
+                Address::new(friend_id.address().to_u8()),
+                friend_id.name()
+            )
+        );
+    }
+    if !friend_decls.is_empty() {
+        push_line!(out, "");
+    }
+
     let mut context = Context::new(&module);
     let mut members = vec![];
     for sdef in module.struct_defs() {
@@ -57,16 +73,16 @@ pub fn write_to_string(compiled_module_file_input_path: &str) -> Result<(ModuleI
         members.push("".to_string());
     }
 
-    let mut public_funs = module
+    let mut callable_funs = module
         .function_defs()
         .iter()
-        .filter(|fdef| fdef.is_public)
+        .filter(|fdef| fdef.visibility != Visibility::Private)
         .peekable();
-    if public_funs.peek().is_some() {
+    if callable_funs.peek().is_some() {
         members.push(format!("    {}", DISCLAIMER));
     }
-    for public_fdef in public_funs {
-        members.push(write_function_def(&mut context, public_fdef));
+    for callable_fdef in callable_funs {
+        members.push(write_function_def(&mut context, callable_fdef));
     }
     if !members.is_empty() {
         members.push("".to_string());
@@ -172,8 +188,14 @@ fn write_function_def(ctx: &mut Context, fdef: &FunctionDefinition) -> String {
     let fhandle = ctx.module.function_handle_at(fdef.function);
     let parameters = &ctx.module.signature_at(fhandle.parameters).0;
     let return_ = &ctx.module.signature_at(fhandle.return_).0;
+    let visibility = if fdef.is_friend() {
+        "public(friend)"
+    } else {
+        "public"
+    };
     format!(
-        "    native public fun {}{}({}){};",
+        "    native {} fun {}{}({}){};",
+        visibility,
         ctx.module.identifier_at(fhandle.name),
         write_type_paramters(&fhandle.type_parameters),
         write_parameters(ctx, parameters),
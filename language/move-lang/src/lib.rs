@@ -20,12 +20,14 @@ pub mod shared;
 pub mod test_utils;
 mod to_bytecode;
 pub mod typing;
+pub mod unit_test;
 
 use anyhow::anyhow;
 use codespan::{ByteIndex, Span};
 use compiled_unit::CompiledUnit;
 use errors::*;
 use move_ir_types::location::*;
+use once_cell::sync::Lazy;
 use parser::syntax::parse_file_string;
 use shared::Address;
 use std::{
@@ -36,6 +38,7 @@ use std::{
     iter::Peekable,
     path::{Path, PathBuf},
     str::Chars,
+    sync::Mutex,
 };
 use tempfile::NamedTempFile;
 
@@ -75,7 +78,7 @@ pub fn move_check_no_report(
 ) -> anyhow::Result<(FilesSourceText, Errors)> {
     let mut deps = deps.to_vec();
     generate_interface_files_for_deps(&mut deps, interface_files_dir_opt)?;
-    let (files, pprog_and_comments_res) = parse_program(targets, &deps)?;
+    let (files, pprog_and_comments_res) = parse_program(targets, &deps, false)?;
     let pprog_res = pprog_and_comments_res.map(|(pprog, _)| pprog);
     match check_program(pprog_res, sender_opt) {
         Err(errors) => Ok((files, errors)),
@@ -111,7 +114,7 @@ pub fn move_compile_no_report(
 ) -> anyhow::Result<(FilesSourceText, Result<Vec<CompiledUnit>, Errors>)> {
     let mut deps = deps.to_vec();
     generate_interface_files_for_deps(&mut deps, interface_files_dir_opt)?;
-    let (files, pprog_and_comments_res) = parse_program(targets, &deps)?;
+    let (files, pprog_and_comments_res) = parse_program(targets, &deps, false)?;
     let pprog_res = pprog_and_comments_res.map(|(pprog, _)| pprog);
     Ok(match compile_program(pprog_res, sender_opt) {
         Err(errors) => (files, Err(errors)),
@@ -119,6 +122,75 @@ pub fn move_compile_no_report(
     })
 }
 
+/// Given a set of targets and a set of dependencies
+/// - Collects the unit tests declared in the targets
+/// - Compiles both the targets and the dependencies to Move bytecode, as running the tests needs
+///   the code of the dependencies too
+/// Returns the errors instead of reporting them to stderr
+pub fn move_compile_unit_tests(
+    targets: &[String],
+    deps: &[String],
+    sender_opt: Option<Address>,
+) -> anyhow::Result<(
+    FilesSourceText,
+    Result<(Vec<CompiledUnit>, unit_test::TestPlan), Errors>,
+)> {
+    let (files, pprog_and_comments_res) = parse_program(targets, deps, true)?;
+    let res = pprog_and_comments_res.and_then(|(pprog, _)| {
+        let test_plan = unit_test::construct_test_plan(&pprog.source_definitions, sender_opt)?;
+        let parser::ast::Program {
+            mut source_definitions,
+            lib_definitions,
+        } = pprog;
+        source_definitions.extend(lib_definitions);
+        let pprog = parser::ast::Program {
+            source_definitions,
+            lib_definitions: vec![],
+        };
+        let units = compile_program(Ok(pprog), sender_opt)?;
+        Ok((units, test_plan))
+    });
+    Ok((files, res))
+}
+
+/// Move check up to and including typing, for tools such as language servers that inspect the
+/// typed program rather than compile it. Files with an entry in `overlay` are read from there
+/// instead of from the file system, so that unsaved changes can be checked.
+/// Unlike `move_check_no_report`, the typed program is returned along with any errors, as long as
+/// every file parses.
+pub fn move_check_to_typing_with_overlay(
+    targets: &[String],
+    deps: &[String],
+    sender_opt: Option<Address>,
+    overlay: &HashMap<String, String>,
+) -> anyhow::Result<(FilesSourceText, Option<typing::ast::Program>, Errors)> {
+    let (files, pprog_and_comments_res) =
+        parse_program_with_overlay(targets, deps, overlay, false)?;
+    Ok(match pprog_and_comments_res {
+        Err(errors) => (files, None, errors),
+        Ok((pprog, _)) => {
+            let (tprog, errors) = check_program_to_typing(pprog, sender_opt);
+            (files, Some(tprog), errors)
+        }
+    })
+}
+
+/// Move check up to and including typing of a program parsed with `parse_file_contents`, for
+/// tools that keep the parsed files around and only parse the files that changed again. Like
+/// `move_check_to_typing_with_overlay`, the typed program is returned along with any errors.
+pub fn move_check_parsed_to_typing(
+    pprog: parser::ast::Program,
+    sender_opt: Option<Address>,
+) -> (Option<typing::ast::Program>, Errors) {
+    match unit_test::filter_test_members(pprog, false) {
+        Err(errors) => (None, errors),
+        Ok(pprog) => {
+            let (tprog, errors) = check_program_to_typing(pprog, sender_opt);
+            (Some(tprog), errors)
+        }
+    }
+}
+
 /// Move compile up to expansion phase, returning errors instead of reporting them to stderr.
 ///
 /// This also returns a map containing documentation comments for each source in `targets`.
@@ -133,7 +205,7 @@ pub fn move_compile_to_expansion_no_report(
 )> {
     let mut deps = deps.to_vec();
     generate_interface_files_for_deps(&mut deps, interface_files_dir_opt)?;
-    let (files, pprog_and_comments_res) = parse_program(targets, &deps)?;
+    let (files, pprog_and_comments_res) = parse_program(targets, &deps, false)?;
     let res = pprog_and_comments_res.and_then(|(pprog, comment_map)| {
         let (eprog, errors) = expansion::translate::program(pprog, sender_opt);
         check_errors(errors)?;
@@ -352,9 +424,7 @@ fn check_program(
     prog: Result<parser::ast::Program, Errors>,
     sender_opt: Option<Address>,
 ) -> Result<cfgir::ast::Program, Errors> {
-    let (eprog, errors) = expansion::translate::program(prog?, sender_opt);
-    let (nprog, errors) = naming::translate::program(eprog, errors);
-    let (tprog, errors) = typing::translate::program(nprog, errors);
+    let (tprog, errors) = check_program_to_typing(prog?, sender_opt);
     check_errors(errors)?;
     let (hprog, errors) = hlir::translate::program(tprog);
     let (cprog, errors) = cfgir::translate::program(errors, hprog);
@@ -362,6 +432,15 @@ fn check_program(
     Ok(cprog)
 }
 
+fn check_program_to_typing(
+    prog: parser::ast::Program,
+    sender_opt: Option<Address>,
+) -> (typing::ast::Program, Errors) {
+    let (eprog, errors) = expansion::translate::program(prog, sender_opt);
+    let (nprog, errors) = naming::translate::program(eprog, errors);
+    typing::translate::program(nprog, errors)
+}
+
 fn compile_program(
     prog: Result<parser::ast::Program, Errors>,
     sender_opt: Option<Address>,
@@ -374,20 +453,35 @@ fn compile_program(
 // Parsing
 //**************************************************************************************************
 
+/// Parses the targets and dependencies. Unless `keep_tests` is set, i.e. outside of unit test mode,
+/// the functions annotated with `#[test]` are removed from the program.
 fn parse_program(
     targets: &[String],
     deps: &[String],
+    keep_tests: bool,
+) -> anyhow::Result<(
+    FilesSourceText,
+    Result<(parser::ast::Program, CommentMap), Errors>,
+)> {
+    parse_program_with_overlay(targets, deps, &HashMap::new(), keep_tests)
+}
+
+fn parse_program_with_overlay(
+    targets: &[String],
+    deps: &[String],
+    overlay: &HashMap<String, String>,
+    keep_tests: bool,
 ) -> anyhow::Result<(
     FilesSourceText,
     Result<(parser::ast::Program, CommentMap), Errors>,
 )> {
     let targets = find_move_filenames(targets, true)?
         .iter()
-        .map(|s| leak_str(s))
+        .map(|s| intern_file_name(s))
         .collect::<Vec<&'static str>>();
     let deps = find_move_filenames(deps, true)?
         .iter()
-        .map(|s| leak_str(s))
+        .map(|s| intern_file_name(s))
         .collect::<Vec<&'static str>>();
     check_targets_deps_dont_intersect(&targets, &deps)?;
     let mut files: FilesSourceText = HashMap::new();
@@ -397,26 +491,24 @@ fn parse_program(
     let mut errors: Errors = Vec::new();
 
     for fname in targets {
-        let (defs, comments, mut es) = parse_file(&mut files, fname)?;
+        let (defs, comments, mut es) = parse_file(&mut files, fname, overlay)?;
         source_definitions.extend(defs);
         source_comments.insert(fname, comments);
         errors.append(&mut es);
     }
 
     for fname in deps {
-        let (defs, _, mut es) = parse_file(&mut files, fname)?;
+        let (defs, _, mut es) = parse_file(&mut files, fname, overlay)?;
         lib_definitions.extend(defs);
         errors.append(&mut es);
     }
 
     let res = if errors.is_empty() {
-        Ok((
-            parser::ast::Program {
-                source_definitions,
-                lib_definitions,
-            },
-            source_comments,
-        ))
+        let pprog = parser::ast::Program {
+            source_definitions,
+            lib_definitions,
+        };
+        unit_test::filter_test_members(pprog, keep_tests).map(|pprog| (pprog, source_comments))
     } else {
         Err(errors)
     };
@@ -501,29 +593,39 @@ pub fn find_filenames<Predicate: FnMut(&Path) -> bool>(
     Ok(result)
 }
 
-// TODO replace with some sort of intern table
-fn leak_str(s: &str) -> &'static str {
-    Box::leak(Box::new(s.to_owned()))
+/// Interns the file name `s`. Each file name is only leaked once, however many times it is
+/// compiled, which matters for long running tools such as language servers.
+pub fn intern_file_name(s: &str) -> &'static str {
+    static FILE_NAMES: Lazy<Mutex<BTreeSet<&'static str>>> =
+        Lazy::new(|| Mutex::new(BTreeSet::new()));
+    let mut file_names = FILE_NAMES.lock().unwrap();
+    match file_names.get(s) {
+        Some(fname) => fname,
+        None => {
+            let fname: &'static str = Box::leak(Box::new(s.to_owned()));
+            file_names.insert(fname);
+            fname
+        }
+    }
 }
 
 fn parse_file(
     files: &mut FilesSourceText,
     fname: &'static str,
+    overlay: &HashMap<String, String>,
 ) -> anyhow::Result<(Vec<parser::ast::Definition>, MatchedFileCommentMap, Errors)> {
     let mut errors: Errors = Vec::new();
-    let mut f = File::open(fname)
-        .map_err(|err| std::io::Error::new(err.kind(), format!("{}: {}", err, fname)))?;
-    let mut source_buffer = String::new();
-    f.read_to_string(&mut source_buffer)?;
-    let (no_comments_buffer, comment_map) = match strip_comments_and_verify(fname, &source_buffer) {
-        Err(errs) => {
-            errors.extend(errs.into_iter());
-            files.insert(fname, source_buffer);
-            return Ok((vec![], MatchedFileCommentMap::new(), errors));
+    let source_buffer = match overlay.get(fname) {
+        Some(contents) => contents.clone(),
+        None => {
+            let mut f = File::open(fname)
+                .map_err(|err| std::io::Error::new(err.kind(), format!("{}: {}", err, fname)))?;
+            let mut source_buffer = String::new();
+            f.read_to_string(&mut source_buffer)?;
+            source_buffer
         }
-        Ok(result) => result,
     };
-    let (defs, comments) = match parse_file_string(fname, &no_comments_buffer, comment_map) {
+    let (defs, comments) = match parse_file_contents(fname, &source_buffer) {
         Ok(defs_and_comments) => defs_and_comments,
         Err(errs) => {
             errors.extend(errs);
@@ -534,6 +636,16 @@ fn parse_file(
     Ok((defs, comments, errors))
 }
 
+/// Parses `contents`, the contents of the file `fname`, on its own. Returns the definitions in the
+/// file along with its documentation comments.
+pub fn parse_file_contents(
+    fname: &'static str,
+    contents: &str,
+) -> Result<(Vec<parser::ast::Definition>, MatchedFileCommentMap), Errors> {
+    let (no_comments_buffer, comment_map) = strip_comments_and_verify(fname, contents)?;
+    parse_file_string(fname, &no_comments_buffer, comment_map)
+}
+
 //**************************************************************************************************
 // Comments
 //**************************************************************************************************
@@ -763,7 +875,7 @@ fn strip_comments(fname: &'static str, input: &str) -> Result<(String, FileComme
 
 // We restrict strings to only ascii visual characters (0x20 <= c <= 0x7E) or a permitted newline
 // character--\n--or a tab--\t.
-fn strip_comments_and_verify(
+pub fn strip_comments_and_verify(
     fname: &'static str,
     string: &str,
 ) -> Result<(String, FileCommentMap), Errors> {
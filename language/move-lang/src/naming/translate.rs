@@ -311,6 +311,7 @@ fn module(
 ) -> N::ModuleDefinition {
     context.current_module = Some(ident);
     let is_source_module = mdef.is_source_module;
+    let friends = mdef.friends;
     let unscoped = context.save_unscoped();
     let structs = mdef.structs.map(|name, s| {
         context.restore_unscoped(unscoped.clone());
@@ -328,6 +329,7 @@ fn module(
     N::ModuleDefinition {
         is_source_module,
         dependency_order: 0,
+        friends,
         structs,
         functions,
         constants,
@@ -18,6 +18,7 @@ pub enum Tok {
     IdentifierValue,
     Exclaim,
     ExclaimEqual,
+    NumSign,
     Percent,
     Amp,
     AmpAmp,
@@ -78,6 +79,7 @@ pub enum Tok {
     Fun,
     Script,
     Const,
+    Friend,
 }
 
 impl fmt::Display for Tok {
@@ -94,6 +96,7 @@ impl fmt::Display for Tok {
             IdentifierValue => "[Identifier]",
             Exclaim => "!",
             ExclaimEqual => "!=",
+            NumSign => "#",
             Percent => "%",
             Amp => "&",
             AmpAmp => "&&",
@@ -154,6 +157,7 @@ impl fmt::Display for Tok {
             Fun => "fun",
             Script => "script",
             Const => "const",
+            Friend => "friend",
         };
         fmt::Display::fmt(s, formatter)
     }
@@ -392,6 +396,7 @@ fn find_token(file: &'static str, text: &str, start_offset: usize) -> Result<(To
                 (Tok::Colon, 1)
             }
         }
+        '#' => (Tok::NumSign, 1),
         '%' => (Tok::Percent, 1),
         '(' => (Tok::LParen, 1),
         ')' => (Tok::RParen, 1),
@@ -495,6 +500,7 @@ fn get_name_token(name: &str) -> Tok {
         "define" => Tok::Define,
         "else" => Tok::Else,
         "false" => Tok::False,
+        "friend" => Tok::Friend,
         "fun" => Tok::Fun,
         "if" => Tok::If,
         "invariant" => Tok::Invariant,
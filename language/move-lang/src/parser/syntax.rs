@@ -1274,6 +1274,74 @@ fn parse_optional_type_parameters<'input>(
     }
 }
 
+//**************************************************************************************************
+// Attributes
+//**************************************************************************************************
+
+// Parse the value of an attribute:
+//      AttributeValue = <Value> | <Num>
+fn parse_attribute_value<'input>(tokens: &mut Lexer<'input>) -> Result<AttributeValue, Error> {
+    let start_loc = tokens.start_loc();
+    let value_ = match tokens.peek() {
+        Tok::NumValue => AttributeValue_::InferredNum(parse_num(tokens)?),
+        Tok::AddressValue
+        | Tok::True
+        | Tok::False
+        | Tok::U8Value
+        | Tok::U64Value
+        | Tok::U128Value
+        | Tok::ByteStringValue => AttributeValue_::Value(parse_value(tokens)?),
+        _ => return Err(unexpected_token_error(tokens, "an attribute value")),
+    };
+    let loc = make_loc(tokens.file_name(), start_loc, tokens.previous_end_loc());
+    Ok(sp(loc, value_))
+}
+
+// Parse a single attribute:
+//      Attribute =
+//          <Identifier>
+//          | <Identifier> "=" <AttributeValue>
+//          | <Identifier> "(" Comma<Attribute> ")"
+fn parse_attribute<'input>(tokens: &mut Lexer<'input>) -> Result<Attribute, Error> {
+    let start_loc = tokens.start_loc();
+    let n = parse_identifier(tokens)?;
+    let attr_ = match tokens.peek() {
+        Tok::Equal => {
+            tokens.advance()?;
+            Attribute_::Assigned(n, Box::new(parse_attribute_value(tokens)?))
+        }
+        Tok::LParen => {
+            let args = parse_comma_list(
+                tokens,
+                Tok::LParen,
+                Tok::RParen,
+                parse_attribute,
+                "attribute",
+            )?;
+            Attribute_::Parameterized(n, args)
+        }
+        _ => Attribute_::Name(n),
+    };
+    let loc = make_loc(tokens.file_name(), start_loc, tokens.previous_end_loc());
+    Ok(sp(loc, attr_))
+}
+
+// Parse the attributes annotating a function, e.g. marking it as a unit test:
+//      Attributes = ("#" "[" Comma<Attribute> "]")*
+fn parse_attributes<'input>(tokens: &mut Lexer<'input>) -> Result<Vec<Attribute>, Error> {
+    let mut attributes = vec![];
+    while match_token(tokens, Tok::NumSign)? {
+        attributes.extend(parse_comma_list(
+            tokens,
+            Tok::LBracket,
+            Tok::RBracket,
+            parse_attribute,
+            "attribute",
+        )?);
+    }
+    Ok(attributes)
+}
+
 //**************************************************************************************************
 // Functions
 //**************************************************************************************************
@@ -1283,13 +1351,13 @@ fn parse_optional_type_parameters<'input>(
 //          <NativeFunctionDecl>
 //          | <MoveFunctionDecl>
 //      NativeFunctionDecl =
-//          <DocComments> "native" ( "public" )? "fun"
+//          <DocComments> <Attributes> "native" ( "public" )? "fun"
 //          <FunctionDefName> "(" Comma<Parameter> ")"
 //          (":" <Type>)?
 //          ("acquires" <ModuleAccess> ("," <ModuleAccess>)*)?
 //          ";"
 //      MoveFunctionDecl =
-//          <DocComments> ( "public" )? "fun"
+//          <DocComments> <Attributes> ( "public" )? "fun"
 //          <FunctionDefName> "(" Comma<Parameter> ")"
 //          (":" <Type>)?
 //          ("acquires" <ModuleAccess> ("," <ModuleAccess>)*)?
@@ -1305,6 +1373,7 @@ fn parse_function_decl<'input>(
 ) -> Result<Function, Error> {
     tokens.match_doc_comments();
     let start_loc = tokens.start_loc();
+    let attributes = parse_attributes(tokens)?;
     // Record the source location of the "native" keyword (if there is one).
     let native_opt = if allow_native {
         consume_optional_token_with_loc(tokens, Tok::Native)?
@@ -1319,10 +1388,22 @@ fn parse_function_decl<'input>(
         None
     };
 
-    // (<Public>)?
+    // (<Public> ("(" "friend" ")")?)?
+    let visibility_start_loc = tokens.start_loc();
     let public_opt = consume_optional_token_with_loc(tokens, Tok::Public)?;
     let visibility = if let Some(loc) = public_opt {
-        FunctionVisibility::Public(loc)
+        if match_token(tokens, Tok::LParen)? {
+            consume_token(tokens, Tok::Friend)?;
+            consume_token(tokens, Tok::RParen)?;
+            let loc = make_loc(
+                tokens.file_name(),
+                visibility_start_loc,
+                tokens.previous_end_loc(),
+            );
+            FunctionVisibility::Friend(loc)
+        } else {
+            FunctionVisibility::Public(loc)
+        }
     } else {
         FunctionVisibility::Internal
     };
@@ -1391,6 +1472,7 @@ fn parse_function_decl<'input>(
 
     let loc = make_loc(tokens.file_name(), start_loc, tokens.previous_end_loc());
     Ok(Function {
+        attributes,
         loc,
         visibility,
         signature,
@@ -1597,6 +1679,18 @@ fn parse_use_alias<'input>(tokens: &mut Lexer<'input>) -> Result<Option<Name>, E
     })
 }
 
+// Parse a friend declaration:
+//      FriendDecl =
+//          "friend" <ModuleIdent> ";"
+fn parse_friend_decl<'input>(tokens: &mut Lexer<'input>) -> Result<Friend, Error> {
+    let start_loc = tokens.start_loc();
+    consume_token(tokens, Tok::Friend)?;
+    let module = parse_module_ident(tokens)?;
+    consume_token(tokens, Tok::Semicolon)?;
+    let loc = make_loc(tokens.file_name(), start_loc, tokens.previous_end_loc());
+    Ok(Friend { loc, module })
+}
+
 // TODO rework parsing modifiers
 fn is_struct_definition<'input>(tokens: &mut Lexer<'input>) -> Result<bool, Error> {
     let mut t = tokens.peek();
@@ -1610,7 +1704,7 @@ fn is_struct_definition<'input>(tokens: &mut Lexer<'input>) -> Result<bool, Erro
 //      Module =
 //          <DocComments> "module" <ModuleName> "{"
 //              <UseDecl>*
-//              ( <ConstantDecl> | <StructDefinition> | <FunctionDecl> | <Spec> )*
+//              ( <FriendDecl> | <ConstantDecl> | <StructDefinition> | <FunctionDecl> | <Spec> )*
 //          "}"
 fn parse_module<'input>(tokens: &mut Lexer<'input>) -> Result<ModuleDefinition, Error> {
     tokens.match_doc_comments();
@@ -1625,6 +1719,7 @@ fn parse_module<'input>(tokens: &mut Lexer<'input>) -> Result<ModuleDefinition,
         members.push(match tokens.peek() {
             Tok::Spec => ModuleMember::Spec(parse_spec_block(tokens)?),
             Tok::Use => ModuleMember::Use(parse_use_decl(tokens)?),
+            Tok::Friend => ModuleMember::Friend(parse_friend_decl(tokens)?),
             Tok::Const => ModuleMember::Constant(parse_constant(tokens)?),
             // TODO rework parsing modifiers
             _ if is_struct_definition(tokens)? => {
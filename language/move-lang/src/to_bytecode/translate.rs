@@ -14,7 +14,7 @@ use crate::{
     naming::ast::{BuiltinTypeName_, TParam},
     parser::ast::{
         BinOp, BinOp_, ConstantName, Field, FunctionName, FunctionVisibility, Kind, Kind_,
-        ModuleIdent, StructName, UnaryOp, UnaryOp_, Var,
+        ModuleIdent, ModuleIdent_, StructName, UnaryOp, UnaryOp_, Var,
     },
     shared::{unique_map::UniqueMap, *},
 };
@@ -114,6 +114,7 @@ fn module(
     >,
 ) -> Result<CompiledUnit, Error> {
     let mut context = Context::new(Some(&ident));
+    let friends = mdef.friends.into_iter().map(|(m, _)| friend(m)).collect();
     let structs = mdef
         .structs
         .into_iter()
@@ -146,6 +147,7 @@ fn module(
     let ir_module = IR::ModuleDefinition {
         name: IR::ModuleName::new(mname.0.value),
         imports,
+        friends,
         explicit_dependency_declarations,
         structs,
         constants,
@@ -446,9 +448,18 @@ fn function(
     )
 }
 
+fn friend(m: ModuleIdent) -> IR::QualifiedModuleIdent {
+    let ModuleIdent_ { address, name } = m.0.value;
+    IR::QualifiedModuleIdent::new(
+        IR::ModuleName::new(name.0.value),
+        LibraAddress::new(address.to_u8()),
+    )
+}
+
 fn visibility(v: FunctionVisibility) -> IR::FunctionVisibility {
     match v {
         FunctionVisibility::Public(_) => IR::FunctionVisibility::Public,
+        FunctionVisibility::Friend(_) => IR::FunctionVisibility::Friend,
         FunctionVisibility::Internal => IR::FunctionVisibility::Internal,
     }
 }
@@ -51,6 +51,7 @@ pub struct ConstantInfo {
 }
 
 pub struct ModuleInfo {
+    pub friends: BTreeMap<ModuleIdent, Loc>,
     pub structs: UniqueMap<StructName, StructDefinition>,
     pub functions: UniqueMap<FunctionName, FunctionInfo>,
     pub constants: UniqueMap<ConstantName, ConstantInfo>,
@@ -89,6 +90,7 @@ impl Context {
                 signature: cdef.signature.clone(),
             });
             ModuleInfo {
+                friends: mdef.friends.clone(),
                 structs,
                 functions,
                 constants,
@@ -691,6 +693,10 @@ pub fn make_function_type(
         Some(current) => m == current,
         None => false,
     };
+    let in_friend_module = match &context.current_module {
+        Some(current) => context.module_info(m).friends.contains_key(current),
+        None => false,
+    };
     let constraints: Vec<_> = context
         .function_info(m, f)
         .signature
@@ -740,6 +746,17 @@ pub fn make_function_type(
                 (defined_loc, internal_msg.into()),
             ])
         }
+        FunctionVisibility::Friend(_) if !in_current_module && !in_friend_module => {
+            let friend_msg = format!(
+                "This function can only be called from the friends of '{}'. Add the calling \
+                 module to its friend declarations",
+                m
+            );
+            context.error(vec![
+                (loc, format!("Invalid call to '{}::{}'", m, f)),
+                (defined_loc, friend_msg),
+            ])
+        }
         _ => (),
     };
     (defined_loc, ty_args, params, acquires, return_ty)
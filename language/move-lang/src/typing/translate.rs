@@ -51,6 +51,7 @@ fn module(
     let N::ModuleDefinition {
         is_source_module,
         dependency_order,
+        friends,
         mut structs,
         functions: n_functions,
         constants: nconstants,
@@ -64,6 +65,7 @@ fn module(
     T::ModuleDefinition {
         is_source_module,
         dependency_order,
+        friends,
         structs,
         functions,
         constants,
@@ -0,0 +1,321 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Construction of unit test plans from `#[test]` annotated functions.
+//!
+//! A unit test is a module function without parameters annotated with `#[test]`. A test that is
+//! expected to abort is additionally annotated with `#[expected_failure]`, or with
+//! `#[expected_failure(abort_code = <code>)]` if it must abort with a particular code.
+//! Outside of unit test mode, tests are removed from the program right after parsing, so they are
+//! neither checked nor compiled.
+
+use crate::{
+    errors::*,
+    parser::ast::{
+        Attribute, AttributeValue_, Attribute_, Definition, Function, FunctionBody_,
+        ModuleDefinition, ModuleMember, Program, Value_,
+    },
+    shared::Address,
+};
+use move_core_types::{
+    account_address::AccountAddress, identifier::Identifier, language_storage::ModuleId,
+};
+use move_ir_types::location::*;
+use std::collections::BTreeMap;
+
+pub const TEST_ATTR: &str = "test";
+pub const EXPECTED_FAILURE_ATTR: &str = "expected_failure";
+pub const ABORT_CODE_ATTR: &str = "abort_code";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExpectedFailure {
+    // #[expected_failure]
+    Expected,
+    // #[expected_failure(abort_code = code)]
+    ExpectedWithCode(u64),
+}
+
+#[derive(Debug, Clone)]
+pub struct TestCase {
+    pub test_name: String,
+    pub loc: Loc,
+    pub expected_failure: Option<ExpectedFailure>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ModuleTestPlan {
+    pub module_id: ModuleId,
+    pub tests: BTreeMap<String, TestCase>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TestPlan {
+    pub module_tests: BTreeMap<ModuleId, ModuleTestPlan>,
+}
+
+impl TestPlan {
+    pub fn num_tests(&self) -> usize {
+        self.module_tests
+            .values()
+            .map(|module_plan| module_plan.tests.len())
+            .sum()
+    }
+}
+
+//**************************************************************************************************
+// Entry
+//**************************************************************************************************
+
+/// Check that the functions in `prog` only carry known attributes, and remove the unit tests from
+/// `prog` unless `keep_tests` is set. The tests of the dependencies are always removed.
+pub fn filter_test_members(prog: Program, keep_tests: bool) -> Result<Program, Errors> {
+    let mut errors = vec![];
+    let Program {
+        source_definitions,
+        lib_definitions,
+    } = prog;
+    let source_definitions = source_definitions
+        .into_iter()
+        .map(|def| filter_definition(&mut errors, def, keep_tests))
+        .collect();
+    let lib_definitions = lib_definitions
+        .into_iter()
+        .map(|def| filter_definition(&mut errors, def, false))
+        .collect();
+    check_errors(errors)?;
+    Ok(Program {
+        source_definitions,
+        lib_definitions,
+    })
+}
+
+/// Collect the unit tests declared in `definitions`. Modules outside of an address block are
+/// placed at `sender`, if any.
+pub fn construct_test_plan(
+    definitions: &[Definition],
+    sender: Option<Address>,
+) -> Result<TestPlan, Errors> {
+    let mut errors = vec![];
+    let mut module_tests = BTreeMap::new();
+    for def in definitions {
+        match def {
+            Definition::Module(m) => {
+                if let Some(addr) = sender {
+                    module_plan(&mut errors, &mut module_tests, addr, m)
+                }
+            }
+            Definition::Address(_, addr, modules) => {
+                for m in modules {
+                    module_plan(&mut errors, &mut module_tests, *addr, m)
+                }
+            }
+            // attributes on scripts are rejected by `filter_test_members`
+            Definition::Script(_) => (),
+        }
+    }
+    check_errors(errors)?;
+    Ok(TestPlan { module_tests })
+}
+
+//**************************************************************************************************
+// Filtering
+//**************************************************************************************************
+
+fn filter_definition(errors: &mut Errors, def: Definition, keep_tests: bool) -> Definition {
+    match def {
+        Definition::Module(m) => Definition::Module(filter_module(errors, m, keep_tests)),
+        Definition::Address(loc, addr, modules) => {
+            let modules = modules
+                .into_iter()
+                .map(|m| filter_module(errors, m, keep_tests))
+                .collect();
+            Definition::Address(loc, addr, modules)
+        }
+        Definition::Script(s) => {
+            if let Some(attr) = s.function.attributes.first() {
+                errors.push(vec![(
+                    attr.loc,
+                    "Invalid attribute. Attributes are only supported on module functions".into(),
+                )])
+            }
+            Definition::Script(s)
+        }
+    }
+}
+
+fn filter_module(
+    errors: &mut Errors,
+    mut mdef: ModuleDefinition,
+    keep_tests: bool,
+) -> ModuleDefinition {
+    mdef.members.retain(|member| match member {
+        ModuleMember::Function(f) => {
+            check_attribute_names(errors, f);
+            keep_tests || !is_test(f)
+        }
+        _ => true,
+    });
+    mdef
+}
+
+fn check_attribute_names(errors: &mut Errors, f: &Function) {
+    for attr in &f.attributes {
+        let name = attr.value.attribute_name();
+        match name.value.as_str() {
+            TEST_ATTR | EXPECTED_FAILURE_ATTR => (),
+            _ => {
+                let msg = format!(
+                    "Unknown attribute '{}'. Expected '{}' or '{}'",
+                    name, TEST_ATTR, EXPECTED_FAILURE_ATTR
+                );
+                errors.push(vec![(name.loc, msg)]);
+            }
+        }
+    }
+}
+
+fn is_test(f: &Function) -> bool {
+    f.attributes
+        .iter()
+        .any(|attr| attr.value.attribute_name().value == TEST_ATTR)
+}
+
+//**************************************************************************************************
+// Test Plans
+//**************************************************************************************************
+
+fn module_plan(
+    errors: &mut Errors,
+    module_tests: &mut BTreeMap<ModuleId, ModuleTestPlan>,
+    addr: Address,
+    mdef: &ModuleDefinition,
+) {
+    let mut tests = BTreeMap::new();
+    for member in &mdef.members {
+        if let ModuleMember::Function(f) = member {
+            if let Some(test_case) = test_case(errors, f) {
+                tests.insert(test_case.test_name.clone(), test_case);
+            }
+        }
+    }
+    if tests.is_empty() {
+        return;
+    }
+    let module_id = ModuleId::new(
+        AccountAddress::new(addr.to_u8()),
+        Identifier::new(mdef.name.value()).unwrap(),
+    );
+    module_tests.insert(module_id.clone(), ModuleTestPlan { module_id, tests });
+}
+
+fn test_case(errors: &mut Errors, f: &Function) -> Option<TestCase> {
+    let mut test_attr = None;
+    let mut expected_failure_attr = None;
+    for attr in &f.attributes {
+        let name = attr.value.attribute_name();
+        let slot = match name.value.as_str() {
+            TEST_ATTR => &mut test_attr,
+            EXPECTED_FAILURE_ATTR => &mut expected_failure_attr,
+            // unknown attributes are reported by `filter_test_members`
+            _ => continue,
+        };
+        if let Some(prev) = slot.replace(attr) {
+            errors.push(vec![
+                (attr.loc, format!("Duplicate attribute '{}'", name)),
+                (prev.loc, "Previously given here".into()),
+            ])
+        }
+    }
+
+    let test_attr = match (test_attr, expected_failure_attr) {
+        (None, None) => return None,
+        (None, Some(attr)) => {
+            let msg = format!(
+                "Invalid attribute. '{}' is only allowed on functions annotated with '#[{}]'",
+                EXPECTED_FAILURE_ATTR, TEST_ATTR
+            );
+            errors.push(vec![(attr.loc, msg)]);
+            return None;
+        }
+        (Some(attr), _) => attr,
+    };
+
+    let mut valid = true;
+    if !matches!(test_attr.value, Attribute_::Name(_)) {
+        let msg = format!("Invalid attribute. '{}' does not take arguments", TEST_ATTR);
+        errors.push(vec![(test_attr.loc, msg)]);
+        valid = false;
+    }
+    if let FunctionBody_::Native = f.body.value {
+        let msg = "Invalid test. Native functions cannot be tests";
+        errors.push(vec![
+            (f.name.loc(), msg.into()),
+            (test_attr.loc, "Test declared here".into()),
+        ]);
+        valid = false;
+    }
+    if !f.signature.type_parameters.is_empty() || !f.signature.parameters.is_empty() {
+        let msg = "Invalid test. Test functions cannot take type parameters or parameters";
+        errors.push(vec![
+            (f.name.loc(), msg.into()),
+            (test_attr.loc, "Test declared here".into()),
+        ]);
+        valid = false;
+    }
+    let expected_failure = match expected_failure_attr {
+        None => None,
+        Some(attr) => match expected_failure(errors, attr) {
+            Some(expected_failure) => Some(expected_failure),
+            None => {
+                valid = false;
+                None
+            }
+        },
+    };
+
+    if valid {
+        Some(TestCase {
+            test_name: f.name.value().to_owned(),
+            loc: f.name.loc(),
+            expected_failure,
+        })
+    } else {
+        None
+    }
+}
+
+fn expected_failure(errors: &mut Errors, attr: &Attribute) -> Option<ExpectedFailure> {
+    let invalid = |errors: &mut Errors, loc: Loc| {
+        let msg = format!(
+            "Invalid attribute. Expected '{}' or '{}({} = <u64>)'",
+            EXPECTED_FAILURE_ATTR, EXPECTED_FAILURE_ATTR, ABORT_CODE_ATTR
+        );
+        errors.push(vec![(loc, msg)]);
+        None
+    };
+    let args = match &attr.value {
+        Attribute_::Name(_) => return Some(ExpectedFailure::Expected),
+        Attribute_::Assigned(_, _) => return invalid(errors, attr.loc),
+        Attribute_::Parameterized(_, args) => args,
+    };
+    let (n, value) = match args.as_slice() {
+        [sp!(_, Attribute_::Assigned(n, value))] => (n, value),
+        _ => return invalid(errors, attr.loc),
+    };
+    if n.value != ABORT_CODE_ATTR {
+        return invalid(errors, n.loc);
+    }
+    let code = match &value.value {
+        AttributeValue_::InferredNum(u) => *u,
+        AttributeValue_::Value(sp!(_, Value_::U8(u))) => *u as u128,
+        AttributeValue_::Value(sp!(_, Value_::U64(u))) => *u as u128,
+        AttributeValue_::Value(sp!(_, Value_::U128(u))) => *u,
+        AttributeValue_::Value(_) => return invalid(errors, value.loc),
+    };
+    if code > (u64::max_value() as u128) {
+        let msg = "Invalid abort code. Abort codes must fit into a 'u64'";
+        errors.push(vec![(value.loc, msg.into())]);
+        return None;
+    }
+    Some(ExpectedFailure::ExpectedWithCode(code as u64))
+}
@@ -0,0 +1,7 @@
+address 0x2 {
+module N {}
+module M {
+    friend 0x2::N;
+    friend 0x2::N;
+}
+}
@@ -0,0 +1,5 @@
+address 0x2 {
+module M {
+    friend 0x2::M;
+}
+}
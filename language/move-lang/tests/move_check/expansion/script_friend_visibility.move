@@ -0,0 +1,3 @@
+script {
+    public(friend) fun main() {}
+}
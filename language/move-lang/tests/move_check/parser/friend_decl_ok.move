@@ -0,0 +1,8 @@
+address 0x2 {
+module N {}
+
+module M {
+    friend 0x2::N;
+    public(friend) fun f() {}
+}
+}
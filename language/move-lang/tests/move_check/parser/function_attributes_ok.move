@@ -0,0 +1,14 @@
+address 0x42 {
+module M {
+    /// Documentation comments come before attributes
+    #[test]
+    fun a() {}
+
+    #[test]
+    #[expected_failure]
+    fun b() { abort 0 }
+
+    #[test, expected_failure(abort_code = 7)]
+    fun c() { abort 7 }
+}
+}
@@ -0,0 +1,3 @@
+module M {
+    public(script) fun f() {}
+}
@@ -0,0 +1,15 @@
+address 0x2 {
+
+module X {
+    friend 0x2::M;
+    public(friend) fun foo() {}
+}
+
+module M {
+    use 0x2::X;
+    fun foo() {
+        X::foo()
+    }
+}
+
+}
@@ -0,0 +1,14 @@
+address 0x2 {
+
+module X {
+    public(friend) fun foo() {}
+}
+
+module M {
+    use 0x2::X;
+    fun foo() {
+        X::foo()
+    }
+}
+
+}
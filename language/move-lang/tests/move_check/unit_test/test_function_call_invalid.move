@@ -0,0 +1,10 @@
+address 0x42 {
+module M {
+    #[test]
+    fun t() {}
+
+    public fun f() {
+        t()
+    }
+}
+}
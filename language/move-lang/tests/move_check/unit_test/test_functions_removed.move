@@ -0,0 +1,12 @@
+address 0x42 {
+module M {
+    // tests are neither checked nor compiled outside of unit test mode
+    #[test]
+    fun ill_typed() {
+        let x: u64 = false;
+        x;
+    }
+
+    public fun f() {}
+}
+}
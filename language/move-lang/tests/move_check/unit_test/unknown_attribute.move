@@ -0,0 +1,9 @@
+address 0x42 {
+module M {
+    #[tset]
+    fun a() {}
+
+    #[some_attribute(x = 0x1, y = true, z = b"bytes", nested(inner = 1u8))]
+    public fun d() {}
+}
+}
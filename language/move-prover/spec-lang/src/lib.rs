@@ -34,6 +34,7 @@ use move_lang::{
     parser::ast::{ModuleIdent, ModuleIdent_},
     shared::{unique_map::UniqueMap, Name},
 };
+use std::collections::BTreeMap;
 
 // =================================================================================================
 // Entry Point
@@ -177,6 +178,7 @@ fn run_spec_checker(
                     let expanded_module = ModuleDefinition {
                         loc,
                         is_source_module: true,
+                        friends: BTreeMap::new(),
                         structs: UniqueMap::new(),
                         constants,
                         functions,
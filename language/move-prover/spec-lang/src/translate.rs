@@ -3169,7 +3169,7 @@ impl<'env, 'translator> ModuleTranslator<'env, 'translator> {
                         return false;
                     }
                 }
-                PA::FunctionVisibility::Internal => {
+                PA::FunctionVisibility::Internal | PA::FunctionVisibility::Friend(..) => {
                     if is_public {
                         return false;
                     }
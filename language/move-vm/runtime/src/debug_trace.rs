@@ -0,0 +1,90 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! The debug trace recorded by debug builds of the VM, for debuggers to replay the execution.
+//!
+//! When `MOVE_VM_DEBUG_TRACE_ENV_VAR_NAME` names a file, the VM appends to it a record of its
+//! state before every instruction it executes, one per line, as formatted by `TraceStep`.
+
+use anyhow::{bail, Result};
+use std::{fmt, fs, path::Path, str::FromStr};
+use vm::file_format::CodeOffset;
+
+/// Names the file recording the state of the VM before every instruction
+pub const MOVE_VM_DEBUG_TRACE_ENV_VAR_NAME: &str = "MOVE_VM_DEBUG_TRACE";
+
+/// Separates the fields of a debug trace record
+pub const DEBUG_TRACE_FIELD_SEPARATOR: &str = "\t";
+
+/// Separates the values of the locals, and of the operand stack, in a debug trace record
+pub const DEBUG_TRACE_VALUE_SEPARATOR: &str = "; ";
+
+/// How a local without a value is recorded
+pub const DEBUG_TRACE_UNSET_VALUE: &str = "-";
+
+/// The state of the VM before executing an instruction. Values that cannot be printed are
+/// recorded as `?`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceStep {
+    /// Number of callers of the function being executed
+    pub depth: usize,
+    /// `<address>::<module>::<function>`, or `main` for the script
+    pub function: String,
+    pub pc: CodeOffset,
+    pub locals: Vec<String>,
+    /// Values on the operand stack, from bottom to top
+    pub stack: Vec<String>,
+}
+
+impl fmt::Display for TraceStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            [
+                self.depth.to_string(),
+                self.function.clone(),
+                self.pc.to_string(),
+                self.locals.join(DEBUG_TRACE_VALUE_SEPARATOR),
+                self.stack.join(DEBUG_TRACE_VALUE_SEPARATOR),
+            ]
+            .join(DEBUG_TRACE_FIELD_SEPARATOR)
+        )
+    }
+}
+
+impl FromStr for TraceStep {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let fields: Vec<&str> = s.split(DEBUG_TRACE_FIELD_SEPARATOR).collect();
+        if fields.len() != 5 {
+            bail!("Malformed trace record '{}'", s)
+        }
+        let values = |field: &str| {
+            if field.is_empty() {
+                vec![]
+            } else {
+                field
+                    .split(DEBUG_TRACE_VALUE_SEPARATOR)
+                    .map(str::to_owned)
+                    .collect()
+            }
+        };
+        Ok(Self {
+            depth: fields[0].parse()?,
+            function: fields[1].to_owned(),
+            pc: fields[2].parse()?,
+            locals: values(fields[3]),
+            stack: values(fields[4]),
+        })
+    }
+}
+
+/// Read the trace recorded in `path`
+pub fn read_trace(path: &Path) -> Result<Vec<TraceStep>> {
+    fs::read_to_string(path)?
+        .lines()
+        .map(TraceStep::from_str)
+        .collect()
+}
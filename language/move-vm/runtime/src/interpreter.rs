@@ -82,7 +82,11 @@ impl<L: LogContext> Interpreter<L> {
         // We count the intrinsic cost of the transaction here, since that needs to also cover the
         // setup of the function.
         let mut interp = Self::new(log_context.clone());
-        interp.execute(loader, data_store, cost_strategy, function, ty_args, args)
+        let profile_depth = cost_strategy.profile_frame_depth();
+        let result = interp.execute(loader, data_store, cost_strategy, function, ty_args, args);
+        // Execution may have stopped in the middle of a call
+        cost_strategy.unwind_profile_frames(profile_depth);
+        result
     }
 
     /// Create a new instance of an `Interpreter` in the context of a transaction with a
@@ -135,6 +139,7 @@ impl<L: LogContext> Interpreter<L> {
                 .map_err(|e| self.set_location(e))?;
         }
 
+        cost_strategy.enter_profile_frame(|| profile_frame_name(&function));
         let mut current_frame = Frame::new(function, ty_args, locals);
         loop {
             let resolver = current_frame.resolver(loader);
@@ -148,6 +153,7 @@ impl<L: LogContext> Interpreter<L> {
                         .locals
                         .check_resources_for_return()
                         .map_err(|e| set_err_info!(current_frame, e))?;
+                    cost_strategy.exit_profile_frame();
                     if let Some(frame) = self.call_stack.pop() {
                         current_frame = frame;
                         current_frame.pc += 1; // advance past the Call instruction in the caller
@@ -182,6 +188,7 @@ impl<L: LogContext> Interpreter<L> {
                         let err = set_err_info!(frame, err);
                         self.maybe_core_dump(err, &frame)
                     })?;
+                    cost_strategy.enter_profile_frame(|| profile_frame_name(&frame.function));
                     current_frame = frame;
                 }
                 ExitCode::CallGeneric(idx) => {
@@ -217,6 +224,7 @@ impl<L: LogContext> Interpreter<L> {
                         let err = set_err_info!(frame, err);
                         self.maybe_core_dump(err, &frame)
                     })?;
+                    cost_strategy.enter_profile_frame(|| profile_frame_name(&frame.function));
                     current_frame = frame;
                 }
             }
@@ -252,14 +260,16 @@ impl<L: LogContext> Interpreter<L> {
         ty_args: Vec<Type>,
     ) -> VMResult<()> {
         // Note: refactor if native functions push a frame on the stack
-        self.call_native_impl(
+        cost_strategy.enter_profile_frame(|| profile_frame_name(&function));
+        let result = self.call_native_impl(
             resolver,
             data_store,
             cost_strategy,
             function.clone(),
             ty_args,
-        )
-        .map_err(|e| match function.module_id() {
+        );
+        cost_strategy.exit_profile_frame();
+        result.map_err(|e| match function.module_id() {
             Some(id) => e
                 .at_code_offset(function.index(), 0)
                 .finish(Location::Module(id.clone())),
@@ -287,7 +297,7 @@ impl<L: LogContext> Interpreter<L> {
         let mut native_context = FunctionContext::new(self, data_store, cost_strategy, resolver);
         let native_function = function.get_native()?;
         let result = native_function.dispatch(&mut native_context, ty_args, arguments)?;
-        cost_strategy.deduct_gas(result.cost)?;
+        cost_strategy.charge_native(result.cost)?;
         let values = result
             .result
             .map_err(|code| PartialVMError::new(StatusCode::ABORTED).with_sub_status(code))?;
@@ -500,6 +510,18 @@ impl<L: LogContext> Interpreter<L> {
         Ok(())
     }
 
+    /// The number of frames on the call stack, i.e. of callers of the function being executed.
+    #[allow(dead_code)]
+    pub(crate) fn debug_call_depth(&self) -> usize {
+        self.call_stack.0.len()
+    }
+
+    /// The values on the operand stack, from bottom to top.
+    #[allow(dead_code)]
+    pub(crate) fn debug_operand_stack(&self) -> &[Value] {
+        &self.operand_stack.0
+    }
+
     #[allow(dead_code)]
     pub(crate) fn debug_print_stack_trace<B: Write>(
         &self,
@@ -1179,3 +1201,21 @@ fn verify_args(signature: &Signature, args: &[Value]) -> PartialVMResult<()> {
     }
     Ok(())
 }
+
+// The name of the frame of `function` in gas profiles, with the address of its module shortened
+// like in Move source code, e.g. `0x1::LibraAccount::pay_from`.
+fn profile_frame_name(function: &Function) -> String {
+    match function.module_id() {
+        Some(module_id) => {
+            let address = format!("{:x}", module_id.address());
+            let address = address.trim_start_matches('0');
+            format!(
+                "0x{}::{}::{}",
+                if address.is_empty() { "0" } else { address },
+                module_id.name(),
+                function.name()
+            )
+        }
+        None => function.name().to_string(),
+    }
+}
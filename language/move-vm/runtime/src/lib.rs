@@ -13,6 +13,7 @@
 extern crate mirai_annotations;
 
 pub mod data_cache;
+pub mod debug_trace;
 mod interpreter;
 mod loader;
 pub mod logging;
@@ -2,12 +2,15 @@
 // SPDX-License-Identifier: Apache-2.0
 
 #[cfg(debug_assertions)]
-use crate::debug::DebugContext;
+use crate::{
+    debug::DebugContext,
+    debug_trace::{TraceStep, MOVE_VM_DEBUG_TRACE_ENV_VAR_NAME},
+};
 
 #[cfg(debug_assertions)]
 use ::{
     libra_infallible::Mutex,
-    move_vm_types::values::Locals,
+    move_vm_types::values::{self, Locals},
     once_cell::sync::Lazy,
     std::{
         env,
@@ -53,6 +56,20 @@ static LOGGING_FILE: Lazy<Mutex<File>> = Lazy::new(|| {
     )
 });
 
+#[cfg(debug_assertions)]
+static DEBUG_TRACE_FILE: Lazy<Option<Mutex<File>>> = Lazy::new(|| {
+    env::var(MOVE_VM_DEBUG_TRACE_ENV_VAR_NAME).ok().map(|path| {
+        Mutex::new(
+            OpenOptions::new()
+                .write(true)
+                .create(true)
+                .append(true)
+                .open(path)
+                .unwrap(),
+        )
+    })
+});
+
 #[cfg(debug_assertions)]
 static DEBUG_CONTEXT: Lazy<Mutex<DebugContext>> = Lazy::new(|| Mutex::new(DebugContext::new()));
 
@@ -70,6 +87,10 @@ pub(crate) fn trace<L: LogContext>(
         let f = &mut *LOGGING_FILE.lock();
         writeln!(f, "{},{},{:?}", function_desc.pretty_string(), pc, instr).unwrap();
     }
+    if let Some(file) = &*DEBUG_TRACE_FILE {
+        let step = debug_trace_step(function_desc, locals, pc, interp);
+        writeln!(&mut *file.lock(), "{}", step).unwrap();
+    }
     if *DEBUGGING_ENABLED {
         DEBUG_CONTEXT
             .lock()
@@ -77,6 +98,42 @@ pub(crate) fn trace<L: LogContext>(
     }
 }
 
+/// The state of the VM recorded in the debug trace before executing an instruction.
+#[cfg(debug_assertions)]
+fn debug_trace_step<L: LogContext>(
+    function_desc: &Function,
+    locals: &Locals,
+    pc: u16,
+    interp: &Interpreter<L>,
+) -> TraceStep {
+    let function = match function_desc.module_id() {
+        Some(module_id) => format!("{}::{}", module_id, function_desc.name()),
+        None => function_desc.name().to_string(),
+    };
+    TraceStep {
+        depth: interp.debug_call_depth(),
+        function,
+        pc,
+        locals: (0..function_desc.local_count())
+            .map(|idx| printed(|buf| values::debug::print_local(buf, locals, idx)))
+            .collect(),
+        stack: interp
+            .debug_operand_stack()
+            .iter()
+            .map(|value| printed(|buf| values::debug::print_value(buf, value)))
+            .collect(),
+    }
+}
+
+#[cfg(debug_assertions)]
+fn printed<E>(print: impl FnOnce(&mut String) -> Result<(), E>) -> String {
+    let mut buf = String::new();
+    match print(&mut buf) {
+        Ok(()) => buf,
+        Err(_) => "?".to_string(),
+    }
+}
+
 #[macro_export]
 macro_rules! trace {
     ($function_desc:expr, $locals:expr, $pc:expr, $instr:tt, $resolver:expr, $interp:expr) => {
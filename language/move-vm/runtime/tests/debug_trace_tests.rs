@@ -0,0 +1,123 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! The VM reads the path of the debug trace once per process, so the trace is recorded by a test
+//! binary of its own.
+
+use move_core_types::{
+    account_address::AccountAddress,
+    gas_schedule::{GasAlgebra, GasUnits},
+    language_storage::{ModuleId, StructTag},
+};
+use move_vm_runtime::{
+    data_cache::RemoteCache,
+    debug_trace::{read_trace, TraceStep, MOVE_VM_DEBUG_TRACE_ENV_VAR_NAME},
+    logging::NoContextLog,
+    move_vm::MoveVM,
+};
+use move_vm_types::{
+    gas_schedule::{zero_cost_schedule, CostStrategy},
+    values::Value,
+};
+use tempfile::TempDir;
+use vm::{
+    errors::{PartialVMResult, VMResult},
+    file_format::{
+        Bytecode, CodeUnit, CompiledScriptMut, Signature, SignatureIndex, SignatureToken,
+    },
+};
+
+struct RemoteStore {}
+
+impl RemoteCache for RemoteStore {
+    fn get_module(&self, _module_id: &ModuleId) -> VMResult<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
+    fn get_resource(
+        &self,
+        _address: &AccountAddress,
+        _tag: &StructTag,
+    ) -> PartialVMResult<Option<Vec<u8>>> {
+        Ok(None)
+    }
+}
+
+fn step(pc: u16, locals: &[&str], stack: &[&str]) -> TraceStep {
+    TraceStep {
+        depth: 0,
+        function: "main".to_string(),
+        pc,
+        locals: locals.iter().map(|value| value.to_string()).collect(),
+        stack: stack.iter().map(|value| value.to_string()).collect(),
+    }
+}
+
+#[test]
+fn record_and_read_debug_trace() {
+    // main(x: u64) { let y = x + 1; }
+    let mut script = vec![];
+    CompiledScriptMut {
+        module_handles: vec![],
+        struct_handles: vec![],
+        function_handles: vec![],
+        function_instantiations: vec![],
+        signatures: vec![Signature(vec![SignatureToken::U64])],
+        identifiers: vec![],
+        address_identifiers: vec![],
+        constant_pool: vec![],
+        type_parameters: vec![],
+        parameters: SignatureIndex(0),
+        code: CodeUnit {
+            locals: SignatureIndex(0),
+            code: vec![
+                Bytecode::CopyLoc(0),
+                Bytecode::LdU64(1),
+                Bytecode::Add,
+                Bytecode::StLoc(1),
+                Bytecode::Ret,
+            ],
+        },
+    }
+    .serialize(&mut script)
+    .unwrap();
+
+    let dir = TempDir::new().unwrap();
+    let trace_file = dir.path().join("trace");
+    std::env::set_var(MOVE_VM_DEBUG_TRACE_ENV_VAR_NAME, &trace_file);
+
+    let move_vm = MoveVM::new();
+    let mut session = move_vm.new_session(&RemoteStore {});
+    let cost_table = zero_cost_schedule();
+    let mut cost_strategy = CostStrategy::system(&cost_table, GasUnits::new(0));
+    session
+        .execute_script(
+            script,
+            vec![],
+            vec![Value::u64(5)],
+            vec![],
+            &mut cost_strategy,
+            &NoContextLog::new(),
+        )
+        .unwrap();
+
+    // Only debug builds of the VM record the trace.
+    if cfg!(debug_assertions) {
+        let trace = read_trace(&trace_file).unwrap();
+        assert_eq!(
+            trace,
+            vec![
+                step(0, &["5", "-"], &[]),
+                step(1, &["5", "-"], &["5"]),
+                step(2, &["5", "-"], &["5", "1"]),
+                step(3, &["5", "-"], &["6"]),
+                step(4, &["5", "6"], &[]),
+            ]
+        );
+        for step in trace {
+            assert_eq!(step.to_string().parse::<TraceStep>().unwrap(), step);
+        }
+    } else {
+        assert!(!trace_file.exists());
+    }
+}
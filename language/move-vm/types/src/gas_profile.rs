@@ -0,0 +1,179 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module attributes the gas charged by a `CostStrategy` to the Move functions and the kinds
+//! of operations it was charged for.
+//!
+//! Gas is recorded in the internal gas units of the `CostTable`, i.e., before the
+//! `gas_unit_scaling_factor` is applied, so that no precision is lost when adding up charges.
+use std::{
+    collections::BTreeMap,
+    fmt,
+    io::{self, Write},
+};
+use vm::file_format_common::Opcodes;
+
+/// What a given amount of gas is charged for.
+#[derive(Clone, Copy, Debug)]
+pub enum GasKind {
+    /// The execution of a bytecode instruction.
+    Instruction(Opcodes),
+    /// The execution of a native function, as reported by the function itself.
+    Native,
+    /// The overall size of the transaction.
+    Intrinsic,
+    /// Anything else, e.g., the writes to global storage charged by an adapter.
+    Other,
+}
+
+impl fmt::Display for GasKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GasKind::Instruction(opcode) => write!(f, "{:?}", opcode),
+            GasKind::Native => write!(f, "NATIVE"),
+            GasKind::Intrinsic => write!(f, "INTRINSIC"),
+            GasKind::Other => write!(f, "OTHER"),
+        }
+    }
+}
+
+/// The gas charged for one kind of operation.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct KindProfile {
+    /// The number of times gas was charged.
+    pub charges: u64,
+    /// The total amount of gas charged.
+    pub gas: u64,
+}
+
+/// The gas charged while executing, by stack of function call frames and by kind of operation.
+///
+/// Gas charged outside of any frame, like the intrinsic cost of a transaction, is attributed to a
+/// pseudo frame named after its kind, e.g. `[INTRINSIC]`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct GasProfile {
+    /// The names of the active frames, outermost first, joined by `;`.
+    stack: String,
+    /// The offset in `stack` at which each active frame starts.
+    frame_offsets: Vec<usize>,
+    /// The gas charged to each stack of frames, excluding the gas charged to the frames it called.
+    stacks: BTreeMap<String, u64>,
+    kinds: BTreeMap<String, KindProfile>,
+}
+
+impl GasProfile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Attribute the gas charged from now on to a new frame called by the current one.
+    pub fn enter_frame(&mut self, name: &str) {
+        self.frame_offsets.push(self.stack.len());
+        if !self.stack.is_empty() {
+            self.stack.push(';');
+        }
+        // `;` separates frames and spaces separate the stack from the gas in the folded format.
+        self.stack.extend(name.chars().map(|c| match c {
+            ';' | ' ' => '_',
+            c => c,
+        }));
+    }
+
+    /// Attribute the gas charged from now on to the frame that called the current one.
+    pub fn exit_frame(&mut self) {
+        if let Some(offset) = self.frame_offsets.pop() {
+            self.stack.truncate(offset)
+        }
+    }
+
+    /// The number of active frames.
+    pub fn frame_depth(&self) -> usize {
+        self.frame_offsets.len()
+    }
+
+    /// Exit frames until only `depth` frames are active, e.g. after execution was interrupted by
+    /// an error.
+    pub fn unwind_frames(&mut self, depth: usize) {
+        while self.frame_depth() > depth {
+            self.exit_frame()
+        }
+    }
+
+    /// Record `amount` units of gas charged for `kind` in the current frame.
+    pub fn record(&mut self, kind: GasKind, amount: u64) {
+        if self.stack.is_empty() {
+            *self.stacks.entry(format!("[{}]", kind)).or_insert(0) += amount;
+        } else if let Some(gas) = self.stacks.get_mut(&self.stack) {
+            *gas += amount;
+        } else {
+            self.stacks.insert(self.stack.clone(), amount);
+        }
+        let kind = self.kinds.entry(kind.to_string()).or_default();
+        kind.charges += 1;
+        kind.gas += amount;
+    }
+
+    /// Add the gas recorded by `other` to this profile.
+    pub fn merge(&mut self, other: &GasProfile) {
+        for (stack, gas) in &other.stacks {
+            *self.stacks.entry(stack.clone()).or_insert(0) += gas;
+        }
+        for (name, other_kind) in &other.kinds {
+            let kind = self.kinds.entry(name.clone()).or_default();
+            kind.charges += other_kind.charges;
+            kind.gas += other_kind.gas;
+        }
+    }
+
+    /// The total amount of gas recorded.
+    pub fn total_gas(&self) -> u64 {
+        self.stacks.values().sum()
+    }
+
+    /// The gas charged to each stack of frames, as the names of the frames joined by `;`,
+    /// excluding the gas charged to the frames it called.
+    pub fn stacks(&self) -> &BTreeMap<String, u64> {
+        &self.stacks
+    }
+
+    /// The gas charged to each kind of operation, by the name of the kind.
+    pub fn kinds(&self) -> &BTreeMap<String, KindProfile> {
+        &self.kinds
+    }
+
+    /// Write the gas charged to each stack of frames in the folded stack format, one
+    /// `frame;frame;... gas` line per stack, as consumed by flamegraph tools.
+    pub fn write_folded<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        for (stack, gas) in &self.stacks {
+            writeln!(w, "{} {}", stack, gas)?;
+        }
+        Ok(())
+    }
+
+    /// Write a table of the gas charged to each kind of operation, most expensive first.
+    pub fn write_kinds<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let total = self.total_gas();
+        let mut kinds = self.kinds.iter().collect::<Vec<_>>();
+        kinds.sort_by(|(name1, kind1), (name2, kind2)| {
+            kind2.gas.cmp(&kind1.gas).then(name1.cmp(name2))
+        });
+        writeln!(
+            w,
+            "{:<24} {:>10} {:>14} {:>7}",
+            "kind", "charges", "gas", "%"
+        )?;
+        for (name, kind) in kinds {
+            let percent = if total == 0 {
+                0.0
+            } else {
+                100.0 * kind.gas as f64 / total as f64
+            };
+            writeln!(
+                w,
+                "{:<24} {:>10} {:>14} {:>6.2}%",
+                name, kind.charges, kind.gas, percent
+            )?;
+        }
+        writeln!(w, "{:<24} {:>10} {:>14}", "total", "", total)
+    }
+}
@@ -6,6 +6,7 @@
 //! It is important to note that the cost schedule defined in this file does not track hashing
 //! operations or other native operations; the cost of each native operation will be returned by the
 //! native function itself.
+use crate::gas_profile::{GasKind, GasProfile};
 use mirai_annotations::*;
 use move_core_types::{
     gas_schedule::{
@@ -34,6 +35,7 @@ pub struct CostStrategy<'a> {
     cost_table: &'a CostTable,
     gas_left: GasUnits<GasCarrier>,
     charge: bool,
+    profile: Option<GasProfile>,
 }
 
 impl<'a> CostStrategy<'a> {
@@ -46,6 +48,7 @@ impl<'a> CostStrategy<'a> {
             gas_left: gas_left.map(|x| x * cost_table.gas_constants.gas_unit_scaling_factor),
             cost_table,
             charge: true,
+            profile: None,
         }
     }
 
@@ -58,6 +61,7 @@ impl<'a> CostStrategy<'a> {
             gas_left: gas_left.map(|x| x * cost_table.gas_constants.gas_unit_scaling_factor),
             cost_table,
             charge: false,
+            profile: None,
         }
     }
 
@@ -74,6 +78,15 @@ impl<'a> CostStrategy<'a> {
 
     /// Charge a given amount of gas and fail if not enough gas units are left.
     pub fn deduct_gas(&mut self, amount: GasUnits<GasCarrier>) -> PartialVMResult<()> {
+        self.charge_gas(GasKind::Other, amount)
+    }
+
+    /// Charge the gas reported by a native function and fail if not enough gas units are left.
+    pub fn charge_native(&mut self, amount: GasUnits<GasCarrier>) -> PartialVMResult<()> {
+        self.charge_gas(GasKind::Native, amount)
+    }
+
+    fn charge_gas(&mut self, kind: GasKind, amount: GasUnits<GasCarrier>) -> PartialVMResult<()> {
         if !self.charge {
             return Ok(());
         }
@@ -82,9 +95,15 @@ impl<'a> CostStrategy<'a> {
             .gas_left
             .app(&amount, |curr_gas, gas_amt| curr_gas >= gas_amt)
         {
+            if let Some(profile) = &mut self.profile {
+                profile.record(kind, amount.get());
+            }
             self.gas_left = self.gas_left.sub(amount);
             Ok(())
         } else {
+            if let Some(profile) = &mut self.profile {
+                profile.record(kind, self.gas_left.get());
+            }
             // Zero out the internal gas state
             self.gas_left = GasUnits::new(0);
             Err(PartialVMError::new(StatusCode::OUT_OF_GAS))
@@ -100,7 +119,8 @@ impl<'a> CostStrategy<'a> {
         // Make sure that the size is always non-zero
         let size = size.map(|x| std::cmp::max(1, x));
         debug_assert!(size.get() > 0);
-        self.deduct_gas(
+        self.charge_gas(
+            GasKind::Instruction(opcode),
             self.cost_table
                 .instruction_cost(opcode as u8)
                 .total()
@@ -110,7 +130,10 @@ impl<'a> CostStrategy<'a> {
 
     /// Charge an instruction and fail if not enough gas units are left.
     pub fn charge_instr(&mut self, opcode: Opcodes) -> PartialVMResult<()> {
-        self.deduct_gas(self.cost_table.instruction_cost(opcode as u8).total())
+        self.charge_gas(
+            GasKind::Instruction(opcode),
+            self.cost_table.instruction_cost(opcode as u8).total(),
+        )
     }
 
     /// Charge gas related to the overall size of a transaction and fail if not enough
@@ -120,7 +143,7 @@ impl<'a> CostStrategy<'a> {
         intrinsic_cost: AbstractMemorySize<GasCarrier>,
     ) -> VMResult<()> {
         let cost = calculate_intrinsic_gas(intrinsic_cost, &self.cost_table.gas_constants);
-        self.deduct_gas(cost)
+        self.charge_gas(GasKind::Intrinsic, cost)
             .map_err(|e| e.finish(Location::Undefined))
     }
 
@@ -130,6 +153,52 @@ impl<'a> CostStrategy<'a> {
     pub fn enable_metering(&mut self) {
         self.charge = true
     }
+
+    /// Start recording the gas charged from now on in a `GasProfile`.
+    ///
+    /// Gas is only recorded while metering is enabled.
+    pub fn enable_profiling(&mut self) {
+        self.profile = Some(GasProfile::new())
+    }
+
+    /// Return the gas recorded so far, if profiling is enabled.
+    pub fn profile(&self) -> Option<&GasProfile> {
+        self.profile.as_ref()
+    }
+
+    /// Stop profiling and return the gas recorded, if profiling was enabled.
+    pub fn take_profile(&mut self) -> Option<GasProfile> {
+        self.profile.take()
+    }
+
+    /// Attribute the gas charged from now on to a new call frame, if profiling is enabled.
+    ///
+    /// The name of the frame is only computed when profiling.
+    pub fn enter_profile_frame(&mut self, name: impl FnOnce() -> String) {
+        if let Some(profile) = &mut self.profile {
+            profile.enter_frame(&name())
+        }
+    }
+
+    /// Attribute the gas charged from now on to the caller of the current frame, if profiling is
+    /// enabled.
+    pub fn exit_profile_frame(&mut self) {
+        if let Some(profile) = &mut self.profile {
+            profile.exit_frame()
+        }
+    }
+
+    /// Return the number of active call frames when profiling, or 0.
+    pub fn profile_frame_depth(&self) -> usize {
+        self.profile.as_ref().map_or(0, GasProfile::frame_depth)
+    }
+
+    /// Exit call frames until only `depth` of them are active, if profiling is enabled.
+    pub fn unwind_profile_frames(&mut self, depth: usize) {
+        if let Some(profile) = &mut self.profile {
+            profile.unwind_frames(depth)
+        }
+    }
 }
 
 pub fn new_from_instructions(
@@ -22,6 +22,7 @@ macro_rules! debug_writeln {
 }
 
 pub mod data_store;
+pub mod gas_profile;
 pub mod gas_schedule;
 pub mod loaded_data;
 pub mod natives;
@@ -0,0 +1,83 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    gas_profile::{GasKind, GasProfile, KindProfile},
+    gas_schedule::{zero_cost_schedule, CostStrategy},
+};
+use move_core_types::gas_schedule::{AbstractMemorySize, GasCost, GasUnits};
+use vm::file_format_common::Opcodes;
+
+#[test]
+fn folded_stacks() {
+    let mut profile = GasProfile::new();
+    profile.record(GasKind::Intrinsic, 7);
+    profile.enter_frame("main");
+    profile.record(GasKind::Instruction(Opcodes::CALL), 1);
+    profile.enter_frame("0x1::M::f");
+    profile.record(GasKind::Instruction(Opcodes::LD_U64), 2);
+    profile.enter_frame("0x1::M::g has spaces;");
+    profile.record(GasKind::Native, 3);
+    profile.unwind_frames(1);
+    profile.record(GasKind::Instruction(Opcodes::RET), 4);
+    profile.exit_frame();
+    profile.exit_frame();
+    assert_eq!(profile.frame_depth(), 0);
+
+    let mut folded = vec![];
+    profile.write_folded(&mut folded).unwrap();
+    assert_eq!(
+        String::from_utf8(folded).unwrap(),
+        "[INTRINSIC] 7\n\
+         main 5\n\
+         main;0x1::M::f 2\n\
+         main;0x1::M::f;0x1::M::g_has_spaces_ 3\n"
+    );
+    assert_eq!(profile.total_gas(), 17);
+    assert_eq!(profile.kinds()["CALL"], KindProfile { charges: 1, gas: 1 });
+}
+
+#[test]
+fn merged_profiles() {
+    let mut profile = GasProfile::new();
+    profile.enter_frame("main");
+    profile.record(GasKind::Instruction(Opcodes::ADD), 2);
+    let mut merged = profile.clone();
+    merged.merge(&profile);
+    assert_eq!(merged.stacks()["main"], 4);
+    assert_eq!(merged.kinds()["ADD"], KindProfile { charges: 2, gas: 4 });
+
+    let mut table = vec![];
+    merged.write_kinds(&mut table).unwrap();
+    let table = String::from_utf8(table).unwrap();
+    assert!(table.contains("ADD"), "{}", table);
+    assert!(table.contains("100.00%"), "{}", table);
+}
+
+#[test]
+fn cost_strategy_records_metered_gas() {
+    let mut cost_table = zero_cost_schedule();
+    for cost in &mut cost_table.instruction_table {
+        *cost = GasCost::new(1, 1);
+    }
+    let mut cost_strategy = CostStrategy::transaction(&cost_table, GasUnits::new(1));
+    assert!(cost_strategy.profile().is_none());
+    cost_strategy.enable_profiling();
+
+    cost_strategy.enter_profile_frame(|| "main".to_string());
+    cost_strategy
+        .charge_instr_with_size(Opcodes::PACK, AbstractMemorySize::new(3))
+        .unwrap();
+    cost_strategy.disable_metering();
+    cost_strategy.charge_instr(Opcodes::POP).unwrap();
+    cost_strategy.enable_metering();
+    cost_strategy.charge_native(GasUnits::new(5)).unwrap();
+    cost_strategy.exit_profile_frame();
+    assert_eq!(cost_strategy.profile_frame_depth(), 0);
+
+    let profile = cost_strategy.take_profile().unwrap();
+    assert_eq!(profile.stacks()["main"], 11);
+    assert!(!profile.kinds().contains_key("POP"));
+    assert_eq!(profile.kinds()["NATIVE"].gas, 5);
+    assert!(cost_strategy.profile().is_none());
+}
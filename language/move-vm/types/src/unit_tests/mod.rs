@@ -3,3 +3,4 @@
 
 #[cfg(feature = "fuzzing")]
 mod identifier_prop_tests;
+mod gas_profile_tests;
@@ -2585,6 +2585,10 @@ pub mod debug {
     pub fn print_value<B: Write>(buf: &mut B, val: &Value) -> PartialVMResult<()> {
         print_value_impl(buf, &val.0)
     }
+
+    pub fn print_local<B: Write>(buf: &mut B, locals: &Locals, idx: usize) -> PartialVMResult<()> {
+        print_slice_elem(buf, &*locals.0.borrow(), idx, print_value_impl)
+    }
 }
 
 /***************************************************************************************
@@ -23,7 +23,7 @@ use libra_types::{
 };
 use libra_vm::{
     data_cache::RemoteStorage, txn_effects_to_writeset_and_events, LibraVM, LibraVMValidator,
-    VMExecutor, VMValidator,
+    ParallelLibraVM, VMExecutor, VMValidator,
 };
 use move_core_types::{
     account_address::AccountAddress,
@@ -33,6 +33,7 @@ use move_core_types::{
 };
 use move_vm_runtime::{logging::NoContextLog, move_vm::MoveVM};
 use move_vm_types::{
+    gas_profile::GasProfile,
     gas_schedule::{zero_cost_schedule, CostStrategy},
     values::Value,
 };
@@ -194,6 +195,21 @@ impl FakeExecutor {
         )
     }
 
+    /// Alternate form of 'execute_block' that executes the transactions in parallel with
+    /// `ParallelLibraVM`. The outputs are the same as the ones of 'execute_block'.
+    pub fn execute_block_parallel(
+        &self,
+        txn_block: Vec<SignedTransaction>,
+    ) -> Result<Vec<TransactionOutput>, VMStatus> {
+        ParallelLibraVM::execute_block(
+            txn_block
+                .into_iter()
+                .map(Transaction::UserTransaction)
+                .collect(),
+            &self.data_store,
+        )
+    }
+
     /// Alternate form of 'execute_block' that keeps the vm_status before it goes into the
     /// `TransactionOutput`
     pub fn execute_block_and_keep_vm_status(
@@ -209,6 +225,32 @@ impl FakeExecutor {
         )
     }
 
+    /// Alternate form of 'execute_block' that also profiles the gas charged to the transactions.
+    ///
+    /// The gas profiles of all the transactions are merged into one, which can be written out for
+    /// flamegraph tools with `GasProfile::write_folded`.
+    pub fn execute_block_with_gas_profile(
+        &self,
+        txn_block: Vec<SignedTransaction>,
+    ) -> Result<(Vec<TransactionOutput>, GasProfile), VMStatus> {
+        let results = LibraVM::execute_block_with_gas_profiles(
+            txn_block
+                .into_iter()
+                .map(Transaction::UserTransaction)
+                .collect(),
+            &self.data_store,
+        )?;
+        let mut gas_profile = GasProfile::new();
+        let mut outputs = vec![];
+        for (_vm_status, output, txn_gas_profile) in results {
+            if let Some(txn_gas_profile) = txn_gas_profile {
+                gas_profile.merge(&txn_gas_profile);
+            }
+            outputs.push(output);
+        }
+        Ok((outputs, gas_profile))
+    }
+
     /// Executes the transaction as a singleton block and applies the resulting write set to the
     /// data store. Panics if execution fails
     pub fn execute_and_apply(&mut self, transaction: SignedTransaction) -> TransactionOutput {
@@ -237,6 +279,15 @@ impl FakeExecutor {
         LibraVM::execute_block(txn_block, &self.data_store)
     }
 
+    /// Alternate form of 'execute_transaction_block' that executes the transactions in parallel
+    /// with `ParallelLibraVM`.
+    pub fn execute_transaction_block_parallel(
+        &self,
+        txn_block: Vec<Transaction>,
+    ) -> Result<Vec<TransactionOutput>, VMStatus> {
+        ParallelLibraVM::execute_block(txn_block, &self.data_store)
+    }
+
     pub fn execute_transaction(&self, txn: SignedTransaction) -> TransactionOutput {
         let txn_block = vec![txn];
         let mut outputs = self
@@ -247,6 +298,21 @@ impl FakeExecutor {
             .expect("A block with one transaction should have one output")
     }
 
+    /// Alternate form of 'execute_transaction' that also profiles the gas charged to the
+    /// transaction.
+    pub fn execute_transaction_with_gas_profile(
+        &self,
+        txn: SignedTransaction,
+    ) -> (TransactionOutput, GasProfile) {
+        let (mut outputs, gas_profile) = self
+            .execute_block_with_gas_profile(vec![txn])
+            .expect("The VM should not fail to startup");
+        let output = outputs
+            .pop()
+            .expect("A block with one transaction should have one output");
+        (output, gas_profile)
+    }
+
     /// Get the blob for the associated AccessPath
     pub fn read_from_access_path(&self, path: &AccessPath) -> Option<Vec<u8>> {
         StateView::get(&self.data_store, path).unwrap()
@@ -307,6 +307,7 @@ impl<'a> ModuleGenerator<'a> {
         let current_module = ModuleDefinition {
             name: ModuleName::new(module_name),
             imports: Self::imports(callable_modules),
+            friends: Vec::new(),
             explicit_dependency_declarations: Vec::new(),
             structs: Vec::new(),
             functions: Vec::new(),
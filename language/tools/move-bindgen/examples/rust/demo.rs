@@ -0,0 +1,32 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use move_bindings::{
+    libra::Libra,
+    libra_account::{Balance, SentPaymentEvent},
+};
+use move_core_types::{account_address::AccountAddress, move_resource::MoveResource};
+
+fn main() {
+    let event = SentPaymentEvent {
+        amount: 1234567,
+        currency_code: b"LBR".to_vec(),
+        payee: AccountAddress::new([0x22; AccountAddress::LENGTH]),
+        metadata: vec![1, 2, 3],
+    };
+    let bytes = event.lcs_serialize().unwrap();
+    assert_eq!(SentPaymentEvent::lcs_deserialize(&bytes).unwrap(), event);
+    assert_eq!(SentPaymentEvent::MODULE_NAME, "LibraAccount");
+
+    // the coin type of a balance is only part of its Move type
+    let balance = Balance {
+        coin: Libra { value: 42 },
+    };
+    assert_eq!(
+        Balance::lcs_deserialize(&balance.lcs_serialize().unwrap()).unwrap(),
+        balance
+    );
+
+    let bytes: Vec<_> = bytes.iter().map(|byte| byte.to_string()).collect();
+    println!("{}", bytes.join(" "));
+}
@@ -0,0 +1,24 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+import { Libra, LibraAccount } from "./move_bindings";
+
+const event = new LibraAccount.SentPaymentEvent(
+    BigInt(1234567),
+    Uint8Array.from([76, 66, 82]),
+    new Uint8Array(16).fill(0x22),
+    Uint8Array.from([1, 2, 3]),
+);
+const bytes = event.lcsSerialize();
+const decoded = LibraAccount.SentPaymentEvent.lcsDeserialize(bytes);
+if (decoded.amount !== event.amount || decoded.payee.toString() !== event.payee.toString()) {
+    throw new Error("Unexpected event after deserialization");
+}
+
+// the coin type of a balance is only part of its Move type
+const balance = new LibraAccount.Balance(new Libra.Libra(BigInt(42)));
+if (LibraAccount.Balance.lcsDeserialize(balance.lcsSerialize()).coin.value !== BigInt(42)) {
+    throw new Error("Unexpected balance after deserialization");
+}
+
+console.log(Array.from(bytes).join(" "));
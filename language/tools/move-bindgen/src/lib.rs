@@ -0,0 +1,275 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+//! Generation of Rust and TypeScript types for the structs declared by compiled Move modules,
+//! e.g. the resources and events of the Libra framework, along with their LCS (de)serializers.
+//!
+//! Structs are read from the modules through `vm::normalized`. Type parameters that do not occur
+//! in the fields of a struct, like the `CoinType` of `Libra<CoinType>`, do not change its
+//! encoding, so they are left out of the generated types.
+
+use anyhow::{anyhow, bail, Result};
+use move_core_types::{account_address::AccountAddress, identifier::Identifier};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+use vm::{
+    access::ModuleAccess,
+    file_format::{CompiledModule, Kind, StructFieldInformation, TypeParameterIndex},
+    normalized::{Field, Struct, Type},
+};
+
+/// Support for code generation in Rust.
+pub mod rust;
+/// Support for code generation in TypeScript.
+pub mod typescript;
+
+/// File extension of compiled modules
+const MODULE_EXTENSION: &str = "mv";
+
+/// Read the compiled modules at `paths`, which are either module files or directories containing
+/// them.
+pub fn read_modules<P: AsRef<Path>>(paths: &[P]) -> Result<Vec<CompiledModule>> {
+    let mut files = vec![];
+    for path in paths {
+        let path = path.as_ref();
+        if path.is_dir() {
+            for entry in std::fs::read_dir(path)? {
+                let entry_path = entry?.path();
+                if entry_path
+                    .extension()
+                    .map_or(false, |ext| ext == MODULE_EXTENSION)
+                {
+                    files.push(entry_path)
+                }
+            }
+        } else {
+            files.push(path.to_path_buf())
+        }
+    }
+    files.sort();
+    files
+        .iter()
+        .map(|file| {
+            CompiledModule::deserialize(&std::fs::read(file)?)
+                .map_err(|e| anyhow!("Unable to deserialize module {:?}: {:?}", file, e))
+        })
+        .collect()
+}
+
+/// The structs of a module to generate types for.
+#[derive(Clone, Debug)]
+pub struct ModuleStructs {
+    pub address: AccountAddress,
+    pub name: Identifier,
+    pub structs: Vec<StructBinding>,
+}
+
+/// A struct to generate a type for.
+#[derive(Clone, Debug)]
+pub struct StructBinding {
+    pub name: Identifier,
+    pub kind: Kind,
+    /// The number of type parameters of the Move struct.
+    pub num_type_parameters: usize,
+    /// The type parameters kept by the generated type, i.e. the ones occurring in the fields of
+    /// the struct, in increasing order.
+    pub type_parameters: Vec<TypeParameterIndex>,
+    pub fields: Vec<Field>,
+}
+
+/// The structs declared by a set of modules, sorted by module address and name. Native structs,
+/// which have no fields to encode, are left out.
+#[derive(Clone, Debug)]
+pub struct Bindings {
+    pub modules: Vec<ModuleStructs>,
+    /// The type parameters kept by each struct, by address, module and struct name.
+    type_parameters: BTreeMap<(AccountAddress, Identifier, Identifier), Vec<TypeParameterIndex>>,
+}
+
+impl Bindings {
+    /// Collect the structs of `modules`. The structs used by their fields must be declared by
+    /// `modules` as well.
+    pub fn new(modules: &[CompiledModule]) -> Result<Self> {
+        let mut modules = modules
+            .iter()
+            .map(|m| {
+                let structs = m
+                    .struct_defs()
+                    .iter()
+                    .filter(|def| !matches!(def.field_information, StructFieldInformation::Native))
+                    .map(|def| Struct::new(m, def))
+                    .collect::<Vec<_>>();
+                (*m.address(), m.name().to_owned(), structs)
+            })
+            .collect::<Vec<_>>();
+        modules.sort_by(|(addr1, name1, _), (addr2, name2, _)| (addr1, name1).cmp(&(addr2, name2)));
+
+        let mut declared = BTreeSet::new();
+        for (address, module, structs) in &modules {
+            for s in structs {
+                declared.insert((*address, module.clone(), s.name.clone()));
+            }
+        }
+        for (address, module, structs) in &modules {
+            for s in structs {
+                for field in &s.fields {
+                    check_field_type(&declared, &field.type_).map_err(|e| {
+                        anyhow!(
+                            "Invalid field {} of {}::{}::{}: {}",
+                            field.name,
+                            address_str(address),
+                            module,
+                            s.name,
+                            e
+                        )
+                    })?;
+                }
+            }
+        }
+
+        // A type parameter is kept if it occurs in a field, outside of the type parameters left
+        // out of the struct types using it. Start from none and add them until nothing changes.
+        let mut type_parameters = declared
+            .into_iter()
+            .map(|key| (key, vec![]))
+            .collect::<BTreeMap<_, _>>();
+        loop {
+            let mut changed = false;
+            for (address, module, structs) in &modules {
+                for s in structs {
+                    let mut used = BTreeSet::new();
+                    for field in &s.fields {
+                        used_type_parameters(&type_parameters, &field.type_, &mut used)
+                    }
+                    let key = (*address, module.clone(), s.name.clone());
+                    let kept = type_parameters.get_mut(&key).unwrap();
+                    if kept.len() != used.len() {
+                        *kept = used.into_iter().collect();
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        let modules = modules
+            .into_iter()
+            .filter(|(_, _, structs)| !structs.is_empty())
+            .map(|(address, name, structs)| {
+                let structs = structs
+                    .into_iter()
+                    .map(|s| StructBinding {
+                        type_parameters: type_parameters[&(address, name.clone(), s.name.clone())]
+                            .clone(),
+                        name: s.name,
+                        kind: s.kind,
+                        num_type_parameters: s.type_parameters.len(),
+                        fields: s.fields,
+                    })
+                    .collect();
+                ModuleStructs {
+                    address,
+                    name,
+                    structs,
+                }
+            })
+            .collect();
+        Ok(Self {
+            modules,
+            type_parameters,
+        })
+    }
+
+    /// The type arguments of the struct type `ty` that are kept by the generated type, or none if
+    /// `ty` is not a struct type.
+    pub fn kept_type_arguments<'a>(&self, ty: &'a Type) -> Vec<&'a Type> {
+        match ty {
+            Type::Struct {
+                address,
+                module,
+                name,
+                type_arguments,
+            } => self.type_parameters[&(*address, module.clone(), name.clone())]
+                .iter()
+                .map(|idx| &type_arguments[*idx as usize])
+                .collect(),
+            _ => vec![],
+        }
+    }
+}
+
+/// Check that `ty` can be the type of a field and that the structs it uses are `declared`.
+fn check_field_type(
+    declared: &BTreeSet<(AccountAddress, Identifier, Identifier)>,
+    ty: &Type,
+) -> Result<()> {
+    match ty {
+        Type::Bool | Type::U8 | Type::U64 | Type::U128 | Type::Address | Type::TypeParameter(_) => {
+            Ok(())
+        }
+        Type::Vector(ty) => check_field_type(declared, ty),
+        Type::Struct {
+            address,
+            module,
+            name,
+            type_arguments,
+        } => {
+            if !declared.contains(&(*address, module.clone(), name.clone())) {
+                bail!(
+                    "struct {}::{}::{} is not declared by the modules read",
+                    address_str(address),
+                    module,
+                    name
+                )
+            }
+            type_arguments
+                .iter()
+                .try_for_each(|ty| check_field_type(declared, ty))
+        }
+        Type::Signer | Type::Reference(_) | Type::MutableReference(_) => {
+            bail!("type {:?} cannot be stored", ty)
+        }
+    }
+}
+
+fn used_type_parameters(
+    type_parameters: &BTreeMap<(AccountAddress, Identifier, Identifier), Vec<TypeParameterIndex>>,
+    ty: &Type,
+    used: &mut BTreeSet<TypeParameterIndex>,
+) {
+    match ty {
+        Type::TypeParameter(idx) => {
+            used.insert(*idx);
+        }
+        Type::Vector(ty) => used_type_parameters(type_parameters, ty, used),
+        Type::Struct {
+            address,
+            module,
+            name,
+            type_arguments,
+        } => {
+            for idx in &type_parameters[&(*address, module.clone(), name.clone())] {
+                used_type_parameters(type_parameters, &type_arguments[*idx as usize], used)
+            }
+        }
+        _ => (),
+    }
+}
+
+/// The name of a type parameter in the generated code.
+fn type_parameter_name(idx: TypeParameterIndex) -> String {
+    format!("T{}", idx)
+}
+
+/// `address` as a hex literal without leading zeros, e.g. `0x1`.
+fn address_str(address: &AccountAddress) -> String {
+    let hex = format!("{:x}", address);
+    let hex = hex.trim_start_matches('0');
+    format!("0x{}", if hex.is_empty() { "0" } else { hex })
+}
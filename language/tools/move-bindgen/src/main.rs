@@ -0,0 +1,57 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+//! # Code generator for the structs of Move modules
+//!
+//! '''bash
+//! cargo run -p move-bindgen -- --help
+//! '''
+
+use move_bindgen::{read_modules, rust, typescript, Bindings};
+use std::{fs::File, io::Write, path::PathBuf};
+use structopt::{clap::arg_enum, StructOpt};
+
+arg_enum! {
+#[derive(Debug, StructOpt)]
+enum Language {
+    Rust,
+    TypeScript,
+}
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "Move bindings generator",
+    about = "Generate types for the structs of compiled Move modules"
+)]
+struct Options {
+    /// Compiled modules, or directories containing them. The structs used by the fields of their
+    /// structs must be declared by these modules as well.
+    #[structopt(required = true)]
+    modules: Vec<PathBuf>,
+
+    /// Language for code generation.
+    #[structopt(long, possible_values = &Language::variants(), case_insensitive = true, default_value = "Rust")]
+    language: Language,
+
+    /// File where to write the generated code (otherwise print code on stdout).
+    #[structopt(long)]
+    output: Option<PathBuf>,
+}
+
+fn main() {
+    let options = Options::from_args();
+    let modules = read_modules(&options.modules).expect("Failed to read modules");
+    let bindings = Bindings::new(&modules).expect("Failed to collect the structs of the modules");
+
+    let mut out: Box<dyn Write> = match &options.output {
+        Some(path) => Box::new(File::create(path).expect("Failed to create the output file")),
+        None => Box::new(std::io::stdout()),
+    };
+    match options.language {
+        Language::Rust => rust::output(&mut out, &bindings).unwrap(),
+        Language::TypeScript => typescript::output(&mut out, &bindings).unwrap(),
+    }
+}
@@ -0,0 +1,240 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{address_str, type_parameter_name, Bindings, ModuleStructs, StructBinding};
+use heck::SnakeCase;
+use move_core_types::language_storage::CORE_CODE_ADDRESS;
+use serde_generate::indent::{IndentConfig, IndentedWriter};
+use std::{
+    collections::BTreeMap,
+    io::{Error, ErrorKind, Result, Write},
+};
+use vm::normalized::Type;
+
+/// Rust keywords that are valid Move identifiers, and need to be written as raw identifiers.
+const KEYWORDS: &[&str] = &[
+    "async", "await", "box", "const", "crate", "do", "dyn", "enum", "extern", "final", "fn", "for",
+    "impl", "in", "macro", "match", "mod", "mut", "override", "priv", "pub", "ref", "static",
+    "super", "trait", "try", "type", "typeof", "unsafe", "unsized", "virtual", "where", "yield",
+];
+
+/// Output a Rust module for each Move module with structs in `bindings`. The types derive the
+/// `Serialize` and `Deserialize` traits of `serde`, so that they can be decoded from their LCS
+/// encoding. The generated code uses the crates `serde`, `move-core-types`, and
+/// `libra-canonical-serialization` imported as `lcs`.
+pub fn output(out: &mut dyn Write, bindings: &Bindings) -> Result<()> {
+    let mut module_names = BTreeMap::new();
+    for module in &bindings.modules {
+        let name = module_name(module);
+        if let Some(other) = module_names.insert(name.clone(), module) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Modules {}::{} and {}::{} would both be generated as `{}`",
+                    address_str(&other.address),
+                    other.name,
+                    address_str(&module.address),
+                    module.name,
+                    name
+                ),
+            ));
+        }
+    }
+
+    let mut emitter = RustEmitter {
+        out: IndentedWriter::new(out, IndentConfig::Space(4)),
+        bindings,
+    };
+    writeln!(
+        emitter.out,
+        "// This file was generated by move-bindgen. Do not modify!"
+    )?;
+    for module in &bindings.modules {
+        emitter.output_module(module)?;
+    }
+    Ok(())
+}
+
+/// Shared state for the Rust code generator.
+struct RustEmitter<'a, T> {
+    /// Writer.
+    out: IndentedWriter<T>,
+    bindings: &'a Bindings,
+}
+
+impl<'a, T> RustEmitter<'a, T>
+where
+    T: Write,
+{
+    fn output_module(&mut self, module: &ModuleStructs) -> Result<()> {
+        writeln!(self.out, "\npub mod {} {{", module_name(module))?;
+        self.out.indent();
+        let uses_addresses = module
+            .structs
+            .iter()
+            .any(|s| s.fields.iter().any(|f| self.uses_address(&f.type_)));
+        let is_resource =
+            |s: &StructBinding| module.address == CORE_CODE_ADDRESS && s.num_type_parameters == 0;
+        if uses_addresses {
+            writeln!(
+                self.out,
+                "use move_core_types::account_address::AccountAddress;"
+            )?;
+        }
+        if module.structs.iter().any(is_resource) {
+            writeln!(
+                self.out,
+                "use move_core_types::move_resource::MoveResource;"
+            )?;
+        }
+        writeln!(self.out, "use serde::{{Deserialize, Serialize}};")?;
+
+        for s in &module.structs {
+            self.output_struct(module, s)?;
+            if s.type_parameters.is_empty() {
+                self.output_lcs_methods(s)?;
+            }
+            if is_resource(s) {
+                self.output_move_resource(module, s)?;
+            }
+        }
+        self.out.unindent();
+        writeln!(self.out, "}}")
+    }
+
+    fn output_struct(&mut self, module: &ModuleStructs, s: &StructBinding) -> Result<()> {
+        writeln!(
+            self.out,
+            "\n/// `{}::{}::{}`",
+            address_str(&module.address),
+            module.name,
+            s.name
+        )?;
+        writeln!(
+            self.out,
+            "#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]"
+        )?;
+        writeln!(self.out, "pub struct {}{} {{", s.name, type_parameters(s))?;
+        self.out.indent();
+        for field in &s.fields {
+            writeln!(
+                self.out,
+                "pub {}: {},",
+                identifier(field.name.as_str()),
+                self.quote_type(module, &field.type_)
+            )?;
+        }
+        self.out.unindent();
+        writeln!(self.out, "}}")
+    }
+
+    fn output_lcs_methods(&mut self, s: &StructBinding) -> Result<()> {
+        writeln!(
+            self.out,
+            r#"
+impl {} {{
+    pub fn lcs_serialize(&self) -> Result<Vec<u8>, lcs::Error> {{
+        lcs::to_bytes(self)
+    }}
+
+    pub fn lcs_deserialize(input: &[u8]) -> Result<Self, lcs::Error> {{
+        lcs::from_bytes(input)
+    }}
+}}"#,
+            s.name
+        )
+    }
+
+    fn output_move_resource(&mut self, module: &ModuleStructs, s: &StructBinding) -> Result<()> {
+        writeln!(
+            self.out,
+            r#"
+impl MoveResource for {} {{
+    const MODULE_NAME: &'static str = "{}";
+    const STRUCT_NAME: &'static str = "{}";
+}}"#,
+            s.name, module.name, s.name
+        )
+    }
+
+    /// Whether the Rust type of the field type `ty` mentions `AccountAddress`.
+    fn uses_address(&self, ty: &Type) -> bool {
+        match ty {
+            Type::Address => true,
+            Type::Vector(ty) => self.uses_address(ty),
+            Type::Struct { .. } => self
+                .bindings
+                .kept_type_arguments(ty)
+                .into_iter()
+                .any(|ty| self.uses_address(ty)),
+            _ => false,
+        }
+    }
+
+    /// The Rust type of the field type `ty`, in the module generated for `module`.
+    fn quote_type(&self, module: &ModuleStructs, ty: &Type) -> String {
+        match ty {
+            Type::Bool => "bool".into(),
+            Type::U8 => "u8".into(),
+            Type::U64 => "u64".into(),
+            Type::U128 => "u128".into(),
+            Type::Address => "AccountAddress".into(),
+            Type::Vector(ty) => format!("Vec<{}>", self.quote_type(module, ty)),
+            Type::TypeParameter(idx) => type_parameter_name(*idx),
+            Type::Struct {
+                address,
+                module: struct_module,
+                name,
+                ..
+            } => {
+                let path = if *address == module.address && *struct_module == module.name {
+                    name.to_string()
+                } else {
+                    format!(
+                        "super::{}::{}",
+                        struct_module.as_str().to_snake_case(),
+                        name
+                    )
+                };
+                let type_arguments = self.bindings.kept_type_arguments(ty);
+                if type_arguments.is_empty() {
+                    path
+                } else {
+                    let type_arguments = type_arguments
+                        .into_iter()
+                        .map(|ty| self.quote_type(module, ty))
+                        .collect::<Vec<_>>();
+                    format!("{}<{}>", path, type_arguments.join(", "))
+                }
+            }
+            Type::Signer | Type::Reference(_) | Type::MutableReference(_) => {
+                unreachable!("Bindings only contain fields with storable types")
+            }
+        }
+    }
+}
+
+fn module_name(module: &ModuleStructs) -> String {
+    module.name.as_str().to_snake_case()
+}
+
+fn type_parameters(s: &StructBinding) -> String {
+    if s.type_parameters.is_empty() {
+        String::new()
+    } else {
+        let names = s
+            .type_parameters
+            .iter()
+            .map(|idx| type_parameter_name(*idx))
+            .collect::<Vec<_>>();
+        format!("<{}>", names.join(", "))
+    }
+}
+
+fn identifier(name: &str) -> String {
+    if KEYWORDS.contains(&name) {
+        format!("r#{}", name)
+    } else {
+        name.to_string()
+    }
+}
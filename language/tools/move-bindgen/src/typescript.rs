@@ -0,0 +1,579 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{address_str, type_parameter_name, Bindings, ModuleStructs, StructBinding};
+use move_core_types::identifier::Identifier;
+use serde_generate::indent::{IndentConfig, IndentedWriter};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{Error, ErrorKind, Result, Write},
+};
+use vm::normalized::Type;
+
+/// TypeScript reserved words that are valid Move identifiers, and cannot name a parameter.
+const RESERVED_WORDS: &[&str] = &[
+    "case",
+    "catch",
+    "class",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "enum",
+    "export",
+    "extends",
+    "finally",
+    "for",
+    "function",
+    "implements",
+    "in",
+    "instanceof",
+    "interface",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "with",
+    "yield",
+];
+
+/// The LCS runtime the generated classes use. Integers that do not fit in a `number` without loss
+/// of precision, i.e. `u64` and `u128`, are represented as `bigint`s.
+const RUNTIME: &str = r#"
+/** A Libra account address, 16 bytes long */
+export type AccountAddress = Uint8Array;
+
+const ADDRESS_LENGTH = 16;
+const MAX_SEQUENCE_LENGTH = 2 ** 31 - 1;
+
+export class LcsSerializer {
+    private bytes: number[] = [];
+
+    serializeBool(value: boolean): void {
+        this.bytes.push(value ? 1 : 0);
+    }
+
+    serializeU8(value: number): void {
+        this.bytes.push(value & 0xff);
+    }
+
+    serializeU64(value: bigint): void {
+        this.serializeUint(value, 8);
+    }
+
+    serializeU128(value: bigint): void {
+        this.serializeUint(value, 16);
+    }
+
+    /** Serialize the length of a sequence, in ULEB128 */
+    serializeLen(length: number): void {
+        if (length > MAX_SEQUENCE_LENGTH) {
+            throw new Error("Sequence too long");
+        }
+        while (length >= 0x80) {
+            this.bytes.push((length & 0x7f) | 0x80);
+            length = Math.floor(length / 0x80);
+        }
+        this.bytes.push(length);
+    }
+
+    serializeBytes(value: Uint8Array): void {
+        this.serializeLen(value.length);
+        value.forEach((byte) => this.bytes.push(byte));
+    }
+
+    serializeAddress(value: AccountAddress): void {
+        if (value.length !== ADDRESS_LENGTH) {
+            throw new Error("Invalid address length");
+        }
+        value.forEach((byte) => this.bytes.push(byte));
+    }
+
+    serializeVector<T>(values: T[], serializeElement: (serializer: LcsSerializer, value: T) => void): void {
+        this.serializeLen(values.length);
+        values.forEach((value) => serializeElement(this, value));
+    }
+
+    getBytes(): Uint8Array {
+        return new Uint8Array(this.bytes);
+    }
+
+    private serializeUint(value: bigint, size: number): void {
+        for (let i = 0; i < size; i++) {
+            this.bytes.push(Number(value & BigInt(0xff)));
+            value >>= BigInt(8);
+        }
+    }
+}
+
+export class LcsDeserializer {
+    private offset = 0;
+
+    constructor(private readonly bytes: Uint8Array) {}
+
+    deserializeBool(): boolean {
+        const value = this.deserializeU8();
+        if (value > 1) {
+            throw new Error("Invalid bool");
+        }
+        return value === 1;
+    }
+
+    deserializeU8(): number {
+        if (this.offset >= this.bytes.length) {
+            throw new Error("Unexpected end of input");
+        }
+        return this.bytes[this.offset++];
+    }
+
+    deserializeU64(): bigint {
+        return this.deserializeUint(8);
+    }
+
+    deserializeU128(): bigint {
+        return this.deserializeUint(16);
+    }
+
+    /** Deserialize the length of a sequence, which must be in canonical ULEB128 */
+    deserializeLen(): number {
+        let length = 0;
+        for (let shift = 0; shift < 32; shift += 7) {
+            const byte = this.deserializeU8();
+            length += (byte & 0x7f) * 2 ** shift;
+            if ((byte & 0x80) === 0) {
+                if (shift > 0 && byte === 0) {
+                    throw new Error("Non-canonical sequence length");
+                }
+                if (length > MAX_SEQUENCE_LENGTH) {
+                    throw new Error("Sequence too long");
+                }
+                return length;
+            }
+        }
+        throw new Error("Sequence too long");
+    }
+
+    deserializeBytes(): Uint8Array {
+        return this.deserializeFixedBytes(this.deserializeLen());
+    }
+
+    deserializeAddress(): AccountAddress {
+        return this.deserializeFixedBytes(ADDRESS_LENGTH);
+    }
+
+    deserializeVector<T>(deserializeElement: (deserializer: LcsDeserializer) => T): T[] {
+        const length = this.deserializeLen();
+        const values: T[] = [];
+        for (let i = 0; i < length; i++) {
+            values.push(deserializeElement(this));
+        }
+        return values;
+    }
+
+    /** The number of bytes left to deserialize */
+    remaining(): number {
+        return this.bytes.length - this.offset;
+    }
+
+    private deserializeFixedBytes(length: number): Uint8Array {
+        if (this.offset + length > this.bytes.length) {
+            throw new Error("Unexpected end of input");
+        }
+        const value = this.bytes.slice(this.offset, this.offset + length);
+        this.offset += length;
+        return value;
+    }
+
+    private deserializeUint(size: number): bigint {
+        let value = BigInt(0);
+        for (let i = 0; i < size; i++) {
+            value |= BigInt(this.deserializeU8()) << BigInt(8 * i);
+        }
+        return value;
+    }
+}"#;
+
+/// Output a TypeScript namespace for each Move module with structs in `bindings`, containing a
+/// class for each struct with methods to serialize and deserialize it in LCS. The LCS runtime is
+/// included in the output, so that it has no dependencies.
+pub fn output(out: &mut dyn Write, bindings: &Bindings) -> Result<()> {
+    let mut namespaces = BTreeMap::new();
+    for module in &bindings.modules {
+        if let Some(other) = namespaces.insert(&module.name, module) {
+            return Err(invalid_input(format!(
+                "Modules {}::{} and {}::{} would both be generated as `{}`",
+                address_str(&other.address),
+                other.name,
+                address_str(&module.address),
+                module.name,
+                module.name
+            )));
+        }
+    }
+    // A class named after another namespace would hide that namespace in its own namespace
+    for module in &bindings.modules {
+        let mut used_namespaces = BTreeSet::new();
+        for s in &module.structs {
+            for field in &s.fields {
+                used_modules(bindings, &field.type_, &mut used_namespaces)
+            }
+        }
+        for s in &module.structs {
+            if s.name != module.name && used_namespaces.contains(&s.name) {
+                return Err(invalid_input(format!(
+                    "Struct {}::{}::{} would hide the namespace of module {}",
+                    address_str(&module.address),
+                    module.name,
+                    s.name,
+                    s.name
+                )));
+            }
+        }
+    }
+
+    let mut emitter = TypeScriptEmitter {
+        out: IndentedWriter::new(out, IndentConfig::Space(4)),
+        bindings,
+        current_module: None,
+    };
+    writeln!(
+        emitter.out,
+        "// This file was generated by move-bindgen. Do not modify!"
+    )?;
+    writeln!(emitter.out, "{}", RUNTIME)?;
+    for module in &bindings.modules {
+        emitter.output_module(module)?;
+    }
+    Ok(())
+}
+
+/// Shared state for the TypeScript code generator.
+struct TypeScriptEmitter<'a, T> {
+    /// Writer.
+    out: IndentedWriter<T>,
+    bindings: &'a Bindings,
+    /// The name of the module whose namespace is being written.
+    current_module: Option<Identifier>,
+}
+
+impl<'a, T> TypeScriptEmitter<'a, T>
+where
+    T: Write,
+{
+    fn output_module(&mut self, module: &ModuleStructs) -> Result<()> {
+        writeln!(self.out, "\nexport namespace {} {{", module.name)?;
+        self.out.indent();
+        self.current_module = Some(module.name.clone());
+        for (i, s) in module.structs.iter().enumerate() {
+            if i > 0 {
+                writeln!(self.out)?;
+            }
+            self.output_class(module, s)?;
+        }
+        self.out.unindent();
+        writeln!(self.out, "}}")
+    }
+
+    fn output_class(&mut self, module: &ModuleStructs, s: &StructBinding) -> Result<()> {
+        let type_parameters = s
+            .type_parameters
+            .iter()
+            .map(|idx| type_parameter_name(*idx))
+            .collect::<Vec<_>>();
+        let generics = if type_parameters.is_empty() {
+            String::new()
+        } else {
+            format!("<{}>", type_parameters.join(", "))
+        };
+        let class_type = format!("{}{}", s.name, generics);
+
+        writeln!(
+            self.out,
+            "/** `{}::{}::{}` */",
+            address_str(&module.address),
+            module.name,
+            s.name
+        )?;
+        writeln!(self.out, "export class {} {{", class_type)?;
+        self.out.indent();
+
+        // constructor
+        if s.fields.is_empty() {
+            writeln!(self.out, "constructor() {{}}")?;
+        } else {
+            writeln!(self.out, "constructor(")?;
+            self.out.indent();
+            for field in &s.fields {
+                writeln!(
+                    self.out,
+                    "public {}: {},",
+                    identifier(field.name.as_str()),
+                    self.quote_type(&field.type_)
+                )?;
+            }
+            self.out.unindent();
+            writeln!(self.out, ") {{}}")?;
+        }
+
+        // serialize
+        let serialize_parameters = type_parameters
+            .iter()
+            .map(|name| {
+                format!(
+                    ", serialize{}: (serializer: LcsSerializer, value: {}) => void",
+                    name, name
+                )
+            })
+            .collect::<String>();
+        writeln!(
+            self.out,
+            "\nserialize(serializer: LcsSerializer{}): void {{",
+            serialize_parameters
+        )?;
+        self.out.indent();
+        for field in &s.fields {
+            let value = format!("this.{}", identifier(field.name.as_str()));
+            writeln!(
+                self.out,
+                "{};",
+                self.serialize(&field.type_, "serializer", &value)
+            )?;
+        }
+        self.out.unindent();
+        writeln!(self.out, "}}")?;
+
+        // deserialize
+        let deserialize_parameters = type_parameters
+            .iter()
+            .map(|name| {
+                format!(
+                    ", deserialize{}: (deserializer: LcsDeserializer) => {}",
+                    name, name
+                )
+            })
+            .collect::<String>();
+        writeln!(
+            self.out,
+            "\nstatic deserialize{}(deserializer: LcsDeserializer{}): {} {{",
+            generics, deserialize_parameters, class_type
+        )?;
+        self.out.indent();
+        for field in &s.fields {
+            writeln!(
+                self.out,
+                "const {} = {};",
+                identifier(field.name.as_str()),
+                self.deserialize(&field.type_, "deserializer")
+            )?;
+        }
+        let arguments = s
+            .fields
+            .iter()
+            .map(|field| identifier(field.name.as_str()))
+            .collect::<Vec<_>>();
+        writeln!(
+            self.out,
+            "return new {}{}({});",
+            s.name,
+            generics,
+            arguments.join(", ")
+        )?;
+        self.out.unindent();
+        writeln!(self.out, "}}")?;
+
+        if type_parameters.is_empty() {
+            self.output_lcs_methods(s)?;
+        }
+        self.out.unindent();
+        writeln!(self.out, "}}")
+    }
+
+    fn output_lcs_methods(&mut self, s: &StructBinding) -> Result<()> {
+        writeln!(
+            self.out,
+            r#"
+lcsSerialize(): Uint8Array {{
+    const serializer = new LcsSerializer();
+    this.serialize(serializer);
+    return serializer.getBytes();
+}}
+
+static lcsDeserialize(input: Uint8Array): {} {{
+    const deserializer = new LcsDeserializer(input);
+    const value = {}.deserialize(deserializer);
+    if (deserializer.remaining() !== 0) {{
+        throw new Error("Some input bytes were not read");
+    }}
+    return value;
+}}"#,
+            s.name, s.name
+        )
+    }
+
+    /// How to refer to the class of the struct `module::name` from the current namespace.
+    fn class_path(&self, module: &Identifier, name: &Identifier) -> String {
+        if Some(module) == self.current_module.as_ref() {
+            name.to_string()
+        } else {
+            format!("{}.{}", module, name)
+        }
+    }
+
+    /// The TypeScript type of the field type `ty`.
+    fn quote_type(&self, ty: &Type) -> String {
+        match ty {
+            Type::Bool => "boolean".into(),
+            Type::U8 => "number".into(),
+            Type::U64 | Type::U128 => "bigint".into(),
+            Type::Address => "AccountAddress".into(),
+            Type::Vector(ty) if **ty == Type::U8 => "Uint8Array".into(),
+            Type::Vector(ty) => format!("{}[]", self.quote_type(ty)),
+            Type::TypeParameter(idx) => type_parameter_name(*idx),
+            Type::Struct { module, name, .. } => {
+                let type_arguments = self.bindings.kept_type_arguments(ty);
+                if type_arguments.is_empty() {
+                    self.class_path(module, name)
+                } else {
+                    let type_arguments = type_arguments
+                        .into_iter()
+                        .map(|ty| self.quote_type(ty))
+                        .collect::<Vec<_>>();
+                    format!(
+                        "{}<{}>",
+                        self.class_path(module, name),
+                        type_arguments.join(", ")
+                    )
+                }
+            }
+            Type::Signer | Type::Reference(_) | Type::MutableReference(_) => {
+                unreachable!("Bindings only contain fields with storable types")
+            }
+        }
+    }
+
+    /// An expression serializing `value` of type `ty` with `serializer`.
+    fn serialize(&self, ty: &Type, serializer: &str, value: &str) -> String {
+        match ty {
+            Type::Bool => format!("{}.serializeBool({})", serializer, value),
+            Type::U8 => format!("{}.serializeU8({})", serializer, value),
+            Type::U64 => format!("{}.serializeU64({})", serializer, value),
+            Type::U128 => format!("{}.serializeU128({})", serializer, value),
+            Type::Address => format!("{}.serializeAddress({})", serializer, value),
+            Type::Vector(ty) if **ty == Type::U8 => {
+                format!("{}.serializeBytes({})", serializer, value)
+            }
+            Type::Vector(ty) => format!(
+                "{}.serializeVector({}, {})",
+                serializer,
+                value,
+                self.serialize_function(ty)
+            ),
+            Type::TypeParameter(idx) => format!(
+                "serialize{}({}, {})",
+                type_parameter_name(*idx),
+                serializer,
+                value
+            ),
+            Type::Struct { .. } => {
+                let functions = self
+                    .bindings
+                    .kept_type_arguments(ty)
+                    .into_iter()
+                    .map(|ty| format!(", {}", self.serialize_function(ty)))
+                    .collect::<String>();
+                format!("{}.serialize({}{})", value, serializer, functions)
+            }
+            Type::Signer | Type::Reference(_) | Type::MutableReference(_) => {
+                unreachable!("Bindings only contain fields with storable types")
+            }
+        }
+    }
+
+    /// A function serializing values of type `ty`.
+    fn serialize_function(&self, ty: &Type) -> String {
+        format!(
+            "(s: LcsSerializer, v: {}) => {}",
+            self.quote_type(ty),
+            self.serialize(ty, "s", "v")
+        )
+    }
+
+    /// An expression deserializing a value of type `ty` with `deserializer`.
+    fn deserialize(&self, ty: &Type, deserializer: &str) -> String {
+        match ty {
+            Type::Bool => format!("{}.deserializeBool()", deserializer),
+            Type::U8 => format!("{}.deserializeU8()", deserializer),
+            Type::U64 => format!("{}.deserializeU64()", deserializer),
+            Type::U128 => format!("{}.deserializeU128()", deserializer),
+            Type::Address => format!("{}.deserializeAddress()", deserializer),
+            Type::Vector(ty) if **ty == Type::U8 => format!("{}.deserializeBytes()", deserializer),
+            Type::Vector(ty) => format!(
+                "{}.deserializeVector({})",
+                deserializer,
+                self.deserialize_function(ty)
+            ),
+            Type::TypeParameter(idx) => {
+                format!("deserialize{}({})", type_parameter_name(*idx), deserializer)
+            }
+            Type::Struct { module, name, .. } => {
+                let functions = self
+                    .bindings
+                    .kept_type_arguments(ty)
+                    .into_iter()
+                    .map(|ty| format!(", {}", self.deserialize_function(ty)))
+                    .collect::<String>();
+                format!(
+                    "{}.deserialize({}{})",
+                    self.class_path(module, name),
+                    deserializer,
+                    functions
+                )
+            }
+            Type::Signer | Type::Reference(_) | Type::MutableReference(_) => {
+                unreachable!("Bindings only contain fields with storable types")
+            }
+        }
+    }
+
+    /// A function deserializing values of type `ty`.
+    fn deserialize_function(&self, ty: &Type) -> String {
+        format!("(d: LcsDeserializer) => {}", self.deserialize(ty, "d"))
+    }
+}
+
+/// Add the modules declaring the structs used by the TypeScript type of `ty` to `modules`.
+fn used_modules(bindings: &Bindings, ty: &Type, modules: &mut BTreeSet<Identifier>) {
+    match ty {
+        Type::Vector(ty) => used_modules(bindings, ty, modules),
+        Type::Struct { module, .. } => {
+            modules.insert(module.clone());
+            for ty in bindings.kept_type_arguments(ty) {
+                used_modules(bindings, ty, modules)
+            }
+        }
+        _ => (),
+    }
+}
+
+fn invalid_input(message: String) -> Error {
+    Error::new(ErrorKind::InvalidInput, message)
+}
+
+/// `name` as a TypeScript identifier. Reserved words are suffixed with `_`.
+fn identifier(name: &str) -> String {
+    if RESERVED_WORDS.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.to_string()
+    }
+}
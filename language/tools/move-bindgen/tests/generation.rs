@@ -0,0 +1,156 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use libra_types::account_config::SentPaymentEvent;
+use move_bindgen::{read_modules, rust, typescript, Bindings};
+use move_core_types::{account_address::AccountAddress, identifier::Identifier};
+use std::{io::Write, process::Command};
+use tempfile::tempdir;
+
+fn get_stdlib_bindings() -> Bindings {
+    let modules = read_modules(&["../../stdlib/compiled/stdlib"]).unwrap();
+    Bindings::new(&modules).unwrap()
+}
+
+/// The LCS encoding of the event built by the demos, using the hand-written type of `libra-types`
+fn expected_output() -> String {
+    let event = SentPaymentEvent::new(
+        1234567,
+        Identifier::new("LBR").unwrap(),
+        AccountAddress::new([0x22; AccountAddress::LENGTH]),
+        vec![1, 2, 3],
+    );
+    let bytes: Vec<_> = lcs::to_bytes(&event)
+        .unwrap()
+        .iter()
+        .map(|byte| byte.to_string())
+        .collect();
+    format!("{}\n", bytes.join(" "))
+}
+
+#[test]
+fn test_phantom_type_parameters_are_left_out() {
+    let bindings = get_stdlib_bindings();
+    let mut output = vec![];
+    rust::output(&mut output, &bindings).unwrap();
+    let output = String::from_utf8(output).unwrap();
+
+    assert!(output.contains(
+        r#"
+    /// `0x1::LibraAccount::Balance`
+    #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+    pub struct Balance {
+        pub coin: super::libra::Libra,
+    }
+"#
+    ));
+    assert!(output.contains(
+        r#"
+    /// `0x1::Option::Option`
+    #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+    pub struct Option<T0> {
+        pub vec: Vec<T0>,
+    }
+"#
+    ));
+    // resources with type parameters cannot implement `MoveResource`
+    assert!(!output.contains("impl MoveResource for Balance"));
+    assert!(output.contains(
+        r#"
+    impl MoveResource for Dealer {
+        const MODULE_NAME: &'static str = "DesignatedDealer";
+        const STRUCT_NAME: &'static str = "Dealer";
+    }
+"#
+    ));
+}
+
+#[test]
+fn test_that_rust_code_compiles_and_demo_runs() {
+    let bindings = get_stdlib_bindings();
+    let dir = tempdir().unwrap();
+
+    let mut cargo = std::fs::File::create(&dir.path().join("Cargo.toml")).unwrap();
+    let current_dir = std::env::current_dir().unwrap();
+    write!(
+        cargo,
+        r#"[package]
+name = "move-bindings"
+version = "0.1.0"
+edition = "2018"
+
+[dependencies]
+serde = {{ version = "1.0.116", features = ["derive"] }}
+lcs = {{ path = "{}", version = "0.1.0", package = "libra-canonical-serialization" }}
+move-core-types = {{ path = "{}", version = "0.1.0" }}
+
+[[bin]]
+name = "move_bindings_demo"
+path = "src/demo.rs"
+test = false
+"#,
+        current_dir.join("../../../common/lcs").to_string_lossy(),
+        current_dir.join("../../move-core/types").to_string_lossy(),
+    )
+    .unwrap();
+    std::fs::create_dir(dir.path().join("src")).unwrap();
+    let mut source = std::fs::File::create(&dir.path().join("src/lib.rs")).unwrap();
+    rust::output(&mut source, &bindings).unwrap();
+    std::fs::copy("examples/rust/demo.rs", dir.path().join("src/demo.rs")).unwrap();
+
+    // Use a stable `target` dir to avoid downloading and recompiling crates everytime.
+    let target_dir = current_dir.join("../../../target");
+    let status = Command::new("cargo")
+        .current_dir(dir.path())
+        .arg("build")
+        .arg("--target-dir")
+        .arg(target_dir.clone())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let output = Command::new(target_dir.join("debug/move_bindings_demo"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        std::str::from_utf8(&output.stdout).unwrap(),
+        expected_output()
+    );
+}
+
+// Cannot run this test in the CI of Libra.
+#[test]
+#[ignore]
+fn test_that_typescript_code_compiles_and_demo_runs() {
+    let bindings = get_stdlib_bindings();
+    let dir = tempdir().unwrap();
+
+    let mut source = std::fs::File::create(&dir.path().join("move_bindings.ts")).unwrap();
+    typescript::output(&mut source, &bindings).unwrap();
+    std::fs::copy("examples/typescript/demo.ts", dir.path().join("demo.ts")).unwrap();
+
+    let status = Command::new(which::which("tsc").unwrap())
+        .current_dir(dir.path())
+        .arg("--strict")
+        .arg("--target")
+        .arg("es2020")
+        .arg("--module")
+        .arg("commonjs")
+        .arg("demo.ts")
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let output = Command::new("node")
+        .current_dir(dir.path())
+        .arg("demo.js")
+        .output()
+        .unwrap();
+    eprintln!("{}", std::str::from_utf8(&output.stderr).unwrap());
+    assert!(output.status.success());
+    assert_eq!(
+        std::str::from_utf8(&output.stdout).unwrap(),
+        expected_output()
+    );
+}
@@ -0,0 +1,639 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A source-level debugger for transaction scripts. The script is executed with the VM recording
+//! its state before every instruction in a trace, which the debugger then replays: instructions
+//! are mapped to source lines, and locals to their source names, with the source maps produced by
+//! the compiler. Code without a source map, e.g. modules published earlier, is stepped over.
+
+use bytecode_source_map::source_map::FunctionSourceMap;
+use move_core_types::language_storage::ModuleId;
+use move_ir_types::location::Loc;
+use move_lang::{
+    compiled_unit::CompiledUnit,
+    errors::FilesSourceText,
+    hlir::{display_var, DisplayVar},
+};
+use move_vm_runtime::debug_trace::DEBUG_TRACE_UNSET_VALUE;
+pub use move_vm_runtime::debug_trace::{read_trace, TraceStep};
+use vm::{
+    access::ModuleAccess,
+    file_format::{CodeOffset, FunctionDefinitionIndex},
+};
+
+use anyhow::{anyhow, bail, Result};
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{BufRead, Write},
+    path::Path,
+    str::FromStr,
+};
+
+/// Number of lines printed by `list` on each side of the current line
+const LIST_CONTEXT: usize = 5;
+
+const HELP: &str = "\
+Commands:
+  step, s                 run to the next source line, entering calls
+  next, n                 run to the next source line of the current function or its callers
+  finish, f               run until the current function returns
+  continue, c             run to the next breakpoint
+  break, b <location>     stop at <location>: <file>:<line>, <line> in the current file, or a
+                          function, as in `M::f`
+  delete, d <n>           remove breakpoint <n>
+  breakpoints             list the breakpoints
+  locals                  print the locals of the current function
+  stack                   print the operand stack
+  backtrace, bt           print the calls leading to the current function
+  list, l                 print the source around the current line
+  quit, q                 end the debugging session
+An empty line repeats the previous command.";
+
+//**************************************************************************************************
+// Source information
+//**************************************************************************************************
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SourceLine {
+    file: &'static str,
+    /// 1-based
+    line: usize,
+}
+
+struct FunctionInfo {
+    /// Name of the function as shown to the user
+    name: String,
+    /// Source line of the instructions starting at each code offset
+    code_map: BTreeMap<CodeOffset, SourceLine>,
+    /// Source name of each local, or `None` for temporaries introduced by the compiler
+    locals: Vec<Option<String>>,
+}
+
+/// The sources and source maps of the compiled units
+pub struct DebugInfo {
+    files: FilesSourceText,
+    /// Byte offset of the start of each line, by file
+    line_starts: HashMap<&'static str, Vec<usize>>,
+    /// By name, in the format of the trace
+    functions: BTreeMap<String, FunctionInfo>,
+}
+
+impl DebugInfo {
+    pub fn new(files: FilesSourceText, units: &[CompiledUnit]) -> Self {
+        let line_starts = files
+            .iter()
+            .map(|(fname, text)| {
+                let starts = std::iter::once(0)
+                    .chain(text.match_indices('\n').map(|(idx, _)| idx + 1))
+                    .collect();
+                (*fname, starts)
+            })
+            .collect();
+        let mut info = Self {
+            files,
+            line_starts,
+            functions: BTreeMap::new(),
+        };
+        for unit in units {
+            match unit {
+                CompiledUnit::Script { source_map, .. } => {
+                    if let Ok(function_map) =
+                        source_map.get_function_source_map(FunctionDefinitionIndex(0))
+                    {
+                        let function = info.function_info("main".to_string(), function_map);
+                        info.functions.insert("main".to_string(), function);
+                    }
+                }
+                CompiledUnit::Module {
+                    module, source_map, ..
+                } => {
+                    let module_id = module.self_id();
+                    for (idx, fdef) in module.function_defs().iter().enumerate() {
+                        let fname =
+                            module.identifier_at(module.function_handle_at(fdef.function).name);
+                        let function_map = match source_map
+                            .get_function_source_map(FunctionDefinitionIndex(idx as u16))
+                        {
+                            Ok(function_map) => function_map,
+                            Err(_) => continue,
+                        };
+                        let name = format!("{}::{}", module_id.name(), fname);
+                        let function = info.function_info(name, function_map);
+                        info.functions
+                            .insert(trace_name(&module_id, fname.as_str()), function);
+                    }
+                }
+            }
+        }
+        info
+    }
+
+    fn function_info(&self, name: String, function_map: &FunctionSourceMap<Loc>) -> FunctionInfo {
+        let code_map = function_map
+            .code_map
+            .iter()
+            .filter_map(|(offset, loc)| Some((*offset, self.source_line(loc)?)))
+            .collect();
+        let locals = function_map
+            .parameters
+            .iter()
+            .chain(&function_map.locals)
+            .map(|(name, _)| match display_var(name) {
+                DisplayVar::Orig(name) => Some(name),
+                DisplayVar::Tmp => None,
+            })
+            .collect();
+        FunctionInfo {
+            name,
+            code_map,
+            locals,
+        }
+    }
+
+    fn source_line(&self, loc: &Loc) -> Option<SourceLine> {
+        let starts = self.line_starts.get(loc.file())?;
+        let line = match starts.binary_search(&loc.span().start().to_usize()) {
+            Ok(idx) => idx + 1,
+            Err(idx) => idx,
+        };
+        Some(SourceLine {
+            file: loc.file(),
+            line,
+        })
+    }
+
+    fn line_text(&self, line: SourceLine) -> Option<&str> {
+        let text = self.files.get(line.file)?;
+        let starts = self.line_starts.get(line.file)?;
+        let start = *starts.get(line.line - 1)?;
+        let end = starts.get(line.line).copied().unwrap_or_else(|| text.len());
+        Some(text[start..end].trim_end())
+    }
+
+    fn line_count(&self, file: &str) -> usize {
+        self.line_starts.get(file).map_or(0, Vec::len)
+    }
+
+    /// The file named `name`, which may be given by any suffix of its path
+    fn find_file(&self, name: &str) -> Result<&'static str> {
+        let matches: Vec<&'static str> = self
+            .files
+            .keys()
+            .copied()
+            .filter(|fname| Path::new(fname).ends_with(name))
+            .collect();
+        match matches.as_slice() {
+            [fname] => Ok(*fname),
+            [] => bail!("Unknown file '{}'", name),
+            _ => bail!("Ambiguous file '{}': {}", name, matches.join(", ")),
+        }
+    }
+}
+
+/// The name of a module function in the trace
+fn trace_name(module_id: &ModuleId, function: &str) -> String {
+    format!("{}::{}", module_id, function)
+}
+
+/// Whether `name`, as given by the user, refers to the function named `function` in the trace
+fn function_matches(function: &str, name: &str) -> bool {
+    function == name || function.ends_with(&format!("::{}", name))
+}
+
+//**************************************************************************************************
+// Debugger
+//**************************************************************************************************
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Breakpoint {
+    Line(SourceLine),
+    Function(String),
+}
+
+#[derive(Debug, Clone)]
+enum Command {
+    Step,
+    Next,
+    Finish,
+    Continue,
+    Break(String),
+    Delete(usize),
+    Breakpoints,
+    Locals,
+    Stack,
+    Backtrace,
+    List,
+    Help,
+    Quit,
+}
+
+impl FromStr for Command {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let (name, arg) = match s.find(char::is_whitespace) {
+            Some(idx) => (&s[..idx], s[idx..].trim()),
+            None => (s, ""),
+        };
+        Ok(match name {
+            "step" | "s" => Command::Step,
+            "next" | "n" => Command::Next,
+            "finish" | "f" => Command::Finish,
+            "continue" | "c" => Command::Continue,
+            "break" | "b" => Command::Break(arg.to_owned()),
+            "delete" | "d" => Command::Delete(
+                arg.parse()
+                    .map_err(|_| anyhow!("Expected the number of a breakpoint"))?,
+            ),
+            "breakpoints" => Command::Breakpoints,
+            "locals" => Command::Locals,
+            "stack" => Command::Stack,
+            "backtrace" | "bt" => Command::Backtrace,
+            "list" | "l" => Command::List,
+            "help" | "h" => Command::Help,
+            "quit" | "q" => Command::Quit,
+            _ => bail!(
+                "Unknown command '{}'. Type `help` for a list of commands",
+                name
+            ),
+        })
+    }
+}
+
+/// Replays a trace, stopping at the source lines and breakpoints the user asks for
+pub struct Debugger {
+    trace: Vec<TraceStep>,
+    info: DebugInfo,
+    /// Index of the step the debugger is stopped at
+    current: usize,
+    /// By number
+    breakpoints: BTreeMap<usize, Breakpoint>,
+    next_breakpoint: usize,
+}
+
+impl Debugger {
+    pub fn new(trace: Vec<TraceStep>, info: DebugInfo) -> Self {
+        Self {
+            trace,
+            info,
+            current: 0,
+            breakpoints: BTreeMap::new(),
+            next_breakpoint: 1,
+        }
+    }
+
+    /// Stop at the first source line executed, then follow the commands read from `input` until
+    /// the user quits or the end of the trace is reached
+    pub fn run<R: BufRead, W: Write>(&mut self, mut input: R, mut output: W) -> Result<()> {
+        match (0..self.trace.len()).find(|idx| self.source_line(*idx).is_some()) {
+            Some(idx) => self.current = idx,
+            None => {
+                writeln!(output, "No source information for the executed code")?;
+                return Ok(());
+            }
+        }
+        writeln!(output, "{}", self.describe(self.current))?;
+        self.print_current_line(&mut output)?;
+
+        let mut prev_command = None;
+        loop {
+            write!(output, "(debug) ")?;
+            output.flush()?;
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                writeln!(output)?;
+                return Ok(());
+            }
+            let command = if line.trim().is_empty() {
+                match prev_command.take() {
+                    Some(command) => command,
+                    None => continue,
+                }
+            } else {
+                match line.parse::<Command>() {
+                    Ok(command) => command,
+                    Err(err) => {
+                        writeln!(output, "{}", err)?;
+                        continue;
+                    }
+                }
+            };
+            prev_command = Some(command.clone());
+            let stop = match command {
+                Command::Step => self.step(),
+                Command::Next => self.next(),
+                Command::Finish => self.finish(),
+                Command::Continue => self.continue_to_breakpoint(),
+                Command::Break(location) => {
+                    match self.parse_breakpoint(&location) {
+                        Ok(breakpoint) => {
+                            let number = self.next_breakpoint;
+                            self.next_breakpoint += 1;
+                            writeln!(
+                                output,
+                                "Breakpoint {} at {}",
+                                number,
+                                self.describe_breakpoint(&breakpoint)
+                            )?;
+                            self.breakpoints.insert(number, breakpoint);
+                        }
+                        Err(err) => writeln!(output, "{}", err)?,
+                    }
+                    continue;
+                }
+                Command::Delete(number) => {
+                    if self.breakpoints.remove(&number).is_none() {
+                        writeln!(output, "No breakpoint number {}", number)?
+                    }
+                    continue;
+                }
+                Command::Breakpoints => {
+                    if self.breakpoints.is_empty() {
+                        writeln!(output, "No breakpoints")?
+                    }
+                    for (number, breakpoint) in &self.breakpoints {
+                        writeln!(
+                            output,
+                            "[{}] {}",
+                            number,
+                            self.describe_breakpoint(breakpoint)
+                        )?
+                    }
+                    continue;
+                }
+                Command::Locals => {
+                    self.print_locals(&mut output)?;
+                    continue;
+                }
+                Command::Stack => {
+                    self.print_stack(&mut output)?;
+                    continue;
+                }
+                Command::Backtrace => {
+                    for (depth, idx) in self.frames().into_iter().enumerate() {
+                        writeln!(output, "#{} {}", depth, self.describe(idx))?
+                    }
+                    continue;
+                }
+                Command::List => {
+                    self.print_source(&mut output)?;
+                    continue;
+                }
+                Command::Help => {
+                    writeln!(output, "{}", HELP)?;
+                    continue;
+                }
+                Command::Quit => return Ok(()),
+            };
+            match stop {
+                Some(idx) => {
+                    self.current = idx;
+                    if let Some(number) = self.breakpoint_hit(idx) {
+                        write!(output, "Breakpoint {}, ", number)?
+                    }
+                    writeln!(output, "{}", self.describe(idx))?;
+                    self.print_current_line(&mut output)?;
+                }
+                None => {
+                    writeln!(output, "Reached the end of the execution")?;
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    //**********************************************************************************************
+    // Moving through the trace
+    //**********************************************************************************************
+
+    /// The next source line, in the current function, a function it calls, or its caller
+    fn step(&self) -> Option<usize> {
+        let depth = self.trace[self.current].depth;
+        let line = self.source_line(self.current);
+        self.find_after(|idx| {
+            let other_line = self.source_line(idx);
+            other_line.is_some() && (self.trace[idx].depth != depth || other_line != line)
+        })
+    }
+
+    /// The next source line in the current function or its caller
+    fn next(&self) -> Option<usize> {
+        let depth = self.trace[self.current].depth;
+        let line = self.source_line(self.current);
+        self.find_after(|idx| {
+            let other_depth = self.trace[idx].depth;
+            let other_line = self.source_line(idx);
+            other_line.is_some()
+                && other_depth <= depth
+                && (other_depth < depth || other_line != line)
+        })
+    }
+
+    /// The first source line after the current function returns
+    fn finish(&self) -> Option<usize> {
+        let depth = self.trace[self.current].depth;
+        self.find_after(|idx| self.trace[idx].depth < depth && self.source_line(idx).is_some())
+    }
+
+    fn continue_to_breakpoint(&self) -> Option<usize> {
+        self.find_after(|idx| self.breakpoint_hit(idx).is_some())
+    }
+
+    fn find_after(&self, mut pred: impl FnMut(usize) -> bool) -> Option<usize> {
+        (self.current + 1..self.trace.len()).find(|idx| pred(*idx))
+    }
+
+    /// The number of a breakpoint at the step `idx`. Line breakpoints are hit when execution
+    /// reaches the line, and function breakpoints when the function is called.
+    fn breakpoint_hit(&self, idx: usize) -> Option<usize> {
+        let step = &self.trace[idx];
+        let line = self.source_line(idx);
+        let prev = idx.checked_sub(1).map(|prev| &self.trace[prev]);
+        let enters_line = line.is_some()
+            && prev.map_or(true, |prev| {
+                prev.depth != step.depth
+                    || prev.function != step.function
+                    || self.source_line(idx - 1) != line
+            });
+        let enters_function = step.pc == 0 && prev.map_or(true, |prev| prev.depth < step.depth);
+        self.breakpoints
+            .iter()
+            .find(|(_, breakpoint)| match breakpoint {
+                Breakpoint::Line(bp_line) => enters_line && line == Some(*bp_line),
+                Breakpoint::Function(name) => {
+                    enters_function && function_matches(&step.function, name)
+                }
+            })
+            .map(|(number, _)| *number)
+    }
+
+    /// The steps of the current function and its callers, innermost first. The step of each caller
+    /// is its call instruction.
+    fn frames(&self) -> Vec<usize> {
+        let mut frames = vec![self.current];
+        let mut idx = self.current;
+        while let Some(caller) = (0..idx)
+            .rev()
+            .find(|caller| self.trace[*caller].depth < self.trace[idx].depth)
+        {
+            frames.push(caller);
+            idx = caller;
+        }
+        frames
+    }
+
+    //**********************************************************************************************
+    // Breakpoints
+    //**********************************************************************************************
+
+    /// Parse `<file>:<line>`, `<line>` in the current file, or the name of a function. Line
+    /// breakpoints are moved to the first line with code at or after the given line.
+    fn parse_breakpoint(&self, location: &str) -> Result<Breakpoint> {
+        if location.is_empty() {
+            bail!("Missing location. Expected <file>:<line>, <line>, or a function name")
+        }
+        let file_and_line = match location.rfind(':') {
+            Some(idx) => location[idx + 1..]
+                .parse::<usize>()
+                .ok()
+                .map(|line| (&location[..idx], line)),
+            None => None,
+        };
+        let (file, line) = match (file_and_line, location.parse::<usize>()) {
+            (Some((file, line)), _) => (self.info.find_file(file)?, line),
+            (None, Ok(line)) => match self.source_line(self.current) {
+                Some(current) => (current.file, line),
+                None => bail!("No current file. Give the location as <file>:<line>"),
+            },
+            (None, Err(_)) => {
+                if !self
+                    .info
+                    .functions
+                    .keys()
+                    .any(|function| function_matches(function, location))
+                {
+                    bail!("Unknown function '{}'", location)
+                }
+                return Ok(Breakpoint::Function(location.to_owned()));
+            }
+        };
+        self.info
+            .functions
+            .values()
+            .flat_map(|function| function.code_map.values())
+            .filter(|code_line| code_line.file == file && code_line.line >= line)
+            .min_by_key(|code_line| code_line.line)
+            .map(|code_line| Breakpoint::Line(*code_line))
+            .ok_or_else(|| anyhow!("No code at or after line {} of {}", line, file))
+    }
+
+    fn describe_breakpoint(&self, breakpoint: &Breakpoint) -> String {
+        match breakpoint {
+            Breakpoint::Line(line) => format!("{}:{}", line.file, line.line),
+            Breakpoint::Function(name) => name.clone(),
+        }
+    }
+
+    //**********************************************************************************************
+    // Printing
+    //**********************************************************************************************
+
+    fn source_line(&self, idx: usize) -> Option<SourceLine> {
+        let step = &self.trace[idx];
+        let function = self.info.functions.get(&step.function)?;
+        function
+            .code_map
+            .range(..=step.pc)
+            .next_back()
+            .map(|(_, line)| *line)
+    }
+
+    /// The function executing at step `idx`, and its location
+    fn describe(&self, idx: usize) -> String {
+        let step = &self.trace[idx];
+        let name = self
+            .info
+            .functions
+            .get(&step.function)
+            .map_or(step.function.as_str(), |function| function.name.as_str());
+        match self.source_line(idx) {
+            Some(line) => format!("{} at {}:{}", name, line.file, line.line),
+            None => format!("{} at code offset {}", name, step.pc),
+        }
+    }
+
+    fn print_current_line<W: Write>(&self, output: &mut W) -> Result<()> {
+        if let Some(line) = self.source_line(self.current) {
+            if let Some(text) = self.info.line_text(line) {
+                writeln!(output, "{}\t{}", line.line, text)?
+            }
+        }
+        Ok(())
+    }
+
+    fn print_source<W: Write>(&self, output: &mut W) -> Result<()> {
+        let current = match self.source_line(self.current) {
+            Some(line) => line,
+            None => {
+                writeln!(output, "No source for the current function")?;
+                return Ok(());
+            }
+        };
+        let first = current.line.saturating_sub(LIST_CONTEXT).max(1);
+        let last = (current.line + LIST_CONTEXT).min(self.info.line_count(current.file));
+        for line in first..=last {
+            let source_line = SourceLine {
+                file: current.file,
+                line,
+            };
+            let marker = if line == current.line { ">" } else { " " };
+            let text = self.info.line_text(source_line).unwrap_or("");
+            writeln!(output, "{} {}\t{}", marker, line, text)?
+        }
+        Ok(())
+    }
+
+    /// The locals holding a value. Temporaries introduced by the compiler are only shown for
+    /// functions without source information.
+    fn print_locals<W: Write>(&self, output: &mut W) -> Result<()> {
+        let step = &self.trace[self.current];
+        let locals: Vec<(String, &String)> = match self.info.functions.get(&step.function) {
+            Some(function) => function
+                .locals
+                .iter()
+                .zip(&step.locals)
+                .filter_map(|(name, value)| Some((name.clone()?, value)))
+                .collect(),
+            None => step
+                .locals
+                .iter()
+                .enumerate()
+                .map(|(idx, value)| (format!("[{}]", idx), value))
+                .collect(),
+        };
+        let mut printed = false;
+        for (name, value) in locals {
+            if value != DEBUG_TRACE_UNSET_VALUE {
+                writeln!(output, "{} = {}", name, value)?;
+                printed = true;
+            }
+        }
+        if !printed {
+            writeln!(output, "No locals")?
+        }
+        Ok(())
+    }
+
+    /// The operand stack, top first
+    fn print_stack<W: Write>(&self, output: &mut W) -> Result<()> {
+        let stack = &self.trace[self.current].stack;
+        if stack.is_empty() {
+            writeln!(output, "The operand stack is empty")?
+        }
+        for (idx, value) in stack.iter().enumerate().rev() {
+            writeln!(output, "[{}] {}", idx, value)?
+        }
+        Ok(())
+    }
+}
@@ -6,6 +6,7 @@ use disassembler::disassembler::Disassembler;
 use libra_types::{contract_event::ContractEvent, event::EventKey};
 use move_core_types::{
     account_address::AccountAddress,
+    gas_schedule::{GasAlgebra, GasUnits},
     identifier::Identifier,
     language_storage::{ModuleId, StructTag, TypeTag},
     parser,
@@ -14,7 +15,7 @@ use move_core_types::{
 };
 use move_lang::MOVE_COMPILED_EXTENSION;
 use move_vm_runtime::data_cache::RemoteCache;
-use move_vm_types::values::Value;
+use move_vm_types::{gas_schedule::CostStrategy, values::Value};
 use resource_viewer::{AnnotatedMoveStruct, AnnotatedMoveValue, MoveValueAnnotator};
 use vm::{
     access::ModuleAccess,
@@ -30,7 +31,10 @@ use std::{
     path::{Path, PathBuf},
 };
 
+pub mod debugger;
+pub mod package;
 pub mod test;
+pub mod unit_test;
 
 /// Default directory where saved Move resources live
 pub const MOVE_DATA: &str = "move_data";
@@ -51,6 +55,22 @@ const MODULES_DIR: &str = "modules";
 /// subdirectory of `MOVE_DATA`/<addr> where events are stored
 const EVENTS_DIR: &str = "events";
 
+/// Create a `CostStrategy` charging at most `gas_budget` units of gas, or an unmetered one if no
+/// budget is given
+pub fn get_cost_strategy(gas_budget: Option<u64>) -> Result<CostStrategy<'static>> {
+    let gas_schedule = &vm_genesis::genesis_gas_schedule::INITIAL_GAS_SCHEDULE;
+    Ok(if let Some(gas_budget) = gas_budget {
+        let max_gas_budget = u64::MAX / gas_schedule.gas_constants.gas_unit_scaling_factor;
+        if gas_budget >= max_gas_budget {
+            bail!("Gas budget set too high; maximum is {}", max_gas_budget)
+        }
+        CostStrategy::transaction(gas_schedule, GasUnits::new(gas_budget))
+    } else {
+        // no budget specified. use CostStrategy::system, which disables gas metering
+        CostStrategy::system(gas_schedule, GasUnits::new(0))
+    })
+}
+
 #[derive(Debug)]
 pub struct OnDiskStateView {
     modules: HashMap<ModuleId, Vec<u8>>,
@@ -301,7 +321,7 @@ impl OnDiskStateView {
     }
 
     /// Save `module` on disk under the path `module.address()`/`module.name()`
-    fn save_module(&self, module_id: &ModuleId, module_bytes: &[u8]) -> Result<()> {
+    pub fn save_module(&self, module_id: &ModuleId, module_bytes: &[u8]) -> Result<()> {
         let path = self.get_module_path(module_id);
         if !path.exists() {
             fs::create_dir_all(path.parent().unwrap())?
@@ -3,18 +3,27 @@
 
 use errmapgen::ErrorMapping;
 
-use move_cli::*;
+use move_cli::{
+    debugger::{self, DebugInfo, Debugger},
+    package::ResolvedPackage,
+    *,
+};
 use move_core_types::{
     account_address::AccountAddress,
-    gas_schedule::{GasAlgebra, GasUnits},
+    gas_schedule::GasAlgebra,
     language_storage::TypeTag,
     parser,
     transaction_argument::TransactionArgument,
     vm_status::{AbortLocation, StatusCode, VMStatus},
 };
+use move_coverage::coverage_map::{output_map_to_file, CoverageMap};
 use move_lang::{self, compiled_unit::CompiledUnit, MOVE_COMPILED_INTERFACES_DIR};
-use move_vm_runtime::{data_cache::TransactionEffects, logging::NoContextLog, move_vm::MoveVM};
-use move_vm_types::{gas_schedule, values::Value};
+use move_lint::{Level, LintConfig};
+use move_vm_runtime::{
+    data_cache::TransactionEffects, debug_trace::MOVE_VM_DEBUG_TRACE_ENV_VAR_NAME,
+    logging::NoContextLog, move_vm::MoveVM,
+};
+use move_vm_types::values::Value;
 use vm::{
     access::ScriptAccess,
     errors::VMError,
@@ -23,11 +32,22 @@ use vm::{
 
 use anyhow::{bail, Result};
 use std::{
-    fs,
+    fs, io,
     path::{Path, PathBuf},
 };
 use structopt::StructOpt;
 
+/// Environment variable telling the VM where to write its execution trace
+const MOVE_VM_TRACE_ENV_VAR: &str = "MOVE_VM_TRACE";
+/// File in the build output directory recording the trace of the unit tests
+const UNIT_TEST_TRACE: &str = "unit_test.trace";
+/// Extension of coverage maps, as expected by the `move-coverage` tools
+const COVERAGE_MAP_EXTENSION: &str = "mvcov";
+/// File in the build output directory recording the trace replayed by the debugger
+const DEBUG_TRACE: &str = "debug.trace";
+/// File in the build output directory recording the gas profile in the folded stack format
+const GAS_PROFILE: &str = "gas_profile.folded";
+
 #[derive(StructOpt)]
 #[structopt(name = "Move", about = "CLI frontend for Move compiler and VM")]
 struct Move {
@@ -51,23 +71,37 @@ struct Move {
 
 #[derive(StructOpt)]
 enum Command {
-    /// Type check and verify the specified script and modules against the modules in `move_data`
+    /// Type check and verify the specified script and modules against the modules in `move_data`.
+    /// Without source files, checks the package in the current directory against its
+    /// dependencies if there is a `Move.toml`, and `move_src` otherwise.
     #[structopt(name = "check")]
     Check {
         /// The source files to check
-        #[structopt(
-            name = "PATH_TO_SOURCE_FILE",
-            default_value = MOVE_SRC,
-        )]
+        #[structopt(name = "PATH_TO_SOURCE_FILE")]
         source_files: Vec<String>,
     },
+    /// Run the lint checks on the specified script and modules, on top of type checking them like
+    /// `check`. Fails if any check configured at the `error` level is violated.
+    #[structopt(name = "lint")]
+    Lint {
+        /// The source files to lint
+        #[structopt(name = "PATH_TO_SOURCE_FILE")]
+        source_files: Vec<String>,
+        /// TOML file setting the level of each check, e.g. `redundant_copy = "allow"` under
+        /// `[rules]`. Checks default to the `warn` level.
+        #[structopt(long = "config", short = "c")]
+        config: Option<String>,
+        /// Print the violations as a JSON array instead of one per line
+        #[structopt(long = "json")]
+        json: bool,
+    },
+    /// Compile and publish the specified modules. Without source files, publishes the package
+    /// in the current directory and all of its dependencies if there is a `Move.toml`, and
+    /// `move_src` otherwise.
     #[structopt(name = "publish")]
     Publish {
         /// The source files containing modules to publish
-        #[structopt(
-            name = "PATH_TO_SOURCE_FILE",
-            default_value = MOVE_SRC,
-        )]
+        #[structopt(name = "PATH_TO_SOURCE_FILE")]
         source_files: Vec<String>,
         /// If set, the effects of executing `script_file` (i.e., published, updated, and
         /// deleted resources) will NOT be committed to disk.
@@ -75,8 +109,8 @@ enum Command {
         dry_run: bool,
     },
     /// Compile/run a Move script that reads/writes resources stored on disk in `move_data`.
-    /// This command compiles each each module stored in `move_src` and loads it into the VM
-    /// before running the script.
+    /// If there is a `Move.toml` in the current directory, the package and its dependencies are
+    /// compiled along with the script and loaded into the VM before running it.
     #[structopt(name = "run")]
     Run {
         /// Path to script to compile and run.
@@ -84,6 +118,7 @@ enum Command {
         script_file: String,
         /// Possibly-empty list of signers for the current transaction (e.g., `account` in
         /// `main(&account: signer)`). Must match the number of signers expected by `script_file`.
+        /// Signers may be given by a named address declared in the package manifest.
         #[structopt(long = "signers")]
         signers: Vec<String>,
         /// Possibly-empty list of arguments passed to the transaction (e.g., `i` in
@@ -103,8 +138,37 @@ enum Command {
         /// deleted resources) will NOT be committed to disk.
         #[structopt(long = "dry-run", short = "n")]
         dry_run: bool,
+        /// Step through the execution of the script in a source-level debugger once it has run.
+        /// Requires a debug build.
+        #[structopt(long = "debug")]
+        debug: bool,
+        /// Print the gas charged to each kind of instruction, and save the gas charged to each
+        /// function in a flamegraph-compatible file in the build output directory. Without a
+        /// `gas-budget`, the maximum budget of a transaction is used.
+        #[structopt(long = "gas-profile")]
+        gas_profile: bool,
     },
 
+    /// Run the Move unit tests, i.e. functions annotated with `#[test]`, declared in the specified
+    /// source files. Without source files, runs the tests of the package in the current directory
+    /// if there is a `Move.toml`, and of `move_src` otherwise.
+    #[structopt(name = "unit-test")]
+    UnitTest {
+        /// The source files containing the tests
+        #[structopt(name = "PATH_TO_SOURCE_FILE")]
+        source_files: Vec<String>,
+        /// Only run the tests whose fully qualified name contains this string
+        #[structopt(long = "filter", short = "f")]
+        filter: Option<String>,
+        /// Maximum number of gas units each test may consume.
+        /// By default, no `gas-budget` is specified and gas metering is disabled.
+        #[structopt(long = "gas-budget", short = "g")]
+        gas_budget: Option<u64>,
+        /// Trace the instructions executed by the tests and save a coverage map for the
+        /// `move-coverage` tools in the build output directory. Requires a debug build.
+        #[structopt(long = "coverage")]
+        coverage: bool,
+    },
     /// Run expected value tests using the given batch file
     #[structopt(name = "test")]
     Test {
@@ -151,24 +215,86 @@ fn interface_files_dir(build_dir: &str) -> Result<String> {
     Ok(dir)
 }
 
-/// Compile the user modules in `move_src` and the script in `script_file`
-fn check(args: &Move, files: &[String]) -> Result<()> {
+/// Compile the modules and scripts in `files`, or in the package if no files are given
+fn check(args: &Move, files: &[String], package: Option<&ResolvedPackage>) -> Result<()> {
     if args.verbose {
         println!("Checking Move files...");
     }
-    let interface_dir = interface_files_dir(&args.build_output)?;
-    move_lang::move_check(files, &[interface_dir], None, None)?;
+    let (targets, deps) = match package {
+        Some(package) if files.is_empty() => (package.root_sources(), package.dependency_sources()),
+        _ => (
+            source_files_or_default(files),
+            vec![interface_files_dir(&args.build_output)?],
+        ),
+    };
+    move_lang::move_check(&targets, &deps, None, None)?;
+    Ok(())
+}
+
+/// Lint the modules and scripts in `files`, or in the package if no files are given
+fn lint(
+    args: &Move,
+    files: &[String],
+    package: Option<&ResolvedPackage>,
+    config_file: Option<&str>,
+    json: bool,
+) -> Result<()> {
+    if args.verbose {
+        println!("Linting Move files...");
+    }
+    let config = match config_file {
+        Some(config_file) => LintConfig::parse(&fs::read_to_string(config_file)?)?,
+        None => LintConfig::default(),
+    };
+    let (targets, deps) = match package {
+        Some(package) if files.is_empty() => (package.root_sources(), package.dependency_sources()),
+        _ => (
+            source_files_or_default(files),
+            vec![interface_files_dir(&args.build_output)?],
+        ),
+    };
+    let (files_source_text, lints) = move_lint::lint(&targets, &deps, None, &config)?;
+    let lints = match lints {
+        Ok(lints) => lints,
+        Err(errors) => move_lang::errors::report_errors(files_source_text, errors),
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&lints)?);
+    } else {
+        for lint in &lints {
+            println!("{}", lint)
+        }
+    }
+    let num_errors = lints
+        .iter()
+        .filter(|lint| lint.level == Level::Error)
+        .count();
+    if num_errors > 0 {
+        bail!("{} lint error(s)", num_errors)
+    }
     Ok(())
 }
 
-fn publish(args: &Move, files: &[String]) -> Result<OnDiskStateView> {
+fn publish(
+    args: &Move,
+    files: &[String],
+    package: Option<&ResolvedPackage>,
+) -> Result<OnDiskStateView> {
     let move_data = maybe_create_dir(&args.move_data)?;
 
     if args.verbose {
         println!("Compiling Move modules...")
     }
-    let interface_dir = interface_files_dir(&args.build_output)?;
-    let (_, compiled_units) = move_lang::move_compile(files, &[interface_dir], None, None)?;
+    // a package is compiled from source together with all of its dependencies
+    let (targets, deps) = match package {
+        Some(package) if files.is_empty() => (package.all_sources(), vec![]),
+        _ => (
+            source_files_or_default(files),
+            vec![interface_files_dir(&args.build_output)?],
+        ),
+    };
+    let (_, compiled_units) = move_lang::move_compile(&targets, &deps, None, None)?;
 
     let num_modules = compiled_units
         .iter()
@@ -196,33 +322,117 @@ fn publish(args: &Move, files: &[String]) -> Result<OnDiskStateView> {
     Ok(OnDiskStateView::create(move_data.to_path_buf(), &modules)?)
 }
 
+/// Run the unit tests in `files`, or in the package if no files are given
+fn unit_test(
+    args: &Move,
+    files: &[String],
+    package: Option<&ResolvedPackage>,
+    filter: Option<&str>,
+    gas_budget: Option<u64>,
+    coverage: bool,
+) -> Result<()> {
+    if args.verbose {
+        println!("Compiling Move unit tests...")
+    }
+    let (targets, deps) = match package {
+        Some(package) if files.is_empty() => (package.root_sources(), package.dependency_sources()),
+        _ => (source_files_or_default(files), vec![]),
+    };
+    let (files_source_text, units_and_plan) =
+        move_lang::move_compile_unit_tests(&targets, &deps, None)?;
+    let (units, test_plan) = match units_and_plan {
+        Ok(units_and_plan) => units_and_plan,
+        Err(errors) => move_lang::errors::report_errors(files_source_text, errors),
+    };
+
+    let trace_file_opt = if coverage {
+        if !cfg!(debug_assertions) {
+            bail!("Collecting coverage requires a debug build of the Move CLI")
+        }
+        let trace_file = maybe_create_dir(&args.build_output)?.join(UNIT_TEST_TRACE);
+        if trace_file.exists() {
+            fs::remove_file(&trace_file)?;
+        }
+        // the VM appends every instruction it executes to the file named by this variable
+        std::env::set_var(MOVE_VM_TRACE_ENV_VAR, &trace_file);
+        Some(trace_file)
+    } else {
+        None
+    };
+
+    let results = unit_test::run_tests(units, &test_plan, filter, gas_budget)?;
+
+    if let Some(trace_file) = trace_file_opt.filter(|trace_file| trace_file.exists()) {
+        let coverage_map = CoverageMap::from_trace_file(&trace_file);
+        let coverage_file = trace_file.with_extension(COVERAGE_MAP_EXTENSION);
+        output_map_to_file(&coverage_file, &coverage_map)?;
+        if args.verbose {
+            println!("Saved coverage map to {:?}", coverage_file)
+        }
+    }
+
+    if !results.is_success() {
+        bail!("{} unit test(s) failed", results.failed.len())
+    }
+    Ok(())
+}
+
+/// The source files given on the command line, or `move_src` if there are none
+fn source_files_or_default(files: &[String]) -> Vec<String> {
+    if files.is_empty() {
+        vec![MOVE_SRC.to_string()]
+    } else {
+        files.to_vec()
+    }
+}
+
 fn run(
     args: &Move,
+    package: Option<&ResolvedPackage>,
     script_file: &str,
     signers: &[String],
     txn_args: &[TransactionArgument],
     vm_type_args: Vec<TypeTag>,
     gas_budget: Option<u64>,
     dry_run: bool,
+    debug: bool,
+    gas_profile: bool,
 ) -> Result<()> {
     fn compile_script(
         args: &Move,
+        package: Option<&ResolvedPackage>,
         script_file: &str,
-    ) -> Result<(OnDiskStateView, Option<CompiledScript>)> {
+        debug: bool,
+    ) -> Result<(OnDiskStateView, Option<CompiledScript>, Option<DebugInfo>)> {
         let move_data = maybe_create_dir(&args.move_data)?;
 
         if args.verbose {
             println!("Compiling transaction script...")
         }
-        let interface_dir = interface_files_dir(&args.build_output)?;
-        let (_, compiled_units) = move_lang::move_compile(
-            &[script_file.to_string()],
-            &[interface_dir.clone()],
-            None,
-            Some(interface_dir),
-        )?;
+        let (files, compiled_units) = match package {
+            Some(package) => {
+                let mut targets = package.all_sources();
+                targets.push(package.stage_file(script_file, Path::new(&args.build_output))?);
+                move_lang::move_compile(&targets, &[], None, None)?
+            }
+            None => {
+                let interface_dir = interface_files_dir(&args.build_output)?;
+                move_lang::move_compile(
+                    &[script_file.to_string()],
+                    &[interface_dir.clone()],
+                    None,
+                    Some(interface_dir),
+                )?
+            }
+        };
+        let debug_info_opt = if debug {
+            Some(DebugInfo::new(files, &compiled_units))
+        } else {
+            None
+        };
 
         let mut script_opt = None;
+        let mut modules = vec![];
         for c in compiled_units {
             match c {
                 CompiledUnit::Script { script, .. } => {
@@ -231,6 +441,7 @@ fn run(
                     }
                     script_opt = Some(script)
                 }
+                CompiledUnit::Module { module, .. } if package.is_some() => modules.push(module),
                 CompiledUnit::Module { ident, .. } => {
                     if args.verbose {
                         println!(
@@ -243,12 +454,16 @@ fn run(
             }
         }
         Ok((
-            OnDiskStateView::create(move_data.to_path_buf(), &[])?,
+            OnDiskStateView::create(move_data.to_path_buf(), &modules)?,
             script_opt,
+            debug_info_opt,
         ))
     }
 
-    let (state, script_opt) = compile_script(args, script_file)?;
+    if debug && !cfg!(debug_assertions) {
+        bail!("Debugging requires a debug build of the Move CLI")
+    }
+    let (state, script_opt, debug_info_opt) = compile_script(args, package, script_file, debug)?;
     let script = match script_opt {
         Some(s) => s,
         None => bail!("Unable to find script in file {:?}", script_file),
@@ -257,21 +472,25 @@ fn run(
     script.serialize(&mut script_bytes)?;
 
     let vm = MoveVM::new();
-    let gas_schedule = &vm_genesis::genesis_gas_schedule::INITIAL_GAS_SCHEDULE;
-    let mut cost_strategy = if let Some(gas_budget) = gas_budget {
-        let max_gas_budget = u64::MAX / gas_schedule.gas_constants.gas_unit_scaling_factor;
-        if gas_budget >= max_gas_budget {
-            bail!("Gas budget set too high; maximum is {}", max_gas_budget)
-        }
-        gas_schedule::CostStrategy::transaction(gas_schedule, GasUnits::new(gas_budget))
+    let mut cost_strategy = if gas_profile {
+        // gas is only recorded while it is metered
+        let max_gas_budget = vm_genesis::genesis_gas_schedule::INITIAL_GAS_SCHEDULE
+            .gas_constants
+            .maximum_number_of_gas_units
+            .get();
+        let mut cost_strategy = get_cost_strategy(Some(gas_budget.unwrap_or(max_gas_budget)))?;
+        cost_strategy.enable_profiling();
+        cost_strategy
     } else {
-        // no budget specified. use CostStrategy::system, which disables gas metering
-        gas_schedule::CostStrategy::system(gas_schedule, GasUnits::new(0))
+        get_cost_strategy(gas_budget)?
     };
 
     let signer_addresses = signers
         .iter()
-        .map(|s| AccountAddress::from_hex_literal(&s))
+        .map(|s| match package {
+            Some(package) => package.resolve_address(s),
+            None => AccountAddress::from_hex_literal(s),
+        })
         .collect::<Result<Vec<AccountAddress>>>()?;
     // TODO: parse Value's directly instead of going through the indirection of TransactionArgument?
     let vm_args: Vec<Value> = txn_args
@@ -286,6 +505,19 @@ fn run(
         })
         .collect();
 
+    let trace_file_opt = match debug_info_opt {
+        Some(_) => {
+            let trace_file = maybe_create_dir(&args.build_output)?.join(DEBUG_TRACE);
+            if trace_file.exists() {
+                fs::remove_file(&trace_file)?;
+            }
+            // the VM appends its state before every instruction to the file named by this variable
+            std::env::set_var(MOVE_VM_DEBUG_TRACE_ENV_VAR_NAME, &trace_file);
+            Some(trace_file)
+        }
+        None => None,
+    };
+
     let log_context = NoContextLog::new();
     let mut session = vm.new_session(&state);
 
@@ -298,6 +530,25 @@ fn run(
         &log_context,
     );
 
+    if let (Some(trace_file), Some(debug_info)) = (trace_file_opt, debug_info_opt) {
+        let trace = if trace_file.exists() {
+            debugger::read_trace(&trace_file)?
+        } else {
+            vec![]
+        };
+        let stdin = io::stdin();
+        Debugger::new(trace, debug_info).run(stdin.lock(), io::stdout())?;
+    }
+
+    if let Some(profile) = cost_strategy.take_profile() {
+        profile.write_kinds(&mut io::stdout())?;
+        let profile_file = maybe_create_dir(&args.build_output)?.join(GAS_PROFILE);
+        profile.write_folded(&mut fs::File::create(&profile_file)?)?;
+        if args.verbose {
+            println!("Saved gas profile to {:?}", profile_file)
+        }
+    }
+
     if let Err(err) = res {
         explain_error(
             err,
@@ -374,7 +625,9 @@ fn maybe_commit_effects(
     state: &OnDiskStateView,
 ) -> Result<()> {
     if commit {
-        if let Some(effects) = effects_opt {
+        // a script only publishes the modules in its effects, while `publish` saves every module
+        // it compiled
+        let modules_saved = if let Some(effects) = effects_opt {
             for (addr, writes) in effects.resources {
                 for (struct_tag, write_opt) in writes {
                     match write_opt {
@@ -397,13 +650,17 @@ fn maybe_commit_effects(
                     event_data,
                 )?
             }
-        }
 
-        let modules_saved = state.save_modules()?;
+            for (module_id, module_bytes) in &effects.modules {
+                state.save_module(module_id, module_bytes)?
+            }
+            !effects.modules.is_empty()
+        } else {
+            state.save_modules()?
+        };
         if modules_saved {
             generate_interface_files(args)?;
         }
-        state.save_modules()?;
     } else if !effects_opt.map_or(true, |effects| effects.resources.is_empty()) {
         println!("Discarding changes; re-run without --dry-run if you would like to keep them.")
     }
@@ -580,14 +837,36 @@ fn view(args: &Move, file: &str) -> Result<()> {
 
 fn main() -> Result<()> {
     let move_args = Move::from_args();
+    let mut package = match &move_args.cmd {
+        Command::Check { .. }
+        | Command::Lint { .. }
+        | Command::Publish { .. }
+        | Command::Run { .. }
+        | Command::UnitTest { .. } => ResolvedPackage::resolve_if_present(Path::new("."))?,
+        _ => None,
+    };
+    if let Some(package) = &mut package {
+        package.stage_sources(maybe_create_dir(&move_args.build_output)?)?;
+    }
 
     match &move_args.cmd {
-        Command::Check { source_files } => check(&move_args, &source_files),
+        Command::Check { source_files } => check(&move_args, &source_files, package.as_ref()),
+        Command::Lint {
+            source_files,
+            config,
+            json,
+        } => lint(
+            &move_args,
+            source_files,
+            package.as_ref(),
+            config.as_deref(),
+            *json,
+        ),
         Command::Publish {
             source_files,
             dry_run,
         } => {
-            let state = publish(&move_args, source_files)?;
+            let state = publish(&move_args, source_files, package.as_ref())?;
             maybe_commit_effects(&move_args, !dry_run, None, &state)
         }
         Command::Run {
@@ -597,14 +876,32 @@ fn main() -> Result<()> {
             type_args,
             gas_budget,
             dry_run,
+            debug,
+            gas_profile,
         } => run(
             &move_args,
+            package.as_ref(),
             script_file,
             signers,
             args,
             type_args.to_vec(),
             *gas_budget,
             *dry_run,
+            *debug,
+            *gas_profile,
+        ),
+        Command::UnitTest {
+            source_files,
+            filter,
+            gas_budget,
+            coverage,
+        } => unit_test(
+            &move_args,
+            source_files,
+            package.as_ref(),
+            filter.as_deref(),
+            *gas_budget,
+            *coverage,
         ),
         Command::Test { path } => test::run_all(path, &std::env::current_exe()?.to_string_lossy()),
         Command::View { file } => view(&move_args, file),
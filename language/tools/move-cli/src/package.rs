@@ -0,0 +1,414 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Move package manifests and dependency resolution.
+//!
+//! A package is a directory containing a `Move.toml` manifest and a directory of Move sources:
+//!
+//! ```toml
+//! [package]
+//! name = "MyLib"
+//! version = "0.1.0"
+//! # directory holding the package's Move sources, relative to the manifest (default `move_src`)
+//! sources = "move_src"
+//!
+//! [addresses]
+//! MyLib = "0x42"
+//!
+//! [dependencies]
+//! Stdlib = { path = "../stdlib", version = "0.1.0" }
+//! ```
+//!
+//! Dependencies are other packages on the local file system. The key of a dependency must be the
+//! name of the package it points to, and an optional `version` pins the dependency to exactly that
+//! version. Named addresses declared by a package are visible to every package depending on it;
+//! two packages in the same dependency graph may not bind the same name to different addresses.
+//!
+//! Sources refer to a named address with a `{{Name}}` placeholder, e.g. `address {{MyLib}} { ... }`
+//! or `use {{MyLib}}::M;`. Before compiling, the sources of every package are copied to the build
+//! output directory with each placeholder replaced by the address it names. Placeholders in comments
+//! and byte strings are left as they are.
+
+use crate::MOVE_SRC;
+use move_core_types::account_address::AccountAddress;
+use move_lang::parser::lexer::{Lexer, Tok};
+
+use anyhow::{anyhow, bail, Result};
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Name of the package manifest file
+pub const MANIFEST_FILE: &str = "Move.toml";
+/// Directory in the build output holding the sources of the packages with their named addresses
+/// substituted
+pub const STAGED_SOURCES_DIR: &str = "package_sources";
+/// The adjacent tokens a `{{Name}}` placeholder lexes to
+const PLACEHOLDER_TOKENS: [Tok; 5] = [
+    Tok::LBrace,
+    Tok::LBrace,
+    Tok::IdentifierValue,
+    Tok::RBrace,
+    Tok::RBrace,
+];
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    package: PackageInfo,
+    #[serde(default)]
+    addresses: BTreeMap<String, String>,
+    #[serde(default)]
+    dependencies: BTreeMap<String, Dependency>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PackageInfo {
+    name: String,
+    version: String,
+    sources: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Dependency {
+    path: String,
+    version: Option<String>,
+}
+
+/// A package found while resolving a dependency graph
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Package {
+    pub name: String,
+    pub version: String,
+    /// Canonical path of the directory containing the manifest
+    pub root: PathBuf,
+    /// Path of the directory containing the Move sources of the package, or of their staged copy
+    /// once `ResolvedPackage::stage_sources` has been called
+    pub sources: PathBuf,
+}
+
+/// A package together with all of its transitive dependencies
+#[derive(Debug)]
+pub struct ResolvedPackage {
+    pub root: Package,
+    /// The transitive dependencies of `root`. Every package comes after its own dependencies, and
+    /// ties are broken by package name, so the order only depends on the manifests.
+    pub dependencies: Vec<Package>,
+    /// The named addresses declared by `root` and its dependencies
+    pub addresses: BTreeMap<String, AccountAddress>,
+}
+
+impl ResolvedPackage {
+    /// Load the manifest in `dir` and resolve its dependency graph
+    pub fn resolve(dir: &Path) -> Result<Self> {
+        let mut resolver = Resolver::default();
+        let root = resolver.visit(dir, None)?;
+        // the root package is visited last
+        let root_package = resolver.order.pop().unwrap();
+        assert!(root_package.root == root);
+        Ok(Self {
+            root: root_package,
+            dependencies: resolver.order,
+            addresses: resolver
+                .addresses
+                .into_iter()
+                .map(|(name, (addr, _))| (name, addr))
+                .collect(),
+        })
+    }
+
+    /// Load the manifest in `dir`, if there is one
+    pub fn resolve_if_present(dir: &Path) -> Result<Option<Self>> {
+        if dir.join(MANIFEST_FILE).is_file() {
+            Ok(Some(Self::resolve(dir)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// The source directory of the root package
+    pub fn root_sources(&self) -> Vec<String> {
+        vec![path_to_string(&self.root.sources)]
+    }
+
+    /// The source directories of the dependencies, in dependency order
+    pub fn dependency_sources(&self) -> Vec<String> {
+        self.dependencies
+            .iter()
+            .map(|package| path_to_string(&package.sources))
+            .collect()
+    }
+
+    /// The source directories of the dependencies followed by the one of the root package
+    pub fn all_sources(&self) -> Vec<String> {
+        let mut sources = self.dependency_sources();
+        sources.extend(self.root_sources());
+        sources
+    }
+
+    /// Copy the sources of every package to `<build_dir>/package_sources/<package name>`, with the
+    /// `{{Name}}` placeholders replaced by the named addresses, and point the packages at the
+    /// copies so that they are compiled instead of the originals
+    pub fn stage_sources(&mut self, build_dir: &Path) -> Result<()> {
+        let staged_dir = build_dir.join(STAGED_SOURCES_DIR);
+        if staged_dir.exists() {
+            fs::remove_dir_all(&staged_dir)?;
+        }
+        let addresses = &self.addresses;
+        for package in self
+            .dependencies
+            .iter_mut()
+            .chain(std::iter::once(&mut self.root))
+        {
+            let package_dir = staged_dir.join(&package.name);
+            fs::create_dir_all(&package_dir)?;
+            let files = move_lang::find_move_filenames(&[path_to_string(&package.sources)], false)?;
+            for file in files {
+                let file = Path::new(&file);
+                let staged_file = package_dir.join(file.strip_prefix(&package.sources)?);
+                if let Some(parent) = staged_file.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                let text = fs::read_to_string(file)?;
+                let location = file.strip_prefix(&package.root).unwrap_or(file);
+                fs::write(
+                    &staged_file,
+                    substitute_addresses(addresses, &package.name, location, &text)?,
+                )?;
+            }
+            package.sources = package_dir;
+        }
+        Ok(())
+    }
+
+    /// Copy `file`, which does not belong to any package, next to the staged package sources with
+    /// its `{{Name}}` placeholders replaced by the named addresses. Returns the path of the copy.
+    pub fn stage_file(&self, file: &str, build_dir: &Path) -> Result<String> {
+        let file = Path::new(file);
+        let file_name = match file.file_name() {
+            Some(file_name) => file_name,
+            None => bail!("Invalid source file {:?}", file),
+        };
+        let staged_dir = build_dir.join(STAGED_SOURCES_DIR);
+        fs::create_dir_all(&staged_dir)?;
+        let staged_file = staged_dir.join(file_name);
+        let text = fs::read_to_string(file)?;
+        fs::write(
+            &staged_file,
+            substitute_addresses(&self.addresses, &self.root.name, file, &text)?,
+        )?;
+        Ok(path_to_string(&staged_file))
+    }
+
+    /// Resolve `s` as either a named address of the package or an address literal
+    pub fn resolve_address(&self, s: &str) -> Result<AccountAddress> {
+        match self.addresses.get(s) {
+            Some(addr) => Ok(*addr),
+            None if s.starts_with("0x") => AccountAddress::from_hex_literal(s),
+            None => bail!(
+                "Unknown named address '{}'. Declare it in the [addresses] section of {}",
+                s,
+                MANIFEST_FILE
+            ),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Resolver {
+    /// Packages whose dependencies are being resolved, outermost first
+    visiting: Vec<(String, PathBuf)>,
+    /// Packages already resolved, in dependency order
+    order: Vec<Package>,
+    /// Name of each package found so far -> its root
+    names: BTreeMap<String, PathBuf>,
+    /// Named address -> (address, name of the declaring package)
+    addresses: BTreeMap<String, (AccountAddress, String)>,
+}
+
+impl Resolver {
+    /// Resolve the package in `dir` and its dependencies, returning its canonical root.
+    /// `required` is the name and optional version pin of the dependency pointing to `dir`.
+    fn visit(&mut self, dir: &Path, required: Option<(&str, Option<&str>)>) -> Result<PathBuf> {
+        let root = dir
+            .canonicalize()
+            .map_err(|e| anyhow!("Unable to find package at {:?}: {}", dir, e))?;
+
+        if let Some(pos) = self.visiting.iter().position(|(_, r)| r == &root) {
+            let cycle = self.visiting[pos..]
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .chain(std::iter::once(self.visiting[pos].0.as_str()))
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            bail!("Cyclic package dependency: {}", cycle)
+        }
+        if let Some(package) = self.order.iter().find(|package| package.root == root) {
+            check_required(package, required)?;
+            return Ok(root);
+        }
+
+        let manifest_path = root.join(MANIFEST_FILE);
+        let manifest_src = fs::read_to_string(&manifest_path)
+            .map_err(|e| anyhow!("Unable to read {:?}: {}", manifest_path, e))?;
+        let manifest: Manifest = toml::from_str(&manifest_src)
+            .map_err(|e| anyhow!("Invalid manifest {:?}: {}", manifest_path, e))?;
+
+        let package = Package {
+            sources: root.join(manifest.package.sources.as_deref().unwrap_or(MOVE_SRC)),
+            name: manifest.package.name,
+            version: manifest.package.version,
+            root: root.clone(),
+        };
+        check_required(&package, required)?;
+        if !package.sources.is_dir() {
+            bail!(
+                "Package '{}' has no source directory at {:?}",
+                package.name,
+                package.sources
+            )
+        }
+        if let Some(other_root) = self.names.get(&package.name) {
+            bail!(
+                "Two different packages are named '{}': {:?} and {:?}",
+                package.name,
+                other_root,
+                root
+            )
+        }
+        self.names.insert(package.name.clone(), root.clone());
+
+        for (name, literal) in manifest.addresses {
+            let addr = AccountAddress::from_hex_literal(&literal).map_err(|e| {
+                anyhow!(
+                    "Invalid address '{}' for named address '{}' in package '{}': {}",
+                    literal,
+                    name,
+                    package.name,
+                    e
+                )
+            })?;
+            match self.addresses.get(&name) {
+                Some((prev_addr, prev_package)) if *prev_addr != addr => bail!(
+                    "Conflicting named address '{}': bound to 0x{} in package '{}' and to 0x{} \
+                     in package '{}'",
+                    name,
+                    prev_addr,
+                    prev_package,
+                    addr,
+                    package.name
+                ),
+                Some(_) => (),
+                None => {
+                    self.addresses.insert(name, (addr, package.name.clone()));
+                }
+            }
+        }
+
+        self.visiting.push((package.name.clone(), root.clone()));
+        for (dep_name, dep) in &manifest.dependencies {
+            self.visit(
+                &root.join(&dep.path),
+                Some((dep_name, dep.version.as_deref())),
+            )?;
+        }
+        self.visiting.pop();
+
+        self.order.push(package);
+        Ok(root)
+    }
+}
+
+fn check_required(package: &Package, required: Option<(&str, Option<&str>)>) -> Result<()> {
+    let (name, version_opt) = match required {
+        None => return Ok(()),
+        Some(required) => required,
+    };
+    if package.name != name {
+        bail!(
+            "Dependency '{}' points to package '{}'. The name of a dependency must match the \
+             name of its package",
+            name,
+            package.name
+        )
+    }
+    match version_opt {
+        Some(version) if version != package.version => bail!(
+            "Dependency '{}' is pinned to version {}, but found version {}",
+            name,
+            version,
+            package.version
+        ),
+        _ => Ok(()),
+    }
+}
+
+/// Replace each `{{Name}}` placeholder in `text`, the contents of `file` in `package`, with the
+/// address bound to `Name`. Placeholders in comments and byte strings are left as they are.
+fn substitute_addresses(
+    addresses: &BTreeMap<String, AccountAddress>,
+    package: &str,
+    file: &Path,
+    text: &str,
+) -> Result<String> {
+    let fname = move_lang::intern_file_name(&path_to_string(file));
+    // Comments are blanked out without moving the rest of the source. Sources the compiler
+    // rejects are staged as they are for it to report the error.
+    let source = match move_lang::strip_comments_and_verify(fname, text) {
+        Ok((source, _)) => source,
+        Err(_) => return Ok(text.to_owned()),
+    };
+    let mut tokens = vec![];
+    let mut lexer = Lexer::new(&source, fname, BTreeMap::new());
+    while lexer.advance().is_ok() && lexer.peek() != Tok::EOF {
+        tokens.push((lexer.peek(), lexer.start_loc(), lexer.content().len()));
+    }
+
+    let mut substituted = String::with_capacity(text.len());
+    let mut copied = 0;
+    let mut i = 0;
+    while i + PLACEHOLDER_TOKENS.len() <= tokens.len() {
+        let placeholder = &tokens[i..i + PLACEHOLDER_TOKENS.len()];
+        let start = placeholder[0].1;
+        let is_placeholder = placeholder
+            .iter()
+            .map(|(tok, _, _)| *tok)
+            .eq(PLACEHOLDER_TOKENS.iter().copied())
+            && placeholder
+                .windows(2)
+                .all(|pair| pair[0].1 + pair[0].2 == pair[1].1);
+        if !is_placeholder {
+            i += 1;
+            continue;
+        }
+        let (_, name_start, name_len) = placeholder[2];
+        let name = &source[name_start..name_start + name_len];
+        let addr = match addresses.get(name) {
+            Some(addr) => addr,
+            None => bail!(
+                "Unknown named address '{}' in {:?} of package '{}'. Declare it in the \
+                 [addresses] section of {}",
+                name,
+                file,
+                package,
+                MANIFEST_FILE
+            ),
+        };
+        substituted.push_str(&text[copied..start]);
+        substituted.push_str(&format!("0x{}", addr));
+        let (_, end_start, end_len) = placeholder[PLACEHOLDER_TOKENS.len() - 1];
+        copied = end_start + end_len;
+        i += PLACEHOLDER_TOKENS.len();
+    }
+    substituted.push_str(&text[copied..]);
+    Ok(substituted)
+}
+
+fn path_to_string(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
@@ -0,0 +1,182 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Runs Move unit tests, i.e. module functions annotated with `#[test]`. Every test is executed
+//! in its own VM session against a fresh in-memory storage holding only the compiled modules, so
+//! tests cannot observe the effects of one another.
+
+use crate::get_cost_strategy;
+use move_core_types::{
+    account_address::AccountAddress, identifier::IdentStr, language_storage::ModuleId,
+    vm_status::StatusCode,
+};
+use move_lang::{
+    compiled_unit::CompiledUnit,
+    unit_test::{ExpectedFailure, TestCase, TestPlan},
+};
+use move_vm_runtime::{logging::NoContextLog, move_vm::MoveVM};
+use move_vm_test_utils::InMemoryStorage;
+use vm::{
+    access::ModuleAccess,
+    errors::{Location, VMError},
+    file_format::CompiledModule,
+};
+
+use anyhow::Result;
+use std::collections::BTreeMap;
+
+/// Outcome of running a set of unit tests
+#[derive(Debug, Default)]
+pub struct TestResults {
+    pub passed: Vec<String>,
+    /// Name of each failed test -> explanation of the failure
+    pub failed: BTreeMap<String, String>,
+}
+
+impl TestResults {
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// Run the tests in `test_plan` whose fully qualified name contains `filter`, printing the result
+/// of each test as it completes.
+pub fn run_tests(
+    units: Vec<CompiledUnit>,
+    test_plan: &TestPlan,
+    filter: Option<&str>,
+    gas_budget: Option<u64>,
+) -> Result<TestResults> {
+    let mut modules = BTreeMap::new();
+    let mut storage = InMemoryStorage::new();
+    for unit in units {
+        if let CompiledUnit::Module { module, .. } = unit {
+            let mut module_bytes = vec![];
+            module.serialize(&mut module_bytes)?;
+            storage.publish_or_overwrite_module(module.self_id(), module_bytes);
+            modules.insert(module.self_id(), module);
+        }
+    }
+
+    println!("Running Move unit tests");
+    let vm = MoveVM::new();
+    let mut results = TestResults::default();
+    for (module_id, module_plan) in &test_plan.module_tests {
+        for (test_name, test_case) in &module_plan.tests {
+            let name = qualified_name(module_id, test_name);
+            if !filter.map_or(true, |filter| name.contains(filter)) {
+                continue;
+            }
+
+            // every test starts from the same pristine storage
+            let test_storage = storage.clone();
+            let mut session = vm.new_session(&test_storage);
+            let mut cost_strategy = get_cost_strategy(gas_budget)?;
+            let log_context = NoContextLog::new();
+            let res = session.execute_function(
+                module_id,
+                IdentStr::new(test_name)?,
+                vec![],
+                vec![],
+                AccountAddress::ZERO,
+                &mut cost_strategy,
+                &log_context,
+            );
+
+            match check_outcome(test_case, res, &modules) {
+                None => {
+                    println!("[ PASS    ] {}", name);
+                    results.passed.push(name);
+                }
+                Some(explanation) => {
+                    println!("[ FAIL    ] {}", name);
+                    results.failed.insert(name, explanation);
+                }
+            }
+        }
+    }
+
+    if !results.failed.is_empty() {
+        println!("\nTest failures:\n");
+        for (name, explanation) in &results.failed {
+            println!("Failure in {}:", name);
+            println!("    {}\n", explanation);
+        }
+    }
+    println!(
+        "Test result: {}. Total tests: {}; passed: {}; failed: {}",
+        if results.is_success() { "OK" } else { "FAILED" },
+        results.passed.len() + results.failed.len(),
+        results.passed.len(),
+        results.failed.len()
+    );
+    Ok(results)
+}
+
+fn qualified_name(module_id: &ModuleId, function_name: &str) -> String {
+    format!(
+        "0x{}::{}::{}",
+        module_id.address(),
+        module_id.name(),
+        function_name
+    )
+}
+
+/// Compare the result of running `test_case` against its expectation, returning an explanation
+/// if they do not match
+fn check_outcome(
+    test_case: &TestCase,
+    res: Result<(), VMError>,
+    modules: &BTreeMap<ModuleId, CompiledModule>,
+) -> Option<String> {
+    match (&test_case.expected_failure, res) {
+        (None, Ok(())) => None,
+        (None, Err(err)) => Some(format!(
+            "Test was not expected to fail, but {}",
+            explain_error(&err, modules)
+        )),
+        (Some(ExpectedFailure::Expected), Err(_)) => None,
+        (Some(ExpectedFailure::Expected), Ok(())) => {
+            Some("Test was expected to fail, but it succeeded".to_string())
+        }
+        (Some(ExpectedFailure::ExpectedWithCode(code)), Err(err))
+            if err.major_status() == StatusCode::ABORTED && err.sub_status() == Some(*code) =>
+        {
+            None
+        }
+        (Some(ExpectedFailure::ExpectedWithCode(code)), Err(err)) => Some(format!(
+            "Test was expected to abort with code {}, but {}",
+            code,
+            explain_error(&err, modules)
+        )),
+        (Some(ExpectedFailure::ExpectedWithCode(code)), Ok(())) => Some(format!(
+            "Test was expected to abort with code {}, but it succeeded",
+            code
+        )),
+    }
+}
+
+/// Describe `err` along with the location it was raised at
+fn explain_error(err: &VMError, modules: &BTreeMap<ModuleId, CompiledModule>) -> String {
+    let location = match err.location() {
+        Location::Module(module_id) => match (err.offsets().first(), modules.get(module_id)) {
+            (Some((fdef_idx, code_offset)), Some(module)) => {
+                let fhandle = module.function_handle_at(module.function_def_at(*fdef_idx).function);
+                format!(
+                    "{} at code offset {}",
+                    qualified_name(module_id, module.identifier_at(fhandle.name).as_str()),
+                    code_offset
+                )
+            }
+            _ => format!("module 0x{}::{}", module_id.address(), module_id.name()),
+        },
+        Location::Script => "a script".to_string(),
+        Location::Undefined => "an unknown location".to_string(),
+    };
+    match (err.major_status(), err.sub_status()) {
+        (StatusCode::ABORTED, Some(code)) => {
+            format!("it aborted with code {} in {}", code, location)
+        }
+        (status_code, _) => format!("it failed with {:?} in {}", status_code, location),
+    }
+}
@@ -0,0 +1,89 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use move_cli::debugger::{DebugInfo, Debugger, TraceStep};
+use move_lang::compiled_unit::CompiledUnit;
+use std::{fs, io::Cursor};
+
+const SCRIPT: &str = "script {
+fun main(a: u64) {
+    let x = a + 1;
+    let y = x * 2;
+    assert(y > a, 42);
+}
+}
+";
+
+/// Run the debugger on `commands`, over a trace executing every instruction of `SCRIPT` in order.
+/// The value of local `i` is `100 + i` throughout.
+fn debug(commands: &str) -> String {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("script.move");
+    fs::write(&file, SCRIPT).unwrap();
+    let (files, units) =
+        move_lang::move_compile(&[file.to_string_lossy().into_owned()], &[], None, None).unwrap();
+    let code_len = match &units[0] {
+        CompiledUnit::Script { script, .. } => script.as_inner().code.code.len(),
+        CompiledUnit::Module { .. } => panic!("expected a script"),
+    };
+    let trace = (0..code_len)
+        .map(|pc| TraceStep {
+            depth: 0,
+            function: "main".to_string(),
+            pc: pc as u16,
+            locals: (0..10).map(|idx| (100 + idx).to_string()).collect(),
+            stack: vec!["7".to_string()],
+        })
+        .collect();
+
+    let mut output = vec![];
+    Debugger::new(trace, DebugInfo::new(files, &units))
+        .run(Cursor::new(commands), &mut output)
+        .unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+fn assert_contains(output: &str, expected: &str) {
+    assert!(output.contains(expected), "{}", output)
+}
+
+#[test]
+fn trace_records() {
+    let step: TraceStep = "1\t0x1::M::f\t3\t5; -\t".parse().unwrap();
+    assert_eq!(step.depth, 1);
+    assert_eq!(step.function, "0x1::M::f");
+    assert_eq!(step.pc, 3);
+    assert_eq!(step.locals, vec!["5", "-"]);
+    assert!(step.stack.is_empty());
+    assert!("1\t0x1::M::f".parse::<TraceStep>().is_err());
+}
+
+#[test]
+fn breakpoints_and_inspection() {
+    let output = debug("break script.move:4\ncontinue\nlocals\nstack\nbt\nlist\nquit\n");
+    assert_contains(&output, "Breakpoint 1 at ");
+    assert_contains(&output, "Breakpoint 1, main at ");
+    assert_contains(&output, "script.move:4\n4\t    let y");
+    // the parameter is local 0, and temporaries are not shown
+    assert_contains(&output, "a = 100");
+    assert!(!output.contains("tmp%"), "{}", output);
+    assert_contains(&output, "[0] 7");
+    assert_contains(&output, "#0 main at ");
+    assert_contains(&output, "> 4\t    let y = x * 2;");
+    assert!(!output.contains("Reached the end"), "{}", output);
+}
+
+#[test]
+fn stepping_to_the_end() {
+    // the first command is repeated by the empty lines
+    let output = debug(&format!("next\n{}", "\n".repeat(20)));
+    assert_contains(&output, "main at ");
+    assert_contains(&output, "\t    assert(y > a, 42);");
+    assert_contains(&output, "Reached the end of the execution");
+
+    let output = debug("break 100\nbreak N::f\nfrobnicate\ncontinue\n");
+    assert_contains(&output, "No code at or after line 100");
+    assert_contains(&output, "Unknown function 'N::f'");
+    assert_contains(&output, "Unknown command 'frobnicate'");
+    assert_contains(&output, "Reached the end of the execution");
+}
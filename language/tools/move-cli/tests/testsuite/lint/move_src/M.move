@@ -0,0 +1,8 @@
+address 0x2 {
+module M {
+    fun f<T>(x: u64): u64 {
+        assert(x > 0, 1);
+        copy x
+    }
+}
+}
@@ -0,0 +1,7 @@
+address {{Base}} {
+module Base {
+    public fun double(i: u64): u64 {
+        i * 2
+    }
+}
+}
@@ -0,0 +1,14 @@
+address {{Counter}} {
+module Counter {
+    use {{Base}}::Base;
+
+    /// Published under the address of the signer, not under {{Signer}}
+    resource struct C { i: u64 }
+
+    /* Placeholders in comments, like {{Unknown}}, and byte strings are not substituted */
+    public fun publish(account: &signer, i: u64) {
+        assert(b"{{Counter}}" == x"7b7b436f756e7465727d7d", 0);
+        move_to(account, C { i: Base::double(i) })
+    }
+}
+}
@@ -0,0 +1,7 @@
+script {
+use {{Counter}}::Counter;
+
+fun main(account: &signer, i: u64) {
+    Counter::publish(account, i)
+}
+}
@@ -0,0 +1,9 @@
+address {{Root}} {
+module Root {
+    use {{Missing}}::M;
+
+    public fun g() {
+        M::f()
+    }
+}
+}
@@ -0,0 +1,5 @@
+address 0x2 {
+module Dep {
+    public fun f() {}
+}
+}
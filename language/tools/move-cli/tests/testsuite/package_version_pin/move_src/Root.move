@@ -0,0 +1,9 @@
+address 0x2 {
+module Root {
+    use 0x2::Dep;
+
+    public fun g() {
+        Dep::f()
+    }
+}
+}
@@ -0,0 +1,35 @@
+address 0x2 {
+module M {
+    fun double(i: u64): u64 {
+        i * 2
+    }
+
+    #[test]
+    fun double_works() {
+        if (double(2) != 4) abort 1
+    }
+
+    #[test]
+    #[expected_failure(abort_code = 7)]
+    fun aborts_with_code() {
+        abort 7
+    }
+
+    #[test]
+    #[expected_failure]
+    fun overflow_fails() {
+        let _ = double(18446744073709551615);
+    }
+
+    #[test]
+    fun unexpected_abort() {
+        abort 42
+    }
+
+    #[test]
+    #[expected_failure(abort_code = 1)]
+    fun wrong_abort_code() {
+        abort 2
+    }
+}
+}
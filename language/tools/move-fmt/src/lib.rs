@@ -0,0 +1,652 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+//! Canonical formatting of Move sources.
+//!
+//! The formatter works on the token stream of the Move lexer, so it handles every construct the
+//! parser accepts, including specification blocks. Line breaks are kept where the author put
+//! them, except that runs of blank lines are collapsed and an opening `{` or an `else` is moved to
+//! the end of the previous line. Everything else is canonical:
+//! - lines are indented by four spaces per enclosing `{`, `(` or `[`, and by four more spaces
+//!   when they continue an unfinished statement; the contents of `address` blocks are not indented
+//! - spacing between tokens on the same line only depends on the tokens
+//! - comments, including documentation comments, are kept, and re-indented if they start a line
+//!
+//! Only sources that parse are formatted, and the formatted source always consists of exactly the
+//! same tokens and comments as the original.
+
+use move_lang::{
+    errors::Errors,
+    parse_file_contents,
+    parser::lexer::{Lexer, Tok},
+    strip_comments_and_verify,
+};
+use std::collections::BTreeMap;
+
+/// Number of spaces per indentation level
+const INDENT_WIDTH: usize = 4;
+
+/// Format `source`, the contents of the file `fname`
+pub fn format_source(fname: &'static str, source: &str) -> Result<String, Errors> {
+    parse_file_contents(fname, source)?;
+    let lexed = lex(fname, source)?;
+    let expected = lexed.summary();
+    let formatted = Formatter::new(fname, lexed).format();
+    match lex(fname, &formatted) {
+        Ok(relexed) if relexed.summary() == expected => Ok(formatted),
+        _ => panic!("ICE formatting changed the tokens or comments of {}", fname),
+    }
+}
+
+//**************************************************************************************************
+// Lexing
+//**************************************************************************************************
+
+struct Token<'a> {
+    tok: Tok,
+    content: &'a str,
+}
+
+/// The comments and line breaks between two tokens
+enum Trivia<'a> {
+    Newline,
+    LineComment(&'a str),
+    BlockComment(&'a str),
+}
+
+struct Lexed<'a> {
+    tokens: Vec<Token<'a>>,
+    /// `trivia[i]` precedes `tokens[i]`, and the last entry follows the last token
+    trivia: Vec<Vec<Trivia<'a>>>,
+}
+
+impl<'a> Lexed<'a> {
+    /// The tokens and comments, which formatting must preserve
+    fn summary(&self) -> (Vec<(Tok, &'a str)>, Vec<&'a str>) {
+        let tokens = self.tokens.iter().map(|t| (t.tok, t.content)).collect();
+        let comments = self
+            .trivia
+            .iter()
+            .flatten()
+            .filter_map(|trivia| match trivia {
+                Trivia::Newline => None,
+                Trivia::LineComment(c) | Trivia::BlockComment(c) => Some(*c),
+            })
+            .collect();
+        (tokens, comments)
+    }
+}
+
+fn lex<'a>(fname: &'static str, source: &'a str) -> Result<Lexed<'a>, Errors> {
+    // the comments are blanked out for the lexer, leaving the positions of the tokens unchanged
+    let (no_comments_buffer, _) = strip_comments_and_verify(fname, source)?;
+    let mut lexer = Lexer::new(&no_comments_buffer, fname, BTreeMap::new());
+    let mut tokens = vec![];
+    let mut trivia = vec![];
+    let mut prev_end = 0;
+    loop {
+        lexer.advance().map_err(|err| vec![err])?;
+        let start = lexer.start_loc();
+        trivia.push(scan_trivia(&source[prev_end..start]));
+        if lexer.peek() == Tok::EOF {
+            break;
+        }
+        let end = start + lexer.content().len();
+        tokens.push(Token {
+            tok: lexer.peek(),
+            // `&mut` is lexed together with the space following it
+            content: source[start..end].trim_end(),
+        });
+        prev_end = end;
+    }
+    Ok(Lexed { tokens, trivia })
+}
+
+/// Split `gap`, which consists only of whitespace and comments, into line breaks and comments
+fn scan_trivia(gap: &str) -> Vec<Trivia> {
+    let mut trivia = vec![];
+    let mut pos = 0;
+    while pos < gap.len() {
+        let rest = &gap[pos..];
+        if rest.starts_with("//") {
+            let len = rest.find('\n').unwrap_or_else(|| rest.len());
+            trivia.push(Trivia::LineComment(rest[..len].trim_end()));
+            pos += len;
+        } else if rest.starts_with("/*") {
+            let len = block_comment_len(rest);
+            trivia.push(Trivia::BlockComment(&rest[..len]));
+            pos += len;
+        } else {
+            if rest.starts_with('\n') {
+                trivia.push(Trivia::Newline)
+            }
+            pos += rest.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    trivia
+}
+
+/// The length of the block comment at the start of `text`. Block comments can be nested.
+fn block_comment_len(text: &str) -> usize {
+    let mut depth = 0;
+    let mut pos = 0;
+    while pos < text.len() {
+        let rest = &text[pos..];
+        if rest.starts_with("/*") {
+            depth += 1;
+            pos += 2;
+        } else if rest.starts_with("*/") {
+            depth -= 1;
+            pos += 2;
+            if depth == 0 {
+                return pos;
+            }
+        } else {
+            pos += rest.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    text.len()
+}
+
+//**************************************************************************************************
+// Formatting
+//**************************************************************************************************
+
+/// An open `{`, `(` or `[`
+struct Delimiter {
+    tok: Tok,
+    /// Indentation of the line holding the matching closing delimiter
+    closing_indent: usize,
+    /// Indentation of the lines inside the delimiters
+    content_indent: usize,
+    /// Whether this is the `[` of an attribute, as in `#[test]`
+    is_attribute: bool,
+    /// Whether this is the `{` of a group of imported members, as in `use 0x1::M::{Self, S}`
+    is_use_group: bool,
+}
+
+struct Formatter<'a> {
+    fname: &'static str,
+    tokens: Vec<Token<'a>>,
+    trivia: Vec<Vec<Trivia<'a>>>,
+    /// Whether each `<` and `>` delimits type parameters or type arguments
+    is_type_angle: Vec<bool>,
+    /// Whether each `&`, `*` and `!` is a unary operator
+    is_unary: Vec<bool>,
+    lines: Vec<String>,
+    /// The line being written, without its indentation
+    line: String,
+    indent: usize,
+    delimiters: Vec<Delimiter>,
+    /// Index of the last token written
+    prev: Option<usize>,
+    /// Whether a comment was written after the last token
+    prev_is_comment: bool,
+    /// Index of the `]` ending the last attribute
+    attribute_end: Option<usize>,
+}
+
+impl<'a> Formatter<'a> {
+    fn new(fname: &'static str, lexed: Lexed<'a>) -> Self {
+        let Lexed { tokens, trivia } = lexed;
+        let is_type_angle = type_angles(&tokens);
+        let is_unary = unary_operators(&tokens, &is_type_angle);
+        Self {
+            fname,
+            tokens,
+            trivia,
+            is_type_angle,
+            is_unary,
+            lines: vec![],
+            line: String::new(),
+            indent: 0,
+            delimiters: vec![],
+            prev: None,
+            prev_is_comment: false,
+            attribute_end: None,
+        }
+    }
+
+    fn format(mut self) -> String {
+        let mut newlines = 0;
+        // set after a line comment, or a block comment spanning several lines
+        let mut must_break = false;
+        for idx in 0..=self.tokens.len() {
+            let trivia = std::mem::take(&mut self.trivia[idx]);
+            let has_comments = trivia.iter().any(|t| !matches!(t, Trivia::Newline));
+            for t in trivia {
+                let comment = match t {
+                    Trivia::Newline => {
+                        newlines += 1;
+                        continue;
+                    }
+                    Trivia::LineComment(c) | Trivia::BlockComment(c) => c,
+                };
+                if newlines == 0 && !must_break && !self.line.is_empty() {
+                    self.line.push(' ');
+                } else {
+                    let blank = newlines > 1 && self.allows_blank_line();
+                    let indent = self.content_indent();
+                    self.start_line(blank, indent);
+                }
+                self.line.push_str(comment);
+                self.prev_is_comment = true;
+                newlines = 0;
+                must_break = comment.starts_with("//") || comment.contains('\n');
+            }
+            if idx == self.tokens.len() {
+                break;
+            }
+
+            let tok = self.tokens[idx].tok;
+            let joins_prev_line = !has_comments && self.joins_prev_line(idx);
+            if (newlines > 0 || must_break) && !joins_prev_line && !self.line.is_empty() {
+                let is_closing = matches!(tok, Tok::RBrace | Tok::RParen | Tok::RBracket);
+                let blank = newlines > 1 && !is_closing && self.allows_blank_line();
+                let indent = match self.delimiters.last() {
+                    Some(delimiter) if is_closing => delimiter.closing_indent,
+                    _ => self.content_indent() + self.continuation_indent(),
+                };
+                self.start_line(blank, indent);
+            } else if self.needs_space(idx) {
+                self.line.push(' ');
+            }
+            let content = if tok == Tok::AmpMut {
+                "&mut"
+            } else {
+                self.tokens[idx].content
+            };
+            self.line.push_str(content);
+            self.prev = Some(idx);
+            self.prev_is_comment = false;
+            newlines = 0;
+            must_break = false;
+
+            match tok {
+                Tok::LBrace | Tok::LParen | Tok::LBracket => {
+                    let prev_tok = idx.checked_sub(1).map(|prev| self.tokens[prev].tok);
+                    let is_address_block = tok == Tok::LBrace
+                        && prev_tok == Some(Tok::AddressValue)
+                        && idx >= 2
+                        && self.tokens[idx - 2].content == "address";
+                    let delimiter = Delimiter {
+                        tok,
+                        closing_indent: self.indent,
+                        content_indent: if is_address_block {
+                            self.indent
+                        } else {
+                            self.indent + 1
+                        },
+                        is_attribute: tok == Tok::LBracket && prev_tok == Some(Tok::NumSign),
+                        is_use_group: tok == Tok::LBrace && prev_tok == Some(Tok::ColonColon),
+                    };
+                    self.delimiters.push(delimiter)
+                }
+                Tok::RBrace | Tok::RParen | Tok::RBracket => {
+                    if let Some(delimiter) = self.delimiters.pop() {
+                        if delimiter.is_attribute {
+                            self.attribute_end = Some(idx)
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        self.finish()
+    }
+
+    fn start_line(&mut self, blank: bool, indent: usize) {
+        if !self.line.is_empty() {
+            self.end_line();
+            if blank {
+                self.lines.push(String::new())
+            }
+        }
+        self.indent = indent;
+    }
+
+    fn end_line(&mut self) {
+        let line = std::mem::take(&mut self.line);
+        let indentation = " ".repeat(INDENT_WIDTH * self.indent);
+        self.lines
+            .push(format!("{}{}", indentation, line).trim_end().to_owned());
+    }
+
+    fn finish(mut self) -> String {
+        if !self.line.is_empty() {
+            self.end_line()
+        }
+        if self.lines.is_empty() {
+            return String::new();
+        }
+        let mut formatted = self.lines.join("\n");
+        formatted.push('\n');
+        formatted
+    }
+
+    /// Indentation of a line inside the innermost delimiters
+    fn content_indent(&self) -> usize {
+        self.delimiters
+            .last()
+            .map_or(0, |delimiter| delimiter.content_indent)
+    }
+
+    /// Extra indentation of a line continuing a statement or declaration, i.e. a line in a block
+    /// whose previous token does not end a statement, item or attribute
+    fn continuation_indent(&self) -> usize {
+        let prev = match self.prev {
+            Some(prev) => prev,
+            None => return 0,
+        };
+        let in_block = self
+            .delimiters
+            .last()
+            .map_or(true, |delimiter| delimiter.tok == Tok::LBrace);
+        let ends_statement = matches!(
+            self.tokens[prev].tok,
+            Tok::Semicolon | Tok::LBrace | Tok::RBrace | Tok::Comma
+        ) || self.attribute_end == Some(prev);
+        if in_block && !ends_statement {
+            1
+        } else {
+            0
+        }
+    }
+
+    /// Blank lines are dropped right after an opening `(` or `[`
+    fn allows_blank_line(&self) -> bool {
+        self.prev.map_or(false, |prev| {
+            !matches!(self.tokens[prev].tok, Tok::LParen | Tok::LBracket)
+        })
+    }
+
+    /// Whether the token at `idx` belongs at the end of the previous line: an `else` following a
+    /// `}`, or the `{` opening a block after a declaration or condition
+    fn joins_prev_line(&self, idx: usize) -> bool {
+        let prev_tok = match self.prev {
+            Some(prev) => self.tokens[prev].tok,
+            None => return false,
+        };
+        match self.tokens[idx].tok {
+            Tok::Else => prev_tok == Tok::RBrace,
+            Tok::LBrace => !matches!(
+                prev_tok,
+                Tok::Semicolon | Tok::LBrace | Tok::RBrace | Tok::Comma
+            ),
+            _ => false,
+        }
+    }
+
+    /// Whether a space separates the token at `idx` from the previous one on the same line
+    fn needs_space(&self, idx: usize) -> bool {
+        let prev = match self.prev {
+            _ if self.line.is_empty() => return false,
+            _ if self.prev_is_comment => return true,
+            Some(prev) => prev,
+            None => return false,
+        };
+        let (prev_tok, tok) = (self.tokens[prev].tok, self.tokens[idx].tok);
+        let in_use_group = self
+            .delimiters
+            .last()
+            .map_or(false, |delimiter| delimiter.is_use_group);
+        let no_space_before = matches!(
+            tok,
+            Tok::Comma
+                | Tok::Semicolon
+                | Tok::RParen
+                | Tok::RBracket
+                | Tok::Period
+                | Tok::PeriodPeriod
+                | Tok::ColonColon
+                | Tok::Colon
+        ) || (matches!(tok, Tok::Greater | Tok::GreaterGreater)
+            && self.is_type_angle[idx]);
+        let no_space_after = matches!(
+            prev_tok,
+            Tok::LParen
+                | Tok::LBracket
+                | Tok::Period
+                | Tok::PeriodPeriod
+                | Tok::ColonColon
+                | Tok::NumSign
+                | Tok::Exclaim
+        ) || (prev_tok == Tok::Less && self.is_type_angle[prev])
+            || (matches!(prev_tok, Tok::Amp | Tok::Star) && self.is_unary[prev]);
+        let space = match (prev_tok, tok) {
+            _ if no_space_before || no_space_after => false,
+            // calls, and `public(friend)`
+            (Tok::IdentifierValue, Tok::LParen) | (Tok::Public, Tok::LParen) => false,
+            (Tok::Greater, Tok::LParen) | (Tok::GreaterGreater, Tok::LParen) => {
+                !self.is_type_angle[prev]
+            }
+            // indexing
+            (Tok::IdentifierValue, Tok::LBracket)
+            | (Tok::RParen, Tok::LBracket)
+            | (Tok::RBracket, Tok::LBracket) => false,
+            (_, Tok::Less) if self.is_type_angle[idx] => false,
+            (Tok::LBrace, Tok::RBrace) => false,
+            (Tok::LBrace, _) | (_, Tok::RBrace) if in_use_group => false,
+            _ => true,
+        };
+        space || !self.separable(prev, idx)
+    }
+
+    /// Whether the tokens at `first` and `second` are still lexed as two tokens when written
+    /// without a space in between
+    fn separable(&self, first: usize, second: usize) -> bool {
+        let first = &self.tokens[first];
+        let text = format!("{}{}", first.content, self.tokens[second].content);
+        let mut lexer = Lexer::new(&text, self.fname, BTreeMap::new());
+        lexer.advance().is_ok()
+            && lexer.peek() == first.tok
+            && lexer.content().len() == first.content.len()
+    }
+}
+
+/// Find the `<` and `>` delimiting type parameters or type arguments. A `<` following a name (or
+/// the `*` of a spec pattern) starts type arguments if it is matched by a `>` with only tokens
+/// that can appear in types in between.
+fn type_angles(tokens: &[Token]) -> Vec<bool> {
+    let mut is_type_angle = vec![false; tokens.len()];
+    for start in 1..tokens.len() {
+        if tokens[start].tok != Tok::Less
+            || !matches!(tokens[start - 1].tok, Tok::IdentifierValue | Tok::Star)
+        {
+            continue;
+        }
+        let mut depth = 1;
+        for end in start + 1..tokens.len() {
+            match tokens[end].tok {
+                Tok::Less => depth += 1,
+                Tok::Greater => depth -= 1,
+                Tok::GreaterGreater => depth -= 2,
+                Tok::IdentifierValue
+                | Tok::AddressValue
+                | Tok::ColonColon
+                | Tok::Comma
+                | Tok::Amp
+                | Tok::AmpMut
+                | Tok::Colon
+                | Tok::Resource
+                | Tok::Copyable => (),
+                _ => break,
+            }
+            if depth <= 0 {
+                is_type_angle[start] = true;
+                is_type_angle[end] = true;
+                break;
+            }
+        }
+    }
+    is_type_angle
+}
+
+/// Find the unary operators: every `!`, and every `&` or `*` that does not follow an operand
+fn unary_operators(tokens: &[Token], is_type_angle: &[bool]) -> Vec<bool> {
+    (0..tokens.len())
+        .map(|idx| match tokens[idx].tok {
+            Tok::Exclaim => true,
+            Tok::Amp | Tok::Star => {
+                idx == 0 || !ends_operand(tokens[idx - 1].tok, is_type_angle[idx - 1])
+            }
+            _ => false,
+        })
+        .collect()
+}
+
+fn ends_operand(tok: Tok, is_type_angle: bool) -> bool {
+    match tok {
+        Tok::IdentifierValue
+        | Tok::AddressValue
+        | Tok::NumValue
+        | Tok::U8Value
+        | Tok::U64Value
+        | Tok::U128Value
+        | Tok::ByteStringValue
+        | Tok::True
+        | Tok::False
+        | Tok::RParen
+        | Tok::RBracket => true,
+        Tok::Greater | Tok::GreaterGreater => is_type_angle,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_lang::find_move_filenames;
+    use std::{fs, path::PathBuf};
+
+    fn format(source: &str) -> String {
+        format_source("test.move", source).unwrap()
+    }
+
+    #[test]
+    fn canonical_spacing_and_indentation() {
+        let source = r#"
+address 0x1 {
+module M {
+  use 0x1::Vector::{ Self,Vector };
+resource struct R<T:copyable>{ value:T }
+
+
+
+    public ( friend ) fun f<T: copyable>(x:&mut vector<vector<u8> >,y:u64):bool acquires R
+    {
+        let v=Vector::empty<u8>( );
+        if(y<10&&!Vector::is_empty(&v)){ return true }
+        else { * &mut y = y*2 };
+        y>3 &&
+        y<100
+    }
+}
+}
+"#;
+        let expected = r#"address 0x1 {
+module M {
+    use 0x1::Vector::{Self, Vector};
+    resource struct R<T: copyable> { value: T }
+
+    public(friend) fun f<T: copyable>(x: &mut vector<vector<u8> >, y: u64): bool acquires R {
+        let v = Vector::empty<u8>();
+        if (y < 10 && !Vector::is_empty(&v)) { return true } else { *&mut y = y * 2 };
+        y > 3 &&
+            y < 100
+    }
+}
+}
+"#;
+        assert_eq!(format(source), expected);
+    }
+
+    #[test]
+    fn comments_are_preserved() {
+        let source = r#"
+script {
+/// Documentation
+fun main() { // trailing
+    // leading
+        let x = 1 /* one */ + 2; /* inline */ let y = x;
+  /* a block comment
+     spanning lines */
+    spec {
+        assert y == 1; // in a spec
+    }
+}
+}
+// at the end
+"#;
+        let expected = r#"script {
+    /// Documentation
+    fun main() { // trailing
+        // leading
+        let x = 1 /* one */ + 2; /* inline */ let y = x;
+        /* a block comment
+     spanning lines */
+        spec {
+            assert y == 1; // in a spec
+        }
+    }
+}
+// at the end
+"#;
+        assert_eq!(format(source), expected);
+    }
+
+    #[test]
+    fn attributes_and_continuations() {
+        let source = r#"
+module M {
+    #[test]
+    #[expected_failure(abort_code = 1)]
+    fun t() {
+        if (true)
+        abort 1;
+        abort
+        1
+    }
+}
+"#;
+        let expected = r#"module M {
+    #[test]
+    #[expected_failure(abort_code = 1)]
+    fun t() {
+        if (true)
+            abort 1;
+        abort
+            1
+    }
+}
+"#;
+        assert_eq!(format(source), expected);
+    }
+
+    #[test]
+    fn sources_that_do_not_parse_are_rejected() {
+        assert!(format_source("test.move", "module M { fun }").is_err());
+    }
+
+    #[test]
+    fn formatting_the_stdlib_is_idempotent() {
+        let stdlib = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../stdlib");
+        let dirs = ["modules", "transaction_scripts"]
+            .iter()
+            .map(|dir| stdlib.join(dir).to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        for fname in find_move_filenames(&dirs, false).unwrap() {
+            let source = fs::read_to_string(&fname).unwrap();
+            let fname: &'static str = Box::leak(fname.into_boxed_str());
+            let formatted = format_source(fname, &source).unwrap();
+            assert_eq!(
+                format_source(fname, &formatted).unwrap(),
+                formatted,
+                "{}",
+                fname
+            );
+        }
+    }
+}
@@ -0,0 +1,85 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+use difference::{Changeset, Difference};
+use move_lang::{errors::report_errors, find_move_filenames};
+use std::{collections::HashMap, fs};
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(name = "move-fmt", about = "Format Move source files")]
+struct Args {
+    /// Move files to format, or directories to search for Move files
+    #[structopt(name = "PATH", required = true)]
+    paths: Vec<String>,
+    /// Instead of rewriting the files, print how they would change, and fail if any would
+    #[structopt(name = "check", long = "check")]
+    check: bool,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::from_args();
+    let mut files = HashMap::new();
+    let mut errors = vec![];
+    let mut unformatted = 0;
+    for fname in find_move_filenames(&args.paths, true)? {
+        let source = fs::read_to_string(&fname)?;
+        let fname: &'static str = Box::leak(fname.into_boxed_str());
+        match move_fmt::format_source(fname, &source) {
+            Ok(formatted) if formatted == source => (),
+            Ok(formatted) => {
+                unformatted += 1;
+                if args.check {
+                    print_diff(fname, &source, &formatted)
+                } else {
+                    fs::write(fname, formatted)?
+                }
+            }
+            Err(errs) => errors.extend(errs),
+        }
+        files.insert(fname, source);
+    }
+    if !errors.is_empty() {
+        report_errors(files, errors)
+    }
+    if args.check && unformatted > 0 {
+        eprintln!("{} file(s) are not formatted", unformatted);
+        std::process::exit(1)
+    }
+    Ok(())
+}
+
+/// Print the lines of `fname` that differ once formatted
+fn print_diff(fname: &str, source: &str, formatted: &str) {
+    let mut line = 1;
+    let mut in_hunk = false;
+    for diff in Changeset::new(source, formatted, "\n").diffs {
+        match diff {
+            Difference::Same(lines) => {
+                line += lines.split('\n').count();
+                in_hunk = false;
+            }
+            Difference::Rem(lines) => {
+                if !in_hunk {
+                    println!("Diff in {} at line {}:", fname, line);
+                    in_hunk = true;
+                }
+                line += lines.split('\n').count();
+                for l in lines.split('\n') {
+                    println!("-{}", l)
+                }
+            }
+            Difference::Add(lines) => {
+                if !in_hunk {
+                    println!("Diff in {} at line {}:", fname, line);
+                    in_hunk = true;
+                }
+                for l in lines.split('\n') {
+                    println!("+{}", l)
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,392 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+//! Lint checks over the typed Move program.
+//!
+//! Each rule walks the functions of a source module or script after typing and reports the
+//! locations it finds suspicious. Rules report at the `warn` level unless configured otherwise, and
+//! can be turned off or made errors with a TOML configuration such as
+//!
+//! ```toml
+//! [rules]
+//! redundant_copy = "allow"
+//! missing_role_check = "error"
+//! ```
+//!
+//! Programs that do not compile are not linted; their compile errors are returned instead.
+
+#[macro_use(sp)]
+extern crate move_ir_types;
+
+mod rules;
+mod visit;
+
+pub use rules::{all_rules, Rule, Unit};
+
+use anyhow::bail;
+use move_lang::{
+    errors::{Errors, FilesSourceText},
+    move_check_to_typing_with_overlay,
+    shared::Address,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
+
+/// How the violations of a rule are reported
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    /// Not reported
+    Allow,
+    Warn,
+    Error,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Level::Allow => write!(f, "allow"),
+            Level::Warn => write!(f, "warning"),
+            Level::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// The level of each rule. Rules that are not configured are reported as warnings.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct LintConfig {
+    #[serde(default)]
+    pub rules: BTreeMap<String, Level>,
+}
+
+impl LintConfig {
+    /// Parse a TOML configuration, rejecting the names that are not rules
+    pub fn parse(toml_str: &str) -> anyhow::Result<Self> {
+        let config: Self = toml::from_str(toml_str)?;
+        let rules = all_rules();
+        for name in config.rules.keys() {
+            if !rules.iter().any(|rule| rule.name() == name) {
+                bail!("Unknown lint rule '{}'", name)
+            }
+        }
+        Ok(config)
+    }
+
+    pub fn level(&self, rule: &str) -> Level {
+        self.rules.get(rule).copied().unwrap_or(Level::Warn)
+    }
+}
+
+/// A violation of a rule
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct Lint {
+    pub file: String,
+    /// The line of the violation, starting at 1
+    pub line: usize,
+    /// The column of the violation in characters, starting at 1
+    pub column: usize,
+    pub rule: String,
+    pub level: Level,
+    pub message: String,
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {}[{}]: {}",
+            self.file, self.line, self.column, self.level, self.rule, self.message
+        )
+    }
+}
+
+/// Lint the source modules and scripts of `targets`, sorted by location. If the program does not
+/// compile, its errors are returned instead.
+pub fn lint(
+    targets: &[String],
+    deps: &[String],
+    sender_opt: Option<Address>,
+    config: &LintConfig,
+) -> anyhow::Result<(FilesSourceText, Result<Vec<Lint>, Errors>)> {
+    let (files, prog_opt, errors) =
+        move_check_to_typing_with_overlay(targets, deps, sender_opt, &HashMap::new())?;
+    let prog = match prog_opt {
+        Some(prog) if errors.is_empty() => prog,
+        _ => return Ok((files, Err(errors))),
+    };
+
+    let mut units = vec![];
+    for (mident, mdef) in prog.modules.iter() {
+        if mdef.is_source_module {
+            units.push(Unit {
+                module: Some(mident),
+                functions: mdef.functions.iter().collect(),
+            })
+        }
+    }
+    for script in prog.scripts.values() {
+        units.push(Unit {
+            module: None,
+            functions: vec![(script.function_name.clone(), &script.function)],
+        })
+    }
+
+    let mut lints = vec![];
+    for rule in all_rules() {
+        let level = config.level(rule.name());
+        if level == Level::Allow {
+            continue;
+        }
+        let mut violations = vec![];
+        for unit in &units {
+            rule.check(unit, &mut violations)
+        }
+        for (loc, message) in violations {
+            let (line, column) = line_and_column(&files[loc.file()], loc.span().start().to_usize());
+            lints.push(Lint {
+                file: loc.file().to_string(),
+                line,
+                column,
+                rule: rule.name().to_string(),
+                level,
+                message,
+            })
+        }
+    }
+    lints.sort();
+    lints.dedup();
+    Ok((files, Ok(lints)))
+}
+
+/// The line and column, both starting at 1, of the byte offset `offset` in `text`
+fn line_and_column(text: &str, offset: usize) -> (usize, usize) {
+    let prefix = &text[..offset.min(text.len())];
+    let line_start = prefix.rfind('\n').map_or(0, |idx| idx + 1);
+    (
+        prefix.matches('\n').count() + 1,
+        prefix[line_start..].chars().count() + 1,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, path::Path};
+
+    /// Stand-ins for the parts of the standard library the rules know about
+    const STDLIB: &str = r#"
+address 0x1 {
+module Errors {
+    public fun invalid_argument(reason: u64): u64 { (reason << 8) | 7 }
+}
+module Signer {
+    native public fun borrow_address(s: &signer): &address;
+    public fun address_of(s: &signer): address { *borrow_address(s) }
+}
+module Roles {
+    public fun assert_libra_root(account: &signer) {
+        assert(0x1::Signer::address_of(account) == 0xA550C18, 0x1::Errors::invalid_argument(0))
+    }
+}
+module FixedPoint32 {
+    struct FixedPoint32 { value: u64 }
+    public fun get_raw_value(num: FixedPoint32): u64 { num.value }
+}
+}
+"#;
+
+    fn lint_source(source: &str, config: &LintConfig) -> Vec<(usize, String)> {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, contents: &str| {
+            let path = dir.path().join(name);
+            fs::write(&path, contents).unwrap();
+            path.to_string_lossy().to_string()
+        };
+        let deps = vec![write("stdlib.move", STDLIB)];
+        let targets = vec![write("source.move", source)];
+        let sender = Address::parse_str("0x2").unwrap();
+        let (files, lints) = lint(&targets, &deps, Some(sender), config).unwrap();
+        let lints = lints.unwrap_or_else(|errors| {
+            panic!(
+                "{}",
+                String::from_utf8(move_lang::errors::report_errors_to_buffer(files, errors))
+                    .unwrap()
+            )
+        });
+        lints
+            .into_iter()
+            .map(|lint| {
+                assert!(Path::new(&lint.file).ends_with("source.move"));
+                (lint.line, lint.rule)
+            })
+            .collect()
+    }
+
+    fn lints(source: &str) -> Vec<(usize, String)> {
+        lint_source(source, &LintConfig::default())
+    }
+
+    fn expected(lints: &[(usize, &str)]) -> Vec<(usize, String)> {
+        lints
+            .iter()
+            .map(|(line, rule)| (*line, rule.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn unused_type_parameters() {
+        let source = r#"
+module M {
+    struct S<T> { b: bool }
+    fun unused<T, U>(x: U): U { x }
+    fun in_body<T>(): bool { let s = S<T> { b: true }; s.b }
+    native fun native_unused<T>();
+}
+"#;
+        assert_eq!(lints(source), expected(&[(4, "unused_type_parameter")]));
+    }
+
+    #[test]
+    fn abort_codes() {
+        let source = r#"
+module M {
+    use 0x1::Errors;
+    const EBAD: u64 = 0;
+    fun f(x: u64) {
+        assert(x > 0, 1);
+        assert(x > 1, (EBAD: u64));
+        assert(x > 2, Errors::invalid_argument(EBAD));
+        if (x > 3) abort 42;
+        abort x
+    }
+}
+"#;
+        assert_eq!(
+            lints(source),
+            expected(&[
+                (6, "abort_code_not_from_errors"),
+                (7, "abort_code_not_from_errors"),
+                (9, "abort_code_not_from_errors"),
+            ])
+        );
+    }
+
+    #[test]
+    fn role_checks() {
+        let source = r#"
+address 0x1 {
+module M {
+    use 0x1::Roles;
+    use 0x1::Signer;
+    resource struct R { v: u64 }
+    fun check(account: &signer) { Roles::assert_libra_root(account) }
+    public fun unchecked(account: &signer, a: address) acquires R {
+        borrow_global_mut<R>(a).v = 0;
+        Roles::assert_libra_root(account);
+        R { v: _ } = move_from<R>(a);
+    }
+    public fun checked(account: &signer, a: address) acquires R {
+        check(account);
+        R { v: _ } = move_from<R>(a);
+    }
+    public fun own_resource(account: &signer) acquires R {
+        let addr = Signer::address_of(account);
+        borrow_global_mut<R>(addr).v = 1;
+        R { v: _ } = move_from<R>(Signer::address_of(account));
+    }
+    public fun no_signer(a: address) acquires R {
+        borrow_global_mut<R>(a).v = 2;
+    }
+}
+}
+"#;
+        assert_eq!(lints(source), expected(&[(9, "missing_role_check")]));
+    }
+
+    #[test]
+    fn redundant_copies() {
+        let source = r#"
+module M {
+    fun f(x: u64, y: u64, z: u64): u64 {
+        let a = copy x;
+        let b = copy y + y;
+        let r = &z;
+        let c = copy z;
+        while (a > 0) { a = copy b };
+        a + c + *r
+    }
+}
+"#;
+        assert_eq!(lints(source), expected(&[(4, "redundant_copy")]));
+    }
+
+    #[test]
+    fn fixed_point_arithmetic() {
+        let source = r#"
+module M {
+    use 0x1::FixedPoint32::{Self, FixedPoint32};
+    fun f(x: FixedPoint32, y: FixedPoint32, z: u64): u64 {
+        let raw = FixedPoint32::get_raw_value(x);
+        let sum = raw + z;
+        let prod = (FixedPoint32::get_raw_value(y) as u128) * 2;
+        sum + (prod as u64) + z * 2
+    }
+}
+"#;
+        assert_eq!(
+            lints(source),
+            expected(&[
+                (6, "unchecked_fixed_point_arithmetic"),
+                (7, "unchecked_fixed_point_arithmetic"),
+            ])
+        );
+    }
+
+    #[test]
+    fn configured_levels() {
+        let source = r#"
+script {
+    fun main<T>(x: u64) {
+        let y = copy x;
+        assert(y > 0, 1)
+    }
+}
+"#;
+        assert_eq!(
+            lints(source),
+            expected(&[
+                (3, "unused_type_parameter"),
+                (4, "redundant_copy"),
+                (5, "abort_code_not_from_errors"),
+            ])
+        );
+
+        let config = LintConfig::parse(
+            "[rules]\nunused_type_parameter = \"allow\"\nredundant_copy = \"error\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.level("redundant_copy"), Level::Error);
+        assert_eq!(config.level("missing_role_check"), Level::Warn);
+        assert_eq!(
+            lint_source(source, &config),
+            expected(&[(4, "redundant_copy"), (5, "abort_code_not_from_errors")])
+        );
+
+        assert!(LintConfig::parse("[rules]\nno_such_rule = \"warn\"\n").is_err());
+        assert!(LintConfig::parse("[rules]\nredundant_copy = \"deny\"\n").is_err());
+    }
+
+    #[test]
+    fn lines_and_columns() {
+        let text = "module M {\n    fun f() {}\n}";
+        assert_eq!(line_and_column(text, 0), (1, 1));
+        assert_eq!(line_and_column(text, 15), (2, 5));
+        assert_eq!(line_and_column(text, text.len()), (3, 2));
+    }
+}
@@ -0,0 +1,533 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! The lint rules.
+//!
+//! Unused `acquires` annotations are not a rule: the typing pass already rejects an annotation that
+//! is not needed by the function or one of its callees.
+
+use crate::visit::{self, strip, Visitor};
+use move_ir_types::location::*;
+use move_lang::{
+    naming::ast::{BuiltinTypeName_, TParamID, Type, TypeName_, Type_},
+    parser::ast::{BinOp_, FunctionName, FunctionVisibility, Identifier, ModuleIdent, Var},
+    shared::Address,
+    typing::ast as T,
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A check over the typed program
+pub trait Rule {
+    /// The name of the rule, as used in configurations and reports
+    fn name(&self) -> &'static str;
+
+    /// What the rule reports
+    fn description(&self) -> &'static str;
+
+    /// Check `unit` and add the location and message of each violation to `violations`
+    fn check(&self, unit: &Unit, violations: &mut Vec<(Loc, String)>);
+}
+
+/// A source module, or a script, to lint
+pub struct Unit<'a> {
+    /// The module, or `None` for a script
+    pub module: Option<ModuleIdent>,
+    pub functions: Vec<(FunctionName, &'a T::Function)>,
+}
+
+impl<'a> Unit<'a> {
+    fn is_core_module(&self, name: &str) -> bool {
+        self.module
+            .as_ref()
+            .map_or(false, |m| is_core_module(m, name))
+    }
+}
+
+/// All the rules, in the order their violations are reported for a given location
+pub fn all_rules() -> Vec<Box<dyn Rule>> {
+    vec![
+        Box::new(UnusedTypeParameter),
+        Box::new(AbortCodeNotFromErrors),
+        Box::new(MissingRoleCheck),
+        Box::new(RedundantCopy),
+        Box::new(UncheckedFixedPointArithmetic),
+    ]
+}
+
+//**************************************************************************************************
+// Unused type parameters
+//**************************************************************************************************
+
+pub struct UnusedTypeParameter;
+
+#[derive(Default)]
+struct TypeParameterUses(BTreeSet<TParamID>);
+
+impl Visitor for TypeParameterUses {
+    fn type_(&mut self, ty: &Type) {
+        match &ty.value {
+            Type_::Param(tparam) => {
+                self.0.insert(tparam.id);
+            }
+            Type_::Ref(_, inner) => self.type_(inner),
+            Type_::Apply(_, _, tys) => tys.iter().for_each(|ty| self.type_(ty)),
+            Type_::Unit | Type_::Var(_) | Type_::Anything | Type_::UnresolvedError => (),
+        }
+    }
+}
+
+impl Rule for UnusedTypeParameter {
+    fn name(&self) -> &'static str {
+        "unused_type_parameter"
+    }
+
+    fn description(&self) -> &'static str {
+        "type parameters of a function that are not used by its signature or its body"
+    }
+
+    fn check(&self, unit: &Unit, violations: &mut Vec<(Loc, String)>) {
+        for (fname, fdef) in &unit.functions {
+            if let T::FunctionBody_::Native = fdef.body.value {
+                continue;
+            }
+            let mut uses = TypeParameterUses::default();
+            visit::function(&mut uses, fdef);
+            for tparam in &fdef.signature.type_parameters {
+                if !uses.0.contains(&tparam.id) {
+                    let msg = format!(
+                        "Unused type parameter '{}' of '{}'",
+                        tparam.user_specified_name, fname
+                    );
+                    violations.push((tparam.user_specified_name.loc, msg))
+                }
+            }
+        }
+    }
+}
+
+//**************************************************************************************************
+// Abort codes
+//**************************************************************************************************
+
+pub struct AbortCodeNotFromErrors;
+
+struct AbortCodes<'a> {
+    violations: &'a mut Vec<(Loc, String)>,
+}
+
+impl<'a> AbortCodes<'a> {
+    /// Report `code` if it is a literal or a constant. Codes computed any other way may come from
+    /// the `Errors` module, e.g. when passed in by the caller.
+    fn check_code(&mut self, code: &T::Exp) {
+        use T::UnannotatedExp_ as E;
+        let code = strip(code);
+        match &code.exp.value {
+            E::Value(_) | E::InferredNum(_) | E::Constant(_, _) => {
+                let msg = "Abort code not built with the 'Errors' module. Pass the reason to one \
+                           of the functions of '0x1::Errors' to give the code a category";
+                self.violations.push((code.exp.loc, msg.to_string()))
+            }
+            _ => (),
+        }
+    }
+}
+
+impl<'a> Visitor for AbortCodes<'a> {
+    fn enter_exp(&mut self, e: &T::Exp) -> bool {
+        use T::UnannotatedExp_ as E;
+        match &e.exp.value {
+            E::Abort(code) => self.check_code(code),
+            E::Builtin(b, args) if matches!(b.value, T::BuiltinFunction_::Assert) => {
+                if let E::ExpList(items) = &args.exp.value {
+                    if let Some(T::ExpListItem::Single(code, _)) = items.get(1) {
+                        self.check_code(code)
+                    }
+                }
+            }
+            _ => (),
+        }
+        true
+    }
+}
+
+impl Rule for AbortCodeNotFromErrors {
+    fn name(&self) -> &'static str {
+        "abort_code_not_from_errors"
+    }
+
+    fn description(&self) -> &'static str {
+        "literal or constant abort codes, which should be built with the functions of \
+         '0x1::Errors'"
+    }
+
+    fn check(&self, unit: &Unit, violations: &mut Vec<(Loc, String)>) {
+        if unit.is_core_module("Errors") {
+            return;
+        }
+        let mut codes = AbortCodes { violations };
+        for (_, fdef) in &unit.functions {
+            visit::function(&mut codes, fdef)
+        }
+    }
+}
+
+//**************************************************************************************************
+// Role checks
+//**************************************************************************************************
+
+/// Only applies to the modules published at `0x1`, which are the only ones that can check roles
+pub struct MissingRoleCheck;
+
+/// The calls of a function, to functions of the `Roles` module and of the module being linted
+struct Calls<'a> {
+    module: Option<&'a ModuleIdent>,
+    calls_roles: bool,
+    local_calls: BTreeSet<String>,
+}
+
+impl<'a> Visitor for Calls<'a> {
+    fn enter_exp(&mut self, e: &T::Exp) -> bool {
+        if let T::UnannotatedExp_::ModuleCall(call) = &e.exp.value {
+            if is_core_module(&call.module, "Roles") {
+                self.calls_roles = true
+            } else if Some(&call.module) == self.module {
+                self.local_calls.insert(call.name.value().to_string());
+            }
+        }
+        true
+    }
+}
+
+/// Report the mutations of global storage that happen before any role check, except for the ones
+/// under the address of the signer
+struct RoleChecks<'a> {
+    module: Option<&'a ModuleIdent>,
+    /// The functions of the module that check roles, directly or not
+    checking_functions: &'a BTreeSet<String>,
+    checked: bool,
+    /// The variables holding the address of the signer
+    signer_addresses: BTreeSet<String>,
+    violations: &'a mut Vec<(Loc, String)>,
+}
+
+impl<'a> RoleChecks<'a> {
+    fn is_signer_address(&self, e: &T::Exp) -> bool {
+        use T::UnannotatedExp_ as E;
+        match &strip(e).exp.value {
+            E::ModuleCall(call) => is_core_function(call, "Signer", "address_of"),
+            E::Copy { var, .. } | E::Move { var, .. } => {
+                self.signer_addresses.contains(var.value())
+            }
+            _ => false,
+        }
+    }
+}
+
+impl<'a> Visitor for RoleChecks<'a> {
+    fn enter_exp(&mut self, e: &T::Exp) -> bool {
+        use T::BuiltinFunction_ as B;
+        use T::UnannotatedExp_ as E;
+        match &e.exp.value {
+            E::ModuleCall(call) => {
+                if is_core_module(&call.module, "Roles")
+                    || (Some(&call.module) == self.module
+                        && self.checking_functions.contains(call.name.value()))
+                {
+                    self.checked = true
+                }
+            }
+            E::Builtin(b, args) if !self.checked => {
+                let name = match &b.value {
+                    B::MoveFrom(_) | B::BorrowGlobal(true, _) => b.value.display_name(),
+                    _ => return true,
+                };
+                if !self.is_signer_address(args) {
+                    let msg = format!(
+                        "'{}' modifies global storage before the role of the signer is checked \
+                         with the 'Roles' module",
+                        name
+                    );
+                    self.violations.push((e.exp.loc, msg))
+                }
+            }
+            _ => (),
+        }
+        true
+    }
+
+    fn bind(&mut self, lvalues: &T::LValueList, e: &T::Exp) {
+        if let [sp!(_, T::LValue_::Var(var, _))] = lvalues.value.as_slice() {
+            if self.is_signer_address(e) {
+                self.signer_addresses.insert(var.value().to_string());
+            } else {
+                self.signer_addresses.remove(var.value());
+            }
+        }
+    }
+}
+
+fn has_signer_parameter(fdef: &T::Function) -> bool {
+    fdef.signature
+        .parameters
+        .iter()
+        .any(|(_, ty)| match &ty.value {
+            Type_::Ref(_, inner) => matches!(
+                &inner.value,
+                Type_::Apply(_, sp!(_, TypeName_::Builtin(sp!(_, BuiltinTypeName_::Signer))), _)
+            ),
+            _ => false,
+        })
+}
+
+impl Rule for MissingRoleCheck {
+    fn name(&self) -> &'static str {
+        "missing_role_check"
+    }
+
+    fn description(&self) -> &'static str {
+        "public functions of 0x1 modules taking a signer that move or mutably borrow a resource \
+         before checking the role of the signer with the 'Roles' module"
+    }
+
+    fn check(&self, unit: &Unit, violations: &mut Vec<(Loc, String)>) {
+        let module = match &unit.module {
+            Some(m) if m.0.value.address == Address::LIBRA_CORE => m,
+            _ => return,
+        };
+        if unit.is_core_module("Roles") {
+            return;
+        }
+
+        let calls = unit
+            .functions
+            .iter()
+            .map(|(fname, fdef)| {
+                let mut calls = Calls {
+                    module: Some(module),
+                    calls_roles: false,
+                    local_calls: BTreeSet::new(),
+                };
+                visit::function(&mut calls, fdef);
+                (fname.value().to_string(), calls)
+            })
+            .collect::<BTreeMap<_, _>>();
+        // A function checks roles if it calls `Roles`, or a function of the module that does
+        let mut checking_functions = BTreeSet::new();
+        loop {
+            let before = checking_functions.len();
+            for (fname, calls) in &calls {
+                if calls.calls_roles
+                    || calls
+                        .local_calls
+                        .iter()
+                        .any(|callee| checking_functions.contains(callee))
+                {
+                    checking_functions.insert(fname.clone());
+                }
+            }
+            if checking_functions.len() == before {
+                break;
+            }
+        }
+
+        for (_, fdef) in &unit.functions {
+            if !matches!(fdef.visibility, FunctionVisibility::Public(_))
+                || !has_signer_parameter(fdef)
+            {
+                continue;
+            }
+            let mut checks = RoleChecks {
+                module: Some(module),
+                checking_functions: &checking_functions,
+                checked: false,
+                signer_addresses: BTreeSet::new(),
+                violations,
+            };
+            visit::function(&mut checks, fdef);
+        }
+    }
+}
+
+//**************************************************************************************************
+// Redundant copies
+//**************************************************************************************************
+
+pub struct RedundantCopy;
+
+#[derive(Default)]
+struct LocalUses {
+    loop_depth: usize,
+    /// The explicit copies outside of loops
+    copies: Vec<(Var, Loc)>,
+    /// The location of every use, assignment or binding of each variable
+    uses: BTreeMap<String, Vec<Loc>>,
+    borrowed: BTreeSet<String>,
+}
+
+impl LocalUses {
+    fn add_use(&mut self, var: &Var, loc: Loc) {
+        self.uses
+            .entry(var.value().to_string())
+            .or_insert_with(Vec::new)
+            .push(loc)
+    }
+}
+
+impl Visitor for LocalUses {
+    fn enter_exp(&mut self, e: &T::Exp) -> bool {
+        use T::UnannotatedExp_ as E;
+        let loc = e.exp.loc;
+        match &e.exp.value {
+            E::While(_, _) | E::Loop { .. } => self.loop_depth += 1,
+            E::Copy {
+                from_user: true,
+                var,
+            } if self.loop_depth == 0 => {
+                self.copies.push((var.clone(), loc));
+                self.add_use(var, loc)
+            }
+            E::Copy { var, .. } | E::Move { var, .. } | E::Use(var) => self.add_use(var, loc),
+            E::BorrowLocal(_, var) => {
+                self.borrowed.insert(var.value().to_string());
+                self.add_use(var, loc)
+            }
+            E::Spec(_, used_locals) => used_locals.keys().for_each(|var| self.add_use(var, loc)),
+            _ => (),
+        }
+        true
+    }
+
+    fn exit_exp(&mut self, e: &T::Exp) {
+        if let T::UnannotatedExp_::While(_, _) | T::UnannotatedExp_::Loop { .. } = &e.exp.value {
+            self.loop_depth -= 1
+        }
+    }
+
+    fn lvalue(&mut self, lvalue: &T::LValue) {
+        if let T::LValue_::Var(var, _) = &lvalue.value {
+            self.add_use(var, lvalue.loc)
+        }
+    }
+}
+
+impl Rule for RedundantCopy {
+    fn name(&self) -> &'static str {
+        "redundant_copy"
+    }
+
+    fn description(&self) -> &'static str {
+        "explicit copies of a variable that is not used afterwards, and could be moved instead"
+    }
+
+    fn check(&self, unit: &Unit, violations: &mut Vec<(Loc, String)>) {
+        for (_, fdef) in &unit.functions {
+            let mut locals = LocalUses::default();
+            visit::function(&mut locals, fdef);
+            for (var, loc) in &locals.copies {
+                // a borrowed variable may not be movable while the reference is alive
+                if locals.borrowed.contains(var.value()) {
+                    continue;
+                }
+                let used_later = locals.uses[var.value()]
+                    .iter()
+                    .any(|use_loc| use_loc.span().start() > loc.span().start());
+                if !used_later {
+                    let msg = format!(
+                        "'{}' is not used after this copy. Use 'move {}' instead",
+                        var, var
+                    );
+                    violations.push((*loc, msg))
+                }
+            }
+        }
+    }
+}
+
+//**************************************************************************************************
+// Fixed point arithmetic
+//**************************************************************************************************
+
+pub struct UncheckedFixedPointArithmetic;
+
+#[derive(Default)]
+struct RawValueArithmetic {
+    /// The variables holding the raw value of a fixed point number
+    raw_values: BTreeSet<String>,
+    violations: Vec<(Loc, String)>,
+}
+
+impl RawValueArithmetic {
+    fn is_raw_value(&self, e: &T::Exp) -> bool {
+        use T::UnannotatedExp_ as E;
+        match &strip(e).exp.value {
+            E::ModuleCall(call) => is_core_function(call, "FixedPoint32", "get_raw_value"),
+            E::Copy { var, .. } | E::Move { var, .. } => self.raw_values.contains(var.value()),
+            _ => false,
+        }
+    }
+}
+
+impl Visitor for RawValueArithmetic {
+    fn enter_exp(&mut self, e: &T::Exp) -> bool {
+        use BinOp_ as B;
+        if let T::UnannotatedExp_::BinopExp(lhs, op, _, rhs) = &e.exp.value {
+            let arithmetic = matches!(
+                op.value,
+                B::Add | B::Sub | B::Mul | B::Mod | B::Div | B::Shl | B::Shr
+            );
+            if arithmetic && (self.is_raw_value(lhs) || self.is_raw_value(rhs)) {
+                let msg = format!(
+                    "'{}' on the raw value of a 'FixedPoint32' is not checked for overflow or \
+                     loss of precision. Use the functions of '0x1::FixedPoint32' instead",
+                    op
+                );
+                self.violations.push((e.exp.loc, msg))
+            }
+        }
+        true
+    }
+
+    fn bind(&mut self, lvalues: &T::LValueList, e: &T::Exp) {
+        if let [sp!(_, T::LValue_::Var(var, _))] = lvalues.value.as_slice() {
+            if self.is_raw_value(e) {
+                self.raw_values.insert(var.value().to_string());
+            } else {
+                self.raw_values.remove(var.value());
+            }
+        }
+    }
+}
+
+impl Rule for UncheckedFixedPointArithmetic {
+    fn name(&self) -> &'static str {
+        "unchecked_fixed_point_arithmetic"
+    }
+
+    fn description(&self) -> &'static str {
+        "arithmetic on the raw value of a 'FixedPoint32' instead of its checked functions"
+    }
+
+    fn check(&self, unit: &Unit, violations: &mut Vec<(Loc, String)>) {
+        if unit.is_core_module("FixedPoint32") {
+            return;
+        }
+        for (_, fdef) in &unit.functions {
+            let mut arithmetic = RawValueArithmetic::default();
+            visit::function(&mut arithmetic, fdef);
+            violations.extend(arithmetic.violations)
+        }
+    }
+}
+
+//**************************************************************************************************
+// Utils
+//**************************************************************************************************
+
+/// Whether `m` is the module called `name` published at `0x1`
+fn is_core_module(m: &ModuleIdent, name: &str) -> bool {
+    m.0.value.address == Address::LIBRA_CORE && m.0.value.name.value() == name
+}
+
+fn is_core_function(call: &T::ModuleCall, module: &str, function: &str) -> bool {
+    is_core_module(&call.module, module) && call.name.value() == function
+}
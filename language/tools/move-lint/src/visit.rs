@@ -0,0 +1,171 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Traversal of the typed AST shared by the lint rules.
+//!
+//! Expressions are visited in evaluation order, so that a rule can tell what happens before what.
+
+use move_lang::{naming::ast::Type, typing::ast as T};
+
+/// The hooks called while walking a function body. All of them do nothing by default.
+pub trait Visitor {
+    /// Called on an expression before its subexpressions, which are skipped if this returns false
+    fn enter_exp(&mut self, _e: &T::Exp) -> bool {
+        true
+    }
+
+    /// Called on an expression after its subexpressions
+    fn exit_exp(&mut self, _e: &T::Exp) {}
+
+    /// Called when `lvalues` are bound or assigned the value of `e`, after visiting `e`
+    fn bind(&mut self, _lvalues: &T::LValueList, _e: &T::Exp) {}
+
+    /// Called on each lvalue, including the ones nested in unpacks
+    fn lvalue(&mut self, _lvalue: &T::LValue) {}
+
+    /// Called on each type written or inferred in the body, but not on the types nested in them
+    fn type_(&mut self, _ty: &Type) {}
+}
+
+/// Walk the signature and the body of `fdef`
+pub fn function<V: Visitor>(v: &mut V, fdef: &T::Function) {
+    for (_, ty) in &fdef.signature.parameters {
+        v.type_(ty)
+    }
+    v.type_(&fdef.signature.return_type);
+    if let T::FunctionBody_::Defined(seq) = &fdef.body.value {
+        sequence(v, seq)
+    }
+}
+
+fn sequence<V: Visitor>(v: &mut V, seq: &T::Sequence) {
+    use T::SequenceItem_ as S;
+    for item in seq {
+        match &item.value {
+            S::Seq(e) => exp(v, e),
+            S::Declare(lvalues) => lvalue_list(v, lvalues),
+            S::Bind(lvalues, tys, e) => {
+                exp(v, e);
+                tys.iter().flatten().for_each(|ty| v.type_(ty));
+                v.bind(lvalues, e);
+                lvalue_list(v, lvalues)
+            }
+        }
+    }
+}
+
+fn lvalue_list<V: Visitor>(v: &mut V, lvalues: &T::LValueList) {
+    for lvalue in &lvalues.value {
+        lvalue_(v, lvalue)
+    }
+}
+
+fn lvalue_<V: Visitor>(v: &mut V, lvalue: &T::LValue) {
+    use T::LValue_ as L;
+    v.lvalue(lvalue);
+    match &lvalue.value {
+        L::Ignore => (),
+        L::Var(_, ty) => v.type_(ty),
+        L::Unpack(_, _, tys, fields) | L::BorrowUnpack(_, _, _, tys, fields) => {
+            tys.iter().for_each(|ty| v.type_(ty));
+            for (_, (_, (ty, inner))) in fields {
+                v.type_(ty);
+                lvalue_(v, inner)
+            }
+        }
+    }
+}
+
+/// Walk `e` and its subexpressions
+pub fn exp<V: Visitor>(v: &mut V, e: &T::Exp) {
+    use T::BuiltinFunction_ as B;
+    use T::UnannotatedExp_ as E;
+    if !v.enter_exp(e) {
+        return;
+    }
+    v.type_(&e.ty);
+    match &e.exp.value {
+        E::ModuleCall(call) => {
+            call.type_arguments.iter().for_each(|ty| v.type_(ty));
+            exp(v, &call.arguments)
+        }
+        E::Builtin(b, args) => {
+            match &b.value {
+                B::MoveTo(ty)
+                | B::MoveFrom(ty)
+                | B::BorrowGlobal(_, ty)
+                | B::Exists(ty)
+                | B::Freeze(ty) => v.type_(ty),
+                B::Assert => (),
+            }
+            exp(v, args)
+        }
+        E::Return(e)
+        | E::Abort(e)
+        | E::Dereference(e)
+        | E::UnaryExp(_, e)
+        | E::Borrow(_, e, _)
+        | E::TempBorrow(_, e)
+        | E::Loop { body: e, .. } => exp(v, e),
+        E::IfElse(cond, if_true, if_false) => {
+            exp(v, cond);
+            exp(v, if_true);
+            exp(v, if_false)
+        }
+        E::While(e1, e2) | E::BinopExp(e1, _, _, e2) => {
+            exp(v, e1);
+            exp(v, e2)
+        }
+        // the value is computed before the reference it is written to
+        E::Mutate(lhs, rhs) => {
+            exp(v, rhs);
+            exp(v, lhs)
+        }
+        E::Block(seq) => sequence(v, seq),
+        E::Assign(lvalues, tys, e) => {
+            exp(v, e);
+            tys.iter().flatten().for_each(|ty| v.type_(ty));
+            v.bind(lvalues, e);
+            lvalue_list(v, lvalues)
+        }
+        E::Pack(_, _, tys, fields) => {
+            tys.iter().for_each(|ty| v.type_(ty));
+            for (_, (_, (_, e))) in fields {
+                exp(v, e)
+            }
+        }
+        E::ExpList(items) => {
+            for item in items {
+                match item {
+                    T::ExpListItem::Single(e, _) | T::ExpListItem::Splat(_, e, _) => exp(v, e),
+                }
+            }
+        }
+        E::Cast(e, ty) | E::Annotate(e, ty) => {
+            exp(v, e);
+            v.type_(ty)
+        }
+        E::Spec(_, used_locals) => used_locals.values().for_each(|ty| v.type_(ty)),
+        E::Unit { .. }
+        | E::Value(_)
+        | E::InferredNum(_)
+        | E::Move { .. }
+        | E::Copy { .. }
+        | E::Use(_)
+        | E::Constant(_, _)
+        | E::BorrowLocal(_, _)
+        | E::Break
+        | E::Continue
+        | E::UnresolvedError => (),
+    }
+    v.exit_exp(e)
+}
+
+/// `e` without the annotations and casts around it
+pub fn strip(e: &T::Exp) -> &T::Exp {
+    use T::UnannotatedExp_ as E;
+    match &e.exp.value {
+        E::Annotate(inner, _) | E::Cast(inner, _) => strip(inner),
+        _ => e,
+    }
+}
@@ -0,0 +1,793 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checking of Move sources and indexing of the symbols in the typed program.
+//!
+//! The sources are run through the front end up to and including typing. Errors from any pass
+//! become diagnostics. If every file parses, the typed program is walked to record, for each name
+//! in the source, where it is defined and what it should display on hover, which serves the
+//! hover, go-to-definition and completion requests until the next successful analysis.
+//!
+//! Parsed files are kept across analyses, so that only the files whose text changed since the
+//! previous analysis are parsed again.
+
+use crate::{
+    document::{offset_at, position_at},
+    protocol::*,
+};
+use move_ir_types::location::*;
+use move_lang::{
+    errors::{Errors, FilesSourceText},
+    find_move_filenames, intern_file_name, move_check_parsed_to_typing,
+    naming::ast::{FunctionSignature, StructFields, TParam, Type, TypeName_, Type_},
+    parse_file_contents,
+    parser::ast::{
+        ConstantName, Definition, FunctionVisibility, Identifier, ModuleIdent, Program, Var,
+    },
+    shared::{format_comma, unique_map::UniqueMap, Address},
+    typing::ast as T,
+};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The result of checking the sources
+#[derive(Debug)]
+pub struct Analysis {
+    /// File -> the diagnostics for the file, for every file with errors
+    pub diagnostics: BTreeMap<PathBuf, Vec<Diagnostic>>,
+    /// The symbols of the program, if every file parsed
+    pub symbols: Option<Symbols>,
+}
+
+/// The files parsed by previous analyses
+#[derive(Debug, Default)]
+pub struct ParsedFiles {
+    files: BTreeMap<&'static str, ParsedFile>,
+}
+
+#[derive(Debug)]
+struct ParsedFile {
+    text: String,
+    definitions: Result<Vec<Definition>, Errors>,
+}
+
+impl ParsedFiles {
+    /// The definitions in `fname`, parsing `text` unless it was already parsed for `fname`
+    fn parse(&mut self, fname: &'static str, text: String) -> &ParsedFile {
+        match self.files.get(fname) {
+            Some(parsed) if parsed.text == text => (),
+            _ => {
+                let definitions = parse_file_contents(fname, &text).map(|(defs, _)| defs);
+                self.files.insert(fname, ParsedFile { text, definitions });
+            }
+        }
+        &self.files[fname]
+    }
+}
+
+/// Check `targets` against `deps`, reading the files with an entry in `overlay` from there and
+/// only parsing the files that changed since they were added to `parsed_files`
+pub fn analyze(
+    targets: &[String],
+    deps: &[String],
+    sender_opt: Option<Address>,
+    overlay: &HashMap<String, String>,
+    parsed_files: &mut ParsedFiles,
+) -> anyhow::Result<Analysis> {
+    let mut files = FilesSourceText::new();
+    let mut parse_errors = vec![];
+    let mut parse = |fname: &str, defs: &mut Vec<Definition>| -> anyhow::Result<()> {
+        let text = match overlay.get(fname) {
+            Some(text) => text.clone(),
+            None => fs::read_to_string(fname)?,
+        };
+        let fname = intern_file_name(fname);
+        let parsed = parsed_files.parse(fname, text);
+        match &parsed.definitions {
+            Ok(file_defs) => defs.extend(file_defs.iter().cloned()),
+            Err(errors) => parse_errors.extend(errors.iter().cloned()),
+        }
+        files.insert(fname, parsed.text.clone());
+        Ok(())
+    };
+    let mut source_definitions = vec![];
+    for fname in find_move_filenames(targets, true)? {
+        parse(&fname, &mut source_definitions)?;
+    }
+    let mut lib_definitions = vec![];
+    for fname in find_move_filenames(deps, true)? {
+        parse(&fname, &mut lib_definitions)?;
+    }
+    // forget the files that are no longer checked
+    parsed_files
+        .files
+        .retain(|fname, _| files.contains_key(fname));
+
+    if !parse_errors.is_empty() {
+        return Ok(Analysis {
+            diagnostics: diagnostics(&files, parse_errors),
+            symbols: None,
+        });
+    }
+    let pprog = Program {
+        source_definitions,
+        lib_definitions,
+    };
+    let (tprog_opt, errors) = move_check_parsed_to_typing(pprog, sender_opt);
+    Ok(Analysis {
+        diagnostics: diagnostics(&files, errors),
+        symbols: tprog_opt.map(|tprog| Symbols::new(&files, &tprog)),
+    })
+}
+
+fn diagnostics(files: &FilesSourceText, errors: Errors) -> BTreeMap<PathBuf, Vec<Diagnostic>> {
+    let mut diagnostics = BTreeMap::new();
+    for error in errors {
+        let mut labels = error.into_iter();
+        let (path, range, message) = match labels.next() {
+            Some((loc, message)) => match loc_range(files, loc) {
+                Some((path, range)) => (path, range, message),
+                None => continue,
+            },
+            None => continue,
+        };
+        let related_information = labels
+            .filter_map(|(loc, message)| {
+                Some(DiagnosticRelatedInformation {
+                    location: loc_location(files, loc)?,
+                    message,
+                })
+            })
+            .collect();
+        diagnostics
+            .entry(path)
+            .or_insert_with(Vec::new)
+            .push(Diagnostic {
+                range,
+                severity: DIAGNOSTIC_SEVERITY_ERROR,
+                source: "move".to_string(),
+                message,
+                related_information,
+            });
+    }
+    diagnostics
+}
+
+fn loc_range(files: &FilesSourceText, loc: Loc) -> Option<(PathBuf, Range)> {
+    let text = files.get(loc.file())?;
+    let range = Range {
+        start: position_at(text, loc.span().start().to_usize()),
+        end: position_at(text, loc.span().end().to_usize()),
+    };
+    Some((PathBuf::from(loc.file()), range))
+}
+
+fn loc_location(files: &FilesSourceText, loc: Loc) -> Option<Location> {
+    let (path, range) = loc_range(files, loc)?;
+    Some(Location {
+        uri: path_to_uri(&path)?,
+        range,
+    })
+}
+
+//**************************************************************************************************
+// Symbols
+//**************************************************************************************************
+
+/// A name in the source, either where it is defined or where it is used
+#[derive(Debug, Clone)]
+struct Symbol {
+    range: Range,
+    def: Option<Location>,
+    /// The declaration of the name, shown on hover
+    description: String,
+    /// The fully qualified name of the type of the symbol, if it is a struct or a reference to
+    /// one. Used to complete field accesses.
+    struct_type: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct Symbols {
+    /// File -> the symbols in the file
+    symbols: BTreeMap<PathBuf, Vec<Symbol>>,
+    /// Module name -> the members of the modules with that name
+    module_members: BTreeMap<String, Vec<CompletionItem>>,
+    /// Fully qualified struct name -> the fields of the struct
+    struct_fields: BTreeMap<String, Vec<CompletionItem>>,
+}
+
+impl Symbols {
+    fn new(files: &FilesSourceText, prog: &T::Program) -> Self {
+        let defs = Definitions::new(prog);
+        let mut symbols = Symbols::default();
+        for (mident, mdef) in &prog.modules {
+            let name = mident.0.value.name.value().to_owned();
+            let members = symbols.module_members.entry(name).or_insert_with(Vec::new);
+            for (sname, _) in &mdef.structs {
+                let key = struct_key(&mident, sname.value());
+                members.push(CompletionItem {
+                    label: sname.value().to_owned(),
+                    kind: COMPLETION_KIND_STRUCT,
+                    detail: Some(defs.structs[&key].description.clone()),
+                });
+                let fields = defs
+                    .fields
+                    .range((key.clone(), String::new())..)
+                    .take_while(|((s, _), _)| s == &key)
+                    .map(|((_, f), def)| CompletionItem {
+                        label: f.clone(),
+                        kind: COMPLETION_KIND_FIELD,
+                        detail: Some(def.description.clone()),
+                    })
+                    .collect();
+                symbols.struct_fields.insert(key, fields);
+            }
+            for (fname, _) in &mdef.functions {
+                let def = &defs.functions[&(mident.to_string(), fname.value().to_owned())];
+                members.push(CompletionItem {
+                    label: fname.value().to_owned(),
+                    kind: COMPLETION_KIND_FUNCTION,
+                    detail: Some(def.description.clone()),
+                });
+            }
+            for (cname, _) in &mdef.constants {
+                let def = &defs.constants[&(mident.to_string(), cname.value().to_owned())];
+                members.push(CompletionItem {
+                    label: cname.value().to_owned(),
+                    kind: COMPLETION_KIND_CONSTANT,
+                    detail: Some(def.description.clone()),
+                });
+            }
+        }
+
+        let mut context = Context {
+            files,
+            defs: &defs,
+            symbols: &mut symbols.symbols,
+            scope: String::new(),
+            locals: vec![],
+        };
+        for (mident, mdef) in &prog.modules {
+            context.scope = mident.to_string();
+            module(&mut context, mdef);
+        }
+        for (name, sdef) in &prog.scripts {
+            context.scope = name.clone();
+            script(&mut context, sdef);
+        }
+        symbols
+    }
+
+    /// The declaration of the name at `pos`
+    pub fn hover(&self, path: &Path, pos: Position) -> Option<Hover> {
+        let symbol = self.symbol_at(path, pos)?;
+        Some(Hover {
+            contents: MarkupContent {
+                kind: "markdown".to_string(),
+                value: format!("```move\n{}\n```", symbol.description),
+            },
+            range: symbol.range,
+        })
+    }
+
+    /// Where the name at `pos` is defined
+    pub fn definition(&self, path: &Path, pos: Position) -> Option<Location> {
+        self.symbol_at(path, pos)?.def.clone()
+    }
+
+    /// Completions at `pos` in `text`, the current contents of `path`:
+    /// - the fields of a struct after `e.`, if the type of `e` is known
+    /// - the members of a module after `M::`
+    /// - the names of the modules otherwise
+    pub fn completions(&self, path: &Path, text: &str, pos: Position) -> Vec<CompletionItem> {
+        let prefix = &text[..offset_at(text, pos)];
+        let before = prefix.trim_end_matches(is_ident_char);
+        if before.ends_with('.') {
+            let dot = position_at(text, before.len() - 1);
+            return self
+                .symbols
+                .get(path)
+                .and_then(|symbols| {
+                    symbols
+                        .iter()
+                        .find(|symbol| symbol.range.end == dot && symbol.struct_type.is_some())
+                })
+                .and_then(|symbol| self.struct_fields.get(symbol.struct_type.as_ref()?))
+                .cloned()
+                .unwrap_or_default();
+        }
+        if before.ends_with("::") {
+            let qualifier = before[..before.len() - 2]
+                .rsplit(|c| !is_ident_char(c))
+                .next();
+            return qualifier
+                .and_then(|module_name| self.module_members.get(module_name))
+                .cloned()
+                .unwrap_or_default();
+        }
+        self.module_members
+            .keys()
+            .map(|name| CompletionItem {
+                label: name.clone(),
+                kind: COMPLETION_KIND_MODULE,
+                detail: None,
+            })
+            .collect()
+    }
+
+    /// The innermost symbol containing `pos`
+    fn symbol_at(&self, path: &Path, pos: Position) -> Option<&Symbol> {
+        self.symbols
+            .get(path)?
+            .iter()
+            .filter(|symbol| symbol.range.contains(pos))
+            .min_by_key(|symbol| (Reverse(symbol.range.start), symbol.range.end))
+    }
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+//**************************************************************************************************
+// Definitions
+//**************************************************************************************************
+
+#[derive(Debug)]
+struct Definition {
+    loc: Loc,
+    description: String,
+    struct_type: Option<String>,
+}
+
+/// The definitions of the program. Module members are keyed by their module and name, script
+/// members by the name of their script and their name, and fields by their struct and name.
+#[derive(Debug, Default)]
+struct Definitions {
+    functions: BTreeMap<(String, String), Definition>,
+    structs: BTreeMap<String, Definition>,
+    fields: BTreeMap<(String, String), Definition>,
+    constants: BTreeMap<(String, String), Definition>,
+}
+
+impl Definitions {
+    fn new(prog: &T::Program) -> Self {
+        let mut defs = Definitions::default();
+        for (mident, mdef) in &prog.modules {
+            let scope = mident.to_string();
+            for (sname, sdef) in &mdef.structs {
+                let key = struct_key(&mident, sname.value());
+                let kind = if sdef.resource_opt.is_some() {
+                    "resource struct"
+                } else {
+                    "struct"
+                };
+                let header = format!("{} {}{}", kind, key, type_parameters(&sdef.type_parameters));
+                let description = match &sdef.fields {
+                    StructFields::Native(_) => format!("native {}", header),
+                    StructFields::Defined(fields) => {
+                        let mut field_descriptions = vec![];
+                        for (f, (_, ty)) in fields {
+                            let description = format!("{}: {}", f, format_type(ty));
+                            field_descriptions.push(format!("    {}", description));
+                            let def = Definition {
+                                loc: f.loc(),
+                                description,
+                                struct_type: struct_type(ty),
+                            };
+                            defs.fields.insert((key.clone(), f.value().to_owned()), def);
+                        }
+                        format!("{} {{\n{}\n}}", header, field_descriptions.join(",\n"))
+                    }
+                };
+                let def = Definition {
+                    loc: sname.loc(),
+                    description,
+                    struct_type: None,
+                };
+                defs.structs.insert(key, def);
+            }
+            for (fname, fdef) in &mdef.functions {
+                let def = Definition {
+                    loc: fname.loc(),
+                    description: function_description(Some(&mident), fname.value(), fdef),
+                    struct_type: None,
+                };
+                defs.functions
+                    .insert((scope.clone(), fname.value().to_owned()), def);
+            }
+            defs.add_constants(&scope, &mdef.constants);
+        }
+        for (name, sdef) in &prog.scripts {
+            let fname = &sdef.function_name;
+            let def = Definition {
+                loc: fname.loc(),
+                description: function_description(None, fname.value(), &sdef.function),
+                struct_type: None,
+            };
+            defs.functions
+                .insert((name.clone(), fname.value().to_owned()), def);
+            defs.add_constants(name, &sdef.constants);
+        }
+        defs
+    }
+
+    fn add_constants(&mut self, scope: &str, constants: &UniqueMap<ConstantName, T::Constant>) {
+        for (cname, cdef) in constants {
+            let def = Definition {
+                loc: cname.loc(),
+                description: format!("const {}: {}", cname, format_type(&cdef.signature)),
+                struct_type: None,
+            };
+            self.constants
+                .insert((scope.to_owned(), cname.value().to_owned()), def);
+        }
+    }
+}
+
+fn struct_key(mident: &ModuleIdent, sname: &str) -> String {
+    format!("{}::{}", mident, sname)
+}
+
+/// The fully qualified name of the struct `ty` is or refers to
+fn struct_type(ty: &Type) -> Option<String> {
+    match &ty.value {
+        Type_::Ref(_, inner) => struct_type(inner),
+        Type_::Apply(_, sp!(_, TypeName_::ModuleType(m, s)), _) => Some(struct_key(m, s.value())),
+        _ => None,
+    }
+}
+
+fn format_type(ty: &Type) -> String {
+    match &ty.value {
+        Type_::Unit => "()".to_string(),
+        Type_::Ref(mut_, inner) => {
+            format!("&{}{}", if *mut_ { "mut " } else { "" }, format_type(inner))
+        }
+        Type_::Param(tp) => tp.user_specified_name.value.clone(),
+        Type_::Apply(_, sp!(_, TypeName_::Multiple(_)), tys) => {
+            format!("({})", format_comma(tys.iter().map(format_type)))
+        }
+        Type_::Apply(_, n, tys) if tys.is_empty() => format!("{}", n),
+        Type_::Apply(_, n, tys) => format!("{}<{}>", n, format_comma(tys.iter().map(format_type))),
+        Type_::Var(_) | Type_::Anything | Type_::UnresolvedError => "_".to_string(),
+    }
+}
+
+fn type_parameters(tparams: &[TParam]) -> String {
+    if tparams.is_empty() {
+        String::new()
+    } else {
+        let names = tparams.iter().map(|tp| &tp.user_specified_name.value);
+        format!("<{}>", format_comma(names))
+    }
+}
+
+fn function_description(
+    mident_opt: Option<&ModuleIdent>,
+    name: &str,
+    fdef: &T::Function,
+) -> String {
+    let FunctionSignature {
+        type_parameters: tparams,
+        parameters,
+        return_type,
+    } = &fdef.signature;
+    let visibility = match fdef.visibility {
+        FunctionVisibility::Public(_) => "public ",
+        FunctionVisibility::Friend(_) => "public(friend) ",
+        FunctionVisibility::Internal => "",
+    };
+    let native = match fdef.body.value {
+        T::FunctionBody_::Native => "native ",
+        T::FunctionBody_::Defined(_) => "",
+    };
+    let qualified_name = match mident_opt {
+        Some(mident) => format!("{}::{}", mident, name),
+        None => name.to_owned(),
+    };
+    let parameters = parameters
+        .iter()
+        .map(|(v, ty)| format!("{}: {}", v, format_type(ty)));
+    let return_type = match &return_type.value {
+        Type_::Unit => String::new(),
+        _ => format!(": {}", format_type(return_type)),
+    };
+    format!(
+        "{}{}fun {}{}({}){}",
+        native,
+        visibility,
+        qualified_name,
+        type_parameters(tparams),
+        format_comma(parameters),
+        return_type
+    )
+}
+
+//**************************************************************************************************
+// Uses
+//**************************************************************************************************
+
+/// A local variable in scope
+#[derive(Clone)]
+struct Local {
+    loc: Loc,
+    description: String,
+    struct_type: Option<String>,
+}
+
+struct Context<'a> {
+    files: &'a FilesSourceText,
+    defs: &'a Definitions,
+    symbols: &'a mut BTreeMap<PathBuf, Vec<Symbol>>,
+    /// The module or script being walked
+    scope: String,
+    /// The local variables in scope, innermost block last
+    locals: Vec<BTreeMap<String, Local>>,
+}
+
+impl<'a> Context<'a> {
+    fn add(&mut self, loc: Loc, def_loc: Loc, description: String, struct_type: Option<String>) {
+        let (path, range) = match loc_range(self.files, loc) {
+            Some(path_and_range) => path_and_range,
+            None => return,
+        };
+        let symbol = Symbol {
+            range,
+            def: loc_location(self.files, def_loc),
+            description,
+            struct_type,
+        };
+        self.symbols
+            .entry(path)
+            .or_insert_with(Vec::new)
+            .push(symbol)
+    }
+
+    fn add_def(&mut self, loc: Loc, def: Option<&Definition>) {
+        if let Some(def) = def {
+            self.add(
+                loc,
+                def.loc,
+                def.description.clone(),
+                def.struct_type.clone(),
+            )
+        }
+    }
+
+    fn add_function(&mut self, loc: Loc, scope: String, name: &str) {
+        let defs = self.defs;
+        self.add_def(loc, defs.functions.get(&(scope, name.to_owned())))
+    }
+
+    fn add_struct(&mut self, loc: Loc, mident: &ModuleIdent, name: &str) {
+        let defs = self.defs;
+        self.add_def(loc, defs.structs.get(&struct_key(mident, name)))
+    }
+
+    fn add_field(&mut self, loc: Loc, struct_type: Option<String>, name: &str) {
+        let defs = self.defs;
+        if let Some(s) = struct_type {
+            self.add_def(loc, defs.fields.get(&(s, name.to_owned())))
+        }
+    }
+
+    fn add_constant(&mut self, loc: Loc, scope: String, name: &str) {
+        let defs = self.defs;
+        self.add_def(loc, defs.constants.get(&(scope, name.to_owned())))
+    }
+
+    fn bind_local(&mut self, v: &Var, ty: &Type) {
+        let local = Local {
+            loc: v.loc(),
+            description: format!("{}: {}", v, format_type(ty)),
+            struct_type: struct_type(ty),
+        };
+        self.add(
+            v.loc(),
+            local.loc,
+            local.description.clone(),
+            local.struct_type.clone(),
+        );
+        self.locals
+            .last_mut()
+            .unwrap()
+            .insert(v.value().to_owned(), local);
+    }
+
+    fn use_local(&mut self, v: &Var) {
+        let local = self
+            .locals
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(v.value()))
+            .cloned();
+        if let Some(local) = local {
+            self.add(v.loc(), local.loc, local.description, local.struct_type)
+        }
+    }
+}
+
+fn module(context: &mut Context, mdef: &T::ModuleDefinition) {
+    for (sname, sdef) in &mdef.structs {
+        let scope = context.scope.clone();
+        let key = format!("{}::{}", scope, sname);
+        let defs = context.defs;
+        context.add_def(sname.loc(), defs.structs.get(&key));
+        if let StructFields::Defined(fields) = &sdef.fields {
+            for (f, (_, ty)) in fields {
+                context.add_field(f.loc(), Some(key.clone()), f.value());
+                type_(context, ty);
+            }
+        }
+    }
+    constants(context, &mdef.constants);
+    for (fname, fdef) in &mdef.functions {
+        let scope = context.scope.clone();
+        context.add_function(fname.loc(), scope, fname.value());
+        function(context, fdef);
+    }
+}
+
+fn script(context: &mut Context, sdef: &T::Script) {
+    constants(context, &sdef.constants);
+    let fname = &sdef.function_name;
+    let scope = context.scope.clone();
+    context.add_function(fname.loc(), scope, fname.value());
+    function(context, &sdef.function);
+}
+
+fn constants(context: &mut Context, constants: &UniqueMap<ConstantName, T::Constant>) {
+    for (cname, cdef) in constants {
+        let scope = context.scope.clone();
+        context.add_constant(cname.loc(), scope, cname.value());
+        type_(context, &cdef.signature);
+        exp(context, &cdef.value);
+    }
+}
+
+fn function(context: &mut Context, fdef: &T::Function) {
+    context.locals.push(BTreeMap::new());
+    for (v, ty) in &fdef.signature.parameters {
+        context.bind_local(v, ty);
+        type_(context, ty);
+    }
+    type_(context, &fdef.signature.return_type);
+    if let T::FunctionBody_::Defined(seq) = &fdef.body.value {
+        sequence(context, seq)
+    }
+    context.locals.pop();
+}
+
+/// Record the struct names in a type written in the source. Inferred types are not walked, as
+/// their locations do not point at struct names.
+fn type_(context: &mut Context, ty: &Type) {
+    match &ty.value {
+        Type_::Ref(_, inner) => type_(context, inner),
+        Type_::Apply(_, sp!(_, TypeName_::ModuleType(m, s)), tys) => {
+            context.add_struct(s.loc(), m, s.value());
+            tys.iter().for_each(|t| type_(context, t))
+        }
+        Type_::Apply(_, _, tys) => tys.iter().for_each(|t| type_(context, t)),
+        Type_::Unit
+        | Type_::Param(_)
+        | Type_::Var(_)
+        | Type_::Anything
+        | Type_::UnresolvedError => {}
+    }
+}
+
+fn sequence(context: &mut Context, seq: &T::Sequence) {
+    use T::SequenceItem_ as S;
+    context.locals.push(BTreeMap::new());
+    for item in seq {
+        match &item.value {
+            S::Seq(e) => exp(context, e),
+            S::Declare(lvalues) => lvalue_list(context, lvalues, true),
+            S::Bind(lvalues, _, e) => {
+                exp(context, e);
+                lvalue_list(context, lvalues, true)
+            }
+        }
+    }
+    context.locals.pop();
+}
+
+/// Record the names in `lvalues`. Variables are bound if `bind` is set, and assigned otherwise.
+fn lvalue_list(context: &mut Context, lvalues: &T::LValueList, bind: bool) {
+    for lvalue in &lvalues.value {
+        lvalue_(context, lvalue, bind)
+    }
+}
+
+fn lvalue_(context: &mut Context, lvalue: &T::LValue, bind: bool) {
+    use T::LValue_ as L;
+    match &lvalue.value {
+        L::Ignore => (),
+        L::Var(v, ty) if bind => context.bind_local(v, ty),
+        L::Var(v, _) => context.use_local(v),
+        L::Unpack(m, s, _, fields) | L::BorrowUnpack(_, m, s, _, fields) => {
+            context.add_struct(s.loc(), m, s.value());
+            for (f, (_, (_, inner))) in fields {
+                context.add_field(f.loc(), Some(struct_key(m, s.value())), f.value());
+                lvalue_(context, inner, bind)
+            }
+        }
+    }
+}
+
+fn exp(context: &mut Context, e: &T::Exp) {
+    use T::UnannotatedExp_ as E;
+    match &e.exp.value {
+        E::Move { var, .. } | E::Copy { var, .. } | E::Use(var) | E::BorrowLocal(_, var) => {
+            context.use_local(var)
+        }
+        E::Constant(mident_opt, c) => {
+            let scope = match mident_opt {
+                Some(mident) => mident.to_string(),
+                None => context.scope.clone(),
+            };
+            context.add_constant(c.loc(), scope, c.value())
+        }
+        E::ModuleCall(call) => {
+            context.add_function(call.name.loc(), call.module.to_string(), call.name.value());
+            exp(context, &call.arguments)
+        }
+        E::Builtin(_, e)
+        | E::Return(e)
+        | E::Abort(e)
+        | E::Dereference(e)
+        | E::UnaryExp(_, e)
+        | E::TempBorrow(_, e)
+        | E::Loop { body: e, .. } => exp(context, e),
+        E::IfElse(cond, if_true, if_false) => {
+            exp(context, cond);
+            exp(context, if_true);
+            exp(context, if_false)
+        }
+        E::While(e1, e2) | E::Mutate(e1, e2) | E::BinopExp(e1, _, _, e2) => {
+            exp(context, e1);
+            exp(context, e2)
+        }
+        E::Block(seq) => sequence(context, seq),
+        E::Assign(lvalues, _, e) => {
+            exp(context, e);
+            lvalue_list(context, lvalues, false)
+        }
+        E::Pack(m, s, _, fields) => {
+            context.add_struct(s.loc(), m, s.value());
+            for (f, (_, (_, e))) in fields {
+                context.add_field(f.loc(), Some(struct_key(m, s.value())), f.value());
+                exp(context, e)
+            }
+        }
+        E::ExpList(items) => {
+            for item in items {
+                match item {
+                    T::ExpListItem::Single(e, _) | T::ExpListItem::Splat(_, e, _) => {
+                        exp(context, e)
+                    }
+                }
+            }
+        }
+        E::Borrow(_, e, f) => {
+            exp(context, e);
+            context.add_field(f.loc(), struct_type(&e.ty), f.value())
+        }
+        E::Cast(e, ty) | E::Annotate(e, ty) => {
+            exp(context, e);
+            type_(context, ty)
+        }
+        E::Unit { .. }
+        | E::Value(_)
+        | E::InferredNum(_)
+        | E::Break
+        | E::Continue
+        | E::Spec(_, _)
+        | E::UnresolvedError => (),
+    }
+}
@@ -0,0 +1,143 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! The contents of the documents open in the editor, which may differ from the files on disk
+
+use crate::protocol::{Position, TextDocumentContentChangeEvent};
+
+#[derive(Debug, Clone)]
+pub struct Document {
+    pub version: i64,
+    pub text: String,
+}
+
+impl Document {
+    pub fn new(version: i64, text: String) -> Self {
+        Self { version, text }
+    }
+
+    /// Apply `changes` in order and move to `version`
+    pub fn apply_changes(&mut self, version: i64, changes: &[TextDocumentContentChangeEvent]) {
+        for change in changes {
+            match change.range {
+                None => self.text = change.text.clone(),
+                Some(range) => {
+                    let start = offset_at(&self.text, range.start);
+                    let end = offset_at(&self.text, range.end).max(start);
+                    self.text.replace_range(start..end, &change.text);
+                }
+            }
+        }
+        self.version = version;
+    }
+}
+
+/// The byte offset of `pos` in `text`. Positions past the end of a line are clamped to the end of
+/// that line, and positions past the last line to the end of the text.
+pub fn offset_at(text: &str, pos: Position) -> usize {
+    let mut line_start = 0;
+    for _ in 0..pos.line {
+        match text[line_start..].find('\n') {
+            Some(idx) => line_start += idx + 1,
+            None => return text.len(),
+        }
+    }
+    let line = match text[line_start..].find('\n') {
+        Some(idx) => &text[line_start..line_start + idx],
+        None => &text[line_start..],
+    };
+    let mut utf16_offset = 0;
+    for (idx, c) in line.char_indices() {
+        if utf16_offset >= pos.character {
+            return line_start + idx;
+        }
+        utf16_offset += c.len_utf16() as u64;
+    }
+    line_start + line.len()
+}
+
+/// The position of the byte offset `offset` in `text`
+pub fn position_at(text: &str, offset: usize) -> Position {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let prefix = &text[..offset];
+    let line_start = prefix.rfind('\n').map_or(0, |idx| idx + 1);
+    Position {
+        line: prefix.matches('\n').count() as u64,
+        character: prefix[line_start..].encode_utf16().count() as u64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::Range;
+
+    fn pos(line: u64, character: u64) -> Position {
+        Position { line, character }
+    }
+
+    fn change(start: Position, end: Position, text: &str) -> TextDocumentContentChangeEvent {
+        TextDocumentContentChangeEvent {
+            range: Some(Range { start, end }),
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn offsets_and_positions() {
+        let text = "module M {\n    fun f() {}\n}";
+        assert_eq!(offset_at(text, pos(0, 0)), 0);
+        assert_eq!(offset_at(text, pos(1, 4)), 15);
+        // past the end of the line
+        assert_eq!(offset_at(text, pos(0, 100)), 10);
+        // past the last line
+        assert_eq!(offset_at(text, pos(5, 0)), text.len());
+
+        assert_eq!(position_at(text, 15), pos(1, 4));
+        assert_eq!(position_at(text, text.len()), pos(2, 1));
+        for offset in 0..=text.len() {
+            assert_eq!(offset_at(text, position_at(text, offset)), offset);
+        }
+    }
+
+    #[test]
+    fn utf16_characters() {
+        // 'é' is two bytes in UTF-8 but a single UTF-16 code unit
+        let text = "// é\nx";
+        assert_eq!(offset_at(text, pos(0, 4)), 5);
+        assert_eq!(position_at(text, 5), pos(0, 4));
+    }
+
+    #[test]
+    fn incremental_changes() {
+        let mut doc = Document::new(1, "script {\n    fun main() {}\n}\n".to_string());
+        doc.apply_changes(
+            2,
+            &[
+                // insert a parameter
+                change(pos(1, 13), pos(1, 13), "x: u64"),
+                // rename the function, using the positions after the first change
+                change(pos(1, 8), pos(1, 12), "run"),
+            ],
+        );
+        assert_eq!(doc.version, 2);
+        assert_eq!(doc.text, "script {\n    fun run(x: u64) {}\n}\n");
+
+        // delete a line
+        doc.apply_changes(3, &[change(pos(1, 0), pos(2, 0), "")]);
+        assert_eq!(doc.text, "script {\n}\n");
+
+        // replace everything
+        doc.apply_changes(
+            4,
+            &[TextDocumentContentChangeEvent {
+                range: None,
+                text: "address 0x1 {}".to_string(),
+            }],
+        );
+        assert_eq!(doc.text, "address 0x1 {}");
+    }
+}
@@ -0,0 +1,12 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+#[macro_use(sp)]
+extern crate move_ir_types;
+
+pub mod analysis;
+pub mod document;
+pub mod protocol;
+pub mod server;
@@ -0,0 +1,13 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+use std::io;
+
+/// Language server for Move. The editor starts it and talks to it over stdin and stdout.
+fn main() -> anyhow::Result<()> {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    move_lsp::server::run(stdin.lock(), stdout.lock())
+}
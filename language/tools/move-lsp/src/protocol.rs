@@ -0,0 +1,220 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! The subset of the Language Server Protocol spoken by the Move language server.
+//!
+//! Messages are JSON-RPC objects, each preceded by a `Content-Length` header giving the length of
+//! the object in bytes and a blank line.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    io::{BufRead, Write},
+    path::{Path, PathBuf},
+};
+use url::Url;
+
+pub const INVALID_PARAMS: i64 = -32602;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const SERVER_NOT_INITIALIZED: i64 = -32002;
+
+pub const DIAGNOSTIC_SEVERITY_ERROR: u8 = 1;
+
+pub const COMPLETION_KIND_FUNCTION: u8 = 3;
+pub const COMPLETION_KIND_FIELD: u8 = 5;
+pub const COMPLETION_KIND_MODULE: u8 = 9;
+pub const COMPLETION_KIND_CONSTANT: u8 = 21;
+pub const COMPLETION_KIND_STRUCT: u8 = 22;
+
+/// Incremental text document synchronization: the editor only sends the ranges that changed
+pub const TEXT_DOCUMENT_SYNC_INCREMENTAL: u8 = 2;
+
+/// Read the next message, returning `None` once the input is closed
+pub fn read_message<R: BufRead>(reader: &mut R) -> Result<Option<Value>> {
+    let mut content_length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let mut parts = line.splitn(2, ':');
+        let name = parts.next().unwrap_or("").trim();
+        let value = parts.next().unwrap_or("").trim();
+        if name.eq_ignore_ascii_case("Content-Length") {
+            content_length = Some(
+                value
+                    .parse::<usize>()
+                    .map_err(|e| anyhow!("Invalid Content-Length '{}': {}", value, e))?,
+            );
+        }
+    }
+    let len = content_length.ok_or_else(|| anyhow!("Message without a Content-Length header"))?;
+    let mut content = vec![0; len];
+    reader.read_exact(&mut content)?;
+    Ok(Some(serde_json::from_slice(&content)?))
+}
+
+/// Write `msg`, preceded by its header
+pub fn write_message<W: Write>(writer: &mut W, msg: &Value) -> Result<()> {
+    let content = serde_json::to_string(msg)?;
+    write!(
+        writer,
+        "Content-Length: {}\r\n\r\n{}",
+        content.len(),
+        content
+    )?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// The path of the file `uri` refers to
+pub fn uri_to_path(uri: &str) -> Result<PathBuf> {
+    Url::parse(uri)
+        .ok()
+        .and_then(|url| url.to_file_path().ok())
+        .ok_or_else(|| anyhow!("Expected a file URI, got '{}'", uri))
+}
+
+/// The URI of the file at `path`, which must be absolute
+pub fn path_to_uri(path: &Path) -> Option<String> {
+    Url::from_file_path(path).ok().map(|url| url.to_string())
+}
+
+/// A zero-based line and UTF-16 code unit offset within that line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Position {
+    pub line: u64,
+    pub character: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Range {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Range {
+    pub fn contains(&self, pos: Position) -> bool {
+        self.start <= pos && pos <= self.end
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Location {
+    pub uri: String,
+    pub range: Range,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic {
+    pub range: Range,
+    pub severity: u8,
+    pub source: String,
+    pub message: String,
+    pub related_information: Vec<DiagnosticRelatedInformation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiagnosticRelatedInformation {
+    pub location: Location,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hover {
+    pub contents: MarkupContent,
+    pub range: Range,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MarkupContent {
+    pub kind: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompletionItem {
+    pub label: String,
+    pub kind: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+//**************************************************************************************************
+// Request and notification parameters
+//**************************************************************************************************
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InitializeParams {
+    pub root_uri: Option<String>,
+    pub initialization_options: Option<InitializationOptions>,
+}
+
+/// Settings for workspaces without a `Move.toml` manifest
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct InitializationOptions {
+    /// Files and directories holding the dependencies of the workspace
+    pub dependencies: Vec<String>,
+    /// Address of the modules declared outside of an address block
+    pub sender: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TextDocumentIdentifier {
+    pub uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TextDocumentItem {
+    pub uri: String,
+    pub version: i64,
+    pub text: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VersionedTextDocumentIdentifier {
+    pub uri: String,
+    pub version: i64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DidOpenTextDocumentParams {
+    pub text_document: TextDocumentItem,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DidChangeTextDocumentParams {
+    pub text_document: VersionedTextDocumentIdentifier,
+    pub content_changes: Vec<TextDocumentContentChangeEvent>,
+}
+
+/// A change to a document: `range` is replaced by `text`, or the whole document is if there is no
+/// range
+#[derive(Debug, Clone, Deserialize)]
+pub struct TextDocumentContentChangeEvent {
+    pub range: Option<Range>,
+    pub text: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextDocumentParams {
+    pub text_document: TextDocumentIdentifier,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextDocumentPositionParams {
+    pub text_document: TextDocumentIdentifier,
+    pub position: Position,
+}
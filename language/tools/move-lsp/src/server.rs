@@ -0,0 +1,325 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Dispatch of the messages sent by the editor.
+//!
+//! The sources are re-checked whenever a document is opened, changed or closed. Diagnostics are
+//! only published for the files whose diagnostics changed since the previous analysis.
+
+use crate::{
+    analysis::{analyze, ParsedFiles, Symbols},
+    document::Document,
+    protocol::*,
+};
+use move_cli::package::ResolvedPackage;
+use move_lang::{find_move_filenames, shared::Address, MOVE_EXTENSION};
+
+use anyhow::{anyhow, bail, Result};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    io::{BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+/// Serve the editor on the other end of `reader` and `writer` until it sends `exit`. Fails if the
+/// editor exits without requesting a shutdown first.
+pub fn run<R: BufRead, W: Write>(mut reader: R, writer: W) -> Result<()> {
+    let mut server = Server::new(writer);
+    while let Some(msg) = read_message(&mut reader)? {
+        if !server.handle_message(msg)? {
+            return Ok(());
+        }
+    }
+    bail!("Connection closed before exit")
+}
+
+type RequestError = (i64, String);
+
+struct Server<W> {
+    writer: W,
+    initialized: bool,
+    shutdown_requested: bool,
+    /// Files and directories checked as sources
+    targets: Vec<String>,
+    /// Files and directories the sources are checked against
+    deps: Vec<String>,
+    sender_opt: Option<Address>,
+    /// The documents open in the editor, by canonical path
+    documents: BTreeMap<PathBuf, Document>,
+    /// The files parsed by previous analyses
+    parsed_files: ParsedFiles,
+    /// The symbols of the last analysis in which every file parsed
+    symbols: Option<Symbols>,
+    /// The diagnostics last published for each file with errors
+    diagnostics: BTreeMap<PathBuf, Vec<Diagnostic>>,
+}
+
+impl<W: Write> Server<W> {
+    fn new(writer: W) -> Self {
+        Self {
+            writer,
+            initialized: false,
+            shutdown_requested: false,
+            targets: vec![],
+            deps: vec![],
+            sender_opt: None,
+            documents: BTreeMap::new(),
+            parsed_files: ParsedFiles::default(),
+            symbols: None,
+            diagnostics: BTreeMap::new(),
+        }
+    }
+
+    /// Handle `msg`, returning false once the editor asks the server to exit
+    fn handle_message(&mut self, msg: Value) -> Result<bool> {
+        // responses to requests from the server are ignored, as it does not send any
+        let method = match msg.get("method").and_then(Value::as_str) {
+            Some(method) => method.to_owned(),
+            None => return Ok(true),
+        };
+        let params = msg.get("params").cloned().unwrap_or(Value::Null);
+        match msg.get("id") {
+            Some(id) => {
+                let response = match self.handle_request(&method, params) {
+                    Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                    Err((code, message)) => json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": code, "message": message },
+                    }),
+                };
+                write_message(&mut self.writer, &response)?;
+            }
+            None if method == "exit" => {
+                if !self.shutdown_requested {
+                    bail!("Exit requested before shutdown")
+                }
+                return Ok(false);
+            }
+            None => {
+                if let Err(err) = self.handle_notification(&method, params) {
+                    eprintln!("Failed to handle '{}': {:#}", method, err)
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    fn handle_request(&mut self, method: &str, params: Value) -> Result<Value, RequestError> {
+        if !self.initialized && method != "initialize" {
+            return Err((
+                SERVER_NOT_INITIALIZED,
+                "The server has not been initialized".to_string(),
+            ));
+        }
+        match method {
+            "initialize" => {
+                self.initialize(parse_params(params)?);
+                Ok(json!({
+                    "capabilities": {
+                        "textDocumentSync": {
+                            "openClose": true,
+                            "change": TEXT_DOCUMENT_SYNC_INCREMENTAL,
+                            "save": { "includeText": false },
+                        },
+                        "hoverProvider": true,
+                        "definitionProvider": true,
+                        "completionProvider": { "triggerCharacters": [".", ":"] },
+                    },
+                    "serverInfo": { "name": "move-lsp", "version": env!("CARGO_PKG_VERSION") },
+                }))
+            }
+            "shutdown" => {
+                self.shutdown_requested = true;
+                Ok(Value::Null)
+            }
+            "textDocument/hover" => {
+                let (path, pos) = position_params(params)?;
+                let hover = self
+                    .symbols
+                    .as_ref()
+                    .and_then(|symbols| symbols.hover(&path, pos));
+                Ok(json!(hover))
+            }
+            "textDocument/definition" => {
+                let (path, pos) = position_params(params)?;
+                let location = self
+                    .symbols
+                    .as_ref()
+                    .and_then(|symbols| symbols.definition(&path, pos));
+                Ok(json!(location))
+            }
+            "textDocument/completion" => {
+                let (path, pos) = position_params(params)?;
+                let items = match (&self.symbols, self.documents.get(&path)) {
+                    (Some(symbols), Some(doc)) => symbols.completions(&path, &doc.text, pos),
+                    _ => vec![],
+                };
+                Ok(json!(items))
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("Unsupported method '{}'", method))),
+        }
+    }
+
+    fn handle_notification(&mut self, method: &str, params: Value) -> Result<()> {
+        if !self.initialized {
+            return Ok(());
+        }
+        match method {
+            "initialized" => self.analyze(),
+            "textDocument/didOpen" => {
+                let params: DidOpenTextDocumentParams = serde_json::from_value(params)?;
+                let doc = params.text_document;
+                let path = canonical_path(&doc.uri)?;
+                self.documents
+                    .insert(path, Document::new(doc.version, doc.text));
+                self.analyze()
+            }
+            "textDocument/didChange" => {
+                let params: DidChangeTextDocumentParams = serde_json::from_value(params)?;
+                let path = canonical_path(&params.text_document.uri)?;
+                let doc = self
+                    .documents
+                    .get_mut(&path)
+                    .ok_or_else(|| anyhow!("Document {:?} is not open", path))?;
+                doc.apply_changes(params.text_document.version, &params.content_changes);
+                self.analyze()
+            }
+            // open documents are checked from their text in the editor, which saving does not
+            // change
+            "textDocument/didSave" => Ok(()),
+            "textDocument/didClose" => {
+                let params: TextDocumentParams = serde_json::from_value(params)?;
+                self.documents
+                    .remove(&canonical_path(&params.text_document.uri)?);
+                self.analyze()
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Configure the sources to check. If the workspace root holds a package manifest, the
+    /// package is checked against its dependencies. Otherwise every Move file under the root is
+    /// checked against the dependencies given in the initialization options.
+    fn initialize(&mut self, params: InitializeParams) {
+        self.initialized = true;
+        let options = params.initialization_options.unwrap_or_default();
+        if let Some(sender) = &options.sender {
+            match Address::parse_str(sender) {
+                Ok(addr) => self.sender_opt = Some(addr),
+                Err(err) => eprintln!("Invalid sender '{}': {}", sender, err),
+            }
+        }
+        let root = match params.root_uri.as_deref().map(canonical_path) {
+            Some(Ok(root)) => root,
+            Some(Err(err)) => {
+                eprintln!("Invalid workspace root: {:#}", err);
+                self.deps = options.dependencies;
+                return;
+            }
+            None => {
+                self.deps = options.dependencies;
+                return;
+            }
+        };
+        match ResolvedPackage::resolve_if_present(&root) {
+            Ok(Some(package)) => {
+                self.targets = package.root_sources();
+                self.deps = package.dependency_sources();
+                return;
+            }
+            Ok(None) => (),
+            Err(err) => eprintln!("Unable to resolve the workspace package: {:#}", err),
+        }
+        self.targets = vec![root.to_string_lossy().into_owned()];
+        self.deps = options
+            .dependencies
+            .iter()
+            .map(|dep| root.join(dep).to_string_lossy().into_owned())
+            .collect();
+    }
+
+    /// Check the sources, along with any open Move document outside of them
+    fn analyze(&mut self) -> Result<()> {
+        let mut known_files = BTreeSet::new();
+        known_files.extend(find_move_filenames(&self.targets, true)?);
+        known_files.extend(find_move_filenames(&self.deps, true)?);
+        let mut targets = self.targets.clone();
+        let mut overlay = HashMap::new();
+        for (path, doc) in &self.documents {
+            let fname = path.to_string_lossy().into_owned();
+            let is_move_file = path.extension().map_or(false, |ext| ext == MOVE_EXTENSION);
+            if is_move_file && !known_files.contains(&fname) {
+                targets.push(fname.clone())
+            }
+            overlay.insert(fname, doc.text.clone());
+        }
+
+        let analysis = analyze(
+            &targets,
+            &self.deps,
+            self.sender_opt,
+            &overlay,
+            &mut self.parsed_files,
+        )?;
+        if analysis.symbols.is_some() {
+            self.symbols = analysis.symbols;
+        }
+        for path in self.diagnostics.keys() {
+            if !analysis.diagnostics.contains_key(path) {
+                publish_diagnostics(&mut self.writer, &self.documents, path, vec![])?;
+            }
+        }
+        for (path, diagnostics) in &analysis.diagnostics {
+            if self.diagnostics.get(path) != Some(diagnostics) {
+                publish_diagnostics(&mut self.writer, &self.documents, path, diagnostics.clone())?;
+            }
+        }
+        self.diagnostics = analysis.diagnostics;
+        Ok(())
+    }
+}
+
+fn publish_diagnostics<W: Write>(
+    writer: &mut W,
+    documents: &BTreeMap<PathBuf, Document>,
+    path: &Path,
+    diagnostics: Vec<Diagnostic>,
+) -> Result<()> {
+    let uri = match path_to_uri(path) {
+        Some(uri) => uri,
+        None => return Ok(()),
+    };
+    let mut params = json!({ "uri": uri, "diagnostics": diagnostics });
+    if let Some(doc) = documents.get(path) {
+        params["version"] = json!(doc.version);
+    }
+    write_message(
+        writer,
+        &json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": params,
+        }),
+    )
+}
+
+/// The canonical path of the file `uri` refers to, so that documents and the files found by the
+/// compiler agree on paths. Files that do not exist on disk keep their path as is.
+fn canonical_path(uri: &str) -> Result<PathBuf> {
+    let path = uri_to_path(uri)?;
+    Ok(path.canonicalize().unwrap_or(path))
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, RequestError> {
+    serde_json::from_value(params).map_err(|err| (INVALID_PARAMS, err.to_string()))
+}
+
+fn position_params(params: Value) -> Result<(PathBuf, Position), RequestError> {
+    let params: TextDocumentPositionParams = parse_params(params)?;
+    let path = canonical_path(&params.text_document.uri)
+        .map_err(|err| (INVALID_PARAMS, err.to_string()))?;
+    Ok((path, params.position))
+}
@@ -0,0 +1,223 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use move_lsp::protocol::{path_to_uri, read_message, write_message};
+use serde_json::{json, Value};
+use std::{fs, io::Cursor};
+
+const MODULE: &str = "address 0x2 {
+module M {
+    struct S { value: u64 }
+
+    public fun make(value: u64): S {
+        S { value: value }
+    }
+
+    public fun get(s: &S): u64 {
+        s.value
+    }
+}
+}
+";
+
+fn request(id: u64, method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+}
+
+fn notification(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+fn position_params(uri: &str, line: u64, character: u64) -> Value {
+    json!({
+        "textDocument": { "uri": uri },
+        "position": { "line": line, "character": character },
+    })
+}
+
+fn range(start: (u64, u64), end: (u64, u64)) -> Value {
+    json!({
+        "start": { "line": start.0, "character": start.1 },
+        "end": { "line": end.0, "character": end.1 },
+    })
+}
+
+/// Run the server on `msgs`, returning the responses by id and the notifications in order
+fn run_server(msgs: &[Value]) -> (Vec<(u64, Value)>, Vec<Value>) {
+    let mut input = vec![];
+    for msg in msgs {
+        write_message(&mut input, msg).unwrap();
+    }
+    let mut output = vec![];
+    move_lsp::server::run(Cursor::new(input), &mut output).unwrap();
+
+    let mut reader = Cursor::new(output);
+    let mut responses = vec![];
+    let mut notifications = vec![];
+    while let Some(msg) = read_message(&mut reader).unwrap() {
+        match msg.get("id").and_then(Value::as_u64) {
+            Some(id) => responses.push((id, msg)),
+            None => notifications.push(msg),
+        }
+    }
+    (responses, notifications)
+}
+
+#[test]
+fn hover_definition_completion_and_diagnostics() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    let file = root.join("M.move");
+    fs::write(&file, MODULE).unwrap();
+    let root_uri = path_to_uri(&root).unwrap();
+    let uri = path_to_uri(&file).unwrap();
+
+    let (responses, notifications) = run_server(&[
+        request(1, "initialize", json!({ "rootUri": root_uri })),
+        notification("initialized", json!({})),
+        notification(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": { "uri": uri, "languageId": "move", "version": 1, "text": MODULE },
+            }),
+        ),
+        // the type of the parameter `s` in `s.value`
+        request(2, "textDocument/hover", position_params(&uri, 9, 8)),
+        // the field `value` in `s.value`
+        request(3, "textDocument/definition", position_params(&uri, 9, 11)),
+        // the struct name in `S { value: value }`
+        request(4, "textDocument/definition", position_params(&uri, 5, 8)),
+        // delete `value` from `s.value`, which no longer parses
+        notification(
+            "textDocument/didChange",
+            json!({
+                "textDocument": { "uri": uri, "version": 2 },
+                "contentChanges": [{ "range": range((9, 10), (9, 15)), "text": "" }],
+            }),
+        ),
+        request(5, "textDocument/completion", position_params(&uri, 9, 10)),
+        // restore it
+        notification(
+            "textDocument/didChange",
+            json!({
+                "textDocument": { "uri": uri, "version": 3 },
+                "contentChanges": [{ "range": range((9, 10), (9, 10)), "text": "value" }],
+            }),
+        ),
+        request(6, "shutdown", Value::Null),
+        notification("exit", Value::Null),
+    ]);
+
+    let ids: Vec<_> = responses.iter().map(|(id, _)| *id).collect();
+    assert_eq!(ids, vec![1, 2, 3, 4, 5, 6]);
+    let result = |id: usize| &responses[id - 1].1["result"];
+
+    assert_eq!(result(1)["capabilities"]["hoverProvider"], json!(true));
+
+    let hover = result(2)["contents"]["value"].as_str().unwrap();
+    assert!(hover.contains("s: &"), "{}", hover);
+    assert!(hover.contains("M::S"), "{}", hover);
+
+    assert_eq!(result(3)["uri"], json!(uri));
+    assert_eq!(result(3)["range"], range((2, 15), (2, 20)));
+    assert_eq!(result(4)["uri"], json!(uri));
+    assert_eq!(result(4)["range"], range((2, 11), (2, 12)));
+
+    let labels: Vec<_> = result(5)
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["label"].as_str().unwrap())
+        .collect();
+    assert_eq!(labels, vec!["value"]);
+
+    assert_eq!(result(6), &Value::Null);
+
+    // the parse error is reported for the second version, and cleared for the third
+    let diagnostics: Vec<_> = notifications
+        .iter()
+        .filter(|msg| msg["method"] == json!("textDocument/publishDiagnostics"))
+        .map(|msg| &msg["params"])
+        .collect();
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0]["uri"], json!(uri));
+    assert_eq!(diagnostics[0]["version"], json!(2));
+    assert!(!diagnostics[0]["diagnostics"].as_array().unwrap().is_empty());
+    assert_eq!(diagnostics[1]["version"], json!(3));
+    assert!(diagnostics[1]["diagnostics"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn diagnostics_are_only_published_when_they_change() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    let file = root.join("M.move");
+    fs::write(&file, MODULE).unwrap();
+    let root_uri = path_to_uri(&root).unwrap();
+    let uri = path_to_uri(&file).unwrap();
+    // `s.value` without `value`, which does not parse
+    let broken = MODULE.replace("s.value", "s.");
+
+    let (_, notifications) = run_server(&[
+        request(1, "initialize", json!({ "rootUri": root_uri })),
+        notification("initialized", json!({})),
+        notification(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": { "uri": uri, "languageId": "move", "version": 1, "text": broken },
+            }),
+        ),
+        // append a line after the error, which leaves the diagnostics as they are
+        notification(
+            "textDocument/didChange",
+            json!({
+                "textDocument": { "uri": uri, "version": 2 },
+                "contentChanges": [{ "range": range((13, 0), (13, 0)), "text": "\n" }],
+            }),
+        ),
+        notification(
+            "textDocument/didSave",
+            json!({ "textDocument": { "uri": uri } }),
+        ),
+        // fix the error
+        notification(
+            "textDocument/didChange",
+            json!({
+                "textDocument": { "uri": uri, "version": 3 },
+                "contentChanges": [{ "range": range((9, 10), (9, 10)), "text": "value" }],
+            }),
+        ),
+        request(2, "shutdown", Value::Null),
+        notification("exit", Value::Null),
+    ]);
+
+    let versions: Vec<_> = notifications
+        .iter()
+        .filter(|msg| msg["method"] == json!("textDocument/publishDiagnostics"))
+        .map(|msg| {
+            let params = &msg["params"];
+            let num_diagnostics = params["diagnostics"].as_array().unwrap().len();
+            (params["version"].clone(), num_diagnostics > 0)
+        })
+        .collect();
+    assert_eq!(versions, vec![(json!(1), true), (json!(3), false)]);
+}
+
+#[test]
+fn requests_before_initialize_are_rejected() {
+    let (responses, _) = run_server(&[
+        request(
+            1,
+            "textDocument/hover",
+            position_params("file:///a.move", 0, 0),
+        ),
+        request(2, "initialize", json!({ "rootUri": null })),
+        request(3, "unknown/method", Value::Null),
+        request(4, "shutdown", Value::Null),
+        notification("exit", Value::Null),
+    ]);
+    assert_eq!(responses[0].1["error"]["code"], json!(-32002));
+    assert!(responses[1].1.get("result").is_some());
+    assert_eq!(responses[2].1["error"]["code"], json!(-32601));
+    assert_eq!(responses[3].1["result"], Value::Null);
+}
@@ -140,6 +140,10 @@ pub trait ModuleAccess: Sync {
         &self.as_module().as_inner().field_handles
     }
 
+    fn friend_decls(&self) -> &[ModuleHandle] {
+        &self.as_module().as_inner().friend_decls
+    }
+
     fn struct_instantiations(&self) -> &[StructDefInstantiation] {
         &self.as_module().as_inner().struct_def_instantiations
     }
@@ -46,6 +46,9 @@ impl<'a> BoundsChecker<'a> {
         for module_handle in &bounds_check.module.module_handles {
             bounds_check.check_module_handle(module_handle)?
         }
+        for friend_decl in &bounds_check.module.friend_decls {
+            bounds_check.check_module_handle(friend_decl)?
+        }
         for struct_handle in &bounds_check.module.struct_handles {
             bounds_check.check_struct_handle(struct_handle)?
         }
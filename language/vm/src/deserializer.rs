@@ -35,6 +35,11 @@ impl CompiledModule {
     }
 }
 
+/// Reads the version of the binary format from the header of a serialized module or script.
+pub fn binary_format_version(binary: &[u8]) -> BinaryLoaderResult<u32> {
+    check_binary(&mut Cursor::new(binary))
+}
+
 impl CompiledModuleMut {
     // exposed as a public function to enable testing the deserializer
     pub fn deserialize_no_check_bounds(binary: &[u8]) -> BinaryLoaderResult<Self> {
@@ -253,10 +258,10 @@ fn load_local_index(cursor: &mut Cursor<&[u8]>) -> BinaryLoaderResult<u8> {
 fn deserialize_compiled_script(binary: &[u8]) -> BinaryLoaderResult<CompiledScriptMut> {
     let binary_len = binary.len();
     let mut cursor = Cursor::new(binary);
-    check_binary(&mut cursor)?;
+    let version = check_binary(&mut cursor)?;
     let table_count = load_table_count(&mut cursor)?;
     let mut tables: Vec<Table> = Vec::new();
-    read_tables(&mut cursor, table_count, &mut tables)?;
+    read_tables(&mut cursor, version, table_count, &mut tables)?;
     let content_len = check_tables(&mut tables, binary_len)?;
 
     let table_contents = read_table_contents(&mut cursor, content_len as usize)?;
@@ -275,10 +280,10 @@ fn deserialize_compiled_script(binary: &[u8]) -> BinaryLoaderResult<CompiledScri
 fn deserialize_compiled_module(binary: &[u8]) -> BinaryLoaderResult<CompiledModuleMut> {
     let binary_len = binary.len();
     let mut cursor = Cursor::new(binary);
-    check_binary(&mut cursor)?;
+    let version = check_binary(&mut cursor)?;
     let table_count = load_table_count(&mut cursor)?;
     let mut tables: Vec<Table> = Vec::new();
-    read_tables(&mut cursor, table_count, &mut tables)?;
+    read_tables(&mut cursor, version, table_count, &mut tables)?;
     let content_len = check_tables(&mut tables, binary_len)?;
 
     let table_contents = read_table_contents(&mut cursor, content_len as usize)?;
@@ -286,15 +291,15 @@ fn deserialize_compiled_module(binary: &[u8]) -> BinaryLoaderResult<CompiledModu
     let mut module = CompiledModuleMut::default();
     module.self_module_handle_idx = load_module_handle_index(&mut cursor)?;
 
-    build_compiled_module(&mut module, &table_contents, &tables)?;
+    build_compiled_module(&mut module, &table_contents, &tables, version)?;
 
     Ok(module)
 }
 
 /// Verifies the correctness of the "static" part of the binary's header.
 ///
-/// Returns the offset where the count of tables in the binary.
-fn check_binary(cursor: &mut Cursor<&[u8]>) -> BinaryLoaderResult<()> {
+/// Returns the version of the binary format.
+fn check_binary(cursor: &mut Cursor<&[u8]>) -> BinaryLoaderResult<u32> {
     let mut magic = [0u8; BinaryConstants::LIBRA_MAGIC_SIZE];
     if let Ok(count) = cursor.read(&mut magic) {
         if count != BinaryConstants::LIBRA_MAGIC_SIZE || magic != BinaryConstants::LIBRA_MAGIC {
@@ -304,16 +309,12 @@ fn check_binary(cursor: &mut Cursor<&[u8]>) -> BinaryLoaderResult<()> {
         return Err(PartialVMError::new(StatusCode::MALFORMED)
             .with_message("Bad binary header".to_string()));
     }
-    let major_ver = 1u32;
-    if let Ok(ver) = read_u32(cursor) {
-        if ver != major_ver {
-            return Err(PartialVMError::new(StatusCode::UNKNOWN_VERSION));
-        }
-    } else {
-        return Err(PartialVMError::new(StatusCode::MALFORMED)
-            .with_message("Bad binary header".to_string()));
+    match read_u32(cursor) {
+        Ok(ver) if (VERSION_1..=VERSION_MAX).contains(&ver) => Ok(ver),
+        Ok(_) => Err(PartialVMError::new(StatusCode::UNKNOWN_VERSION)),
+        Err(_) => Err(PartialVMError::new(StatusCode::MALFORMED)
+            .with_message("Bad binary header".to_string())),
     }
-    Ok(())
 }
 
 /// Reads all the table headers.
@@ -321,18 +322,20 @@ fn check_binary(cursor: &mut Cursor<&[u8]>) -> BinaryLoaderResult<()> {
 /// Return a Vec<Table> that contains all the table headers defined and checked.
 fn read_tables(
     cursor: &mut Cursor<&[u8]>,
+    version: u32,
     table_count: u8,
     tables: &mut Vec<Table>,
 ) -> BinaryLoaderResult<()> {
     for _count in 0..table_count {
-        tables.push(read_table(cursor)?);
+        tables.push(read_table(cursor, version)?);
     }
     Ok(())
 }
 
 /// Reads a table from a slice at a given offset.
-/// If a table is not recognized an error is returned.
-fn read_table(cursor: &mut Cursor<&[u8]>) -> BinaryLoaderResult<Table> {
+/// If a table is not recognized, or is not supported by the version of the binary, an error is
+/// returned.
+fn read_table(cursor: &mut Cursor<&[u8]>, version: u32) -> BinaryLoaderResult<Table> {
     let kind = match read_u8(cursor) {
         Ok(kind) => kind,
         Err(_) => {
@@ -340,9 +343,13 @@ fn read_table(cursor: &mut Cursor<&[u8]>) -> BinaryLoaderResult<Table> {
                 .with_message("Error reading table".to_string()))
         }
     };
+    let kind = TableType::from_u8(kind)?;
+    if kind == TableType::FRIEND_DECLS && version < VERSION_2 {
+        return Err(PartialVMError::new(StatusCode::UNKNOWN_TABLE_TYPE));
+    }
     let table_offset = load_table_offset(cursor)?;
     let count = load_table_size(cursor)?;
-    Ok(Table::new(TableType::from_u8(kind)?, table_offset, count))
+    Ok(Table::new(kind, table_offset, count))
 }
 
 fn read_table_contents(cursor: &mut Cursor<&[u8]>, n: usize) -> BinaryLoaderResult<Vec<u8>> {
@@ -489,9 +496,10 @@ fn build_compiled_module(
     module: &mut CompiledModuleMut,
     binary: &[u8],
     tables: &[Table],
+    version: u32,
 ) -> BinaryLoaderResult<()> {
     build_common_tables(binary, tables, module)?;
-    build_module_tables(binary, tables, module)?;
+    build_module_tables(binary, tables, module, version)?;
     Ok(())
 }
 
@@ -531,7 +539,8 @@ fn build_common_tables(
             | TableType::STRUCT_DEFS
             | TableType::STRUCT_DEF_INST
             | TableType::FIELD_HANDLE
-            | TableType::FIELD_INST => continue,
+            | TableType::FIELD_INST
+            | TableType::FRIEND_DECLS => continue,
         }
     }
     Ok(())
@@ -542,6 +551,7 @@ fn build_module_tables(
     binary: &[u8],
     tables: &[Table],
     module: &mut CompiledModuleMut,
+    version: u32,
 ) -> BinaryLoaderResult<()> {
     for table in tables {
         match table.kind {
@@ -552,7 +562,7 @@ fn build_module_tables(
                 load_struct_instantiations(binary, table, &mut module.struct_def_instantiations)?;
             }
             TableType::FUNCTION_DEFS => {
-                load_function_defs(binary, table, &mut module.function_defs)?;
+                load_function_defs(binary, table, &mut module.function_defs, version)?;
             }
             TableType::FIELD_HANDLE => {
                 load_field_handles(binary, table, &mut module.field_handles)?;
@@ -560,6 +570,9 @@ fn build_module_tables(
             TableType::FIELD_INST => {
                 load_field_instantiations(binary, table, &mut module.field_instantiations)?;
             }
+            TableType::FRIEND_DECLS => {
+                load_module_handles(binary, table, &mut module.friend_decls)?;
+            }
             TableType::MODULE_HANDLES
             | TableType::STRUCT_HANDLES
             | TableType::FUNCTION_HANDLES
@@ -597,7 +610,8 @@ fn build_script_tables(
             | TableType::STRUCT_DEF_INST
             | TableType::FUNCTION_DEFS
             | TableType::FIELD_INST
-            | TableType::FIELD_HANDLE => {
+            | TableType::FIELD_HANDLE
+            | TableType::FRIEND_DECLS => {
                 return Err(PartialVMError::new(StatusCode::MALFORMED)
                     .with_message("Bad table in Script".to_string()));
             }
@@ -1051,12 +1065,13 @@ fn load_function_defs(
     binary: &[u8],
     table: &Table,
     func_defs: &mut Vec<FunctionDefinition>,
+    version: u32,
 ) -> BinaryLoaderResult<()> {
     let start = table.offset as usize;
     let end = start + table.count as usize;
     let mut cursor = Cursor::new(&binary[start..end]);
     while cursor.position() < u64::from(table.count) {
-        let func_def = load_function_def(&mut cursor)?;
+        let func_def = load_function_def(&mut cursor, version)?;
         func_defs.push(func_def);
     }
     Ok(())
@@ -1107,13 +1122,27 @@ fn load_field_instantiations(
 }
 
 /// Deserializes a `FunctionDefinition`.
-fn load_function_def(cursor: &mut Cursor<&[u8]>) -> BinaryLoaderResult<FunctionDefinition> {
+fn load_function_def(
+    cursor: &mut Cursor<&[u8]>,
+    version: u32,
+) -> BinaryLoaderResult<FunctionDefinition> {
     let function = load_function_handle_index(cursor)?;
 
     let flags = read_u8(cursor).map_err(|_| {
         PartialVMError::new(StatusCode::MALFORMED).with_message("Unexpected EOF".to_string())
     })?;
     let is_public = (flags & FunctionDefinition::PUBLIC) != 0;
+    // the friend flag is ignored in version 1, as all unknown flags were
+    let is_friend = version >= VERSION_2 && (flags & FunctionDefinition::FRIEND) != 0;
+    let visibility = match (is_public, is_friend) {
+        (false, false) => Visibility::Private,
+        (true, false) => Visibility::Public,
+        (false, true) => Visibility::Friend,
+        (true, true) => {
+            return Err(PartialVMError::new(StatusCode::MALFORMED)
+                .with_message("Function is both public and friend".to_string()))
+        }
+    };
     let acquires_global_resources = load_struct_definition_indices(cursor)?;
     let code_unit = if (flags & FunctionDefinition::NATIVE) != 0 {
         None
@@ -1122,7 +1151,7 @@ fn load_function_def(cursor: &mut Cursor<&[u8]>) -> BinaryLoaderResult<FunctionD
     };
     Ok(FunctionDefinition {
         function,
-        is_public,
+        visibility,
         acquires_global_resources,
         code: code_unit,
     })
@@ -1271,6 +1300,7 @@ impl TableType {
             0xC => Ok(TableType::FUNCTION_DEFS),
             0xD => Ok(TableType::FIELD_HANDLE),
             0xE => Ok(TableType::FIELD_INST),
+            0xF => Ok(TableType::FRIEND_DECLS),
             _ => Err(PartialVMError::new(StatusCode::UNKNOWN_TABLE_TYPE)),
         }
     }
@@ -374,6 +374,25 @@ pub struct FieldDefinition {
     pub signature: TypeSignature,
 }
 
+/// `Visibility` restricts the modules a function can be called from.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+#[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
+#[cfg_attr(any(test, feature = "fuzzing"), proptest(no_params))]
+pub enum Visibility {
+    /// Callable from the declaring module only.
+    Private,
+    /// Callable from any module and from scripts.
+    Public,
+    /// Callable from the declaring module and from the modules in its friend list.
+    Friend,
+}
+
+impl Default for Visibility {
+    fn default() -> Self {
+        Visibility::Private
+    }
+}
+
 /// A `FunctionDefinition` is the implementation of a function. It defines
 /// the *prototype* of the function and the function body.
 
@@ -383,8 +402,8 @@ pub struct FieldDefinition {
 pub struct FunctionDefinition {
     /// The prototype of the function (module, name, signature).
     pub function: FunctionHandleIndex,
-    /// Flag to indicate if this function is public.
-    pub is_public: bool,
+    /// The modules the function can be called from.
+    pub visibility: Visibility,
     /// List of nominal resources (declared in this module) that the procedure might access
     /// Either through: BorrowGlobal, MoveFrom, or transitively through another procedure
     /// This list of acquires grants the borrow checker the ability to statically verify the safety
@@ -406,7 +425,11 @@ pub struct FunctionDefinition {
 impl FunctionDefinition {
     /// Returns whether the FunctionDefinition is public.
     pub fn is_public(&self) -> bool {
-        self.is_public
+        self.visibility == Visibility::Public
+    }
+    /// Returns whether the FunctionDefinition can be called from friend modules only.
+    pub fn is_friend(&self) -> bool {
+        self.visibility == Visibility::Friend
     }
     /// Returns whether the FunctionDefinition is native.
     pub fn is_native(&self) -> bool {
@@ -417,6 +440,8 @@ impl FunctionDefinition {
     pub const PUBLIC: u8 = 0x1;
     /// A native function implemented in Rust.
     pub const NATIVE: u8 = 0x2;
+    /// Function can be invoked from the friend modules of its declaring module.
+    pub const FRIEND: u8 = 0x4;
 }
 
 // Signature
@@ -1532,7 +1557,7 @@ impl CompiledScriptMut {
         // Create a function definition for the main function.
         let main_def = FunctionDefinition {
             function: main_handle_idx,
-            is_public: true,
+            visibility: Visibility::Public,
             acquires_global_resources: vec![],
             code: Some(self.code),
         };
@@ -1550,6 +1575,7 @@ impl CompiledScriptMut {
             struct_handles: self.struct_handles,
             function_handles: self.function_handles,
             field_handles: vec![],
+            friend_decls: vec![],
 
             struct_def_instantiations: vec![],
             function_instantiations: self.function_instantiations,
@@ -1592,6 +1618,8 @@ pub struct CompiledModuleMut {
     pub function_handles: Vec<FunctionHandle>,
     /// Handles to fields.
     pub field_handles: Vec<FieldHandle>,
+    /// Modules allowed to call the friend functions of this module.
+    pub friend_decls: Vec<ModuleHandle>,
 
     /// Struct instantiations.
     pub struct_def_instantiations: Vec<StructDefInstantiation>,
@@ -1709,6 +1737,7 @@ impl Arbitrary for CompiledModuleMut {
                         function_handles,
                         self_module_handle_idx,
                         field_handles: vec![],
+                        friend_decls: vec![],
                         struct_def_instantiations: vec![],
                         function_instantiations: vec![],
                         field_instantiations: vec![],
@@ -1859,6 +1888,7 @@ pub fn empty_module() -> CompiledModuleMut {
         struct_handles: vec![],
         function_handles: vec![],
         field_handles: vec![],
+        friend_decls: vec![],
         struct_def_instantiations: vec![],
         function_instantiations: vec![],
         field_instantiations: vec![],
@@ -1888,7 +1918,7 @@ pub fn basic_test_module() -> CompiledModuleMut {
 
     m.function_defs.push(FunctionDefinition {
         function: FunctionHandleIndex(0),
-        is_public: false,
+        visibility: Visibility::Private,
         acquires_global_resources: vec![],
         code: Some(CodeUnit {
             locals: SignatureIndex(0),
@@ -33,6 +33,18 @@ impl BinaryConstants {
     pub const TABLE_HEADER_SIZE: u8 = size_of::<u32>() as u8 * 2 + 1;
 }
 
+/// Version 1: the initial version of the binary format.
+pub const VERSION_1: u32 = 1;
+
+/// Version 2: changes compared with version 1
+///  + friend declarations (`FRIEND_DECLS` table)
+///  + functions callable from friend modules only (`FunctionDefinition::FRIEND` flag)
+pub const VERSION_2: u32 = 2;
+
+/// The latest version of the binary format. Binaries are serialized with the lowest version
+/// supporting the features they use, so that they can be loaded by older VMs when possible.
+pub const VERSION_MAX: u32 = VERSION_2;
+
 pub const TABLE_COUNT_MAX: u64 = 255;
 
 pub const TABLE_OFFSET_MAX: u64 = 0xffff_ffff;
@@ -96,6 +108,7 @@ pub enum TableType {
     FUNCTION_DEFS           = 0xC,
     FIELD_HANDLE            = 0xD,
     FIELD_INST              = 0xE,
+    FRIEND_DECLS            = 0xF,
 }
 
 /// Constants for signature blob values.
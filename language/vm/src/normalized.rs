@@ -94,7 +94,7 @@ impl Module {
             .function_defs()
             .iter()
             .filter_map(|f| {
-                if f.is_public {
+                if f.is_public() {
                     Some(FunctionSignature::new(m, m.function_handle_at(f.function)))
                 } else {
                     None
@@ -118,19 +118,24 @@ impl Type {
             Struct(shi) => {
                 let handle = m.struct_handle_at(*shi);
                 assert!(handle.type_parameters.is_empty(), "A struct with N type parameters should be encoded as StructModuleInstantiation with type_arguments = [TypeParameter(1), ..., TypeParameter(N)]");
+                let module_handle = m.module_handle_at(handle.module);
                 Type::Struct {
-                    address: *m.address(),
-                    module: m.name().to_owned(),
+                    address: *m.address_identifier_at(module_handle.address),
+                    module: m.identifier_at(module_handle.name).to_owned(),
                     name: m.identifier_at(handle.name).to_owned(),
                     type_arguments: Vec::new(),
                 }
             }
-            StructInstantiation(shi, type_actuals) => Type::Struct {
-                address: *m.address(),
-                module: m.name().to_owned(),
-                name: m.identifier_at(m.struct_handle_at(*shi).name).to_owned(),
-                type_arguments: type_actuals.iter().map(|t| Type::new(m, t)).collect(),
-            },
+            StructInstantiation(shi, type_actuals) => {
+                let handle = m.struct_handle_at(*shi);
+                let module_handle = m.module_handle_at(handle.module);
+                Type::Struct {
+                    address: *m.address_identifier_at(module_handle.address),
+                    module: m.identifier_at(module_handle.name).to_owned(),
+                    name: m.identifier_at(handle.name).to_owned(),
+                    type_arguments: type_actuals.iter().map(|t| Type::new(m, t)).collect(),
+                }
+            }
             Bool => Type::Bool,
             U8 => Type::U8,
             U64 => Type::U64,
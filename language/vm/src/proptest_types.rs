@@ -296,6 +296,7 @@ impl CompiledModuleStrategyGen {
                         struct_handles,
                         function_handles,
                         field_handles,
+                        friend_decls: vec![],
 
                         struct_def_instantiations,
                         function_instantiations,
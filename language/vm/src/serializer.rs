@@ -158,7 +158,7 @@ impl CompiledScriptMut {
     /// [`CompiledScript::serialize`].
     pub fn serialize(&self, binary: &mut Vec<u8>) -> Result<()> {
         let mut binary_data = BinaryData::from(binary.clone());
-        let mut ser = ScriptSerializer::new(1);
+        let mut ser = ScriptSerializer::new(VERSION_1);
         let mut temp = BinaryData::new();
 
         ser.common.serialize_common_tables(&mut temp, self)?;
@@ -196,7 +196,7 @@ impl CompiledModuleMut {
     /// [`CompiledModule::serialize`].
     pub fn serialize(&self, binary: &mut Vec<u8>) -> Result<()> {
         let mut binary_data = BinaryData::from(binary.clone());
-        let mut ser = ModuleSerializer::new(1);
+        let mut ser = ModuleSerializer::new(self.binary_version());
         let mut temp = BinaryData::new();
         ser.serialize_tables(&mut temp, self)?;
         if temp.len() > u32::max_value() as usize {
@@ -216,6 +216,20 @@ impl CompiledModuleMut {
         *binary = binary_data.into_inner();
         Ok(())
     }
+
+    /// The lowest version of the binary format supporting the features used by this module.
+    fn binary_version(&self) -> u32 {
+        let uses_friends = !self.friend_decls.is_empty()
+            || self
+                .function_defs
+                .iter()
+                .any(|function_def| function_def.is_friend());
+        if uses_friends {
+            VERSION_2
+        } else {
+            VERSION_1
+        }
+    }
 }
 
 /// Holds data to compute the header of a generic binary.
@@ -248,6 +262,7 @@ struct ModuleSerializer {
     function_defs: (u32, u32),
     field_handles: (u32, u32),
     field_instantiations: (u32, u32),
+    friend_decls: (u32, u32),
 }
 
 /// Holds data to compute the header of a transaction script binary.
@@ -529,17 +544,17 @@ fn serialize_function_definition(
 ) -> Result<()> {
     serialize_function_handle_index(binary, &function_definition.function)?;
 
-    let is_public = if function_definition.is_public() {
-        FunctionDefinition::PUBLIC
-    } else {
-        0
+    let visibility = match function_definition.visibility {
+        Visibility::Private => 0,
+        Visibility::Public => FunctionDefinition::PUBLIC,
+        Visibility::Friend => FunctionDefinition::FRIEND,
     };
     let is_native = if function_definition.is_native() {
         FunctionDefinition::NATIVE
     } else {
         0
     };
-    binary.push(is_public | is_native)?;
+    binary.push(visibility | is_native)?;
 
     serialize_acquires(binary, &function_definition.acquires_global_resources)?;
     if let Some(code) = &function_definition.code {
@@ -1123,6 +1138,7 @@ impl ModuleSerializer {
             function_defs: (0, 0),
             field_handles: (0, 0),
             field_instantiations: (0, 0),
+            friend_decls: (0, 0),
         }
     }
 
@@ -1136,7 +1152,8 @@ impl ModuleSerializer {
         self.serialize_struct_def_instantiations(binary, &module.struct_def_instantiations)?;
         self.serialize_function_definitions(binary, &module.function_defs)?;
         self.serialize_field_handles(binary, &module.field_handles)?;
-        self.serialize_field_instantiations(binary, &module.field_instantiations)
+        self.serialize_field_instantiations(binary, &module.field_instantiations)?;
+        self.serialize_friend_decls(binary, &module.friend_decls)
     }
 
     fn serialize_table_indices(&mut self, binary: &mut BinaryData) -> Result<()> {
@@ -1171,6 +1188,12 @@ impl ModuleSerializer {
             self.field_instantiations.0,
             self.field_instantiations.1,
         )?;
+        serialize_table_index(
+            binary,
+            TableType::FRIEND_DECLS,
+            self.friend_decls.0,
+            self.friend_decls.1,
+        )?;
         Ok(())
     }
 
@@ -1258,6 +1281,23 @@ impl ModuleSerializer {
         }
         Ok(())
     }
+
+    /// Serializes the friend declarations, as a table of `ModuleHandle`.
+    fn serialize_friend_decls(
+        &mut self,
+        binary: &mut BinaryData,
+        friend_decls: &[ModuleHandle],
+    ) -> Result<()> {
+        if !friend_decls.is_empty() {
+            self.common.table_count += 1;
+            self.friend_decls.0 = check_index_in_binary(binary.len())?;
+            for friend_decl in friend_decls {
+                serialize_module_handle(binary, friend_decl)?;
+            }
+            self.friend_decls.1 = checked_calculate_table_size(binary, self.friend_decls.0)?;
+        }
+        Ok(())
+    }
 }
 
 impl ScriptSerializer {
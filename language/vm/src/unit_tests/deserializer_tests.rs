@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    file_format::{CompiledModule, CompiledScript},
+    file_format::{basic_test_module, CompiledModule, CompiledScript, ModuleHandle, Visibility},
     file_format_common::*,
 };
 use move_core_types::vm_status::StatusCode;
@@ -43,7 +43,7 @@ fn malformed_simple() {
 
     // bad version
     binary = BinaryConstants::LIBRA_MAGIC.to_vec();
-    binary.push(2); // version
+    binary.push((VERSION_MAX + 1) as u8); // version
     binary.push(0);
     binary.push(0);
     binary.push(0);
@@ -57,7 +57,7 @@ fn malformed_simple() {
 
     // bad version
     binary = BinaryConstants::LIBRA_MAGIC.to_vec();
-    binary.push(2); // version
+    binary.push((VERSION_MAX + 1) as u8); // version
     binary.push(0);
     binary.push(0);
     binary.push(0);
@@ -280,3 +280,35 @@ static EMPTY_SCRIPT: &[u8] = include_bytes!("../../../../types/src/test_helpers/
 fn deserialize_file() {
     CompiledScript::deserialize(EMPTY_SCRIPT).expect("script should deserialize properly");
 }
+
+#[test]
+fn friend_decls_versioning() {
+    let version = |binary: &[u8]| binary[BinaryConstants::LIBRA_MAGIC_SIZE];
+
+    // modules without friends keep the initial version
+    let module = basic_test_module();
+    let mut binary = vec![];
+    module.serialize(&mut binary).unwrap();
+    assert_eq!(u32::from(version(&binary)), VERSION_1);
+
+    let mut module = basic_test_module();
+    module.friend_decls.push(ModuleHandle {
+        address: module.module_handles[0].address,
+        name: module.module_handles[0].name,
+    });
+    module.function_defs[0].visibility = Visibility::Friend;
+    let mut binary = vec![];
+    module.serialize(&mut binary).unwrap();
+    assert_eq!(u32::from(version(&binary)), VERSION_2);
+    let deserialized = CompiledModule::deserialize(&binary).unwrap().into_inner();
+    assert_eq!(deserialized, module);
+
+    // version 1 binaries can't declare friends
+    binary[BinaryConstants::LIBRA_MAGIC_SIZE] = VERSION_1 as u8;
+    assert_eq!(
+        CompiledModule::deserialize(&binary)
+            .expect_err("Expected unknown table")
+            .major_status(),
+        StatusCode::UNKNOWN_TABLE_TYPE
+    );
+}
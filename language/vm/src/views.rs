@@ -480,6 +480,10 @@ impl<'a, T: ModuleAccess> FunctionDefinitionView<'a, T> {
         self.function_def.is_public()
     }
 
+    pub fn visibility(&self) -> Visibility {
+        self.function_def.visibility
+    }
+
     pub fn is_native(&self) -> bool {
         self.function_def.is_native()
     }
@@ -2,7 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use backup_service::start_backup_service;
-use consensus::{consensus_provider::start_consensus, gen_consensus_reconfig_subscription};
+use consensus::{
+    consensus_provider::{open_consensus_db, start_consensus, start_consensus_observer},
+    gen_consensus_reconfig_subscription,
+};
+use consensus_types::equivocation_evidence::EquivocationEvidenceReader;
 use debug_interface::node_debug_service::NodeDebugService;
 use executor::{db_bootstrapper::maybe_bootstrap, Executor};
 use executor_types::ChunkExecutor;
@@ -217,7 +221,11 @@ fn setup_debug_interface(config: &NodeConfig, logger: Option<Arc<Logger>>) -> No
     libra_trace::set_libra_trace(&config.debug_interface.libra_trace.sampling)
         .expect("Failed to set libra trace sampling rate.");
 
-    NodeDebugService::new(addr, logger)
+    NodeDebugService::new(
+        addr,
+        logger,
+        config.debug_interface.network_admin_token.clone(),
+    )
 }
 
 async fn periodic_state_dump(node_config: NodeConfig, db: DbReaderWriter) {
@@ -311,6 +319,7 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
     let mut state_sync_network_handles = vec![];
     let mut mempool_network_handles = vec![];
     let mut consensus_network_handles = None;
+    let mut observer_network_handles = vec![];
     let mut reconfig_subscriptions = vec![];
 
     let (mempool_reconfig_subscription, mempool_reconfig_events) =
@@ -319,7 +328,9 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
     // consensus has to subscribe to ALL on-chain configs
     let (consensus_reconfig_subscription, consensus_reconfig_events) =
         gen_consensus_reconfig_subscription();
-    if node_config.base.role.is_validator() {
+    // full nodes running the consensus observer need the validator set of every epoch
+    let observer_enabled = node_config.consensus.observer.enabled;
+    if node_config.base.role.is_validator() || observer_enabled {
         reconfig_subscriptions.push(consensus_reconfig_subscription);
     }
 
@@ -375,8 +386,16 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
                         consensus::network_interface::network_endpoint_config(),
                     ));
             }
-            // Currently no FullNode network specific steps.
-            RoleType::FullNode => (),
+            // Validators publish to the consensus observers of their full node networks, full
+            // nodes subscribe to their upstream peers and republish to their downstream peers.
+            RoleType::FullNode => {
+                if observer_enabled {
+                    observer_network_handles.push(
+                        network_builder
+                            .add_protocol_handler(consensus::observer::network_endpoint_config()),
+                    );
+                }
+            }
         }
 
         reconfig_subscriptions.append(network_builder.reconfig_subscriptions());
@@ -399,6 +418,7 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
             .build()
             .expect("Failed to start runtime. Won't be able to start networking.");
         network_builder.build(runtime.handle().clone());
+        debug_if.add_network_admin(network_builder.network_admin());
         network_runtimes.push(runtime);
         debug!(
             "Network built for network context: {}",
@@ -429,7 +449,20 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
     );
     let (mp_client_sender, mp_client_events) = channel(AC_SMP_CHANNEL_BUFFER_SIZE);
 
-    let rpc_runtime = bootstrap_rpc(&node_config, chain_id, libra_db.clone(), mp_client_sender);
+    // Validators share the consensus db with the JSON-RPC endpoint serving the equivocation
+    // evidence collected by consensus.
+    let consensus_db = consensus_network_handles
+        .as_ref()
+        .map(|_| open_consensus_db(node_config));
+    let rpc_runtime = bootstrap_rpc(
+        &node_config,
+        chain_id,
+        libra_db.clone(),
+        consensus_db
+            .clone()
+            .map(|db| db as Arc<dyn EquivocationEvidenceReader>),
+        mp_client_sender,
+    );
 
     let mut consensus_runtime = None;
     let (consensus_to_mempool_sender, consensus_requests) = channel(INTRA_NODE_CHANNEL_BUFFER_SIZE);
@@ -463,14 +496,26 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
         instant = Instant::now();
         consensus_runtime = Some(start_consensus(
             node_config,
+            consensus_db.expect("Consensus db is opened for validators"),
             consensus_network_sender,
             consensus_network_events,
+            observer_network_handles,
             state_synchronizer.create_client(),
             consensus_to_mempool_sender,
             libra_db,
             consensus_reconfig_events,
         ));
         debug!("Consensus started in {} ms", instant.elapsed().as_millis());
+    } else if observer_enabled {
+        block_on(state_synchronizer.wait_until_initialized())
+            .expect("State synchronizer initialization failure");
+        consensus_runtime = Some(start_consensus_observer(
+            node_config,
+            observer_network_handles,
+            state_synchronizer.create_client(),
+            consensus_reconfig_events,
+        ));
+        debug!("Consensus observer started");
     }
 
     // Spawn a task which will periodically dump some interesting state
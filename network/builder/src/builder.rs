@@ -12,7 +12,8 @@
 use channel::{self, message_queues::QueueStyle};
 use libra_config::{
     config::{
-        DiscoveryMethod, NetworkConfig, RoleType, CONNECTION_BACKOFF_BASE,
+        DiscoveryMethod, DnsDiscoveryConfig, FileDiscoveryConfig, InboundAdmissionConfig,
+        NetworkConfig, ProtocolQuotaConfig, RoleType, CONNECTION_BACKOFF_BASE,
         CONNECTIVITY_CHECK_INTERVAL_MS, MAX_CONCURRENT_NETWORK_NOTIFS, MAX_CONCURRENT_NETWORK_REQS,
         MAX_CONNECTION_DELAY_MS, MAX_FRAME_SIZE, MAX_FULLNODE_CONNECTIONS, NETWORK_CHANNEL_SIZE,
     },
@@ -26,6 +27,7 @@ use libra_network_address::NetworkAddress;
 use libra_network_address_encryption::Encryptor;
 use libra_types::{chain_id::ChainId, PeerId};
 use network::{
+    admin::NetworkAdmin,
     connectivity_manager::{builder::ConnectivityManagerBuilder, ConnectivityRequest},
     logging::NetworkSchema,
     peer_manager::{
@@ -36,19 +38,36 @@ use network::{
         health_checker::{self, builder::HealthCheckerBuilder},
         network::{NewNetworkEvents, NewNetworkSender},
     },
+    transport::fault_injection::NetworkFaults,
     ProtocolId,
 };
+use network_discovery::builder::DiscoveryListenerBuilder;
 use network_simple_onchain_discovery::{
     builder::ConfigurationChangeListenerBuilder, gen_simple_discovery_reconfig_subscription,
 };
 use std::{
     clone::Clone,
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     sync::Arc,
 };
 use subscription_service::ReconfigSubscription;
 use tokio::runtime::Handle;
 
+/// Keys the protocol quotas of the config by `ProtocolId`, panics on unknown protocol names.
+pub(crate) fn parse_protocol_quotas(
+    quotas: &BTreeMap<String, ProtocolQuotaConfig>,
+) -> HashMap<ProtocolId, ProtocolQuotaConfig> {
+    quotas
+        .iter()
+        .map(|(name, quota)| {
+            let protocol = name
+                .parse::<ProtocolId>()
+                .unwrap_or_else(|e| panic!("Invalid protocol quota: {}", e));
+            (protocol, *quota)
+        })
+        .collect()
+}
+
 #[derive(Debug, PartialEq, PartialOrd)]
 enum State {
     CREATED,
@@ -67,10 +86,16 @@ pub struct NetworkBuilder {
     network_context: Arc<NetworkContext>,
 
     configuration_change_listener_builder: Option<ConfigurationChangeListenerBuilder>,
+    discovery_listener_builder: Option<DiscoveryListenerBuilder>,
     connectivity_manager_builder: Option<ConnectivityManagerBuilder>,
     health_checker_builder: Option<HealthCheckerBuilder>,
     peer_manager_builder: PeerManagerBuilder,
 
+    // Kept for the network admin interface.
+    trusted_peers: Arc<RwLock<HashMap<PeerId, HashSet<x25519::PublicKey>>>>,
+    connection_reqs_tx: ConnectionRequestSender,
+    network_faults: Option<NetworkFaults>,
+
     // (StateSync) ReconfigSubscriptions required by internal Network components.
     reconfig_subscriptions: Vec<ReconfigSubscription>,
 }
@@ -96,7 +121,7 @@ impl NetworkBuilder {
             chain_id,
             network_context.clone(),
             listen_address,
-            trusted_peers,
+            trusted_peers.clone(),
             authentication_mode,
             network_channel_size,
             max_concurrent_network_reqs,
@@ -104,15 +129,21 @@ impl NetworkBuilder {
             max_frame_size,
             enable_proxy_protocol,
         );
+        let connection_reqs_tx =
+            ConnectionRequestSender::new(peer_manager_builder.connection_reqs_tx());
 
         NetworkBuilder {
             state: State::CREATED,
             executor: None,
             network_context,
             configuration_change_listener_builder: None,
+            discovery_listener_builder: None,
             connectivity_manager_builder: None,
             health_checker_builder: None,
             peer_manager_builder,
+            trusted_peers,
+            connection_reqs_tx,
+            network_faults: None,
             reconfig_subscriptions: vec![],
         }
     }
@@ -185,6 +216,21 @@ impl NetworkBuilder {
             config.max_concurrent_network_notifs,
         );
 
+        network_builder.set_protocol_quotas(parse_protocol_quotas(&config.protocol_quotas));
+        network_builder.set_inbound_admission(config.inbound_admission.clone());
+        if config.enable_compression {
+            network_builder.enable_compression();
+        }
+        if config.enable_fault_injection {
+            assert_eq!(
+                chain_id,
+                ChainId::test(),
+                "{} Fault injection can only be enabled on test chains",
+                config.network_id
+            );
+            network_builder.set_network_faults(NetworkFaults::new());
+        }
+
         network_builder.add_connection_monitoring(
             config.ping_interval_ms,
             config.ping_timeout_ms,
@@ -228,6 +274,12 @@ impl NetworkBuilder {
             DiscoveryMethod::Onchain => {
                 network_builder.add_configuration_change_listener(config.encryptor());
             }
+            DiscoveryMethod::Dns(dns_config) => {
+                network_builder.add_dns_discovery_listener(dns_config.clone());
+            }
+            DiscoveryMethod::File(file_config) => {
+                network_builder.add_file_discovery_listener(file_config.clone());
+            }
             DiscoveryMethod::None => {}
         }
 
@@ -241,6 +293,7 @@ impl NetworkBuilder {
         self.executor = Some(executor);
         self.build_peer_manager()
             .build_configuration_change_listener()
+            .build_discovery_listener()
             .build_connectivity_manager()
             .build_connection_monitoring()
     }
@@ -253,6 +306,7 @@ impl NetworkBuilder {
             .start_connectivity_manager()
             .start_connection_monitoring()
             .start_configuration_change_listener()
+            .start_discovery_listener()
     }
 
     pub fn reconfig_subscriptions(&mut self) -> &mut Vec<ReconfigSubscription> {
@@ -270,6 +324,56 @@ impl NetworkBuilder {
         }
     }
 
+    /// Set the weights and rate limits of the protocols in the outbound path of the peers.
+    pub fn set_protocol_quotas(
+        &mut self,
+        protocol_quotas: HashMap<ProtocolId, ProtocolQuotaConfig>,
+    ) -> &mut Self {
+        self.peer_manager_builder
+            .set_protocol_quotas(Arc::new(protocol_quotas));
+        self
+    }
+
+    /// Set the limits of the inbound connections by source IP address.
+    pub fn set_inbound_admission(
+        &mut self,
+        inbound_admission: InboundAdmissionConfig,
+    ) -> &mut Self {
+        self.peer_manager_builder
+            .set_inbound_admission(inbound_admission);
+        self
+    }
+
+    /// Return a handle to list, dial, disconnect and ban peers at runtime. The network must be
+    /// built, so that the health of the peers can be reported.
+    pub fn network_admin(&self) -> NetworkAdmin {
+        assert!(self.state >= State::BUILT);
+        NetworkAdmin::new(
+            self.network_context.clone(),
+            self.connection_reqs_tx.clone(),
+            self.trusted_peers.clone(),
+            self.health_checker_builder
+                .as_ref()
+                .and_then(|health_checker| health_checker.peer_health()),
+            self.network_faults.clone(),
+        )
+    }
+
+    /// Subject the connections to the faults of the given handle, which can be changed at
+    /// runtime to test the network under degraded conditions.
+    pub fn set_network_faults(&mut self, network_faults: NetworkFaults) -> &mut Self {
+        self.peer_manager_builder
+            .set_network_faults(network_faults.clone());
+        self.network_faults = Some(network_faults);
+        self
+    }
+
+    /// Compress message payloads on the connections to peers which support it.
+    pub fn enable_compression(&mut self) -> &mut Self {
+        self.peer_manager_builder.enable_compression();
+        self
+    }
+
     fn add_connection_event_listener(&mut self) -> conn_notifs_channel::Receiver {
         self.peer_manager_builder.add_connection_event_listener()
     }
@@ -397,6 +501,46 @@ impl NetworkBuilder {
         self
     }
 
+    /// Add a listener which periodically resolves the TXT records of a DNS name to discover peers.
+    fn add_dns_discovery_listener(&mut self, config: DnsDiscoveryConfig) -> &mut Self {
+        let conn_mgr_reqs_tx = self
+            .conn_mgr_reqs_tx()
+            .expect("ConnectivityManager must be installed for DNS discovery");
+        self.discovery_listener_builder = Some(DiscoveryListenerBuilder::create_dns(
+            self.network_context.clone(),
+            conn_mgr_reqs_tx,
+            config,
+        ));
+        self
+    }
+
+    /// Add a listener which watches a local file of peer addresses to discover peers.
+    fn add_file_discovery_listener(&mut self, config: FileDiscoveryConfig) -> &mut Self {
+        let conn_mgr_reqs_tx = self
+            .conn_mgr_reqs_tx()
+            .expect("ConnectivityManager must be installed for file discovery");
+        self.discovery_listener_builder = Some(DiscoveryListenerBuilder::create_file(
+            self.network_context.clone(),
+            conn_mgr_reqs_tx,
+            config,
+        ));
+        self
+    }
+
+    fn build_discovery_listener(&mut self) -> &mut Self {
+        if let Some(discovery_listener) = self.discovery_listener_builder.as_mut() {
+            discovery_listener.build();
+        }
+        self
+    }
+
+    fn start_discovery_listener(&mut self) -> &mut Self {
+        if let Some(discovery_listener) = self.discovery_listener_builder.as_mut() {
+            discovery_listener.start(self.executor.as_mut().expect("Executor must exist"));
+        }
+        self
+    }
+
     /// Add a HealthChecker to the network.
     fn add_connection_monitoring(
         &mut self,
@@ -2,9 +2,13 @@
 // SPDX-License-Identifier: Apache-2.0
 
 //! Integration tests for validator_network.
-use crate::dummy::{setup_network, DummyMsg};
+use crate::{
+    builder::parse_protocol_quotas,
+    dummy::{setup_network, DummyMsg},
+};
 use futures::{future::join, StreamExt};
-use network::protocols::network::Event;
+use libra_config::config::{NetworkConfig, ProtocolQuotaConfig};
+use network::{protocols::network::Event, ProtocolId};
 use std::time::Duration;
 
 #[test]
@@ -106,3 +110,24 @@ fn test_rpc() {
     let (res_msg, _) = tn.runtime.block_on(join(f_send, f_respond));
     assert_eq!(res_msg.unwrap(), msg);
 }
+
+#[test]
+fn test_protocol_quotas() {
+    let mut quotas = NetworkConfig::default().protocol_quotas;
+    let quota = ProtocolQuotaConfig {
+        weight: 2,
+        max_bytes_per_sec: Some(1024),
+    };
+    quotas.insert(ProtocolId::MempoolDirectSend.as_str().to_string(), quota);
+    let protocol_quotas = parse_protocol_quotas(&quotas);
+    assert_eq!(protocol_quotas.len(), quotas.len());
+    assert_eq!(protocol_quotas[&ProtocolId::MempoolDirectSend], quota);
+}
+
+#[test]
+#[should_panic(expected = "Unknown protocol: ConsensusRcp")]
+fn test_unknown_protocol_quota() {
+    let mut quotas = NetworkConfig::default().protocol_quotas;
+    quotas.insert("ConsensusRcp".to_string(), ProtocolQuotaConfig::default());
+    parse_protocol_quotas(&quotas);
+}
@@ -0,0 +1,104 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{dns::DnsDiscoveryListener, file::FileDiscoveryListener};
+use libra_config::{
+    config::{DnsDiscoveryConfig, FileDiscoveryConfig},
+    network_id::NetworkContext,
+};
+use network::connectivity_manager::ConnectivityRequest;
+use std::sync::Arc;
+use tokio::runtime::Handle;
+
+enum DiscoveryListenerConfig {
+    Dns(DnsDiscoveryConfig),
+    File(FileDiscoveryConfig),
+}
+
+enum DiscoveryListener {
+    Dns(DnsDiscoveryListener),
+    File(FileDiscoveryListener),
+}
+
+#[derive(Debug, PartialEq, PartialOrd)]
+enum State {
+    CREATED,
+    BUILT,
+    STARTED,
+}
+
+pub struct DiscoveryListenerBuilder {
+    network_context: Arc<NetworkContext>,
+    conn_mgr_reqs_tx: channel::Sender<ConnectivityRequest>,
+    config: Option<DiscoveryListenerConfig>,
+    listener: Option<DiscoveryListener>,
+    state: State,
+}
+
+impl DiscoveryListenerBuilder {
+    fn create(
+        network_context: Arc<NetworkContext>,
+        conn_mgr_reqs_tx: channel::Sender<ConnectivityRequest>,
+        config: DiscoveryListenerConfig,
+    ) -> Self {
+        Self {
+            network_context,
+            conn_mgr_reqs_tx,
+            config: Some(config),
+            listener: None,
+            state: State::CREATED,
+        }
+    }
+
+    pub fn create_dns(
+        network_context: Arc<NetworkContext>,
+        conn_mgr_reqs_tx: channel::Sender<ConnectivityRequest>,
+        config: DnsDiscoveryConfig,
+    ) -> Self {
+        Self::create(
+            network_context,
+            conn_mgr_reqs_tx,
+            DiscoveryListenerConfig::Dns(config),
+        )
+    }
+
+    pub fn create_file(
+        network_context: Arc<NetworkContext>,
+        conn_mgr_reqs_tx: channel::Sender<ConnectivityRequest>,
+        config: FileDiscoveryConfig,
+    ) -> Self {
+        Self::create(
+            network_context,
+            conn_mgr_reqs_tx,
+            DiscoveryListenerConfig::File(config),
+        )
+    }
+
+    pub fn build(&mut self) -> &mut Self {
+        assert_eq!(self.state, State::CREATED);
+        self.state = State::BUILT;
+        let network_context = self.network_context.clone();
+        let conn_mgr_reqs_tx = self.conn_mgr_reqs_tx.clone();
+        self.listener = Some(
+            match self.config.take().expect("Listener must be configured") {
+                DiscoveryListenerConfig::Dns(config) => DiscoveryListener::Dns(
+                    DnsDiscoveryListener::new(network_context, config, conn_mgr_reqs_tx),
+                ),
+                DiscoveryListenerConfig::File(config) => DiscoveryListener::File(
+                    FileDiscoveryListener::new(network_context, config, conn_mgr_reqs_tx),
+                ),
+            },
+        );
+        self
+    }
+
+    pub fn start(&mut self, executor: &Handle) -> &mut Self {
+        assert_eq!(self.state, State::BUILT);
+        self.state = State::STARTED;
+        match self.listener.take().expect("Listener must be built") {
+            DiscoveryListener::Dns(listener) => executor.spawn(listener.start()),
+            DiscoveryListener::File(listener) => executor.spawn(listener.start()),
+        };
+        self
+    }
+}
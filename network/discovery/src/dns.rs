@@ -0,0 +1,107 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Discovery of peers from the TXT records of a DNS name, each record holding the
+//! `NetworkAddress` of a peer. The records are looked up periodically so that operators can
+//! update the list of peers without touching the configuration of the nodes.
+
+use crate::{PeerUpdateSender, DISCOVERY_COUNTS};
+use anyhow::Result;
+use libra_config::{config::DnsDiscoveryConfig, network_id::NetworkContext};
+use libra_logger::prelude::*;
+use network::{
+    connectivity_manager::{ConnectivityRequest, DiscoverySource},
+    counters::inc_by_with_context,
+    logging::NetworkSchema,
+};
+use std::{sync::Arc, time::Duration};
+use trust_dns_resolver::TokioAsyncResolver;
+
+/// Listener which periodically resolves the TXT records of a DNS name and converts them to
+/// ConnectivityRequests for the ConnectivityManager.
+pub struct DnsDiscoveryListener {
+    network_context: Arc<NetworkContext>,
+    config: DnsDiscoveryConfig,
+    update_sender: PeerUpdateSender,
+}
+
+impl DnsDiscoveryListener {
+    pub fn new(
+        network_context: Arc<NetworkContext>,
+        config: DnsDiscoveryConfig,
+        conn_mgr_reqs_tx: channel::Sender<ConnectivityRequest>,
+    ) -> Self {
+        Self {
+            update_sender: PeerUpdateSender::new(
+                network_context.clone(),
+                DiscoverySource::Dns,
+                conn_mgr_reqs_tx,
+            ),
+            network_context,
+            config,
+        }
+    }
+
+    /// Looks up the TXT records of the configured name. A record may be split in several
+    /// character strings, which are concatenated.
+    async fn lookup(&self, resolver: &TokioAsyncResolver) -> Result<Vec<String>> {
+        let records = resolver.txt_lookup(self.config.name.as_str()).await?;
+        Ok(records
+            .iter()
+            .map(|record| {
+                record
+                    .txt_data()
+                    .iter()
+                    .map(|data| String::from_utf8_lossy(data))
+                    .collect::<String>()
+            })
+            .collect())
+    }
+
+    /// Starts the listener, resolving the records every `interval_secs`. Creates an infinite loop.
+    pub async fn start(mut self) {
+        info!(
+            NetworkSchema::new(&self.network_context).discovery_source(&DiscoverySource::Dns),
+            "{} Starting DNS Discovery actor for '{}'", self.network_context, self.config.name
+        );
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(self.config.interval_secs.max(1)));
+        let mut resolver = None;
+        loop {
+            interval.tick().await;
+            if resolver.is_none() {
+                match TokioAsyncResolver::tokio_from_system_conf().await {
+                    Ok(new_resolver) => resolver = Some(new_resolver),
+                    Err(err) => {
+                        error!(
+                            NetworkSchema::new(&self.network_context)
+                                .discovery_source(&DiscoverySource::Dns),
+                            "DNS discovery: failed to create resolver: {}", err
+                        );
+                        continue;
+                    }
+                }
+            }
+            let lookup_result = match resolver.as_ref() {
+                Some(resolver) => self.lookup(resolver).await,
+                None => continue,
+            };
+            match lookup_result {
+                Ok(entries) => self.update_sender.update(entries).await,
+                Err(err) => {
+                    inc_by_with_context(
+                        &DISCOVERY_COUNTS,
+                        &self.network_context,
+                        "lookup_failure",
+                        1,
+                    );
+                    warn!(
+                        NetworkSchema::new(&self.network_context)
+                            .discovery_source(&DiscoverySource::Dns),
+                        "DNS discovery: failed to look up '{}': {}", self.config.name, err
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,81 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Discovery of peers from a local file listing one `NetworkAddress` per line. Empty lines and
+//! lines starting with `#` are ignored. The file is read periodically and the peers are updated
+//! whenever its content changes.
+
+use crate::{PeerUpdateSender, DISCOVERY_COUNTS};
+use libra_config::{config::FileDiscoveryConfig, network_id::NetworkContext};
+use libra_logger::prelude::*;
+use network::{
+    connectivity_manager::{ConnectivityRequest, DiscoverySource},
+    counters::inc_by_with_context,
+    logging::NetworkSchema,
+};
+use std::{sync::Arc, time::Duration};
+
+/// Extracts the entries of a peer file.
+pub fn parse_peer_file(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect()
+}
+
+/// Listener which watches a local file of peer addresses and converts its content to
+/// ConnectivityRequests for the ConnectivityManager.
+pub struct FileDiscoveryListener {
+    network_context: Arc<NetworkContext>,
+    config: FileDiscoveryConfig,
+    update_sender: PeerUpdateSender,
+}
+
+impl FileDiscoveryListener {
+    pub fn new(
+        network_context: Arc<NetworkContext>,
+        config: FileDiscoveryConfig,
+        conn_mgr_reqs_tx: channel::Sender<ConnectivityRequest>,
+    ) -> Self {
+        Self {
+            update_sender: PeerUpdateSender::new(
+                network_context.clone(),
+                DiscoverySource::File,
+                conn_mgr_reqs_tx,
+            ),
+            network_context,
+            config,
+        }
+    }
+
+    /// Starts the listener, reading the file every `interval_secs`. Creates an infinite loop.
+    pub async fn start(mut self) {
+        info!(
+            NetworkSchema::new(&self.network_context).discovery_source(&DiscoverySource::File),
+            "{} Starting File Discovery actor for {:?}", self.network_context, self.config.path
+        );
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(self.config.interval_secs.max(1)));
+        loop {
+            interval.tick().await;
+            match tokio::fs::read_to_string(&self.config.path).await {
+                Ok(content) => self.update_sender.update(parse_peer_file(&content)).await,
+                Err(err) => {
+                    inc_by_with_context(
+                        &DISCOVERY_COUNTS,
+                        &self.network_context,
+                        "read_failure",
+                        1,
+                    );
+                    warn!(
+                        NetworkSchema::new(&self.network_context)
+                            .discovery_source(&DiscoverySource::File),
+                        "File discovery: failed to read {:?}: {}", self.config.path, err
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,148 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Discovery of peers from sources other than the on-chain validator set: the TXT records of a
+//! DNS name ([`dns`]) or a local file ([`file`]).
+//!
+//! Both sources list one `NetworkAddress` per entry. Each address must be a LibraNet address,
+//! the peer id being derived from the noise public key it carries. The discovered peers are sent
+//! to the `ConnectivityManager` whenever the list changes.
+
+use anyhow::{ensure, format_err, Result};
+use futures::sink::SinkExt;
+use libra_config::network_id::NetworkContext;
+use libra_logger::prelude::*;
+use libra_metrics::{register_int_counter_vec, IntCounterVec};
+use libra_network_address::NetworkAddress;
+use libra_types::PeerId;
+use network::{
+    connectivity_manager::{ConnectivityRequest, DiscoverySource},
+    counters::inc_by_with_context,
+    logging::NetworkSchema,
+};
+use once_cell::sync::Lazy;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+pub mod builder;
+pub mod dns;
+pub mod file;
+#[cfg(test)]
+mod test;
+
+pub static DISCOVERY_COUNTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "libra_network_discovery_counts",
+        "Counters of DNS and file based peer discovery events",
+        &["role_type", "network_id", "peer_id", "metric"]
+    )
+    .unwrap()
+});
+
+/// Parses an entry of a discovery source into the peer id and address of a peer.
+pub fn parse_peer_address(entry: &str) -> Result<(PeerId, NetworkAddress)> {
+    let addr: NetworkAddress = entry
+        .parse()
+        .map_err(|err| format_err!("Invalid network address '{}': {}", entry, err))?;
+    ensure!(
+        addr.is_libranet_addr(),
+        "Not a LibraNet network address: '{}'",
+        addr
+    );
+    let pubkey = addr
+        .find_noise_proto()
+        .ok_or_else(|| format_err!("Missing noise public key: '{}'", addr))?;
+    Ok((PeerId::from_identity_public_key(pubkey), addr))
+}
+
+/// Sends the peers listed by a discovery source to the `ConnectivityManager`, skipping the
+/// updates that don't change the list.
+pub(crate) struct PeerUpdateSender {
+    network_context: Arc<NetworkContext>,
+    source: DiscoverySource,
+    conn_mgr_reqs_tx: channel::Sender<ConnectivityRequest>,
+    // Entries of the last list successfully sent
+    last_entries: Option<Vec<String>>,
+}
+
+impl PeerUpdateSender {
+    pub(crate) fn new(
+        network_context: Arc<NetworkContext>,
+        source: DiscoverySource,
+        conn_mgr_reqs_tx: channel::Sender<ConnectivityRequest>,
+    ) -> Self {
+        Self {
+            network_context,
+            source,
+            conn_mgr_reqs_tx,
+            last_entries: None,
+        }
+    }
+
+    /// Sends the peers in `entries` if they differ from the ones last sent. Invalid entries are
+    /// logged and ignored.
+    pub(crate) async fn update(&mut self, mut entries: Vec<String>) {
+        entries.sort();
+        entries.dedup();
+        if self.last_entries.as_ref() == Some(&entries) {
+            return;
+        }
+
+        let mut peer_addrs: HashMap<PeerId, Vec<NetworkAddress>> = HashMap::new();
+        for entry in entries.iter() {
+            match parse_peer_address(entry) {
+                Ok((peer_id, addr)) => peer_addrs.entry(peer_id).or_default().push(addr),
+                Err(err) => {
+                    inc_by_with_context(
+                        &DISCOVERY_COUNTS,
+                        &self.network_context,
+                        "parse_failure",
+                        1,
+                    );
+                    warn!(
+                        NetworkSchema::new(&self.network_context).discovery_source(&self.source),
+                        "{} discovery: ignored entry: {}", self.source, err
+                    );
+                }
+            }
+        }
+        let peer_pubkeys: HashMap<_, _> = peer_addrs
+            .iter()
+            .map(|(peer_id, addrs)| {
+                let pubkeys: HashSet<_> = addrs
+                    .iter()
+                    .filter_map(NetworkAddress::find_noise_proto)
+                    .collect();
+                (*peer_id, pubkeys)
+            })
+            .collect();
+
+        info!(
+            NetworkSchema::new(&self.network_context).discovery_source(&self.source),
+            "{} discovery: update {} with {} peers",
+            self.source,
+            self.network_context,
+            peer_addrs.len()
+        );
+        let updates = vec![
+            ConnectivityRequest::UpdateAddresses(self.source, peer_addrs),
+            ConnectivityRequest::UpdateEligibleNodes(self.source, peer_pubkeys),
+        ];
+        for update in updates {
+            if let Err(err) = self.conn_mgr_reqs_tx.send(update).await {
+                inc_by_with_context(&DISCOVERY_COUNTS, &self.network_context, "send_failure", 1);
+                warn!(
+                    NetworkSchema::new(&self.network_context).discovery_source(&self.source),
+                    "{} discovery: failed to send update to ConnectivityManager: {}",
+                    self.source,
+                    err
+                );
+                return;
+            }
+        }
+        inc_by_with_context(&DISCOVERY_COUNTS, &self.network_context, "update", 1);
+        self.last_entries = Some(entries);
+    }
+}
@@ -0,0 +1,88 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    file::{parse_peer_file, FileDiscoveryListener},
+    parse_peer_address,
+};
+use futures::StreamExt;
+use libra_config::{
+    config::{FileDiscoveryConfig, HANDSHAKE_VERSION},
+    network_id::NetworkContext,
+};
+use libra_crypto::{x25519, Uniform};
+use libra_network_address::NetworkAddress;
+use libra_temppath::TempPath;
+use libra_types::PeerId;
+use network::connectivity_manager::{ConnectivityRequest, DiscoverySource};
+use tokio::runtime::Runtime;
+
+fn libranet_addr(addr: &str) -> (x25519::PublicKey, NetworkAddress) {
+    let pubkey = x25519::PrivateKey::generate_for_testing().public_key();
+    let addr = addr
+        .parse::<NetworkAddress>()
+        .unwrap()
+        .append_prod_protos(pubkey, HANDSHAKE_VERSION);
+    (pubkey, addr)
+}
+
+#[test]
+fn test_parse_peer_address() {
+    let (pubkey, addr) = libranet_addr("/ip4/127.0.0.1/tcp/6180");
+    let (peer_id, parsed_addr) = parse_peer_address(&addr.to_string()).unwrap();
+    assert_eq!(peer_id, PeerId::from_identity_public_key(pubkey));
+    assert_eq!(parsed_addr, addr);
+
+    // not a network address
+    parse_peer_address("seed.example.com").unwrap_err();
+    // no noise public key to authenticate the peer
+    parse_peer_address("/ip4/127.0.0.1/tcp/6180").unwrap_err();
+}
+
+#[test]
+fn test_parse_peer_file() {
+    let content = "# seed peers\n\n  /ip4/127.0.0.1/tcp/6180  \n/dns4/example.com/tcp/6180\n";
+    assert_eq!(
+        parse_peer_file(content),
+        vec![
+            "/ip4/127.0.0.1/tcp/6180".to_string(),
+            "/dns4/example.com/tcp/6180".to_string()
+        ]
+    );
+}
+
+#[test]
+fn test_file_discovery() {
+    let (pubkey, addr) = libranet_addr("/ip4/127.0.0.1/tcp/6180");
+    let peer_id = PeerId::from_identity_public_key(pubkey);
+    let path = TempPath::new();
+    std::fs::write(path.path(), format!("{}\nnot-an-address\n", addr)).unwrap();
+
+    let (conn_mgr_reqs_tx, mut conn_mgr_reqs_rx) = channel::new_test(8);
+    let listener = FileDiscoveryListener::new(
+        NetworkContext::mock(),
+        FileDiscoveryConfig {
+            path: path.path().to_path_buf(),
+            interval_secs: 1,
+        },
+        conn_mgr_reqs_tx,
+    );
+
+    let mut rt = Runtime::new().unwrap();
+    rt.spawn(listener.start());
+    rt.block_on(async move {
+        match conn_mgr_reqs_rx.next().await.unwrap() {
+            ConnectivityRequest::UpdateAddresses(DiscoverySource::File, peer_addrs) => {
+                assert_eq!(peer_addrs.len(), 1);
+                assert_eq!(peer_addrs.get(&peer_id).unwrap(), &vec![addr]);
+            }
+            request => panic!("Unexpected request: {:?}", request),
+        }
+        match conn_mgr_reqs_rx.next().await.unwrap() {
+            ConnectivityRequest::UpdateEligibleNodes(DiscoverySource::File, peer_pubkeys) => {
+                assert!(peer_pubkeys.get(&peer_id).unwrap().contains(&pubkey));
+            }
+            request => panic!("Unexpected request: {:?}", request),
+        }
+    });
+}
@@ -22,6 +22,8 @@ pub mod boxed;
 #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
 pub mod memory;
 pub mod proxy_protocol;
+#[cfg(feature = "quic")]
+pub mod quic;
 pub mod tcp;
 
 /// Origin of how a Connection was established.
@@ -0,0 +1,401 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! QUIC Transport
+//!
+//! Every connection opens a bidirectional "control" stream which is exposed as the
+//! `AsyncRead`/`AsyncWrite` of the [`QuicSocket`], so that the upper layers can run their
+//! handshakes on it as they would on a tcp socket. Additional unidirectional streams can then be
+//! opened on the same connection, each one with its own flow control.
+//!
+//! The TLS layer of QUIC uses a self-signed certificate and does not authenticate the peers. It
+//! is up to the upper layers to authenticate the remote and to bind the connection to that
+//! authentication, using the certificate of the listener (see [`QuicSocket::server_certificate`]).
+use crate::{
+    compat::IoCompat,
+    transport::{tcp::resolve_with_filter, Transport},
+};
+use futures::{
+    future::{Future, FutureExt},
+    io::{AsyncRead, AsyncWrite},
+    stream::{Stream, StreamExt},
+};
+use libra_network_address::{parse_dns_quic, parse_ip_quic, NetworkAddress, Protocol};
+use libra_types::PeerId;
+use quinn::{
+    Certificate, CertificateChain, ClientConfig, ClientConfigBuilder, Connection, Endpoint,
+    IncomingUniStreams, NewConnection, PrivateKey, RecvStream, SendStream, ServerConfigBuilder,
+};
+use std::{
+    convert::TryFrom,
+    fmt, io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+/// The server name presented in the TLS handshake. Certificates are not verified, so it only
+/// needs to match the name the self-signed certificates are generated for.
+const SERVER_NAME: &str = "libra";
+
+/// Transport to build QUIC connections
+#[derive(Debug, Clone, Default)]
+pub struct QuicTransport;
+
+impl Transport for QuicTransport {
+    type Output = QuicSocket;
+    type Error = io::Error;
+    type Listener = Pin<Box<dyn Stream<Item = io::Result<(Self::Inbound, NetworkAddress)>> + Send>>;
+    type Inbound = Pin<Box<dyn Future<Output = io::Result<QuicSocket>> + Send + 'static>>;
+    type Outbound = Pin<Box<dyn Future<Output = io::Result<QuicSocket>> + Send + 'static>>;
+
+    fn listen_on(
+        &self,
+        addr: NetworkAddress,
+    ) -> Result<(Self::Listener, NetworkAddress), Self::Error> {
+        let ((ipaddr, port), addr_suffix) =
+            parse_ip_quic(addr.as_slice()).ok_or_else(|| invalid_addr_error(&addr))?;
+        if !addr_suffix.is_empty() {
+            return Err(invalid_addr_error(&addr));
+        }
+
+        let (cert_der, key_der) = self_signed_certificate()?;
+        let mut server_config = ServerConfigBuilder::default();
+        server_config
+            .certificate(
+                CertificateChain::from_certs(vec![
+                    Certificate::from_der(&cert_der).map_err(quic_error)?
+                ]),
+                PrivateKey::from_der(&key_der).map_err(quic_error)?,
+            )
+            .map_err(quic_error)?;
+
+        let mut builder = Endpoint::builder();
+        builder.listen(server_config.build());
+        let (endpoint, incoming) = builder
+            .bind(&SocketAddr::new(ipaddr, port))
+            .map_err(quic_error)?;
+        let listen_addr = quic_addr(endpoint.local_addr()?);
+
+        let listener = incoming.map(move |connecting| {
+            let dialer_addr = quic_addr(connecting.remote_address());
+            let endpoint = endpoint.clone();
+            let cert_der = cert_der.clone();
+            let inbound = async move {
+                let NewConnection {
+                    connection,
+                    mut bi_streams,
+                    uni_streams,
+                    ..
+                } = connecting.await.map_err(quic_error)?;
+                // The dialer opens the control stream right after connecting.
+                let (send, recv) = bi_streams
+                    .next()
+                    .await
+                    .ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            "QUIC connection closed before opening the control stream",
+                        )
+                    })?
+                    .map_err(quic_error)?;
+                Ok(QuicSocket::new(
+                    endpoint,
+                    connection,
+                    send,
+                    recv,
+                    uni_streams,
+                    cert_der,
+                ))
+            };
+            Ok((inbound.boxed(), dialer_addr))
+        });
+
+        Ok((listener.boxed(), listen_addr))
+    }
+
+    fn dial(&self, _peer_id: PeerId, addr: NetworkAddress) -> Result<Self::Outbound, Self::Error> {
+        let protos = addr.as_slice();
+
+        // ensure addr is well formed to save some work before potentially
+        // spawning a dial task that will fail anyway.
+        parse_ip_quic(protos)
+            .map(|_| ())
+            .or_else(|| parse_dns_quic(protos).map(|_| ()))
+            .ok_or_else(|| invalid_addr_error(&addr))?;
+
+        Ok(resolve_and_connect(addr).boxed())
+    }
+}
+
+/// Note: we need to take ownership of this `NetworkAddress` (instead of just
+/// borrowing the `&[Protocol]` slice) so this future can be `Send + 'static`.
+async fn resolve_and_connect(addr: NetworkAddress) -> io::Result<QuicSocket> {
+    let protos = addr.as_slice();
+
+    if let Some(((ipaddr, port), _addr_suffix)) = parse_ip_quic(protos) {
+        connect(SocketAddr::new(ipaddr, port)).await
+    } else if let Some(((ip_filter, dns_name, port), _addr_suffix)) = parse_dns_quic(protos) {
+        // resolve dns name and filter
+        let socketaddr_iter = resolve_with_filter(ip_filter, dns_name.as_ref(), port).await?;
+        let mut last_err = None;
+
+        // try to connect until the first succeeds
+        for socketaddr in socketaddr_iter {
+            match connect(socketaddr).await {
+                Ok(socket) => return Ok(socket),
+                Err(err) => last_err = Some(err),
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "could not resolve dns name to any address: name: {}, ip filter: {:?}",
+                    dns_name.as_ref(),
+                    ip_filter,
+                ),
+            )
+        }))
+    } else {
+        Err(invalid_addr_error(&addr))
+    }
+}
+
+async fn connect(remote_addr: SocketAddr) -> io::Result<QuicSocket> {
+    let local_addr = match remote_addr.ip() {
+        IpAddr::V4(_) => SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), 0),
+        IpAddr::V6(_) => SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), 0),
+    };
+    let mut builder = Endpoint::builder();
+    builder.default_client_config(insecure_client_config());
+    let (endpoint, _incoming) = builder.bind(&local_addr).map_err(quic_error)?;
+
+    let NewConnection {
+        connection,
+        uni_streams,
+        ..
+    } = endpoint
+        .connect(&remote_addr, SERVER_NAME)
+        .map_err(quic_error)?
+        .await
+        .map_err(quic_error)?;
+    let server_certificate = connection
+        .authentication_data()
+        .peer_certificates
+        .and_then(|certs| certs.iter().next().map(|cert| cert.0.clone()))
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "QUIC listener did not present a certificate",
+            )
+        })?;
+    let (send, recv) = connection.open_bi().await.map_err(quic_error)?;
+
+    Ok(QuicSocket::new(
+        endpoint,
+        connection,
+        send,
+        recv,
+        uni_streams,
+        server_certificate,
+    ))
+}
+
+/// Generates the self-signed certificate of a listener, returning the DER encoded certificate
+/// and private key.
+fn self_signed_certificate() -> io::Result<(Vec<u8>, Vec<u8>)> {
+    let cert =
+        rcgen::generate_simple_self_signed(vec![SERVER_NAME.to_string()]).map_err(quic_error)?;
+    let cert_der = cert.serialize_der().map_err(quic_error)?;
+    Ok((cert_der, cert.serialize_private_key_der()))
+}
+
+/// Accepts any certificate presented by the listener: peers are authenticated by the upper
+/// layers, which also check that the certificate seen here is the one of the listener.
+struct SkipServerVerification;
+
+impl rustls::ServerCertVerifier for SkipServerVerification {
+    fn verify_server_cert(
+        &self,
+        _roots: &rustls::RootCertStore,
+        _presented_certs: &[rustls::Certificate],
+        _dns_name: webpki::DNSNameRef,
+        _ocsp_response: &[u8],
+    ) -> Result<rustls::ServerCertVerified, rustls::TLSError> {
+        Ok(rustls::ServerCertVerified::assertion())
+    }
+}
+
+fn insecure_client_config() -> ClientConfig {
+    let mut config = ClientConfigBuilder::default().build();
+    Arc::make_mut(&mut config.crypto)
+        .dangerous()
+        .set_certificate_verifier(Arc::new(SkipServerVerification));
+    config
+}
+
+/// Builds the `/ip4/<addr>/quic/<port>` or `/ip6/<addr>/quic/<port>` address of a socket.
+fn quic_addr(socketaddr: SocketAddr) -> NetworkAddress {
+    NetworkAddress::try_from(vec![
+        Protocol::from(socketaddr.ip()),
+        Protocol::Quic(socketaddr.port()),
+    ])
+    .expect("protocols are non-empty")
+}
+
+fn quic_error<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, error)
+}
+
+fn invalid_addr_error(addr: &NetworkAddress) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("Invalid NetworkAddress: '{}'", addr),
+    )
+}
+
+/// An established QUIC connection.
+///
+/// Reading from and writing to the socket uses the control stream of the connection. Closing
+/// the socket closes the whole connection.
+pub struct QuicSocket {
+    // Keeps the endpoint of the connection alive
+    _endpoint: Endpoint,
+    connection: Connection,
+    send: IoCompat<SendStream>,
+    recv: IoCompat<RecvStream>,
+    uni_streams: Option<IncomingUniStreams>,
+    server_certificate: Vec<u8>,
+}
+
+impl QuicSocket {
+    fn new(
+        endpoint: Endpoint,
+        connection: Connection,
+        send: SendStream,
+        recv: RecvStream,
+        uni_streams: IncomingUniStreams,
+        server_certificate: Vec<u8>,
+    ) -> Self {
+        Self {
+            _endpoint: endpoint,
+            connection,
+            send: IoCompat::new(send),
+            recv: IoCompat::new(recv),
+            uni_streams: Some(uni_streams),
+            server_certificate,
+        }
+    }
+
+    /// A handle to the connection, used to open new streams.
+    pub fn connection(&self) -> Connection {
+        self.connection.clone()
+    }
+
+    /// Takes the stream of unidirectional streams opened by the remote. Returns `None` if it
+    /// was already taken.
+    pub fn take_incoming_streams(&mut self) -> Option<IncomingUniStreams> {
+        self.uni_streams.take()
+    }
+
+    /// The DER encoded certificate of the listener: the certificate presented by the remote for
+    /// outbound connections, our own for inbound connections.
+    pub fn server_certificate(&self) -> &[u8] {
+        &self.server_certificate
+    }
+}
+
+impl fmt::Debug for QuicSocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "QuicSocket({})", self.connection.remote_address())
+    }
+}
+
+impl AsyncRead for QuicSocket {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.recv).poll_read(context, buf)
+    }
+}
+
+impl AsyncWrite for QuicSocket {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.send).poll_write(context, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.send).poll_flush(context)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        let result = Pin::new(&mut self.send).poll_close(context);
+        if result.is_ready() {
+            self.connection.close(0u32.into(), b"");
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures::{
+        future::join,
+        io::{AsyncReadExt, AsyncWriteExt},
+    };
+
+    #[tokio::test]
+    async fn simple_listen_and_dial() -> io::Result<()> {
+        let t = QuicTransport::default();
+        let (listener, addr) = t.listen_on("/ip4/127.0.0.1/quic/0".parse().unwrap())?;
+        let dial = t.dial(PeerId::random(), addr)?;
+        let listener = listener.into_future().then(|(maybe_result, _stream)| {
+            let (incoming, _addr) = maybe_result.unwrap().unwrap();
+            incoming
+        });
+
+        let outbound = async move {
+            let mut socket = dial.await?;
+            // the control stream is only announced to the listener once written to
+            socket.write_all(b"Earth").await?;
+            let mut buf = [0; 3];
+            socket.read_exact(&mut buf).await?;
+            assert_eq!(&buf, b"Air");
+            Ok::<_, io::Error>(socket)
+        };
+        let inbound = async move {
+            let mut socket = listener.await?;
+            let mut buf = [0; 5];
+            socket.read_exact(&mut buf).await?;
+            assert_eq!(&buf, b"Earth");
+            socket.write_all(b"Air").await?;
+            Ok::<_, io::Error>(socket)
+        };
+
+        let (outbound, inbound) = join(outbound, inbound).await;
+        let (outbound, inbound) = (outbound?, inbound?);
+        assert_eq!(outbound.server_certificate(), inbound.server_certificate());
+        Ok(())
+    }
+
+    #[test]
+    fn unsupported_multiaddrs() {
+        let t = QuicTransport::default();
+
+        let result = t.listen_on("/ip4/127.0.0.1/tcp/0".parse().unwrap());
+        assert!(result.is_err());
+
+        let result = t.dial(PeerId::random(), "/memory/22".parse().unwrap());
+        assert!(result.is_err());
+    }
+}
@@ -116,7 +116,7 @@ impl Transport for TcpTransport {
 }
 
 /// Try to lookup the dns name, then filter addrs according to the `IpFilter`.
-fn resolve_with_filter<'a>(
+pub(crate) fn resolve_with_filter<'a>(
     ip_filter: IpFilter,
     dns_name: &'a str,
     port: u16,
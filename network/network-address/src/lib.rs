@@ -115,6 +115,7 @@ pub enum Protocol {
     // probably need to move network wire into its own crate to avoid circular
     // dependency b/w network and types.
     Handshake(u8),
+    Quic(u16),
 }
 
 /// A minimally parsed DNS name. We don't really do any checking other than
@@ -171,6 +172,9 @@ pub enum ParseError {
     #[error("dns name is too long: len: {0} bytes, max len: 255 bytes")]
     DnsNameTooLong(usize),
 
+    #[error("cidr block prefix length is too long: len: {0} bits, max len: {1} bits")]
+    CidrPrefixTooLong(u8, u8),
+
     #[error("error decrypting network address")]
     DecryptError,
 
@@ -472,6 +476,10 @@ pub fn arb_libranet_addr() -> impl Strategy<Value = NetworkAddress> {
             .prop_map(|(name, port)| vec![Protocol::Dns4(name), Protocol::Tcp(port)]),
         any::<(DnsName, u16)>()
             .prop_map(|(name, port)| vec![Protocol::Dns6(name), Protocol::Tcp(port)]),
+        any::<(Ipv4Addr, u16)>()
+            .prop_map(|(addr, port)| vec![Protocol::Ip4(addr), Protocol::Quic(port)]),
+        any::<(DnsName, u16)>()
+            .prop_map(|(name, port)| vec![Protocol::Dns(name), Protocol::Quic(port)]),
     ];
     let arb_libranet_protos = any::<(x25519::PublicKey, u8)>()
         .prop_map(|(pubkey, hs)| vec![Protocol::NoiseIK(pubkey), Protocol::Handshake(hs)]);
@@ -507,6 +515,7 @@ impl fmt::Display for Protocol {
                     .expect("ValidCryptoMaterialStringExt::to_encoded_string is infallible")
             ),
             Handshake(version) => write!(f, "/ln-handshake/{}", version),
+            Quic(port) => write!(f, "/quic/{}", port),
         }
     }
 }
@@ -537,6 +546,7 @@ impl Protocol {
                 args.next().ok_or(ParseError::UnexpectedEnd)?,
             )?),
             "ln-handshake" => Protocol::Handshake(parse_one(args)?),
+            "quic" => Protocol::Quic(parse_one(args)?),
             unknown => return Err(ParseError::UnknownProtocolType(unknown.to_string())),
         };
         Ok(protocol)
@@ -692,6 +702,108 @@ impl IpFilter {
     }
 }
 
+///////////////
+// CidrBlock //
+///////////////
+
+/// A block of IP addresses in CIDR notation, e.g. `10.0.0.0/8` or `2001:db8::/32`. A bare
+/// address is a block of that single address.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct CidrBlock {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl CidrBlock {
+    /// The block of the addresses sharing the first `prefix_len` bits of `addr`.
+    pub fn new(addr: IpAddr, prefix_len: u8) -> Result<Self, ParseError> {
+        let addr = match addr {
+            IpAddr::V4(ip) if prefix_len <= 32 => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(prefix_len))
+                    .unwrap_or(0);
+                IpAddr::V4(Ipv4Addr::from(u32::from(ip) & mask))
+            }
+            IpAddr::V6(ip) if prefix_len <= 128 => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(prefix_len))
+                    .unwrap_or(0);
+                IpAddr::V6(Ipv6Addr::from(u128::from(ip) & mask))
+            }
+            IpAddr::V4(_) => return Err(ParseError::CidrPrefixTooLong(prefix_len, 32)),
+            IpAddr::V6(_) => return Err(ParseError::CidrPrefixTooLong(prefix_len, 128)),
+        };
+        Ok(Self { addr, prefix_len })
+    }
+
+    pub fn addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    pub fn matches(&self, ipaddr: IpAddr) -> bool {
+        match (self.addr.is_ipv4(), canonical_ip(ipaddr)) {
+            (true, ipaddr @ IpAddr::V4(_)) | (false, ipaddr @ IpAddr::V6(_)) => {
+                CidrBlock::new(ipaddr, self.prefix_len).map_or(false, |block| block == *self)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// IPv4 addresses mapped to IPv6, as reported by dual-stack sockets, are IPv4 addresses.
+fn canonical_ip(ipaddr: IpAddr) -> IpAddr {
+    match ipaddr {
+        IpAddr::V6(ip) => match ip.segments() {
+            [0, 0, 0, 0, 0, 0xffff, ..] => IpAddr::V4(ip.to_ipv4().expect("mapped address")),
+            _ => ipaddr,
+        },
+        ipaddr => ipaddr,
+    }
+}
+
+impl FromStr for CidrBlock {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.find('/') {
+            Some(idx) => CidrBlock::new(s[..idx].parse()?, s[idx + 1..].parse()?),
+            None => {
+                let addr: IpAddr = s.parse()?;
+                CidrBlock::new(addr, if addr.is_ipv4() { 32 } else { 128 })
+            }
+        }
+    }
+}
+
+impl fmt::Display for CidrBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+impl Serialize for CidrBlock {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for CidrBlock {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = <String>::deserialize(deserializer)?;
+        CidrBlock::from_str(s.as_str()).map_err(de::Error::custom)
+    }
+}
+
 /// parse the `&[Protocol]` into the `"/dns/<domain>/tcp/<port>"`,
 /// `"/dns4/<domain>/tcp/<port>"`, or `"/dns6/<domain>/tcp/<port>"` prefix and
 /// unparsed `&[Protocol]` suffix.
@@ -711,6 +823,42 @@ pub fn parse_dns_tcp(protos: &[Protocol]) -> Option<((IpFilter, &DnsName, u16),
     }
 }
 
+/// parse the `&[Protocol]` into the `"/ip4/<addr>/quic/<port>"` or
+/// `"/ip6/<addr>/quic/<port>"` prefix and unparsed `&[Protocol]` suffix.
+pub fn parse_ip_quic(protos: &[Protocol]) -> Option<((IpAddr, u16), &[Protocol])> {
+    use Protocol::*;
+
+    if protos.len() < 2 {
+        return None;
+    }
+
+    let (prefix, suffix) = protos.split_at(2);
+    match prefix {
+        [Ip4(ip), Quic(port)] => Some(((IpAddr::V4(*ip), *port), suffix)),
+        [Ip6(ip), Quic(port)] => Some(((IpAddr::V6(*ip), *port), suffix)),
+        _ => None,
+    }
+}
+
+/// parse the `&[Protocol]` into the `"/dns/<domain>/quic/<port>"`,
+/// `"/dns4/<domain>/quic/<port>"`, or `"/dns6/<domain>/quic/<port>"` prefix and
+/// unparsed `&[Protocol]` suffix.
+pub fn parse_dns_quic(protos: &[Protocol]) -> Option<((IpFilter, &DnsName, u16), &[Protocol])> {
+    use Protocol::*;
+
+    if protos.len() < 2 {
+        return None;
+    }
+
+    let (prefix, suffix) = protos.split_at(2);
+    match prefix {
+        [Dns(name), Quic(port)] => Some(((IpFilter::Any, name, *port), suffix)),
+        [Dns4(name), Quic(port)] => Some(((IpFilter::OnlyIp4, name, *port), suffix)),
+        [Dns6(name), Quic(port)] => Some(((IpFilter::OnlyIp6, name, *port), suffix)),
+        _ => None,
+    }
+}
+
 /// parse the `&[Protocol]` into the `"/ln-noise-ik/<pubkey>"` prefix and
 /// unparsed `&[Protocol]` suffix.
 pub fn parse_noise_ik(protos: &[Protocol]) -> Option<(&x25519::PublicKey, &[Protocol])> {
@@ -737,11 +885,15 @@ fn parse_libranet_protos(protos: &[Protocol]) -> Option<&[Protocol]> {
     // ---
     // parse_ip_tcp
     // <or> parse_dns_tcp
+    // <or> parse_ip_quic
+    // <or> parse_dns_quic
     // <or> cfg!(test) parse_memory
 
     let transport_suffix = parse_ip_tcp(protos)
         .map(|x| x.1)
         .or_else(|| parse_dns_tcp(protos).map(|x| x.1))
+        .or_else(|| parse_ip_quic(protos).map(|x| x.1))
+        .or_else(|| parse_dns_quic(protos).map(|x| x.1))
         .or_else(|| {
             if cfg!(test) {
                 parse_memory(protos).map(|x| x.1)
@@ -845,6 +997,32 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_cidr_block() {
+        let block = CidrBlock::from_str("10.1.2.3/16").unwrap();
+        assert_eq!(block.to_string(), "10.1.0.0/16");
+        assert!(block.matches("10.1.255.1".parse().unwrap()));
+        assert!(block.matches("::ffff:10.1.0.1".parse().unwrap()));
+        assert!(!block.matches("10.2.0.1".parse().unwrap()));
+        assert!(!block.matches("::1".parse().unwrap()));
+
+        let block = CidrBlock::from_str("2001:db8::/32").unwrap();
+        assert!(block.matches("2001:db8:1::1".parse().unwrap()));
+        assert!(!block.matches("2001:db9::1".parse().unwrap()));
+
+        let block = CidrBlock::from_str("127.0.0.1").unwrap();
+        assert_eq!(block.prefix_len(), 32);
+        assert!(block.matches("127.0.0.1".parse().unwrap()));
+        assert!(!block.matches("127.0.0.2".parse().unwrap()));
+        assert!(CidrBlock::from_str("0.0.0.0/0")
+            .unwrap()
+            .matches("1.2.3.4".parse().unwrap()));
+
+        assert!(CidrBlock::from_str("10.0.0.0/33").is_err());
+        assert!(CidrBlock::from_str("10.0.0.0/").is_err());
+        assert!(CidrBlock::from_str("example.com/8").is_err());
+    }
+
     #[test]
     fn test_network_address_parse_fail() {
         let test_cases = [
@@ -954,6 +1132,40 @@ mod test {
         assert_eq!(None, parse_dns_tcp(addr.as_slice()));
     }
 
+    #[test]
+    fn test_parse_ip_quic() {
+        let addr = NetworkAddress::from_str("/ip4/1.2.3.4/quic/123").unwrap();
+        let expected_suffix: &[Protocol] = &[];
+        assert_eq!(
+            parse_ip_quic(addr.as_slice()).unwrap(),
+            ((IpAddr::from_str("1.2.3.4").unwrap(), 123), expected_suffix)
+        );
+
+        let addr = NetworkAddress::from_str("/ip6/::1/quic/123/memory/999").unwrap();
+        let expected_suffix: &[Protocol] = &[Protocol::Memory(999)];
+        assert_eq!(
+            parse_ip_quic(addr.as_slice()).unwrap(),
+            ((IpAddr::from_str("::1").unwrap(), 123), expected_suffix)
+        );
+
+        let addr = NetworkAddress::from_str("/ip4/1.2.3.4/tcp/123").unwrap();
+        assert_eq!(None, parse_ip_quic(addr.as_slice()));
+    }
+
+    #[test]
+    fn test_parse_dns_quic() {
+        let dns_name = DnsName::from_str("example.com").unwrap();
+        let addr = NetworkAddress::from_str("/dns4/example.com/quic/123").unwrap();
+        let expected_suffix: &[Protocol] = &[];
+        assert_eq!(
+            parse_dns_quic(addr.as_slice()).unwrap(),
+            ((IpFilter::OnlyIp4, &dns_name, 123), expected_suffix)
+        );
+
+        let addr = NetworkAddress::from_str("/dns/example.com/tcp/123").unwrap();
+        assert_eq!(None, parse_dns_quic(addr.as_slice()));
+    }
+
     #[test]
     fn test_parse_noise_ik() {
         let pubkey_str = "080e287879c918794170e258bfaddd75acac5b3e350419044655e4983a487120";
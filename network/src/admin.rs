@@ -0,0 +1,150 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Runtime administration of a network: operators can list the connected and eligible peers,
+//! and dial, disconnect or temporarily ban a peer without restarting the node. Tests can also
+//! change the faults injected in the connections, when fault injection is enabled.
+
+use crate::{
+    peer_manager::{ConnectionRequestSender, PeerManagerError},
+    protocols::health_checker::{PeerHealth, PeerHealthMap},
+    transport::{fault_injection::NetworkFaults, ConnectionMetadata},
+    ProtocolId,
+};
+use libra_config::network_id::NetworkContext;
+use libra_crypto::x25519;
+use libra_infallible::RwLock;
+use libra_network_address::NetworkAddress;
+use libra_types::PeerId;
+use serde::Serialize;
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryInto,
+    fmt,
+    sync::Arc,
+    time::Duration,
+};
+
+/// A connected peer, as reported to operators.
+#[derive(Serialize)]
+pub struct PeerInfo {
+    pub metadata: ConnectionMetadata,
+    /// Application protocols negotiated with the peer
+    pub protocols: Vec<ProtocolId>,
+    /// `None` when the network doesn't run the HealthChecker
+    pub health: Option<PeerHealth>,
+}
+
+#[derive(Serialize)]
+pub struct NetworkStatus {
+    pub network_context: NetworkContext,
+    pub connected_peers: Vec<PeerInfo>,
+    /// Peers allowed to connect on mutually authenticated networks
+    pub eligible_peers: Vec<PeerId>,
+}
+
+/// Handle to administer a running network.
+#[derive(Clone)]
+pub struct NetworkAdmin {
+    network_context: Arc<NetworkContext>,
+    connection_reqs_tx: ConnectionRequestSender,
+    eligible_peers: Arc<RwLock<HashMap<PeerId, HashSet<x25519::PublicKey>>>>,
+    peer_health: Option<PeerHealthMap>,
+    network_faults: Option<NetworkFaults>,
+}
+
+impl fmt::Debug for NetworkAdmin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "NetworkAdmin({})", self.network_context)
+    }
+}
+
+impl NetworkAdmin {
+    pub fn new(
+        network_context: Arc<NetworkContext>,
+        connection_reqs_tx: ConnectionRequestSender,
+        eligible_peers: Arc<RwLock<HashMap<PeerId, HashSet<x25519::PublicKey>>>>,
+        peer_health: Option<PeerHealthMap>,
+        network_faults: Option<NetworkFaults>,
+    ) -> Self {
+        Self {
+            network_context,
+            connection_reqs_tx,
+            eligible_peers,
+            peer_health,
+            network_faults,
+        }
+    }
+
+    pub fn network_context(&self) -> &NetworkContext {
+        &self.network_context
+    }
+
+    /// The faults injected in the connections, `None` when fault injection is not enabled.
+    pub fn network_faults(&self) -> Option<&NetworkFaults> {
+        self.network_faults.as_ref()
+    }
+
+    pub async fn status(&self) -> Result<NetworkStatus, PeerManagerError> {
+        let connections = self
+            .connection_reqs_tx
+            .clone()
+            .get_connected_peers()
+            .await?;
+        let connected_peers = connections
+            .into_iter()
+            .map(|metadata| {
+                let health = self.peer_health.as_ref().and_then(|peer_health| {
+                    peer_health.read().get(&metadata.remote_peer_id).copied()
+                });
+                let protocols = metadata
+                    .application_protocols
+                    .clone()
+                    .try_into()
+                    .unwrap_or_default();
+                PeerInfo {
+                    metadata,
+                    protocols,
+                    health,
+                }
+            })
+            .collect();
+        let mut eligible_peers: Vec<_> = self.eligible_peers.read().keys().copied().collect();
+        eligible_peers.sort();
+        Ok(NetworkStatus {
+            network_context: self.network_context.as_ref().clone(),
+            connected_peers,
+            eligible_peers,
+        })
+    }
+
+    pub async fn dial_peer(
+        &self,
+        peer_id: PeerId,
+        addr: NetworkAddress,
+    ) -> Result<(), PeerManagerError> {
+        self.connection_reqs_tx
+            .clone()
+            .dial_peer(peer_id, addr)
+            .await
+    }
+
+    pub async fn disconnect_peer(&self, peer_id: PeerId) -> Result<(), PeerManagerError> {
+        self.connection_reqs_tx
+            .clone()
+            .disconnect_peer(peer_id)
+            .await
+    }
+
+    /// Disconnects from the peer and refuses connections with it for `duration`.
+    pub async fn ban_peer(
+        &self,
+        peer_id: PeerId,
+        duration: Duration,
+    ) -> Result<(), PeerManagerError> {
+        self.connection_reqs_tx
+            .clone()
+            .ban_peer(peer_id, duration)
+            .await
+    }
+}
@@ -106,6 +106,8 @@ pub struct ConnectivityManager<TTicker, TBackoff> {
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, NumVariants, Serialize)]
 pub enum DiscoverySource {
     OnChain,
+    File,
+    Dns,
     Config,
 }
 
@@ -122,6 +124,8 @@ impl fmt::Display for DiscoverySource {
             "{}",
             match self {
                 DiscoverySource::OnChain => "OnChain",
+                DiscoverySource::File => "File",
+                DiscoverySource::Dns => "Dns",
                 DiscoverySource::Config => "Config",
             }
         )
@@ -110,6 +110,27 @@ pub fn pending_connection_upgrades(
     ])
 }
 
+pub static LIBRA_NETWORK_INBOUND_CONNECTIONS_REFUSED: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "libra_network_inbound_connections_refused",
+        "Number of inbound connections refused by admission control, by reason",
+        &["role_type", "network_id", "peer_id", "reason"]
+    )
+    .unwrap()
+});
+
+pub fn inbound_connections_refused(
+    network_context: &NetworkContext,
+    reason: &'static str,
+) -> IntCounter {
+    LIBRA_NETWORK_INBOUND_CONNECTIONS_REFUSED.with_label_values(&[
+        network_context.role().as_str(),
+        network_context.network_id().as_str(),
+        network_context.peer_id().short_str().as_str(),
+        reason,
+    ])
+}
+
 pub static LIBRA_NETWORK_CONNECTION_UPGRADE_TIME: Lazy<HistogramVec> = Lazy::new(|| {
     register_histogram_vec!(
         "libra_network_connection_upgrade_time_seconds",
@@ -290,6 +311,69 @@ pub fn direct_send_bytes(
     ])
 }
 
+pub static LIBRA_NETWORK_OUTBOUND_PROTOCOL_BYTES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "libra_network_outbound_protocol_bytes",
+        "Number of bytes written on the wire, by protocol",
+        &["role_type", "network_id", "peer_id", "protocol_id"]
+    )
+    .unwrap()
+});
+
+pub fn outbound_protocol_bytes(
+    network_context: &NetworkContext,
+    protocol_label: &'static str,
+) -> IntCounter {
+    LIBRA_NETWORK_OUTBOUND_PROTOCOL_BYTES.with_label_values(&[
+        network_context.role().as_str(),
+        network_context.network_id().as_str(),
+        network_context.peer_id().short_str().as_str(),
+        protocol_label,
+    ])
+}
+
+pub static LIBRA_NETWORK_OUTBOUND_PROTOCOL_THROTTLED: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "libra_network_outbound_protocol_throttled",
+        "Number of times outbound messages were held back by the byte-rate limit of their protocol",
+        &["role_type", "network_id", "peer_id", "protocol_id"]
+    )
+    .unwrap()
+});
+
+pub fn outbound_protocol_throttled(
+    network_context: &NetworkContext,
+    protocol_label: &'static str,
+) -> IntCounter {
+    LIBRA_NETWORK_OUTBOUND_PROTOCOL_THROTTLED.with_label_values(&[
+        network_context.role().as_str(),
+        network_context.network_id().as_str(),
+        network_context.peer_id().short_str().as_str(),
+        protocol_label,
+    ])
+}
+
+pub static LIBRA_NETWORK_OUTBOUND_PROTOCOL_DROPPED: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "libra_network_outbound_protocol_dropped",
+        "Number of outbound messages dropped because the queue of their protocol was full",
+        &["role_type", "network_id", "peer_id", "protocol_id"]
+    )
+    .unwrap()
+});
+
+pub fn outbound_protocol_dropped(
+    network_context: &NetworkContext,
+    protocol_label: &'static str,
+) -> IntCounter {
+    LIBRA_NETWORK_OUTBOUND_PROTOCOL_DROPPED.with_label_values(&[
+        network_context.role().as_str(),
+        network_context.network_id().as_str(),
+        network_context.peer_id().short_str().as_str(),
+        protocol_label,
+    ])
+}
+
 /// Counters(queued,dequeued,dropped) related to inbound network notifications for RPCs and
 /// DirectSends.
 pub static PENDING_NETWORK_NOTIFICATIONS: Lazy<IntCounterVec> = Lazy::new(|| {
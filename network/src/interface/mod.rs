@@ -14,7 +14,7 @@
 use crate::{
     constants, counters,
     logging::NetworkSchema,
-    peer::{Peer, PeerHandle, PeerNotification},
+    peer::{Peer, PeerHandle, PeerNotification, ProtocolQuotas},
     peer_manager::TransportNotification,
     protocols::{
         direct_send::{DirectSend, DirectSendNotification, DirectSendRequest, Message},
@@ -73,6 +73,7 @@ where
         max_concurrent_notifs: usize,
         channel_size: usize,
         max_frame_size: usize,
+        protocol_quotas: ProtocolQuotas,
     ) -> (
         libra_channel::Sender<ProtocolId, NetworkRequest>,
         libra_channel::Receiver<ProtocolId, NetworkNotification>,
@@ -104,6 +105,7 @@ where
             peer_rpc_notifs_tx,
             peer_ds_notifs_tx,
             max_frame_size,
+            protocol_quotas,
         );
         executor.spawn(peer.start());
 
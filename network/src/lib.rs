@@ -8,6 +8,7 @@
 
 pub use interface::NetworkProvider;
 
+pub mod admin;
 pub mod connectivity_manager;
 pub mod constants;
 pub mod error;
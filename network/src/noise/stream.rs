@@ -61,6 +61,17 @@ impl<TSocket> NoiseStream<TSocket> {
     pub fn get_remote_static(&self) -> x25519::PublicKey {
         self.session.get_remote_static()
     }
+
+    /// Get a reference to the underlying socket
+    pub fn get_ref(&self) -> &TSocket {
+        &self.socket
+    }
+
+    /// Get a mutable reference to the underlying socket. Reading from or writing to it directly
+    /// would corrupt the noise stream.
+    pub fn get_mut(&mut self) -> &mut TSocket {
+        &mut self.socket
+    }
 }
 
 //
@@ -8,7 +8,7 @@ use crate::{
     counters::inc_by_with_context,
     logging::NetworkSchema,
     peer_manager::PeerManagerError,
-    protocols::wire::messaging::v1::{ErrorCode, NetworkMessage},
+    protocols::wire::messaging::v1::{compression, ErrorCode, NetworkMessage},
     transport,
     transport::{Connection, ConnectionMetadata},
     ProtocolId,
@@ -17,6 +17,7 @@ use bytes::BytesMut;
 use futures::{
     self,
     channel::oneshot,
+    future,
     io::{AsyncRead, AsyncWrite},
     stream::StreamExt,
     FutureExt, SinkExt, TryFutureExt,
@@ -25,8 +26,14 @@ use libra_config::network_id::NetworkContext;
 use libra_logger::prelude::*;
 use libra_types::PeerId;
 use netcore::compat::IoCompat;
+use outbound_queue::{protocol_label, Dequeued, OutboundMessage, OutboundQueue};
 use serde::{export::Formatter, Serialize};
-use std::{fmt::Debug, io, sync::Arc, time::Duration};
+use std::{
+    fmt::Debug,
+    io,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use stream_ratelimiter::*;
 use tokio::runtime::Handle;
 use tokio_util::codec::{FramedRead, FramedWrite, LengthDelimitedCodec};
@@ -35,9 +42,23 @@ use tokio_util::codec::{FramedRead, FramedWrite, LengthDelimitedCodec};
 pub const MESSAGE_RATE_LIMIT_WINDOW: Duration = Duration::from_millis(10);
 pub const MESSAGE_RATE_LIMIT_COUNT: usize = 100;
 
+pub mod outbound_queue;
 #[cfg(test)]
 mod test;
 
+pub use outbound_queue::ProtocolQuotas;
+
+/// Maximum number of outbound messages of each protocol scheduled by the writer of a connection.
+const MAX_QUEUED_OUTBOUND_MESSAGES: usize = 1024;
+
+/// A message to be written on the wire, with the protocol it belongs to (`None` for errors) and
+/// the channel to notify once it is written.
+type WriteRequest = (
+    NetworkMessage,
+    Option<ProtocolId>,
+    oneshot::Sender<Result<(), PeerManagerError>>,
+);
+
 #[derive(Debug)]
 pub enum PeerRequest {
     SendMessage(
@@ -100,6 +121,8 @@ pub struct Peer<TSocket> {
     /// The maximum size of an inbound or outbound request frame
     /// Currently, requests are only a single frame
     max_frame_size: usize,
+    /// Weights and rate limits of the protocols in the outbound path.
+    protocol_quotas: ProtocolQuotas,
 }
 
 impl<TSocket> Peer<TSocket>
@@ -115,6 +138,7 @@ where
         rpc_notifs_tx: channel::Sender<PeerNotification>,
         direct_send_notifs_tx: channel::Sender<PeerNotification>,
         max_frame_size: usize,
+        protocol_quotas: ProtocolQuotas,
     ) -> Self {
         let Connection {
             metadata: connection_metadata,
@@ -131,6 +155,7 @@ where
             direct_send_notifs_tx,
             state: State::Connected,
             max_frame_size,
+            protocol_quotas,
         }
     }
 
@@ -174,6 +199,7 @@ where
             self.connection_metadata.clone(),
             self.network_context.clone(),
             writer,
+            self.protocol_quotas.clone(),
         );
         // Start main Peer event loop.
         loop {
@@ -283,41 +309,60 @@ where
     // 2. The second channel is used to instruct the task to close the connection and terminate.
     // If outbound messages are queued when the task receives a close instruction, it discards
     // them and immediately closes the connection.
+    // Outbound messages are scheduled by an `OutboundQueue`, which shares the bandwidth between
+    // protocols according to their quotas.
     fn start_writer_task<T: tokio::io::AsyncWrite + Send + Unpin + 'static>(
         executor: &Handle,
         connection_metadata: ConnectionMetadata,
         network_context: Arc<NetworkContext>,
         mut writer: FramedWrite<T, LengthDelimitedCodec>,
-    ) -> (
-        channel::Sender<(
-            NetworkMessage,
-            oneshot::Sender<Result<(), PeerManagerError>>,
-        )>,
-        oneshot::Sender<()>,
-    ) {
+        protocol_quotas: ProtocolQuotas,
+    ) -> (channel::Sender<WriteRequest>, oneshot::Sender<()>) {
         let remote_peer_id = connection_metadata.remote_peer_id;
-        let (write_reqs_tx, mut write_reqs_rx): (
-            channel::Sender<(
-                NetworkMessage,
-                oneshot::Sender<Result<(), PeerManagerError>>,
-            )>,
-            _,
-        ) = channel::new(1024, &counters::PENDING_WIRE_MESSAGES);
+        let (write_reqs_tx, mut write_reqs_rx): (channel::Sender<WriteRequest>, _) =
+            channel::new(1024, &counters::PENDING_WIRE_MESSAGES);
         let (close_tx, close_rx) = oneshot::channel();
         let writer_task = async move {
             let mut close_rx = close_rx.into_stream();
+            let mut queue = OutboundQueue::new(protocol_quotas, MAX_QUEUED_OUTBOUND_MESSAGES);
+            let enqueue = |queue: &mut OutboundQueue, (message, protocol, ack_ch): WriteRequest| {
+                let bytes = lcs::to_bytes(&message)
+                    .expect("Outbound message failed to serialize")
+                    .into();
+                let queued = queue.push(
+                    OutboundMessage {
+                        protocol,
+                        bytes,
+                        ack_ch,
+                    },
+                    Instant::now(),
+                );
+                if !queued {
+                    counters::outbound_protocol_dropped(&network_context, protocol_label(protocol))
+                        .inc();
+                }
+            };
             loop {
-                futures::select! {
-                    (message, ack_ch) = write_reqs_rx.select_next_some() => {
-                        if let Err(e) = writer
-                            .send(
-                                lcs::to_bytes(&message)
-                                    .expect("Outbound message failed to serialize")
-                                    .into(),
-                            )
-                            .map_ok(|_| ack_ch.send(Ok(())))
-                            .await
-                        {
+                // Move all pending requests to the queue, so that they are scheduled together.
+                while let Some(Some(request)) = write_reqs_rx.next().now_or_never() {
+                    enqueue(&mut queue, request);
+                }
+
+                let throttled_until = match queue.pop(Instant::now(), |protocol| {
+                    counters::outbound_protocol_throttled(
+                        &network_context,
+                        protocol_label(protocol),
+                    )
+                    .inc()
+                }) {
+                    Dequeued::Message(message) => {
+                        let OutboundMessage {
+                            protocol,
+                            bytes,
+                            ack_ch,
+                        } = message;
+                        let num_bytes = bytes.len();
+                        if let Err(e) = writer.send(bytes).map_ok(|_| ack_ch.send(Ok(()))).await {
                             warn!(
                                 NetworkSchema::new(&network_context)
                                     .connection_metadata(&connection_metadata),
@@ -329,7 +374,30 @@ where
                             );
                             break;
                         }
-                    },
+                        counters::outbound_protocol_bytes(
+                            &network_context,
+                            protocol_label(protocol),
+                        )
+                        .inc_by(num_bytes as i64);
+                        continue;
+                    }
+                    Dequeued::Throttled(until) => Some(until),
+                    Dequeued::Empty => None,
+                };
+
+                // Wait for a new request, or for the rate limits to allow sending again.
+                let next_request = write_reqs_rx.select_next_some();
+                let throttle_delay = async move {
+                    match throttled_until {
+                        Some(until) => tokio::time::delay_until(until.into()).await,
+                        None => future::pending().await,
+                    }
+                }
+                .fuse();
+                futures::pin_mut!(next_request, throttle_delay);
+                futures::select! {
+                    request = next_request => enqueue(&mut queue, request),
+                    _ = throttle_delay => {},
                     _ = close_rx.select_next_some() => {
                         break;
                     }
@@ -382,13 +450,18 @@ where
         (write_reqs_tx, close_tx)
     }
 
+    /// Whether both peers advertised the compression capability during the handshake, in which
+    /// case all message payloads are encoded with a compression marker.
+    fn compression_enabled(&self) -> bool {
+        self.connection_metadata
+            .application_protocols
+            .supports_compression()
+    }
+
     async fn handle_inbound_message(
         &mut self,
         message: BytesMut,
-        mut write_reqs_tx: channel::Sender<(
-            NetworkMessage,
-            oneshot::Sender<Result<(), PeerManagerError>>,
-        )>,
+        mut write_reqs_tx: channel::Sender<WriteRequest>,
     ) -> Result<(), PeerManagerError> {
         trace!(
             NetworkSchema::new(&self.network_context)
@@ -407,11 +480,17 @@ where
                     let error = ErrorCode::parsing_error(message[0], message[1]);
                     let message = NetworkMessage::Error(error);
                     let (ack_tx, _) = oneshot::channel();
-                    write_reqs_tx.send((message, ack_tx)).await?;
+                    write_reqs_tx.send((message, None, ack_tx)).await?;
                 }
                 return Err(err.into());
             }
         };
+        let message = if self.compression_enabled() {
+            compression::decode_message(message, self.max_frame_size)
+                .map_err(|err| PeerManagerError::Error(err.into()))?
+        } else {
+            message
+        };
         match message {
             NetworkMessage::DirectSendMsg(_) => {
                 let notif = PeerNotification::NewMessage(message);
@@ -462,10 +541,7 @@ where
     async fn handle_request<'a>(
         &'a mut self,
         request: PeerRequest,
-        mut write_reqs_tx: channel::Sender<(
-            NetworkMessage,
-            oneshot::Sender<Result<(), PeerManagerError>>,
-        )>,
+        mut write_reqs_tx: channel::Sender<WriteRequest>,
     ) {
         trace!(
             "Peer {} PeerRequest::{:?}",
@@ -474,7 +550,12 @@ where
         );
         match request {
             PeerRequest::SendMessage(message, protocol, channel) => {
-                if let Err(e) = write_reqs_tx.send((message, channel)).await {
+                let message = if self.compression_enabled() {
+                    compression::encode_message(message, protocol.is_compressible())
+                } else {
+                    message
+                };
+                if let Err(e) = write_reqs_tx.send((message, Some(protocol), channel)).await {
                     inc_by_with_context(
                         &counters::PEER_SEND_FAILURES,
                         &self.network_context,
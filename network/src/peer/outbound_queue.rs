@@ -0,0 +1,328 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Scheduling of the outbound messages of a connection.
+//!
+//! Messages are queued per protocol and dequeued with weighted fair queueing (self-clocked
+//! variant): each message gets a virtual finish time of `size / weight` after the previous
+//! message of its protocol, and the message with the earliest finish time goes first. A
+//! protocol with twice the weight of another thus gets twice its share of the bandwidth when
+//! both are backlogged, and small consensus messages don't wait behind large state sync chunks.
+//!
+//! On top of that, a protocol can be limited to a number of bytes per second with a token
+//! bucket. A message is sent as long as the bucket is not in deficit, so messages larger than
+//! the rate are not blocked forever.
+//!
+//! The number of messages queued is capped per protocol, so that a throttled or backlogged
+//! protocol can't keep the messages of the others out of the queue.
+
+use crate::{peer_manager::PeerManagerError, ProtocolId};
+use anyhow::anyhow;
+use bytes::Bytes;
+use futures::channel::oneshot;
+use libra_config::config::ProtocolQuotaConfig;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Quotas of the protocols, protocols not listed get the default quota.
+pub type ProtocolQuotas = Arc<HashMap<ProtocolId, ProtocolQuotaConfig>>;
+
+/// A serialized message waiting to be written on the wire.
+pub struct OutboundMessage {
+    /// `None` for messages which don't belong to a protocol, e.g. errors.
+    pub protocol: Option<ProtocolId>,
+    pub bytes: Bytes,
+    pub ack_ch: oneshot::Sender<Result<(), PeerManagerError>>,
+}
+
+pub enum Dequeued {
+    Message(OutboundMessage),
+    /// All queued messages are held back by rate limits until the given time.
+    Throttled(Instant),
+    Empty,
+}
+
+/// Label of a protocol in the counters.
+pub fn protocol_label(protocol: Option<ProtocolId>) -> &'static str {
+    protocol.map_or("other", ProtocolId::as_str)
+}
+
+struct TokenBucket {
+    bytes_per_sec: f64,
+    /// Negative when more bytes than allowed were sent
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(bytes_per_sec: u64, now: Instant) -> Self {
+        let bytes_per_sec = bytes_per_sec.max(1) as f64;
+        Self {
+            bytes_per_sec,
+            // allow a burst of one second
+            tokens: bytes_per_sec,
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.tokens =
+            (self.tokens + elapsed.as_secs_f64() * self.bytes_per_sec).min(self.bytes_per_sec);
+        self.last_refill = now;
+    }
+
+    /// When the bucket gets out of deficit, if it is in deficit.
+    fn ready_at(&self) -> Option<Instant> {
+        if self.tokens >= 0.0 {
+            None
+        } else {
+            Some(self.last_refill + Duration::from_secs_f64(-self.tokens / self.bytes_per_sec))
+        }
+    }
+}
+
+struct ProtocolQueue {
+    /// Messages with their virtual finish time
+    messages: VecDeque<(f64, OutboundMessage)>,
+    weight: f64,
+    /// Virtual finish time of the last message queued
+    last_finish: f64,
+    rate_limit: Option<TokenBucket>,
+    /// Whether the head of the queue is held back by the rate limit, to count throttling once
+    /// per message
+    throttled: bool,
+}
+
+pub struct OutboundQueue {
+    quotas: ProtocolQuotas,
+    queues: HashMap<Option<ProtocolId>, ProtocolQueue>,
+    /// Virtual finish time of the last message dequeued
+    virtual_time: f64,
+    /// Maximum number of messages queued for each protocol
+    max_protocol_len: usize,
+}
+
+impl OutboundQueue {
+    pub fn new(quotas: ProtocolQuotas, max_protocol_len: usize) -> Self {
+        Self {
+            quotas,
+            queues: HashMap::new(),
+            virtual_time: 0.0,
+            max_protocol_len,
+        }
+    }
+
+    /// Queues the message, unless the queue of its protocol is full. Dropped messages are
+    /// acknowledged with an error and `false` is returned.
+    pub fn push(&mut self, message: OutboundMessage, now: Instant) -> bool {
+        let quotas = &self.quotas;
+        let protocol = message.protocol;
+        let queue = self.queues.entry(protocol).or_insert_with(|| {
+            let quota = protocol
+                .and_then(|protocol| quotas.get(&protocol).copied())
+                .unwrap_or_default();
+            ProtocolQueue {
+                messages: VecDeque::new(),
+                weight: quota.weight.max(1) as f64,
+                last_finish: 0.0,
+                rate_limit: quota
+                    .max_bytes_per_sec
+                    .map(|bytes_per_sec| TokenBucket::new(bytes_per_sec, now)),
+                throttled: false,
+            }
+        });
+        if queue.messages.len() >= self.max_protocol_len {
+            let _ = message.ack_ch.send(Err(PeerManagerError::Error(anyhow!(
+                "Outbound queue of protocol {} is full",
+                protocol_label(protocol)
+            ))));
+            return false;
+        }
+        let start = queue.last_finish.max(self.virtual_time);
+        let finish = start + message.bytes.len() as f64 / queue.weight;
+        queue.last_finish = finish;
+        queue.messages.push_back((finish, message));
+        true
+    }
+
+    /// Dequeues the message with the earliest virtual finish time among the protocols which are
+    /// not over their rate limit. Calls `on_throttled` for the protocols whose next message
+    /// starts being held back by their rate limit.
+    pub fn pop(
+        &mut self,
+        now: Instant,
+        mut on_throttled: impl FnMut(Option<ProtocolId>),
+    ) -> Dequeued {
+        let mut next: Option<(f64, Option<ProtocolId>)> = None;
+        let mut throttled_until: Option<Instant> = None;
+        for (protocol, queue) in self.queues.iter_mut() {
+            let finish = match queue.messages.front() {
+                Some((finish, _)) => *finish,
+                None => continue,
+            };
+            if let Some(rate_limit) = queue.rate_limit.as_mut() {
+                rate_limit.refill(now);
+                if let Some(ready_at) = rate_limit.ready_at() {
+                    if !queue.throttled {
+                        queue.throttled = true;
+                        on_throttled(*protocol);
+                    }
+                    throttled_until = Some(throttled_until.map_or(ready_at, |t| t.min(ready_at)));
+                    continue;
+                }
+            }
+            if next.map_or(true, |(next_finish, _)| finish < next_finish) {
+                next = Some((finish, *protocol));
+            }
+        }
+
+        match (next, throttled_until) {
+            (Some((finish, protocol)), _) => {
+                let queue = self
+                    .queues
+                    .get_mut(&protocol)
+                    .expect("protocol queue must exist");
+                let (_, message) = queue
+                    .messages
+                    .pop_front()
+                    .expect("protocol queue must not be empty");
+                queue.throttled = false;
+                if let Some(rate_limit) = queue.rate_limit.as_mut() {
+                    rate_limit.tokens -= message.bytes.len() as f64;
+                }
+                self.virtual_time = finish;
+                Dequeued::Message(message)
+            }
+            (None, Some(until)) => Dequeued::Throttled(until),
+            (None, None) => Dequeued::Empty,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn message(protocol: ProtocolId, size: usize) -> OutboundMessage {
+        message_with_ack(protocol, size).0
+    }
+
+    fn message_with_ack(
+        protocol: ProtocolId,
+        size: usize,
+    ) -> (
+        OutboundMessage,
+        oneshot::Receiver<Result<(), PeerManagerError>>,
+    ) {
+        let (ack_ch, ack_rx) = oneshot::channel();
+        let message = OutboundMessage {
+            protocol: Some(protocol),
+            bytes: vec![0; size].into(),
+            ack_ch,
+        };
+        (message, ack_rx)
+    }
+
+    fn pop_protocol(queue: &mut OutboundQueue, now: Instant) -> Option<ProtocolId> {
+        match queue.pop(now, |_| {}) {
+            Dequeued::Message(message) => message.protocol,
+            _ => panic!("expected a message"),
+        }
+    }
+
+    #[test]
+    fn test_weighted_fair_queueing() {
+        let quotas: HashMap<_, _> = vec![(
+            ProtocolId::ConsensusRpc,
+            ProtocolQuotaConfig {
+                weight: 4,
+                max_bytes_per_sec: None,
+            },
+        )]
+        .into_iter()
+        .collect();
+        let mut queue = OutboundQueue::new(Arc::new(quotas), 100);
+        let now = Instant::now();
+
+        // a backlog of state sync chunks doesn't delay consensus messages queued later
+        for _ in 0..4 {
+            queue.push(message(ProtocolId::StateSynchronizerDirectSend, 1000), now);
+        }
+        queue.push(message(ProtocolId::ConsensusRpc, 1000), now);
+        assert_eq!(
+            pop_protocol(&mut queue, now),
+            Some(ProtocolId::ConsensusRpc)
+        );
+
+        // with both backlogged, consensus gets 4 times the bandwidth
+        for _ in 0..8 {
+            queue.push(message(ProtocolId::ConsensusRpc, 1000), now);
+        }
+        let sent: Vec<_> = (0..10).map(|_| pop_protocol(&mut queue, now)).collect();
+        let consensus_sent = sent
+            .iter()
+            .filter(|protocol| **protocol == Some(ProtocolId::ConsensusRpc))
+            .count();
+        assert_eq!(consensus_sent, 8);
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let quotas: HashMap<_, _> = vec![(
+            ProtocolId::MempoolDirectSend,
+            ProtocolQuotaConfig {
+                weight: 1,
+                max_bytes_per_sec: Some(1000),
+            },
+        )]
+        .into_iter()
+        .collect();
+        let mut queue = OutboundQueue::new(Arc::new(quotas), 2);
+        let now = Instant::now();
+
+        assert!(queue.push(message(ProtocolId::MempoolDirectSend, 1500), now));
+        assert!(queue.push(message(ProtocolId::MempoolDirectSend, 100), now));
+
+        // the burst allows the first message, which puts the bucket in a deficit of 500 bytes
+        assert_eq!(
+            pop_protocol(&mut queue, now),
+            Some(ProtocolId::MempoolDirectSend)
+        );
+        let mut throttled = vec![];
+        match queue.pop(now, |protocol| throttled.push(protocol)) {
+            Dequeued::Throttled(until) => assert_eq!(until, now + Duration::from_millis(500)),
+            _ => panic!("expected the protocol to be throttled"),
+        }
+        assert_eq!(throttled, vec![Some(ProtocolId::MempoolDirectSend)]);
+
+        // other protocols are not affected, even with the throttled protocol's queue full
+        let (dropped, mut dropped_ack) = message_with_ack(ProtocolId::MempoolDirectSend, 100);
+        assert!(queue.push(message(ProtocolId::MempoolDirectSend, 100), now));
+        assert!(!queue.push(dropped, now));
+        assert!(dropped_ack.try_recv().unwrap().unwrap().is_err());
+        assert!(queue.push(message(ProtocolId::ConsensusRpc, 100), now));
+        assert_eq!(
+            pop_protocol(&mut queue, now),
+            Some(ProtocolId::ConsensusRpc)
+        );
+
+        let later = now + Duration::from_millis(500);
+        assert_eq!(
+            pop_protocol(&mut queue, later),
+            Some(ProtocolId::MempoolDirectSend)
+        );
+        let even_later = later + Duration::from_millis(100);
+        assert_eq!(
+            pop_protocol(&mut queue, even_later),
+            Some(ProtocolId::MempoolDirectSend)
+        );
+        match queue.pop(even_later, |_| {}) {
+            Dequeued::Empty => (),
+            _ => panic!("expected an empty queue"),
+        }
+    }
+}
@@ -17,7 +17,7 @@ use libra_network_address::NetworkAddress;
 use libra_types::PeerId;
 use memsocket::MemorySocket;
 use netcore::{compat::IoCompat, transport::ConnectionOrigin};
-use std::{mem::ManuallyDrop, str::FromStr, time::Duration};
+use std::{collections::HashMap, mem::ManuallyDrop, str::FromStr, sync::Arc, time::Duration};
 use tokio::{
     runtime::{Handle, Runtime},
     time::timeout,
@@ -65,6 +65,7 @@ fn build_test_peer(
         peer_rpc_notifs_tx,
         peer_direct_send_notifs_tx,
         constants::MAX_FRAME_SIZE,
+        Arc::new(HashMap::new()),
     );
     let peer_handle = PeerHandle::new(NetworkContext::mock(), connection_metadata, peer_req_tx);
 
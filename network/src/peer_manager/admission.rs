@@ -0,0 +1,306 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Admission control of inbound connections by source IP address.
+//!
+//! Before an inbound connection is upgraded, its source address must pass the allow and deny
+//! lists, and must not exceed the connection limits of its IP and of its subnet. A connection
+//! counts against the limits from the moment it's accepted until it's closed, so connections
+//! still being upgraded count too.
+//!
+//! If enabled, an address whose handshakes (Noise and protocol handshakes) fail
+//! `max_handshake_failures` times in a row goes to the penalty box. The address of a handshake is
+//! the one of the proxy protocol header, if any, so the penalty box is checked before the upgrade
+//! without a proxy, and after it behind one.
+//!
+//! Connections without an IP address, e.g. in-memory ones, are always admitted.
+
+use crate::transport::ConnectionId;
+use libra_config::config::InboundAdmissionConfig;
+use libra_network_address::{CidrBlock, NetworkAddress, Protocol};
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum AdmissionError {
+    #[error("{0} is not allowed to connect")]
+    Denied(IpAddr),
+
+    #[error("{0} is refused after repeated handshake failures")]
+    Penalized(IpAddr),
+
+    #[error("{0} already has {1} connections")]
+    TooManyConnectionsFromIp(IpAddr, usize),
+
+    #[error("{0} already has {1} connections")]
+    TooManyConnectionsFromSubnet(CidrBlock, usize),
+}
+
+impl AdmissionError {
+    /// Label of the error in the counters.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AdmissionError::Denied(_) => "denied",
+            AdmissionError::Penalized(_) => "penalized",
+            AdmissionError::TooManyConnectionsFromIp(_, _) => "ip_limit",
+            AdmissionError::TooManyConnectionsFromSubnet(_, _) => "subnet_limit",
+        }
+    }
+}
+
+/// The IP address of a dialer, as reported by the listener.
+pub fn source_ip(addr: &NetworkAddress) -> Option<IpAddr> {
+    match addr.as_slice().first() {
+        Some(Protocol::Ip4(ip)) => Some(IpAddr::V4(*ip)),
+        Some(Protocol::Ip6(ip)) => Some(IpAddr::V6(*ip)),
+        _ => None,
+    }
+}
+
+pub struct InboundAdmission {
+    config: InboundAdmissionConfig,
+    connections_per_ip: HashMap<IpAddr, usize>,
+    connections_per_subnet: HashMap<CidrBlock, usize>,
+    /// Source addresses of the upgraded connections, to release them once closed
+    upgraded: HashMap<ConnectionId, IpAddr>,
+    /// Number of consecutive failed upgrades per address
+    handshake_failures: HashMap<IpAddr, usize>,
+    /// Addresses refused until the given time
+    penalty_box: HashMap<IpAddr, Instant>,
+}
+
+impl InboundAdmission {
+    pub fn new(config: InboundAdmissionConfig) -> Self {
+        Self {
+            config,
+            connections_per_ip: HashMap::new(),
+            connections_per_subnet: HashMap::new(),
+            upgraded: HashMap::new(),
+            handshake_failures: HashMap::new(),
+            penalty_box: HashMap::new(),
+        }
+    }
+
+    fn subnet(&self, ip: IpAddr) -> CidrBlock {
+        let prefix_len = match ip {
+            IpAddr::V4(_) => self.config.ipv4_subnet_prefix_len,
+            IpAddr::V6(_) => self.config.ipv6_subnet_prefix_len,
+        };
+        // Out of range prefix lengths put all the addresses in the same subnet.
+        CidrBlock::new(ip, prefix_len)
+            .or_else(|_| CidrBlock::new(ip, 0))
+            .expect("a prefix length of 0 is always valid")
+    }
+
+    /// Checks whether handshakes from `ip` are refused.
+    pub fn check_penalty(&mut self, ip: IpAddr, now: Instant) -> Result<(), AdmissionError> {
+        if let Some(until) = self.penalty_box.get(&ip) {
+            if now < *until {
+                return Err(AdmissionError::Penalized(ip));
+            }
+            self.penalty_box.remove(&ip);
+        }
+        Ok(())
+    }
+
+    /// Admits a new connection from `ip`, which then counts against its limits until it's
+    /// released.
+    pub fn admit(&mut self, ip: IpAddr) -> Result<(), AdmissionError> {
+        if self.config.deny.iter().any(|block| block.matches(ip))
+            || (!self.config.allow.is_empty()
+                && !self.config.allow.iter().any(|block| block.matches(ip)))
+        {
+            return Err(AdmissionError::Denied(ip));
+        }
+
+        let ip_connections = self.connections_per_ip.get(&ip).copied().unwrap_or(0);
+        if let Some(max) = self.config.max_connections_per_ip {
+            if ip_connections >= max {
+                return Err(AdmissionError::TooManyConnectionsFromIp(ip, ip_connections));
+            }
+        }
+        let subnet = self.subnet(ip);
+        let subnet_connections = self
+            .connections_per_subnet
+            .get(&subnet)
+            .copied()
+            .unwrap_or(0);
+        if let Some(max) = self.config.max_connections_per_subnet {
+            if subnet_connections >= max {
+                return Err(AdmissionError::TooManyConnectionsFromSubnet(
+                    subnet,
+                    subnet_connections,
+                ));
+            }
+        }
+
+        self.connections_per_ip.insert(ip, ip_connections + 1);
+        self.connections_per_subnet
+            .insert(subnet, subnet_connections + 1);
+        Ok(())
+    }
+
+    /// The upgrade of an admitted connection succeeded.
+    pub fn upgrade_succeeded(&mut self, ip: IpAddr, connection_id: ConnectionId) {
+        self.upgraded.insert(connection_id, ip);
+    }
+
+    /// The upgrade of an admitted connection failed or its connection was refused, which
+    /// releases it.
+    pub fn upgrade_failed(&mut self, ip: IpAddr) {
+        self.release(ip);
+    }
+
+    /// A handshake from `ip` succeeded, which resets its failures.
+    pub fn handshake_succeeded(&mut self, ip: IpAddr) {
+        self.handshake_failures.remove(&ip);
+    }
+
+    /// A handshake from `ip` failed, which puts it in the penalty box after too many failures.
+    pub fn handshake_failed(&mut self, ip: IpAddr, now: Instant) {
+        let max_failures = match self.config.max_handshake_failures {
+            Some(max_failures) => max_failures,
+            None => return,
+        };
+        let failures = self.handshake_failures.entry(ip).or_insert(0);
+        *failures += 1;
+        if *failures >= max_failures {
+            self.handshake_failures.remove(&ip);
+            let penalty = Duration::from_secs(self.config.handshake_failure_penalty_secs);
+            self.penalty_box.insert(ip, now + penalty);
+        }
+    }
+
+    /// An upgraded connection was closed. Unknown connections, e.g. outbound ones, are ignored.
+    pub fn connection_closed(&mut self, connection_id: ConnectionId) {
+        if let Some(ip) = self.upgraded.remove(&connection_id) {
+            self.release(ip);
+        }
+    }
+
+    fn release(&mut self, ip: IpAddr) {
+        let subnet = self.subnet(ip);
+        decrement(&mut self.connections_per_ip, ip);
+        decrement(&mut self.connections_per_subnet, subnet);
+    }
+}
+
+fn decrement<K: std::hash::Hash + Eq>(counts: &mut HashMap<K, usize>, key: K) {
+    if let Some(count) = counts.get_mut(&key) {
+        *count -= 1;
+        if *count == 0 {
+            counts.remove(&key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_allow_and_deny_lists() {
+        let mut admission = InboundAdmission::new(InboundAdmissionConfig {
+            allow: vec!["10.0.0.0/8".parse().unwrap()],
+            deny: vec!["10.1.0.0/16".parse().unwrap()],
+            ..InboundAdmissionConfig::default()
+        });
+        assert_eq!(admission.admit(ip("10.2.0.1")), Ok(()));
+        assert_eq!(
+            admission.admit(ip("10.1.0.1")),
+            Err(AdmissionError::Denied(ip("10.1.0.1")))
+        );
+        assert_eq!(
+            admission.admit(ip("192.168.0.1")),
+            Err(AdmissionError::Denied(ip("192.168.0.1")))
+        );
+    }
+
+    #[test]
+    fn test_connection_limits() {
+        let mut admission = InboundAdmission::new(InboundAdmissionConfig {
+            max_connections_per_ip: Some(2),
+            max_connections_per_subnet: Some(3),
+            ..InboundAdmissionConfig::default()
+        });
+        assert_eq!(admission.admit(ip("1.2.3.4")), Ok(()));
+        assert_eq!(admission.admit(ip("1.2.3.4")), Ok(()));
+        assert_eq!(
+            admission.admit(ip("1.2.3.4")),
+            Err(AdmissionError::TooManyConnectionsFromIp(ip("1.2.3.4"), 2))
+        );
+        assert_eq!(admission.admit(ip("1.2.3.5")), Ok(()));
+        assert_eq!(
+            admission.admit(ip("1.2.3.6")),
+            Err(AdmissionError::TooManyConnectionsFromSubnet(
+                "1.2.3.0/24".parse().unwrap(),
+                3
+            ))
+        );
+        // other subnets are not affected
+        assert_eq!(admission.admit(ip("1.2.4.1")), Ok(()));
+
+        // closing an upgraded connection frees its slot
+        let connection_id = ConnectionId::from(7);
+        admission.upgrade_succeeded(ip("1.2.3.4"), connection_id);
+        admission.connection_closed(connection_id);
+        assert_eq!(admission.admit(ip("1.2.3.6")), Ok(()));
+        // and closing it again doesn't
+        admission.connection_closed(connection_id);
+        assert!(admission.admit(ip("1.2.3.7")).is_err());
+    }
+
+    #[test]
+    fn test_handshake_failure_penalty() {
+        let mut admission = InboundAdmission::new(InboundAdmissionConfig {
+            max_connections_per_ip: Some(1),
+            max_handshake_failures: Some(2),
+            handshake_failure_penalty_secs: 10,
+            ..InboundAdmissionConfig::default()
+        });
+        let now = Instant::now();
+        let bad = ip("6.6.6.6");
+
+        // failed upgrades free their slot
+        assert_eq!(admission.admit(bad), Ok(()));
+        admission.upgrade_failed(bad);
+        admission.handshake_failed(bad, now);
+        assert_eq!(admission.check_penalty(bad, now), Ok(()));
+        assert_eq!(admission.admit(bad), Ok(()));
+        admission.upgrade_failed(bad);
+        admission.handshake_failed(bad, now);
+        assert_eq!(
+            admission.check_penalty(bad, now),
+            Err(AdmissionError::Penalized(bad))
+        );
+        assert_eq!(admission.check_penalty(ip("6.6.6.7"), now), Ok(()));
+
+        let later = now + Duration::from_secs(10);
+        assert_eq!(admission.check_penalty(bad, later), Ok(()));
+        // a successful handshake resets the failures
+        admission.handshake_failed(bad, later);
+        admission.handshake_succeeded(bad);
+        admission.handshake_failed(bad, later);
+        assert_eq!(admission.check_penalty(bad, later), Ok(()));
+    }
+
+    #[test]
+    fn test_handshake_failure_penalty_disabled() {
+        let mut admission = InboundAdmission::new(InboundAdmissionConfig::default());
+        let now = Instant::now();
+        let bad = ip("6.6.6.6");
+
+        for _ in 0..100 {
+            admission.handshake_failed(bad, now);
+        }
+        assert_eq!(admission.check_penalty(bad, now), Ok(()));
+    }
+}
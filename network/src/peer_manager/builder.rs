@@ -1,19 +1,29 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+#[cfg(feature = "quic")]
+use crate::transport::quic::{LibraNetQuicTransport, QuicMuxSocket};
 use crate::{
     counters,
     noise::stream::NoiseStream,
+    peer::ProtocolQuotas,
     peer_manager::{
         conn_notifs_channel, ConnectionRequest, ConnectionRequestSender, PeerManager,
         PeerManagerNotification, PeerManagerRequest, PeerManagerRequestSender,
     },
     protocols::wire::handshake::v1::SupportedProtocols,
-    transport::{self, Connection, LibraNetTransport, LIBRA_TCP_TRANSPORT},
+    transport::{
+        self,
+        fault_injection::{FaultInjectionTransport, FaultySocket, FaultyTransport, NetworkFaults},
+        Connection, LibraNetTransport, LIBRA_TCP_TRANSPORT,
+    },
     ProtocolId,
 };
 use channel::{self, libra_channel, message_queues::QueueStyle};
-use libra_config::{config::HANDSHAKE_VERSION, network_id::NetworkContext};
+use libra_config::{
+    config::{InboundAdmissionConfig, HANDSHAKE_VERSION},
+    network_id::NetworkContext,
+};
 use libra_crypto::x25519;
 use libra_infallible::RwLock;
 use libra_logger::prelude::*;
@@ -55,6 +65,8 @@ struct TransportContext {
     rpc_protocols: Vec<ProtocolId>,
     authentication_mode: AuthenticationMode,
     trusted_peers: Arc<RwLock<HashMap<PeerId, HashSet<x25519::PublicKey>>>>,
+    /// Whether to advertise the compression capability in the handshake
+    enable_compression: bool,
 }
 
 impl TransportContext {
@@ -71,14 +83,21 @@ impl TransportContext {
             rpc_protocols,
             authentication_mode,
             trusted_peers,
+            enable_compression: false,
         }
     }
 
     fn supported_protocols(&self) -> SupportedProtocols {
-        self.direct_send_protocols
+        let protocols: SupportedProtocols = self
+            .direct_send_protocols
             .iter()
             .chain(&self.rpc_protocols)
-            .into()
+            .into();
+        if self.enable_compression {
+            protocols.with_compression()
+        } else {
+            protocols
+        }
     }
 
     fn augment_direct_send_protocols(
@@ -163,6 +182,17 @@ impl PeerManagerContext {
 type MemoryPeerManager =
     PeerManager<LibraNetTransport<MemoryTransport>, NoiseStream<memsocket::MemorySocket>>;
 type TcpPeerManager = PeerManager<LibraNetTransport<TcpTransport>, NoiseStream<TcpSocket>>;
+#[cfg(any(test, feature = "testing", feature = "fuzzing"))]
+type FaultyMemoryPeerManager = PeerManager<
+    FaultInjectionTransport<LibraNetTransport<FaultyTransport<MemoryTransport>>>,
+    NoiseStream<FaultySocket<memsocket::MemorySocket>>,
+>;
+type FaultyTcpPeerManager = PeerManager<
+    FaultInjectionTransport<LibraNetTransport<FaultyTransport<TcpTransport>>>,
+    NoiseStream<FaultySocket<TcpSocket>>,
+>;
+#[cfg(feature = "quic")]
+type QuicPeerManager = PeerManager<LibraNetQuicTransport, QuicMuxSocket>;
 
 #[derive(Debug, PartialEq, PartialOrd)]
 enum State {
@@ -180,11 +210,20 @@ pub struct PeerManagerBuilder {
     #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
     memory_peer_manager: Option<MemoryPeerManager>,
     tcp_peer_manager: Option<TcpPeerManager>,
+    #[cfg(feature = "quic")]
+    quic_peer_manager: Option<QuicPeerManager>,
+    #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
+    faulty_memory_peer_manager: Option<FaultyMemoryPeerManager>,
+    faulty_tcp_peer_manager: Option<FaultyTcpPeerManager>,
     // ListenAddress will be updated when the PeerManager is built
     listen_address: NetworkAddress,
     state: State,
     max_frame_size: usize,
     enable_proxy_protocol: bool,
+    protocol_quotas: ProtocolQuotas,
+    inbound_admission: InboundAdmissionConfig,
+    trusted_peers: Arc<RwLock<HashMap<PeerId, HashSet<x25519::PublicKey>>>>,
+    network_faults: Option<NetworkFaults>,
 }
 
 impl PeerManagerBuilder {
@@ -221,7 +260,7 @@ impl PeerManagerBuilder {
                 Vec::new(),
                 Vec::new(),
                 authentication_mode,
-                trusted_peers,
+                trusted_peers.clone(),
             )),
             peer_manager_context: Some(PeerManagerContext::new(
                 pm_reqs_tx,
@@ -237,10 +276,19 @@ impl PeerManagerBuilder {
             #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
             memory_peer_manager: None,
             tcp_peer_manager: None,
+            #[cfg(feature = "quic")]
+            quic_peer_manager: None,
+            #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
+            faulty_memory_peer_manager: None,
+            faulty_tcp_peer_manager: None,
             listen_address,
             state: State::CREATED,
             max_frame_size,
             enable_proxy_protocol,
+            protocol_quotas: Arc::new(HashMap::new()),
+            inbound_admission: InboundAdmissionConfig::default(),
+            trusted_peers,
+            network_faults: None,
         }
     }
 
@@ -256,6 +304,42 @@ impl PeerManagerBuilder {
             .clone()
     }
 
+    /// Set the weights and rate limits of the protocols in the outbound path of the peers.
+    /// Protocols without a quota get the default one.
+    pub fn set_protocol_quotas(&mut self, protocol_quotas: ProtocolQuotas) -> &mut Self {
+        assert_eq!(self.state, State::CREATED);
+        self.protocol_quotas = protocol_quotas;
+        self
+    }
+
+    /// Set the limits of the inbound connections by source IP address.
+    pub fn set_inbound_admission(
+        &mut self,
+        inbound_admission: InboundAdmissionConfig,
+    ) -> &mut Self {
+        assert_eq!(self.state, State::CREATED);
+        self.inbound_admission = inbound_admission;
+        self
+    }
+
+    /// Advertise the compression capability in the handshake. Message payloads are compressed on
+    /// the connections to peers which advertise it too.
+    pub fn enable_compression(&mut self) -> &mut Self {
+        self.transport_context
+            .as_mut()
+            .expect("Cannot enable compression once PeerManager has been built")
+            .enable_compression = true;
+        self
+    }
+
+    /// Subject the connections to the faults of the given handle, for testing. Only the TCP and
+    /// memory transports support fault injection.
+    pub fn set_network_faults(&mut self, network_faults: NetworkFaults) -> &mut Self {
+        assert_eq!(self.state, State::CREATED);
+        self.network_faults = Some(network_faults);
+        self
+    }
+
     pub fn add_connection_event_listener(&mut self) -> conn_notifs_channel::Receiver {
         self.peer_manager_context
             .as_mut()
@@ -284,9 +368,30 @@ impl PeerManagerBuilder {
         };
 
         match self.listen_address.as_slice() {
-            [Ip4(_), Tcp(_)] | [Ip6(_), Tcp(_)] => {
-                self.tcp_peer_manager = Some(self.build_with_transport(
-                    LibraNetTransport::new(
+            [Ip4(_), Tcp(_)] | [Ip6(_), Tcp(_)] => match self.network_faults.clone() {
+                Some(faults) => {
+                    let transport = LibraNetTransport::new(
+                        FaultyTransport::new(
+                            LIBRA_TCP_TRANSPORT.clone(),
+                            self.network_context.peer_id(),
+                            faults,
+                        ),
+                        self.network_context.clone(),
+                        key,
+                        maybe_trusted_peers,
+                        HANDSHAKE_VERSION,
+                        chain_id,
+                        protos,
+                        self.enable_proxy_protocol,
+                    );
+                    self.faulty_tcp_peer_manager =
+                        Some(self.build_with_transport(
+                            FaultInjectionTransport::new(transport),
+                            executor,
+                        ))
+                }
+                None => {
+                    let transport = LibraNetTransport::new(
                         LIBRA_TCP_TRANSPORT.clone(),
                         self.network_context.clone(),
                         key,
@@ -295,14 +400,50 @@ impl PeerManagerBuilder {
                         chain_id,
                         protos,
                         self.enable_proxy_protocol,
+                    );
+                    self.tcp_peer_manager = Some(self.build_with_transport(transport, executor))
+                }
+            },
+            #[cfg(feature = "quic")]
+            [Ip4(_), Quic(_)] | [Ip6(_), Quic(_)] => {
+                self.quic_peer_manager = Some(self.build_with_transport(
+                    LibraNetQuicTransport::new(
+                        self.network_context.clone(),
+                        key,
+                        maybe_trusted_peers,
+                        HANDSHAKE_VERSION,
+                        chain_id,
+                        protos,
+                        self.max_frame_size,
                     ),
                     executor,
                 ))
             }
             #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
-            [Memory(_)] => {
-                self.memory_peer_manager = Some(self.build_with_transport(
-                    LibraNetTransport::new(
+            [Memory(_)] => match self.network_faults.clone() {
+                Some(faults) => {
+                    let transport = LibraNetTransport::new(
+                        FaultyTransport::new(
+                            MemoryTransport,
+                            self.network_context.peer_id(),
+                            faults,
+                        ),
+                        self.network_context.clone(),
+                        key,
+                        maybe_trusted_peers,
+                        HANDSHAKE_VERSION,
+                        chain_id,
+                        protos,
+                        self.enable_proxy_protocol,
+                    );
+                    self.faulty_memory_peer_manager =
+                        Some(self.build_with_transport(
+                            FaultInjectionTransport::new(transport),
+                            executor,
+                        ))
+                }
+                None => {
+                    let transport = LibraNetTransport::new(
                         MemoryTransport,
                         self.network_context.clone(),
                         key,
@@ -311,13 +452,14 @@ impl PeerManagerBuilder {
                         chain_id,
                         protos,
                         self.enable_proxy_protocol,
-                    ),
-                    executor,
-                ))
-            }
+                    );
+                    self.memory_peer_manager = Some(self.build_with_transport(transport, executor))
+                }
+            },
             _ => panic!(
                 "{} Unsupported listen_address: '{}', expected '/memory/<port>', \
-                 '/ip4/<addr>/tcp/<port>', or '/ip6/<addr>/tcp/<port>'.",
+                 '/ip4/<addr>/tcp/<port>', '/ip6/<addr>/tcp/<port>', \
+                 '/ip4/<addr>/quic/<port>', or '/ip6/<addr>/quic/<port>'.",
                 self.network_context, self.listen_address
             ),
         };
@@ -356,6 +498,10 @@ impl PeerManagerBuilder {
             pm_context.max_concurrent_network_notifs,
             pm_context.channel_size,
             self.max_frame_size,
+            self.protocol_quotas.clone(),
+            self.inbound_admission.clone(),
+            self.trusted_peers.clone(),
+            self.enable_proxy_protocol,
         );
 
         // PeerManager constructor appends a public key to the listen_address.
@@ -387,6 +533,17 @@ impl PeerManagerBuilder {
         if let Some(tcp_pm) = self.tcp_peer_manager.take() {
             self.start_peer_manager(tcp_pm, executor);
         }
+        #[cfg(feature = "quic")]
+        if let Some(quic_pm) = self.quic_peer_manager.take() {
+            self.start_peer_manager(quic_pm, executor);
+        }
+        #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
+        if let Some(faulty_memory_pm) = self.faulty_memory_peer_manager.take() {
+            self.start_peer_manager(faulty_memory_pm, executor);
+        }
+        if let Some(faulty_tcp_pm) = self.faulty_tcp_peer_manager.take() {
+            self.start_peer_manager(faulty_tcp_pm, executor);
+        }
     }
 
     /// Add a handler for given protocols using raw bytes.
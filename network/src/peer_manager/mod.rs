@@ -14,13 +14,13 @@ use crate::{
     counters::{self, FAILED_LABEL, SUCCEEDED_LABEL},
     interface::{NetworkNotification, NetworkProvider, NetworkRequest},
     logging::*,
-    peer::DisconnectReason,
+    peer::{DisconnectReason, ProtocolQuotas},
     protocols::{
         direct_send::Message,
         rpc::{error::RpcError, InboundRpcRequest, OutboundRpcRequest},
     },
     transport,
-    transport::{Connection, ConnectionId, ConnectionMetadata},
+    transport::{Connection, ConnectionId, ConnectionMetadata, InboundUpgradeError},
     ProtocolId,
 };
 use anyhow::format_err;
@@ -33,21 +33,25 @@ use futures::{
     sink::SinkExt,
     stream::{Fuse, FuturesUnordered, StreamExt},
 };
-use libra_config::network_id::NetworkContext;
+use libra_config::{config::InboundAdmissionConfig, network_id::NetworkContext};
+use libra_crypto::x25519;
+use libra_infallible::{Mutex, RwLock};
 use libra_logger::prelude::*;
 use libra_network_address::NetworkAddress;
 use libra_types::PeerId;
 use netcore::transport::{ConnectionOrigin, Transport};
 use serde::Serialize;
 use std::{
-    collections::{hash_map::Entry, HashMap},
+    collections::{hash_map::Entry, HashMap, HashSet},
     fmt::Debug,
     marker::PhantomData,
+    net::IpAddr,
     sync::Arc,
     time::{Duration, Instant},
 };
 use tokio::runtime::Handle;
 
+mod admission;
 pub mod builder;
 pub mod conn_notifs_channel;
 mod error;
@@ -55,6 +59,7 @@ mod error;
 mod tests;
 
 pub use self::error::PeerManagerError;
+use admission::{AdmissionError, InboundAdmission};
 use serde::export::Formatter;
 
 /// Request received by PeerManager from upstream actors.
@@ -86,6 +91,14 @@ pub enum ConnectionRequest {
         PeerId,
         #[serde(skip)] oneshot::Sender<Result<(), PeerManagerError>>,
     ),
+    /// Disconnects from the peer and refuses connections with it for the given duration.
+    BanPeer(
+        PeerId,
+        Duration,
+        #[serde(skip)] oneshot::Sender<Result<(), PeerManagerError>>,
+    ),
+    /// Gets the metadata of the connections with all connected peers.
+    GetConnectedPeers(#[serde(skip)] oneshot::Sender<Vec<ConnectionMetadata>>),
 }
 
 #[derive(Clone, PartialEq, Eq, Serialize)]
@@ -235,6 +248,29 @@ impl ConnectionRequestSender {
             .push(peer, ConnectionRequest::DisconnectPeer(peer, oneshot_tx))?;
         oneshot_rx.await?
     }
+
+    pub async fn ban_peer(
+        &mut self,
+        peer: PeerId,
+        duration: Duration,
+    ) -> Result<(), PeerManagerError> {
+        let (oneshot_tx, oneshot_rx) = oneshot::channel();
+        self.inner
+            .push(peer, ConnectionRequest::BanPeer(peer, duration, oneshot_tx))?;
+        oneshot_rx.await?
+    }
+
+    pub async fn get_connected_peers(
+        &mut self,
+    ) -> Result<Vec<ConnectionMetadata>, PeerManagerError> {
+        let (oneshot_tx, oneshot_rx) = oneshot::channel();
+        // The request is not about a peer, queue it under the zero peer id.
+        self.inner.push(
+            PeerId::ZERO,
+            ConnectionRequest::GetConnectedPeers(oneshot_tx),
+        )?;
+        Ok(oneshot_rx.await?)
+    }
 }
 
 /// Responsible for handling and maintaining connections to other Peers
@@ -287,6 +323,12 @@ where
     channel_size: usize,
     /// Max network frame size
     max_frame_size: usize,
+    /// Weights and rate limits of the protocols in the outbound path of the peers.
+    protocol_quotas: ProtocolQuotas,
+    /// Peers we refuse connections with, until the given time.
+    banned_peers: HashMap<PeerId, Instant>,
+    /// Admission control of the inbound connections, shared with the `TransportHandler`.
+    inbound_admission: Arc<Mutex<InboundAdmission>>,
 }
 
 impl<TTransport, TSocket> PeerManager<TTransport, TSocket>
@@ -312,6 +354,10 @@ where
         max_concurrent_network_reqs: usize,
         max_concurrent_network_notifs: usize,
         max_frame_size: usize,
+        protocol_quotas: ProtocolQuotas,
+        inbound_admission_config: InboundAdmissionConfig,
+        trusted_peers: Arc<RwLock<HashMap<PeerId, HashSet<x25519::PublicKey>>>>,
+        enable_proxy_protocol: bool,
     ) -> Self {
         let (transport_notifs_tx, transport_notifs_rx) = channel::new(
             channel_size,
@@ -322,6 +368,8 @@ where
         //TODO now that you can only listen on a socket inside of a tokio runtime we'll need to
         // rethink how we init the PeerManager so we don't have to do this funny thing.
         let transport_notifs_tx_clone = transport_notifs_tx.clone();
+        let inbound_admission =
+            Arc::new(Mutex::new(InboundAdmission::new(inbound_admission_config)));
         let (transport_handler, listen_addr) = executor.enter(|| {
             TransportHandler::new(
                 network_context.clone(),
@@ -329,6 +377,9 @@ where
                 listen_addr,
                 transport_reqs_rx,
                 transport_notifs_tx_clone,
+                inbound_admission.clone(),
+                trusted_peers,
+                enable_proxy_protocol,
             )
         });
         Self {
@@ -350,6 +401,9 @@ where
             max_concurrent_network_notifs,
             channel_size,
             max_frame_size,
+            protocol_quotas,
+            banned_peers: HashMap::new(),
+            inbound_admission,
         }
     }
 
@@ -464,6 +518,9 @@ where
                     reason
                 );
                 let peer_id = lost_conn_metadata.remote_peer_id;
+                self.inbound_admission
+                    .lock()
+                    .connection_closed(lost_conn_metadata.connection_id);
                 // If the active connection with the peer is lost, remove it from `active_peers`.
                 if let Entry::Occupied(entry) = self.active_peers.entry(peer_id) {
                     let (conn_metadata, _) = entry.get();
@@ -519,7 +576,17 @@ where
         match request {
             ConnectionRequest::DialPeer(requested_peer_id, addr, response_tx) => {
                 // Only dial peers which we aren't already connected with
-                if let Some((curr_connection, _)) = self.active_peers.get(&requested_peer_id) {
+                if self.is_banned(&requested_peer_id) {
+                    debug!(
+                        NetworkSchema::new(&self.network_context).remote_peer(&requested_peer_id),
+                        "{} Peer {} is banned. Not dialing address {}",
+                        self.network_context,
+                        requested_peer_id.short_str(),
+                        addr
+                    );
+                    let _ = response_tx.send(Err(PeerManagerError::Banned(requested_peer_id)));
+                } else if let Some((curr_connection, _)) = self.active_peers.get(&requested_peer_id)
+                {
                     let error = PeerManagerError::AlreadyConnected(curr_connection.addr.clone());
                     debug!(
                         NetworkSchema::new(&self.network_context)
@@ -573,6 +640,40 @@ where
                     }
                 }
             }
+            ConnectionRequest::BanPeer(peer_id, duration, resp_tx) => {
+                info!(
+                    NetworkSchema::new(&self.network_context).remote_peer(&peer_id),
+                    "{} Banning peer: {} for {:?}",
+                    self.network_context,
+                    peer_id.short_str(),
+                    duration
+                );
+                self.banned_peers.insert(peer_id, Instant::now() + duration);
+                // Dropping the NetworkRequest channel triggers a disconnect, like for
+                // `DisconnectPeer`, except that nobody waits for its completion.
+                self.active_peers.remove(&peer_id);
+                let _ = resp_tx.send(Ok(()));
+            }
+            ConnectionRequest::GetConnectedPeers(resp_tx) => {
+                let peers = self
+                    .active_peers
+                    .values()
+                    .map(|(conn_metadata, _)| conn_metadata.clone())
+                    .collect();
+                let _ = resp_tx.send(peers);
+            }
+        }
+    }
+
+    /// Whether the peer is banned, forgetting expired bans.
+    fn is_banned(&mut self, peer_id: &PeerId) -> bool {
+        match self.banned_peers.get(peer_id) {
+            Some(until) if *until > Instant::now() => true,
+            Some(_) => {
+                self.banned_peers.remove(peer_id);
+                false
+            }
+            None => false,
         }
     }
 
@@ -668,6 +769,32 @@ where
         }
     }
 
+    /// Closes a connection which was never handed to a `Peer` actor.
+    fn close_connection(&self, connection: Connection<TSocket>) {
+        self.inbound_admission
+            .lock()
+            .connection_closed(connection.metadata.connection_id);
+        let network_context = self.network_context.clone();
+        let peer_id = connection.metadata.remote_peer_id;
+        let drop_fut = async move {
+            let mut connection = connection;
+            if let Err(e) =
+                tokio::time::timeout(transport::TRANSPORT_TIMEOUT, connection.socket.close()).await
+            {
+                error!(
+                    NetworkSchema::new(&network_context)
+                        .remote_peer(&peer_id),
+                    error = %e,
+                    "{} Closing connection with Peer {} failed with error: {}",
+                    network_context,
+                    peer_id.short_str(),
+                    e
+                );
+            };
+        };
+        self.executor.spawn(drop_fut);
+    }
+
     fn add_peer(&mut self, connection: Connection<TSocket>) {
         let conn_meta = connection.metadata.clone();
         let peer_id = conn_meta.remote_peer_id;
@@ -675,6 +802,17 @@ where
 
         let mut send_new_peer_notification = true;
 
+        if self.is_banned(&peer_id) {
+            info!(
+                NetworkSchema::new(&self.network_context).remote_peer(&peer_id),
+                "{} Closing connection with banned Peer {}",
+                self.network_context,
+                peer_id.short_str()
+            );
+            self.close_connection(connection);
+            return;
+        }
+
         // Check for and handle simultaneous dialing
         if let Entry::Occupied(active_entry) = self.active_peers.entry(peer_id) {
             let (curr_conn_metadata, _) = active_entry.get();
@@ -701,28 +839,8 @@ where
                     self.network_context,
                     peer_id.short_str()
                 );
-                let network_context = self.network_context.clone();
                 // Drop the new connection and keep the one already stored in active_peers
-                let drop_fut = async move {
-                    let mut connection = connection;
-                    if let Err(e) = tokio::time::timeout(
-                        transport::TRANSPORT_TIMEOUT,
-                        connection.socket.close(),
-                    )
-                    .await
-                    {
-                        error!(
-                            NetworkSchema::new(&network_context)
-                                .remote_peer(&peer_id),
-                            error = %e,
-                            "{} Closing connection with Peer {} failed with error: {}",
-                            network_context,
-                            peer_id.short_str(),
-                            e
-                        );
-                    };
-                };
-                self.executor.spawn(drop_fut);
+                self.close_connection(connection);
                 return;
             }
         }
@@ -737,6 +855,7 @@ where
             self.max_concurrent_network_notifs,
             self.channel_size,
             self.max_frame_size,
+            self.protocol_quotas.clone(),
         );
         // Start background task to handle events (RPCs and DirectSend messages) received from
         // peer.
@@ -890,6 +1009,10 @@ where
     listener: Fuse<TTransport::Listener>,
     transport_reqs_rx: channel::Receiver<TransportRequest>,
     transport_notifs_tx: channel::Sender<TransportNotification<TSocket>>,
+    inbound_admission: Arc<Mutex<InboundAdmission>>,
+    /// Trusted and eligible peers, exempt from the handshake failure penalty
+    trusted_peers: Arc<RwLock<HashMap<PeerId, HashSet<x25519::PublicKey>>>>,
+    enable_proxy_protocol: bool,
 }
 
 impl<TTransport, TSocket> TransportHandler<TTransport, TSocket>
@@ -906,6 +1029,9 @@ where
         listen_addr: NetworkAddress,
         transport_reqs_rx: channel::Receiver<TransportRequest>,
         transport_notifs_tx: channel::Sender<TransportNotification<TSocket>>,
+        inbound_admission: Arc<Mutex<InboundAdmission>>,
+        trusted_peers: Arc<RwLock<HashMap<PeerId, HashSet<x25519::PublicKey>>>>,
+        enable_proxy_protocol: bool,
     ) -> (Self, NetworkAddress) {
         let (listener, listen_addr) = transport
             .listen_on(listen_addr)
@@ -924,6 +1050,9 @@ where
                 listener: listener.fuse(),
                 transport_reqs_rx,
                 transport_notifs_tx,
+                inbound_admission,
+                trusted_peers,
+                enable_proxy_protocol,
             },
             listen_addr,
         )
@@ -948,6 +1077,11 @@ where
                 incoming_connection = self.listener.select_next_some() => {
                     match incoming_connection {
                         Ok((upgrade, addr)) => {
+                            // Dropping the pending upgrade of a refused connection closes it.
+                            if !self.admit_inbound(&addr) {
+                                continue;
+                            }
+
                             debug!(
                                 NetworkSchema::new(&self.network_context)
                                     .network_address(&addr),
@@ -992,6 +1126,97 @@ where
         );
     }
 
+    /// Admits an inbound connection before its upgrade. It then counts against the limits of its
+    /// source address until it's closed.
+    fn admit_inbound(&self, addr: &NetworkAddress) -> bool {
+        let ip = match admission::source_ip(addr) {
+            Some(ip) => ip,
+            None => return true,
+        };
+        let mut inbound_admission = self.inbound_admission.lock();
+        // Behind a proxy, the address of the handshake is only known once the upgrade read the
+        // proxy protocol header.
+        let admitted = if self.enable_proxy_protocol {
+            inbound_admission.admit(ip)
+        } else {
+            inbound_admission
+                .check_penalty(ip, Instant::now())
+                .and_then(|()| inbound_admission.admit(ip))
+        };
+        match admitted {
+            Ok(()) => true,
+            Err(err) => {
+                self.log_refused_inbound(addr, &err);
+                false
+            }
+        }
+    }
+
+    fn log_refused_inbound(&self, addr: &NetworkAddress, err: &AdmissionError) {
+        info!(
+            NetworkSchema::new(&self.network_context).network_address(addr),
+            error = %err,
+            "{} Refusing inbound connection from {}: {}",
+            self.network_context,
+            addr,
+            err
+        );
+        counters::inbound_connections_refused(&self.network_context, err.as_str()).inc();
+    }
+
+    /// Checks the penalty box for the address of the handshake of an upgraded inbound
+    /// connection, behind a proxy. It then counts against the limits of its source address until
+    /// it's closed.
+    fn admit_upgraded_inbound(
+        &self,
+        source_ip: Option<IpAddr>,
+        metadata: &ConnectionMetadata,
+    ) -> bool {
+        let mut inbound_admission = self.inbound_admission.lock();
+        if let Some(ip) = admission::source_ip(&metadata.addr) {
+            if self.enable_proxy_protocol && !self.is_trusted(&metadata.remote_peer_id) {
+                if let Err(err) = inbound_admission.check_penalty(ip, Instant::now()) {
+                    if let Some(source_ip) = source_ip {
+                        inbound_admission.upgrade_failed(source_ip);
+                    }
+                    self.log_refused_inbound(&metadata.addr, &err);
+                    return false;
+                }
+            }
+            inbound_admission.handshake_succeeded(ip);
+        }
+        if let Some(source_ip) = source_ip {
+            inbound_admission.upgrade_succeeded(source_ip, metadata.connection_id);
+        }
+        true
+    }
+
+    /// Releases a failed inbound connection, and counts the failure of its handshake against the
+    /// address in the proxy protocol header if any, unless the peer is trusted.
+    fn handle_failed_inbound_upgrade(
+        &self,
+        source_ip: Option<IpAddr>,
+        err: &(dyn std::error::Error + 'static),
+    ) {
+        let mut inbound_admission = self.inbound_admission.lock();
+        if let Some(source_ip) = source_ip {
+            inbound_admission.upgrade_failed(source_ip);
+        }
+        if let Some(failure) = InboundUpgradeError::find(err) {
+            let trusted = failure
+                .remote_peer_id
+                .map_or(false, |peer_id| self.is_trusted(&peer_id));
+            if let (false, Some(ip)) = (trusted, admission::source_ip(&failure.addr)) {
+                inbound_admission.handshake_failed(ip, Instant::now());
+            }
+        }
+    }
+
+    /// Trusted and eligible peers never go to the penalty box.
+    fn is_trusted(&self, peer_id: &PeerId) -> bool {
+        self.trusted_peers.read().contains_key(peer_id)
+    }
+
     fn dial_peer(
         &self,
         dial_peer_request: TransportRequest,
@@ -1142,8 +1367,13 @@ where
             .dec();
 
         let elapsed_time = start_time.elapsed().as_secs_f64();
+        let source_ip = admission::source_ip(&addr);
         match upgrade {
             Ok(connection) => {
+                // Dropping the connection of a refused peer closes it.
+                if !self.admit_upgraded_inbound(source_ip, &connection.metadata) {
+                    return;
+                }
                 debug!(
                     NetworkSchema::new(&self.network_context)
                         .connection_metadata_with_address(&connection.metadata),
@@ -1166,6 +1396,7 @@ where
                 self.transport_notifs_tx.send(event).await.unwrap();
             }
             Err(err) => {
+                self.handle_failed_inbound_upgrade(source_ip, &err);
                 warn!(
                     NetworkSchema::new(&self.network_context)
                         .network_address(&addr),
@@ -18,7 +18,8 @@ use crate::{
 };
 use channel::{libra_channel, message_queues::QueueStyle};
 use futures::{channel::oneshot, io::AsyncWriteExt, sink::SinkExt, stream::StreamExt};
-use libra_config::network_id::NetworkContext;
+use libra_config::{config::InboundAdmissionConfig, network_id::NetworkContext};
+use libra_infallible::RwLock;
 use libra_network_address::NetworkAddress;
 use libra_types::PeerId;
 use memsocket::MemorySocket;
@@ -26,7 +27,7 @@ use netcore::{
     compat::IoCompat,
     transport::{boxed::BoxedTransport, memory::MemoryTransport, ConnectionOrigin, TransportExt},
 };
-use std::{collections::HashMap, iter::FromIterator, num::NonZeroUsize};
+use std::{collections::HashMap, iter::FromIterator, num::NonZeroUsize, sync::Arc, time::Duration};
 use tokio::runtime::Handle;
 use tokio_util::codec::{Framed, LengthDelimitedCodec};
 
@@ -103,6 +104,10 @@ fn build_test_peer_manager(
         constants::MAX_CONCURRENT_NETWORK_REQS,
         constants::MAX_CONCURRENT_NETWORK_NOTIFS,
         constants::MAX_FRAME_SIZE,
+        Arc::new(HashMap::new()),
+        InboundAdmissionConfig::default(),
+        Arc::new(RwLock::new(HashMap::new())),
+        false,
     );
 
     (
@@ -621,3 +626,80 @@ fn test_dial_disconnect() {
 
     runtime.block_on(test);
 }
+
+#[test]
+fn test_ban_peer() {
+    ::libra_logger::Logger::init_for_testing();
+    let mut runtime = ::tokio::runtime::Runtime::new().unwrap();
+
+    let ids = ordered_peer_ids(2);
+    let (mut peer_manager, _request_tx, _connection_reqs_tx, _hello_rx, mut conn_status_rx) =
+        build_test_peer_manager(runtime.handle().clone(), ids[1]);
+
+    let test = async move {
+        let (outbound, _inbound) = build_test_connection();
+        peer_manager.add_peer(create_connection(
+            outbound,
+            ids[0],
+            NetworkAddress::mock(),
+            ConnectionOrigin::Outbound,
+            ConnectionId::from(0),
+        ));
+        let conn_notif = conn_status_rx.next().await.unwrap();
+        assert!(matches!(
+            conn_notif,
+            ConnectionNotification::NewPeer(_, _, _, _)
+        ));
+
+        let (peers_tx, peers_rx) = oneshot::channel();
+        peer_manager
+            .handle_connection_request(ConnectionRequest::GetConnectedPeers(peers_tx))
+            .await;
+        let peers = peers_rx.await.unwrap();
+        assert_eq!(peers.len(), 1);
+        assert_eq!(peers[0].remote_peer_id, ids[0]);
+
+        // Banning the peer disconnects from it.
+        let (ban_resp_tx, ban_resp_rx) = oneshot::channel();
+        peer_manager
+            .handle_connection_request(ConnectionRequest::BanPeer(
+                ids[0],
+                Duration::from_secs(60),
+                ban_resp_tx,
+            ))
+            .await;
+        ban_resp_rx.await.unwrap().unwrap();
+        let (peers_tx, peers_rx) = oneshot::channel();
+        peer_manager
+            .handle_connection_request(ConnectionRequest::GetConnectedPeers(peers_tx))
+            .await;
+        assert!(peers_rx.await.unwrap().is_empty());
+
+        // The peer can't be dialed anymore.
+        let (dial_resp_tx, dial_resp_rx) = oneshot::channel();
+        peer_manager
+            .handle_connection_request(ConnectionRequest::DialPeer(
+                ids[0],
+                NetworkAddress::mock(),
+                dial_resp_tx,
+            ))
+            .await;
+        assert!(matches!(
+            dial_resp_rx.await.unwrap(),
+            Err(PeerManagerError::Banned(_))
+        ));
+
+        // Its new connections are closed.
+        let (outbound, _inbound) = build_test_connection();
+        peer_manager.add_peer(create_connection(
+            outbound,
+            ids[0],
+            NetworkAddress::mock(),
+            ConnectionOrigin::Inbound,
+            ConnectionId::from(1),
+        ));
+        assert!(!peer_manager.active_peers.contains_key(&ids[0]));
+    };
+
+    runtime.block_on(test);
+}
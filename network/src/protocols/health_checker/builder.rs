@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::protocols::health_checker::{
-    HealthChecker, HealthCheckerNetworkEvents, HealthCheckerNetworkSender,
+    HealthChecker, HealthCheckerNetworkEvents, HealthCheckerNetworkSender, PeerHealthMap,
 };
 use futures::stream::StreamExt;
 use futures_util::stream::Fuse;
@@ -48,6 +48,7 @@ pub type HealthCheckerService = HealthChecker<Fuse<Interval>>;
 pub struct HealthCheckerBuilder {
     config: Option<HealthCheckerBuilderConfig>,
     service: Option<HealthCheckerService>,
+    peer_health: Option<PeerHealthMap>,
     built: bool,
     started: bool,
 }
@@ -57,6 +58,7 @@ impl HealthCheckerBuilder {
         Self {
             config: Some(config),
             service: None,
+            peer_health: None,
             built: false,
             started: false,
         }
@@ -96,11 +98,17 @@ impl HealthCheckerBuilder {
                     config.ping_failures_tolerated,
                 )
             });
+            self.peer_health = Some(service.peer_health());
             self.service = Some(service);
         }
         self
     }
 
+    /// The health of the connected peers, available once built.
+    pub fn peer_health(&self) -> Option<PeerHealthMap> {
+        self.peer_health.clone()
+    }
+
     pub fn start(&mut self, executor: &Handle) {
         // Must be built to start.
         assert!(self.built);
@@ -36,6 +36,7 @@ use futures::{
     stream::{FusedStream, FuturesUnordered, Stream, StreamExt},
 };
 use libra_config::network_id::NetworkContext;
+use libra_infallible::RwLock;
 use libra_logger::prelude::*;
 use libra_metrics::IntCounterVec;
 use libra_types::PeerId;
@@ -131,6 +132,18 @@ pub struct Ping(u32);
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Pong(u32);
 
+/// Health of a connected peer, as seen by the HealthChecker.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct PeerHealth {
+    /// Last round of successful ping
+    pub last_success_round: u64,
+    /// Number of ping failures since then
+    pub failures: u64,
+}
+
+/// Health of all connected peers, shared with the network admin interface.
+pub type PeerHealthMap = Arc<RwLock<HashMap<PeerId, PeerHealth>>>;
+
 /// The actor performing health checks by running the Ping protocol
 pub struct HealthChecker<TTicker> {
     network_context: Arc<NetworkContext>,
@@ -143,7 +156,7 @@ pub struct HealthChecker<TTicker> {
     network_rx: HealthCheckerNetworkEvents,
     /// Map from connected peer to last round of successful ping, and number of failures since
     /// then.
-    connected: HashMap<PeerId, (u64, u64)>,
+    connected: PeerHealthMap,
     /// Random-number generator.
     rng: SmallRng,
     /// Ping timmeout duration.
@@ -174,7 +187,7 @@ where
             ticker,
             network_tx,
             network_rx,
-            connected: HashMap::new(),
+            connected: Arc::new(RwLock::new(HashMap::new())),
             rng: SmallRng::from_entropy(),
             ping_timeout,
             ping_failures_tolerated,
@@ -182,6 +195,11 @@ where
         }
     }
 
+    /// Returns the health of the connected peers, which stays up to date while the actor runs.
+    pub fn peer_health(&self) -> PeerHealthMap {
+        self.connected.clone()
+    }
+
     pub async fn start(mut self) {
         let mut tick_handlers = FuturesUnordered::new();
         info!(
@@ -193,10 +211,13 @@ where
                 event = self.network_rx.select_next_some() => {
                     match event {
                         Event::NewPeer(peer_id, _origin) => {
-                            self.connected.insert(peer_id, (self.round, 0));
+                            self.connected.write().insert(peer_id, PeerHealth {
+                                last_success_round: self.round,
+                                failures: 0,
+                            });
                         },
                         Event::LostPeer(peer_id, _origin) => {
-                            self.connected.remove(&peer_id);
+                            self.connected.write().remove(&peer_id);
                         },
                         Event::RpcRequest(peer_id, msg, res_tx) => {
                             match msg {
@@ -323,14 +344,12 @@ where
                         round
                     );
                     // Update last successful ping to current round.
-                    self.connected
-                        .entry(peer_id)
-                        .and_modify(|(ref mut r, ref mut count)| {
-                            if round > *r {
-                                *r = round;
-                                *count = 0;
-                            }
-                        });
+                    self.connected.write().entry(peer_id).and_modify(|health| {
+                        if round > health.last_success_round {
+                            health.last_success_round = round;
+                            health.failures = 0;
+                        }
+                    });
                 } else {
                     warn!(
                         SecurityEvent::InvalidHealthCheckerMsg,
@@ -356,40 +375,43 @@ where
                     round,
                     err
                 );
-                match self.connected.get_mut(&peer_id) {
+                let failures = match self.connected.write().get_mut(&peer_id) {
                     None => {
                         // If we are no longer connected to the peer, we ignore ping
                         // failure.
+                        return;
                     }
-                    Some((ref mut prev, ref mut failures)) => {
+                    Some(health) => {
                         // If this is the result of an older ping, we ignore it.
-                        if *prev > round {
+                        if health.last_success_round > round {
                             return;
                         }
-                        // Increment num of failures. If the ping failures are now more than
-                        // `self.ping_failures_tolerated`, we disconnect from the node.
-                        // The HealthChecker only performs the disconnect. It relies on
-                        // ConnectivityManager or the remote peer to re-establish the connection.
-                        *failures += 1;
-                        if *failures > self.ping_failures_tolerated {
-                            info!(
-                                NetworkSchema::new(&self.network_context).remote_peer(&peer_id),
-                                "{} Disconnecting from peer: {}",
-                                self.network_context,
-                                peer_id.short_str()
-                            );
-                            if let Err(err) = self.network_tx.disconnect_peer(peer_id).await {
-                                warn!(
-                                    NetworkSchema::new(&self.network_context)
-                                        .remote_peer(&peer_id),
-                                    error = ?err,
-                                    "{} Failed to disconnect from peer: {} with error: {:?}",
-                                    self.network_context,
-                                    peer_id.short_str(),
-                                    err
-                                );
-                            }
-                        }
+                        // Increment num of failures.
+                        health.failures += 1;
+                        health.failures
+                    }
+                };
+                // If the ping failures are now more than `self.ping_failures_tolerated`, we
+                // disconnect from the node.
+                // The HealthChecker only performs the disconnect. It relies on
+                // ConnectivityManager or the remote peer to re-establish the connection.
+                if failures > self.ping_failures_tolerated {
+                    info!(
+                        NetworkSchema::new(&self.network_context).remote_peer(&peer_id),
+                        "{} Disconnecting from peer: {}",
+                        self.network_context,
+                        peer_id.short_str()
+                    );
+                    if let Err(err) = self.network_tx.disconnect_peer(peer_id).await {
+                        warn!(
+                            NetworkSchema::new(&self.network_context)
+                                .remote_peer(&peer_id),
+                            error = ?err,
+                            "{} Failed to disconnect from peer: {} with error: {:?}",
+                            self.network_context,
+                            peer_id.short_str(),
+                            err
+                        );
                     }
                 }
             }
@@ -424,7 +446,7 @@ where
     }
 
     fn sample_random_peer(&mut self) -> Option<PeerId> {
-        let peers: Vec<_> = self.connected.keys().cloned().collect();
+        let peers: Vec<_> = self.connected.read().keys().cloned().collect();
         peers.choose(&mut self.rng).cloned()
     }
 
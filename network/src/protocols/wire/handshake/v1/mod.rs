@@ -9,11 +9,14 @@
 //! supported messaging protocol versions to a bit vector representing application protocols
 //! supported over that messaging protocol. On receipt, both ends will determine the highest
 //! intersecting messaging protocol version and use that for the remainder of the session.
+//!
+//! Besides application protocols, the bit vector carries capability bits, which are placed after
+//! the range of `ProtocolId`s so that peers which don't know them drop them from the intersection.
 
 use libra_config::network_id::NetworkId;
 use libra_types::chain_id::ChainId;
 use serde::{export::Formatter, Deserialize, Serialize};
-use std::{collections::BTreeMap, convert::TryInto, fmt, iter::Iterator};
+use std::{collections::BTreeMap, convert::TryInto, fmt, iter::Iterator, str::FromStr};
 use thiserror::Error;
 
 #[cfg(any(test, feature = "fuzzing"))]
@@ -36,9 +39,15 @@ pub enum ProtocolId {
     StateSynchronizerDirectSend = 3,
     DiscoveryDirectSend = 4,
     HealthCheckerRpc = 5,
+    ConsensusObserverDirectSend = 6,
 }
 
 impl ProtocolId {
+    /// All the protocols, in the order of their ids.
+    pub fn all() -> impl Iterator<Item = ProtocolId> {
+        (0..COMPRESSION_CAPABILITY).filter_map(|id| lcs::from_bytes(&[id]).ok())
+    }
+
     pub fn as_str(self) -> &'static str {
         use ProtocolId::*;
         match self {
@@ -48,10 +57,36 @@ impl ProtocolId {
             StateSynchronizerDirectSend => "StateSynchronizerDirectSend",
             DiscoveryDirectSend => "DiscoveryDirectSend",
             HealthCheckerRpc => "HealthCheckerRpc",
+            ConsensusObserverDirectSend => "ConsensusObserverDirectSend",
+        }
+    }
+
+    /// Whether the payloads of this protocol are compressed on connections which negotiated
+    /// compression. Latency sensitive protocols with small messages are sent as is.
+    pub fn is_compressible(self) -> bool {
+        use ProtocolId::*;
+        match self {
+            MempoolDirectSend | StateSynchronizerDirectSend => true,
+            ConsensusRpc
+            | ConsensusDirectSend
+            | DiscoveryDirectSend
+            | HealthCheckerRpc
+            | ConsensusObserverDirectSend => false,
         }
     }
 }
 
+impl FromStr for ProtocolId {
+    type Err = anyhow::Error;
+
+    /// Parses the names returned by `as_str`.
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Self::all()
+            .find(|protocol| protocol.as_str() == s)
+            .ok_or_else(|| anyhow::format_err!("Unknown protocol: {}", s))
+    }
+}
+
 impl fmt::Debug for ProtocolId {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self)
@@ -68,6 +103,9 @@ impl fmt::Display for ProtocolId {
 // SupportedProtocols
 //
 
+/// Capability bit advertising support for the compression of message payloads.
+const COMPRESSION_CAPABILITY: u8 = u8::MAX;
+
 #[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
 #[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
 pub struct SupportedProtocols(bitvec::BitVec);
@@ -79,7 +117,7 @@ impl TryInto<Vec<ProtocolId>> for SupportedProtocols {
         let mut protocols = Vec::with_capacity(self.0.count_ones() as usize);
         if let Some(last_bit) = self.0.last_set_bit() {
             for i in 0..=last_bit {
-                if self.0.is_set(i) {
+                if i != COMPRESSION_CAPABILITY && self.0.is_set(i) {
                     let protocol: ProtocolId = lcs::from_bytes(&[i])?;
                     protocols.push(protocol);
                 }
@@ -102,6 +140,18 @@ impl SupportedProtocols {
     fn intersection(self, other: SupportedProtocols) -> SupportedProtocols {
         SupportedProtocols(self.0 & other.0)
     }
+
+    /// Advertises the compression capability.
+    pub fn with_compression(mut self) -> Self {
+        self.0.set(COMPRESSION_CAPABILITY);
+        self
+    }
+
+    /// Whether the compression capability is set. After the handshake, this tells whether both
+    /// peers advertised it.
+    pub fn supports_compression(&self) -> bool {
+        self.0.is_set(COMPRESSION_CAPABILITY)
+    }
 }
 
 //
@@ -11,6 +11,25 @@ fn net_protocol() -> lcs::Result<()> {
     Ok(())
 }
 
+#[test]
+fn protocol_id_from_str() {
+    for i in 0..=u8::MAX {
+        if let Ok(protocol) = lcs::from_bytes::<ProtocolId>(&[i]) {
+            assert_eq!(protocol.as_str().parse::<ProtocolId>().unwrap(), protocol);
+        }
+    }
+    "NotAProtocol".parse::<ProtocolId>().unwrap_err();
+}
+
+#[test]
+fn protocol_id_all() {
+    let all: Vec<_> = ProtocolId::all().collect();
+    for (id, protocol) in all.iter().enumerate() {
+        assert_eq!(*protocol as usize, id);
+    }
+    assert_eq!(all.last(), Some(&ProtocolId::ConsensusObserverDirectSend));
+}
+
 #[test]
 fn protocols_to_from_vec() {
     let supported_protocols: SupportedProtocols =
@@ -115,3 +134,37 @@ fn common_protocols() {
         h1.perform_handshake(&h2).unwrap()
     );
 }
+
+#[test]
+fn compression_capability() {
+    let mut supported_protocols = BTreeMap::new();
+    supported_protocols.insert(
+        MessagingProtocolVersion::V1,
+        SupportedProtocols::from([ProtocolId::MempoolDirectSend].iter()).with_compression(),
+    );
+    let h1 = HandshakeMsg {
+        supported_protocols,
+        ..HandshakeMsg::default()
+    };
+
+    // negotiated when both peers advertise it, and not mistaken for a protocol
+    let (_, protocols) = h1.perform_handshake(&h1).unwrap();
+    assert!(protocols.supports_compression());
+    let protocols: Vec<ProtocolId> = protocols.try_into().unwrap();
+    assert_eq!(protocols, vec![ProtocolId::MempoolDirectSend]);
+
+    // peers which don't advertise it stay compatible
+    let mut supported_protocols = BTreeMap::new();
+    supported_protocols.insert(
+        MessagingProtocolVersion::V1,
+        [ProtocolId::MempoolDirectSend].iter().into(),
+    );
+    let h2 = HandshakeMsg {
+        supported_protocols,
+        ..HandshakeMsg::default()
+    };
+    let (_, protocols) = h1.perform_handshake(&h2).unwrap();
+    assert!(!protocols.supports_compression());
+    let (_, protocols) = h2.perform_handshake(&h1).unwrap();
+    assert!(!protocols.supports_compression());
+}
@@ -0,0 +1,102 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Compression of message payloads, used on connections where both peers advertised the
+//! compression capability during the handshake.
+//!
+//! On such connections, every payload starts with a one byte marker telling whether the rest of
+//! the payload is sent as is or compressed with DEFLATE. The sender decides per protocol whether
+//! to compress, and falls back to sending the payload as is when compression doesn't make it
+//! smaller. Message headers are never compressed.
+
+use crate::protocols::wire::messaging::v1::NetworkMessage;
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use std::io::{self, Read, Write};
+use thiserror::Error;
+
+/// Marker of a payload sent as is.
+const UNCOMPRESSED: u8 = 0;
+/// Marker of a payload compressed with DEFLATE.
+const DEFLATE: u8 = 1;
+
+#[derive(Debug, Error)]
+pub enum CompressionError {
+    #[error("compressed payload is missing its marker")]
+    MissingMarker,
+    #[error("unknown compression marker: {0}")]
+    UnknownMarker(u8),
+    #[error("decompressed payload exceeds the maximum size of {0} bytes")]
+    TooLarge(usize),
+    #[error("invalid compressed payload: {0}")]
+    InvalidPayload(#[from] io::Error),
+}
+
+/// Returns the payload of the message, if it has one.
+fn payload_mut(message: &mut NetworkMessage) -> Option<&mut Vec<u8>> {
+    match message {
+        NetworkMessage::RpcRequest(request) => Some(&mut request.raw_request),
+        NetworkMessage::RpcResponse(response) => Some(&mut response.raw_response),
+        NetworkMessage::DirectSendMsg(message) => Some(&mut message.raw_msg),
+        NetworkMessage::Error(_) => None,
+    }
+}
+
+fn deflate(payload: &[u8]) -> io::Result<Vec<u8>> {
+    let mut encoder = DeflateEncoder::new(vec![DEFLATE], Compression::fast());
+    encoder.write_all(payload)?;
+    encoder.finish()
+}
+
+fn encode_payload(payload: Vec<u8>, compress: bool) -> Vec<u8> {
+    if compress {
+        if let Ok(compressed) = deflate(&payload) {
+            if compressed.len() < payload.len() {
+                return compressed;
+            }
+        }
+    }
+    let mut encoded = Vec::with_capacity(payload.len() + 1);
+    encoded.push(UNCOMPRESSED);
+    encoded.extend_from_slice(&payload);
+    encoded
+}
+
+fn decode_payload(payload: &[u8], max_size: usize) -> Result<Vec<u8>, CompressionError> {
+    match payload.split_first() {
+        None => Err(CompressionError::MissingMarker),
+        Some((&UNCOMPRESSED, rest)) => Ok(rest.to_vec()),
+        Some((&DEFLATE, rest)) => {
+            // Read one byte past the maximum size to detect oversized payloads without
+            // inflating them entirely.
+            let mut decompressed = Vec::new();
+            DeflateDecoder::new(rest)
+                .take(max_size as u64 + 1)
+                .read_to_end(&mut decompressed)?;
+            if decompressed.len() > max_size {
+                return Err(CompressionError::TooLarge(max_size));
+            }
+            Ok(decompressed)
+        }
+        Some((&marker, _)) => Err(CompressionError::UnknownMarker(marker)),
+    }
+}
+
+/// Encodes the payload of an outbound message, compressing it if `compress` is set.
+pub fn encode_message(mut message: NetworkMessage, compress: bool) -> NetworkMessage {
+    if let Some(payload) = payload_mut(&mut message) {
+        *payload = encode_payload(std::mem::take(payload), compress);
+    }
+    message
+}
+
+/// Decodes the payload of an inbound message, which must not decompress to more than
+/// `max_size` bytes.
+pub fn decode_message(
+    mut message: NetworkMessage,
+    max_size: usize,
+) -> Result<NetworkMessage, CompressionError> {
+    if let Some(payload) = payload_mut(&mut message) {
+        *payload = decode_payload(payload, max_size)?;
+    }
+    Ok(message)
+}
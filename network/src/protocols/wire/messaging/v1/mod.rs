@@ -7,6 +7,7 @@
 use crate::protocols::wire::handshake::v1::ProtocolId;
 use serde::{Deserialize, Serialize};
 
+pub mod compression;
 #[cfg(test)]
 mod test;
 
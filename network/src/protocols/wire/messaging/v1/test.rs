@@ -40,3 +40,63 @@ fn rpc_request() -> lcs::Result<()> {
     );
     Ok(())
 }
+
+#[test]
+fn compression_roundtrip() {
+    let message = NetworkMessage::DirectSendMsg(DirectSendMsg {
+        protocol_id: ProtocolId::MempoolDirectSend,
+        priority: 0,
+        raw_msg: vec![7; 1000],
+    });
+
+    // compressible payloads shrink, and headers are left as is
+    let compressed = compression::encode_message(message.clone(), true);
+    match &compressed {
+        NetworkMessage::DirectSendMsg(msg) => {
+            assert_eq!(msg.protocol_id, ProtocolId::MempoolDirectSend);
+            assert!(msg.raw_msg.len() < 1000);
+        }
+        _ => panic!("unexpected message type"),
+    }
+    assert_eq!(
+        compression::decode_message(compressed, 1000).unwrap(),
+        message
+    );
+
+    // payloads are only prefixed with a marker when not compressed
+    let uncompressed = compression::encode_message(message.clone(), false);
+    assert_eq!(
+        lcs::to_bytes(&uncompressed).unwrap().len(),
+        lcs::to_bytes(&message).unwrap().len() + 1
+    );
+    assert_eq!(
+        compression::decode_message(uncompressed, 1000).unwrap(),
+        message
+    );
+
+    // errors have no payload
+    let error = NetworkMessage::Error(ErrorCode::parsing_error(0, 0));
+    assert_eq!(
+        compression::decode_message(compression::encode_message(error.clone(), true), 0).unwrap(),
+        error
+    );
+}
+
+#[test]
+fn compression_invalid_payloads() {
+    let message = |raw_response| {
+        NetworkMessage::RpcResponse(RpcResponse {
+            request_id: 0,
+            priority: 0,
+            raw_response,
+        })
+    };
+
+    // decompressing past the maximum size fails
+    let compressed = compression::encode_message(message(vec![0; 1001]), true);
+    compression::decode_message(compressed, 1000).unwrap_err();
+
+    compression::decode_message(message(vec![]), 1000).unwrap_err();
+    compression::decode_message(message(vec![2, 0]), 1000).unwrap_err();
+    compression::decode_message(message(vec![1, 0xff, 0xff]), 1000).unwrap_err();
+}
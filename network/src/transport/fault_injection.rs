@@ -0,0 +1,613 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Fault injection for testing the network under degraded conditions.
+//!
+//! [`FaultyTransport`] wraps the sockets of a base transport, e.g. tcp, so that the bytes
+//! received on them are subject to the faults of the link they come from: latency, jitter, packet
+//! loss, bandwidth caps and partitions. Faults are set per directed link between two peers in a
+//! shared [`NetworkFaults`] handle, and take effect immediately on the established connections.
+//!
+//! Faults apply below the Noise handshake, which is delayed or cut like any other traffic. The
+//! remote peer of an inbound connection is only known once the handshake authenticated it, so
+//! until then inbound connections get the default faults. [`FaultInjectionTransport`] wraps the
+//! `LibraNetTransport` over a [`FaultyTransport`] to switch them to the faults of their link.
+//!
+//! Faults are applied by the receiving end: the link `a -> b` is degraded by the node `b`. Nodes
+//! sharing a handle, e.g. in the same process, thus apply the faults of each link exactly once.
+//!
+//! Connections are reliable streams, so nothing is actually dropped. A lost packet is modeled as
+//! a retransmission delay, and the bytes of a partitioned link are held back until it is healed,
+//! which looks to the sender like an unresponsive peer.
+
+use crate::{
+    noise::stream::NoiseStream,
+    transport::{self, Connection},
+};
+use bytes::Bytes;
+use futures::{
+    future::{Future, FutureExt, TryFutureExt},
+    io::{AsyncRead, AsyncWrite},
+    ready,
+    stream::{Stream, StreamExt, TryStreamExt},
+};
+use libra_infallible::RwLock;
+use libra_network_address::NetworkAddress;
+use libra_types::PeerId;
+use netcore::transport::Transport;
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt, io,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+use tokio::time::Delay;
+
+/// Extra delay of the bytes of a lost packet, as TCP's minimum retransmission timeout.
+const MIN_RETRANSMISSION_DELAY: Duration = Duration::from_millis(200);
+/// How often a partitioned link checks whether it was healed.
+const PARTITION_CHECK_INTERVAL: Duration = Duration::from_millis(50);
+/// Size of the reads from the underlying socket, each read is a "packet".
+const READ_SIZE: usize = 8 * 1024;
+/// Maximum number of bytes waiting for their delivery, before applying backpressure.
+const MAX_PENDING_BYTES: usize = 256 * 1024;
+
+/// Faults of a directed link between two peers.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LinkFaults {
+    /// Delay of the bytes sent over the link
+    pub latency_ms: u64,
+    /// Maximum random variation of the latency, in either direction. Bytes are never reordered.
+    pub jitter_ms: u64,
+    /// Probability, between 0 and 1, that a packet is lost and has to be retransmitted
+    pub loss_rate: f64,
+    /// Bandwidth of the link, unlimited if not set
+    pub max_bytes_per_sec: Option<u64>,
+    /// Nothing gets through the link
+    pub partitioned: bool,
+}
+
+impl LinkFaults {
+    pub fn is_none(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// All the faults of a [`NetworkFaults`] handle.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkFaultsConfig {
+    /// Faults of the links which are not listed
+    pub default: LinkFaults,
+    /// Faults of specific links, as `(from, to, faults)`
+    pub links: Vec<(PeerId, PeerId, LinkFaults)>,
+}
+
+#[derive(Debug, Default)]
+struct Faults {
+    default: LinkFaults,
+    links: HashMap<(PeerId, PeerId), LinkFaults>,
+}
+
+/// A shared and runtime-modifiable set of link faults.
+#[derive(Clone, Debug, Default)]
+pub struct NetworkFaults(Arc<RwLock<Faults>>);
+
+impl NetworkFaults {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The faults of the links which have no faults of their own.
+    pub fn default_link(&self) -> LinkFaults {
+        self.0.read().default
+    }
+
+    /// The faults of the link `from -> to`.
+    pub fn link(&self, from: PeerId, to: PeerId) -> LinkFaults {
+        let faults = self.0.read();
+        faults
+            .links
+            .get(&(from, to))
+            .copied()
+            .unwrap_or(faults.default)
+    }
+
+    /// Set the faults of the links which have no faults of their own.
+    pub fn set_default(&self, faults: LinkFaults) {
+        self.0.write().default = faults;
+    }
+
+    pub fn set_link(&self, from: PeerId, to: PeerId, faults: LinkFaults) {
+        self.0.write().links.insert((from, to), faults);
+    }
+
+    /// The link `from -> to` goes back to the default faults.
+    pub fn clear_link(&self, from: PeerId, to: PeerId) {
+        self.0.write().links.remove(&(from, to));
+    }
+
+    /// Cut the link `from -> to`, keeping its other faults. The reverse link is not affected.
+    pub fn partition(&self, from: PeerId, to: PeerId) {
+        self.set_partitioned(from, to, true);
+    }
+
+    /// Undo [`NetworkFaults::partition`].
+    pub fn heal(&self, from: PeerId, to: PeerId) {
+        self.set_partitioned(from, to, false);
+    }
+
+    fn set_partitioned(&self, from: PeerId, to: PeerId, partitioned: bool) {
+        let mut faults = self.0.write();
+        let default = faults.default;
+        faults
+            .links
+            .entry((from, to))
+            .or_insert(default)
+            .partitioned = partitioned;
+    }
+
+    /// Remove all faults.
+    pub fn reset(&self) {
+        self.set_config(NetworkFaultsConfig::default());
+    }
+
+    pub fn config(&self) -> NetworkFaultsConfig {
+        let faults = self.0.read();
+        NetworkFaultsConfig {
+            default: faults.default,
+            links: faults
+                .links
+                .iter()
+                .map(|((from, to), link)| (*from, *to, *link))
+                .collect(),
+        }
+    }
+
+    /// Replace all the faults.
+    pub fn set_config(&self, config: NetworkFaultsConfig) {
+        let mut faults = self.0.write();
+        faults.default = config.default;
+        faults.links = config
+            .links
+            .into_iter()
+            .map(|(from, to, link)| ((from, to), link))
+            .collect();
+    }
+}
+
+/// A base transport whose sockets are subject to the faults of a [`NetworkFaults`] handle.
+pub struct FaultyTransport<TTransport> {
+    transport: TTransport,
+    local_peer_id: PeerId,
+    faults: NetworkFaults,
+}
+
+impl<TTransport> FaultyTransport<TTransport> {
+    pub fn new(transport: TTransport, local_peer_id: PeerId, faults: NetworkFaults) -> Self {
+        Self {
+            transport,
+            local_peer_id,
+            faults,
+        }
+    }
+}
+
+impl<TTransport> Transport for FaultyTransport<TTransport>
+where
+    TTransport: Transport<Error = io::Error> + Send + 'static,
+    TTransport::Output: transport::TSocket,
+    TTransport::Outbound: Send + 'static,
+    TTransport::Inbound: Send + 'static,
+    TTransport::Listener: Send + 'static,
+{
+    type Output = FaultySocket<TTransport::Output>;
+    type Error = io::Error;
+    type Inbound = Pin<Box<dyn Future<Output = io::Result<Self::Output>> + Send + 'static>>;
+    type Outbound = Pin<Box<dyn Future<Output = io::Result<Self::Output>> + Send + 'static>>;
+    type Listener =
+        Pin<Box<dyn Stream<Item = io::Result<(Self::Inbound, NetworkAddress)>> + Send + 'static>>;
+
+    fn listen_on(&self, addr: NetworkAddress) -> io::Result<(Self::Listener, NetworkAddress)> {
+        let (listener, listen_addr) = self.transport.listen_on(addr)?;
+        let local_peer_id = self.local_peer_id;
+        let faults = self.faults.clone();
+        let listener = listener
+            .map_ok(move |(inbound, addr)| {
+                let faults = faults.clone();
+                // the remote peer is identified by the handshake
+                let inbound = inbound
+                    .map_ok(move |socket| FaultySocket::new(socket, None, local_peer_id, faults))
+                    .boxed();
+                (inbound, addr)
+            })
+            .boxed();
+        Ok((listener, listen_addr))
+    }
+
+    fn dial(&self, peer_id: PeerId, addr: NetworkAddress) -> io::Result<Self::Outbound> {
+        let local_peer_id = self.local_peer_id;
+        let faults = self.faults.clone();
+        let outbound = self
+            .transport
+            .dial(peer_id, addr)?
+            .map_ok(move |socket| FaultySocket::new(socket, Some(peer_id), local_peer_id, faults))
+            .boxed();
+        Ok(outbound)
+    }
+}
+
+/// A `LibraNetTransport` over a [`FaultyTransport`], whose authenticated connections get the
+/// faults of the link with their remote peer.
+pub struct FaultInjectionTransport<TTransport> {
+    transport: TTransport,
+}
+
+impl<TTransport> FaultInjectionTransport<TTransport> {
+    pub fn new(transport: TTransport) -> Self {
+        Self { transport }
+    }
+}
+
+impl<TTransport, TSocket> Transport for FaultInjectionTransport<TTransport>
+where
+    TTransport: Transport<Output = Connection<NoiseStream<FaultySocket<TSocket>>>, Error = io::Error>
+        + Send
+        + 'static,
+    TTransport::Outbound: Send + 'static,
+    TTransport::Inbound: Send + 'static,
+    TTransport::Listener: Send + 'static,
+    TSocket: 'static,
+{
+    type Output = TTransport::Output;
+    type Error = io::Error;
+    type Inbound = Pin<Box<dyn Future<Output = io::Result<Self::Output>> + Send + 'static>>;
+    type Outbound = Pin<Box<dyn Future<Output = io::Result<Self::Output>> + Send + 'static>>;
+    type Listener =
+        Pin<Box<dyn Stream<Item = io::Result<(Self::Inbound, NetworkAddress)>> + Send + 'static>>;
+
+    fn listen_on(&self, addr: NetworkAddress) -> io::Result<(Self::Listener, NetworkAddress)> {
+        let (listener, listen_addr) = self.transport.listen_on(addr)?;
+        let listener = listener
+            .map_ok(|(inbound, addr)| (inbound.map_ok(identify_remote_peer).boxed(), addr))
+            .boxed();
+        Ok((listener, listen_addr))
+    }
+
+    fn dial(&self, peer_id: PeerId, addr: NetworkAddress) -> io::Result<Self::Outbound> {
+        Ok(self
+            .transport
+            .dial(peer_id, addr)?
+            .map_ok(identify_remote_peer)
+            .boxed())
+    }
+}
+
+fn identify_remote_peer<TSocket>(
+    mut connection: Connection<NoiseStream<FaultySocket<TSocket>>>,
+) -> Connection<NoiseStream<FaultySocket<TSocket>>> {
+    let remote_peer_id = connection.metadata.remote_peer_id;
+    connection
+        .socket
+        .get_mut()
+        .set_remote_peer_id(remote_peer_id);
+    connection
+}
+
+/// A socket whose received bytes are subject to the faults of the link they come from.
+pub struct FaultySocket<TSocket> {
+    socket: TSocket,
+    /// The remote peer, bytes are read from the link `from -> to`. Unknown until the handshake
+    /// of inbound connections, which get the default faults meanwhile.
+    from: Option<PeerId>,
+    to: PeerId,
+    faults: NetworkFaults,
+    /// Bytes read from the socket with their delivery time
+    pending: VecDeque<(Instant, Bytes)>,
+    pending_len: usize,
+    read_buf: Box<[u8]>,
+    eof: bool,
+    /// When the link is done transmitting the bytes read so far, when its bandwidth is capped
+    link_free_at: Instant,
+    last_delivery: Instant,
+    delay: Option<Delay>,
+    rng: SmallRng,
+}
+
+impl<TSocket> FaultySocket<TSocket> {
+    pub fn new(socket: TSocket, from: Option<PeerId>, to: PeerId, faults: NetworkFaults) -> Self {
+        let now = Instant::now();
+        Self {
+            socket,
+            from,
+            to,
+            faults,
+            pending: VecDeque::new(),
+            pending_len: 0,
+            read_buf: vec![0; READ_SIZE].into_boxed_slice(),
+            eof: false,
+            link_free_at: now,
+            last_delivery: now,
+            delay: None,
+            rng: SmallRng::from_entropy(),
+        }
+    }
+
+    pub fn set_remote_peer_id(&mut self, remote_peer_id: PeerId) {
+        self.from = Some(remote_peer_id);
+    }
+
+    fn link_faults(&self) -> LinkFaults {
+        match self.from {
+            Some(from) => self.faults.link(from, self.to),
+            None => self.faults.default_link(),
+        }
+    }
+
+    /// When bytes read now are delivered.
+    fn delivery_time(&mut self, faults: &LinkFaults, len: usize, now: Instant) -> Instant {
+        let transmitted_at = match faults.max_bytes_per_sec {
+            Some(bytes_per_sec) => {
+                let start = self.link_free_at.max(now);
+                self.link_free_at =
+                    start + Duration::from_secs_f64(len as f64 / bytes_per_sec.max(1) as f64);
+                self.link_free_at
+            }
+            None => now,
+        };
+
+        let latency = Duration::from_millis(faults.latency_ms);
+        let jitter = faults.jitter_ms as i64;
+        let mut delay = if jitter > 0 {
+            let latency_ms = faults.latency_ms as i64 + self.rng.gen_range(-jitter, jitter + 1);
+            Duration::from_millis(latency_ms.max(0) as u64)
+        } else {
+            latency
+        };
+        if faults.loss_rate > 0.0 && self.rng.gen_bool(faults.loss_rate.min(1.0)) {
+            delay += MIN_RETRANSMISSION_DELAY.max(2 * latency);
+        }
+
+        self.last_delivery = self.last_delivery.max(transmitted_at + delay);
+        self.last_delivery
+    }
+
+    fn poll_delay(&mut self, context: &mut Context, deadline: Instant) -> Poll<()> {
+        let deadline = deadline.into();
+        match self.delay.as_mut() {
+            Some(delay) if delay.deadline() != deadline => delay.reset(deadline),
+            Some(_) => (),
+            None => self.delay = Some(tokio::time::delay_until(deadline)),
+        }
+        Pin::new(self.delay.as_mut().expect("delay must be set")).poll(context)
+    }
+}
+
+impl<TSocket: AsyncRead + Unpin> AsyncRead for FaultySocket<TSocket> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        loop {
+            let faults = this.link_faults();
+            let now = Instant::now();
+            if faults.partitioned {
+                // The socket is not read, which eventually blocks the sender.
+                ready!(this.poll_delay(context, now + PARTITION_CHECK_INTERVAL));
+                continue;
+            }
+            if faults.is_none() && this.pending.is_empty() {
+                return Pin::new(&mut this.socket).poll_read(context, buf);
+            }
+
+            // Read ahead, so the delivery times are based on when the bytes were received.
+            while !this.eof && this.pending_len < MAX_PENDING_BYTES {
+                match Pin::new(&mut this.socket).poll_read(context, &mut this.read_buf) {
+                    Poll::Ready(Ok(0)) => this.eof = true,
+                    Poll::Ready(Ok(len)) => {
+                        let delivery_time = this.delivery_time(&faults, len, now);
+                        let bytes = Bytes::copy_from_slice(&this.read_buf[..len]);
+                        this.pending.push_back((delivery_time, bytes));
+                        this.pending_len += len;
+                    }
+                    Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                    Poll::Pending => break,
+                }
+            }
+
+            match this.pending.front_mut() {
+                Some((delivery_time, bytes)) if *delivery_time <= now => {
+                    let len = bytes.len().min(buf.len());
+                    buf[..len].copy_from_slice(&bytes.split_to(len));
+                    if bytes.is_empty() {
+                        this.pending.pop_front();
+                    }
+                    this.pending_len -= len;
+                    return Poll::Ready(Ok(len));
+                }
+                Some((delivery_time, _)) => {
+                    let delivery_time = *delivery_time;
+                    ready!(this.poll_delay(context, delivery_time));
+                }
+                None if this.eof => return Poll::Ready(Ok(0)),
+                // The socket will wake us up when there's more to read.
+                None => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl<TSocket: AsyncWrite + Unpin> AsyncWrite for FaultySocket<TSocket> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.socket).poll_write(context, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.socket).poll_flush(context)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.socket).poll_close(context)
+    }
+}
+
+impl<TSocket: fmt::Debug> fmt::Debug for FaultySocket<TSocket> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FaultySocket")
+            .field("socket", &self.socket)
+            .field("from", &self.from)
+            .field("to", &self.to)
+            .field("pending_len", &self.pending_len)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures::io::{AsyncReadExt, AsyncWriteExt};
+    use memsocket::MemorySocket;
+    use tokio::{runtime::Runtime, time::timeout};
+
+    fn socket_pair(faults: &NetworkFaults) -> (MemorySocket, FaultySocket<MemorySocket>, PeerId) {
+        let (sender, receiver) = MemorySocket::new_pair();
+        let (from, to) = (PeerId::random(), PeerId::random());
+        let receiver = FaultySocket::new(receiver, Some(from), to, faults.clone());
+        (sender, receiver, from)
+    }
+
+    #[test]
+    fn test_latency() {
+        let faults = NetworkFaults::new();
+        let (mut sender, mut receiver, _) = socket_pair(&faults);
+        faults.set_default(LinkFaults {
+            latency_ms: 100,
+            ..LinkFaults::default()
+        });
+
+        Runtime::new().unwrap().block_on(async move {
+            let start = Instant::now();
+            sender.write_all(b"hello").await.unwrap();
+            sender.flush().await.unwrap();
+            let mut buf = [0; 5];
+            receiver.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"hello");
+            assert!(start.elapsed() >= Duration::from_millis(100));
+        });
+    }
+
+    #[test]
+    fn test_bandwidth() {
+        let faults = NetworkFaults::new();
+        let (mut sender, mut receiver, _) = socket_pair(&faults);
+        faults.set_default(LinkFaults {
+            max_bytes_per_sec: Some(40_000),
+            ..LinkFaults::default()
+        });
+
+        Runtime::new().unwrap().block_on(async move {
+            let start = Instant::now();
+            let data = vec![7; 8_000];
+            let write = async move {
+                sender.write_all(&data).await.unwrap();
+                sender.flush().await.unwrap();
+            };
+            let read = async move {
+                let mut buf = vec![0; 8_000];
+                receiver.read_exact(&mut buf).await.unwrap();
+                buf
+            };
+            let (_, buf) = futures::join!(write, read);
+            assert_eq!(buf, vec![7; 8_000]);
+            assert!(start.elapsed() >= Duration::from_millis(200));
+        });
+    }
+
+    #[test]
+    fn test_partition() {
+        let faults = NetworkFaults::new();
+        let (mut sender, mut receiver, from) = socket_pair(&faults);
+        let to = receiver.to;
+        faults.partition(from, to);
+        // the reverse link is not affected
+        assert!(!faults.link(to, from).partitioned);
+
+        Runtime::new().unwrap().block_on(async move {
+            sender.write_all(b"hello").await.unwrap();
+            sender.flush().await.unwrap();
+            let mut buf = [0; 5];
+            assert!(
+                timeout(Duration::from_millis(200), receiver.read_exact(&mut buf))
+                    .await
+                    .is_err()
+            );
+
+            faults.heal(from, to);
+            receiver.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"hello");
+            assert!(faults.link(from, to).is_none());
+        });
+    }
+
+    #[test]
+    fn test_unidentified_remote_peer() {
+        let faults = NetworkFaults::new();
+        let (mut sender, receiver) = MemorySocket::new_pair();
+        let (from, to) = (PeerId::random(), PeerId::random());
+        let mut receiver = FaultySocket::new(receiver, None, to, faults.clone());
+        faults.partition(from, to);
+
+        Runtime::new().unwrap().block_on(async move {
+            // the default faults apply until the remote peer is known
+            sender.write_all(b"hello").await.unwrap();
+            sender.flush().await.unwrap();
+            let mut buf = [0; 5];
+            receiver.read_exact(&mut buf).await.unwrap();
+
+            receiver.set_remote_peer_id(from);
+            sender.write_all(b"hello").await.unwrap();
+            sender.flush().await.unwrap();
+            assert!(
+                timeout(Duration::from_millis(200), receiver.read_exact(&mut buf))
+                    .await
+                    .is_err()
+            );
+        });
+    }
+
+    #[test]
+    fn test_config_round_trip() {
+        let faults = NetworkFaults::new();
+        let (from, to) = (PeerId::random(), PeerId::random());
+        let link = LinkFaults {
+            latency_ms: 50,
+            jitter_ms: 10,
+            loss_rate: 0.1,
+            max_bytes_per_sec: Some(1_000_000),
+            partitioned: false,
+        };
+        faults.set_link(from, to, link);
+
+        let config = faults.config();
+        assert_eq!(config.links, vec![(from, to, link)]);
+        let json = serde_json::to_string(&config).unwrap();
+        let other = NetworkFaults::new();
+        other.set_config(serde_json::from_str(&json).unwrap());
+        assert_eq!(other.link(from, to), link);
+        assert!(other.link(to, from).is_none());
+
+        faults.reset();
+        assert!(faults.link(from, to).is_none());
+    }
+}
@@ -21,7 +21,9 @@ use libra_config::{
 use libra_crypto::x25519;
 use libra_infallible::RwLock;
 use libra_logger::prelude::*;
-use libra_network_address::{parse_dns_tcp, parse_ip_tcp, parse_memory, NetworkAddress};
+use libra_network_address::{
+    parse_dns_quic, parse_dns_tcp, parse_ip_quic, parse_ip_tcp, parse_memory, NetworkAddress,
+};
 use libra_types::{chain_id::ChainId, PeerId};
 use netcore::transport::{proxy_protocol, tcp, ConnectionOrigin, Transport};
 use serde::{export::Formatter, Serialize};
@@ -39,6 +41,9 @@ use std::{
 };
 use tokio::time::timeout;
 
+pub mod fault_injection;
+#[cfg(feature = "quic")]
+pub mod quic;
 #[cfg(test)]
 mod test;
 
@@ -200,6 +205,52 @@ fn add_pp_addr(proxy_protocol_enabled: bool, error: io::Error, addr: &NetworkAdd
     }
 }
 
+/// A failed inbound handshake, carried by the `io::Error` of the upgrade so that admission
+/// control knows who failed it.
+#[derive(Debug)]
+pub struct InboundUpgradeError {
+    /// Address of the dialer, as read from the proxy protocol header if enabled
+    pub addr: NetworkAddress,
+    /// The dialer, if it was authenticated before the failure
+    pub remote_peer_id: Option<PeerId>,
+    error: io::Error,
+}
+
+impl InboundUpgradeError {
+    fn new(
+        proxy_protocol_enabled: bool,
+        error: io::Error,
+        addr: &NetworkAddress,
+        remote_peer_id: Option<PeerId>,
+    ) -> io::Error {
+        let kind = error.kind();
+        io::Error::new(
+            kind,
+            InboundUpgradeError {
+                addr: addr.clone(),
+                remote_peer_id,
+                error: add_pp_addr(proxy_protocol_enabled, error, addr),
+            },
+        )
+    }
+
+    /// The failed handshake behind an upgrade error, if it got that far.
+    pub fn find(error: &(dyn std::error::Error + 'static)) -> Option<&InboundUpgradeError> {
+        error
+            .downcast_ref::<io::Error>()
+            .and_then(|error| error.get_ref())
+            .and_then(|error| error.downcast_ref::<InboundUpgradeError>())
+    }
+}
+
+impl std::fmt::Display for InboundUpgradeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+impl std::error::Error for InboundUpgradeError {}
+
 /// Upgrade an inbound connection. This means we run a Noise IK handshake for
 /// authentication and then negotiate common supported protocols. If
 /// `ctxt.trusted_peers` is `Some(_)`, then we will only allow connections from
@@ -247,7 +298,7 @@ async fn upgrade_inbound<T: TSocket>(
             );
         }
         let err = io::Error::new(io::ErrorKind::Other, err);
-        add_pp_addr(proxy_protocol_enabled, err, &addr)
+        InboundUpgradeError::new(proxy_protocol_enabled, err, &addr, None)
     })?;
     let remote_pubkey = socket.get_remote_static();
     let addr = addr.append_prod_protos(remote_pubkey, HANDSHAKE_VERSION);
@@ -260,7 +311,9 @@ async fn upgrade_inbound<T: TSocket>(
     };
     let remote_handshake = exchange_handshake(&handshake_msg, &mut socket)
         .await
-        .map_err(|err| add_pp_addr(proxy_protocol_enabled, err, &addr))?;
+        .map_err(|err| {
+            InboundUpgradeError::new(proxy_protocol_enabled, err, &addr, Some(remote_peer_id))
+        })?;
 
     // try to negotiate common libranet version and supported application protocols
     let (messaging_protocol, application_protocols) = handshake_msg
@@ -271,10 +324,11 @@ async fn upgrade_inbound<T: TSocket>(
                 remote_peer_id.short_str(),
                 err
             );
-            add_pp_addr(
+            InboundUpgradeError::new(
                 proxy_protocol_enabled,
                 io::Error::new(io::ErrorKind::Other, err),
                 &addr,
+                Some(remote_peer_id),
             )
         })?;
 
@@ -441,13 +495,15 @@ where
         let (base_transport_protos, base_transport_suffix) = parse_ip_tcp(protos)
             .map(|x| (&protos[..2], x.1))
             .or_else(|| parse_dns_tcp(protos).map(|x| (&protos[..2], x.1)))
+            .or_else(|| parse_ip_quic(protos).map(|x| (&protos[..2], x.1)))
+            .or_else(|| parse_dns_quic(protos).map(|x| (&protos[..2], x.1)))
             .or_else(|| parse_memory(protos).map(|x| (&protos[..1], x.1)))
             .ok_or_else(|| {
                 io::Error::new(
                     io::ErrorKind::InvalidInput,
                     format!(
                         "Unexpected dialing network address: '{}', expected: \
-                         memory, ip+tcp, or dns+tcp",
+                         memory, ip+tcp, dns+tcp, ip+quic, or dns+quic",
                         addr
                     ),
                 )
@@ -493,6 +549,9 @@ where
     /// `/dns/<ipaddr>/tcp/<port>` or
     /// `/dns4/<ipaddr>/tcp/<port>` or
     /// `/dns6/<ipaddr>/tcp/<port>`
+    ///
+    /// If the base transport is `QuicTransport`, then `/<base_transport>` is any of the
+    /// above with `/quic/<port>` in place of `/tcp/<port>`.
     pub fn dial(
         &self,
         peer_id: PeerId,
@@ -539,6 +598,11 @@ where
     ///
     /// `/ip4/<ipaddr>/tcp/<port>` or
     /// `/ip6/<ipaddr>/tcp/<port>`
+    ///
+    /// If the base transport is `QuicTransport`, then we expect:
+    ///
+    /// `/ip4/<ipaddr>/quic/<port>` or
+    /// `/ip6/<ipaddr>/quic/<port>`
     pub fn listen_on(
         &self,
         addr: NetworkAddress,
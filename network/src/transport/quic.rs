@@ -0,0 +1,576 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! LibraNet over QUIC.
+//!
+//! Connections are first upgraded as with any other base transport: the Noise IK handshake and
+//! the `HandshakeMsg` exchange run on the control stream of the QUIC connection, so peers are
+//! authenticated exactly as over tcp. Both sides then bind the QUIC connection to the Noise
+//! session by exchanging, over Noise, the fingerprint of the TLS certificate of the listener as
+//! they see it. A man in the middle terminating QUIC has to present its own certificate, which
+//! makes the fingerprints differ.
+//!
+//! Once bound, [`QuicMuxSocket`] carries the messages of each `ProtocolId` on a separate QUIC
+//! stream, so that e.g. large state sync chunks don't delay consensus messages. The Peer actor
+//! still sees a single stream of length-delimited `NetworkMessage` frames.
+
+use crate::{
+    noise::stream::NoiseStream,
+    protocols::wire::{
+        handshake::v1::{ProtocolId, SupportedProtocols},
+        messaging::v1::{DirectSendMsg, NetworkMessage, RequestId, RpcRequest, RpcResponse},
+    },
+    transport::{timeout_io, Connection, LibraNetTransport, TRANSPORT_TIMEOUT},
+};
+use bytes::{Buf, Bytes, BytesMut};
+use futures::{
+    channel::mpsc,
+    future::{self, Future, FutureExt, TryFutureExt},
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    ready,
+    stream::{Stream, StreamExt, TryStreamExt},
+};
+use libra_config::network_id::NetworkContext;
+use libra_crypto::{x25519, HashValue};
+use libra_infallible::RwLock;
+use libra_logger::prelude::*;
+use libra_network_address::NetworkAddress;
+use libra_types::{chain_id::ChainId, PeerId};
+use netcore::transport::{
+    quic::{QuicSocket, QuicTransport},
+    Transport,
+};
+use once_cell::sync::Lazy;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt, io,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::task::JoinHandle;
+
+/// Length of the big endian length prefix of the frames written by the Peer actor.
+const FRAME_LEN_PREFIX: usize = 4;
+/// Maximum number of frames queued for each outbound stream.
+const STREAM_QUEUE_SIZE: usize = 64;
+/// Maximum number of inbound frames queued for the Peer actor.
+const INBOUND_QUEUE_SIZE: usize = 1024;
+/// Maximum number of inbound rpc requests tracked to send their responses on the stream of
+/// their protocol. Beyond it, the oldest requests are forgotten and their responses, if any, are
+/// sent on the default stream.
+const MAX_TRACKED_RPCS: usize = 1024;
+
+// LCS variant indices of `NetworkMessage`, i.e. the first byte of its frames
+static RPC_REQUEST: Lazy<u8> = Lazy::new(|| {
+    variant_index(NetworkMessage::RpcRequest(RpcRequest {
+        protocol_id: ProtocolId::ConsensusRpc,
+        request_id: 0,
+        priority: 0,
+        raw_request: vec![],
+    }))
+});
+static RPC_RESPONSE: Lazy<u8> = Lazy::new(|| {
+    variant_index(NetworkMessage::RpcResponse(RpcResponse {
+        request_id: 0,
+        priority: 0,
+        raw_response: vec![],
+    }))
+});
+static DIRECT_SEND_MSG: Lazy<u8> = Lazy::new(|| {
+    variant_index(NetworkMessage::DirectSendMsg(DirectSendMsg {
+        protocol_id: ProtocolId::ConsensusDirectSend,
+        priority: 0,
+        raw_msg: vec![],
+    }))
+});
+
+fn variant_index(message: NetworkMessage) -> u8 {
+    lcs::to_bytes(&message).expect("NetworkMessage serialization can't fail")[0]
+}
+
+/// The `LibraNetTransport` over `QuicTransport`, multiplexing protocols over QUIC streams.
+pub struct LibraNetQuicTransport {
+    inner: LibraNetTransport<QuicTransport>,
+    max_frame_size: usize,
+}
+
+impl LibraNetQuicTransport {
+    pub fn new(
+        network_context: Arc<NetworkContext>,
+        identity_key: x25519::PrivateKey,
+        trusted_peers: Option<Arc<RwLock<HashMap<PeerId, HashSet<x25519::PublicKey>>>>>,
+        handshake_version: u8,
+        chain_id: ChainId,
+        application_protocols: SupportedProtocols,
+        max_frame_size: usize,
+    ) -> Self {
+        Self {
+            inner: LibraNetTransport::new(
+                QuicTransport,
+                network_context,
+                identity_key,
+                trusted_peers,
+                handshake_version,
+                chain_id,
+                application_protocols,
+                // the proxy protocol is a tcp thing
+                false,
+            ),
+            max_frame_size,
+        }
+    }
+}
+
+impl Transport for LibraNetQuicTransport {
+    type Output = Connection<QuicMuxSocket>;
+    type Error = io::Error;
+    type Inbound = Pin<Box<dyn Future<Output = io::Result<Self::Output>> + Send + 'static>>;
+    type Outbound = Pin<Box<dyn Future<Output = io::Result<Self::Output>> + Send + 'static>>;
+    type Listener =
+        Pin<Box<dyn Stream<Item = io::Result<(Self::Inbound, NetworkAddress)>> + Send + 'static>>;
+
+    fn dial(&self, peer_id: PeerId, addr: NetworkAddress) -> io::Result<Self::Outbound> {
+        let upgrade_fut = self.inner.dial(peer_id, addr)?;
+        let max_frame_size = self.max_frame_size;
+        Ok(upgrade_fut
+            .and_then(move |connection| {
+                timeout_io(
+                    TRANSPORT_TIMEOUT,
+                    bind_connection(connection, max_frame_size),
+                )
+            })
+            .boxed())
+    }
+
+    fn listen_on(&self, addr: NetworkAddress) -> io::Result<(Self::Listener, NetworkAddress)> {
+        let (listener, listen_addr) = self.inner.listen_on(addr)?;
+        let max_frame_size = self.max_frame_size;
+        let listener = listener
+            .map_ok(move |(upgrade_fut, addr)| {
+                let upgrade_fut = upgrade_fut
+                    .and_then(move |connection| {
+                        timeout_io(
+                            TRANSPORT_TIMEOUT,
+                            bind_connection(connection, max_frame_size),
+                        )
+                    })
+                    .boxed();
+                (upgrade_fut, addr)
+            })
+            .boxed();
+        Ok((listener, listen_addr))
+    }
+}
+
+/// Binds the QUIC connection to the authenticated Noise session, then switches it to
+/// per-protocol streams.
+async fn bind_connection(
+    connection: Connection<NoiseStream<QuicSocket>>,
+    max_frame_size: usize,
+) -> io::Result<Connection<QuicMuxSocket>> {
+    let Connection {
+        mut socket,
+        metadata,
+    } = connection;
+
+    let fingerprint = HashValue::sha3_256_of(socket.get_ref().server_certificate()).to_vec();
+    socket.write_all(&fingerprint).await?;
+    socket.flush().await?;
+    let mut remote_fingerprint = [0u8; HashValue::LENGTH];
+    socket.read_exact(&mut remote_fingerprint).await?;
+    if remote_fingerprint[..] != fingerprint[..] {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "QUIC certificate of the connection with peer {} doesn't match the one seen by \
+                 the peer",
+                metadata.remote_peer_id.short_str()
+            ),
+        ));
+    }
+
+    Ok(Connection {
+        socket: QuicMuxSocket::new(socket, max_frame_size),
+        metadata,
+    })
+}
+
+/// Where an outbound frame is sent.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum StreamKey {
+    /// The stream of a `ProtocolId`, identified by its LCS encoding
+    Protocol(u8),
+    /// Messages without protocol, e.g. errors
+    Default,
+}
+
+/// Extracts the `ProtocolId` and `RequestId` of an rpc request frame, without deserializing it.
+fn parse_rpc_request(frame: &[u8]) -> Option<(u8, RequestId)> {
+    match frame {
+        [message_type, protocol_id, a, b, c, d, ..] if *message_type == *RPC_REQUEST => {
+            Some((*protocol_id, RequestId::from_le_bytes([*a, *b, *c, *d])))
+        }
+        _ => None,
+    }
+}
+
+/// Chooses the stream of an outbound frame: requests and messages go on the stream of their
+/// protocol, responses on the stream of the protocol of the request.
+fn stream_key(frame: &[u8], inbound_rpcs: &mut InboundRpcs) -> StreamKey {
+    match frame {
+        [message_type, protocol_id, ..]
+            if *message_type == *RPC_REQUEST || *message_type == *DIRECT_SEND_MSG =>
+        {
+            StreamKey::Protocol(*protocol_id)
+        }
+        [message_type, a, b, c, d, ..] if *message_type == *RPC_RESPONSE => inbound_rpcs
+            .remove(RequestId::from_le_bytes([*a, *b, *c, *d]))
+            .map_or(StreamKey::Default, StreamKey::Protocol),
+        _ => StreamKey::Default,
+    }
+}
+
+/// The protocols of the inbound rpc requests not yet responded to, by request id.
+///
+/// Requests declined or timed out by the Rpc actor never get a response, so at most
+/// `MAX_TRACKED_RPCS` requests are tracked, forgetting the oldest ones first.
+#[derive(Default)]
+struct InboundRpcs {
+    protocols: HashMap<RequestId, u8>,
+    /// Request ids in arrival order, including the ones already responded to
+    arrivals: VecDeque<RequestId>,
+}
+
+impl InboundRpcs {
+    fn insert(&mut self, request_id: RequestId, protocol_id: u8) {
+        if self.protocols.len() >= MAX_TRACKED_RPCS {
+            while let Some(oldest) = self.arrivals.pop_front() {
+                if self.protocols.remove(&oldest).is_some() {
+                    sample!(
+                        SampleRate::Duration(Duration::from_secs(60)),
+                        warn!(
+                            "Tracking more than {} inbound rpcs over QUIC, the response to \
+                             request {} will be sent on the default stream",
+                            MAX_TRACKED_RPCS, oldest
+                        )
+                    );
+                    break;
+                }
+            }
+        }
+        self.protocols.insert(request_id, protocol_id);
+        self.arrivals.push_back(request_id);
+        // drop the ids of the requests responded to
+        if self.arrivals.len() > 2 * MAX_TRACKED_RPCS {
+            let protocols = &self.protocols;
+            self.arrivals
+                .retain(|request_id| protocols.contains_key(request_id));
+        }
+    }
+
+    fn remove(&mut self, request_id: RequestId) -> Option<u8> {
+        self.protocols.remove(&request_id)
+    }
+}
+
+/// A `QuicSocket` presenting its per-protocol streams as a single stream of length-delimited
+/// frames.
+///
+/// Outbound frames are routed to the stream of their protocol, which is opened on first use.
+/// Inbound frames are read from all the streams opened by the remote and interleaved frame by
+/// frame.
+pub struct QuicMuxSocket {
+    /// The authenticated control stream, which holds the connection
+    control: NoiseStream<QuicSocket>,
+    connection: quinn::Connection,
+    max_frame_size: usize,
+    /// Outbound bytes not yet routed to a stream
+    write_buf: BytesMut,
+    outbound_streams: HashMap<StreamKey, mpsc::Sender<Bytes>>,
+    writer_tasks: Vec<JoinHandle<()>>,
+    inbound_rpcs: InboundRpcs,
+    inbound_frames: mpsc::Receiver<Bytes>,
+    read_buf: Bytes,
+}
+
+impl QuicMuxSocket {
+    fn new(mut control: NoiseStream<QuicSocket>, max_frame_size: usize) -> Self {
+        let connection = control.get_ref().connection();
+        let (inbound_tx, inbound_frames) = mpsc::channel(INBOUND_QUEUE_SIZE);
+        if let Some(incoming_streams) = control.get_mut().take_incoming_streams() {
+            tokio::spawn(accept_streams(incoming_streams, inbound_tx, max_frame_size));
+        }
+        Self {
+            control,
+            connection,
+            max_frame_size,
+            write_buf: BytesMut::new(),
+            outbound_streams: HashMap::new(),
+            writer_tasks: Vec::new(),
+            inbound_rpcs: InboundRpcs::default(),
+            inbound_frames,
+            read_buf: Bytes::new(),
+        }
+    }
+
+    /// Hands the complete frames of `write_buf` to the writers of their streams.
+    fn poll_route_frames(&mut self, context: &mut Context) -> Poll<io::Result<()>> {
+        while self.write_buf.len() >= FRAME_LEN_PREFIX {
+            let mut len_prefix = [0u8; FRAME_LEN_PREFIX];
+            len_prefix.copy_from_slice(&self.write_buf[..FRAME_LEN_PREFIX]);
+            let frame_len = u32::from_be_bytes(len_prefix) as usize;
+            if frame_len > self.max_frame_size {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Outbound frame too large: {}", frame_len),
+                )));
+            }
+            if self.write_buf.len() < FRAME_LEN_PREFIX + frame_len {
+                break;
+            }
+
+            let key = stream_key(
+                &self.write_buf[FRAME_LEN_PREFIX..FRAME_LEN_PREFIX + frame_len],
+                &mut self.inbound_rpcs,
+            );
+            let connection = &self.connection;
+            let writer_tasks = &mut self.writer_tasks;
+            let sender = self.outbound_streams.entry(key).or_insert_with(|| {
+                let (sender, frames) = mpsc::channel(STREAM_QUEUE_SIZE);
+                writer_tasks.push(tokio::spawn(write_stream(connection.clone(), frames)));
+                sender
+            });
+            ready!(sender.poll_ready(context)).map_err(broken_pipe)?;
+            let frame = self
+                .write_buf
+                .split_to(FRAME_LEN_PREFIX + frame_len)
+                .freeze();
+            sender.start_send(frame).map_err(broken_pipe)?;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    /// Remembers the protocol of inbound rpc requests, to send their responses on its stream.
+    fn track_inbound_frame(&mut self, frame: &[u8]) {
+        if let Some((protocol_id, request_id)) = parse_rpc_request(frame) {
+            self.inbound_rpcs.insert(request_id, protocol_id);
+        }
+    }
+}
+
+/// Opens a unidirectional stream on the first frame to send and writes the frames to it.
+async fn write_stream(connection: quinn::Connection, mut frames: mpsc::Receiver<Bytes>) {
+    let first_frame = match frames.next().await {
+        Some(frame) => frame,
+        None => return,
+    };
+    let mut stream = match connection.open_uni().await {
+        Ok(stream) => stream,
+        Err(_) => return,
+    };
+    if stream.write_all(&first_frame).await.is_err() {
+        return;
+    }
+    while let Some(frame) = frames.next().await {
+        if stream.write_all(&frame).await.is_err() {
+            return;
+        }
+    }
+    let _ = stream.finish().await;
+}
+
+/// Reads the frames of every stream opened by the remote. Inbound frames stop once the
+/// connection and all its streams are closed.
+async fn accept_streams(
+    mut incoming_streams: quinn::IncomingUniStreams,
+    inbound_tx: mpsc::Sender<Bytes>,
+    max_frame_size: usize,
+) {
+    while let Some(Ok(stream)) = incoming_streams.next().await {
+        tokio::spawn(read_stream(stream, inbound_tx.clone(), max_frame_size));
+    }
+}
+
+async fn read_stream(
+    mut stream: quinn::RecvStream,
+    mut inbound_tx: mpsc::Sender<Bytes>,
+    max_frame_size: usize,
+) {
+    loop {
+        let mut len_prefix = [0u8; FRAME_LEN_PREFIX];
+        if stream.read_exact(&mut len_prefix).await.is_err() {
+            return;
+        }
+        let frame_len = u32::from_be_bytes(len_prefix) as usize;
+        if frame_len > max_frame_size {
+            let _ = stream.stop(0u32.into());
+            return;
+        }
+        let mut frame = BytesMut::with_capacity(FRAME_LEN_PREFIX + frame_len);
+        frame.extend_from_slice(&len_prefix);
+        frame.resize(FRAME_LEN_PREFIX + frame_len, 0);
+        if stream
+            .read_exact(&mut frame[FRAME_LEN_PREFIX..])
+            .await
+            .is_err()
+        {
+            return;
+        }
+        if futures::SinkExt::send(&mut inbound_tx, frame.freeze())
+            .await
+            .is_err()
+        {
+            return;
+        }
+    }
+}
+
+fn broken_pipe(err: mpsc::SendError) -> io::Error {
+    io::Error::new(io::ErrorKind::BrokenPipe, err)
+}
+
+impl fmt::Debug for QuicMuxSocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "QuicMuxSocket({:?})", self.control.get_ref())
+    }
+}
+
+impl AsyncRead for QuicMuxSocket {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        while self.read_buf.is_empty() {
+            match ready!(self.inbound_frames.poll_next_unpin(context)) {
+                Some(frame) => {
+                    self.track_inbound_frame(&frame[FRAME_LEN_PREFIX..]);
+                    self.read_buf = frame;
+                }
+                // all the streams are closed
+                None => return Poll::Ready(Ok(0)),
+            }
+        }
+        let len = std::cmp::min(buf.len(), self.read_buf.len());
+        buf[..len].copy_from_slice(&self.read_buf[..len]);
+        self.read_buf.advance(len);
+        Poll::Ready(Ok(len))
+    }
+}
+
+impl AsyncWrite for QuicMuxSocket {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        // apply backpressure while previous frames can't be routed
+        ready!(self.poll_route_frames(context))?;
+        self.write_buf.extend_from_slice(buf);
+        if let Poll::Ready(Err(err)) = self.poll_route_frames(context) {
+            return Poll::Ready(Err(err));
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    /// Flushing only hands the pending frames to the writers of their streams.
+    fn poll_flush(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        self.poll_route_frames(context)
+    }
+
+    /// Closes the outbound streams once their frames are written, then the connection.
+    fn poll_close(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        ready!(self.poll_route_frames(context))?;
+        self.outbound_streams.clear();
+        let writer_tasks = std::mem::take(&mut self.writer_tasks);
+        let connection = self.connection.clone();
+        tokio::spawn(future::join_all(writer_tasks).map(move |_| {
+            connection.close(0u32.into(), b"");
+        }));
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::protocols::wire::messaging::v1::ErrorCode;
+
+    fn to_frame(message: &NetworkMessage) -> Vec<u8> {
+        lcs::to_bytes(message).unwrap()
+    }
+
+    #[test]
+    fn test_stream_key() {
+        let mut inbound_rpcs = InboundRpcs::default();
+
+        let request = to_frame(&NetworkMessage::RpcRequest(RpcRequest {
+            protocol_id: ProtocolId::HealthCheckerRpc,
+            request_id: 0x0102_0304,
+            priority: 0,
+            raw_request: vec![1, 2, 3],
+        }));
+        assert_eq!(
+            parse_rpc_request(&request),
+            Some((ProtocolId::HealthCheckerRpc as u8, 0x0102_0304))
+        );
+        assert_eq!(
+            stream_key(&request, &mut inbound_rpcs),
+            StreamKey::Protocol(ProtocolId::HealthCheckerRpc as u8)
+        );
+
+        let message = to_frame(&NetworkMessage::DirectSendMsg(DirectSendMsg {
+            protocol_id: ProtocolId::ConsensusDirectSend,
+            priority: 0,
+            raw_msg: vec![],
+        }));
+        assert_eq!(parse_rpc_request(&message), None);
+        assert_eq!(
+            stream_key(&message, &mut inbound_rpcs),
+            StreamKey::Protocol(ProtocolId::ConsensusDirectSend as u8)
+        );
+
+        // responses go on the stream of the request, if known
+        let response = to_frame(&NetworkMessage::RpcResponse(RpcResponse {
+            request_id: 7,
+            priority: 0,
+            raw_response: vec![],
+        }));
+        assert_eq!(stream_key(&response, &mut inbound_rpcs), StreamKey::Default);
+        inbound_rpcs.insert(7, ProtocolId::ConsensusRpc as u8);
+        assert_eq!(
+            stream_key(&response, &mut inbound_rpcs),
+            StreamKey::Protocol(ProtocolId::ConsensusRpc as u8)
+        );
+        assert!(inbound_rpcs.protocols.is_empty());
+
+        let error = to_frame(&NetworkMessage::Error(ErrorCode::parsing_error(0, 0)));
+        assert_eq!(stream_key(&error, &mut inbound_rpcs), StreamKey::Default);
+    }
+
+    #[test]
+    fn test_inbound_rpcs_forget_oldest() {
+        let mut inbound_rpcs = InboundRpcs::default();
+        let protocol_id = ProtocolId::StateSynchronizerRpc as u8;
+        for request_id in 0..MAX_TRACKED_RPCS as RequestId {
+            inbound_rpcs.insert(request_id, protocol_id);
+        }
+        assert_eq!(inbound_rpcs.remove(1), Some(protocol_id));
+
+        // request 0 is forgotten, the others are still tracked
+        inbound_rpcs.insert(MAX_TRACKED_RPCS as RequestId, protocol_id);
+        inbound_rpcs.insert(MAX_TRACKED_RPCS as RequestId + 1, protocol_id);
+        assert_eq!(inbound_rpcs.protocols.len(), MAX_TRACKED_RPCS);
+        assert_eq!(inbound_rpcs.remove(0), None);
+        assert_eq!(inbound_rpcs.remove(2), Some(protocol_id));
+
+        // the ids of the requests responded to don't accumulate
+        for request_id in 0..4 * MAX_TRACKED_RPCS as RequestId {
+            inbound_rpcs.insert(request_id, protocol_id);
+            inbound_rpcs.remove(request_id);
+        }
+        assert!(inbound_rpcs.arrivals.len() <= 2 * MAX_TRACKED_RPCS);
+    }
+}
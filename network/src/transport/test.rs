@@ -271,11 +271,15 @@ fn test_transport_rejects_unauthed_dialer<TTransport>(
     // we try to accept one inbound connection from the dialer. however, the
     // connection upgrade should fail because the dialer is not authenticated
     // (not in the trusted peers set).
+    // the failure names the dialer, which isn't authenticated.
     let listener_task = async move {
-        let (inbound, _dialer_addr) = inbounds.next().await.unwrap().unwrap();
-        inbound
+        let (inbound, dialer_addr) = inbounds.next().await.unwrap().unwrap();
+        let err = inbound
             .await
             .expect_err("should fail because the dialer is not a trusted peer");
+        let failure = InboundUpgradeError::find(&err).expect("the handshake should fail");
+        assert_eq!(failure.addr, dialer_addr);
+        assert_eq!(failure.remote_peer_id, None);
     };
 
     // we attempt to dial the listener. however, the connection upgrade should
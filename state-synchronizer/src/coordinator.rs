@@ -9,6 +9,9 @@ use crate::{
     logging::{LogEntry, LogEvent, LogSchema},
     network::{StateSynchronizerEvents, StateSynchronizerMsg, StateSynchronizerSender},
     request_manager::{PeerScoreUpdateType, RequestManager},
+    state_snapshot::{
+        next_account_key, GetStateSnapshotRequest, GetStateSnapshotResponse, StateSnapshotTarget,
+    },
     SynchronizerState,
 };
 use anyhow::{bail, ensure, format_err, Result};
@@ -22,12 +25,14 @@ use libra_config::{
     config::{PeerNetworkId, RoleType, StateSyncConfig, UpstreamConfig},
     network_id::NodeNetworkId,
 };
+use libra_crypto::{hash::SPARSE_MERKLE_PLACEHOLDER_HASH, HashValue};
 use libra_logger::prelude::*;
 use libra_mempool::{CommitNotification, CommitResponse, CommittedTransaction};
 use libra_types::{
     contract_event::ContractEvent,
     epoch_change::Verifier,
     ledger_info::LedgerInfoWithSignatures,
+    proof::SparseMerkleRangeProof,
     transaction::{Transaction, TransactionListWithProof, Version},
     waypoint::Waypoint,
 };
@@ -63,6 +68,9 @@ pub enum CoordinatorMessage {
     GetState(oneshot::Sender<SynchronizerState>),
     // Receive a notification via a given channel when coordinator is initialized.
     WaitInitialize(oneshot::Sender<Result<()>>),
+    // Stop modifying the local storage until the resume channel is dropped or `max_pause_ms`
+    // elapsed, the callback receives the local state once paused.
+    Pause(oneshot::Sender<SynchronizerState>, oneshot::Receiver<()>),
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -146,6 +154,30 @@ impl PendingLedgerInfos {
     }
 }
 
+// Progress of fast sync: the account state at an epoch boundary is restored from chunks served by
+// the upstream peers, after which the coordinator syncs chunks of transactions as usual.
+struct StateSnapshotSync {
+    // Verified target of the snapshot, known once the first response has been received
+    target: Option<StateSnapshotTarget>,
+    // Key of the first account in the next chunk to request
+    next_key: HashValue,
+    // Number of accounts restored so far
+    num_accounts: usize,
+    // Peer the outstanding request was sent to and when
+    last_request: Option<(PeerNetworkId, SystemTime)>,
+}
+
+impl StateSnapshotSync {
+    fn new() -> Self {
+        Self {
+            target: None,
+            next_key: HashValue::zero(),
+            num_accounts: 0,
+            last_request: None,
+        }
+    }
+}
+
 /// Coordination of synchronization process is driven by SyncCoordinator, which `start()` function
 /// runs an infinite event loop and triggers actions based on external / internal requests.
 /// The coordinator can work in two modes:
@@ -185,6 +217,14 @@ pub(crate) struct SyncCoordinator<T> {
     // queue of incoming long polling requests
     // peer will be notified about new chunk of transactions if it's available before expiry time
     subscriptions: HashMap<PeerNetworkId, PendingRequestInfo>,
+    // Fast sync in progress, if any. While it is set, no chunks of transactions are requested.
+    state_snapshot: Option<StateSnapshotSync>,
+    // Chunks received in response to parallel chunk requests ahead of the local synced version,
+    // keyed by their first version. They are applied once all the preceding chunks are.
+    pending_chunks: BTreeMap<Version, (PeerNetworkId, GetChunkResponse)>,
+    // Resume channels of the components that paused state sync, with the time at which the pause
+    // expires. No chunk is applied to the local storage while any of them is pending.
+    pauses: Vec<(oneshot::Receiver<()>, SystemTime)>,
     executor_proxy: T,
 }
 
@@ -206,6 +246,20 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
             RoleType::Validator => 2 * config.tick_interval_ms,
         };
         let multicast_timeout = Duration::from_millis(config.multicast_timeout_ms);
+        // Fast sync is only attempted on startup and never over synced transactions that are not
+        // covered by a ledger info yet.
+        let state_snapshot = if config.enable_fast_sync
+            && initial_state.highest_local_li.ledger_info().version()
+                == initial_state.highest_version_in_local_storage()
+        {
+            info!(LogSchema::event_log(
+                LogEntry::FastSync,
+                LogEvent::Initialize
+            ));
+            Some(StateSnapshotSync::new())
+        } else {
+            None
+        };
 
         Self {
             client_events,
@@ -219,12 +273,16 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
                 upstream_config,
                 Duration::from_millis(retry_timeout_val),
                 multicast_timeout,
+                Duration::from_millis(config.peer_ban_duration_ms),
                 network_senders.clone(),
             ),
             network_senders,
             subscriptions: HashMap::new(),
             sync_request: None,
             initialization_listener: None,
+            state_snapshot,
+            pending_chunks: BTreeMap::new(),
+            pauses: vec![],
             executor_proxy,
         }
     }
@@ -283,6 +341,9 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
                         CoordinatorMessage::WaitInitialize(cb_sender) => {
                             self.set_initialization_listener(cb_sender);
                         }
+                        CoordinatorMessage::Pause(callback, resume) => {
+                            self.pause(callback, resume);
+                        }
                     };
                 },
                 (network_id, event) = network_events.select_next_some() => {
@@ -305,6 +366,9 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
                     }
                 },
                 _ = interval.select_next_some() => {
+                    if !self.pauses.is_empty() && !self.is_paused() {
+                        self.apply_pending_chunks().await;
+                    }
                     self.check_progress();
                 }
             }
@@ -358,6 +422,33 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
                     .start_timer();
                 self.process_chunk_response(&peer, *response).await;
             }
+            StateSynchronizerMsg::GetStateSnapshotRequest(request) => {
+                let _timer = counters::PROCESS_MSG_LATENCY
+                    .with_label_values(&[
+                        &peer.raw_network_id().to_string(),
+                        &peer.peer_id().to_string(),
+                        counters::STATE_SNAPSHOT_REQUEST_MSG_LABEL,
+                    ])
+                    .start_timer();
+                if let Err(err) = self.process_state_snapshot_request(peer.clone(), *request) {
+                    error!(LogSchema::event_log(
+                        LogEntry::ProcessStateSnapshotRequest,
+                        LogEvent::Fail
+                    )
+                    .peer(&peer)
+                    .error(&err));
+                }
+            }
+            StateSynchronizerMsg::GetStateSnapshotResponse(response) => {
+                let _timer = counters::PROCESS_MSG_LATENCY
+                    .with_label_values(&[
+                        &peer.raw_network_id().to_string(),
+                        &peer.peer_id().to_string(),
+                        counters::STATE_SNAPSHOT_RESPONSE_MSG_LABEL,
+                    ])
+                    .start_timer();
+                self.process_state_snapshot_response(&peer, *response).await;
+            }
         }
     }
 
@@ -584,6 +675,61 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
         Ok(())
     }
 
+    /// No chunk is applied while paused: the component committing to the local storage in the
+    /// meantime gets the local state once nothing else modifies it. The other messages are still
+    /// processed, the chunks received in the meantime are buffered until state sync resumes.
+    fn pause(
+        &mut self,
+        callback: oneshot::Sender<SynchronizerState>,
+        resume: oneshot::Receiver<()>,
+    ) {
+        if let Err(e) = self.sync_state_with_local_storage() {
+            error!(
+                "[state sync] failed to sync with local storage for pause request: {:?}",
+                e
+            );
+            return;
+        }
+        if callback.send(self.local_state.clone()).is_ok() {
+            let expiration = SystemTime::now() + Duration::from_millis(self.config.max_pause_ms);
+            self.pauses.push((resume, expiration));
+        }
+    }
+
+    /// Returns whether state sync is paused. A pause ends when the sender of its resume channel
+    /// is dropped, or when it expires. The local state is read again from storage on resume, as
+    /// it was modified by the components that paused state sync.
+    fn is_paused(&mut self) -> bool {
+        if self.pauses.is_empty() {
+            return false;
+        }
+        let now = SystemTime::now();
+        self.pauses = std::mem::take(&mut self.pauses)
+            .into_iter()
+            .filter_map(|(mut resume, expiration)| {
+                if now >= expiration {
+                    warn!("[state sync] pause expired, resuming state sync");
+                    return None;
+                }
+                // The sender of the resume channel is never used: dropping it resumes state sync.
+                match resume.try_recv() {
+                    Ok(None) => Some((resume, expiration)),
+                    _ => None,
+                }
+            })
+            .collect();
+        if !self.pauses.is_empty() {
+            return true;
+        }
+        if let Err(e) = self.sync_state_with_local_storage() {
+            error!(
+                "[state sync] failed to sync with local storage on resume: {:?}",
+                e
+            );
+        }
+        false
+    }
+
     fn get_state(&mut self, callback: oneshot::Sender<SynchronizerState>) {
         if let Err(e) = self.sync_state_with_local_storage() {
             error!(
@@ -830,6 +976,10 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
         fail_point!("state_sync::apply_chunk", |_| {
             Err(anyhow::anyhow!("Injected error in apply_chunk"))
         });
+        ensure!(
+            self.state_snapshot.is_none(),
+            "received chunk response while fast syncing"
+        );
 
         if !self.request_manager.is_known_upstream_peer(peer) {
             counters::RESPONSE_FROM_DOWNSTREAM_COUNT
@@ -923,9 +1073,92 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
         Ok(())
     }
 
+    /// * Buffers the chunk if it is ahead of the local synced version and was requested in parallel
+    /// * Otherwise verifies and stores chunk in response, followed by the buffered chunks that
+    ///   become applicable
+    async fn process_chunk_response(&mut self, peer: &PeerNetworkId, response: GetChunkResponse) {
+        if let Some(first_version) = response.txn_list_with_proof.first_transaction_version {
+            let request_version = first_version.saturating_sub(1);
+            self.request_manager.record_chunk_throughput(
+                peer,
+                request_version,
+                response.txn_list_with_proof.len() as u64,
+            );
+            if self.is_paused()
+                || (self.config.max_concurrent_chunk_requests > 1
+                    && request_version > self.local_state.highest_version_in_local_storage()
+                    && self
+                        .request_manager
+                        .is_request_sent_to(request_version, peer))
+            {
+                self.buffer_chunk(peer, first_version, response);
+                return;
+            }
+        } else if self.is_paused() {
+            return;
+        }
+
+        if self.apply_and_commit_chunk(peer, response).await {
+            self.apply_pending_chunks().await;
+        }
+    }
+
+    /// Keeps a chunk received ahead of the local synced version until the preceding ones are
+    /// applied. Its verification is deferred until then as well.
+    fn buffer_chunk(
+        &mut self,
+        peer: &PeerNetworkId,
+        first_version: Version,
+        response: GetChunkResponse,
+    ) {
+        if self.pending_chunks.len() >= self.config.max_concurrent_chunk_requests {
+            warn!(
+                LogSchema::new(LogEntry::ProcessChunkResponse)
+                    .peer(peer)
+                    .version(first_version),
+                "Dropped chunk: too many pending chunks"
+            );
+            return;
+        }
+        debug!(
+            LogSchema::event_log(LogEntry::ProcessChunkResponse, LogEvent::Received)
+                .peer(peer)
+                .version(first_version),
+            "Buffered chunk ahead of the local synced version"
+        );
+        self.pending_chunks
+            .insert(first_version, (peer.clone(), response));
+        counters::PENDING_CHUNKS.set(self.pending_chunks.len() as i64);
+    }
+
+    /// Applies the buffered chunks that start right after the local synced version, in order.
+    async fn apply_pending_chunks(&mut self) {
+        loop {
+            // Drop the chunks made obsolete by the ones applied so far.
+            let next_version = self.local_state.highest_version_in_local_storage() + 1;
+            self.pending_chunks = self.pending_chunks.split_off(&next_version);
+            counters::PENDING_CHUNKS.set(self.pending_chunks.len() as i64);
+
+            let (peer, response) = match self.pending_chunks.remove(&next_version) {
+                Some(pending_chunk) => pending_chunk,
+                None => return,
+            };
+            counters::PENDING_CHUNKS.set(self.pending_chunks.len() as i64);
+            if !self.apply_and_commit_chunk(&peer, response).await {
+                // The range is requested again on the next progress check.
+                return;
+            }
+        }
+    }
+
     /// * Verifies and stores chunk in response
     /// * Triggers post-commit actions based on new local state after successful chunk processing in above step
-    async fn process_chunk_response(&mut self, peer: &PeerNetworkId, response: GetChunkResponse) {
+    /// Returns whether the chunk was applied.
+    async fn apply_and_commit_chunk(
+        &mut self,
+        peer: &PeerNetworkId,
+        response: GetChunkResponse,
+    ) -> bool {
         let new_txns = response.txn_list_with_proof.transactions.clone();
         // Part 1: check response, validate and store chunk
         // any errors thrown here should be for detecting actual bad chunks
@@ -944,7 +1177,7 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
                     counters::FAIL_LABEL,
                 ])
                 .inc();
-            return;
+            return false;
         }
 
         counters::APPLY_CHUNK_COUNT
@@ -962,6 +1195,7 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
                     .error(&e)
             );
         }
+        true
     }
 
     /// Processing chunk responses that carry a LedgerInfo that should be verified using the
@@ -1098,9 +1332,14 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
     /// * kick-starts initial sync process (= initialization syncing to waypoint)
     /// * issue a new request if too much time passed since requesting highest_synced_version + 1.
     fn check_progress(&mut self) {
+        self.request_manager.unban_expired_peers();
         if self.request_manager.no_available_peers() {
             return;
         }
+        if self.state_snapshot.is_some() {
+            self.check_state_snapshot_progress();
+            return;
+        }
         if self.role == RoleType::Validator && self.sync_request.is_none() && self.is_initialized()
         {
             return;
@@ -1155,9 +1394,345 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
         }
     }
 
+    /// Re-sends the outstanding state snapshot request if it timed out (or sends the first one).
+    fn check_state_snapshot_progress(&mut self) {
+        let timeout = Duration::from_millis(self.config.snapshot_request_timeout_ms);
+        let timed_out_peer = match self.state_snapshot.as_ref() {
+            Some(snapshot) => match &snapshot.last_request {
+                Some((peer, tst)) => match tst.checked_add(timeout) {
+                    Some(deadline) if SystemTime::now().duration_since(deadline).is_err() => {
+                        return;
+                    }
+                    _ => Some(peer.clone()),
+                },
+                None => None,
+            },
+            None => return,
+        };
+        if let Some(peer) = timed_out_peer {
+            counters::TIMEOUT.inc();
+            warn!(LogSchema::event_log(LogEntry::FastSync, LogEvent::Timeout).peer(&peer));
+            self.request_manager
+                .update_score(&peer, PeerScoreUpdateType::TimeOut);
+        }
+        if let Err(e) = self.send_state_snapshot_request() {
+            error!(LogSchema::event_log(LogEntry::FastSync, LogEvent::Fail).error(&e));
+        }
+    }
+
+    /// Requests the next chunk of the state snapshot. Until the target is known, the responder is
+    /// asked to choose it.
+    fn send_state_snapshot_request(&mut self) -> Result<()> {
+        let (version, next_key) = match self.state_snapshot.as_ref() {
+            Some(snapshot) => (
+                snapshot
+                    .target
+                    .as_ref()
+                    .map(StateSnapshotTarget::version)
+                    .transpose()?,
+                snapshot.next_key,
+            ),
+            None => return Ok(()),
+        };
+        let request = GetStateSnapshotRequest::new(
+            self.local_state.epoch(),
+            version,
+            next_key,
+            self.config.snapshot_chunk_limit,
+        );
+        let peer = self.request_manager.send_state_snapshot_request(request)?;
+        if let Some(snapshot) = self.state_snapshot.as_mut() {
+            snapshot.last_request = Some((peer, SystemTime::now()));
+        }
+        Ok(())
+    }
+
+    /// Serves a chunk of the state snapshot at the requested version, or at the latest epoch
+    /// boundary (together with its target) if no version is requested.
+    fn process_state_snapshot_request(
+        &mut self,
+        peer: PeerNetworkId,
+        request: GetStateSnapshotRequest,
+    ) -> Result<()> {
+        debug!(
+            LogSchema::event_log(LogEntry::ProcessStateSnapshotRequest, LogEvent::Received)
+                .peer(&peer),
+            request = %request
+        );
+        let limit = std::cmp::min(request.limit, self.config.snapshot_chunk_limit);
+        let response = match request.version {
+            Some(version) => {
+                let (account_blobs, proof) = self.executor_proxy.get_state_snapshot_chunk(
+                    version,
+                    request.start_key,
+                    limit,
+                )?;
+                GetStateSnapshotResponse::new(version, None, account_blobs, proof)
+            }
+            None => {
+                self.sync_state_with_local_storage()?;
+                if request.known_epoch >= self.local_state.epoch() {
+                    // There is no epoch boundary to fast sync to, the requester is going to sync
+                    // transactions instead.
+                    GetStateSnapshotResponse::new(
+                        self.local_state.highest_local_li.ledger_info().version(),
+                        None,
+                        vec![],
+                        SparseMerkleRangeProof::new(vec![]),
+                    )
+                } else {
+                    let target = self
+                        .executor_proxy
+                        .get_state_snapshot_target(request.known_epoch)?;
+                    let version = target.version()?;
+                    let (account_blobs, proof) = self.executor_proxy.get_state_snapshot_chunk(
+                        version,
+                        request.start_key,
+                        limit,
+                    )?;
+                    GetStateSnapshotResponse::new(version, Some(target), account_blobs, proof)
+                }
+            }
+        };
+
+        let network_sender = self
+            .network_senders
+            .get_mut(&peer.network_id())
+            .expect("missing network sender");
+        let send_result = network_sender.send_to(
+            peer.peer_id(),
+            StateSynchronizerMsg::GetStateSnapshotResponse(Box::new(response)),
+        );
+        counters::RESPONSES_SENT
+            .with_label_values(&[
+                &peer.raw_network_id().to_string(),
+                &peer.peer_id().to_string(),
+                if send_result.is_err() {
+                    counters::SEND_FAIL_LABEL
+                } else {
+                    counters::SEND_SUCCESS_LABEL
+                },
+            ])
+            .inc();
+        send_result
+            .map_err(|e| format_err!("Network error in sending state snapshot to {}: {}", peer, e))
+    }
+
+    /// * Verifies and restores the state snapshot chunk in response
+    /// * Requests the next chunk, or switches to syncing transactions once fast sync is over
+    async fn process_state_snapshot_response(
+        &mut self,
+        peer: &PeerNetworkId,
+        response: GetStateSnapshotResponse,
+    ) {
+        debug!(
+            LogSchema::event_log(LogEntry::ProcessStateSnapshotResponse, LogEvent::Received)
+                .peer(peer),
+            response = %response
+        );
+        if self.is_paused() {
+            // The chunk is requested again once the request times out.
+            return;
+        }
+        let result = self.apply_state_snapshot_chunk(peer, response);
+        counters::APPLY_STATE_SNAPSHOT_CHUNK_COUNT
+            .with_label_values(&[
+                &peer.raw_network_id().to_string(),
+                &peer.peer_id().to_string(),
+                if result.is_ok() {
+                    counters::SUCCESS_LABEL
+                } else {
+                    counters::FAIL_LABEL
+                },
+            ])
+            .inc();
+
+        match result {
+            Ok(false) => {
+                self.request_manager.process_success_response(peer);
+                if let Err(e) = self.send_state_snapshot_request() {
+                    error!(LogSchema::event_log(LogEntry::FastSync, LogEvent::Fail).error(&e));
+                }
+            }
+            Ok(true) => {
+                // Notify the waiting components about the new local state and carry on with
+                // the transactions after the snapshot.
+                if let Err(e) = self.process_commit(vec![], None, Some(peer)).await {
+                    error!(LogSchema::event_log(
+                        LogEntry::ProcessStateSnapshotResponse,
+                        LogEvent::PostCommitFail
+                    )
+                    .error(&e));
+                }
+                if let Err(e) = self.send_chunk_request(
+                    self.local_state.highest_version_in_local_storage(),
+                    self.local_state.epoch(),
+                ) {
+                    error!(LogSchema::event_log(
+                        LogEntry::ProcessStateSnapshotResponse,
+                        LogEvent::SendChunkRequestFail
+                    )
+                    .error(&e));
+                }
+            }
+            Err(e) => {
+                self.request_manager
+                    .update_score(peer, PeerScoreUpdateType::InvalidChunk);
+                error!(LogSchema::event_log(
+                    LogEntry::ProcessStateSnapshotResponse,
+                    LogEvent::Fail
+                )
+                .peer(peer)
+                .error(&e));
+            }
+        }
+    }
+
+    /// Applies the state snapshot chunk in `response` to the restore in progress, starting the
+    /// restore with the first response which carries the target to verify.
+    /// Returns true if fast sync is over, either because the whole snapshot has been restored or
+    /// because there is no snapshot ahead of the local state.
+    fn apply_state_snapshot_chunk(
+        &mut self,
+        peer: &PeerNetworkId,
+        response: GetStateSnapshotResponse,
+    ) -> Result<bool> {
+        let snapshot = self
+            .state_snapshot
+            .as_mut()
+            .ok_or_else(|| format_err!("Received state snapshot while not fast syncing"))?;
+        ensure!(
+            snapshot
+                .last_request
+                .as_ref()
+                .map_or(false, |(requested_peer, _)| requested_peer == peer),
+            "Received unexpected state snapshot from {}",
+            peer
+        );
+        snapshot.last_request = None;
+
+        if snapshot.target.is_none() {
+            let target = match response.target {
+                Some(target) => target,
+                None => {
+                    info!(
+                        LogSchema::event_log(LogEntry::FastSync, LogEvent::Complete).peer(peer),
+                        "No state snapshot ahead of the local state, syncing transactions instead"
+                    );
+                    self.state_snapshot = None;
+                    return Ok(true);
+                }
+            };
+            target.verify(&self.local_state.trusted_epoch)?;
+            let target_version = target.version()?;
+            ensure!(
+                response.version == target_version,
+                "State snapshot version {} doesn't match its target {}",
+                response.version,
+                target_version
+            );
+            let local_li_version = self.local_state.highest_local_li.ledger_info().version();
+            if target_version <= self.local_state.highest_version_in_local_storage()
+                || target_version < self.waypoint.version()
+            {
+                info!(
+                    LogSchema::event_log(LogEntry::FastSync, LogEvent::Complete)
+                        .target_version(target_version)
+                        .local_synced_version(self.local_state.highest_version_in_local_storage()),
+                    "State snapshot is not useful, syncing transactions instead"
+                );
+                self.state_snapshot = None;
+                return Ok(true);
+            }
+            if self.waypoint.version() > local_li_version {
+                let waypoint_li = target
+                    .epoch_change_proof
+                    .ledger_info_with_sigs
+                    .iter()
+                    .find(|li| li.ledger_info().version() == self.waypoint.version())
+                    .ok_or_else(|| {
+                        format_err!("State snapshot target doesn't prove the waypoint")
+                    })?;
+                self.waypoint.verify(waypoint_li.ledger_info())?;
+            }
+            self.executor_proxy.start_state_snapshot_restore(
+                target_version,
+                target.transaction_info()?.state_root_hash(),
+            )?;
+            info!(
+                LogSchema::event_log(LogEntry::FastSync, LogEvent::Success)
+                    .target_version(target_version),
+                "Restoring state snapshot"
+            );
+            snapshot.target = Some(target);
+        }
+
+        let target_version = snapshot
+            .target
+            .as_ref()
+            .map(StateSnapshotTarget::version)
+            .transpose()?
+            .expect("state snapshot target must be set");
+        ensure!(
+            response.version == target_version,
+            "State snapshot version {} doesn't match the target {}",
+            response.version,
+            target_version
+        );
+        let (last_key, num_accounts) = match response.account_blobs.last() {
+            Some((key, _blob)) => (*key, response.account_blobs.len()),
+            None => bail!("Empty state snapshot chunk from {}", peer),
+        };
+        let is_last_chunk = response
+            .proof
+            .right_siblings()
+            .iter()
+            .all(|hash| *hash == *SPARSE_MERKLE_PLACEHOLDER_HASH);
+        if let Err(e) = self
+            .executor_proxy
+            .add_state_snapshot_chunk(response.account_blobs, response.proof)
+        {
+            // The partially restored tree can't be trusted anymore, start over.
+            *snapshot = StateSnapshotSync::new();
+            return Err(e);
+        }
+        snapshot.num_accounts += num_accounts;
+        counters::STATE_SNAPSHOT_ACCOUNTS.set(snapshot.num_accounts as i64);
+
+        match next_account_key(last_key) {
+            Some(next_key) if !is_last_chunk => {
+                snapshot.next_key = next_key;
+                return Ok(false);
+            }
+            _ => (),
+        }
+
+        let target = snapshot
+            .target
+            .take()
+            .expect("state snapshot target must be set");
+        let num_accounts = snapshot.num_accounts;
+        self.state_snapshot = None;
+        if let Err(e) = self.executor_proxy.finish_state_snapshot_restore(target) {
+            self.state_snapshot = Some(StateSnapshotSync::new());
+            return Err(e);
+        }
+        info!(
+            LogSchema::event_log(LogEntry::FastSync, LogEvent::Complete)
+                .version(target_version)
+                .count(num_accounts),
+            "Restored state snapshot"
+        );
+        Ok(true)
+    }
+
     /// Sends a chunk request with a given `known_version` and `known_epoch`
     /// (might be chosen optimistically).
     fn send_chunk_request(&mut self, known_version: u64, known_epoch: u64) -> Result<()> {
+        if self.state_snapshot.is_some() {
+            // The local state is going to be replaced by the state snapshot.
+            return Ok(());
+        }
         if self.request_manager.no_available_peers() {
             warn!(LogSchema::event_log(
                 LogEntry::SendChunkRequest,
@@ -1196,10 +1771,104 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
             }
         };
 
+        if let Some(target_version) = self.parallel_sync_target_version(&target, known_epoch) {
+            return self.send_parallel_chunk_requests(
+                known_version,
+                known_epoch,
+                target,
+                target_version,
+            );
+        }
         let req = GetChunkRequest::new(known_version, known_epoch, self.config.chunk_limit, target);
         self.request_manager.send_chunk_request(req)
     }
 
+    /// Returns the version up to which chunks can be requested in parallel for `target`, if any.
+    /// This requires the target version to be known and the whole range to belong to
+    /// `known_epoch`, as the epoch of a chunk determines how its ledger info is verified.
+    fn parallel_sync_target_version(
+        &self,
+        target: &TargetType,
+        known_epoch: u64,
+    ) -> Option<Version> {
+        if self.config.max_concurrent_chunk_requests <= 1 {
+            return None;
+        }
+        let target_li = match target {
+            TargetType::TargetLedgerInfo(li) => li,
+            TargetType::HighestAvailable {
+                target_li: Some(li),
+                ..
+            } => li,
+            _ => return None,
+        };
+        if target_li.ledger_info().epoch() == known_epoch {
+            Some(target_li.ledger_info().version())
+        } else {
+            None
+        }
+    }
+
+    /// Splits the versions between `known_version` and `target_version` into non-overlapping
+    /// ranges of at most `chunk_limit` transactions, and requests the first
+    /// `max_concurrent_chunk_requests` ranges that have neither been received nor requested yet.
+    /// The first range is requested the same way as a regular chunk request, the following ones
+    /// each from a different idle peer.
+    fn send_parallel_chunk_requests(
+        &mut self,
+        known_version: u64,
+        known_epoch: u64,
+        target: TargetType,
+        target_version: Version,
+    ) -> Result<()> {
+        let mut version = known_version;
+        let mut num_ranges = 0;
+        let mut result = Ok(());
+        while version < target_version && num_ranges < self.config.max_concurrent_chunk_requests {
+            if let Some((_, response)) = self.pending_chunks.get(&(version + 1)) {
+                version += response.txn_list_with_proof.len() as u64;
+                continue;
+            }
+            num_ranges += 1;
+            // The range ends where the next received chunk or requested range starts.
+            let next_pending_chunk = self
+                .pending_chunks
+                .range(version + 2..)
+                .next()
+                .map(|(first_version, _)| *first_version - 1);
+            let range_end = [
+                Some(target_version),
+                Some(version + self.config.chunk_limit),
+                next_pending_chunk,
+                self.request_manager.next_request_in_flight(version),
+            ]
+            .iter()
+            .filter_map(|v| *v)
+            .min()
+            .unwrap_or(target_version);
+
+            if !self.request_manager.is_request_in_flight(version) {
+                let req =
+                    GetChunkRequest::new(version, known_epoch, range_end - version, target.clone());
+                let send_result = if version == known_version {
+                    self.request_manager.send_chunk_request(req)
+                } else {
+                    self.request_manager.send_parallel_chunk_request(req)
+                };
+                if let Err(e) = send_result {
+                    if version == known_version {
+                        return Err(e);
+                    }
+                    // Remaining ranges are requested once there are idle peers again.
+                    result = Err(e);
+                    break;
+                }
+            }
+            version = range_end;
+        }
+        result
+    }
+
     fn deliver_subscription(
         &mut self,
         peer: PeerNetworkId,
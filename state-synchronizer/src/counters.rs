@@ -17,6 +17,8 @@ pub const SYNC_MSG_LABEL: &str = "sync";
 pub const COMMIT_MSG_LABEL: &str = "commit";
 pub const CHUNK_REQUEST_MSG_LABEL: &str = "chunk_request";
 pub const CHUNK_RESPONSE_MSG_LABEL: &str = "chunk_response";
+pub const STATE_SNAPSHOT_REQUEST_MSG_LABEL: &str = "state_snapshot_request";
+pub const STATE_SNAPSHOT_RESPONSE_MSG_LABEL: &str = "state_snapshot_response";
 
 // version type labels
 pub const COMMITTED_VERSION_LABEL: &str = "committed"; // Version of latest ledger info committed.
@@ -101,6 +103,25 @@ pub static PROCESS_CHUNK_REQUEST_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Number of attempts to apply a state snapshot chunk during fast sync
+pub static APPLY_STATE_SNAPSHOT_CHUNK_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "libra_state_sync_apply_state_snapshot_chunk_total",
+        "Number of results of applying a state snapshot chunk",
+        &["network", "sender", "result"]
+    )
+    .unwrap()
+});
+
+/// Number of accounts restored so far by fast sync
+pub static STATE_SNAPSHOT_ACCOUNTS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "libra_state_sync_state_snapshot_accounts",
+        "Number of accounts restored so far from a state snapshot"
+    )
+    .unwrap()
+});
+
 /// Number of transactions in a received chunk response
 pub static STATE_SYNC_CHUNK_SIZE: Lazy<HistogramVec> = Lazy::new(|| {
     register_histogram_vec!(
@@ -122,6 +143,56 @@ pub static ACTIVE_UPSTREAM_PEERS: Lazy<IntGaugeVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Estimated rate (in transactions per second) at which an upstream peer serves chunks.
+/// It is used to weight the selection of peers to send chunk requests to.
+pub static PEER_CHUNK_THROUGHPUT: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "libra_state_sync_peer_chunk_throughput",
+        "Estimated number of transactions per second served by an upstream peer",
+        &["network", "peer"]
+    )
+    .unwrap()
+});
+
+/// Reputation score of an upstream peer, between 1 and 100
+pub static PEER_SCORE: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "libra_state_sync_peer_score",
+        "Reputation score of an upstream peer used to select peers to send chunk requests to",
+        &["network", "peer"]
+    )
+    .unwrap()
+});
+
+/// Number of updates of the score of an upstream peer, by type of update
+pub static PEER_SCORE_UPDATES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "libra_state_sync_peer_score_updates",
+        "Number of updates of the score of an upstream peer",
+        &["network", "peer", "type"]
+    )
+    .unwrap()
+});
+
+/// Number of times an upstream peer was temporarily excluded from peer selection
+pub static PEER_BAN_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "libra_state_sync_peer_ban_count",
+        "Number of times an upstream peer was banned",
+        &["network", "peer"]
+    )
+    .unwrap()
+});
+
+/// Number of chunks received ahead of the local synced version, waiting to be applied in order
+pub static PENDING_CHUNKS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "libra_state_sync_pending_chunks",
+        "Number of chunk responses buffered until the preceding chunks are applied"
+    )
+    .unwrap()
+});
+
 /// Highest preference of the networks this node is sending chunk requests to.
 /// It is usually 0 if the node's primary network is healthy, but can be >0 if the node's primary
 /// network is unhealthy/all peers in that network are dead
@@ -4,22 +4,27 @@
 use crate::{
     counters,
     logging::{LogEntry, LogEvent, LogSchema},
+    state_snapshot::StateSnapshotTarget,
     SynchronizerState,
 };
-use anyhow::{format_err, Result};
+use anyhow::{ensure, format_err, Result};
 use executor_types::{ChunkExecutor, ExecutedTrees};
 use itertools::Itertools;
+use libra_crypto::HashValue;
 use libra_logger::prelude::*;
 use libra_types::{
     account_state::AccountState,
+    account_state_blob::AccountStateBlob,
     contract_event::ContractEvent,
+    epoch_change::EpochChangeProof,
     ledger_info::LedgerInfoWithSignatures,
     move_resource::MoveStorage,
     on_chain_config::{config_address, OnChainConfigPayload, ON_CHAIN_CONFIG_REGISTRY},
-    transaction::TransactionListWithProof,
+    proof::SparseMerkleRangeProof,
+    transaction::{TransactionListWithProof, Version},
 };
 use std::{collections::HashSet, convert::TryFrom, sync::Arc};
-use storage_interface::DbReader;
+use storage_interface::{DbReader, StateSnapshotReceiver};
 use subscription_service::ReconfigSubscription;
 
 /// Proxies interactions with execution and storage for state synchronization
@@ -58,6 +63,37 @@ pub trait ExecutorProxyTrait: Send {
 
     /// publishes on-chain config updates to subscribed components
     fn publish_on_chain_config_updates(&mut self, events: Vec<ContractEvent>) -> Result<()>;
+
+    /// Get the target of a state snapshot at the latest epoch boundary, with the epoch changes
+    /// starting from `known_epoch`.
+    fn get_state_snapshot_target(&self, known_epoch: u64) -> Result<StateSnapshotTarget>;
+
+    /// Get up to `limit` account state blobs at `version` starting from `start_key`, with the
+    /// range proof of the rightmost one.
+    fn get_state_snapshot_chunk(
+        &self,
+        version: Version,
+        start_key: HashValue,
+        limit: u64,
+    ) -> Result<(Vec<(HashValue, AccountStateBlob)>, SparseMerkleRangeProof)>;
+
+    /// Start restoring the state tree at `version`, dropping any restore in progress.
+    fn start_state_snapshot_restore(
+        &mut self,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<()>;
+
+    /// Add a chunk of account state blobs to the restore in progress, which is dropped if the
+    /// chunk can't be added.
+    fn add_state_snapshot_chunk(
+        &mut self,
+        account_blobs: Vec<(HashValue, AccountStateBlob)>,
+        proof: SparseMerkleRangeProof,
+    ) -> Result<()>;
+
+    /// Finish the restore in progress and make the verified `target` the latest local state.
+    fn finish_state_snapshot_restore(&mut self, target: StateSnapshotTarget) -> Result<()>;
 }
 
 pub(crate) struct ExecutorProxy {
@@ -65,6 +101,7 @@ pub(crate) struct ExecutorProxy {
     executor: Box<dyn ChunkExecutor>,
     reconfig_subscriptions: Vec<ReconfigSubscription>,
     on_chain_configs: OnChainConfigPayload,
+    state_snapshot_receiver: Option<Box<dyn StateSnapshotReceiver>>,
 }
 
 impl ExecutorProxy {
@@ -85,6 +122,7 @@ impl ExecutorProxy {
             executor,
             reconfig_subscriptions,
             on_chain_configs,
+            state_snapshot_receiver: None,
         }
     }
 
@@ -268,4 +306,120 @@ impl ExecutorProxyTrait for ExecutorProxy {
             Err(format_err!("failed to publish at least one subscription"))
         }
     }
+
+    fn get_state_snapshot_target(&self, known_epoch: u64) -> Result<StateSnapshotTarget> {
+        let latest_epoch = self
+            .storage
+            .get_latest_ledger_info()?
+            .ledger_info()
+            .next_block_epoch();
+        ensure!(
+            known_epoch < latest_epoch,
+            "No epoch boundary after epoch {}",
+            known_epoch
+        );
+        // Storage caps the number of ledger infos it returns at once, the target is the end of
+        // the latest epoch.
+        let mut ledger_info_with_sigs = vec![];
+        let mut start_epoch = known_epoch;
+        loop {
+            let proof = self
+                .storage
+                .get_epoch_ending_ledger_infos(start_epoch, latest_epoch)?;
+            let more = proof.more;
+            ledger_info_with_sigs.extend(proof.ledger_info_with_sigs);
+            start_epoch = ledger_info_with_sigs
+                .last()
+                .ok_or_else(|| format_err!("Empty EpochChangeProof"))?
+                .ledger_info()
+                .next_block_epoch();
+            if !more || start_epoch >= latest_epoch {
+                break;
+            }
+        }
+        let epoch_change_proof = EpochChangeProof::new(ledger_info_with_sigs, false);
+        let version = epoch_change_proof
+            .ledger_info_with_sigs
+            .last()
+            .ok_or_else(|| format_err!("Empty EpochChangeProof"))?
+            .ledger_info()
+            .version();
+        let txn_list_with_proof = self.storage.get_transactions(version, 1, version, false)?;
+        let frozen_subtrees = self.storage.get_frozen_subtree_hashes(version + 1)?;
+        Ok(StateSnapshotTarget::new(
+            epoch_change_proof,
+            txn_list_with_proof,
+            frozen_subtrees,
+        ))
+    }
+
+    fn get_state_snapshot_chunk(
+        &self,
+        version: Version,
+        start_key: HashValue,
+        limit: u64,
+    ) -> Result<(Vec<(HashValue, AccountStateBlob)>, SparseMerkleRangeProof)> {
+        self.storage
+            .get_state_snapshot_chunk(version, start_key, limit as usize)
+    }
+
+    fn start_state_snapshot_restore(
+        &mut self,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<()> {
+        self.state_snapshot_receiver = Some(
+            self.executor
+                .get_state_snapshot_receiver(version, expected_root_hash)?,
+        );
+        Ok(())
+    }
+
+    fn add_state_snapshot_chunk(
+        &mut self,
+        account_blobs: Vec<(HashValue, AccountStateBlob)>,
+        proof: SparseMerkleRangeProof,
+    ) -> Result<()> {
+        let result = self
+            .state_snapshot_receiver
+            .as_mut()
+            .ok_or_else(|| format_err!("No state snapshot restore in progress"))?
+            .add_chunk(account_blobs, proof);
+        if result.is_err() {
+            // The partially restored tree can't be trusted anymore.
+            self.state_snapshot_receiver = None;
+        }
+        result
+    }
+
+    fn finish_state_snapshot_restore(&mut self, target: StateSnapshotTarget) -> Result<()> {
+        self.state_snapshot_receiver
+            .take()
+            .ok_or_else(|| format_err!("No state snapshot restore in progress"))?
+            .finish()?;
+
+        let version = target.version()?;
+        let transaction_info = target.transaction_info()?.clone();
+        let transaction = target
+            .txn_list_with_proof
+            .transactions
+            .into_iter()
+            .next()
+            .ok_or_else(|| format_err!("Missing transaction of the target"))?;
+        self.executor.finalize_state_snapshot(
+            version,
+            transaction,
+            transaction_info,
+            target.frozen_subtrees,
+            target.epoch_change_proof.ledger_info_with_sigs,
+        )?;
+
+        // All the on-chain configs might have changed since the previous local state, so
+        // every subscriber is notified.
+        self.on_chain_configs = Self::fetch_all_configs(&*self.storage)?;
+        for subscription in self.reconfig_subscriptions.iter_mut() {
+            subscription.publish(self.on_chain_configs.clone())?;
+        }
+        Ok(())
+    }
 }
@@ -9,7 +9,7 @@
 
 use executor_types::ExecutedTrees;
 use libra_types::{epoch_state::EpochState, ledger_info::LedgerInfoWithSignatures};
-pub use synchronizer::{StateSyncClient, StateSynchronizer};
+pub use synchronizer::{StateSyncClient, StateSynchronizer, SyncPause};
 
 pub mod chunk_request;
 pub mod chunk_response;
@@ -19,6 +19,7 @@ mod executor_proxy;
 mod logging;
 pub mod network;
 mod request_manager;
+pub mod state_snapshot;
 mod synchronizer;
 
 /// The state distinguishes between the following fields:
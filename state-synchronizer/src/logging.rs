@@ -115,6 +115,10 @@ pub enum LogEntry {
     CommitFlow,
     Multicast,
     SubscriptionDeliveryFail,
+    FastSync,
+    ProcessStateSnapshotRequest,
+    ProcessStateSnapshotResponse,
+    PeerScore,
 }
 
 #[derive(Clone, Copy, Serialize)]
@@ -149,4 +153,8 @@ pub enum LogEvent {
     // Multicast network events
     Failover,
     Recover,
+
+    // PeerScore events
+    Ban,
+    Unban,
 }
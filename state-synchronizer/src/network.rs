@@ -3,7 +3,12 @@
 
 //! Interface between StateSynchronizer and Network layers.
 
-use crate::{chunk_request::GetChunkRequest, chunk_response::GetChunkResponse, counters};
+use crate::{
+    chunk_request::GetChunkRequest,
+    chunk_response::GetChunkResponse,
+    counters,
+    state_snapshot::{GetStateSnapshotRequest, GetStateSnapshotResponse},
+};
 use channel::message_queues::QueueStyle;
 use libra_metrics::IntCounterVec;
 use libra_types::PeerId;
@@ -20,6 +25,8 @@ use serde::{Deserialize, Serialize};
 pub enum StateSynchronizerMsg {
     GetChunkRequest(Box<GetChunkRequest>),
     GetChunkResponse(Box<GetChunkResponse>),
+    GetStateSnapshotRequest(Box<GetStateSnapshotRequest>),
+    GetStateSnapshotResponse(Box<GetStateSnapshotResponse>),
 }
 
 /// The interface from Network to StateSynchronizer layer.
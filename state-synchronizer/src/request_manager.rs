@@ -6,8 +6,9 @@ use crate::{
     counters,
     logging::{LogEntry, LogEvent, LogSchema},
     network::{StateSynchronizerMsg, StateSynchronizerSender},
+    state_snapshot::GetStateSnapshotRequest,
 };
-use anyhow::{bail, Result};
+use anyhow::{bail, format_err, Result};
 use itertools::Itertools;
 use libra_config::{
     config::{PeerNetworkId, UpstreamConfig},
@@ -20,23 +21,55 @@ use rand::{
     thread_rng,
 };
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 const MAX_SCORE: f64 = 100.0;
 const MIN_SCORE: f64 = 1.0;
 const PRIMARY_NETWORK_PREFERENCE: usize = 0;
+// weight of the latest sample in the moving average of a peer's throughput
+const THROUGHPUT_SAMPLE_WEIGHT: f64 = 0.3;
+// lower bound of the throughput factor applied to the score of a slow peer
+const MIN_THROUGHPUT_FACTOR: f64 = 0.1;
+// number of invalid chunks in a row after which a peer is banned
+const MAX_CONSECUTIVE_INVALID_CHUNKS: u64 = 3;
 
 #[derive(Default, Debug, Clone)]
 pub struct PeerInfo {
     is_alive: bool,
     score: f64,
+    // moving average of the number of transactions per second served by the peer,
+    // `None` until the peer has served a chunk
+    throughput: Option<f64>,
+    // number of invalid chunks received from the peer since its last successful one
+    consecutive_invalid_chunks: u64,
+    // the peer is excluded from peer selection until then
+    banned_until: Option<SystemTime>,
 }
 
 impl PeerInfo {
     pub fn new(is_alive: bool, score: f64) -> Self {
-        Self { is_alive, score }
+        Self {
+            is_alive,
+            score,
+            throughput: None,
+            consecutive_invalid_chunks: 0,
+            banned_until: None,
+        }
+    }
+
+    // The weight of the peer when picking peers to send chunk requests to: its score scaled down
+    // by its throughput relative to the fastest peer it is compared to.
+    // Peers with no throughput measured yet are not scaled down so they get a chance to be tried.
+    fn selection_weight(&self, max_throughput: Option<f64>) -> f64 {
+        let throughput_factor = match (self.throughput, max_throughput) {
+            (Some(throughput), Some(max_throughput)) if max_throughput > 0.0 => {
+                (throughput / max_throughput).max(MIN_THROUGHPUT_FACTOR)
+            }
+            _ => 1.0,
+        };
+        self.score * throughput_factor
     }
 }
 
@@ -76,6 +109,18 @@ pub enum PeerScoreUpdateType {
     TimeOut,
 }
 
+impl PeerScoreUpdateType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            PeerScoreUpdateType::Success => "success",
+            PeerScoreUpdateType::EmptyChunk => "empty_chunk",
+            PeerScoreUpdateType::ChunkVersionCannotBeApplied => "chunk_version_cannot_be_applied",
+            PeerScoreUpdateType::InvalidChunk => "invalid_chunk",
+            PeerScoreUpdateType::TimeOut => "time_out",
+        }
+    }
+}
+
 pub struct RequestManager {
     // list of peers that are eligible for this node to send sync requests to
     // grouped by network preference
@@ -90,6 +135,8 @@ pub struct RequestManager {
     // the maximum preference level of all the networks to try to multicast the same chunk request to,
     // where network preference is specified by the upstream config
     multicast_level: usize,
+    // duration for which a peer is excluded from peer selection once banned
+    peer_ban_duration: Duration,
     network_senders: HashMap<NodeNetworkId, StateSynchronizerSender>,
 }
 
@@ -98,6 +145,7 @@ impl RequestManager {
         upstream_config: UpstreamConfig,
         request_timeout: Duration,
         multicast_timeout: Duration,
+        peer_ban_duration: Duration,
         network_senders: HashMap<NodeNetworkId, StateSynchronizerSender>,
     ) -> Self {
         counters::MULTICAST_LEVEL.set(PRIMARY_NETWORK_PREFERENCE as i64);
@@ -109,6 +157,7 @@ impl RequestManager {
             request_timeout,
             multicast_timeout,
             multicast_level: PRIMARY_NETWORK_PREFERENCE,
+            peer_ban_duration,
             network_senders,
         }
     }
@@ -152,16 +201,33 @@ impl RequestManager {
         self.eligible_peers.is_empty()
     }
 
+    /// Updates the score of `peer`. A peer is banned, i.e. temporarily excluded from peer selection,
+    /// once it sent `MAX_CONSECUTIVE_INVALID_CHUNKS` invalid chunks in a row or once its score
+    /// reaches the minimum. The last peer eligible for peer selection is never banned: syncing
+    /// from a bad peer is better than not syncing at all.
     pub fn update_score(&mut self, peer: &PeerNetworkId, update_type: PeerScoreUpdateType) {
+        let network_label = peer.raw_network_id().to_string();
+        let peer_label = peer.peer_id().to_string();
+        counters::PEER_SCORE_UPDATES
+            .with_label_values(&[&network_label, &peer_label, update_type.as_str()])
+            .inc();
+        let has_other_eligible_peers = self.peers.iter().any(|(other_peer, peer_info)| {
+            other_peer != peer && peer_info.is_alive && peer_info.banned_until.is_none()
+        });
         if let Some(peer_info) = self.peers.get_mut(peer) {
             let old_score = peer_info.score;
             match update_type {
                 PeerScoreUpdateType::Success => {
                     let new_score = peer_info.score + 1.0;
                     peer_info.score = new_score.min(MAX_SCORE);
+                    peer_info.consecutive_invalid_chunks = 0;
                 }
-                PeerScoreUpdateType::InvalidChunk
-                | PeerScoreUpdateType::ChunkVersionCannotBeApplied => {
+                PeerScoreUpdateType::InvalidChunk => {
+                    let new_score = peer_info.score * 0.8;
+                    peer_info.score = new_score.max(MIN_SCORE);
+                    peer_info.consecutive_invalid_chunks += 1;
+                }
+                PeerScoreUpdateType::ChunkVersionCannotBeApplied => {
                     let new_score = peer_info.score * 0.8;
                     peer_info.score = new_score.max(MIN_SCORE);
                 }
@@ -170,21 +236,107 @@ impl RequestManager {
                     peer_info.score = new_score.max(MIN_SCORE);
                 }
             }
-            if (old_score - peer_info.score).abs() > std::f64::EPSILON {
+            counters::PEER_SCORE
+                .with_label_values(&[&network_label, &peer_label])
+                .set(peer_info.score as i64);
+
+            let should_ban = has_other_eligible_peers
+                && peer_info.banned_until.is_none()
+                && (peer_info.consecutive_invalid_chunks >= MAX_CONSECUTIVE_INVALID_CHUNKS
+                    || peer_info.score <= MIN_SCORE);
+            if should_ban {
+                peer_info.banned_until = SystemTime::now().checked_add(self.peer_ban_duration);
+                peer_info.consecutive_invalid_chunks = 0;
+                warn!(
+                    LogSchema::event_log(LogEntry::PeerScore, LogEvent::Ban).peer(peer),
+                    score = peer_info.score,
+                    update_type = update_type.as_str()
+                );
+                counters::PEER_BAN_COUNT
+                    .with_label_values(&[&network_label, &peer_label])
+                    .inc();
+                self.update_peer_selection_data();
+            } else if (old_score - peer_info.score).abs() > std::f64::EPSILON {
                 self.update_peer_selection_data();
             }
         }
     }
 
+    /// Lifts the bans that are over, making the peers eligible for peer selection again with the
+    /// score of a new peer: a peer banned for its minimum score would be banned again right away.
+    pub fn unban_expired_peers(&mut self) {
+        let now = SystemTime::now();
+        let mut unbanned = false;
+        for (peer, peer_info) in self.peers.iter_mut() {
+            if peer_info.banned_until.map_or(false, |until| until <= now) {
+                peer_info.banned_until = None;
+                peer_info.score = MAX_SCORE;
+                counters::PEER_SCORE
+                    .with_label_values(&[
+                        &peer.raw_network_id().to_string(),
+                        &peer.peer_id().to_string(),
+                    ])
+                    .set(peer_info.score as i64);
+                info!(
+                    LogSchema::event_log(LogEntry::PeerScore, LogEvent::Unban).peer(peer),
+                    score = peer_info.score
+                );
+                unbanned = true;
+            }
+        }
+        if unbanned {
+            self.update_peer_selection_data();
+        }
+    }
+
+    pub fn is_banned(&self, peer: &PeerNetworkId) -> bool {
+        self.peers
+            .get(peer)
+            .map_or(false, |peer_info| peer_info.banned_until.is_some())
+    }
+
+    /// Updates the throughput of `peer` with a chunk of `num_txns` transactions received in
+    /// response to the request with known_version = `request_version`.
+    pub fn record_chunk_throughput(
+        &mut self,
+        peer: &PeerNetworkId,
+        request_version: u64,
+        num_txns: u64,
+    ) {
+        let request_time = match self.requests.get(&request_version) {
+            Some(req) if req.last_request_peers.contains(peer) => req.last_request_time,
+            _ => return,
+        };
+        let elapsed_secs = SystemTime::now()
+            .duration_since(request_time)
+            .map_or(0.0, |duration| duration.as_secs_f64())
+            .max(0.001);
+        let sample = num_txns as f64 / elapsed_secs;
+        if let Some(peer_info) = self.peers.get_mut(peer) {
+            let throughput = peer_info.throughput.map_or(sample, |throughput| {
+                throughput * (1.0 - THROUGHPUT_SAMPLE_WEIGHT) + sample * THROUGHPUT_SAMPLE_WEIGHT
+            });
+            peer_info.throughput = Some(throughput);
+            counters::PEER_CHUNK_THROUGHPUT
+                .with_label_values(&[
+                    &peer.raw_network_id().to_string(),
+                    &peer.peer_id().to_string(),
+                ])
+                .set(throughput as i64);
+            self.update_peer_selection_data();
+        }
+    }
+
     // Updates the information used to select a peer to send a chunk request to:
     // * eligible_peers
     // * weighted_index: the chance that a peer is selected from `eligible_peers` is weighted by its score
+    //   and throughput
     fn update_peer_selection_data(&mut self) {
         // group active peers by network
         let active_peers = self
             .peers
             .iter()
-            .filter(|(_peer, peer_info)| peer_info.is_alive)
+            .filter(|(_peer, peer_info)| peer_info.is_alive && peer_info.banned_until.is_none())
             .map(|(peer, peer_info)| {
                 let network_pref = self
                     .upstream_config
@@ -198,12 +350,13 @@ impl RequestManager {
         self.eligible_peers = active_peers
             .into_iter()
             .map(|(network_pref, peers)| {
+                let max_throughput = Self::max_throughput(peers.iter().map(|(_, info)| *info));
                 let mut eligible_peers = vec![];
                 let weights: Vec<_> = peers
                     .iter()
                     .map(|(peer, peer_info)| {
                         eligible_peers.push((*peer).clone());
-                        peer_info.score
+                        peer_info.selection_weight(max_throughput)
                     })
                     .collect();
                 let weighted_index = WeightedIndex::new(&weights)
@@ -220,6 +373,14 @@ impl RequestManager {
             .collect();
     }
 
+    fn max_throughput<'a>(peer_infos: impl Iterator<Item = &'a PeerInfo>) -> Option<f64> {
+        peer_infos
+            .filter_map(|peer_info| peer_info.throughput)
+            .fold(None, |max, throughput| {
+                Some(max.map_or(throughput, |max: f64| max.max(throughput)))
+            })
+    }
+
     fn pick_peer(
         peers: &[PeerNetworkId],
         weighted_index: &Option<WeightedIndex<f64>>,
@@ -270,6 +431,37 @@ impl RequestManager {
         chosen_peers
     }
 
+    /// Picks a peer that has no chunk request in flight, among the networks with preference level
+    /// <= multicast level. The chance that a peer is picked is weighted by its score and throughput.
+    fn pick_idle_peer(&self) -> Option<PeerNetworkId> {
+        let busy_peers: HashSet<_> = self
+            .requests
+            .values()
+            .filter(|req| !Self::is_timeout(req.last_request_time, self.request_timeout))
+            .flat_map(|req| req.last_request_peers.iter())
+            .collect();
+        let candidates: Vec<_> = self
+            .eligible_peers
+            .range(..=self.multicast_level)
+            .flat_map(|(_level, (peers, _weighted_index))| peers.iter())
+            .filter(|peer| !busy_peers.contains(peer))
+            .collect();
+        let max_throughput =
+            Self::max_throughput(candidates.iter().filter_map(|peer| self.peers.get(*peer)));
+        let weights: Vec<_> = candidates
+            .iter()
+            .map(|peer| {
+                self.peers.get(*peer).map_or(MIN_SCORE, |peer_info| {
+                    peer_info.selection_weight(max_throughput)
+                })
+            })
+            .collect();
+        let weighted_index = WeightedIndex::new(&weights).ok()?;
+        candidates
+            .get(weighted_index.sample(&mut thread_rng()))
+            .map(|peer| (*peer).clone())
+    }
+
     pub fn send_chunk_request(&mut self, req: GetChunkRequest) -> Result<()> {
         let log = LogSchema::new(LogEntry::SendChunkRequest).chunk_req(&req);
 
@@ -285,8 +477,44 @@ impl RequestManager {
             .clone()
             .event(LogEvent::ChunkRequestInfo)
             .chunk_req_info(&req_info));
+        self.send_to_peers(req, peers, log)
+    }
+
+    /// Sends a chunk request for a range ahead of the local synced version, to be fetched in
+    /// parallel with the other outstanding requests. Unlike `send_chunk_request`, the request is
+    /// never multicast: it is sent to a single peer that has no other chunk request in flight.
+    /// The peers a previous (timed out) request for the same range was sent to are penalized.
+    pub fn send_parallel_chunk_request(&mut self, req: GetChunkRequest) -> Result<()> {
+        let log = LogSchema::new(LogEntry::SendChunkRequest).chunk_req(&req);
+
+        let peer = match self.pick_idle_peer() {
+            Some(peer) => peer,
+            None => bail!("No idle peers to send parallel chunk request to"),
+        };
+        let timed_out_peers = self
+            .requests
+            .get(&req.known_version)
+            .map_or_else(Vec::new, |prev_request| {
+                prev_request.last_request_peers.clone()
+            });
+        for timed_out_peer in timed_out_peers.iter() {
+            self.update_score(timed_out_peer, PeerScoreUpdateType::TimeOut);
+        }
+
+        let req_info = self.add_request(req.known_version, vec![peer.clone()]);
+        debug!(log
+            .clone()
+            .event(LogEvent::ChunkRequestInfo)
+            .chunk_req_info(&req_info));
+        self.send_to_peers(req, vec![peer], log)
+    }
 
-        // actually execute network send
+    fn send_to_peers(
+        &mut self,
+        req: GetChunkRequest,
+        peers: Vec<PeerNetworkId>,
+        log: LogSchema,
+    ) -> Result<()> {
         let target_version = req.target().version();
         let msg = StateSynchronizerMsg::GetChunkRequest(Box::new(req));
         let mut failed_peer_sends = vec![];
@@ -328,6 +556,43 @@ impl RequestManager {
         }
     }
 
+    /// Sends a state snapshot request to a single peer picked the same way as for chunk requests.
+    /// Unlike chunk requests, snapshot requests are not tracked here: the caller is responsible
+    /// for retrying them. Returns the chosen peer.
+    pub fn send_state_snapshot_request(
+        &mut self,
+        req: GetStateSnapshotRequest,
+    ) -> Result<PeerNetworkId> {
+        let peer = match self.pick_peers().into_iter().next() {
+            Some(peer) => peer,
+            None => bail!("No peers to send state snapshot request to"),
+        };
+        let sender = self
+            .network_senders
+            .get_mut(&peer.network_id())
+            .expect("missing network sender for peer");
+        let send_result = sender.send_to(
+            peer.peer_id(),
+            StateSynchronizerMsg::GetStateSnapshotRequest(Box::new(req)),
+        );
+        let result_label = if send_result.is_err() {
+            counters::SEND_FAIL_LABEL
+        } else {
+            counters::SEND_SUCCESS_LABEL
+        };
+        counters::REQUESTS_SENT
+            .with_label_values(&[
+                &peer.raw_network_id().to_string(),
+                &peer.peer_id().to_string(),
+                result_label,
+            ])
+            .inc();
+        send_result.map_err(|e| {
+            format_err!("Failed to send state snapshot request to {:?}: {}", peer, e)
+        })?;
+        Ok(peer)
+    }
+
     pub fn add_request(&mut self, version: u64, peers: Vec<PeerNetworkId>) -> ChunkRequestInfo {
         if let Some(prev_request) = self.requests.get_mut(&version) {
             let now = SystemTime::now();
@@ -401,6 +666,29 @@ impl RequestManager {
         })
     }
 
+    /// Returns true if a chunk request with known_version = `version` has been sent to `peer`
+    /// and has not been removed yet, even if it timed out.
+    pub fn is_request_sent_to(&self, version: u64, peer: &PeerNetworkId) -> bool {
+        self.requests
+            .get(&version)
+            .map_or(false, |req| req.last_request_peers.contains(peer))
+    }
+
+    /// Returns true if a chunk request with known_version = `version` is waiting for a response
+    /// and has not timed out.
+    pub fn is_request_in_flight(&self, version: u64) -> bool {
+        self.get_last_request_time(version)
+            .map_or(false, |tst| !Self::is_timeout(tst, self.request_timeout))
+    }
+
+    /// Returns the smallest known_version > `version` of the chunk requests in flight.
+    pub fn next_request_in_flight(&self, version: u64) -> Option<u64> {
+        self.requests
+            .range(version + 1..)
+            .find(|(_, req)| !Self::is_timeout(req.last_request_time, self.request_timeout))
+            .map(|(version, _)| *version)
+    }
+
     pub fn get_last_request_time(&self, version: u64) -> Option<SystemTime> {
         self.requests
             .get(&version)
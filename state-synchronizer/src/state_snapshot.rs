@@ -0,0 +1,204 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Messages used by fast sync to download the account state at an epoch boundary instead of
+//! replaying all the transactions before it.
+
+use anyhow::{ensure, format_err, Result};
+use libra_crypto::{hash::TransactionAccumulatorHasher, HashValue};
+use libra_types::{
+    account_state_blob::AccountStateBlob,
+    epoch_change::{EpochChangeProof, Verifier},
+    ledger_info::LedgerInfoWithSignatures,
+    proof::{accumulator::InMemoryAccumulator, SparseMerkleRangeProof},
+    transaction::{TransactionInfo, TransactionListWithProof, Version},
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Clone, Deserialize, Eq, PartialEq, Serialize)]
+pub struct GetStateSnapshotRequest {
+    /// Epoch of the requester, the epoch change proof of the target starts from it.
+    pub known_epoch: u64,
+    /// Version of the snapshot. If `None`, the responder picks its latest epoch ending version
+    /// and includes the `StateSnapshotTarget` in the response.
+    pub version: Option<Version>,
+    /// The response should start with the first account whose key is not less than `start_key`.
+    pub start_key: HashValue,
+    /// Max number of accounts in the response.
+    pub limit: u64,
+}
+
+impl GetStateSnapshotRequest {
+    pub fn new(
+        known_epoch: u64,
+        version: Option<Version>,
+        start_key: HashValue,
+        limit: u64,
+    ) -> Self {
+        Self {
+            known_epoch,
+            version,
+            start_key,
+            limit,
+        }
+    }
+}
+
+impl fmt::Debug for GetStateSnapshotRequest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl fmt::Display for GetStateSnapshotRequest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[StateSnapshotRequest: known epoch: {}, version: {}, start key: {:x}, limit: {}]",
+            self.known_epoch,
+            self.version
+                .map_or_else(|| "latest".to_string(), |v| v.to_string()),
+            self.start_key,
+            self.limit,
+        )
+    }
+}
+
+/// Everything a node needs, on top of the account state, to start from an epoch ending version.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct StateSnapshotTarget {
+    /// Epoch changes from the known epoch of the requester, the last ledger info is the target.
+    pub epoch_change_proof: EpochChangeProof,
+    /// The transaction at the target version, with the proof relative to the target.
+    pub txn_list_with_proof: TransactionListWithProof,
+    /// Roots of the frozen subtrees of the transaction accumulator up to the target version,
+    /// from left to right.
+    pub frozen_subtrees: Vec<HashValue>,
+}
+
+impl StateSnapshotTarget {
+    pub fn new(
+        epoch_change_proof: EpochChangeProof,
+        txn_list_with_proof: TransactionListWithProof,
+        frozen_subtrees: Vec<HashValue>,
+    ) -> Self {
+        Self {
+            epoch_change_proof,
+            txn_list_with_proof,
+            frozen_subtrees,
+        }
+    }
+
+    pub fn ledger_info(&self) -> Result<&LedgerInfoWithSignatures> {
+        self.epoch_change_proof
+            .ledger_info_with_sigs
+            .last()
+            .ok_or_else(|| format_err!("Empty EpochChangeProof"))
+    }
+
+    pub fn version(&self) -> Result<Version> {
+        Ok(self.ledger_info()?.ledger_info().version())
+    }
+
+    /// The info of the transaction at the target version, which carries the root hash of the
+    /// account state tree to restore.
+    pub fn transaction_info(&self) -> Result<&TransactionInfo> {
+        self.txn_list_with_proof
+            .proof
+            .transaction_infos()
+            .first()
+            .ok_or_else(|| format_err!("Missing transaction info of the target"))
+    }
+
+    /// Verifies the epoch changes with `verifier`, then the transaction and the accumulator
+    /// against the target ledger info.
+    pub fn verify(&self, verifier: &dyn Verifier) -> Result<()> {
+        let li = self.epoch_change_proof.verify(verifier)?.ledger_info();
+        ensure!(
+            self.txn_list_with_proof.transactions.len() == 1,
+            "Expected a single transaction at the target version, got {}",
+            self.txn_list_with_proof.transactions.len()
+        );
+        self.txn_list_with_proof.verify(li, Some(li.version()))?;
+        let accumulator = InMemoryAccumulator::<TransactionAccumulatorHasher>::new(
+            self.frozen_subtrees.clone(),
+            li.version() + 1,
+        )?;
+        ensure!(
+            accumulator.root_hash() == li.transaction_accumulator_hash(),
+            "Frozen subtrees don't match the transaction accumulator root {}",
+            li.transaction_accumulator_hash()
+        );
+        Ok(())
+    }
+}
+
+#[derive(Clone, Deserialize, Eq, PartialEq, Serialize)]
+pub struct GetStateSnapshotResponse {
+    /// Version of the snapshot the accounts belong to.
+    pub version: Version,
+    /// Set if the request didn't specify the version.
+    pub target: Option<StateSnapshotTarget>,
+    /// Account state blobs in the increasing order of their keys.
+    pub account_blobs: Vec<(HashValue, AccountStateBlob)>,
+    /// Proof of the range ending at the last account in `account_blobs`.
+    pub proof: SparseMerkleRangeProof,
+}
+
+impl GetStateSnapshotResponse {
+    pub fn new(
+        version: Version,
+        target: Option<StateSnapshotTarget>,
+        account_blobs: Vec<(HashValue, AccountStateBlob)>,
+        proof: SparseMerkleRangeProof,
+    ) -> Self {
+        Self {
+            version,
+            target,
+            account_blobs,
+            proof,
+        }
+    }
+}
+
+impl fmt::Debug for GetStateSnapshotResponse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl fmt::Display for GetStateSnapshotResponse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let accounts_repr = match (self.account_blobs.first(), self.account_blobs.last()) {
+            (Some((first, _)), Some((last, _))) => format!(
+                "{} accounts [{:x} - {:x}]",
+                self.account_blobs.len(),
+                first,
+                last
+            ),
+            _ => "empty".to_string(),
+        };
+        write!(
+            f,
+            "[StateSnapshotResponse: version: {}, with target: {}, accounts: {}]",
+            self.version,
+            self.target.is_some(),
+            accounts_repr,
+        )
+    }
+}
+
+/// Computes the account key immediately after `key`, or `None` if `key` is the largest one.
+pub(crate) fn next_account_key(key: HashValue) -> Option<HashValue> {
+    let mut buf = key.to_vec();
+    for byte in buf.iter_mut().rev() {
+        if *byte == u8::max_value() {
+            *byte = 0;
+        } else {
+            *byte += 1;
+            return HashValue::from_slice(&buf).ok();
+        }
+    }
+    None
+}
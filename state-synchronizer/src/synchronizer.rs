@@ -136,6 +136,11 @@ impl StateSynchronizer {
     }
 }
 
+/// State synchronizer is paused as long as this is alive, see `StateSyncClient::pause`.
+pub struct SyncPause {
+    _resume: oneshot::Sender<()>,
+}
+
 pub struct StateSyncClient {
     coordinator_sender: mpsc::UnboundedSender<CoordinatorMessage>,
 }
@@ -204,6 +209,26 @@ impl StateSyncClient {
         }
     }
 
+    /// Pauses state synchronizer, it doesn't modify the local storage until the returned
+    /// `SyncPause` is dropped. Returns the local state once paused.
+    pub fn pause(&self) -> impl Future<Output = Result<(SynchronizerState, SyncPause)>> {
+        let mut sender = self.coordinator_sender.clone();
+        let (cb_sender, cb_receiver) = oneshot::channel();
+        let (resume_sender, resume_receiver) = oneshot::channel();
+        async move {
+            sender
+                .send(CoordinatorMessage::Pause(cb_sender, resume_receiver))
+                .await?;
+            let state = cb_receiver.await?;
+            Ok((
+                state,
+                SyncPause {
+                    _resume: resume_sender,
+                },
+            ))
+        }
+    }
+
     /// Returns information about StateSynchronizer internal state
     pub fn get_state(&self) -> impl Future<Output = Result<SynchronizerState>> {
         let mut sender = self.coordinator_sender.clone();
@@ -2,21 +2,29 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    executor_proxy::ExecutorProxyTrait, tests::mock_storage::MockStorage, SynchronizerState,
+    executor_proxy::ExecutorProxyTrait, state_snapshot::StateSnapshotTarget,
+    tests::mock_storage::MockStorage, SynchronizerState,
 };
-use anyhow::Result;
+use anyhow::{bail, Result};
 use libra_config::config::HANDSHAKE_VERSION;
-use libra_crypto::{hash::ACCUMULATOR_PLACEHOLDER_HASH, test_utils::TEST_SEED, x25519, Uniform};
+use libra_crypto::{
+    hash::ACCUMULATOR_PLACEHOLDER_HASH, test_utils::TEST_SEED, x25519, HashValue, Uniform,
+};
 use libra_infallible::RwLock;
 use libra_network_address::{
     encrypted::{TEST_SHARED_VAL_NETADDR_KEY, TEST_SHARED_VAL_NETADDR_KEY_VERSION},
     NetworkAddress, Protocol,
 };
 use libra_types::{
-    contract_event::ContractEvent, ledger_info::LedgerInfoWithSignatures,
-    on_chain_config::ValidatorSet, proof::TransactionListProof,
-    transaction::TransactionListWithProof, validator_config::ValidatorConfig,
-    validator_info::ValidatorInfo, validator_signer::ValidatorSigner,
+    account_state_blob::AccountStateBlob,
+    contract_event::ContractEvent,
+    ledger_info::LedgerInfoWithSignatures,
+    on_chain_config::ValidatorSet,
+    proof::{SparseMerkleRangeProof, TransactionListProof},
+    transaction::{TransactionListWithProof, Version},
+    validator_config::ValidatorConfig,
+    validator_info::ValidatorInfo,
+    validator_signer::ValidatorSigner,
     validator_verifier::random_validator_verifier,
 };
 use memsocket::MemoryListener;
@@ -159,4 +167,37 @@ impl ExecutorProxyTrait for MockExecutorProxy {
     fn publish_on_chain_config_updates(&mut self, _events: Vec<ContractEvent>) -> Result<()> {
         Ok(())
     }
+
+    fn get_state_snapshot_target(&self, _known_epoch: u64) -> Result<StateSnapshotTarget> {
+        bail!("State snapshots are not supported by the mock executor proxy")
+    }
+
+    fn get_state_snapshot_chunk(
+        &self,
+        _version: Version,
+        _start_key: HashValue,
+        _limit: u64,
+    ) -> Result<(Vec<(HashValue, AccountStateBlob)>, SparseMerkleRangeProof)> {
+        bail!("State snapshots are not supported by the mock executor proxy")
+    }
+
+    fn start_state_snapshot_restore(
+        &mut self,
+        _version: Version,
+        _expected_root_hash: HashValue,
+    ) -> Result<()> {
+        bail!("State snapshots are not supported by the mock executor proxy")
+    }
+
+    fn add_state_snapshot_chunk(
+        &mut self,
+        _account_blobs: Vec<(HashValue, AccountStateBlob)>,
+        _proof: SparseMerkleRangeProof,
+    ) -> Result<()> {
+        bail!("State snapshots are not supported by the mock executor proxy")
+    }
+
+    fn finish_state_snapshot_restore(&mut self, _target: StateSnapshotTarget) -> Result<()> {
+        bail!("State snapshots are not supported by the mock executor proxy")
+    }
 }
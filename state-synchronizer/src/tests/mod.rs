@@ -8,6 +8,8 @@ mod mock_storage;
 #[cfg(test)]
 mod on_chain_config_tests;
 #[cfg(test)]
+mod state_snapshot_tests;
+#[cfg(test)]
 mod unit_tests;
 
 pub mod fuzzing;
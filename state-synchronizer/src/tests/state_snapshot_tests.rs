@@ -0,0 +1,220 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    executor_proxy::ExecutorProxy,
+    network::{StateSynchronizerEvents, StateSynchronizerMsg, StateSynchronizerSender},
+    StateSynchronizer,
+};
+use channel::{libra_channel, message_queues::QueueStyle};
+use executor::Executor;
+use executor_test_helpers::{
+    gen_block_id, gen_block_metadata, gen_ledger_info_with_sigs, get_test_signed_transaction,
+    integration_test_impl::create_db_and_executor,
+};
+use executor_types::BlockExecutor;
+use futures::{executor::block_on, StreamExt};
+use libra_config::{
+    config::{NodeConfig, RoleType},
+    network_id::{NetworkContext, NetworkId, NodeNetworkId},
+};
+use libra_crypto::{HashValue, PrivateKey};
+use libra_mempool::mocks::MockSharedMempool;
+use libra_network_address::NetworkAddress;
+use libra_temppath::TempPath;
+use libra_types::{
+    account_config::libra_root_address,
+    transaction::{Transaction, WriteSetPayload},
+    validator_signer::ValidatorSigner,
+    waypoint::Waypoint,
+    PeerId,
+};
+use libra_vm::LibraVM;
+use libradb::LibraDB;
+use netcore::transport::ConnectionOrigin;
+use network::{
+    peer_manager::{
+        conn_notifs_channel, ConnectionNotification, ConnectionRequestSender,
+        PeerManagerNotification, PeerManagerRequest, PeerManagerRequestSender,
+    },
+    protocols::direct_send::Message,
+    ProtocolId,
+};
+use std::{num::NonZeroUsize, sync::Arc};
+use storage_interface::DbReader;
+use tokio::runtime::Runtime;
+use transaction_builder::{encode_add_to_script_allow_list_script, encode_block_prologue_script};
+
+/// A state synchronizer over a real storage, whose messages are delivered by the test.
+struct SnapshotNode {
+    peer_id: PeerId,
+    db: Arc<LibraDB>,
+    _synchronizer: StateSynchronizer,
+    _mempool: MockSharedMempool,
+    network_reqs_rx: libra_channel::Receiver<(PeerId, ProtocolId), PeerManagerRequest>,
+    network_notifs_tx: libra_channel::Sender<(PeerId, ProtocolId), PeerManagerNotification>,
+    conn_notifs_tx: conn_notifs_channel::Sender,
+}
+
+impl SnapshotNode {
+    fn new(
+        db: Arc<LibraDB>,
+        executor: Executor<LibraVM>,
+        role: RoleType,
+        waypoint: Waypoint,
+    ) -> Self {
+        let mut config = NodeConfig::default_for_validator();
+        config.base.role = role;
+        // only the full node fast syncs, from the validator
+        config.state_sync.enable_fast_sync = !role.is_validator();
+        // several chunks are needed to restore the accounts of genesis
+        config.state_sync.snapshot_chunk_limit = 5;
+        let network_id = NetworkId::vfn_network();
+        config.upstream.networks = vec![network_id.clone()];
+
+        let (network_reqs_tx, network_reqs_rx) =
+            libra_channel::new(QueueStyle::FIFO, NonZeroUsize::new(8).unwrap(), None);
+        let (connection_reqs_tx, _) =
+            libra_channel::new(QueueStyle::FIFO, NonZeroUsize::new(8).unwrap(), None);
+        let (network_notifs_tx, network_notifs_rx) =
+            libra_channel::new(QueueStyle::FIFO, NonZeroUsize::new(8).unwrap(), None);
+        let (conn_notifs_tx, conn_notifs_rx) = conn_notifs_channel::new();
+        let network_sender = StateSynchronizerSender::new(
+            PeerManagerRequestSender::new(network_reqs_tx),
+            ConnectionRequestSender::new(connection_reqs_tx),
+        );
+        let network_events = StateSynchronizerEvents::new(network_notifs_rx, conn_notifs_rx);
+
+        let (mempool_sender, mempool_requests) = futures::channel::mpsc::channel(1_024);
+        let synchronizer = StateSynchronizer::bootstrap_with_executor_proxy(
+            Runtime::new().unwrap(),
+            vec![(
+                NodeNetworkId::new(network_id, 0),
+                network_sender,
+                network_events,
+            )],
+            mempool_sender,
+            role,
+            waypoint,
+            &config.state_sync,
+            config.upstream,
+            ExecutorProxy::new(db.clone(), Box::new(executor), vec![]),
+        );
+        Self {
+            peer_id: PeerId::random(),
+            db,
+            _synchronizer: synchronizer,
+            _mempool: MockSharedMempool::new(Some(mempool_requests)),
+            network_reqs_rx,
+            network_notifs_tx,
+            conn_notifs_tx,
+        }
+    }
+
+    /// Notifies the node about a new upstream peer.
+    fn connect_to(&mut self, peer_id: PeerId) {
+        let notif = ConnectionNotification::NewPeer(
+            peer_id,
+            NetworkAddress::mock(),
+            ConnectionOrigin::Outbound,
+            NetworkContext::mock(),
+        );
+        self.conn_notifs_tx.push(peer_id, notif).unwrap();
+    }
+
+    /// Waits for the next message sent by the node.
+    fn next_message(&mut self) -> (PeerId, Message) {
+        match block_on(self.network_reqs_rx.next()).unwrap() {
+            PeerManagerRequest::SendMessage(receiver_id, msg) => (receiver_id, msg),
+            _ => panic!("received network request other than SendMessage"),
+        }
+    }
+
+    fn deliver(&mut self, sender_id: PeerId, msg: Message) {
+        self.network_notifs_tx
+            .push(
+                (sender_id, ProtocolId::StateSynchronizerDirectSend),
+                PeerManagerNotification::RecvMessage(sender_id, msg),
+            )
+            .unwrap();
+    }
+}
+
+#[test]
+fn test_fast_sync_from_state_snapshot() {
+    ::libra_logger::Logger::init_for_testing();
+    let (genesis, validators) = vm_genesis::test_genesis_change_set_and_validators(Some(1));
+    let genesis_txn = Transaction::GenesisTransaction(WriteSetPayload::Direct(genesis));
+    let genesis_key = vm_genesis::GENESIS_KEYPAIR.0.clone();
+    let signer = ValidatorSigner::new(validators[0].owner_address, validators[0].key.clone());
+
+    // The upstream node ends the first epoch with a reconfiguration block.
+    let upstream_path = TempPath::new();
+    upstream_path.create_as_dir().unwrap();
+    let (upstream_db, upstream_db_rw, mut block_executor, waypoint) =
+        create_db_and_executor(upstream_path.path(), &genesis_txn);
+    let block_id = gen_block_id(1);
+    let block = vec![
+        encode_block_prologue_script(gen_block_metadata(1, validators[0].owner_address)),
+        get_test_signed_transaction(
+            libra_root_address(),
+            /* sequence_number = */ 1,
+            genesis_key.clone(),
+            genesis_key.public_key(),
+            Some(encode_add_to_script_allow_list_script(
+                HashValue::sha3_256_of(&[]).to_vec(),
+                0,
+            )),
+        ),
+    ];
+    let parent_block_id = block_executor.committed_block_id();
+    let output = block_executor
+        .execute_block((block_id, block), parent_block_id)
+        .unwrap();
+    assert!(output.has_reconfiguration());
+    let target_li = gen_ledger_info_with_sigs(1, output, block_id, vec![&signer]);
+    block_executor
+        .commit_blocks(vec![block_id], target_li.clone())
+        .unwrap();
+    let target_version = target_li.ledger_info().version();
+
+    let mut upstream = SnapshotNode::new(
+        upstream_db,
+        Executor::<LibraVM>::new(upstream_db_rw),
+        RoleType::Validator,
+        waypoint,
+    );
+
+    // The full node only has genesis and restores the state at the end of the first epoch.
+    let path = TempPath::new();
+    path.create_as_dir().unwrap();
+    let (db, _, executor, _) = create_db_and_executor(path.path(), &genesis_txn);
+    let mut full_node = SnapshotNode::new(db, executor, RoleType::FullNode, waypoint);
+    full_node.connect_to(upstream.peer_id);
+
+    let mut num_snapshot_chunks = 0;
+    loop {
+        let (receiver_id, request) = full_node.next_message();
+        assert_eq!(receiver_id, upstream.peer_id);
+        match lcs::from_bytes::<StateSynchronizerMsg>(&request.mdata).unwrap() {
+            StateSynchronizerMsg::GetStateSnapshotRequest(_) => num_snapshot_chunks += 1,
+            // fast sync is over, the transactions after the snapshot are synced next
+            StateSynchronizerMsg::GetChunkRequest(_) => break,
+            msg => panic!("unexpected message {:?}", msg),
+        }
+        upstream.deliver(full_node.peer_id, request);
+        let (receiver_id, response) = upstream.next_message();
+        assert_eq!(receiver_id, full_node.peer_id);
+        full_node.deliver(upstream.peer_id, response);
+    }
+    assert!(num_snapshot_chunks > 1);
+
+    assert_eq!(full_node.db.get_latest_ledger_info().unwrap(), target_li);
+    let root_account = |db: &LibraDB| {
+        db.get_account_state_with_proof_by_version(libra_root_address(), target_version)
+            .unwrap()
+            .0
+    };
+    assert!(root_account(&full_node.db).is_some());
+    assert_eq!(root_account(&full_node.db), root_account(&upstream.db));
+}
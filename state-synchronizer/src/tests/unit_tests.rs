@@ -18,6 +18,7 @@ fn test_request_manager() {
         UpstreamConfig::default(),
         Duration::from_secs(10),
         Duration::from_secs(30),
+        Duration::from_secs(60),
         HashMap::new(),
     );
     for peer_id in peers.clone() {
@@ -52,6 +53,7 @@ fn test_remove_requests() {
         UpstreamConfig::default(),
         Duration::from_secs(0),
         Duration::from_secs(30),
+        Duration::from_secs(60),
         HashMap::new(),
     );
     for peer in peers.iter() {
@@ -83,6 +85,7 @@ fn test_request_manager_request_metadata() {
         UpstreamConfig::default(),
         Duration::from_secs(0),
         Duration::from_secs(30),
+        Duration::from_secs(60),
         HashMap::new(),
     );
     for peer in peers.iter() {
@@ -100,3 +103,174 @@ fn test_request_manager_request_metadata() {
             <= request_manager.get_last_request_time(1).unwrap()
     );
 }
+
+#[test]
+fn test_request_manager_throughput() {
+    let peers = vec![
+        PeerNetworkId::random_validator(),
+        PeerNetworkId::random_validator(),
+    ];
+    let mut request_manager = RequestManager::new(
+        UpstreamConfig::default(),
+        Duration::from_secs(10),
+        Duration::from_secs(30),
+        Duration::from_secs(60),
+        HashMap::new(),
+    );
+    for peer in peers.iter() {
+        request_manager.enable_peer(peer.clone(), ConnectionOrigin::Outbound);
+    }
+
+    request_manager.add_request(1, vec![peers[0].clone()]);
+    request_manager.add_request(101, vec![peers[1].clone()]);
+    // throughput is only recorded for the peers the request was sent to
+    request_manager.record_chunk_throughput(&peers[1], 1, 1000);
+    request_manager.record_chunk_throughput(&peers[0], 1, 1000);
+    request_manager.record_chunk_throughput(&peers[1], 101, 1);
+
+    let mut pick_counts = HashMap::new();
+    for _ in 0..1000 {
+        let picked_peer_id = request_manager.pick_peers()[0].clone();
+        let counter = pick_counts.entry(picked_peer_id).or_insert(0);
+        *counter += 1;
+    }
+    assert!(pick_counts.get(&peers[1]).unwrap_or(&0) < pick_counts.get(&peers[0]).unwrap());
+}
+
+#[test]
+fn test_request_manager_requests_in_flight() {
+    let peers = vec![
+        PeerNetworkId::random_validator(),
+        PeerNetworkId::random_validator(),
+    ];
+    let mut request_manager = RequestManager::new(
+        UpstreamConfig::default(),
+        Duration::from_secs(10),
+        Duration::from_secs(30),
+        Duration::from_secs(60),
+        HashMap::new(),
+    );
+    for peer in peers.iter() {
+        request_manager.enable_peer(peer.clone(), ConnectionOrigin::Outbound);
+    }
+
+    request_manager.add_request(10, vec![peers[0].clone()]);
+    request_manager.add_request(20, vec![peers[1].clone()]);
+
+    assert!(request_manager.is_request_in_flight(10));
+    assert!(!request_manager.is_request_in_flight(15));
+    assert_eq!(request_manager.next_request_in_flight(0), Some(10));
+    assert_eq!(request_manager.next_request_in_flight(10), Some(20));
+    assert_eq!(request_manager.next_request_in_flight(20), None);
+    assert!(request_manager.is_request_sent_to(20, &peers[1]));
+    assert!(!request_manager.is_request_sent_to(20, &peers[0]));
+}
+
+#[test]
+fn test_request_manager_peer_ban() {
+    let peers = vec![
+        PeerNetworkId::random_validator(),
+        PeerNetworkId::random_validator(),
+    ];
+    let mut request_manager = RequestManager::new(
+        UpstreamConfig::default(),
+        Duration::from_secs(10),
+        Duration::from_secs(30),
+        Duration::from_secs(0),
+        HashMap::new(),
+    );
+    for peer in peers.iter() {
+        request_manager.enable_peer(peer.clone(), ConnectionOrigin::Outbound);
+    }
+
+    // a successful chunk resets the count of invalid chunks in a row
+    request_manager.update_score(&peers[0], PeerScoreUpdateType::InvalidChunk);
+    request_manager.update_score(&peers[0], PeerScoreUpdateType::InvalidChunk);
+    request_manager.update_score(&peers[0], PeerScoreUpdateType::Success);
+    request_manager.update_score(&peers[0], PeerScoreUpdateType::InvalidChunk);
+    request_manager.update_score(&peers[0], PeerScoreUpdateType::InvalidChunk);
+    assert!(!request_manager.is_banned(&peers[0]));
+
+    request_manager.update_score(&peers[0], PeerScoreUpdateType::InvalidChunk);
+    assert!(request_manager.is_banned(&peers[0]));
+    for _ in 0..100 {
+        assert_eq!(request_manager.pick_peers(), vec![peers[1].clone()]);
+    }
+
+    // the ban duration is zero, so the ban is over
+    request_manager.unban_expired_peers();
+    assert!(!request_manager.is_banned(&peers[0]));
+
+    // timeouts only ban a peer once its score is down to the minimum
+    for _ in 0..50 {
+        request_manager.update_score(&peers[1], PeerScoreUpdateType::TimeOut);
+    }
+    assert!(!request_manager.is_banned(&peers[1]));
+    for _ in 0..50 {
+        request_manager.update_score(&peers[1], PeerScoreUpdateType::TimeOut);
+    }
+    assert!(request_manager.is_banned(&peers[1]));
+    assert!(!request_manager.no_available_peers());
+}
+
+#[test]
+fn test_request_manager_never_bans_last_peer() {
+    let peers = vec![
+        PeerNetworkId::random_validator(),
+        PeerNetworkId::random_validator(),
+    ];
+    let mut request_manager = RequestManager::new(
+        UpstreamConfig::default(),
+        Duration::from_secs(10),
+        Duration::from_secs(30),
+        Duration::from_secs(60),
+        HashMap::new(),
+    );
+    for peer in peers.iter() {
+        request_manager.enable_peer(peer.clone(), ConnectionOrigin::Outbound);
+    }
+
+    for _ in 0..3 {
+        request_manager.update_score(&peers[0], PeerScoreUpdateType::InvalidChunk);
+    }
+    assert!(request_manager.is_banned(&peers[0]));
+
+    // the other peer is the last one eligible for peer selection
+    for _ in 0..10 {
+        request_manager.update_score(&peers[1], PeerScoreUpdateType::InvalidChunk);
+    }
+    assert!(!request_manager.is_banned(&peers[1]));
+    assert_eq!(request_manager.pick_peers(), vec![peers[1].clone()]);
+}
+
+#[test]
+fn test_request_manager_unban_resets_score() {
+    let peers = vec![
+        PeerNetworkId::random_validator(),
+        PeerNetworkId::random_validator(),
+    ];
+    let mut request_manager = RequestManager::new(
+        UpstreamConfig::default(),
+        Duration::from_secs(10),
+        Duration::from_secs(30),
+        Duration::from_secs(0),
+        HashMap::new(),
+    );
+    for peer in peers.iter() {
+        request_manager.enable_peer(peer.clone(), ConnectionOrigin::Outbound);
+    }
+    let initial_score = request_manager.peer_score(&peers[0]).unwrap();
+
+    // a peer banned for its minimum score
+    while !request_manager.is_banned(&peers[0]) {
+        request_manager.update_score(&peers[0], PeerScoreUpdateType::TimeOut);
+    }
+    assert!(request_manager.peer_score(&peers[0]).unwrap() < initial_score);
+
+    // starts over once unbanned, instead of being banned again by the next timeout
+    request_manager.unban_expired_peers();
+    assert!(!request_manager.is_banned(&peers[0]));
+    assert_eq!(request_manager.peer_score(&peers[0]), Some(initial_score));
+    request_manager.update_score(&peers[0], PeerScoreUpdateType::TimeOut);
+    assert!(!request_manager.is_banned(&peers[0]));
+}
@@ -115,7 +115,7 @@ pub trait TreeReader {
     fn get_rightmost_leaf(&self) -> Result<Option<(NodeKey, LeafNode)>>;
 }
 
-pub trait TreeWriter {
+pub trait TreeWriter: Send + Sync {
     /// Writes a node batch into storage.
     fn write_node_batch(&self, node_batch: &NodeBatch) -> Result<()>;
 }
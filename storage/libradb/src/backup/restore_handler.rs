@@ -85,32 +85,7 @@ impl RestoreHandler {
         frozen_subtrees: &[HashValue],
     ) -> Result<()> {
         let mut cs = ChangeSet::new();
-        let positions: Vec<_> = FrozenSubTreeIterator::new(num_leaves).collect();
-
-        ensure!(
-            positions.len() == frozen_subtrees.len(),
-            "Number of frozen subtree roots not expected. Expected: {}, actual: {}",
-            positions.len(),
-            frozen_subtrees.len(),
-        );
-
-        positions
-            .iter()
-            .zip(frozen_subtrees.iter().rev())
-            .map(|(p, h)| {
-                if let Some(_h) = self.db.get::<TransactionAccumulatorSchema>(&p)? {
-                    ensure!(
-                        h == &_h,
-                        "Frozen subtree root does not match that already in DB. Provided: {}, in db: {}.",
-                        h,
-                        _h,
-                    );
-                } else {
-                    cs.batch.put::<TransactionAccumulatorSchema>(p, h)?;
-                }
-                Ok(())
-            })
-            .collect::<Result<Vec<_>>>()?;
+        confirm_or_put_frozen_subtrees(&self.db, num_leaves, frozen_subtrees, &mut cs)?;
         self.db.write_schemas(cs.batch)
     }
 
@@ -157,3 +132,41 @@ impl RestoreHandler {
             .map_or(0, |(ver, _txn_info)| ver + 1))
     }
 }
+
+/// Checks the frozen subtree roots against those already in the DB and puts the missing ones into
+/// `cs`. The roots are expected in the order of `TransactionAccumulatorRangeProof::left_siblings`,
+/// i.e. from right to left.
+pub(crate) fn confirm_or_put_frozen_subtrees(
+    db: &DB,
+    num_leaves: LeafCount,
+    frozen_subtrees: &[HashValue],
+    cs: &mut ChangeSet,
+) -> Result<()> {
+    let positions: Vec<_> = FrozenSubTreeIterator::new(num_leaves).collect();
+
+    ensure!(
+        positions.len() == frozen_subtrees.len(),
+        "Number of frozen subtree roots not expected. Expected: {}, actual: {}",
+        positions.len(),
+        frozen_subtrees.len(),
+    );
+
+    positions
+        .iter()
+        .zip(frozen_subtrees.iter().rev())
+        .map(|(p, h)| {
+            if let Some(_h) = db.get::<TransactionAccumulatorSchema>(&p)? {
+                ensure!(
+                    h == &_h,
+                    "Frozen subtree root does not match that already in DB. Provided: {}, in db: {}.",
+                    h,
+                    _h,
+                );
+            } else {
+                cs.batch.put::<TransactionAccumulatorSchema>(p, h)?;
+            }
+            Ok(())
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(())
+}
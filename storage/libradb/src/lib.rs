@@ -35,7 +35,10 @@ mod libradb_test;
 pub use libradb_test::test_save_blocks_impl;
 
 use crate::{
-    backup::{backup_handler::BackupHandler, restore_handler::RestoreHandler},
+    backup::{
+        backup_handler::BackupHandler,
+        restore_handler::{confirm_or_put_frozen_subtrees, RestoreHandler},
+    },
     change_set::{ChangeSet, SealedChangeSet},
     errors::LibraDbError,
     event_store::EventStore,
@@ -49,11 +52,11 @@ use crate::{
     },
     pruner::Pruner,
     schema::*,
-    state_store::StateStore,
+    state_store::{StateSnapshotRestore, StateStore},
     system_store::SystemStore,
     transaction_store::TransactionStore,
 };
-use anyhow::{ensure, Result};
+use anyhow::{ensure, format_err, Result};
 use itertools::{izip, zip_eq};
 use libra_crypto::hash::{CryptoHash, HashValue, SPARSE_MERKLE_PLACEHOLDER_HASH};
 use libra_logger::prelude::*;
@@ -65,17 +68,17 @@ use libra_types::{
     event::EventKey,
     ledger_info::LedgerInfoWithSignatures,
     proof::{
-        AccountStateProof, AccumulatorConsistencyProof, EventProof, SparseMerkleProof,
-        TransactionListProof,
+        definition::LeafCount, AccountStateProof, AccumulatorConsistencyProof, EventProof,
+        SparseMerkleProof, SparseMerkleRangeProof, TransactionListProof,
     },
     transaction::{
-        TransactionInfo, TransactionListWithProof, TransactionToCommit, TransactionWithProof,
-        Version, PRE_GENESIS_VERSION,
+        Transaction, TransactionInfo, TransactionListWithProof, TransactionToCommit,
+        TransactionWithProof, Version, PRE_GENESIS_VERSION,
     },
 };
 use schemadb::{ColumnFamilyName, DB, DEFAULT_CF_NAME};
 use std::{iter::Iterator, path::Path, sync::Arc, time::Instant};
-use storage_interface::{DbReader, DbWriter, Order, StartupInfo, TreeState};
+use storage_interface::{DbReader, DbWriter, Order, StartupInfo, StateSnapshotReceiver, TreeState};
 
 const MAX_LIMIT: u64 = 1000;
 
@@ -761,6 +764,26 @@ impl DbReader for LibraDB {
             self.ledger_store.get_latest_transaction_info_option()
         })
     }
+
+    fn get_frozen_subtree_hashes(&self, num_transactions: LeafCount) -> Result<Vec<HashValue>> {
+        gauged_api("get_frozen_subtree_hashes", || {
+            self.ledger_store
+                .get_frozen_subtree_hashes(num_transactions)
+        })
+    }
+
+    fn get_state_snapshot_chunk(
+        &self,
+        version: Version,
+        start_key: HashValue,
+        limit: usize,
+    ) -> Result<(Vec<(HashValue, AccountStateBlob)>, SparseMerkleRangeProof)> {
+        gauged_api("get_state_snapshot_chunk", || {
+            error_if_too_many_requested(limit as u64, MAX_LIMIT)?;
+            self.state_store
+                .get_account_chunk_with_proof(version, start_key, limit)
+        })
+    }
 }
 
 impl DbWriter for LibraDB {
@@ -847,6 +870,71 @@ impl DbWriter for LibraDB {
             Ok(())
         })
     }
+
+    fn get_state_snapshot_receiver(
+        &self,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<Box<dyn StateSnapshotReceiver>> {
+        gauged_api("get_state_snapshot_receiver", || {
+            Ok(Box::new(StateSnapshotRestore::new(
+                &self.state_store,
+                version,
+                expected_root_hash,
+            )?) as Box<dyn StateSnapshotReceiver>)
+        })
+    }
+
+    /// The state tree at `version` is expected to have been fully restored via the receiver
+    /// returned by `get_state_snapshot_receiver`.
+    fn finalize_state_snapshot(
+        &self,
+        version: Version,
+        transaction: Transaction,
+        transaction_info: TransactionInfo,
+        frozen_subtrees: &[HashValue],
+        ledger_infos: &[LedgerInfoWithSignatures],
+    ) -> Result<()> {
+        gauged_api("finalize_state_snapshot", || {
+            let latest_li = ledger_infos
+                .last()
+                .ok_or_else(|| format_err!("No LedgerInfos to save."))?;
+            ensure!(
+                latest_li.ledger_info().version() == version,
+                "Latest LedgerInfo version {} doesn't match the snapshot version {}.",
+                latest_li.ledger_info().version(),
+                version,
+            );
+            ensure!(
+                self.state_store.get_root_hash(version)? == transaction_info.state_root_hash(),
+                "State tree at version {} is not restored.",
+                version,
+            );
+
+            let mut cs = ChangeSet::new();
+            let reversed_frozen_subtrees: Vec<_> = frozen_subtrees.iter().rev().cloned().collect();
+            confirm_or_put_frozen_subtrees(
+                &self.db,
+                version + 1,
+                &reversed_frozen_subtrees,
+                &mut cs,
+            )?;
+            self.transaction_store
+                .put_transaction(version, &transaction, &mut cs)?;
+            self.ledger_store
+                .put_transaction_infos(version, &[transaction_info], &mut cs)?;
+            for li in ledger_infos {
+                self.ledger_store.put_ledger_info(li, &mut cs)?;
+            }
+            self.db.write_schemas(cs.batch)?;
+
+            self.ledger_store.set_latest_ledger_info(latest_li.clone());
+            LIBRA_STORAGE_LEDGER_VERSION.set(version as i64);
+            LIBRA_STORAGE_LATEST_TXN_VERSION.set(version as i64);
+            LIBRA_STORAGE_NEXT_BLOCK_EPOCH.set(latest_li.ledger_info().next_block_epoch() as i64);
+            Ok(())
+        })
+    }
 }
 
 // Convert requested range and order to a range in ascending order.
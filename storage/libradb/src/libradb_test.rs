@@ -10,6 +10,7 @@ use crate::{
 use libra_crypto::hash::CryptoHash;
 #[allow(unused_imports)]
 use libra_jellyfish_merkle::node_type::{Node, NodeKey};
+use libra_jellyfish_merkle::test_helper::plus_one;
 use libra_temppath::TempPath;
 #[allow(unused_imports)]
 use libra_types::{
@@ -162,6 +163,74 @@ fn test_sync_transactions_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoW
     }
 }
 
+fn test_state_snapshot_restore_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) {
+    let tmp_dir = TempPath::new();
+    let db = LibraDB::new_for_test(&tmp_dir);
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        db.save_transactions(&txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+    }
+    let latest_li = db.get_latest_ledger_info().unwrap();
+    let version = latest_li.ledger_info().version();
+    let mut ledger_infos = db
+        .get_epoch_ending_ledger_infos(0, latest_li.ledger_info().next_block_epoch())
+        .unwrap()
+        .ledger_info_with_sigs;
+    if ledger_infos.last() != Some(&latest_li) {
+        ledger_infos.push(latest_li);
+    }
+    let mut txn_list_with_proof = db.get_transactions(version, 1, version, false).unwrap();
+    let txn_info = txn_list_with_proof.proof.transaction_infos()[0].clone();
+    let frozen_subtrees = db.get_frozen_subtree_hashes(version + 1).unwrap();
+
+    // Restore the state in small chunks into an empty DB.
+    let tmp_dir2 = TempPath::new();
+    let db2 = LibraDB::new_for_test(&tmp_dir2);
+    let mut receiver = db2
+        .get_state_snapshot_receiver(version, txn_info.state_root_hash())
+        .unwrap();
+    let mut start_key = HashValue::zero();
+    loop {
+        let (chunk, proof) = db.get_state_snapshot_chunk(version, start_key, 2).unwrap();
+        let last_key = chunk.last().unwrap().0;
+        let is_last_chunk = proof
+            .right_siblings()
+            .iter()
+            .all(|hash| *hash == *SPARSE_MERKLE_PLACEHOLDER_HASH);
+        receiver.add_chunk(chunk, proof).unwrap();
+        if is_last_chunk {
+            break;
+        }
+        start_key = plus_one(last_key);
+    }
+    receiver.finish().unwrap();
+    db2.finalize_state_snapshot(
+        version,
+        txn_list_with_proof.transactions.pop().unwrap(),
+        txn_info,
+        &frozen_subtrees,
+        &ledger_infos,
+    )
+    .unwrap();
+
+    assert_eq!(
+        db2.get_latest_ledger_info().unwrap(),
+        db.get_latest_ledger_info().unwrap()
+    );
+    assert_eq!(
+        db2.get_latest_tree_state().unwrap(),
+        db.get_latest_tree_state().unwrap()
+    );
+    assert_eq!(
+        db2.get_latest_state_root().unwrap(),
+        db.get_latest_state_root().unwrap()
+    );
+}
+
 fn get_events_by_event_key(
     db: &LibraDB,
     ledger_info: &LedgerInfo,
@@ -391,6 +460,11 @@ proptest! {
     fn test_sync_transactions(input in arb_blocks_to_commit()) {
         test_sync_transactions_impl(input);
     }
+
+    #[test]
+    fn test_state_snapshot_restore(input in arb_blocks_to_commit()) {
+        test_state_snapshot_restore_impl(input);
+    }
 }
 
 #[test]
@@ -13,10 +13,12 @@ use crate::{
         jellyfish_merkle_node::JellyfishMerkleNodeSchema, stale_node_index::StaleNodeIndexSchema,
     },
 };
-use anyhow::Result;
+use anyhow::{bail, Result};
 use libra_crypto::{hash::CryptoHash, HashValue};
 use libra_jellyfish_merkle::{
+    iterator::JellyfishMerkleIterator,
     node_type::{LeafNode, Node, NodeKey},
+    restore::JellyfishMerkleRestore,
     JellyfishMerkleTree, NodeBatch, TreeReader, TreeWriter, ROOT_NIBBLE_HEIGHT,
 };
 use libra_types::{
@@ -27,6 +29,7 @@ use libra_types::{
 };
 use schemadb::{SchemaBatch, DB};
 use std::{collections::HashMap, sync::Arc};
+use storage_interface::StateSnapshotReceiver;
 
 #[derive(Debug)]
 pub(crate) struct StateStore {
@@ -56,6 +59,25 @@ impl StateStore {
         JellyfishMerkleTree::new(self).get_range_proof(rightmost_key, version)
     }
 
+    /// Gets up to `limit` account state blobs at `version` starting from `start_key`, together
+    /// with the proof of the range ending at the last returned account.
+    pub fn get_account_chunk_with_proof(
+        self: &Arc<Self>,
+        version: Version,
+        start_key: HashValue,
+        limit: usize,
+    ) -> Result<(Vec<(HashValue, AccountStateBlob)>, SparseMerkleRangeProof)> {
+        let chunk = JellyfishMerkleIterator::new(Arc::clone(self), version, start_key)?
+            .take(limit)
+            .collect::<Result<Vec<_>>>()?;
+        let rightmost_key = match chunk.last() {
+            Some((key, _blob)) => *key,
+            None => bail!("No accounts at version {} from key {}.", version, start_key),
+        };
+        let proof = self.get_account_state_range_proof(rightmost_key, version)?;
+        Ok((chunk, proof))
+    }
+
     /// Put the results generated by `account_state_sets` to `batch` and return the result root
     /// hashes for each write set.
     pub fn put_account_state_sets(
@@ -214,6 +236,37 @@ impl TreeWriter for StateStore {
     }
 }
 
+/// Restores the state tree of a snapshot received from state sync.
+pub(crate) struct StateSnapshotRestore(JellyfishMerkleRestore);
+
+impl StateSnapshotRestore {
+    pub fn new(
+        state_store: &Arc<StateStore>,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<Self> {
+        Ok(Self(JellyfishMerkleRestore::new_overwrite(
+            Arc::clone(state_store),
+            version,
+            expected_root_hash,
+        )?))
+    }
+}
+
+impl StateSnapshotReceiver for StateSnapshotRestore {
+    fn add_chunk(
+        &mut self,
+        chunk: Vec<(HashValue, AccountStateBlob)>,
+        proof: SparseMerkleRangeProof,
+    ) -> Result<()> {
+        self.0.add_chunk(chunk, proof)
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        self.0.finish()
+    }
+}
+
 fn add_node_batch(batch: &mut SchemaBatch, node_batch: &NodeBatch) -> Result<()> {
     node_batch
         .iter()
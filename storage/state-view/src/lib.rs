@@ -11,8 +11,9 @@ use libra_types::{access_path::AccessPath, transaction::Version};
 
 /// `StateView` is a trait that defines a read-only snapshot of the global state. It is passed to
 /// the VM for transaction execution, during which the VM is guaranteed to read anything at the
-/// given state.
-pub trait StateView {
+/// given state. Views are shared between the threads executing the transactions of a block in
+/// parallel, hence the `Sync` bound.
+pub trait StateView: Sync {
     /// For logging and debugging purpose, identifies what this view is for.
     fn id(&self) -> StateViewId {
         StateViewId::Miscellaneous
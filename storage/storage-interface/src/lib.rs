@@ -15,10 +15,13 @@ use libra_types::{
     event::EventKey,
     ledger_info::LedgerInfoWithSignatures,
     move_resource::MoveStorage,
-    proof::{definition::LeafCount, AccumulatorConsistencyProof, SparseMerkleProof},
+    proof::{
+        definition::LeafCount, AccumulatorConsistencyProof, SparseMerkleProof,
+        SparseMerkleRangeProof,
+    },
     transaction::{
-        TransactionInfo, TransactionListWithProof, TransactionToCommit, TransactionWithProof,
-        Version,
+        Transaction, TransactionInfo, TransactionListWithProof, TransactionToCommit,
+        TransactionWithProof, Version,
     },
 };
 use serde::{Deserialize, Serialize};
@@ -297,6 +300,24 @@ pub trait DbReader: Send + Sync {
     fn get_latest_transaction_info_option(&self) -> Result<Option<(Version, TransactionInfo)>> {
         unimplemented!()
     }
+
+    /// Gets the roots of the frozen subtrees of the transaction accumulator with
+    /// `num_transactions` leaves, from left to right.
+    fn get_frozen_subtree_hashes(&self, _num_transactions: LeafCount) -> Result<Vec<HashValue>> {
+        unimplemented!()
+    }
+
+    /// Gets up to `limit` account state blobs of the state tree at `version`, in the increasing
+    /// order of their keys starting from `start_key` (inclusive), together with the range proof
+    /// for the rightmost returned key. Used to serve state snapshots to syncing peers.
+    fn get_state_snapshot_chunk(
+        &self,
+        _version: Version,
+        _start_key: HashValue,
+        _limit: usize,
+    ) -> Result<(Vec<(HashValue, AccountStateBlob)>, SparseMerkleRangeProof)> {
+        unimplemented!()
+    }
 }
 
 impl MoveStorage for &dyn DbReader {
@@ -374,6 +395,45 @@ pub trait DbWriter: Send + Sync {
         first_version: Version,
         ledger_info_with_sigs: Option<&LedgerInfoWithSignatures>,
     ) -> Result<()>;
+
+    /// Gets a receiver that restores the state tree at `version` from chunks of account state
+    /// blobs. The tree is verified to have `expected_root_hash` when the receiver finishes.
+    fn get_state_snapshot_receiver(
+        &self,
+        _version: Version,
+        _expected_root_hash: HashValue,
+    ) -> Result<Box<dyn StateSnapshotReceiver>> {
+        unimplemented!()
+    }
+
+    /// Makes a restored state snapshot at `version` the new starting point of the ledger by
+    /// persisting the transaction at `version` with its info, the frozen subtrees (from left to
+    /// right) of the transaction accumulator up to `version` and the epoch ending ledger infos,
+    /// the last of which becomes the latest ledger info.
+    /// It is the responsibility of the caller to verify all the inputs.
+    fn finalize_state_snapshot(
+        &self,
+        _version: Version,
+        _transaction: Transaction,
+        _transaction_info: TransactionInfo,
+        _frozen_subtrees: &[HashValue],
+        _ledger_infos: &[LedgerInfoWithSignatures],
+    ) -> Result<()> {
+        unimplemented!()
+    }
+}
+
+/// Receives the chunks of a state snapshot in the increasing order of account keys.
+pub trait StateSnapshotReceiver: Send {
+    /// Adds a chunk of account state blobs, verifying it against the range proof.
+    fn add_chunk(
+        &mut self,
+        chunk: Vec<(HashValue, AccountStateBlob)>,
+        proof: SparseMerkleRangeProof,
+    ) -> Result<()>;
+
+    /// Writes the remaining nodes and verifies the root hash of the restored tree.
+    fn finish(self: Box<Self>) -> Result<()>;
 }
 
 #[derive(Clone)]
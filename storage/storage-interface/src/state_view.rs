@@ -7,6 +7,7 @@ use libra_crypto::{
     hash::{CryptoHash, SPARSE_MERKLE_PLACEHOLDER_HASH},
     HashValue,
 };
+use libra_infallible::Mutex;
 use libra_state_view::{StateView, StateViewId};
 use libra_types::{
     access_path::AccessPath,
@@ -16,12 +17,7 @@ use libra_types::{
     transaction::{Version, PRE_GENESIS_VERSION},
 };
 use scratchpad::{AccountStatus, SparseMerkleTree};
-use std::{
-    cell::RefCell,
-    collections::{hash_map::Entry, HashMap},
-    convert::TryInto,
-    sync::Arc,
-};
+use std::{collections::HashMap, convert::TryInto, sync::Arc};
 
 /// `VerifiedStateView` is like a snapshot of the global state comprised of state view at two
 /// levels, persistent storage and memory.
@@ -78,8 +74,8 @@ pub struct VerifiedStateView<'a> {
     ///        | +------------------------------+ +--------------------+ |
     ///        +---------------------------------------------------------+
     /// ```
-    account_to_state_cache: RefCell<HashMap<AccountAddress, AccountState>>,
-    account_to_proof_cache: RefCell<HashMap<HashValue, SparseMerkleProof>>,
+    account_to_state_cache: Mutex<HashMap<AccountAddress, AccountState>>,
+    account_to_proof_cache: Mutex<HashMap<HashValue, SparseMerkleProof>>,
 }
 
 impl<'a> VerifiedStateView<'a> {
@@ -108,8 +104,8 @@ impl<'a> VerifiedStateView<'a> {
             latest_persistent_version,
             latest_persistent_state_root,
             speculative_state,
-            account_to_state_cache: RefCell::new(HashMap::new()),
-            account_to_proof_cache: RefCell::new(HashMap::new()),
+            account_to_state_cache: Mutex::new(HashMap::new()),
+            account_to_proof_cache: Mutex::new(HashMap::new()),
         }
     }
 }
@@ -141,56 +137,58 @@ impl<'a> StateView for VerifiedStateView<'a> {
     fn get(&self, access_path: &AccessPath) -> Result<Option<Vec<u8>>> {
         let address = access_path.address;
         let path = &access_path.path;
-        match self.account_to_state_cache.borrow_mut().entry(address) {
-            Entry::Occupied(occupied) => Ok(occupied.get().get(path).cloned()),
-            Entry::Vacant(vacant) => {
-                let address_hash = address.hash();
-                let account_blob_option = match self.speculative_state.get(address_hash) {
-                    AccountStatus::ExistsInScratchPad(blob) => Some(blob),
-                    AccountStatus::DoesNotExist => None,
-                    // No matter it is in db or unknown, we have to query from db since even the
-                    // former case, we don't have the blob data but only its hash.
-                    AccountStatus::ExistsInDB | AccountStatus::Unknown => {
-                        let (blob, proof) = match self.latest_persistent_version {
-                            Some(version) => self
-                                .reader
-                                .get_account_state_with_proof_by_version(address, version)?,
-                            None => (None, SparseMerkleProof::new(None, vec![])),
-                        };
-                        proof
-                            .verify(
-                                self.latest_persistent_state_root,
-                                address.hash(),
-                                blob.as_ref(),
-                            )
-                            .map_err(|err| {
-                                format_err!(
-                                "Proof is invalid for address {:?} with state root hash {:?}: {}",
-                                address,
-                                self.latest_persistent_state_root,
-                                err
-                            )
-                            })?;
-                        assert!(self
-                            .account_to_proof_cache
-                            .borrow_mut()
-                            .insert(address_hash, proof)
-                            .is_none());
-                        blob
-                    }
+        if let Some(account_state) = self.account_to_state_cache.lock().get(&address) {
+            return Ok(account_state.get(path).cloned());
+        }
+        // The cache is not locked while the account state is fetched and verified, so that the
+        // parallel executions are not serialized on the reads missing the cache.
+        let address_hash = address.hash();
+        let account_blob_option = match self.speculative_state.get(address_hash) {
+            AccountStatus::ExistsInScratchPad(blob) => Some(blob),
+            AccountStatus::DoesNotExist => None,
+            // No matter it is in db or unknown, we have to query from db since even the
+            // former case, we don't have the blob data but only its hash.
+            AccountStatus::ExistsInDB | AccountStatus::Unknown => {
+                let (blob, proof) = match self.latest_persistent_version {
+                    Some(version) => self
+                        .reader
+                        .get_account_state_with_proof_by_version(address, version)?,
+                    None => (None, SparseMerkleProof::new(None, vec![])),
                 };
-                Ok(vacant
-                    .insert(
-                        account_blob_option
-                            .as_ref()
-                            .map(TryInto::try_into)
-                            .transpose()?
-                            .unwrap_or_default(),
+                proof
+                    .verify(
+                        self.latest_persistent_state_root,
+                        address.hash(),
+                        blob.as_ref(),
                     )
-                    .get(path)
-                    .cloned())
+                    .map_err(|err| {
+                        format_err!(
+                            "Proof is invalid for address {:?} with state root hash {:?}: {}",
+                            address,
+                            self.latest_persistent_state_root,
+                            err
+                        )
+                    })?;
+                // The account may have been fetched concurrently, with the same proof.
+                self.account_to_proof_cache
+                    .lock()
+                    .entry(address_hash)
+                    .or_insert(proof);
+                blob
             }
-        }
+        };
+        let account_state = account_blob_option
+            .as_ref()
+            .map(TryInto::try_into)
+            .transpose()?
+            .unwrap_or_default();
+        Ok(self
+            .account_to_state_cache
+            .lock()
+            .entry(address)
+            .or_insert(account_state)
+            .get(path)
+            .cloned())
     }
 
     fn multi_get(&self, _access_paths: &[AccessPath]) -> Result<Vec<Option<Vec<u8>>>> {
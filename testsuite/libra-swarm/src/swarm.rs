@@ -1,7 +1,7 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use anyhow::{Context, Result};
+use anyhow::{format_err, Context, Result};
 use debug_interface::NodeDebugClient;
 use libra_config::config::{NodeConfig, RoleType};
 use libra_genesis_tool::{
@@ -11,6 +11,7 @@ use libra_genesis_tool::{
 use libra_logger::prelude::*;
 use libra_temppath::TempPath;
 use libra_types::account_address::AccountAddress;
+use network::transport::fault_injection::NetworkFaultsConfig;
 use std::{
     collections::HashMap,
     env,
@@ -22,12 +23,16 @@ use std::{
 };
 use thiserror::Error;
 
+/// Token of the network admin endpoints of the swarm nodes.
+const SWARM_NETWORK_ADMIN_TOKEN: &str = "libra-swarm";
+
 pub struct LibraNode {
     node: Child,
     node_id: String,
     validator_peer_id: Option<AccountAddress>,
     role: RoleType,
     debug_client: NodeDebugClient,
+    network_admin_token: Option<String>,
     port: u16,
     pub log: PathBuf,
 }
@@ -91,6 +96,7 @@ impl LibraNode {
             validator_peer_id,
             role,
             debug_client,
+            network_admin_token: config.debug_interface.network_admin_token.clone(),
             port: config.json_rpc.address.port(),
             log: log_path,
         })
@@ -129,6 +135,22 @@ impl LibraNode {
         }
     }
 
+    /// Replace the faults injected in the connections of the network at `network_index`. The
+    /// node must be configured with a network admin token, and fault injection enabled on the
+    /// network.
+    pub fn set_network_faults(
+        &mut self,
+        network_index: usize,
+        faults: &NetworkFaultsConfig,
+    ) -> Result<()> {
+        let token = self
+            .network_admin_token
+            .as_ref()
+            .ok_or_else(|| format_err!("Node '{}' has no network admin token", self.node_id))?;
+        self.debug_client
+            .set_network_faults(token, network_index, faults)
+    }
+
     pub fn check_connectivity(&mut self, expected_peers: i64) -> bool {
         let connected_peers = format!(
             "libra_network_peers{{role_type={},state=connected}}",
@@ -261,10 +283,11 @@ impl LibraSwarm {
         let swarm_config_dir = Self::setup_config_dir(&config_dir);
         info!("logs for {:?} at {:?}", fn_type, swarm_config_dir);
 
-        let node_config = template.unwrap_or_else(|| match fn_type {
+        let mut node_config = template.unwrap_or_else(|| match fn_type {
             FullnodeType::ValidatorFullnode => NodeConfig::default_for_validator_full_node(),
             FullnodeType::PublicFullnode(_) => NodeConfig::default_for_public_full_node(),
         });
+        Self::enable_fault_injection(&mut node_config);
 
         let config_path = &swarm_config_dir.as_ref().to_path_buf();
         let builder = FullnodeBuilder::new(
@@ -293,7 +316,8 @@ impl LibraSwarm {
         let swarm_config_dir = Self::setup_config_dir(&config_dir);
         info!("logs for validator at {:?}", swarm_config_dir);
 
-        let node_config = template.unwrap_or_else(NodeConfig::default_for_validator);
+        let mut node_config = template.unwrap_or_else(NodeConfig::default_for_validator);
+        Self::enable_fault_injection(&mut node_config);
 
         let config_path = &swarm_config_dir.as_ref().to_path_buf();
         let builder = ValidatorBuilder::new(num_nodes, node_config, &swarm_config_dir);
@@ -308,6 +332,21 @@ impl LibraSwarm {
         })
     }
 
+    /// Lets the tests degrade the links of every network through the network admin endpoints.
+    fn enable_fault_injection(config: &mut NodeConfig) {
+        for network in config
+            .validator_network
+            .iter_mut()
+            .chain(config.full_node_networks.iter_mut())
+        {
+            network.enable_fault_injection = true;
+        }
+        config
+            .debug_interface
+            .network_admin_token
+            .get_or_insert_with(|| SWARM_NETWORK_ADMIN_TOKEN.to_string());
+    }
+
     pub fn launch(&mut self) {
         let num_attempts = 5;
         for _ in 0..num_attempts {
@@ -508,6 +547,11 @@ impl LibraSwarm {
         self.nodes.get(&node_id)
     }
 
+    pub fn mut_validator(&mut self, idx: usize) -> Option<&mut LibraNode> {
+        let node_id = format!("{}", idx);
+        self.nodes.get_mut(&node_id)
+    }
+
     pub fn kill_node(&mut self, idx: usize) {
         let node_id = format!("{}", idx);
         self.nodes.remove(&node_id);
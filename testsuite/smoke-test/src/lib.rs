@@ -13,6 +13,9 @@ mod genesis;
 #[cfg(test)]
 mod key_manager;
 
+#[cfg(test)]
+mod network_faults;
+
 #[cfg(test)]
 mod operational_tooling;
 
@@ -0,0 +1,57 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::test_utils::{compare_balances, setup_swarm_and_client_proxy};
+use network::transport::fault_injection::{LinkFaults, NetworkFaultsConfig};
+
+// The validator network is registered after the full node network of the validators.
+const VALIDATOR_NETWORK_INDEX: usize = 1;
+
+#[test]
+fn test_network_faults() {
+    let num_nodes = 4;
+    let (mut env, mut client) = setup_swarm_and_client_proxy(num_nodes, 0);
+
+    // Slow down every link of the validator network
+    let faults = NetworkFaultsConfig {
+        default: LinkFaults {
+            latency_ms: 100,
+            jitter_ms: 20,
+            ..LinkFaults::default()
+        },
+        links: vec![],
+    };
+    for idx in 0..num_nodes {
+        env.validator_swarm
+            .mut_validator(idx)
+            .unwrap()
+            .set_network_faults(VALIDATOR_NETWORK_INDEX, &faults)
+            .unwrap();
+    }
+
+    // The validators still commit transactions
+    client.create_next_account(false).unwrap();
+    client
+        .mint_coins(&["mintb", "0", "10", "Coin1"], true)
+        .unwrap();
+    assert!(compare_balances(
+        vec![(10.0, "Coin1".to_string())],
+        client.get_balances(&["b", "0"]).unwrap(),
+    ));
+
+    // Heal the network
+    for idx in 0..num_nodes {
+        env.validator_swarm
+            .mut_validator(idx)
+            .unwrap()
+            .set_network_faults(VALIDATOR_NETWORK_INDEX, &NetworkFaultsConfig::default())
+            .unwrap();
+    }
+    client
+        .mint_coins(&["mintb", "0", "10", "Coin1"], true)
+        .unwrap();
+    assert!(compare_balances(
+        vec![(20.0, "Coin1".to_string())],
+        client.get_balances(&["b", "0"]).unwrap(),
+    ));
+}